@@ -0,0 +1,478 @@
+//!
+//! Python bindings for the surface reconstruction of SPH particle data with [`splashsurf_lib`]
+//!
+//! The Python module is built with [maturin](https://github.com/PyO3/maturin) (`maturin develop` in this directory)
+//! and provides the function `reconstruct_surface` for single reconstructions and the class `Reconstructor` for
+//! sequences of reconstructions (e.g. the frames of a simulation) reusing the memory of previous reconstructions.
+//! Particle positions are passed as NumPy arrays of shape `(N, 3)` with type `float32` or `float64`, the resulting
+//! mesh is returned as NumPy arrays of the same type that take ownership of the mesh buffers without copies.
+
+use numpy::{Element, PyArray1, PyArray2, PyReadonlyArray2};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyRuntimeError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use splashsurf_lib::mesh::{AttributeData, MeshWithData, TriMesh3d};
+use splashsurf_lib::nalgebra::Vector3;
+use splashsurf_lib::{
+    AttributeSpecs, MarchingCubesVariant, NormalsComputation, Parameters,
+    ParticleDensityComputationStrategy, Real, ReconstructionError, SpatialDecompositionParameters,
+    SplashOutput, SubdivisionCriterion, SurfaceReconstruction,
+};
+use std::error::Error;
+
+create_exception!(
+    pysplashsurf,
+    ParameterError,
+    PyValueError,
+    "Raised if a reconstruction parameter is invalid, the name of the parameter is stored in the `field` attribute"
+);
+create_exception!(
+    pysplashsurf,
+    ReconstructionFailedError,
+    PyRuntimeError,
+    "Raised if the surface reconstruction failed"
+);
+
+/// Python module `pysplashsurf`
+#[pymodule]
+fn pysplashsurf(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(reconstruct_surface, m)?)?;
+    m.add_class::<Reconstructor>()?;
+    m.add("ParameterError", py.get_type::<ParameterError>())?;
+    m.add(
+        "ReconstructionFailedError",
+        py.get_type::<ReconstructionFailedError>(),
+    )?;
+    Ok(())
+}
+
+/// Reconstructs the surface of the given particles and returns the mesh as `(vertices, triangles)`
+///
+/// The particle positions have to be a NumPy array of shape `(N, 3)` with type `float32` or `float64`. All lengths are
+/// given in absolute distance units: `kernel_radius` is the compact support radius of the SPH kernel and `cube_size`
+/// the edge length of the marching cubes background grid.
+///
+/// Optional keyword arguments:
+///  - `rest_density` (default: 1000.0)
+///  - `enable_multi_threading` (default: True)
+///  - `deterministic`: produce bitwise identical meshes for repeated reconstructions (default: False)
+///  - `octree`: use the octree based spatial decomposition (default: True)
+///  - `max_particles_per_leaf`: maximum number of particles per octree leaf (default: chosen automatically)
+///  - `stitching`: stitch the meshes of the octree leaves to a closed mesh (default: True)
+///  - `normals`: compute vertex normals using `"sph"` interpolation or `"area_weighted"` triangle normals
+///  - `vertex_densities`: interpolate the particle densities to the vertices (default: False)
+///
+/// If normals or vertex densities are requested, `(vertices, triangles, attributes)` is returned instead where
+/// `attributes` is a dict with the entries `"normals"` and/or `"density"`.
+///
+/// Raises a `ParameterError` (subclass of `ValueError`) if a parameter is invalid and a `ReconstructionFailedError`
+/// (subclass of `RuntimeError`) if the reconstruction fails.
+#[pyfunction]
+#[pyo3(signature = (positions, particle_radius, kernel_radius, cube_size, iso_surface_threshold = 0.6, **kwargs))]
+fn reconstruct_surface(
+    py: Python,
+    positions: &PyAny,
+    particle_radius: f64,
+    kernel_radius: f64,
+    cube_size: f64,
+    iso_surface_threshold: f64,
+    kwargs: Option<&PyDict>,
+) -> PyResult<PyObject> {
+    let options = ReconstructionOptions::new(
+        py,
+        particle_radius,
+        kernel_radius,
+        cube_size,
+        iso_surface_threshold,
+        kwargs,
+    )?;
+
+    if let Ok(positions) = positions.extract::<PyReadonlyArray2<f64>>() {
+        reconstruct_auto(py, &positions, &options)
+    } else if let Ok(positions) = positions.extract::<PyReadonlyArray2<f32>>() {
+        reconstruct_auto(py, &positions, &options)
+    } else {
+        Err(unsupported_positions_error())
+    }
+}
+
+/// Reusable surface reconstruction for sequences of particle data sets (e.g. the frames of a simulation)
+///
+/// Takes the same parameters as `reconstruct_surface`. The memory allocated by a reconstruction is kept for
+/// subsequent calls of `reconstruct`.
+#[pyclass]
+struct Reconstructor {
+    options: ReconstructionOptions,
+    surface_f32: SurfaceReconstruction<i64, f32>,
+    surface_f64: SurfaceReconstruction<i64, f64>,
+}
+
+#[pymethods]
+impl Reconstructor {
+    #[new]
+    #[pyo3(signature = (particle_radius, kernel_radius, cube_size, iso_surface_threshold = 0.6, **kwargs))]
+    fn new(
+        py: Python,
+        particle_radius: f64,
+        kernel_radius: f64,
+        cube_size: f64,
+        iso_surface_threshold: f64,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            options: ReconstructionOptions::new(
+                py,
+                particle_radius,
+                kernel_radius,
+                cube_size,
+                iso_surface_threshold,
+                kwargs,
+            )?,
+            surface_f32: SurfaceReconstruction::default(),
+            surface_f64: SurfaceReconstruction::default(),
+        })
+    }
+
+    /// Reconstructs the surface of the given particles, returns the mesh like `reconstruct_surface`
+    fn reconstruct(&mut self, py: Python, positions: &PyAny) -> PyResult<PyObject> {
+        if let Ok(positions) = positions.extract::<PyReadonlyArray2<f64>>() {
+            reconstruct_inplace(py, &positions, &self.options, &mut self.surface_f64)
+        } else if let Ok(positions) = positions.extract::<PyReadonlyArray2<f32>>() {
+            reconstruct_inplace(py, &positions, &self.options, &mut self.surface_f32)
+        } else {
+            Err(unsupported_positions_error())
+        }
+    }
+}
+
+/// Parameters and requested outputs of a reconstruction parsed from the Python arguments
+struct ReconstructionOptions {
+    parameters: Parameters<f64>,
+    normals: Option<NormalsComputation>,
+    vertex_densities: bool,
+}
+
+impl ReconstructionOptions {
+    /// Parses the keyword arguments and validates the resulting parameters
+    fn new(
+        py: Python,
+        particle_radius: f64,
+        kernel_radius: f64,
+        cube_size: f64,
+        iso_surface_threshold: f64,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<Self> {
+        let mut rest_density = 1000.0;
+        let mut enable_multi_threading = true;
+        let mut deterministic = false;
+        let mut octree = true;
+        let mut max_particles_per_leaf: Option<usize> = None;
+        let mut stitching = true;
+        let mut normals = None;
+        let mut vertex_densities = false;
+
+        for (key, value) in kwargs.into_iter().flatten() {
+            let key: &str = key.extract()?;
+            match key {
+                "rest_density" => rest_density = value.extract()?,
+                "enable_multi_threading" => enable_multi_threading = value.extract()?,
+                "deterministic" => deterministic = value.extract()?,
+                "octree" => octree = value.extract()?,
+                "max_particles_per_leaf" => max_particles_per_leaf = value.extract()?,
+                "stitching" => stitching = value.extract()?,
+                "normals" => {
+                    normals = match value.extract::<Option<&str>>()? {
+                        Some("sph") => Some(NormalsComputation::SphInterpolation),
+                        Some("area_weighted") => Some(NormalsComputation::AreaWeighted),
+                        Some(method) => {
+                            return Err(parameter_error(
+                                py,
+                                "normals",
+                                format!(
+                                    "unknown normals computation method '{}', expected 'sph' or 'area_weighted'",
+                                    method
+                                ),
+                            ))
+                        }
+                        None => None,
+                    }
+                }
+                "vertex_densities" => vertex_densities = value.extract()?,
+                _ => {
+                    return Err(PyTypeError::new_err(format!(
+                        "unexpected keyword argument '{}'",
+                        key
+                    )))
+                }
+            }
+        }
+
+        if max_particles_per_leaf == Some(0) {
+            return Err(parameter_error(
+                py,
+                "max_particles_per_leaf",
+                "the maximum number of particles per leaf has to be larger than zero".to_string(),
+            ));
+        }
+
+        let spatial_decomposition = octree.then(|| SpatialDecompositionParameters {
+            subdivision_criterion: max_particles_per_leaf
+                .map(SubdivisionCriterion::MaxParticleCount)
+                .unwrap_or(SubdivisionCriterion::MaxParticleCountAuto),
+            ghost_particle_safety_factor: None,
+            enable_stitching: stitching,
+            particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+        });
+
+        let parameters = Parameters {
+            particle_radius,
+            rest_density,
+            compact_support_radius: kernel_radius,
+            cube_size,
+            iso_surface_threshold,
+            triangulation_scheme: MarchingCubesVariant::Classic,
+            vertex_snapping: None,
+            splash_detection: None,
+            splash_particle_output: SplashOutput::Ignore,
+            domain_aabb: None,
+            clipping_planes: Vec::new(),
+            enable_multi_threading,
+            deterministic,
+            progress_interval: None,
+            temporal_density_smoothing: None,
+            spatial_decomposition,
+        };
+        parameters
+            .validate()
+            .map_err(|err| lib_parameter_error(py, &err))?;
+
+        Ok(Self {
+            parameters,
+            normals,
+            vertex_densities,
+        })
+    }
+
+    /// Returns the parameters converted to the type of the particle positions
+    fn parameters<R: Real>(&self, py: Python) -> PyResult<Parameters<R>> {
+        self.parameters
+            .try_convert_checked()
+            .map_err(|err| parameter_error(py, &python_field_name(&err.field), err.to_string()))
+    }
+
+    /// Returns whether any per vertex attributes were requested
+    fn has_attributes(&self) -> bool {
+        self.normals.is_some() || self.vertex_densities
+    }
+
+    /// Returns the attribute specification for the requested outputs
+    fn attribute_specs<R: Real>(&self) -> AttributeSpecs<'static, R> {
+        AttributeSpecs {
+            normals: self.normals,
+            vertex_densities: self.vertex_densities,
+            ..AttributeSpecs::default()
+        }
+    }
+}
+
+/// Reconstructs a surface using an automatically selected index type
+fn reconstruct_auto<R: Real + Element>(
+    py: Python,
+    positions: &PyReadonlyArray2<R>,
+    options: &ReconstructionOptions,
+) -> PyResult<PyObject> {
+    let particles = particles_from_array(positions)?;
+    let parameters = options.parameters::<R>(py)?;
+    let attribute_specs = options.attribute_specs::<R>();
+
+    let mesh_with_data = py
+        .allow_threads(|| {
+            let mut surface = splashsurf_lib::reconstruct_surface_auto(&particles, &parameters)?;
+            if options.has_attributes() {
+                surface.mesh_with_attributes(&particles, &parameters, &attribute_specs)
+            } else {
+                Ok(MeshWithData::new(surface.take_mesh()))
+            }
+        })
+        .map_err(|err| reconstruction_error(py, &err))?;
+
+    mesh_to_python(py, mesh_with_data, options.has_attributes())
+}
+
+/// Reconstructs a surface reusing the memory of the given reconstruction
+fn reconstruct_inplace<R: Real + Element>(
+    py: Python,
+    positions: &PyReadonlyArray2<R>,
+    options: &ReconstructionOptions,
+    surface: &mut SurfaceReconstruction<i64, R>,
+) -> PyResult<PyObject> {
+    let particles = particles_from_array(positions)?;
+    let parameters = options.parameters::<R>(py)?;
+    let attribute_specs = options.attribute_specs::<R>();
+
+    let mesh_with_data = py
+        .allow_threads(|| {
+            splashsurf_lib::reconstruct_surface_inplace(&particles, &parameters, surface)?;
+            if options.has_attributes() {
+                surface.mesh_with_attributes(&particles, &parameters, &attribute_specs)
+            } else {
+                Ok(MeshWithData::new(surface.take_mesh()))
+            }
+        })
+        .map_err(|err| reconstruction_error(py, &err))?;
+
+    mesh_to_python(py, mesh_with_data, options.has_attributes())
+}
+
+/// Copies the particle positions from a NumPy array of shape `(N, 3)`
+fn particles_from_array<R: Real + Element>(
+    positions: &PyReadonlyArray2<R>,
+) -> PyResult<Vec<Vector3<R>>> {
+    let positions = positions.as_array();
+    if positions.ncols() != 3 {
+        return Err(PyValueError::new_err(format!(
+            "particle positions have to be an array of shape (N, 3), got shape {:?}",
+            positions.shape()
+        )));
+    }
+
+    Ok(positions
+        .rows()
+        .into_iter()
+        .map(|p| Vector3::new(p[0], p[1], p[2]))
+        .collect())
+}
+
+/// Converts the reconstructed mesh to the returned tuple, the NumPy arrays take ownership of the mesh buffers
+fn mesh_to_python<R: Real + Element>(
+    py: Python,
+    mesh_with_data: MeshWithData<R, TriMesh3d<R>>,
+    with_attributes: bool,
+) -> PyResult<PyObject> {
+    let MeshWithData {
+        mesh: TriMesh3d {
+            vertices,
+            triangles,
+        },
+        point_attributes,
+        ..
+    } = mesh_with_data;
+
+    let vertices = vectors_to_array(py, vertices)?;
+    let num_triangles = triangles.len();
+    // Safety: an array `[usize; 3]` has the same layout as three consecutive `usize` values
+    let triangles = PyArray1::from_vec(py, unsafe { flatten_vec::<_, usize, 3>(triangles) })
+        .reshape([num_triangles, 3])?;
+
+    if !with_attributes {
+        return Ok((vertices, triangles).into_py(py));
+    }
+
+    let attributes = PyDict::new(py);
+    for attribute in point_attributes {
+        match attribute.data {
+            AttributeData::ScalarU64(values) => {
+                attributes.set_item(attribute.name, PyArray1::from_vec(py, values))?
+            }
+            AttributeData::ScalarReal(values) => {
+                attributes.set_item(attribute.name, PyArray1::from_vec(py, values))?
+            }
+            AttributeData::Vector3Real(values) => {
+                attributes.set_item(attribute.name, vectors_to_array(py, values)?)?
+            }
+            AttributeData::VectorReal {
+                num_components,
+                values,
+            } => {
+                let num_values = values.len() / num_components;
+                attributes.set_item(
+                    attribute.name,
+                    PyArray1::from_vec(py, values).reshape([num_values, num_components])?,
+                )?
+            }
+        }
+    }
+
+    Ok((vertices, triangles, attributes).into_py(py))
+}
+
+/// Moves the vectors into a NumPy array of shape `(N, 3)` without copying them
+fn vectors_to_array<R: Real + Element>(
+    py: Python,
+    vectors: Vec<Vector3<R>>,
+) -> PyResult<&PyArray2<R>> {
+    let num_vectors = vectors.len();
+    // Safety: nalgebra's `Vector3<R>` has the same layout as `[R; 3]`
+    PyArray1::from_vec(py, unsafe { flatten_vec::<_, R, 3>(vectors) }).reshape([num_vectors, 3])
+}
+
+/// Reinterprets a vector of items with `N` components as a flat vector of the components without copying the data
+///
+/// # Safety
+/// The type `T` has to have the same memory layout as `[C; N]`.
+unsafe fn flatten_vec<T, C, const N: usize>(vec: Vec<T>) -> Vec<C> {
+    assert_eq!(std::mem::size_of::<T>(), N * std::mem::size_of::<C>());
+    assert_eq!(std::mem::align_of::<T>(), std::mem::align_of::<C>());
+
+    let mut vec = std::mem::ManuallyDrop::new(vec);
+    Vec::from_raw_parts(
+        vec.as_mut_ptr() as *mut C,
+        vec.len() * N,
+        vec.capacity() * N,
+    )
+}
+
+/// Returns the name of the Python argument corresponding to a parameter of the library
+fn python_field_name(name: &str) -> String {
+    match name {
+        "compact support radius" | "compact_support_radius" => "kernel_radius".to_string(),
+        name => name.replace([' ', '-'], "_"),
+    }
+}
+
+/// Creates a `ParameterError` with the name of the invalid parameter stored in its `field` attribute
+fn parameter_error(py: Python, field: &str, message: String) -> PyErr {
+    let err = ParameterError::new_err(message);
+    if let Err(setattr_err) = err.value(py).setattr("field", field) {
+        return setattr_err;
+    }
+    err
+}
+
+/// Converts a parameter error of the library to a `ParameterError`
+fn lib_parameter_error(py: Python, err: &splashsurf_lib::ParameterError) -> PyErr {
+    let field = match err {
+        splashsurf_lib::ParameterError::AbsoluteAndRelativeValue(name)
+        | splashsurf_lib::ParameterError::MissingValue(name)
+        | splashsurf_lib::ParameterError::InvalidValue(name)
+        | splashsurf_lib::ParameterError::InvalidFraction(name)
+        | splashsurf_lib::ParameterError::InvalidRange(name, _)
+        | splashsurf_lib::ParameterError::IncompatibleOptions(name, _) => python_field_name(name),
+    };
+    parameter_error(py, &field, err.to_string())
+}
+
+/// Converts a reconstruction error to a `ParameterError` for invalid parameters or a `ReconstructionFailedError` otherwise
+fn reconstruction_error<R: Real>(py: Python, err: &ReconstructionError<i64, R>) -> PyErr {
+    if let ReconstructionError::InvalidParameters(err) = err {
+        return lib_parameter_error(py, err);
+    }
+
+    // Include all sources of the error in the message
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+    ReconstructionFailedError::new_err(message)
+}
+
+/// Error for particle positions that are not a two-dimensional float array
+fn unsupported_positions_error() -> PyErr {
+    PyTypeError::new_err(
+        "particle positions have to be a NumPy array of type float32 or float64 with shape (N, 3)",
+    )
+}
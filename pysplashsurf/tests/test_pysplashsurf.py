@@ -0,0 +1,117 @@
+# Tests of the Python bindings, run with `maturin develop && pytest` in the `pysplashsurf` directory
+
+import numpy as np
+import pytest
+
+import pysplashsurf
+
+PARTICLE_RADIUS = 0.025
+PARAMETERS = dict(
+    particle_radius=PARTICLE_RADIUS,
+    kernel_radius=4.0 * PARTICLE_RADIUS,
+    cube_size=0.5 * PARTICLE_RADIUS,
+)
+
+
+def particle_cube(n=10, dtype=np.float64):
+    """Returns a cube of n^3 particles with a spacing of twice the particle radius"""
+    coords = 2.0 * PARTICLE_RADIUS * np.arange(n)
+    return np.stack(np.meshgrid(coords, coords, coords, indexing="ij"), axis=-1).reshape(-1, 3).astype(dtype)
+
+
+@pytest.mark.parametrize("dtype", [np.float32, np.float64])
+def test_reconstruct_surface(dtype):
+    vertices, triangles = pysplashsurf.reconstruct_surface(particle_cube(dtype=dtype), **PARAMETERS)
+
+    assert vertices.dtype == dtype
+    assert vertices.ndim == 2 and vertices.shape[1] == 3
+    assert triangles.ndim == 2 and triangles.shape[1] == 3
+    assert len(vertices) > 0 and len(triangles) > 0
+    assert triangles.max() < len(vertices)
+
+    # All vertices are close to the particle cube
+    assert vertices.min() > -4.0 * PARTICLE_RADIUS
+    assert vertices.max() < 20.0 * PARTICLE_RADIUS + 2.0 * PARTICLE_RADIUS
+
+
+def test_global_and_octree_reconstruction_extents():
+    particles = particle_cube()
+    vertices_global, triangles_global = pysplashsurf.reconstruct_surface(particles, octree=False, **PARAMETERS)
+    vertices_octree, triangles_octree = pysplashsurf.reconstruct_surface(
+        particles, max_particles_per_leaf=100, stitching=True, **PARAMETERS
+    )
+
+    assert len(triangles_global) > 0 and len(triangles_octree) > 0
+    cube_size = PARAMETERS["cube_size"]
+    np.testing.assert_allclose(vertices_global.min(axis=0), vertices_octree.min(axis=0), atol=cube_size)
+    np.testing.assert_allclose(vertices_global.max(axis=0), vertices_octree.max(axis=0), atol=cube_size)
+
+
+def test_attributes():
+    particles = particle_cube()
+    vertices, triangles, attributes = pysplashsurf.reconstruct_surface(
+        particles, normals="sph", vertex_densities=True, **PARAMETERS
+    )
+
+    normals = attributes["normals"]
+    assert normals.shape == vertices.shape
+    np.testing.assert_allclose(np.linalg.norm(normals, axis=1), 1.0, rtol=1e-5)
+
+    densities = attributes["density"]
+    assert densities.shape == (len(vertices),)
+    assert np.all(densities > 0.0)
+
+
+def test_reconstructor_sequence():
+    reconstructor = pysplashsurf.Reconstructor(**PARAMETERS)
+    reference_vertices, reference_triangles = pysplashsurf.reconstruct_surface(particle_cube(), **PARAMETERS)
+
+    for n in [10, 8, 10]:
+        vertices, triangles = reconstructor.reconstruct(particle_cube(n))
+        assert len(vertices) > 0 and triangles.max() < len(vertices)
+
+    # Reusing the reconstruction does not change the result
+    assert vertices.shape == reference_vertices.shape
+    assert triangles.shape == reference_triangles.shape
+
+    vertices_f32, _ = reconstructor.reconstruct(particle_cube(dtype=np.float32))
+    assert vertices_f32.dtype == np.float32
+
+
+@pytest.mark.parametrize(
+    "field, kwargs",
+    [
+        ("particle_radius", dict(particle_radius=-1.0)),
+        ("kernel_radius", dict(kernel_radius=0.0)),
+        ("cube_size", dict(cube_size=float("nan"))),
+        ("iso_surface_threshold", dict(iso_surface_threshold=-0.5)),
+        ("rest_density", dict(rest_density=0.0)),
+        ("normals", dict(normals="unknown")),
+        ("max_particles_per_leaf", dict(max_particles_per_leaf=0)),
+    ],
+)
+def test_parameter_errors(field, kwargs):
+    parameters = {**PARAMETERS, **kwargs}
+    with pytest.raises(pysplashsurf.ParameterError) as exc_info:
+        pysplashsurf.reconstruct_surface(particle_cube(), **parameters)
+    assert exc_info.value.field == field
+    assert isinstance(exc_info.value, ValueError)
+
+    with pytest.raises(pysplashsurf.ParameterError):
+        pysplashsurf.Reconstructor(**parameters)
+
+
+def test_conversion_error():
+    # The cube size overflows to infinity in single precision
+    with pytest.raises(pysplashsurf.ParameterError) as exc_info:
+        pysplashsurf.reconstruct_surface(particle_cube(dtype=np.float32), **{**PARAMETERS, "cube_size": 1e300})
+    assert exc_info.value.field == "cube_size"
+
+
+def test_invalid_positions():
+    with pytest.raises(ValueError):
+        pysplashsurf.reconstruct_surface(np.zeros((10, 2)), **PARAMETERS)
+    with pytest.raises(TypeError):
+        pysplashsurf.reconstruct_surface(np.zeros((10, 3), dtype=np.int32), **PARAMETERS)
+    with pytest.raises(TypeError):
+        pysplashsurf.reconstruct_surface(particle_cube(), unknown_argument=1.0, **PARAMETERS)
@@ -0,0 +1,348 @@
+use crate::io;
+use anyhow::{anyhow, Context};
+use log::info;
+use results_table::ResultsTable;
+use splashsurf_lib::nalgebra::Vector3;
+use splashsurf_lib::parameter_suggestion::estimate_reconstruction;
+use splashsurf_lib::timings::TimingsCollector;
+use splashsurf_lib::{
+    profile, AxisAlignedBoundingBox3d, MarchingCubesVariant, Parameters,
+    ParticleDensityComputationStrategy, RelativeParameters, SpatialDecompositionParameters,
+    SplashOutput, SubdivisionCriterion, SurfaceReconstruction,
+};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use structopt::StructOpt;
+
+mod results_table;
+
+/// Command line arguments for the `bench` subcommand
+#[derive(Clone, Debug, StructOpt)]
+pub struct BenchSubcommandArgs {
+    /// Path to the input file where the particle positions are stored (supported formats: VTK, binary f32 XYZ, PLY, BGEO, JSON)
+    #[structopt(display_order = 1, parse(from_os_str))]
+    input_file: PathBuf,
+    /// Optional filename for writing the results as CSV
+    #[structopt(display_order = 1, long, parse(from_os_str))]
+    csv: Option<PathBuf>,
+
+    /// The particle radius of the input data
+    #[structopt(display_order = 2, long)]
+    particle_radius: f64,
+    /// The rest density of the fluid
+    #[structopt(display_order = 2, long, default_value = "1000.0")]
+    rest_density: f64,
+    /// The smoothing length radius used for the SPH kernel, the kernel compact support radius will be twice the smoothing length (in multiplies of the particle radius)
+    #[structopt(display_order = 2, long)]
+    smoothing_length: f64,
+    /// Comma separated list of the cube edge lengths to benchmark in multiplies of the particle radius, e.g. `--cube-sizes 0.3,0.5,0.8`
+    #[structopt(
+        display_order = 2,
+        long,
+        use_delimiter = true,
+        require_delimiter = true,
+        required = true
+    )]
+    cube_sizes: Vec<f64>,
+    /// The iso-surface threshold for the density, i.e. the normalized value of the reconstructed density level that indicates the fluid surface (in multiplies of the rest density)
+    #[structopt(display_order = 2, long, default_value = "0.6")]
+    surface_threshold: f64,
+
+    /// Benchmark the reconstruction with spatial decomposition using an octree (the default if neither `--with-octree` nor `--without-octree` is given)
+    #[structopt(display_order = 3, long)]
+    with_octree: bool,
+    /// Benchmark the global reconstruction without spatial decomposition
+    #[structopt(display_order = 3, long)]
+    without_octree: bool,
+    /// Number of repetitions of every reconstruction, the minimum and the median of the timings are reported
+    #[structopt(display_order = 3, long, default_value = "1")]
+    repeat: usize,
+}
+
+/// A single combination of the benchmarked parameters
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct BenchConfig {
+    /// Cube size in multiplies of the particle radius
+    cube_size: f64,
+    /// Whether to use the octree based reconstruction
+    octree: bool,
+}
+
+/// Results of all repetitions of the reconstruction with a single configuration
+#[derive(Clone, Debug)]
+struct BenchResult {
+    config: BenchConfig,
+    num_triangles: usize,
+    num_vertices: usize,
+    /// Estimated memory consumption of the reconstruction in bytes, see [`estimate_reconstruction`]
+    estimated_memory_bytes: u64,
+    /// Memory allocated by the workspace of the reconstruction after the runs of this configuration
+    workspace_bytes: usize,
+    /// Wall-clock times of all repetitions
+    durations: Vec<Duration>,
+}
+
+impl BenchSubcommandArgs {
+    /// Returns whether the octree based and/or the global reconstruction should be benchmarked
+    fn octree_settings(&self) -> Vec<bool> {
+        match (self.with_octree, self.without_octree) {
+            (true, true) => vec![true, false],
+            (false, true) => vec![false],
+            _ => vec![true],
+        }
+    }
+
+    /// Assembles the reconstruction parameters for the given configuration
+    fn parameters(&self, config: &BenchConfig) -> Result<Parameters<f64>, anyhow::Error> {
+        // Same spatial decomposition as the default of the `reconstruct` subcommand
+        let spatial_decomposition = config.octree.then_some(SpatialDecompositionParameters {
+            subdivision_criterion: SubdivisionCriterion::MaxParticleCountAuto,
+            ghost_particle_safety_factor: None,
+            enable_stitching: true,
+            particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+        });
+
+        let relative_params = RelativeParameters {
+            rest_density: self.rest_density,
+            compact_support_radius: None,
+            relative_compact_support_radius: Some(2.0 * self.smoothing_length),
+            cube_size: None,
+            relative_cube_size: Some(config.cube_size),
+            iso_surface_threshold: self.surface_threshold,
+            triangulation_scheme: MarchingCubesVariant::Classic,
+            vertex_snapping: None,
+            splash_detection: None,
+            splash_particle_output: SplashOutput::Ignore,
+            domain_aabb: None,
+            clipping_planes: Vec::new(),
+            enable_multi_threading: true,
+            deterministic: false,
+            progress_interval: None,
+            temporal_density_smoothing: None,
+            spatial_decomposition,
+        };
+        Ok(relative_params.resolve(self.particle_radius)?)
+    }
+}
+
+/// Executes the `bench` subcommand
+pub fn bench_subcommand(cmd_args: &BenchSubcommandArgs) -> Result<(), anyhow::Error> {
+    profile!("bench subcommand");
+
+    if cmd_args.repeat == 0 {
+        return Err(anyhow!("The number of repetitions has to be positive"));
+    }
+    if let Some(cube_size) = cmd_args
+        .cube_sizes
+        .iter()
+        .find(|cube_size| !(cube_size.is_finite() && **cube_size > 0.0))
+    {
+        return Err(anyhow!(
+            "The cube sizes have to be finite and positive (got {})",
+            cube_size
+        ));
+    }
+
+    let io_params = io::FormatParameters::default();
+    let input_file = &cmd_args.input_file;
+    let particle_positions: Vec<Vector3<f32>> =
+        io::read_particle_positions(input_file.as_path(), &io_params.input).with_context(|| {
+            format!(
+                "Failed to load particle positions from file \"{}\"",
+                input_file.display()
+            )
+        })?;
+    let particle_aabb = AxisAlignedBoundingBox3d::from_points(&particle_positions);
+
+    let configs = expand_configurations(&cmd_args.cube_sizes, &cmd_args.octree_settings());
+
+    // The reconstruction is reused for all runs, i.e. the workspace persists between the runs like for a sequence of frames
+    let mut reconstruction = SurfaceReconstruction::<i64, f32>::default();
+    reconstruction.set_timings_collector(Some(TimingsCollector::new()));
+
+    let mut results = Vec::with_capacity(configs.len());
+    for config in configs {
+        let params = cmd_args.parameters(&config)?;
+        let params_f32 = params
+            .try_convert_checked()
+            .context("Unable to convert surface reconstruction parameters from f64 to f32.")?;
+        info!(
+            "Benchmarking cube size {} ({}) with {} repetition(s)...",
+            config.cube_size,
+            if config.octree { "octree" } else { "global" },
+            cmd_args.repeat
+        );
+
+        let mut durations = Vec::with_capacity(cmd_args.repeat);
+        for _ in 0..cmd_args.repeat {
+            splashsurf_lib::reconstruct_surface_inplace(
+                &particle_positions,
+                &params_f32,
+                &mut reconstruction,
+            )?;
+            let timings = reconstruction
+                .stats()
+                .timings
+                .as_ref()
+                .ok_or_else(|| anyhow!("No timings were recorded for the reconstruction"))?;
+            durations.push(timings.total);
+        }
+
+        let estimate = estimate_reconstruction(
+            &particle_aabb,
+            particle_positions.len(),
+            params_f32.particle_radius,
+            params_f32.compact_support_radius,
+            params_f32.cube_size,
+        );
+
+        results.push(BenchResult {
+            config,
+            num_triangles: reconstruction.mesh().triangles.len(),
+            num_vertices: reconstruction.mesh().vertices.len(),
+            estimated_memory_bytes: estimate.memory_bytes,
+            workspace_bytes: reconstruction.workspace_memory_usage().total_bytes(),
+            durations,
+        });
+    }
+
+    let table = results_table(&results);
+    println!(
+        "Benchmark of \"{}\" ({} particles, {} repetition(s) per configuration):",
+        input_file.display(),
+        particle_positions.len(),
+        cmd_args.repeat
+    );
+    print!("{}", table.to_text());
+
+    if let Some(csv_file) = &cmd_args.csv {
+        fs::write(csv_file, table.to_csv()).with_context(|| {
+            format!(
+                "Failed to write benchmark results to \"{}\"",
+                csv_file.display()
+            )
+        })?;
+        info!("Wrote benchmark results to \"{}\".", csv_file.display());
+    }
+
+    Ok(())
+}
+
+/// Returns all combinations of the given cube sizes and octree settings, ordered by cube size
+fn expand_configurations(cube_sizes: &[f64], octree_settings: &[bool]) -> Vec<BenchConfig> {
+    cube_sizes
+        .iter()
+        .flat_map(|&cube_size| {
+            octree_settings
+                .iter()
+                .map(move |&octree| BenchConfig { cube_size, octree })
+        })
+        .collect()
+}
+
+/// Returns the median of the given durations, the mean of the two middle values for an even number of durations
+fn median(durations: &[Duration]) -> Duration {
+    let mut sorted = durations.to_vec();
+    sorted.sort_unstable();
+    match sorted.len() {
+        0 => Duration::ZERO,
+        n if n % 2 == 1 => sorted[n / 2],
+        n => (sorted[n / 2 - 1] + sorted[n / 2]) / 2,
+    }
+}
+
+/// Collects the results of all configurations in a table
+fn results_table(results: &[BenchResult]) -> ResultsTable {
+    let mut table = ResultsTable::new(&[
+        "cube_size",
+        "octree",
+        "triangles",
+        "vertices",
+        "estimated_memory_mib",
+        "workspace_mib",
+        "min_ms",
+        "median_ms",
+    ]);
+
+    let mib = |bytes: f64| format!("{:.1}", bytes / (1024.0 * 1024.0));
+    let ms = |duration: Duration| format!("{:.1}", duration.as_secs_f64() * 1e3);
+    for result in results {
+        table.push_row(vec![
+            result.config.cube_size.to_string(),
+            if result.config.octree { "on" } else { "off" }.to_string(),
+            result.num_triangles.to_string(),
+            result.num_vertices.to_string(),
+            mib(result.estimated_memory_bytes as f64),
+            mib(result.workspace_bytes as f64),
+            ms(result.durations.iter().copied().min().unwrap_or_default()),
+            ms(median(&result.durations)),
+        ]);
+    }
+
+    table
+}
+
+#[test]
+fn test_expand_configurations() {
+    let configs = expand_configurations(&[0.3, 0.5, 0.8], &[true, false]);
+    assert_eq!(configs.len(), 6);
+    assert_eq!(
+        configs[..2],
+        [
+            BenchConfig {
+                cube_size: 0.3,
+                octree: true
+            },
+            BenchConfig {
+                cube_size: 0.3,
+                octree: false
+            }
+        ]
+    );
+    assert_eq!(
+        configs[5],
+        BenchConfig {
+            cube_size: 0.8,
+            octree: false
+        }
+    );
+
+    assert!(expand_configurations(&[0.5], &[]).is_empty());
+
+    let args = BenchSubcommandArgs::from_iter_safe(&[
+        "bench",
+        "particles.vtk",
+        "--particle-radius=0.025",
+        "--smoothing-length=2.0",
+        "--cube-sizes",
+        "0.5,0.75",
+        "--without-octree",
+        "--with-octree",
+    ])
+    .unwrap();
+    assert_eq!(args.cube_sizes, vec![0.5, 0.75]);
+    assert_eq!(
+        expand_configurations(&args.cube_sizes, &args.octree_settings()).len(),
+        4
+    );
+
+    // Only the octree based reconstruction is benchmarked by default
+    let args = BenchSubcommandArgs::from_iter_safe(&[
+        "bench",
+        "particles.vtk",
+        "--particle-radius=0.025",
+        "--smoothing-length=2.0",
+        "--cube-sizes=0.5",
+    ])
+    .unwrap();
+    assert_eq!(args.octree_settings(), vec![true]);
+}
+
+#[test]
+fn test_median() {
+    let ms = Duration::from_millis;
+    assert_eq!(median(&[ms(30), ms(10), ms(20)]), ms(20));
+    assert_eq!(median(&[ms(40), ms(10), ms(20), ms(30)]), ms(25));
+    assert_eq!(median(&[]), Duration::ZERO);
+}
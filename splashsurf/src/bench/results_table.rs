@@ -0,0 +1,113 @@
+//! Table of benchmark results that can be printed with aligned columns or written as CSV
+
+use std::borrow::Cow;
+
+/// A table with a fixed set of named columns and rows of already formatted values
+#[derive(Clone, Debug)]
+pub(crate) struct ResultsTable {
+    columns: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl ResultsTable {
+    /// Creates an empty table with the given column names
+    pub(crate) fn new(columns: &[&'static str]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Appends a row to the table, panics if the number of values does not match the number of columns
+    pub(crate) fn push_row(&mut self, row: Vec<String>) {
+        assert_eq!(
+            row.len(),
+            self.columns.len(),
+            "number of values of the row does not match the number of columns"
+        );
+        self.rows.push(row);
+    }
+
+    /// Formats the table with right-aligned columns separated by two spaces
+    pub(crate) fn to_text(&self) -> String {
+        let mut widths: Vec<_> = self.columns.iter().map(|column| column.len()).collect();
+        for row in &self.rows {
+            for (width, value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(value.len());
+            }
+        }
+
+        self.format_lines(|values| {
+            values
+                .into_iter()
+                .zip(&widths)
+                .map(|(value, width)| format!("{:>width$}", value, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+        })
+    }
+
+    /// Formats the table as CSV with a header line, values are quoted if necessary (RFC 4180)
+    pub(crate) fn to_csv(&self) -> String {
+        self.format_lines(|values| {
+            values
+                .into_iter()
+                .map(csv_field)
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+    }
+
+    /// Formats the header and all rows with the given function, every line is terminated by a line break
+    fn format_lines<F: Fn(Vec<&str>) -> String>(&self, format_line: F) -> String {
+        let mut lines = format_line(self.columns.clone());
+        lines.push('\n');
+        for row in &self.rows {
+            lines.push_str(&format_line(row.iter().map(String::as_str).collect()));
+            lines.push('\n');
+        }
+        lines
+    }
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break, quotes inside of the field are doubled
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+#[test]
+fn test_results_table_csv() {
+    let mut table = ResultsTable::new(&["cube_size", "octree", "note"]);
+    table.push_row(vec!["0.5".to_string(), "on".to_string(), String::new()]);
+    table.push_row(vec![
+        "0.75".to_string(),
+        "off".to_string(),
+        "slow, \"global\"".to_string(),
+    ]);
+    table.push_row(vec![
+        "1".to_string(),
+        "off".to_string(),
+        "two\nlines".to_string(),
+    ]);
+
+    assert_eq!(
+        table.to_csv(),
+        "cube_size,octree,note\n0.5,on,\n0.75,off,\"slow, \"\"global\"\"\"\n1,off,\"two\nlines\"\n"
+    );
+}
+
+#[test]
+fn test_results_table_text() {
+    let mut table = ResultsTable::new(&["cube_size", "triangles"]);
+    table.push_row(vec!["0.5".to_string(), "123456".to_string()]);
+    table.push_row(vec!["0.75".to_string(), "42".to_string()]);
+
+    assert_eq!(
+        table.to_text(),
+        "cube_size  triangles\n      0.5     123456\n     0.75         42\n"
+    );
+}
@@ -0,0 +1,363 @@
+//! Named parameter profiles loaded from a TOML config file, see `--profile` of the `reconstruct` subcommand
+
+use crate::scene;
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use splashsurf_lib::profile;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// Config file that is used if `--profile` is given without `--config`
+pub(crate) const DEFAULT_CONFIG_FILE: &str = "splashsurf.toml";
+
+/// Command line arguments for the `print-config` subcommand
+#[derive(Clone, Debug, StructOpt)]
+pub struct PrintConfigSubcommandArgs {
+    /// Path to the TOML config file containing the profiles (default: "splashsurf.toml" in the current working directory)
+    #[structopt(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+    /// Name of the profile that should be resolved, only the built-in defaults are printed if no profile is given
+    #[structopt(long)]
+    profile: Option<String>,
+    /// SPlisHSPlasH scene file (JSON) that provides default values for the particle radius, rest density and smoothing length
+    #[structopt(long, parse(from_os_str))]
+    scene: Option<PathBuf>,
+}
+
+/// Reconstruction parameters that can be specified in a profile or on the command line, unspecified values are `None`
+///
+/// The keys in the config file are the names of the corresponding command line arguments of the `reconstruct`
+/// subcommand. Switches like `--normals=on` are given as booleans.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct ProfileParameters {
+    pub particle_radius: Option<f64>,
+    pub rest_density: Option<f64>,
+    pub smoothing_length: Option<f64>,
+    pub cube_size: Option<f64>,
+    pub surface_threshold: Option<f64>,
+    pub double_precision: Option<bool>,
+    pub octree_decomposition: Option<bool>,
+    pub octree_stitch_subdomains: Option<bool>,
+    pub octree_max_particles: Option<usize>,
+    pub octree_ghost_margin_factor: Option<f64>,
+    pub octree_global_density: Option<bool>,
+    pub octree_sync_local_density: Option<bool>,
+    pub normals: Option<bool>,
+    pub sph_normals: Option<bool>,
+}
+
+/// Parameters after applying the built-in defaults, the selected profile and the command line arguments
+///
+/// Parameters without a built-in default (e.g. the particle radius) remain `None` if they were neither specified
+/// in the profile nor on the command line.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct ResolvedConfig {
+    /// Name of the profile that was applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Path of the scene file that was applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scene: Option<String>,
+    pub particle_radius: Option<f64>,
+    pub rest_density: f64,
+    pub smoothing_length: Option<f64>,
+    pub cube_size: Option<f64>,
+    pub surface_threshold: f64,
+    pub double_precision: bool,
+    pub octree_decomposition: bool,
+    pub octree_stitch_subdomains: bool,
+    pub octree_max_particles: Option<usize>,
+    pub octree_ghost_margin_factor: Option<f64>,
+    pub octree_global_density: bool,
+    pub octree_sync_local_density: bool,
+    pub normals: bool,
+    pub sph_normals: bool,
+}
+
+/// Contents of a config file: a table of named profiles, e.g. `[profiles.final]`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, ProfileParameters>,
+}
+
+impl ProfileParameters {
+    /// Returns the built-in defaults of the `reconstruct` subcommand
+    pub(crate) fn defaults() -> Self {
+        Self {
+            particle_radius: None,
+            rest_density: Some(1000.0),
+            smoothing_length: None,
+            cube_size: None,
+            surface_threshold: Some(0.6),
+            double_precision: Some(false),
+            octree_decomposition: Some(true),
+            octree_stitch_subdomains: Some(true),
+            octree_max_particles: None,
+            octree_ghost_margin_factor: None,
+            octree_global_density: Some(false),
+            octree_sync_local_density: Some(true),
+            normals: Some(false),
+            sph_normals: Some(true),
+        }
+    }
+
+    /// Returns the parameters where all values specified in `overrides` replace the values of `self`
+    pub(crate) fn overridden_by(&self, overrides: &ProfileParameters) -> Self {
+        Self {
+            particle_radius: overrides.particle_radius.or(self.particle_radius),
+            rest_density: overrides.rest_density.or(self.rest_density),
+            smoothing_length: overrides.smoothing_length.or(self.smoothing_length),
+            cube_size: overrides.cube_size.or(self.cube_size),
+            surface_threshold: overrides.surface_threshold.or(self.surface_threshold),
+            double_precision: overrides.double_precision.or(self.double_precision),
+            octree_decomposition: overrides.octree_decomposition.or(self.octree_decomposition),
+            octree_stitch_subdomains: overrides
+                .octree_stitch_subdomains
+                .or(self.octree_stitch_subdomains),
+            octree_max_particles: overrides.octree_max_particles.or(self.octree_max_particles),
+            octree_ghost_margin_factor: overrides
+                .octree_ghost_margin_factor
+                .or(self.octree_ghost_margin_factor),
+            octree_global_density: overrides
+                .octree_global_density
+                .or(self.octree_global_density),
+            octree_sync_local_density: overrides
+                .octree_sync_local_density
+                .or(self.octree_sync_local_density),
+            normals: overrides.normals.or(self.normals),
+            sph_normals: overrides.sph_normals.or(self.sph_normals),
+        }
+    }
+}
+
+impl ResolvedConfig {
+    /// Resolves the parameters with the precedence: built-in defaults < scene file < profile < command line arguments
+    pub(crate) fn resolve(
+        scene: Option<(&Path, &ProfileParameters)>,
+        profile: Option<(&str, &ProfileParameters)>,
+        cli: &ProfileParameters,
+    ) -> Self {
+        let mut parameters = ProfileParameters::defaults();
+        if let Some((_, scene_parameters)) = scene {
+            parameters = parameters.overridden_by(scene_parameters);
+        }
+        if let Some((_, profile_parameters)) = profile {
+            parameters = parameters.overridden_by(profile_parameters);
+        }
+        let parameters = parameters.overridden_by(cli);
+
+        // All parameters with a built-in default are set after applying the defaults
+        Self {
+            profile: profile.map(|(name, _)| name.to_string()),
+            scene: scene.map(|(path, _)| path.display().to_string()),
+            particle_radius: parameters.particle_radius,
+            rest_density: parameters.rest_density.unwrap(),
+            smoothing_length: parameters.smoothing_length,
+            cube_size: parameters.cube_size,
+            surface_threshold: parameters.surface_threshold.unwrap(),
+            double_precision: parameters.double_precision.unwrap(),
+            octree_decomposition: parameters.octree_decomposition.unwrap(),
+            octree_stitch_subdomains: parameters.octree_stitch_subdomains.unwrap(),
+            octree_max_particles: parameters.octree_max_particles,
+            octree_ghost_margin_factor: parameters.octree_ghost_margin_factor,
+            octree_global_density: parameters.octree_global_density.unwrap(),
+            octree_sync_local_density: parameters.octree_sync_local_density.unwrap(),
+            normals: parameters.normals.unwrap(),
+            sph_normals: parameters.sph_normals.unwrap(),
+        }
+    }
+
+    /// Returns the particle radius or an error if it was neither specified in the profile nor on the command line
+    pub(crate) fn particle_radius(&self) -> Result<f64, anyhow::Error> {
+        self.particle_radius.ok_or_else(|| {
+            anyhow!("The particle radius has to be specified with --particle-radius, in the selected profile or in the scene file")
+        })
+    }
+
+    /// Returns the smoothing length or an error if it was neither specified in the profile nor on the command line
+    pub(crate) fn smoothing_length(&self) -> Result<f64, anyhow::Error> {
+        self.smoothing_length.ok_or_else(|| {
+            anyhow!("The smoothing length has to be specified with --smoothing-length or in the selected profile")
+        })
+    }
+
+    /// Converts the configuration to a JSON value for the run summary
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("configuration is always serializable")
+    }
+}
+
+/// Loads the profile with the given name from the config file, uses "splashsurf.toml" if no config file is given
+pub(crate) fn load_profile(
+    config_file: Option<&Path>,
+    profile_name: &str,
+) -> Result<ProfileParameters, anyhow::Error> {
+    let config_file = config_file.unwrap_or_else(|| Path::new(DEFAULT_CONFIG_FILE));
+    let content = fs::read_to_string(config_file).with_context(|| {
+        format!(
+            "Failed to read config file \"{}\" for profile \"{}\"",
+            config_file.display(),
+            profile_name
+        )
+    })?;
+    let mut config: ConfigFile = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse config file \"{}\"", config_file.display()))?;
+
+    config.profiles.remove(profile_name).ok_or_else(|| {
+        anyhow!(
+            "The profile \"{}\" does not exist in config file \"{}\" (available profiles: {})",
+            profile_name,
+            config_file.display(),
+            if config.profiles.is_empty() {
+                "none".to_string()
+            } else {
+                config
+                    .profiles
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        )
+    })
+}
+
+/// Executes the `print-config` subcommand
+pub fn print_config_subcommand(cmd_args: &PrintConfigSubcommandArgs) -> Result<(), anyhow::Error> {
+    profile!("print-config subcommand");
+
+    let profile = match cmd_args.profile.as_deref() {
+        Some(name) => Some((name, load_profile(cmd_args.config.as_deref(), name)?)),
+        None => None,
+    };
+    let scene = match cmd_args.scene.as_deref() {
+        Some(path) => Some((path, scene::load_scene(path)?)),
+        None => None,
+    };
+    let config = ResolvedConfig::resolve(
+        scene.as_ref().map(|(path, parameters)| (*path, parameters)),
+        profile
+            .as_ref()
+            .map(|(name, parameters)| (*name, parameters)),
+        &ProfileParameters::default(),
+    );
+
+    print!(
+        "{}",
+        toml::to_string(&config).context("Failed to serialize the resolved configuration")?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_profile_precedence() {
+    let profile: ProfileParameters = toml::from_str(
+        r#"
+        particle-radius = 0.025
+        smoothing-length = 2.0
+        cube-size = 0.5
+        surface-threshold = 0.7
+        normals = true
+        "#,
+    )
+    .unwrap();
+    let cli = ProfileParameters {
+        cube_size: Some(0.75),
+        normals: Some(false),
+        octree_decomposition: Some(false),
+        ..Default::default()
+    };
+
+    // Built-in defaults only
+    let config = ResolvedConfig::resolve(None, None, &ProfileParameters::default());
+    assert_eq!(config.profile, None);
+    assert_eq!(config.rest_density, 1000.0);
+    assert_eq!(config.surface_threshold, 0.6);
+    assert_eq!(config.cube_size, None);
+    assert!(config.octree_decomposition);
+    assert!(!config.normals);
+    assert!(config.particle_radius().is_err());
+
+    // Profile values replace the defaults
+    let config = ResolvedConfig::resolve(
+        None,
+        Some(("final", &profile)),
+        &ProfileParameters::default(),
+    );
+    assert_eq!(config.profile.as_deref(), Some("final"));
+    assert_eq!(config.particle_radius().unwrap(), 0.025);
+    assert_eq!(config.cube_size, Some(0.5));
+    assert_eq!(config.surface_threshold, 0.7);
+    assert!(config.normals);
+    assert_eq!(config.rest_density, 1000.0);
+
+    // Command line arguments replace profile values and defaults
+    let config = ResolvedConfig::resolve(None, Some(("final", &profile)), &cli);
+    assert_eq!(config.cube_size, Some(0.75));
+    assert!(!config.normals);
+    assert!(!config.octree_decomposition);
+    assert_eq!(config.surface_threshold, 0.7);
+    assert_eq!(config.smoothing_length().unwrap(), 2.0);
+
+    // Scene values replace the defaults but are overridden by the profile and the command line
+    let scene = ProfileParameters {
+        particle_radius: Some(0.05),
+        rest_density: Some(998.0),
+        smoothing_length: Some(1.5),
+        ..Default::default()
+    };
+    let scene_file = Path::new("scene.json");
+    let config =
+        ResolvedConfig::resolve(Some((scene_file, &scene)), Some(("final", &profile)), &cli);
+    assert_eq!(config.scene.as_deref(), Some("scene.json"));
+    assert_eq!(config.particle_radius().unwrap(), 0.025);
+    assert_eq!(config.smoothing_length().unwrap(), 2.0);
+    assert_eq!(config.rest_density, 998.0);
+    let cli = ProfileParameters {
+        rest_density: Some(1000.0),
+        ..Default::default()
+    };
+    let config = ResolvedConfig::resolve(Some((scene_file, &scene)), None, &cli);
+    assert_eq!(config.particle_radius().unwrap(), 0.05);
+    assert_eq!(config.smoothing_length().unwrap(), 1.5);
+    assert_eq!(config.rest_density, 1000.0);
+
+    // Unknown keys are rejected
+    assert!(toml::from_str::<ProfileParameters>("cube_size = 0.5").is_err());
+}
+
+#[test]
+fn test_load_profile() {
+    let dir = std::env::temp_dir().join("splashsurf_test_load_profile");
+    fs::create_dir_all(&dir).unwrap();
+    let config_file = dir.join("splashsurf.toml");
+    fs::write(
+        &config_file,
+        r#"
+[profiles.preview]
+smoothing-length = 2.0
+cube-size = 1.5
+
+[profiles.final]
+smoothing-length = 2.0
+cube-size = 0.5
+octree-max-particles = 50000
+"#,
+    )
+    .unwrap();
+
+    let profile = load_profile(Some(&config_file), "final").unwrap();
+    assert_eq!(profile.cube_size, Some(0.5));
+    assert_eq!(profile.octree_max_particles, Some(50000));
+    assert_eq!(profile.particle_radius, None);
+
+    let err = load_profile(Some(&config_file), "medium").unwrap_err();
+    assert!(err.to_string().contains("final, preview"));
+    assert!(load_profile(Some(&dir.join("missing.toml")), "final").is_err());
+}
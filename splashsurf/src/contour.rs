@@ -0,0 +1,100 @@
+use crate::io;
+use anyhow::{anyhow, Context};
+use log::info;
+use splashsurf_lib::mesh::LineMesh2d;
+use splashsurf_lib::nalgebra::{Vector2, Vector3};
+use splashsurf_lib::{profile, Parameters2d};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Command line arguments for the `contour` subcommand
+#[derive(Clone, Debug, StructOpt)]
+pub struct ContourSubcommandArgs {
+    /// Path to the input file where the particle positions are stored, only the x and y coordinates are used (supported formats: VTK, binary f32 XYZ, PLY, BGEO)
+    #[structopt(display_order = 1, short = "-i", long, parse(from_os_str))]
+    input_file: PathBuf,
+    /// Filename for writing the reconstructed contour lines to disk (supported formats: .vtk)
+    #[structopt(display_order = 1, short = "-o", long, parse(from_os_str))]
+    output_file: PathBuf,
+    /// Whether to overwrite existing files without asking
+    #[structopt(display_order = 1, long)]
+    overwrite: bool,
+
+    /// The particle radius of the input data
+    #[structopt(display_order = 2, long)]
+    particle_radius: f64,
+    /// The rest density of the fluid (mass per area)
+    #[structopt(display_order = 2, long, default_value = "1000.0")]
+    rest_density: f64,
+    /// The smoothing length radius used for the SPH kernel, the kernel compact support radius will be twice the smoothing length (in multiplies of the particle radius)
+    #[structopt(display_order = 2, long)]
+    smoothing_length: f64,
+    /// The square edge length used for marching squares in multiplies of the particle radius, corresponds to the cell size of the implicit background grid
+    #[structopt(display_order = 2, long)]
+    cube_size: f64,
+    /// The iso-surface threshold for the density, i.e. the normalized value of the reconstructed density level that indicates the fluid surface (in multiplies of the rest density)
+    #[structopt(display_order = 2, long, default_value = "0.6")]
+    surface_threshold: f64,
+}
+
+/// Executes the `contour` subcommand
+pub fn contour_subcommand(cmd_args: &ContourSubcommandArgs) -> Result<(), anyhow::Error> {
+    profile!("contour reconstruction cli");
+
+    if !cmd_args.overwrite && cmd_args.output_file.exists() {
+        return Err(anyhow!(
+            "Aborting: Output file \"{}\" already exists. Use overwrite flag to ignore this.",
+            cmd_args.output_file.display()
+        ));
+    }
+
+    let is_vtk = cmd_args
+        .output_file
+        .extension()
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("vtk"));
+    if !is_vtk {
+        return Err(anyhow!(
+            "Unsupported output file format \"{}\", contours can only be written to .vtk files",
+            cmd_args.output_file.display()
+        ));
+    }
+
+    let io_params = io::FormatParameters::default();
+    let input_file = &cmd_args.input_file;
+
+    // Read particles and project them onto the xy-plane
+    let particle_positions: Vec<Vector3<f64>> =
+        io::read_particle_positions(input_file.as_path(), &io_params.input).with_context(|| {
+            format!(
+                "Failed to load particle positions from file \"{}\"",
+                input_file.as_path().display()
+            )
+        })?;
+    let particle_positions: Vec<Vector2<f64>> = particle_positions.iter().map(|p| p.xy()).collect();
+
+    let parameters = Parameters2d {
+        particle_radius: cmd_args.particle_radius,
+        rest_density: cmd_args.rest_density,
+        compact_support_radius: cmd_args.particle_radius * 2.0 * cmd_args.smoothing_length,
+        cell_size: cmd_args.particle_radius * cmd_args.cube_size,
+        iso_surface_threshold: cmd_args.surface_threshold,
+        domain_aabb: None,
+    };
+
+    let contour: LineMesh2d<f64> =
+        splashsurf_lib::reconstruct_contour_2d::<i64, f64>(&particle_positions, &parameters)
+            .context("Error during contour reconstruction")?
+            .into();
+
+    info!(
+        "Writing contour with {} vertices and {} line segments to \"{}\"...",
+        contour.vertices.len(),
+        contour.lines.len(),
+        cmd_args.output_file.display()
+    );
+
+    io::vtk_format::write_vtk(&contour, &cmd_args.output_file, "contour")?;
+
+    info!("Done.");
+    Ok(())
+}
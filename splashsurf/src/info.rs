@@ -0,0 +1,366 @@
+use crate::io;
+use crate::io::{AttributeInfo, FileInfo};
+use anyhow::{anyhow, Context};
+use splashsurf_lib::{profile, AxisAlignedBoundingBox3d};
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// Command line arguments for the `info` subcommand
+#[derive(Clone, Debug, StructOpt)]
+pub struct InfoSubcommandArgs {
+    /// Path to the particle or mesh file to inspect, use `{}` in the filename to inspect a sequence of files (supported formats: .vtk, .bgeo, .ply, .xyz, .json)
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+    /// Print the information as JSON to stdout (the log is written to stderr in this case)
+    #[structopt(long)]
+    json: bool,
+}
+
+impl InfoSubcommandArgs {
+    /// Returns whether the subcommand writes JSON to stdout
+    pub(crate) fn json_to_stdout(&self) -> bool {
+        self.json
+    }
+}
+
+/// Executes the `info` subcommand
+pub fn info_subcommand(cmd_args: &InfoSubcommandArgs) -> Result<(), anyhow::Error> {
+    profile!("info subcommand");
+
+    let io_params = io::FormatParameters::default();
+    let files = input_files(&cmd_args.input)?
+        .into_iter()
+        .map(|input_file| {
+            let info = io::read_file_info(&input_file, &io_params.input).with_context(|| {
+                format!(
+                    "Failed to read file info from file \"{}\"",
+                    input_file.display()
+                )
+            })?;
+            Ok((input_file, info))
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+    if cmd_args.json {
+        let json = info_to_json(&files);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json).context("Failed to serialize file info to JSON")?
+        );
+    } else {
+        print!("{}", format_info(&files));
+    }
+
+    Ok(())
+}
+
+/// Returns all files matching the input path, a `{}` in the filename is replaced by consecutive frame indices starting at 1
+fn input_files(input: &Path) -> Result<Vec<PathBuf>, anyhow::Error> {
+    let input_filename = input
+        .file_name()
+        .ok_or_else(|| {
+            anyhow!(
+                "The input file path \"{}\" does not end with a filename",
+                input.display()
+            )
+        })?
+        .to_string_lossy();
+
+    if !input_filename.contains("{}") {
+        return if input.is_file() {
+            Ok(vec![input.to_path_buf()])
+        } else {
+            Err(anyhow!(
+                "Input file does not exist: \"{}\"",
+                input.display()
+            ))
+        };
+    }
+
+    let input_dir = input.parent().unwrap_or_else(|| Path::new(""));
+    let files: Vec<_> = (1..)
+        .map(|i| input_dir.join(input_filename.replace("{}", &i.to_string())))
+        .take_while(|input_file| input_file.is_file())
+        .collect();
+
+    if files.is_empty() {
+        Err(anyhow!(
+            "No files were found for the input sequence pattern \"{}\"",
+            input.display()
+        ))
+    } else {
+        Ok(files)
+    }
+}
+
+/// Aggregated information about all files of a sequence
+#[derive(Clone, Debug)]
+struct SequenceInfo {
+    min_num_points: usize,
+    max_num_points: usize,
+    aabb: Option<AxisAlignedBoundingBox3d<f64>>,
+}
+
+impl SequenceInfo {
+    fn new(files: &[(PathBuf, FileInfo)]) -> Self {
+        let num_points = files.iter().map(|(_, info)| info.num_points);
+        let aabb = files
+            .iter()
+            .filter_map(|(_, info)| info.aabb.as_ref())
+            .fold(
+                None,
+                |joined: Option<AxisAlignedBoundingBox3d<f64>>, aabb| {
+                    Some(match joined {
+                        Some(mut joined) => {
+                            joined.join(aabb);
+                            joined
+                        }
+                        None => aabb.clone(),
+                    })
+                },
+            );
+
+        Self {
+            min_num_points: num_points.clone().min().unwrap_or(0),
+            max_num_points: num_points.max().unwrap_or(0),
+            aabb,
+        }
+    }
+}
+
+/// Formats the information about the given files as a human readable report
+fn format_info(files: &[(PathBuf, FileInfo)]) -> String {
+    let mut report = String::new();
+    let (first_file, first_info) = match files.first() {
+        Some(first) => first,
+        None => return report,
+    };
+
+    report.push_str(&format!("File \"{}\":\n", first_file.display()));
+    if first_info.is_mesh() {
+        report.push_str(&format!(
+            "  mesh with {} vertices and {} cells\n",
+            first_info.num_points, first_info.num_cells
+        ));
+    } else {
+        report.push_str(&format!("  {} particles\n", first_info.num_points));
+    }
+    report.push_str(&format!(
+        "  bounding box: {}\n",
+        format_aabb(first_info.aabb.as_ref())
+    ));
+
+    if first_info.attributes.is_empty() {
+        report.push_str("  no attributes\n");
+    } else {
+        report.push_str("  attributes:\n");
+        report.push_str(&format_attribute_table(&first_info.attributes, "    "));
+    }
+
+    if files.len() > 1 {
+        let sequence = SequenceInfo::new(files);
+        report.push_str(&format!(
+            "Sequence of {} files (\"{}\" to \"{}\"):\n",
+            files.len(),
+            first_file.display(),
+            files[files.len() - 1].0.display()
+        ));
+        report.push_str(&format!(
+            "  points per file: min {}, max {}\n",
+            sequence.min_num_points, sequence.max_num_points
+        ));
+        report.push_str(&format!(
+            "  bounding box of all files: {}\n",
+            format_aabb(sequence.aabb.as_ref())
+        ));
+    }
+
+    report
+}
+
+fn format_aabb(aabb: Option<&AxisAlignedBoundingBox3d<f64>>) -> String {
+    match aabb {
+        Some(aabb) => format!(
+            "[{}, {}, {}] to [{}, {}, {}]",
+            aabb.min().x,
+            aabb.min().y,
+            aabb.min().z,
+            aabb.max().x,
+            aabb.max().y,
+            aabb.max().z
+        ),
+        None => "none (no points)".to_string(),
+    }
+}
+
+/// Formats the given attributes as a table with aligned columns, every line is prefixed with the given indentation
+fn format_attribute_table(attributes: &[AttributeInfo], indent: &str) -> String {
+    let header = ["name", "location", "type", "components"];
+    let rows: Vec<[String; 4]> = attributes
+        .iter()
+        .map(|attribute| {
+            [
+                attribute.name.clone(),
+                attribute.location.as_str().to_string(),
+                attribute.data_type.clone(),
+                attribute
+                    .num_components
+                    .map_or_else(|| "variable".to_string(), |n| n.to_string()),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut table = String::new();
+    let mut push_row = |cells: [&str; 4]| {
+        let line = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        table.push_str(indent);
+        table.push_str(line.trim_end());
+        table.push('\n');
+    };
+
+    push_row(header);
+    for row in &rows {
+        push_row([&row[0], &row[1], &row[2], &row[3]]);
+    }
+
+    table
+}
+
+fn aabb_to_json(aabb: Option<&AxisAlignedBoundingBox3d<f64>>) -> serde_json::Value {
+    match aabb {
+        Some(aabb) => serde_json::json!({
+            "min": aabb.min().as_slice(),
+            "max": aabb.max().as_slice(),
+        }),
+        None => serde_json::Value::Null,
+    }
+}
+
+fn info_to_json(files: &[(PathBuf, FileInfo)]) -> serde_json::Value {
+    let file_to_json = |(file, info): &(PathBuf, FileInfo)| {
+        let attributes: Vec<_> = info
+            .attributes
+            .iter()
+            .map(|attribute| {
+                serde_json::json!({
+                    "name": attribute.name,
+                    "location": attribute.location.as_str(),
+                    "data_type": attribute.data_type,
+                    "num_components": attribute.num_components,
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "file": file.display().to_string(),
+            "is_mesh": info.is_mesh(),
+            "num_points": info.num_points,
+            "num_cells": info.num_cells,
+            "aabb": aabb_to_json(info.aabb.as_ref()),
+            "attributes": attributes,
+        })
+    };
+
+    let sequence = SequenceInfo::new(files);
+    serde_json::json!({
+        "num_files": files.len(),
+        "min_num_points": sequence.min_num_points,
+        "max_num_points": sequence.max_num_points,
+        "aabb": aabb_to_json(sequence.aabb.as_ref()),
+        "files": files.iter().map(file_to_json).collect::<Vec<_>>(),
+    })
+}
+
+#[test]
+fn test_format_info_of_sequence() {
+    use crate::io::AttributeLocation;
+    use splashsurf_lib::nalgebra::Vector3;
+
+    let frame = |num_points: usize, max: f64| FileInfo {
+        num_points,
+        num_cells: 0,
+        aabb: Some(AxisAlignedBoundingBox3d::new(
+            Vector3::zeros(),
+            Vector3::repeat(max),
+        )),
+        attributes: vec![
+            AttributeInfo {
+                name: "velocity".to_string(),
+                location: AttributeLocation::Point,
+                data_type: "f32".to_string(),
+                num_components: Some(3),
+            },
+            AttributeInfo {
+                name: "id".to_string(),
+                location: AttributeLocation::Point,
+                data_type: "i32".to_string(),
+                num_components: Some(1),
+            },
+        ],
+    };
+    let files = vec![
+        (PathBuf::from("fluid_1.vtk"), frame(100, 1.0)),
+        (PathBuf::from("fluid_2.vtk"), frame(250, 3.0)),
+        (PathBuf::from("fluid_3.vtk"), frame(50, 2.0)),
+    ];
+
+    assert_eq!(
+        format_info(&files),
+        "File \"fluid_1.vtk\":
+  100 particles
+  bounding box: [0, 0, 0] to [1, 1, 1]
+  attributes:
+    name      location  type  components
+    velocity  point     f32   3
+    id        point     i32   1
+Sequence of 3 files (\"fluid_1.vtk\" to \"fluid_3.vtk\"):
+  points per file: min 50, max 250
+  bounding box of all files: [0, 0, 0] to [3, 3, 3]
+"
+    );
+
+    let json = info_to_json(&files);
+    assert_eq!(json["num_files"], 3);
+    assert_eq!(json["min_num_points"], 50);
+    assert_eq!(json["max_num_points"], 250);
+    assert_eq!(json["aabb"]["max"], serde_json::json!([3.0, 3.0, 3.0]));
+    assert_eq!(json["files"][1]["attributes"][0]["num_components"], 3);
+}
+
+#[test]
+fn test_info_subcommand() {
+    let input_dir = std::env::temp_dir().join("splashsurf_test_info");
+    let _ = std::fs::remove_dir_all(&input_dir);
+    std::fs::create_dir_all(&input_dir).unwrap();
+
+    let input_file = crate::io::test_cube_particles_file();
+    for i in 1..=2 {
+        std::fs::copy(input_file, input_dir.join(format!("cube_{}.vtk", i))).unwrap();
+    }
+
+    let input_pattern = input_dir.join("cube_{}.vtk");
+    let files = input_files(&input_pattern).unwrap();
+    assert_eq!(files.len(), 2);
+    assert!(input_files(&input_dir.join("missing_{}.vtk")).is_err());
+
+    let info = io::read_file_info(&files[0], &io::InputFormatParameters::default()).unwrap();
+    assert_eq!(info.num_points, 2366);
+    assert!(!info.is_mesh());
+
+    let args =
+        InfoSubcommandArgs::from_iter_safe(&["info", input_pattern.to_str().unwrap(), "--json"])
+            .unwrap();
+    info_subcommand(&args).unwrap();
+}
@@ -1,4 +1,4 @@
-use crate::io::vtk_format::VtkFile;
+use crate::io::vtk_format::{VtkFile, VtkIoError};
 use anyhow::{anyhow, Context};
 use log::{info, warn};
 use splashsurf_lib::mesh::MeshAttribute;
@@ -25,57 +25,109 @@ pub struct FormatParameters {
 
 /// File format parameters for input files
 #[derive(Clone, Debug)]
-pub struct InputFormatParameters {}
+pub struct InputFormatParameters {
+    /// Format of the input file (e.g. "vtk"), overrides the detection from the file extension and is required for stdin
+    pub format: Option<String>,
+}
 
 impl Default for InputFormatParameters {
     fn default() -> Self {
-        Self {}
+        Self { format: None }
     }
 }
 
 /// File format parameters for output files
 #[derive(Clone, Debug)]
-pub struct OutputFormatParameters {}
+pub struct OutputFormatParameters {
+    /// Format of the output file (e.g. "obj"), overrides the detection from the file extension and is required for stdout
+    pub format: Option<String>,
+}
 
 impl Default for OutputFormatParameters {
     fn default() -> Self {
-        Self {}
+        Self { format: None }
+    }
+}
+
+/// Returns whether the given path is `-`, i.e. refers to stdin for input files and to stdout for output files
+pub fn is_stdio<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref() == Path::new("-")
+}
+
+/// Returns the lowercase file format of the given file, either the explicitly specified format or its extension
+fn file_format(
+    file: &Path,
+    explicit_format: Option<&str>,
+    file_kind: &str,
+) -> Result<String, anyhow::Error> {
+    if let Some(format) = explicit_format {
+        return Ok(format.to_lowercase());
+    }
+
+    if is_stdio(file) {
+        return Err(anyhow!(
+            "The file format of the {} has to be specified explicitly when using stdin or stdout",
+            file_kind
+        ));
     }
+
+    let extension = file.extension().ok_or(anyhow!(
+        "Unable to detect file format of {} (file name has to end with supported extension)",
+        file_kind
+    ))?;
+    let extension = extension
+        .to_str()
+        .ok_or(anyhow!("Invalid extension of {}", file_kind))?;
+    Ok(extension.to_lowercase())
 }
 
-/// Loads particles positions from the given file path, automatically detects the file format
+/// Loads particles positions from the given file path (or stdin for `-`), automatically detects the file format
 pub fn read_particle_positions<R: Real, P: AsRef<Path>>(
     input_file: P,
-    _format_params: &InputFormatParameters,
+    format_params: &InputFormatParameters,
 ) -> Result<Vec<Vector3<R>>, anyhow::Error> {
     let input_file = input_file.as_ref();
-    info!(
-        "Reading particle dataset from \"{}\"...",
-        input_file.display()
-    );
-
-    let particle_positions = if let Some(extension) = input_file.extension() {
+    let format = file_format(
+        input_file,
+        format_params.format.as_deref(),
+        "particle input file",
+    )?;
+
+    let particle_positions = if is_stdio(input_file) {
+        info!("Reading particle dataset from stdin...");
         profile!("loading particle positions");
 
-        let extension = extension
-            .to_str()
-            .ok_or(anyhow!("Invalid extension of input file"))?;
+        let stdin = std::io::stdin();
+        let reader = stdin.lock();
+        match format.as_str() {
+            "vtk" => vtk_format::particles_from_vtk_reader(reader).map_err(Into::into),
+            "xyz" => xyz_format::particles_from_xyz_reader(reader),
+            "ply" => ply_format::particles_from_ply_reader(reader),
+            "bgeo" => bgeo_format::particles_from_bgeo_reader(reader),
+            "json" => json_format::particles_from_json_reader(reader),
+            _ => Err(anyhow!(
+                "Unsupported file format \"{}\" for reading particles",
+                format
+            )),
+        }
+    } else {
+        info!(
+            "Reading particle dataset from \"{}\"...",
+            input_file.display()
+        );
+        profile!("loading particle positions");
 
-        match extension.to_lowercase().as_str() {
-            "vtk" => vtk_format::particles_from_vtk(&input_file),
+        match format.as_str() {
+            "vtk" => vtk_format::particles_from_vtk(&input_file).map_err(Into::into),
             "xyz" => xyz_format::particles_from_xyz(&input_file),
             "ply" => ply_format::particles_from_ply(&input_file),
             "bgeo" => bgeo_format::particles_from_bgeo(&input_file),
             "json" => json_format::particles_from_json(&input_file),
             _ => Err(anyhow!(
                 "Unsupported file format extension \"{}\" for reading particles",
-                extension
+                format
             )),
         }
-    } else {
-        Err(anyhow!(
-            "Unable to detect file format of particle input file (file name has to end with supported extension)",
-        ))
     }?;
 
     info!(
@@ -104,21 +156,28 @@ pub fn read_particle_positions_with_attributes<R: Real, P: AsRef<Path>>(
 
     profile!("loading particle positions and attributes");
 
-    // Check file extension: only VTK is supported for reading attributes at the moment
+    // Check file format: only VTK is supported for reading attributes at the moment
     {
-        let extension = input_file.extension().ok_or(anyhow!(
-            "Unable to detect file format of particle input file (file name has to end with supported extension)",
-        ))?.to_str().ok_or(anyhow!("Invalid extension of input file"))?.to_lowercase();
+        let format = file_format(
+            input_file,
+            format_params.format.as_deref(),
+            "particle input file",
+        )?;
 
-        if extension != "vtk" {
+        if format != "vtk" {
             return Err(anyhow!(
-                "Unsupported file format extension \"{}\" for reading particles and attributes",
-                extension
+                "Unsupported file format \"{}\" for reading particles and attributes",
+                format
             ));
         }
     }
 
-    let vtk_pieces = VtkFile::load_file(input_file)
+    let vtk_file = if is_stdio(input_file) {
+        VtkFile::load_from_reader(std::io::stdin().lock())
+    } else {
+        VtkFile::load_file(input_file)
+    };
+    let vtk_pieces = vtk_file
         .map(|f| f.into_pieces())
         .with_context(|| format!("Failed to load particle positions from file"))?;
 
@@ -148,11 +207,14 @@ pub fn read_particle_positions_with_attributes<R: Real, P: AsRef<Path>>(
                 .difference(&attributes)
                 .cloned()
                 .collect::<Vec<_>>();
-            if !missing_attributes.is_empty() {
-                return Err(anyhow!(
+            if let Some(missing_attribute) = missing_attributes.first() {
+                return Err(anyhow::Error::new(VtkIoError::AttributeMissing {
+                    name: missing_attribute.clone(),
+                })
+                .context(format!(
                     "Missing attribute(s) \"{}\" in input file",
                     missing_attributes.join("\", \""),
-                ));
+                )));
             }
         }
 
@@ -168,6 +230,39 @@ pub fn read_particle_positions_with_attributes<R: Real, P: AsRef<Path>>(
     Ok((particle_positions, attributes))
 }
 
+/// Reads the number of points and cells, the bounding box and the available attributes of the given file, automatically detects the file format
+pub fn read_file_info<P: AsRef<Path>>(
+    input_file: P,
+    _format_params: &InputFormatParameters,
+) -> Result<FileInfo, anyhow::Error> {
+    let input_file = input_file.as_ref();
+    info!("Reading file info of \"{}\"...", input_file.display());
+
+    if let Some(extension) = input_file.extension() {
+        profile!("loading file info");
+
+        let extension = extension
+            .to_str()
+            .ok_or(anyhow!("Invalid extension of input file"))?;
+
+        match extension.to_lowercase().as_str() {
+            "vtk" => vtk_format::file_info_from_vtk(&input_file).map_err(Into::into),
+            "xyz" => xyz_format::file_info_from_xyz(&input_file),
+            "ply" => ply_format::file_info_from_ply(&input_file),
+            "bgeo" => bgeo_format::file_info_from_bgeo(&input_file),
+            "json" => json_format::file_info_from_json(&input_file),
+            _ => Err(anyhow!(
+                "Unsupported file format extension \"{}\" for reading file info",
+                extension
+            )),
+        }
+    } else {
+        Err(anyhow!(
+            "Unable to detect file format of input file (file name has to end with supported extension)",
+        ))
+    }
+}
+
 /// Writes particles positions to the given file path, automatically detects the file format
 pub fn write_particle_positions<R: Real, P: AsRef<Path>>(
     particles: &[Vector3<R>],
@@ -189,7 +284,7 @@ pub fn write_particle_positions<R: Real, P: AsRef<Path>>(
             .ok_or(anyhow!("Invalid extension of output file"))?;
 
         match extension.to_lowercase().as_str() {
-            "vtk" => vtk_format::particles_to_vtk(particles, &output_file),
+            "vtk" => vtk_format::particles_to_vtk(particles, &output_file).map_err(Into::into),
             _ => Err(anyhow!(
                 "Unsupported file format extension \"{}\" for writing particles",
                 extension
@@ -221,7 +316,7 @@ pub fn read_surface_mesh<R: Real, P: AsRef<Path>>(
             .ok_or(anyhow!("Invalid extension of input file"))?;
 
         match extension.to_lowercase().as_str() {
-            "vtk" => vtk_format::surface_mesh_from_vtk(&input_file),
+            "vtk" => vtk_format::surface_mesh_from_vtk(&input_file).map_err(Into::into),
             "ply" => ply_format::surface_mesh_from_ply(&input_file),
             _ => Err(anyhow!(
                 "Unsupported file format extension \"{}\" for reading surface meshes",
@@ -243,16 +338,44 @@ pub fn read_surface_mesh<R: Real, P: AsRef<Path>>(
     Ok(mesh)
 }
 
-/// Writes a mesh and its attribute data to the given file path, automatically detects the file format
+/// Writes a mesh and its attribute data to the given file path (or stdout for `-`), automatically detects the file format
 pub fn write_mesh<'a, R: Real, MeshT: Mesh3d<R>, P: AsRef<Path>>(
     mesh: &'a MeshWithData<R, MeshT>,
     output_file: P,
-    _format_params: &OutputFormatParameters,
+    format_params: &OutputFormatParameters,
 ) -> Result<(), anyhow::Error>
 where
     &'a MeshWithData<R, MeshT>: Into<DataSet>,
 {
     let output_file = output_file.as_ref();
+    let format = file_format(
+        output_file,
+        format_params.format.as_deref(),
+        "mesh output file",
+    )?;
+
+    if is_stdio(output_file) {
+        info!(
+            "Writing mesh with {} vertices and {} cells to stdout...",
+            mesh.mesh.vertices().len(),
+            mesh.mesh.cells().len(),
+        );
+        profile!("writing mesh");
+
+        let stdout = std::io::stdout();
+        let mut writer = BufWriter::new(stdout.lock());
+        match format.as_str() {
+            "vtk" => vtk_format::write_vtk_to_writer(mesh, &mut writer, "mesh").map_err(Into::into),
+            "obj" => obj_format::mesh_to_obj_writer(mesh, &mut writer),
+            "ply" => ply_format::mesh_to_ply_writer(mesh, &mut writer),
+            _ => Err(anyhow!("Unsupported file format \"{}\"", format)),
+        }?;
+        writer.flush().context("Failed to flush mesh to stdout")?;
+
+        info!("Successfully wrote mesh to stdout.");
+        return Ok(());
+    }
+
     info!(
         "Writing mesh with {} vertices and {} cells to \"{}\"...",
         mesh.mesh.vertices().len(),
@@ -260,26 +383,16 @@ where
         output_file.display()
     );
 
-    if let Some(extension) = output_file.extension() {
+    {
         profile!("writing mesh");
 
-        let extension = extension
-            .to_str()
-            .ok_or(anyhow!("Invalid extension of output file"))?;
-
-        match extension.to_lowercase().as_str() {
-            "vtk" => vtk_format::write_vtk(mesh, &output_file, "mesh"),
+        match format.as_str() {
+            "vtk" => vtk_format::write_vtk(mesh, &output_file, "mesh").map_err(Into::into),
             "obj" => obj_format::mesh_to_obj(mesh, &output_file),
-            _ => Err(anyhow!(
-                "Unsupported file format extension \"{}\"",
-                extension,
-            )),
+            "ply" => ply_format::mesh_to_ply(mesh, &output_file),
+            _ => Err(anyhow!("Unsupported file format extension \"{}\"", format,)),
         }?;
-    } else {
-        return Err(anyhow!(
-            "Unable to detect file format of mesh output file (file name has to end with supported extension)",
-        ));
-    };
+    }
 
     info!("Successfully wrote mesh to file.");
     Ok(())
@@ -298,3 +411,33 @@ pub fn to_binary_f32<R: Real, P: AsRef<Path>>(file: P, values: &[R]) -> Result<(
 
     Ok(())
 }
+
+/// Returns the path of a VTK file with a cube of 13x13x14 particles on a lattice with a spacing of 0.05 for the tests of the subcommands
+///
+/// The file is generated in the temporary directory once per process so that the tests do not depend on the data
+/// files of the repository that are stored in Git LFS.
+#[cfg(test)]
+pub(crate) fn test_cube_particles_file() -> &'static str {
+    use std::sync::OnceLock;
+
+    static FILE: OnceLock<String> = OnceLock::new();
+    FILE.get_or_init(|| {
+        let mut particles = Vec::with_capacity(13 * 13 * 14);
+        for i in 0..13 {
+            for j in 0..13 {
+                for k in 0..14 {
+                    particles.push(Vector3::new(i as f64, j as f64, k as f64) * 0.05);
+                }
+            }
+        }
+
+        let dir = std::env::temp_dir().join("splashsurf_test_data");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("cube_2366_particles.vtk");
+        // Write to a temporary file first to never expose partially written files to other test processes
+        let tmp_file = dir.join(format!("cube_2366_particles_{}.vtk", std::process::id()));
+        vtk_format::particles_to_vtk(&particles, &tmp_file).unwrap();
+        std::fs::rename(&tmp_file, &file).unwrap();
+        file.to_str().unwrap().to_string()
+    })
+}
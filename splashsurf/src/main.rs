@@ -1,6 +1,11 @@
+mod bench;
+mod config;
+mod contour;
 mod convert;
+mod info;
 mod io;
 mod reconstruction;
+mod scene;
 #[macro_use]
 mod allocator;
 
@@ -43,6 +48,14 @@ enum Subcommand {
     Reconstruct(reconstruction::ReconstructSubcommandArgs),
     /// Convert particle or mesh files between different file formats
     Convert(convert::ConvertSubcommandArgs),
+    /// Reconstruct the contour lines of planar particle data (only the x and y coordinates are used)
+    Contour(contour::ContourSubcommandArgs),
+    /// Print the number of particles, the bounding box and the available attributes of particle or mesh files
+    Info(info::InfoSubcommandArgs),
+    /// Benchmark the reconstruction of a particle file for a set of cube sizes with and without spatial decomposition
+    Bench(bench::BenchSubcommandArgs),
+    /// Print the reconstruction parameters resolved from the built-in defaults and a profile of a config file
+    PrintConfig(config::PrintConfigSubcommandArgs),
 }
 
 fn main() -> Result<(), anyhow::Error> {
@@ -58,24 +71,49 @@ fn main() -> Result<(), anyhow::Error> {
         Ok(_) => 0,
         Err(err) => {
             log_error(&err);
-            1
+            exit_code(&err)
         }
     });
 }
 
+/// Returns the exit code of the process for the given error: 1 if only some input files failed to be processed, 2 for all other (fatal) errors
+pub(crate) fn exit_code(err: &anyhow::Error) -> i32 {
+    if err
+        .chain()
+        .any(|cause| cause.is::<reconstruction::FramesFailed>())
+    {
+        1
+    } else {
+        2
+    }
+}
+
 fn run_splashsurf() -> Result<(), anyhow::Error> {
     let cmd_args = CommandlineArgs::from_args();
 
     let verbosity = VerbosityLevel::from(cmd_args.verbosity);
     let is_quiet = cmd_args.quiet;
 
-    initialize_logging(verbosity, is_quiet).context("Failed to initialize logging")?;
+    // Keep stdout free for the JSON summary, the surface mesh or the printed configuration if they are written to stdout
+    let log_to_stderr = match &cmd_args.subcommand {
+        Subcommand::Reconstruct(cmd_args) => cmd_args.log_to_stderr(),
+        Subcommand::Info(cmd_args) => cmd_args.json_to_stdout(),
+        Subcommand::PrintConfig(_) => true,
+        _ => false,
+    };
+
+    initialize_logging(verbosity, is_quiet, log_to_stderr)
+        .context("Failed to initialize logging")?;
     log_program_info();
 
     // Delegate to subcommands
     match &cmd_args.subcommand {
         Subcommand::Reconstruct(cmd_args) => reconstruction::reconstruct_subcommand(cmd_args)?,
         Subcommand::Convert(cmd_args) => convert::convert_subcommand(cmd_args)?,
+        Subcommand::Contour(cmd_args) => contour::contour_subcommand(cmd_args)?,
+        Subcommand::Info(cmd_args) => info::info_subcommand(cmd_args)?,
+        Subcommand::Bench(cmd_args) => bench::bench_subcommand(cmd_args)?,
+        Subcommand::PrintConfig(cmd_args) => config::print_config_subcommand(cmd_args)?,
     }
 
     // Write coarse_prof stats using log::info
@@ -106,8 +144,12 @@ pub fn log_error(err: &anyhow::Error) {
         .for_each(|cause| error!("  caused by: {}", cause));
 }
 
-/// Initializes logging with fern
-fn initialize_logging(verbosity: VerbosityLevel, quiet_mode: bool) -> Result<(), anyhow::Error> {
+/// Initializes logging with fern, writes the log to stdout unless `log_to_stderr` is set
+fn initialize_logging(
+    verbosity: VerbosityLevel,
+    quiet_mode: bool,
+    log_to_stderr: bool,
+) -> Result<(), anyhow::Error> {
     let mut unknown_log_filter_level = None;
     let log_filter_level = if quiet_mode {
         // First option: disable logging in quiet mode
@@ -137,7 +179,7 @@ fn initialize_logging(verbosity: VerbosityLevel, quiet_mode: bool) -> Result<(),
         })
     };
 
-    fern::Dispatch::new()
+    let dispatch = fern::Dispatch::new()
         .format(|out, message, record| {
             out.finish(format_args!(
                 "[{}][{}][{}] {}",
@@ -150,8 +192,13 @@ fn initialize_logging(verbosity: VerbosityLevel, quiet_mode: bool) -> Result<(),
                 message
             ))
         })
-        .level(log_filter_level)
-        .chain(std::io::stdout())
+        .level(log_filter_level);
+    let dispatch = if log_to_stderr {
+        dispatch.chain(std::io::stderr())
+    } else {
+        dispatch.chain(std::io::stdout())
+    };
+    dispatch
         .apply()
         .map_err(|e| anyhow!("Unable to apply logger configuration ({:?})", e))?;
 
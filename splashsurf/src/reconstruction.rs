@@ -1,75 +1,234 @@
-use crate::{io, log_error};
+use crate::config::{self, ProfileParameters, ResolvedConfig};
+use crate::io;
+use crate::scene;
 use anyhow::{anyhow, Context};
 use arguments::{
-    ReconstructionRunnerArgs, ReconstructionRunnerPathCollection, ReconstructionRunnerPaths,
+    CoordinateTransform, ReconstructionRunnerArgs, ReconstructionRunnerPathCollection,
+    ReconstructionRunnerPaths, RegionOfInterest,
 };
-use log::info;
+use cache::DensityMapCache;
+use error_policy::{ErrorPolicy, FrameError};
+use log::{error, info, log, log_enabled, trace, warn, Level};
+use measure::{MeasureCsv, SurfaceMetrics};
+use output_policy::{FrameAction, FrameCounts, OutputPolicy};
+use post_processing::{PostProcessingStep, SmoothingMethod};
 use rayon::prelude::*;
-use splashsurf_lib::mesh::{AttributeData, Mesh3d, MeshAttribute, MeshWithData, PointCloud3d};
-use splashsurf_lib::nalgebra::{Unit, Vector3};
+use splashsurf_lib::mesh::{
+    curvature, AttributeData, Connectivity, Mesh3d, MeshAttribute, MeshWithData, PointCloud3d,
+    TriMesh3d,
+};
+use splashsurf_lib::nalgebra::Vector3;
+use splashsurf_lib::parameter_suggestion::{
+    estimate_reconstruction, Budget, ReconstructionEstimate,
+};
 use splashsurf_lib::profile;
-use splashsurf_lib::sph_interpolation::SphInterpolator;
-use splashsurf_lib::{density_map, Index, Real};
+use splashsurf_lib::profiling::{self, ProfilingReport, ProfilingScope};
+use splashsurf_lib::timings::{StageTiming, TimingsCollector};
+use splashsurf_lib::{
+    density_map, select_index_type, AttributeInterpolation, AttributeSpecs,
+    AxisAlignedBoundingBox3d, GridEscapePolicy, Index, IndexType, LeafTimings, NormalsComputation,
+    Real, ReconstructionWarnings, SplashDetection, SubdivisionCriterion, SurfaceReconstruction,
+    SurfaceReconstructionDyn, Timings,
+};
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::convert::TryFrom;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use structopt::clap::arg_enum;
 use structopt::StructOpt;
 
-// TODO: Detect smallest index type (i.e. check if ok to use i32 as index)
+mod cache;
+mod error_policy;
+mod measure;
+mod output_policy;
+mod post_processing;
+mod time_series;
+mod watch;
+
+pub use error_policy::FramesFailed;
 
 /// Command line arguments for the `reconstruct` subcommand
 #[derive(Clone, Debug, StructOpt)]
 pub struct ReconstructSubcommandArgs {
-    /// Path to the input file where the particle positions are stored (supported formats: VTK, binary f32 XYZ, PLY, BGEO)
+    /// Path to the input file where the particle positions are stored, alternative to `--input-file`, use `-` to read the particles from stdin (requires `--input-format`)
+    #[structopt(display_order = 1, parse(from_os_str), conflicts_with_all = &["input-file", "input-sequence", "watch"])]
+    input: Option<PathBuf>,
+    /// Path to the input file where the particle positions are stored (supported formats: VTK, binary f32 XYZ, PLY, BGEO), use `-` to read the particles from stdin (requires `--input-format`)
     #[structopt(display_order = 1, short = "-i", long, parse(from_os_str))]
     input_file: Option<PathBuf>,
     /// Path to a sequence of particle files that should be processed, use `{}` in the filename to indicate a placeholder
     #[structopt(display_order = 1, short = "-s", long, parse(from_os_str))]
     input_sequence: Option<PathBuf>,
-    /// Filename for writing the reconstructed surface to disk (default: "{original_filename}_surface.vtk")
+    /// Filename for writing the reconstructed surface to disk (default: "{original_filename}_surface.vtk"), use `-` to write the surface to stdout (requires `--output-format`, the log is then written to stderr)
     #[structopt(display_order = 1, short = "-o", long, parse(from_os_str))]
     output_file: Option<PathBuf>,
+    /// Format of the input file, overrides the detection from the file extension (required for stdin)
+    #[structopt(display_order = 1, long, possible_values = &["vtk", "xyz", "ply", "bgeo", "json"], case_insensitive = true)]
+    input_format: Option<String>,
+    /// Format of the output file, overrides the detection from the file extension (required for stdout)
+    #[structopt(display_order = 1, long, possible_values = &["vtk", "obj", "ply"], case_insensitive = true)]
+    output_format: Option<String>,
     /// Optional base directory for all output files (default: current working directory)
     #[structopt(display_order = 1, long, parse(from_os_str))]
     output_dir: Option<PathBuf>,
+    /// Regex with a single capture group to parse the physical time of every frame of a sequence from its filename, e.g. `_t([0-9.]+)\.vtk$` for "fluid_t0.0125.vtk". The time is written to the ParaView collection and the JSON summary.
+    #[structopt(display_order = 1, long, requires = "input-sequence")]
+    time_regex: Option<String>,
+    /// Time between two consecutive frames of a sequence, the time of a frame is its index times the time step if no time regex is given or the parsed times are invalid or not strictly increasing
+    #[structopt(display_order = 1, long, default_value = "1.0")]
+    time_step: f64,
+    /// Optional filename for writing a ParaView collection (.pvd) that references all output files with the time of their frame (relative to the output directory if given)
+    #[structopt(display_order = 1, long, parse(from_os_str), conflicts_with = "watch")]
+    output_pvd: Option<PathBuf>,
+    /// Directory or file pattern with a `{}` placeholder for the frame number that is watched for new particle files written by a running simulation. Every file is reconstructed as soon as its size is stable, files with an existing output file that is newer than the input file are skipped. Runs until interrupted with Ctrl-C.
+    #[structopt(display_order = 1, long, parse(from_os_str), conflicts_with_all = &["input-file", "input-sequence", "dry-run", "suggest-cube-size"])]
+    watch: Option<PathBuf>,
+    /// Interval in seconds for polling the files of `--watch`
+    #[structopt(display_order = 1, long, default_value = "1.0")]
+    watch_interval: f64,
+    /// Skip input files with an existing output file that is newer than the input file (outdated output files are only replaced with `--overwrite`)
+    #[structopt(display_order = 1, long)]
+    skip_existing: bool,
+    /// Allow to overwrite existing output files, by default input files with an existing output file are not processed
+    #[structopt(display_order = 1, long)]
+    overwrite: bool,
+    /// Reconstruct all input files and overwrite their output files, even if they are up-to-date (overrides `--skip-existing`)
+    #[structopt(display_order = 1, long)]
+    force: bool,
+    /// What to do if the reconstruction of an input file fails (e.g. a corrupt file or a panic): `abort` stops processing further input files, `skip` continues with the next input file and `retry:N` retries the input file up to N times before continuing. The process exits with code 1 if any input file failed.
+    #[structopt(display_order = 1, long, default_value = "abort")]
+    on_error: ErrorPolicy,
 
-    /// The particle radius of the input data
+    /// Name of a profile of the config file that provides default values for the reconstruction parameters, arguments given on the command line override the values of the profile
+    #[structopt(display_order = 2, long)]
+    profile: Option<String>,
+    /// Path to the TOML config file containing the profiles (default: "splashsurf.toml" in the current working directory)
+    #[structopt(display_order = 2, long, parse(from_os_str), requires = "profile")]
+    config: Option<PathBuf>,
+    /// SPlisHSPlasH scene file (JSON) that provides default values for the particle radius, rest density and smoothing length (if the scene specifies a support radius), the profile and arguments given on the command line override the values of the scene
+    #[structopt(display_order = 2, long, parse(from_os_str))]
+    scene: Option<PathBuf>,
+    /// The particle radius of the input data (required unless given by the profile or the scene file)
+    #[structopt(display_order = 2, long)]
+    particle_radius: Option<f64>,
+    /// The rest density of the fluid (default: 1000.0)
+    #[structopt(display_order = 2, long)]
+    rest_density: Option<f64>,
+    /// The smoothing length radius used for the SPH kernel, the kernel compact support radius will be twice the smoothing length (in multiplies of the particle radius, required unless given by the profile)
+    #[structopt(display_order = 2, long)]
+    smoothing_length: Option<f64>,
+    /// The cube edge length used for marching cubes in multiplies of the particle radius, corresponds to the cell size of the implicit background grid (required for a reconstruction unless given by the profile)
     #[structopt(display_order = 2, long)]
-    particle_radius: f64,
-    /// The rest density of the fluid
-    #[structopt(display_order = 2, long, default_value = "1000.0")]
-    rest_density: f64,
-    /// The smoothing length radius used for the SPH kernel, the kernel compact support radius will be twice the smoothing length (in multiplies of the particle radius)
+    cube_size: Option<f64>,
+    /// Instead of performing a reconstruction, print estimates of the resource requirements for a range of cube sizes and suggest a cube size meeting the triangle/memory budget
     #[structopt(display_order = 2, long)]
-    smoothing_length: f64,
-    /// The cube edge length used for marching cubes in multiplies of the particle radius, corresponds to the cell size of the implicit background grid
+    suggest_cube_size: bool,
+    /// Instead of performing a reconstruction, load the first input file, report the resolved parameters, the background grid and estimates of the resource requirements and check that all output files can be written (fails if the estimates exceed `--max-triangles` or `--max-memory`)
+    #[structopt(display_order = 2, long, conflicts_with = "suggest-cube-size")]
+    dry_run: bool,
+    /// Maximum number of triangles of the surface mesh used to suggest a cube size with `--suggest-cube-size` or checked by `--dry-run`
     #[structopt(display_order = 2, long)]
-    cube_size: f64,
-    /// The iso-surface threshold for the density, i.e. the normalized value of the reconstructed density level that indicates the fluid surface (in multiplies of the rest density)
-    #[structopt(display_order = 2, long, default_value = "0.6")]
-    surface_threshold: f64,
-
-    /// Whether to enable the use of double precision for all computations
-    #[structopt(display_order = 3, short = "-d", long, default_value = "off", possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
-    double_precision: Switch,
-    /// Lower corner of the domain where surface reconstruction should be performed, format: domain-min=x_min;y_min;z_min (requires domain-max to be specified)
+    max_triangles: Option<u64>,
+    /// Maximum memory consumption in MiB used to suggest a cube size with `--suggest-cube-size` or checked by `--dry-run`
+    #[structopt(display_order = 2, long)]
+    max_memory: Option<f64>,
+    /// The iso-surface threshold for the density, i.e. the normalized value of the reconstructed density level that indicates the fluid surface (in multiplies of the rest density, default: 0.6)
+    #[structopt(display_order = 2, long)]
+    surface_threshold: Option<f64>,
+    /// Variant of the triangulation: the classic marching cubes lookup table, the topologically correct `mc33` variant that resolves ambiguous cells using the density values at their corners (avoids tiny holes in thin sheets of fluid) or the dual `surfacenets` triangulation with one vertex per cell (more regular triangles, requires `--octree-stitch-subdomains=off` when the octree decomposition is enabled)
+    #[structopt(display_order = 2, long, default_value = "classic", possible_values = &["classic", "mc33", "surfacenets"], case_insensitive = true, require_equals = true)]
+    mc_variant: MarchingCubesMode,
+    /// If specified, iso-surface vertices closer than this distance to a grid point are snapped onto the point and merged to avoid sliver triangles (in multiplies of the cube size, has to be less than 0.5, e.g. 1e-4, ignored by the `surfacenets` variant)
+    #[structopt(display_order = 2, long)]
+    mc_vertex_snapping: Option<f64>,
+    /// If specified, particles without any neighbors within this radius are classified as splash particles and excluded from the reconstruction (in multiplies of the particle radius)
+    #[structopt(display_order = 2, long)]
+    splash_detection_radius: Option<f64>,
+    /// If specified, particles with a density below this factor times the density of an isolated particle are classified as splash particles and excluded from the reconstruction (avoids an additional neighborhood search, cannot be combined with the splash detection radius or with disabling both the global and the synchronized local density computation of the octree)
+    #[structopt(display_order = 2, long)]
+    splash_detection_density_factor: Option<f64>,
+    /// How to output the detected splash particles: not at all, as spheres in a separate mesh (written next to the output file with a "_splash" suffix) or as spheres merged into the surface mesh
+    #[structopt(display_order = 2, long, default_value = "ignore", possible_values = &["ignore", "separate", "merged"], case_insensitive = true, require_equals = true)]
+    splash_output: SplashOutputMode,
+    /// Number of subdivisions of the icosahedron used as the sphere geometry for the output of splash particles
+    #[structopt(display_order = 2, long, default_value = "1")]
+    splash_sphere_subdivisions: usize,
+    /// How to handle particles with non-finite (NaN or infinite) positions and duplicate particles: abort with an error, remove them before the reconstruction or skip the validation
+    #[structopt(display_order = 2, long, default_value = "error", possible_values = &["error", "filter", "skip"], case_insensitive = true, require_equals = true)]
+    particle_validation: ParticleValidationMode,
+    /// If specified, particles closer than this distance to another particle are treated as duplicates by the particle validation, otherwise only exact duplicates are detected (in multiplies of the particle radius)
+    #[structopt(display_order = 2, long)]
+    duplicate_distance: Option<f64>,
+
+    /// Whether to enable the use of double precision for all computations (default: off)
+    #[structopt(display_order = 3, short = "-d", long, possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
+    double_precision: Option<Switch>,
+    /// Lower corner of the domain where surface reconstruction should be performed, format: `--domain-min=x,y,z` or `--domain-min x y z` (requires domain-max to be specified, particles outside of the domain are ignored)
     #[structopt(
         display_order = 3,
         long,
-        number_of_values = 3,
-        value_delimiter = ";",
+        min_values = 1,
+        max_values = 3,
         requires = "domain-max"
     )]
-    domain_min: Option<Vec<f64>>,
-    /// Upper corner of the domain where surface reconstruction should be performed, format:domain-max=x_max;y_max;z_max (requires domain-min to be specified)
+    domain_min: Option<Vec<String>>,
+    /// Upper corner of the domain where surface reconstruction should be performed, format: `--domain-max=x,y,z` or `--domain-max x y z` (requires domain-min to be specified)
     #[structopt(
         display_order = 3,
         long,
-        number_of_values = 3,
-        value_delimiter = ";",
+        min_values = 1,
+        max_values = 3,
         requires = "domain-min"
     )]
-    domain_max: Option<Vec<f64>>,
+    domain_max: Option<Vec<String>>,
+    /// Lower corner of the region of interest, the surface is only kept inside of this box and left open along its faces unless `--roi-cap` is set, same format as `--domain-min` (requires roi-max to be specified)
+    #[structopt(
+        display_order = 3,
+        long,
+        min_values = 1,
+        max_values = 3,
+        requires = "roi-max"
+    )]
+    roi_min: Option<Vec<String>>,
+    /// Upper corner of the region of interest, same format as `--domain-max` (requires roi-min to be specified)
+    #[structopt(
+        display_order = 3,
+        long,
+        min_values = 1,
+        max_values = 3,
+        requires = "roi-min"
+    )]
+    roi_max: Option<Vec<String>>,
+    /// Close the surface along the faces of the region of interest instead of leaving it open (requires roi-min and roi-max to be specified)
+    #[structopt(display_order = 3, long, requires = "roi-min")]
+    roi_cap: bool,
+    /// Clip the triangles of the surface exactly along the faces of the region of interest instead of removing all triangles that are not completely inside of it, attributes are interpolated to the new vertices (requires roi-min and roi-max to be specified, cannot be combined with roi-cap)
+    #[structopt(
+        display_order = 3,
+        long,
+        requires = "roi-min",
+        conflicts_with = "roi-cap"
+    )]
+    clip_aabb: bool,
+    /// Whether to use the same background grid for all input files, computed from the particles of the first input file (or the domain given by `--domain-min` and `--domain-max`), such that the grid does not move with the fluid (default: off)
+    #[structopt(display_order = 3, long, default_value = "off", possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
+    fixed_grid: Switch,
+    /// How to handle input files with particles outside of the fixed grid of `--fixed-grid=on`: abort the input file with an error or compute a separate grid for the input file
+    #[structopt(display_order = 3, long, default_value = "error", possible_values = &["error", "regrid"], case_insensitive = true, require_equals = true)]
+    grid_escape: GridEscapeMode,
+    /// Uniform scaling factor applied to the particles or the surface mesh relative to the coordinate origin (see `--transform-target`), e.g. `0.01` to convert from centimeters to meters
+    #[structopt(display_order = 3, long)]
+    scale: Option<f64>,
+    /// Translation applied to the particles or the surface mesh after the scaling (see `--transform-target`), same format as `--domain-min`
+    #[structopt(display_order = 3, long, min_values = 1, max_values = 3)]
+    translate: Option<Vec<String>>,
+    /// Whether `--scale` and `--translate` are applied to the output meshes or to the input particles before the reconstruction. In both cases, the particle radius, the domain and the region of interest are given in the coordinates of the input file and are transformed accordingly
+    #[structopt(display_order = 3, long, default_value = "mesh", possible_values = &["mesh", "particles"], case_insensitive = true, require_equals = true)]
+    transform_target: TransformTarget,
 
     /// Flag to enable multi-threading to process multiple input files in parallel
     #[structopt(display_order = 4, long = "mt-files", default_value = "off", possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
@@ -77,30 +236,45 @@ pub struct ReconstructSubcommandArgs {
     /// Flag to enable multi-threading for a single input file by processing chunks of particles in parallel
     #[structopt(display_order = 4, long = "mt-particles", default_value = "on", possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
     parallelize_over_particles: Switch,
-    /// Set the number of threads for the worker thread pool
-    #[structopt(display_order = 4, long, short = "-n")]
+    /// Set the number of threads for the worker thread pool of this invocation, shared by all input files (the global thread pool of the process is not modified)
+    #[structopt(display_order = 4, long, short = "-n", conflicts_with_all = &["frames-in-flight", "threads-per-frame"])]
     num_threads: Option<usize>,
+    /// Number of input files that are processed concurrently with `--mt-files=on` (default: one per logical core)
+    #[structopt(display_order = 4, long)]
+    frames_in_flight: Option<usize>,
+    /// Number of threads of a separate thread pool used for the reconstruction of every input file. Together with `--mt-files=on`, the frames in flight times the threads per frame may not exceed the number of logical cores (unless `--oversubscribe` is set)
+    #[structopt(display_order = 4, long)]
+    threads_per_frame: Option<usize>,
+    /// Allow more threads in total (frames in flight times threads per frame) than logical cores
+    #[structopt(display_order = 4, long)]
+    oversubscribe: bool,
+    /// Flag to enable the deterministic mode, i.e. bitwise identical meshes for repeated runs on the same input independent of the number of threads (slower)
+    #[structopt(display_order = 4, long, default_value = "off", possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
+    deterministic: Switch,
+    /// Interval in seconds for periodic progress messages of long-running stages of the reconstruction (e.g. the density map generation), disabled by default
+    #[structopt(display_order = 4, long)]
+    progress_interval: Option<f64>,
 
-    /// Whether to enable spatial decomposition using an octree (faster) instead of a global approach
-    #[structopt(display_order = 5, long, default_value = "on", possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
-    octree_decomposition: Switch,
-    /// Whether to enable stitching of the disconnected local meshes resulting from the reconstruction when spatial decomposition is enabled (slower, but without stitching meshes will not be closed)
-    #[structopt(display_order = 5, long, default_value = "on", possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
-    octree_stitch_subdomains: Switch,
+    /// Whether to enable spatial decomposition using an octree (faster) instead of a global approach (default: on)
+    #[structopt(display_order = 5, long, possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
+    octree_decomposition: Option<Switch>,
+    /// Whether to enable stitching of the disconnected local meshes resulting from the reconstruction when spatial decomposition is enabled (slower, but without stitching meshes will not be closed, default: on)
+    #[structopt(display_order = 5, long, possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
+    octree_stitch_subdomains: Option<Switch>,
     /// The maximum number of particles for leaf nodes of the octree, default is to compute it based on the number of threads and particles
     #[structopt(display_order = 5, long)]
     octree_max_particles: Option<usize>,
     /// Safety factor applied to the kernel compact support radius when it's used as a margin to collect ghost particles in the leaf nodes when performing the spatial decomposition
     #[structopt(display_order = 5, long)]
     octree_ghost_margin_factor: Option<f64>,
-    /// Whether to compute particle densities in a global step before domain decomposition (slower)
-    #[structopt(display_order = 5, long, default_value = "off", possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
-    octree_global_density: Switch,
-    /// Whether to compute particle densities per subdomain but synchronize densities for ghost-particles (faster, recommended).
+    /// Whether to compute particle densities in a global step before domain decomposition (slower, default: off)
+    #[structopt(display_order = 5, long, possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
+    octree_global_density: Option<Switch>,
+    /// Whether to compute particle densities per subdomain but synchronize densities for ghost-particles (faster, recommended, default: on).
     /// Note: if both this and global particle density computation is disabled the ghost particle margin has to be increased to at least 2.0
     /// to compute correct density values for ghost particles.
-    #[structopt(display_order = 5, long, default_value = "on", possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
-    octree_sync_local_density: Switch,
+    #[structopt(display_order = 5, long, possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
+    octree_sync_local_density: Option<Switch>,
 
     /// Optional filename for writing the point cloud representation of the intermediate density map to disk
     #[structopt(display_order = 6, long, parse(from_os_str))]
@@ -111,20 +285,177 @@ pub struct ReconstructSubcommandArgs {
     /// Optional filename for writing the octree used to partition the particles to disk
     #[structopt(display_order = 6, long, parse(from_os_str))]
     output_octree: Option<PathBuf>,
+    /// Optional directory of a cache for the density maps of reconstructions without octree decomposition (`--octree-decomposition=off`). Repeated reconstructions of the same particles with the same density parameters (particle radius, rest density, smoothing length, cube size, domain and splash detection) load the density map from the cache and skip directly to the triangulation.
+    #[structopt(display_order = 6, long, parse(from_os_str))]
+    cache_dir: Option<PathBuf>,
+    /// Maximum total size of the cache directory in GB, the least recently used density maps are removed if the size is exceeded
+    #[structopt(display_order = 6, long, default_value = "10.0")]
+    cache_max_gb: f64,
+    /// Disable the cache of density maps even if `--cache-dir` is specified
+    #[structopt(display_order = 6, long)]
+    no_cache: bool,
 
-    /// Whether to compute surface normals at the mesh vertices and write them to the output file
-    #[structopt(display_order = 7, long, default_value = "off", possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
-    normals: Switch,
-    /// Whether to compute the normals using SPH interpolation (smoother and more true to actual fluid surface, but slower) instead of just using area weighted triangle normals
+    /// Whether to compute surface normals at the mesh vertices and write them to the output file (default: off)
+    #[structopt(display_order = 7, long, possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
+    normals: Option<Switch>,
+    /// Whether to compute the normals using SPH interpolation (smoother and more true to actual fluid surface, but slower) instead of just using area weighted triangle normals (default: on)
+    #[structopt(display_order = 7, long, possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
+    sph_normals: Option<Switch>,
+    /// List of point attribute field names from the input file that should be transferred to the reconstructed surface, optionally with the method as suffix, e.g. `velocity:sph,id:nearest`. `sph` uses SPH interpolation, `nearest` assigns the value of the nearest particle (default for integer attributes like ids or phase labels). SPH interpolated values are zero at vertices without particles within the compact support radius. Currently this is only supported for VTK input files.
+    #[structopt(
+        display_order = 7,
+        long,
+        alias = "interpolate-attribute",
+        use_delimiter = true
+    )]
+    interpolate_attributes: Vec<AttributeSelection>,
+    /// Write the point attributes `mc_density` (value of the density map at which a vertex was extracted, close to the surface threshold) and `sph_density` (SPH interpolation of the density map at the vertex) to the output file, e.g. to check the consistency of the reconstruction
+    #[structopt(display_order = 7, long)]
+    output_density_attributes: bool,
+    /// Write the point attributes `mean_curvature` (cotangent Laplacian) and `gaussian_curvature` (angle defect) of the output surface mesh to the output file, e.g. for foam or spray shading. The curvatures are computed after all other post-processing steps and are zero at the boundary of the surface (e.g. along the faces of an open region of interest)
+    #[structopt(display_order = 7, long)]
+    output_curvature: bool,
+    /// Write the cell attributes `cell_id` (flat index of the background grid cell in which a triangle was generated) and `node_id` (id of the octree node that generated a triangle, only with octree decomposition) to the output file, e.g. to trace artifacts of the surface back to the reconstruction. Triangles without an originating cell (merged splash particle spheres) get the maximum value of an unsigned 64 bit integer
+    #[structopt(display_order = 7, long, conflicts_with_all = &["mesh-cleanup", "mesh-min-component-triangles", "mesh-decimate-target"])]
+    debug_cell_ids: bool,
+    /// Weld vertices with a distance of at most `--mesh-cleanup-weld-tolerance` and remove degenerate triangles (with repeated vertices or an area of at most `--mesh-cleanup-area-epsilon`) and the vertices only referenced by them from the surface mesh (cannot be combined with `--splash-output=merged`). The post-processing of the surface mesh is always applied in the order: cleanup, removal of small components, smoothing and decimation, before the normals and attributes are computed at its vertices
+    #[structopt(display_order = 7, long)]
+    mesh_cleanup: bool,
+    /// Distance up to which vertices are welded by `--mesh-cleanup`, in multiples of the cube size
+    #[structopt(display_order = 7, long, default_value = "1e-6")]
+    mesh_cleanup_weld_tolerance: f64,
+    /// Area below which triangles are removed by `--mesh-cleanup`, in multiples of the squared cube size
+    #[structopt(display_order = 7, long, default_value = "1e-6")]
+    mesh_cleanup_area_epsilon: f64,
+    /// Remove all connected components of the surface mesh with less than this number of triangles, e.g. small blobs formed by isolated particles (default: keep all components, cannot be combined with `--splash-output=merged`)
+    #[structopt(display_order = 7, long, visible_alias = "remove-components")]
+    mesh_min_component_triangles: Option<usize>,
+    /// Number of smoothing iterations applied to the surface mesh before the normals and attributes are computed at its vertices (default: no smoothing, cannot be combined with `--output-density-attributes`)
+    #[structopt(
+        display_order = 7,
+        long,
+        visible_alias = "smoothing-iters",
+        conflicts_with = "output-density-attributes"
+    )]
+    mesh_smoothing_iters: Option<usize>,
+    /// Method of the mesh smoothing: `laplacian` (uniform Laplacian smoothing, shrinks the surface) or `taubin` (alternating shrinking and inflating steps that approximately preserve the volume)
+    #[structopt(display_order = 7, long, visible_alias = "smoothing-method", default_value = "laplacian", possible_values = &SmoothingMethod::variants(), case_insensitive = true)]
+    mesh_smoothing_method: SmoothingMethod,
+    /// Fraction of the distance to the average position of its neighbors that every vertex is moved per smoothing iteration, has to be larger than zero and at most one
+    #[structopt(display_order = 7, long, default_value = "0.5")]
+    mesh_smoothing_weight: f64,
+    /// Whether to keep the vertices on boundary edges of the surface fixed during the smoothing (default: on)
     #[structopt(display_order = 7, long, default_value = "on", possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
-    sph_normals: Switch,
-    /// List of point attribute field names from the input file that should be interpolated to the reconstructed surface. Currently this is only supported for VTK input files.
-    #[structopt(display_order = 7, long, use_delimiter = true)]
-    interpolate_attributes: Vec<String>,
+    mesh_smoothing_keep_boundary: Switch,
+    /// Decimate the surface mesh by edge collapses until it has at most this number of triangles, as last step of the post-processing (default: no decimation, cannot be combined with `--output-density-attributes` or `--splash-output=merged`)
+    #[structopt(
+        display_order = 7,
+        long,
+        visible_alias = "decimate-target",
+        conflicts_with = "output-density-attributes"
+    )]
+    mesh_decimate_target: Option<usize>,
 
-    /// Whether to check the final mesh for topological problems such as holes (note that when stitching is disabled this will lead to a lot of reported problems)
+    /// Whether to check the final mesh for topological problems such as holes and print a summary of all problems (non-manifold edges, duplicate triangles, isolated vertices, etc.), the reconstruction fails if the mesh has holes (note that when stitching is disabled this will lead to a lot of reported problems)
     #[structopt(display_order = 100, long, default_value = "off", possible_values = &["on", "off"], case_insensitive = true, require_equals = true)]
     check_mesh: Switch,
+    /// Optional filename for writing the profiling data of every processed input file as JSON (cannot be combined with `--mt-files=on`)
+    #[structopt(display_order = 100, long, parse(from_os_str))]
+    profile_json: Option<PathBuf>,
+    /// Print statistics of every reconstruction: the timings of the stages and a summary of the timings of the octree leaves (the five slowest leaves are printed with `-vv`)
+    #[structopt(display_order = 100, long)]
+    stats: bool,
+    /// Exit with an error if any warnings (e.g. particles outside of the domain or clamped density values) were counted during the reconstructions
+    #[structopt(display_order = 100, long)]
+    strict_warnings: bool,
+    /// Optional filename for writing a machine-readable JSON summary of the run (paths, resolved parameters, statistics, warnings and timings of every input file), use `-` to write it to stdout (the log is then written to stderr)
+    #[structopt(display_order = 100, long, parse(from_os_str))]
+    summary_json: Option<PathBuf>,
+    /// Optional filename of a CSV file that is extended by one row per reconstructed input file with the particle, splash particle, vertex and triangle counts, the surface area and the enclosed volume of the output mesh. Input files that already have a row in an existing file are skipped, so interrupted runs can be resumed.
+    #[structopt(
+        display_order = 100,
+        long,
+        parse(from_os_str),
+        conflicts_with = "watch"
+    )]
+    measure_csv: Option<PathBuf>,
+    /// Only write the rows of `--measure-csv` and no surface meshes (existing output files are ignored)
+    #[structopt(display_order = 100, long, requires = "measure-csv")]
+    measure_only: bool,
+}
+
+impl ReconstructSubcommandArgs {
+    /// Returns the resource limits given by `--max-memory` and `--max-triangles`
+    fn budget(&self) -> Budget {
+        Budget {
+            max_memory_bytes: self
+                .max_memory
+                .map(|mebibytes| (mebibytes * 1024.0 * 1024.0) as u64),
+            max_triangles: self.max_triangles,
+        }
+    }
+
+    /// Returns whether the JSON summary should be written to stdout
+    pub(crate) fn summary_json_to_stdout(&self) -> bool {
+        self.summary_json
+            .as_ref()
+            .is_some_and(|path| path.as_os_str() == "-")
+    }
+
+    /// Returns whether the reconstructed surface should be written to stdout
+    fn output_to_stdout(&self) -> bool {
+        self.output_file.as_ref().is_some_and(io::is_stdio)
+    }
+
+    /// Returns whether the log has to be written to stderr to keep stdout free for the JSON summary or the surface mesh
+    pub(crate) fn log_to_stderr(&self) -> bool {
+        self.summary_json_to_stdout() || self.output_to_stdout()
+    }
+
+    /// Returns the reconstruction parameters that were explicitly given on the command line
+    fn cli_parameters(&self) -> ProfileParameters {
+        let switch = |switch: Option<Switch>| switch.map(Switch::into_bool);
+        ProfileParameters {
+            particle_radius: self.particle_radius,
+            rest_density: self.rest_density,
+            smoothing_length: self.smoothing_length,
+            cube_size: self.cube_size,
+            surface_threshold: self.surface_threshold,
+            double_precision: switch(self.double_precision),
+            octree_decomposition: switch(self.octree_decomposition),
+            octree_stitch_subdomains: switch(self.octree_stitch_subdomains),
+            octree_max_particles: self.octree_max_particles,
+            octree_ghost_margin_factor: self.octree_ghost_margin_factor,
+            octree_global_density: switch(self.octree_global_density),
+            octree_sync_local_density: switch(self.octree_sync_local_density),
+            normals: switch(self.normals),
+            sph_normals: switch(self.sph_normals),
+        }
+    }
+
+    /// Resolves the reconstruction parameters from the built-in defaults, the scene file, the selected profile and the command line
+    pub(crate) fn resolve_config(&self) -> Result<ResolvedConfig, anyhow::Error> {
+        let scene = match self.scene.as_deref() {
+            Some(path) => Some((path, scene::load_scene(path)?)),
+            None => None,
+        };
+        let profile = match self.profile.as_deref() {
+            Some(name) => Some((name, config::load_profile(self.config.as_deref(), name)?)),
+            None => None,
+        };
+        Ok(ResolvedConfig::resolve(
+            scene.as_ref().map(|(path, parameters)| (*path, parameters)),
+            profile
+                .as_ref()
+                .map(|(name, parameters)| (*name, parameters)),
+            &self.cli_parameters(),
+        ))
+    }
+
+    /// Returns the input file given either as positional argument or with `--input-file`
+    fn input_file(&self) -> Option<&PathBuf> {
+        self.input_file.as_ref().or(self.input.as_ref())
+    }
 }
 
 arg_enum! {
@@ -144,60 +475,552 @@ impl Switch {
     }
 }
 
+/// Particle attribute selected with `--interpolate-attributes` together with the method used to transfer it to the surface
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeSelection {
+    pub name: String,
+    /// Explicitly selected method, otherwise the method is chosen based on the type of the attribute
+    pub interpolation: Option<AttributeInterpolation>,
+}
+
+impl FromStr for AttributeSelection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, interpolation) = match s.rsplit_once(':') {
+            Some((name, method)) => {
+                let interpolation = match method.to_ascii_lowercase().as_str() {
+                    "sph" => AttributeInterpolation::Sph,
+                    "nearest" => AttributeInterpolation::Nearest,
+                    _ => {
+                        return Err(anyhow!(
+                            "Unknown interpolation method \"{}\" of attribute \"{}\", expected \"sph\" or \"nearest\"",
+                            method,
+                            name
+                        ))
+                    }
+                };
+                (name, Some(interpolation))
+            }
+            None => (s, None),
+        };
+
+        if name.is_empty() {
+            return Err(anyhow!("Missing attribute name in \"{}\"", s));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            interpolation,
+        })
+    }
+}
+
+arg_enum! {
+    #[derive(Copy, Clone, Debug)]
+    pub enum SplashOutputMode {
+        Ignore,
+        Separate,
+        Merged
+    }
+}
+
+arg_enum! {
+    #[derive(Copy, Clone, Debug)]
+    pub enum MarchingCubesMode {
+        Classic,
+        Mc33,
+        SurfaceNets
+    }
+}
+
+arg_enum! {
+    #[derive(Copy, Clone, Debug)]
+    pub enum ParticleValidationMode {
+        Error,
+        Filter,
+        Skip
+    }
+}
+
+arg_enum! {
+    #[derive(Copy, Clone, Debug)]
+    pub enum GridEscapeMode {
+        Error,
+        Regrid
+    }
+}
+
+arg_enum! {
+    #[derive(Copy, Clone, Debug)]
+    pub enum TransformTarget {
+        Mesh,
+        Particles
+    }
+}
+
 /// Executes the `reconstruct` subcommand
 pub fn reconstruct_subcommand(cmd_args: &ReconstructSubcommandArgs) -> Result<(), anyhow::Error> {
+    if let Some(watch_path) = &cmd_args.watch {
+        return watch::watch_subcommand(cmd_args, watch_path);
+    }
+
     let paths = ReconstructionRunnerPathCollection::try_from(cmd_args)
         .context("Failed parsing input file path(s) from command line")?
         .collect();
-    let args = ReconstructionRunnerArgs::try_from(cmd_args)
+
+    if cmd_args.suggest_cube_size {
+        return paths
+            .iter()
+            .try_for_each(|path| print_cube_size_suggestion(path, cmd_args));
+    }
+
+    let mut args = ReconstructionRunnerArgs::try_from(cmd_args)
         .context("Failed processing parameters from command line")?;
 
-    let result = if cmd_args.parallelize_over_files.into_bool() {
-        paths.par_iter().try_for_each(|path| {
-            reconstruction_pipeline(path, &args)
-                .with_context(|| {
-                    format!(
-                        "Error while processing input file \"{}\" from a file sequence",
+    if cmd_args.dry_run {
+        return dry_run(cmd_args, &paths, &args);
+    }
+
+    // The fixed grid of all input files is given by the domain, computed from the first input file if not specified
+    if args.grid_escape_policy.is_some() && args.params.domain_aabb.is_none() {
+        if let Some(first_paths) = paths.first() {
+            let domain_aabb = fixed_grid_domain(first_paths, &args)?;
+            info!(
+                "Using a fixed background grid for all input files with the domain {:?} computed from \"{}\".",
+                domain_aabb,
+                first_paths.input_file.display()
+            );
+            args.params.domain_aabb = Some(domain_aabb);
+        }
+    }
+
+    if cmd_args.output_to_stdout() {
+        if cmd_args.summary_json_to_stdout() {
+            return Err(anyhow!(
+                "The surface mesh and the JSON summary cannot both be written to stdout"
+            ));
+        }
+        if matches!(cmd_args.splash_output, SplashOutputMode::Separate) {
+            return Err(anyhow!("The splash particles cannot be written to a separate mesh file when the surface mesh is written to stdout, use --splash-output=merged instead"));
+        }
+    }
+
+    if cmd_args.profile_json.is_some() && cmd_args.parallelize_over_files.into_bool() {
+        return Err(anyhow!("The profiling data of input files that are processed in parallel cannot be separated, --profile-json cannot be combined with --mt-files=on"));
+    }
+
+    // Run all reconstructions on a local thread pool instead of initializing the global pool (which may already be initialized)
+    if let Some(thread_pool) = &args.thread_pool {
+        let thread_pool = thread_pool
+            .build_local()
+            .context("Failed to build the thread pool")?;
+        thread_pool.install(|| reconstruct_all(cmd_args, &paths, &args))
+    } else {
+        reconstruct_all(cmd_args, &paths, &args)
+    }
+}
+
+/// Computes the domain of the background grid of the given input file that is used for all input files with `--fixed-grid=on`
+fn fixed_grid_domain(
+    paths: &ReconstructionRunnerPaths,
+    args: &ReconstructionRunnerArgs,
+) -> Result<AxisAlignedBoundingBox3d<f64>, anyhow::Error> {
+    let mut particle_positions =
+        io::read_particle_positions::<f64, _>(&paths.input_file, &args.io_params.input)
+            .with_context(|| {
+                format!(
+                    "Failed to load particle positions from file \"{}\"",
+                    paths.input_file.display()
+                )
+            })?;
+    // Invalid particles are handled by the particle validation of every input file
+    particle_positions.retain(|particle| particle.iter().all(|c| c.is_finite()));
+    if let Some(transform) = CoordinateTransform::for_particles(args.transform.as_ref()) {
+        transform.apply(&mut particle_positions);
+    }
+
+    let params = &args.params;
+    let grid = splashsurf_lib::grid_for_reconstruction::<i64, f64>(
+        &particle_positions,
+        params.particle_radius,
+        params.compact_support_radius,
+        params.cube_size,
+        None,
+        params.enable_multi_threading,
+    )
+    .context("Failed to compute the fixed background grid")?;
+    Ok(grid.aabb().clone())
+}
+
+/// Runs the reconstruction pipeline for all input files and prints a summary of the warnings
+fn reconstruct_all(
+    cmd_args: &ReconstructSubcommandArgs,
+    paths: &[ReconstructionRunnerPaths],
+    args: &ReconstructionRunnerArgs,
+) -> Result<(), anyhow::Error> {
+    // Decide for every input file whether it has to be reconstructed depending on its existing output file
+    let output_policy = OutputPolicy::from(cmd_args);
+    let mut counts = FrameCounts::default();
+    let all_paths = paths;
+
+    // Input files that already have a row in the CSV file of the metrics are not processed again
+    let measure_csv = match &cmd_args.measure_csv {
+        Some(measure_csv) => Some(MeasureCsv::open(&match &cmd_args.output_dir {
+            Some(output_dir) => output_dir.join(measure_csv),
+            None => measure_csv.clone(),
+        })?),
+        None => None,
+    };
+
+    let paths: Vec<&ReconstructionRunnerPaths> = all_paths
+        .iter()
+        .filter(|path| {
+            if let Some(measure_csv) = &measure_csv {
+                if measure_csv.is_completed(&path.input_file) {
+                    info!(
+                        "Skipping \"{}\", the CSV file of the metrics already contains a row of the input file.",
                         path.input_file.display()
-                    )
-                })
-                .map_err(|err| {
-                    // Already log the error in case there are multiple errors
-                    log_error(&err);
-                    err
-                })
+                    );
+                    counts.skipped += 1;
+                    return false;
+                }
+            }
+
+            // No output files are written with `--measure-only`
+            let action = if cmd_args.measure_only {
+                FrameAction::Process
+            } else {
+                output_policy.frame_action_for_files(&path.input_file, &path.output_file)
+            };
+            match action {
+                FrameAction::Process => {}
+                FrameAction::Overwrite => info!(
+                    "The output file \"{}\" of \"{}\" exists and will be overwritten.",
+                    path.output_file.display(),
+                    path.input_file.display()
+                ),
+                FrameAction::SkipUpToDate => {
+                    info!(
+                        "Skipping \"{}\", the output file \"{}\" is newer than the input file.",
+                        path.input_file.display(),
+                        path.output_file.display()
+                    );
+                    counts.skipped += 1;
+                }
+                FrameAction::KeepExisting => {
+                    error!(
+                        "Not processing \"{}\", the output file \"{}\" already exists (use --overwrite to replace it or --skip-existing to skip up-to-date output files).",
+                        path.input_file.display(),
+                        path.output_file.display()
+                    );
+                    counts.failed += 1;
+                }
+            }
+            action.is_processed()
         })
-    } else {
-        paths
-            .iter()
-            .try_for_each(|path| reconstruction_pipeline(path, &args))
+        .collect();
+
+    // Reconstructs a single input file according to the error policy, returns `None` if the input file was not processed after an abort
+    let aborted = AtomicBool::new(false);
+    let process_frame = |path: &ReconstructionRunnerPaths| {
+        if aborted.load(Ordering::Relaxed) {
+            return None;
+        }
+        let result = error_policy::run_frame(cmd_args.on_error, path, || {
+            let frame = reconstruction_pipeline(path, args)?;
+            if let Some(measure_csv) = &measure_csv {
+                measure_csv.append(&frame)?;
+            }
+            Ok(frame)
+        });
+        if result.is_err() && cmd_args.on_error.aborts() {
+            aborted.store(true, Ordering::Relaxed);
+        }
+        Some(result)
     };
 
-    if result.is_ok() {
-        info!("Successfully finished processing all inputs.");
+    let results: Vec<Option<Result<FrameSummary, FrameError>>> =
+        if cmd_args.parallelize_over_files.into_bool() {
+            paths.par_iter().map(|path| process_frame(path)).collect()
+        } else {
+            let mut profiling_reports = Vec::new();
+            let results = paths
+                .iter()
+                .map(|path| {
+                    let result = process_frame(path);
+
+                    // Collect the profiling data of every file separately
+                    if cmd_args.profile_json.is_some() && result.is_some() {
+                        profiling_reports.push((path.input_file.clone(), profiling::report()));
+                        profiling::reset();
+                    }
+                    result
+                })
+                .collect();
+
+            if let Some(profile_json) = &cmd_args.profile_json {
+                write_profiling_json(profile_json, &profiling_reports)?;
+            }
+
+            results
+        };
+
+    let mut frames = Vec::with_capacity(results.len());
+    let mut failed_frames = Vec::new();
+    for result in results {
+        match result {
+            Some(Ok(frame)) => frames.push(frame),
+            Some(Err(frame_error)) => failed_frames.push(frame_error),
+            None => counts.aborted += 1,
+        }
+    }
+    counts.processed = frames.len();
+    counts.failed += failed_frames.len();
+    info!(
+        "Processed {} input files, skipped {}, failed {}.",
+        counts.processed, counts.skipped, counts.failed
+    );
+    if counts.aborted > 0 {
+        error!(
+            "Aborted after the first failed input file, {} input files were not processed (use --on-error=skip to continue with the remaining input files).",
+            counts.aborted
+        );
+    }
+    if !failed_frames.is_empty() {
+        error!("Failed input files:");
+        for frame_error in &failed_frames {
+            error!("  {}", frame_error);
+        }
+    }
+
+    if let Some(output_pvd) = &cmd_args.output_pvd {
+        let pvd_file = match &cmd_args.output_dir {
+            Some(output_dir) => output_dir.join(output_pvd),
+            None => output_pvd.clone(),
+        };
+        // Reference all output files of the sequence that exist, including outputs of skipped input files
+        let entries: Vec<_> = all_paths
+            .iter()
+            .filter(|path| path.output_file.is_file())
+            .map(|path| (path.time.unwrap_or(0.0), path.output_file.as_path()))
+            .collect();
+        time_series::write_pvd(&pvd_file, &entries)?;
+    }
+
+    let warnings = frames
+        .iter()
+        .fold(ReconstructionWarnings::default(), |warnings, frame| {
+            warnings + frame.warnings
+        });
+
+    if let Some(summary_json) = &cmd_args.summary_json {
+        let summary = RunSummary {
+            config: &args.config,
+            parameters: &args.params,
+            region_of_interest: args.region_of_interest.as_ref(),
+            frames: &frames,
+            failed_frames: &failed_frames,
+            counts,
+            warnings,
+        };
+        write_summary_json(summary_json, &summary.to_json())?;
+    }
+
+    if !warnings.is_empty() {
+        warn!("Warnings of all reconstructions: {}", warnings);
+        if cmd_args.strict_warnings {
+            return Err(anyhow!(
+                "Reconstruction finished with {} warnings and --strict-warnings was set",
+                warnings.total()
+            ));
+        }
+    }
+
+    if counts.failed + counts.aborted > 0 {
+        return Err(FramesFailed {
+            num_failed: counts.failed + counts.aborted,
+            num_frames: all_paths.len(),
+        }
+        .into());
     }
 
-    result
+    info!("Successfully finished processing all inputs.");
+    Ok(())
 }
 
 /// Conversion and validation of command line arguments
 mod arguments {
-    use super::ReconstructSubcommandArgs;
+    use super::cache::DensityMapCache;
+    use super::post_processing::{MeshCleanup, MeshPostProcessing, MeshSmoothing};
+    use super::time_series::TimeExtraction;
+    use super::{
+        AttributeSelection, GridEscapeMode, MarchingCubesMode, ParticleValidationMode,
+        ReconstructSubcommandArgs, SplashOutputMode, TransformTarget,
+    };
+    use crate::config::ResolvedConfig;
     use crate::io;
     use anyhow::{anyhow, Context};
-    use log::info;
+    use log::{info, warn};
     use splashsurf_lib::nalgebra::Vector3;
-    use splashsurf_lib::{AxisAlignedBoundingBox3d, ParticleDensityComputationStrategy};
+    use splashsurf_lib::{
+        AxisAlignedBoundingBox3d, ClippingPlane, GridEscapePolicy, MarchingCubesVariant,
+        ParticleDensityComputationStrategy, Real, SplashOutput, ThreadPoolConfig, ValidationPolicy,
+    };
     use std::convert::TryFrom;
     use std::fs;
     use std::path::{Path, PathBuf};
+    use std::time::Duration;
 
     /// All arguments that can be supplied to the surface reconstruction tool converted to useful types
     pub struct ReconstructionRunnerArgs {
         pub params: splashsurf_lib::Parameters<f64>,
+        pub particle_validation: ValidationPolicy,
+        pub duplicate_distance: f64,
         pub use_double_precision: bool,
         pub check_mesh: bool,
+        /// Whether the `mc_density` and `sph_density` attributes are added to the output mesh
+        pub density_attributes: bool,
+        /// Whether the `mean_curvature` and `gaussian_curvature` attributes are added to the output mesh
+        pub curvature_attributes: bool,
+        pub print_stats: bool,
+        /// Box that restricts the reconstructed surface
+        pub region_of_interest: Option<RegionOfInterest>,
+        /// Whether the timings of the stages should be recorded for the JSON summary
+        pub record_timings: bool,
+        /// Configuration of the local thread pool used for all input files
+        pub thread_pool: Option<ThreadPoolConfig>,
+        /// Configuration of the separate thread pool created for every input file
+        pub frame_thread_pool: Option<ThreadPoolConfig>,
+        /// Cache of the density maps of global reconstructions
+        pub cache: Option<DensityMapCache>,
+        /// Whether the surface and splash meshes are written to their output files (disabled by `--measure-only`)
+        pub write_meshes: bool,
+        /// Policy for particles outside of the fixed grid, `None` if every input file uses its own grid
+        pub grid_escape_policy: Option<GridEscapePolicy>,
+        /// Whether the originating grid cells and octree nodes of the triangles are written to the output files, see `--debug-cell-ids`
+        pub debug_cell_ids: bool,
+        /// Cleanup, component removal, smoothing and decimation of the surface meshes
+        pub mesh_post_processing: MeshPostProcessing,
+        /// Scaling and translation of the particles or meshes, see `--scale` and `--translate`
+        pub transform: Option<CoordinateTransform>,
         pub io_params: io::FormatParameters,
+        /// Parameters resolved from the defaults, the selected profile and the command line
+        pub config: ResolvedConfig,
+    }
+
+    /// Uniform scaling followed by a translation, see `--scale` and `--translate`
+    #[derive(Clone, Debug)]
+    pub struct CoordinateTransform {
+        pub scaling: f64,
+        pub translation: Vector3<f64>,
+        /// Whether the input particles are transformed before the reconstruction, otherwise the output meshes
+        pub particles: bool,
+    }
+
+    impl CoordinateTransform {
+        /// Returns the transformation if it has to be applied to the input particles
+        pub fn for_particles(transform: Option<&Self>) -> Option<&Self> {
+            transform.filter(|transform| transform.particles)
+        }
+
+        /// Returns the transformation if it has to be applied to the output meshes
+        pub fn for_meshes(transform: Option<&Self>) -> Option<&Self> {
+            transform.filter(|transform| !transform.particles)
+        }
+
+        /// Scales and translates all points
+        pub fn apply<R: Real>(&self, points: &mut [Vector3<R>]) {
+            let scaling = R::from_f64(self.scaling).unwrap();
+            let translation = self.translation.map(|c| R::from_f64(c).unwrap());
+            splashsurf_lib::transform::par_scale_points(points, scaling);
+            splashsurf_lib::transform::par_translate_points(points, &translation);
+        }
+
+        /// Returns the transformed box
+        fn apply_to_aabb(
+            &self,
+            aabb: &AxisAlignedBoundingBox3d<f64>,
+        ) -> AxisAlignedBoundingBox3d<f64> {
+            AxisAlignedBoundingBox3d::new(
+                aabb.min() * self.scaling + self.translation,
+                aabb.max() * self.scaling + self.translation,
+            )
+        }
+    }
+
+    /// Box that restricts the reconstructed surface, see `--roi-min` and `--roi-max`
+    #[derive(Clone, Debug)]
+    pub struct RegionOfInterest {
+        pub aabb: AxisAlignedBoundingBox3d<f64>,
+        /// Whether the surface is closed along the faces of the box, otherwise it is left open
+        pub cap: bool,
+        /// Whether the triangles of an open surface are clipped along the faces of the box, otherwise all triangles that are not completely inside are removed
+        pub clip: bool,
+    }
+
+    impl RegionOfInterest {
+        /// Returns the clipping planes of the six faces of the box with normals pointing inwards
+        fn clipping_planes(&self) -> Vec<ClippingPlane<f64>> {
+            let mut planes = Vec::with_capacity(6);
+            for axis in 0..3 {
+                let normal = Vector3::ith(axis, 1.0);
+                planes.push(ClippingPlane::new(*self.aabb.min(), normal));
+                planes.push(ClippingPlane::new(*self.aabb.max(), -normal));
+            }
+            planes
+        }
+    }
+
+    /// Parses a point from the values of the command line argument with the given name
+    ///
+    /// The three coordinates may be separated by commas, semicolons or whitespace and may be spread over multiple values.
+    pub(crate) fn parse_point(
+        name: &str,
+        values: &[String],
+    ) -> Result<Vector3<f64>, anyhow::Error> {
+        let coordinates = values
+            .iter()
+            .flat_map(|value| value.split(|c: char| c == ',' || c == ';' || c.is_whitespace()))
+            .filter(|coordinate| !coordinate.is_empty())
+            .map(|coordinate| {
+                coordinate
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|coordinate| coordinate.is_finite())
+                    .ok_or_else(|| anyhow!("Invalid coordinate \"{}\" of --{}", coordinate, name))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if coordinates.len() != 3 {
+            return Err(anyhow!(
+                "Expected three coordinates for --{} but got {} (format: x,y,z or \"x y z\")",
+                name,
+                coordinates.len()
+            ));
+        }
+
+        Ok(Vector3::from_vec(coordinates))
+    }
+
+    /// Parses the corners of a box from the `--<name>-min` and `--<name>-max` arguments, the min corner has to be strictly smaller than the max corner in every dimension
+    pub(crate) fn parse_aabb(
+        name: &str,
+        min: &[String],
+        max: &[String],
+    ) -> Result<AxisAlignedBoundingBox3d<f64>, anyhow::Error> {
+        let min = parse_point(&format!("{}-min", name), min)?;
+        let max = parse_point(&format!("{}-max", name), max)?;
+        if min.iter().zip(max.iter()).any(|(min, max)| min >= max) {
+            return Err(anyhow!(
+                "The user specified {} min/max values are inconsistent, min has to be smaller than max in every dimension! min: {:?} max: {:?}",
+                name,
+                min.as_slice(),
+                max.as_slice()
+            ));
+        }
+
+        Ok(AxisAlignedBoundingBox3d::new(min, max))
     }
 
     // Convert raw command line arguments to more useful types
@@ -205,58 +1028,57 @@ mod arguments {
         type Error = anyhow::Error;
 
         fn try_from(args: &ReconstructSubcommandArgs) -> Result<Self, Self::Error> {
-            // Convert domain args to aabb
+            let config = args.resolve_config()?;
+            let particle_radius = config.particle_radius()?;
+            let smoothing_length = config.smoothing_length()?;
+            let cube_size = config.cube_size.ok_or_else(|| {
+                anyhow!(
+                    "The cube size has to be specified with --cube-size or in the selected profile"
+                )
+            })?;
+
+            // Convert domain and region of interest args to aabbs
             let domain_aabb = match (&args.domain_min, &args.domain_max) {
                 (Some(domain_min), Some(domain_max)) => {
-                    // This should already be ensured by StructOpt parsing
-                    assert_eq!(domain_min.len(), 3);
-                    assert_eq!(domain_max.len(), 3);
-
-                    let aabb = AxisAlignedBoundingBox3d::new(
-                        Vector3::from_iterator(domain_min.clone()),
-                        Vector3::from_iterator(domain_max.clone()),
-                    );
-
-                    if !aabb.is_consistent() {
-                        return Err(anyhow!("The user specified domain min/max values are inconsistent! min: {:?} max: {:?}", aabb.min().as_slice(), aabb.max().as_slice()));
-                    }
-
-                    if aabb.is_degenerate() {
-                        return Err(anyhow!(
-                            "The user specified domain is degenerate! min: {:?} max: {:?}",
-                            aabb.min().as_slice(),
-                            aabb.max().as_slice()
-                        ));
-                    }
-
-                    Some(aabb)
+                    Some(parse_aabb("domain", domain_min, domain_max)?)
                 }
                 _ => None,
             };
+            let region_of_interest = match (&args.roi_min, &args.roi_max) {
+                (Some(roi_min), Some(roi_max)) => Some(RegionOfInterest {
+                    aabb: parse_aabb("roi", roi_min, roi_max)?,
+                    cap: args.roi_cap,
+                    clip: args.clip_aabb,
+                }),
+                _ => None,
+            };
 
-            // Scale kernel radius and cube size by particle radius
-            let compact_support_radius = args.particle_radius * 2.0 * args.smoothing_length;
-            let cube_size = args.particle_radius * args.cube_size;
+            // A capped region of interest is realized by clipping the density map along the faces of the box
+            let clipping_planes = match &region_of_interest {
+                Some(roi) if roi.cap => roi.clipping_planes(),
+                _ => Vec::new(),
+            };
 
-            let spatial_decomposition = if !args.octree_decomposition.into_bool() {
+            let spatial_decomposition = if !config.octree_decomposition {
                 None
             } else {
-                let subdivision_criterion = if let Some(max_particles) = args.octree_max_particles {
+                let subdivision_criterion = if let Some(max_particles) = config.octree_max_particles
+                {
                     splashsurf_lib::SubdivisionCriterion::MaxParticleCount(max_particles)
                 } else {
                     splashsurf_lib::SubdivisionCriterion::MaxParticleCountAuto
                 };
-                let ghost_particle_safety_factor = args.octree_ghost_margin_factor;
-                let enable_stitching = args.octree_stitch_subdomains.into_bool();
+                let ghost_particle_safety_factor = config.octree_ghost_margin_factor;
+                let enable_stitching = config.octree_stitch_subdomains;
 
-                let particle_density_computation = if args.octree_global_density.into_bool()
-                    && args.octree_sync_local_density.into_bool()
+                let particle_density_computation = if config.octree_global_density
+                    && config.octree_sync_local_density
                 {
                     return Err(anyhow!("Cannot enable both global and merged local particle density computation at the same time. Switch off at least one."));
                 } else {
-                    if args.octree_global_density.into_bool() {
+                    if config.octree_global_density {
                         ParticleDensityComputationStrategy::Global
-                    } else if args.octree_sync_local_density.into_bool() {
+                    } else if config.octree_sync_local_density {
                         ParticleDensityComputationStrategy::SynchronizeSubdomains
                     } else {
                         ParticleDensityComputationStrategy::IndependentSubdomains
@@ -271,28 +1093,265 @@ mod arguments {
                 })
             };
 
-            // Assemble all parameters for the surface reconstruction
-            let params = splashsurf_lib::Parameters {
-                particle_radius: args.particle_radius,
-                rest_density: args.rest_density,
-                compact_support_radius,
-                cube_size,
-                iso_surface_threshold: args.surface_threshold,
+            let splash_detection = match (
+                args.splash_detection_radius,
+                args.splash_detection_density_factor,
+            ) {
+                (Some(_), Some(_)) => {
+                    return Err(anyhow!("Cannot use both the splash detection radius and the splash detection density factor at the same time. Specify at most one of them."));
+                }
+                (Some(radius), None) => Some(splashsurf_lib::SplashDetection::NeighborCount(
+                    particle_radius * radius,
+                )),
+                (None, Some(factor)) => {
+                    Some(splashsurf_lib::SplashDetection::DensityThreshold(factor))
+                }
+                (None, None) => None,
+            };
+
+            let progress_interval = match args.progress_interval {
+                Some(seconds) if !(seconds.is_finite() && seconds >= 0.0) => {
+                    return Err(anyhow!(
+                        "The progress interval has to be a finite, non-negative number of seconds (got {})",
+                        seconds
+                    ));
+                }
+                seconds => seconds.map(Duration::from_secs_f64),
+            };
+
+            let splash_particle_output = match args.splash_output {
+                SplashOutputMode::Ignore => SplashOutput::Ignore,
+                SplashOutputMode::Separate => SplashOutput::SeparateMesh {
+                    sphere_subdivisions: args.splash_sphere_subdivisions,
+                },
+                SplashOutputMode::Merged => SplashOutput::MergedSpheres {
+                    sphere_subdivisions: args.splash_sphere_subdivisions,
+                },
+            };
+
+            // Assemble all parameters for the surface reconstruction, kernel radius and cube size are scaled by the particle radius
+            let relative_params = splashsurf_lib::RelativeParameters {
+                rest_density: config.rest_density,
+                compact_support_radius: None,
+                relative_compact_support_radius: Some(2.0 * smoothing_length),
+                cube_size: None,
+                relative_cube_size: Some(cube_size),
+                iso_surface_threshold: config.surface_threshold,
+                triangulation_scheme: match args.mc_variant {
+                    MarchingCubesMode::Classic => MarchingCubesVariant::Classic,
+                    MarchingCubesMode::Mc33 => MarchingCubesVariant::Mc33,
+                    MarchingCubesMode::SurfaceNets => MarchingCubesVariant::SurfaceNets,
+                },
+                vertex_snapping: args.mc_vertex_snapping,
+                splash_detection,
+                splash_particle_output,
                 domain_aabb,
+                clipping_planes,
                 enable_multi_threading: args.parallelize_over_particles.into_bool(),
+                deterministic: args.deterministic.into_bool(),
+                progress_interval,
+                temporal_density_smoothing: None,
                 spatial_decomposition,
             };
+            let mut params = relative_params.resolve(particle_radius)?;
+            let mut region_of_interest = region_of_interest;
+            let mut duplicate_distance = particle_radius * args.duplicate_distance.unwrap_or(0.0);
+
+            let transform = if args.scale.is_some() || args.translate.is_some() {
+                let scaling = args.scale.unwrap_or(1.0);
+                if !(scaling.is_finite() && scaling > 0.0) {
+                    return Err(anyhow!(
+                        "The scaling factor has to be a finite, positive number (got {})",
+                        scaling
+                    ));
+                }
+                let translation = match &args.translate {
+                    Some(translate) => parse_point("translate", translate)?,
+                    None => Vector3::zeros(),
+                };
+                Some(CoordinateTransform {
+                    scaling,
+                    translation,
+                    particles: matches!(args.transform_target, TransformTarget::Particles),
+                })
+            } else {
+                None
+            };
 
-            // Optionally initialize thread pool
-            if let Some(num_threads) = args.num_threads {
-                splashsurf_lib::initialize_thread_pool(num_threads)?;
+            // All lengths and positions of the parameters are given in the coordinates of the input files
+            if let Some(transform) = CoordinateTransform::for_particles(transform.as_ref()) {
+                params.scale_and_translate(transform.scaling, &transform.translation);
+                duplicate_distance *= transform.scaling;
+                if let Some(roi) = region_of_interest.as_mut() {
+                    roi.aabb = transform.apply_to_aabb(&roi.aabb);
+                }
             }
 
+            // Local thread pools of this invocation, the global thread pool is not initialized
+            if args.frames_in_flight == Some(0) || args.threads_per_frame == Some(0) {
+                return Err(anyhow!(
+                    "The number of frames in flight and threads per frame have to be positive"
+                ));
+            }
+            if args.frames_in_flight.is_some() && !args.parallelize_over_files.into_bool() {
+                return Err(anyhow!("--frames-in-flight requires --mt-files=on"));
+            }
+            if let Some(threads_per_frame) = args.threads_per_frame {
+                let logical_cores = std::thread::available_parallelism()
+                    .map(|n| n.get())
+                    .unwrap_or(1);
+                let frames_in_flight = if args.parallelize_over_files.into_bool() {
+                    args.frames_in_flight.unwrap_or(logical_cores)
+                } else {
+                    1
+                };
+                let total_threads = frames_in_flight * threads_per_frame;
+                if total_threads > logical_cores && !args.oversubscribe {
+                    return Err(anyhow!(
+                        "{} frames in flight with {} threads per frame require {} threads but only {} logical cores are available (reduce --frames-in-flight or --threads-per-frame, or set --oversubscribe)",
+                        frames_in_flight,
+                        threads_per_frame,
+                        total_threads,
+                        logical_cores
+                    ));
+                }
+            }
+
+            let thread_pool_config = |num_threads, thread_name_prefix: &str| ThreadPoolConfig {
+                num_threads,
+                thread_name_prefix: Some(thread_name_prefix.to_string()),
+                ..Default::default()
+            };
+            let thread_pool = args
+                .num_threads
+                .or(args.frames_in_flight)
+                .map(|num_threads| thread_pool_config(num_threads, "splashsurf-worker"));
+            let frame_thread_pool = args
+                .threads_per_frame
+                .map(|num_threads| thread_pool_config(num_threads, "splashsurf-frame-worker"));
+
+            let particle_validation = match args.particle_validation {
+                ParticleValidationMode::Error => ValidationPolicy::Error,
+                ParticleValidationMode::Filter => ValidationPolicy::Filter,
+                ParticleValidationMode::Skip => ValidationPolicy::Skip,
+            };
+            let grid_escape_policy = match args.grid_escape {
+                _ if !args.fixed_grid.into_bool() => None,
+                GridEscapeMode::Error => Some(GridEscapePolicy::Error),
+                GridEscapeMode::Regrid => Some(GridEscapePolicy::Regrid),
+            };
+            if grid_escape_policy.is_some() && args.watch.is_some() {
+                return Err(anyhow!("--fixed-grid=on cannot be combined with --watch"));
+            }
+
+            if args.mesh_min_component_triangles.is_some()
+                && matches!(args.splash_output, SplashOutputMode::Merged)
+            {
+                return Err(anyhow!(
+                    "--mesh-min-component-triangles cannot be combined with --splash-output=merged"
+                ));
+            }
+
+            if args.mesh_cleanup && matches!(args.splash_output, SplashOutputMode::Merged) {
+                return Err(anyhow!(
+                    "--mesh-cleanup cannot be combined with --splash-output=merged"
+                ));
+            }
+            if !(args.mesh_cleanup_weld_tolerance.is_finite()
+                && args.mesh_cleanup_weld_tolerance >= 0.0)
+            {
+                return Err(anyhow!(
+                    "The weld tolerance of the mesh cleanup has to be a finite, non-negative number (got {})",
+                    args.mesh_cleanup_weld_tolerance
+                ));
+            }
+            if !(args.mesh_cleanup_area_epsilon.is_finite()
+                && args.mesh_cleanup_area_epsilon >= 0.0)
+            {
+                return Err(anyhow!(
+                    "The area epsilon of the mesh cleanup has to be a finite, non-negative number (got {})",
+                    args.mesh_cleanup_area_epsilon
+                ));
+            }
+            // The tolerances are relative to the size of the marching cubes triangles
+            let mesh_cleanup = args.mesh_cleanup.then(|| MeshCleanup {
+                weld_tolerance: args.mesh_cleanup_weld_tolerance * params.cube_size,
+                area_epsilon: args.mesh_cleanup_area_epsilon * params.cube_size * params.cube_size,
+            });
+
+            if args.mesh_decimate_target.is_some()
+                && matches!(args.splash_output, SplashOutputMode::Merged)
+            {
+                return Err(anyhow!(
+                    "--mesh-decimate-target cannot be combined with --splash-output=merged"
+                ));
+            }
+
+            if !(args.mesh_smoothing_weight > 0.0 && args.mesh_smoothing_weight <= 1.0) {
+                return Err(anyhow!(
+                    "The mesh smoothing weight has to be larger than zero and at most one (got {})",
+                    args.mesh_smoothing_weight
+                ));
+            }
+            let mesh_smoothing = args.mesh_smoothing_iters.map(|iterations| MeshSmoothing {
+                method: args.mesh_smoothing_method,
+                iterations,
+                weight: args.mesh_smoothing_weight,
+                keep_boundary: args.mesh_smoothing_keep_boundary.into_bool(),
+            });
+
+            if !(args.cache_max_gb.is_finite() && args.cache_max_gb >= 0.0) {
+                return Err(anyhow!(
+                    "The maximum size of the cache has to be a finite, non-negative number of GB (got {})",
+                    args.cache_max_gb
+                ));
+            }
+            let cache = match &args.cache_dir {
+                Some(_) if args.no_cache => None,
+                Some(_) if params.spatial_decomposition.is_some() => {
+                    warn!("The density map cache is only supported for reconstructions without octree decomposition, ignoring --cache-dir (use --octree-decomposition=off).");
+                    None
+                }
+                Some(cache_dir) => Some(DensityMapCache::new(
+                    cache_dir.clone(),
+                    (args.cache_max_gb * 1e9) as u64,
+                )),
+                None => None,
+            };
+
             Ok(ReconstructionRunnerArgs {
                 params,
-                use_double_precision: args.double_precision.into_bool(),
+                particle_validation,
+                duplicate_distance,
+                use_double_precision: config.double_precision,
                 check_mesh: args.check_mesh.into_bool(),
-                io_params: io::FormatParameters::default(),
+                density_attributes: args.output_density_attributes,
+                curvature_attributes: args.output_curvature,
+                debug_cell_ids: args.debug_cell_ids,
+                print_stats: args.stats,
+                region_of_interest,
+                record_timings: args.summary_json.is_some(),
+                thread_pool,
+                frame_thread_pool,
+                cache,
+                write_meshes: !args.measure_only,
+                grid_escape_policy,
+                mesh_post_processing: MeshPostProcessing {
+                    cleanup: mesh_cleanup,
+                    min_component_triangles: args.mesh_min_component_triangles,
+                    smoothing: mesh_smoothing,
+                    decimate_target: args.mesh_decimate_target,
+                },
+                transform,
+                io_params: io::FormatParameters {
+                    input: io::InputFormatParameters {
+                        format: args.input_format.clone(),
+                    },
+                    output: io::OutputFormatParameters {
+                        format: args.output_format.clone(),
+                    },
+                },
+                config,
             })
         }
     }
@@ -310,34 +1369,42 @@ mod arguments {
         /// Whether to use SPH interpolation to compute the normals for all files
         sph_normals: bool,
         /// Additional attributes to load and interpolate to surface
-        attributes: Vec<String>,
+        attributes: Vec<AttributeSelection>,
+        /// Determines the physical time of the frames of a sequence
+        time_extraction: TimeExtraction,
     }
 
     impl ReconstructionRunnerPathCollection {
-        fn try_new<P: Into<PathBuf>>(
+        /// Collects the paths of the input file or sequence and the corresponding output file (or pattern), the paths of
+        /// all other outputs and the attributes of the output meshes are taken from the command line arguments
+        fn try_new(
             is_sequence: bool,
-            input_file: P,
-            output_base_path: Option<P>,
-            output_file: P,
-            output_density_map_points_file: Option<P>,
-            output_density_map_grid_file: Option<P>,
-            output_octree_file: Option<P>,
-            compute_normals: bool,
-            sph_normals: bool,
-            attributes: Vec<String>,
+            input_file: PathBuf,
+            output_file: PathBuf,
+            args: &ReconstructSubcommandArgs,
+            config: &ResolvedConfig,
         ) -> Result<Self, anyhow::Error> {
-            let input_file = input_file.into();
-            let output_base_path = output_base_path.map(|p| p.into());
-            let output_file = output_file.into();
-            let output_density_map_points_file = output_density_map_points_file.map(|p| p.into());
-            let output_density_map_grid_file = output_density_map_grid_file.map(|p| p.into());
-            let output_octree_file = output_octree_file.map(|p| p.into());
+            let output_base_path = args.output_dir.clone();
+            let output_density_map_points_file = args.output_dm_points.clone();
+            let output_density_map_grid_file = args.output_dm_grid.clone();
+            let output_octree_file = args.output_octree.clone();
+            let compute_normals = config.normals;
+            let sph_normals = config.sph_normals;
+            let attributes = args.interpolate_attributes.clone();
+            let time_extraction = TimeExtraction::new(args.time_regex.as_deref(), args.time_step)?;
 
             if let Some(output_base_path) = output_base_path {
-                let output_file = output_base_path.join(output_file);
+                // The output directory does not apply to stdout
+                let output_file = if io::is_stdio(&output_file) {
+                    output_file
+                } else {
+                    output_base_path.join(output_file)
+                };
 
                 // Ensure that output directory exists/create it
-                if let Some(output_dir) = output_file.parent() {
+                if let Some(output_dir) =
+                    output_file.parent().filter(|_| !io::is_stdio(&output_file))
+                {
                     if !output_dir.exists() {
                         info!("The output directory \"{}\" of the output file \"{}\" does not exist. Trying to create it now...", output_dir.display(), output_file.display());
                         fs::create_dir_all(output_dir).with_context(|| {
@@ -361,6 +1428,7 @@ mod arguments {
                     compute_normals,
                     sph_normals,
                     attributes,
+                    time_extraction,
                 })
             } else {
                 Ok(Self {
@@ -373,6 +1441,7 @@ mod arguments {
                     compute_normals,
                     sph_normals,
                     attributes,
+                    time_extraction,
                 })
             }
         }
@@ -399,13 +1468,10 @@ mod arguments {
                         let output_filename_i = output_filename.replace("{}", &i.to_string());
                         let output_file_i = output_dir.join(output_filename_i);
 
+                        // Don't write density maps etc. when processing a sequence of files
                         paths.push(ReconstructionRunnerPaths::new(
                             input_file_i,
                             output_file_i,
-                            // Don't write density maps etc. when processing a sequence of files
-                            None,
-                            None,
-                            None,
                             self.compute_normals,
                             self.sph_normals,
                             self.attributes.clone(),
@@ -417,21 +1483,31 @@ mod arguments {
                     i += 1;
                 }
 
+                // Frame indices start at 1
+                let frames: Vec<_> = paths
+                    .iter()
+                    .enumerate()
+                    .map(|(i, path)| (i + 1, path.input_file.as_path()))
+                    .collect();
+                let times = self.time_extraction.frame_times(&frames);
+                for (path, time) in paths.iter_mut().zip(times) {
+                    path.time = Some(time);
+                }
+
                 paths
             } else {
-                vec![
-                    ReconstructionRunnerPaths::new(
+                vec![ReconstructionRunnerPaths {
+                    output_density_map_points_file: self.output_density_map_points_file.clone(),
+                    output_density_map_grid_file: self.output_density_map_grid_file.clone(),
+                    output_octree_file: self.output_octree_file.clone(),
+                    ..ReconstructionRunnerPaths::new(
                         self.input_file.clone(),
                         self.output_file.clone(),
-                        self.output_density_map_points_file.clone(),
-                        self.output_density_map_grid_file.clone(),
-                        self.output_octree_file.clone(),
                         self.compute_normals,
                         self.sph_normals,
                         self.attributes.clone(),
-                    );
-                    1
-                ]
+                    )
+                }]
             }
         }
     }
@@ -442,30 +1518,24 @@ mod arguments {
 
         fn try_from(args: &ReconstructSubcommandArgs) -> Result<Self, Self::Error> {
             let output_suffix = "surface";
+            let config = args.resolve_config()?;
 
-            if let Some(input_file) = &args.input_file {
-                if input_file.is_file() {
+            if let Some(input_file) = args.input_file() {
+                if input_file.is_file() || io::is_stdio(input_file) {
                     // Use the user defined output file name if provided...
                     let output_file = if let Some(output_file) = &args.output_file {
                         output_file.clone()
+                    } else if io::is_stdio(input_file) {
+                        return Err(anyhow!(
+                            "An output file (or `-o -` for stdout) has to be specified when reading from stdin"
+                        ));
                     // ...otherwise, generate one based on the input filename
                     } else {
                         let input_stem = input_file.file_stem().unwrap().to_string_lossy();
                         format!("{}_{}.vtk", input_stem, output_suffix).into()
                     };
 
-                    Self::try_new(
-                        false,
-                        input_file.clone(),
-                        args.output_dir.clone(),
-                        output_file,
-                        args.output_dm_points.clone(),
-                        args.output_dm_grid.clone(),
-                        args.output_octree.clone(),
-                        args.normals.into_bool(),
-                        args.sph_normals.into_bool(),
-                        args.interpolate_attributes.clone(),
-                    )
+                    Self::try_new(false, input_file.clone(), output_file, args, &config)
                 } else {
                     return Err(anyhow!(
                         "Input file does not exist: \"{}\"",
@@ -507,14 +1577,9 @@ mod arguments {
                     Self::try_new(
                         true,
                         input_pattern.clone(),
-                        args.output_dir.clone(),
                         output_filename.into(),
-                        args.output_dm_points.clone(),
-                        args.output_dm_grid.clone(),
-                        args.output_octree.clone(),
-                        args.normals.into_bool(),
-                        args.sph_normals.into_bool(),
-                        args.interpolate_attributes.clone(),
+                        args,
+                        &config,
                     )
                 } else {
                     return Err(anyhow!(
@@ -542,76 +1607,437 @@ mod arguments {
         /// Whether to use SPH interpolation to compute the normals
         pub sph_normals: bool,
         /// Additional attributes to load and interpolate to surface
-        pub attributes: Vec<String>,
+        pub attributes: Vec<AttributeSelection>,
+        /// Physical time of the frame if the input file is part of a sequence
+        pub time: Option<f64>,
     }
 
     impl ReconstructionRunnerPaths {
-        fn new(
+        /// Returns the paths of an input file and its surface mesh, no density maps or octrees are written
+        pub(crate) fn new(
             input_file: PathBuf,
             output_file: PathBuf,
-            output_density_map_points_file: Option<PathBuf>,
-            output_density_map_grid_file: Option<PathBuf>,
-            output_octree_file: Option<PathBuf>,
             compute_normals: bool,
             sph_normals: bool,
-            attributes: Vec<String>,
+            attributes: Vec<AttributeSelection>,
         ) -> Self {
             ReconstructionRunnerPaths {
                 input_file,
                 output_file,
-                output_density_map_points_file,
-                output_density_map_grid_file,
-                output_octree_file,
+                output_density_map_points_file: None,
+                output_density_map_grid_file: None,
+                output_octree_file: None,
                 compute_normals,
                 sph_normals,
                 attributes,
+                time: None,
             }
         }
     }
 }
 
-/// Calls the reconstruction pipeline for single or double precision depending on the runtime parameters
+/// Surface reconstruction of the previous input file that is reused by the next input file, see `--watch`
+///
+/// Reusing a reconstruction keeps the allocations of its workspace and mesh (see
+/// [`splashsurf_lib::reconstruct_surface_inplace`]). The reconstruction is taken out at the start of every input file
+/// and only stored again if the input file was processed successfully, so no state of a failed or panicked input
+/// file is reused.
+#[derive(Default)]
+pub(crate) struct PersistentReconstruction {
+    single_precision: Option<SurfaceReconstructionDyn<f32>>,
+    double_precision: Option<SurfaceReconstructionDyn<f64>>,
+}
+
+/// Calls the reconstruction pipeline for single or double precision depending on the runtime parameters, returns a summary of the reconstruction
 pub(crate) fn reconstruction_pipeline(
     paths: &ReconstructionRunnerPaths,
     args: &ReconstructionRunnerArgs,
-) -> Result<(), anyhow::Error> {
+) -> Result<FrameSummary, anyhow::Error> {
+    reconstruction_pipeline_persistent(paths, args, None)
+}
+
+/// Calls the reconstruction pipeline like [`reconstruction_pipeline`], reusing and updating the given persistent reconstruction
+pub(crate) fn reconstruction_pipeline_persistent(
+    paths: &ReconstructionRunnerPaths,
+    args: &ReconstructionRunnerArgs,
+    persistent: Option<&mut PersistentReconstruction>,
+) -> Result<FrameSummary, anyhow::Error> {
+    // With `--threads-per-frame` every input file is processed on its own thread pool
+    if let Some(frame_thread_pool) = &args.frame_thread_pool {
+        let frame_thread_pool = frame_thread_pool
+            .build_local()
+            .context("Failed to build the thread pool of the input file")?;
+        return frame_thread_pool
+            .install(|| reconstruction_pipeline_precision(paths, args, persistent));
+    }
+
+    reconstruction_pipeline_precision(paths, args, persistent)
+}
+
+/// Calls the reconstruction pipeline for single or double precision depending on the runtime parameters
+fn reconstruction_pipeline_precision(
+    paths: &ReconstructionRunnerPaths,
+    args: &ReconstructionRunnerArgs,
+    persistent: Option<&mut PersistentReconstruction>,
+) -> Result<FrameSummary, anyhow::Error> {
+    // A capped region of interest is already part of the parameters, otherwise the mesh is cut after the reconstruction
+    let open_region_of_interest = args
+        .region_of_interest
+        .as_ref()
+        .filter(|roi| !roi.cap)
+        .map(|roi| &roi.aabb);
+
     if args.use_double_precision {
         info!("Using double precision (f64) for surface reconstruction.");
-        reconstruction_pipeline_generic::<i64, f64>(
+        reconstruction_pipeline_generic::<f64>(
             paths,
+            args,
             &args.params,
-            &args.io_params,
-            args.check_mesh,
-        )?;
+            args.duplicate_distance,
+            open_region_of_interest.cloned(),
+            persistent.map(|persistent| &mut persistent.double_precision),
+        )
     } else {
         info!("Using single precision (f32) for surface reconstruction.");
-        reconstruction_pipeline_generic::<i64, f32>(
+        reconstruction_pipeline_generic::<f32>(
             paths,
-            &args.params.try_convert().ok_or(anyhow!(
-                "Unable to convert surface reconstruction parameters from f64 to f32."
-            ))?,
-            &args.io_params,
-            args.check_mesh,
+            args,
+            &args
+                .params
+                .try_convert_checked()
+                .context("Unable to convert surface reconstruction parameters from f64 to f32.")?,
+            args.duplicate_distance as f32,
+            open_region_of_interest
+                .map(|aabb| aabb.try_convert_checked())
+                .transpose()
+                .context("Unable to convert the region of interest from f64 to f32.")?,
+            persistent.map(|persistent| &mut persistent.single_precision),
+        )
+    }
+}
+
+/// Loads the particles of the input file and prints estimates of the resource requirements for a range of cube sizes
+fn print_cube_size_suggestion(
+    paths: &ReconstructionRunnerPaths,
+    cmd_args: &ReconstructSubcommandArgs,
+) -> Result<(), anyhow::Error> {
+    let format_params = io::InputFormatParameters {
+        format: cmd_args.input_format.clone(),
+    };
+    let particle_positions: Vec<Vector3<f64>> =
+        io::read_particle_positions(&paths.input_file, &format_params).with_context(|| {
+            format!(
+                "Failed to load particle positions from file \"{}\"",
+                paths.input_file.display()
+            )
+        })?;
+
+    let config = cmd_args.resolve_config()?;
+    let particle_radius = config.particle_radius()?;
+    let budget = cmd_args.budget();
+    let suggestion = splashsurf_lib::suggest_parameters(
+        &particle_positions,
+        particle_radius,
+        particle_radius * 2.0 * config.smoothing_length()?,
+        &budget,
+    );
+
+    println!(
+        "Estimates for \"{}\" ({} particles):",
+        paths.input_file.display(),
+        particle_positions.len()
+    );
+    println!(
+        "{:>10} {:>14} {:>14} {:>14} {:>12}",
+        "cube size", "grid points", "dm points", "triangles", "memory MiB"
+    );
+    for (i, estimate) in suggestion.estimates.iter().enumerate() {
+        println!(
+            "{:>10.3} {:>14} {:>14} {:>14} {:>12.1}{}",
+            estimate.relative_cube_size,
+            estimate.grid_points,
+            estimate.density_map_points,
+            estimate.triangles,
+            estimate.memory_bytes as f64 / (1024.0 * 1024.0),
+            if suggestion.suggested == Some(i) {
+                "  <- suggested"
+            } else {
+                ""
+            }
+        );
+    }
+
+    if let Some(estimate) = suggestion.suggested_estimate() {
+        println!(
+            "Suggested cube size: --cube-size={:.3}",
+            estimate.relative_cube_size
+        );
+    } else if cmd_args.max_triangles.is_some() || cmd_args.max_memory.is_some() {
+        println!("None of the cube sizes meets the specified budget.");
+    } else {
+        println!("Specify --max-triangles and/or --max-memory to get a suggestion.");
+    }
+
+    Ok(())
+}
+
+/// Loads the first input file and reports the background grid and estimates of the resource requirements of its reconstruction without reconstructing
+///
+/// Fails if any output file cannot be written or if the estimates exceed the budget given by `--max-memory`/`--max-triangles`.
+fn dry_run(
+    cmd_args: &ReconstructSubcommandArgs,
+    paths: &[ReconstructionRunnerPaths],
+    args: &ReconstructionRunnerArgs,
+) -> Result<(), anyhow::Error> {
+    let first_paths = paths
+        .first()
+        .ok_or_else(|| anyhow!("No input files were found"))?;
+
+    // Every frame has to be written to its own output file
+    let mut output_files = HashSet::with_capacity(paths.len());
+    if let Some(duplicate) = paths
+        .iter()
+        .find(|frame| !output_files.insert(&frame.output_file))
+    {
+        return Err(anyhow!(
+            "The output file \"{}\" would be written by multiple input files, the output filename has to contain the frame number",
+            duplicate.output_file.display()
+        ));
+    }
+
+    // Check that all output directories are writable
+    let output_dirs: HashSet<_> = paths
+        .iter()
+        .filter(|frame| !io::is_stdio(&frame.output_file))
+        .map(|frame| frame.output_file.parent().unwrap_or_else(|| Path::new(".")))
+        .collect();
+    for output_dir in output_dirs {
+        check_directory_writable(output_dir)?;
+    }
+
+    let mut particle_positions: Vec<Vector3<f64>> =
+        io::read_particle_positions(&first_paths.input_file, &args.io_params.input).with_context(
+            || {
+                format!(
+                    "Failed to load particle positions from file \"{}\"",
+                    first_paths.input_file.display()
+                )
+            },
         )?;
+    if let Some(transform) = CoordinateTransform::for_particles(args.transform.as_ref()) {
+        transform.apply(&mut particle_positions);
+    }
+
+    let params = &args.params;
+    let index_type =
+        splashsurf_lib::reconstruction_auto::select_index_type(&particle_positions, params);
+    let grid = splashsurf_lib::grid_for_reconstruction::<i64, f64>(
+        &particle_positions,
+        params.particle_radius,
+        params.compact_support_radius,
+        params.cube_size,
+        params.domain_aabb.as_ref(),
+        params.enable_multi_threading,
+    )
+    .context("Failed to construct the background grid of the reconstruction")?;
+
+    let particle_aabb = AxisAlignedBoundingBox3d::from_points(&particle_positions);
+    let estimate = estimate_with_precision(
+        &particle_aabb,
+        particle_positions.len(),
+        params,
+        args.use_double_precision,
+    )?;
+    let budget = cmd_args.budget();
+    let fits_budget = budget.is_satisfied_by(&estimate);
+
+    let float_type = if args.use_double_precision {
+        "f64"
+    } else {
+        "f32"
+    };
+    let num_grid_points: u64 = grid.points_per_dim().iter().map(|&n| n as u64).product();
+
+    if !cmd_args.summary_json_to_stdout() {
+        println!(
+            "Dry run for {} input file(s), first input file \"{}\" ({} particles):",
+            paths.len(),
+            first_paths.input_file.display(),
+            particle_positions.len()
+        );
+        println!(
+            "  particle radius: {}, compact support radius: {}, cube size: {}, iso-surface threshold: {}",
+            params.particle_radius,
+            params.compact_support_radius,
+            params.cube_size,
+            params.iso_surface_threshold
+        );
+        println!(
+            "  float type: {}, index type: {}",
+            float_type,
+            index_type.name()
+        );
+        println!(
+            "  background grid: {:?} cells, {} points",
+            grid.cells_per_dim(),
+            num_grid_points
+        );
+        println!(
+            "  estimates: {} density map points, {} triangles, {} vertices, {:.1} MiB",
+            estimate.density_map_points,
+            estimate.triangles,
+            estimate.vertices,
+            estimate.memory_bytes as f64 / (1024.0 * 1024.0)
+        );
+    }
+
+    if let Some(summary_json) = &cmd_args.summary_json {
+        let json = serde_json::json!({
+            "schema_version": RunSummary::SCHEMA_VERSION,
+            "splashsurf_version": env!("CARGO_PKG_VERSION"),
+            "dry_run": true,
+            "config": args.config.to_json(),
+            "parameters": parameters_to_json(params, args.region_of_interest.as_ref()),
+            "num_frames": paths.len(),
+            "input_file": first_paths.input_file.display().to_string(),
+            "num_particles": particle_positions.len(),
+            "float_type": float_type,
+            "index_type": index_type.name(),
+            "grid": {
+                "cells_per_dim": grid.cells_per_dim(),
+                "points_per_dim": grid.points_per_dim(),
+                "num_points": num_grid_points,
+            },
+            "estimate": {
+                "density_map_points": estimate.density_map_points,
+                "triangles": estimate.triangles,
+                "vertices": estimate.vertices,
+                "memory_bytes": estimate.memory_bytes,
+            },
+            "budget": {
+                "max_memory_bytes": budget.max_memory_bytes,
+                "max_triangles": budget.max_triangles,
+                "satisfied": fits_budget,
+            },
+        });
+        write_summary_json(summary_json, &json)?;
+    }
+
+    if !fits_budget {
+        return Err(anyhow!(
+            "The estimated resource requirements ({} triangles, {:.1} MiB) exceed the limits given by --max-triangles/--max-memory",
+            estimate.triangles,
+            estimate.memory_bytes as f64 / (1024.0 * 1024.0)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Estimates the resource requirements of a reconstruction, the memory estimate depends on the float type used for the reconstruction
+fn estimate_with_precision(
+    particle_aabb: &AxisAlignedBoundingBox3d<f64>,
+    num_particles: usize,
+    params: &splashsurf_lib::Parameters<f64>,
+    use_double_precision: bool,
+) -> Result<ReconstructionEstimate<f64>, anyhow::Error> {
+    let estimate = estimate_reconstruction(
+        particle_aabb,
+        num_particles,
+        params.particle_radius,
+        params.compact_support_radius,
+        params.cube_size,
+    );
+
+    if use_double_precision {
+        Ok(estimate)
+    } else {
+        let estimate_f32 = estimate_reconstruction::<f32>(
+            &particle_aabb
+                .try_convert_checked()
+                .context("Unable to convert the bounding box of the particles to f32")?,
+            num_particles,
+            params.particle_radius as f32,
+            params.compact_support_radius as f32,
+            params.cube_size as f32,
+        );
+        Ok(ReconstructionEstimate {
+            memory_bytes: estimate_f32.memory_bytes,
+            ..estimate
+        })
     }
+}
+
+/// Checks that files can be created in the given directory by creating and removing a temporary file
+///
+/// Output directories that do not exist yet are created by the reconstruction, in this case the nearest existing
+/// ancestor of the directory is checked instead.
+fn check_directory_writable(dir: &Path) -> Result<(), anyhow::Error> {
+    let existing_dir = dir
+        .ancestors()
+        .map(|dir| {
+            if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            }
+        })
+        .find(|dir| dir.is_dir())
+        .unwrap_or(dir);
 
+    let test_file = existing_dir.join(format!(".splashsurf_dry_run_{}", std::process::id()));
+    fs::write(&test_file, b"").with_context(|| {
+        if existing_dir == dir {
+            format!("The output directory \"{}\" is not writable", dir.display())
+        } else {
+            format!(
+                "The output directory \"{}\" cannot be created, \"{}\" is not writable",
+                dir.display(),
+                existing_dir.display()
+            )
+        }
+    })?;
+    fs::remove_file(&test_file).with_context(|| {
+        format!(
+            "Failed to remove the temporary file \"{}\"",
+            test_file.display()
+        )
+    })?;
     Ok(())
 }
 
-/// Wrapper for the reconstruction pipeline: loads input file, runs reconstructions, stores output files
-pub(crate) fn reconstruction_pipeline_generic<I: Index, R: Real>(
+/// Wrapper for the reconstruction pipeline: loads input file, runs reconstructions, stores output files and returns a summary of the reconstruction
+///
+/// If a persistent reconstruction is given, it is reused by the reconstruction and replaced by the reconstruction of
+/// this input file if it was processed successfully.
+pub(crate) fn reconstruction_pipeline_generic<R: Real>(
     paths: &ReconstructionRunnerPaths,
+    args: &ReconstructionRunnerArgs,
     params: &splashsurf_lib::Parameters<R>,
-    io_params: &io::FormatParameters,
-    check_mesh: bool,
-) -> Result<(), anyhow::Error> {
+    duplicate_distance: R,
+    region_of_interest: Option<AxisAlignedBoundingBox3d<R>>,
+    mut persistent: Option<&mut Option<SurfaceReconstructionDyn<R>>>,
+) -> Result<FrameSummary, anyhow::Error> {
     profile!("surface reconstruction cli");
+    let start = Instant::now();
+
+    // Record the wall-clock timings of all stages if statistics, a summary or verbose output are requested
+    let timings_collector = (args.print_stats || args.record_timings || log_enabled!(Level::Debug))
+        .then(TimingsCollector::new);
+    let timings_guard = timings_collector
+        .as_ref()
+        .map(|collector| collector.attach());
 
     // Load particle positions and attributes to interpolate
-    let (particle_positions, attributes) = io::read_particle_positions_with_attributes(
+    let attribute_names: Vec<_> = paths
+        .attributes
+        .iter()
+        .map(|attribute| attribute.name.clone())
+        .collect();
+    let (mut particle_positions, mut attributes) = io::read_particle_positions_with_attributes(
         &paths.input_file,
-        &paths.attributes,
-        &io_params.input,
+        &attribute_names,
+        &args.io_params.input,
     )
     .with_context(|| {
         format!(
@@ -619,128 +2045,498 @@ pub(crate) fn reconstruction_pipeline_generic<I: Index, R: Real>(
             paths.input_file.display()
         )
     })?;
+    let num_input_particles = particle_positions.len();
+    if let Some(transform) = CoordinateTransform::for_particles(args.transform.as_ref()) {
+        transform.apply(&mut particle_positions);
+    }
+
+    // Check the particles for non-finite positions and duplicates
+    let validation = splashsurf_lib::particle_validation::validate_particles_with_tolerance(
+        particle_positions.as_slice(),
+        args.particle_validation,
+        duplicate_distance,
+    )
+    .with_context(|| {
+        format!(
+            "Invalid particle data in file \"{}\" (use --particle-validation=filter to remove invalid particles)",
+            paths.input_file.display()
+        )
+    })?;
+    if !validation.is_valid() {
+        warn!(
+            "Removing {} particles with non-finite positions (first indices: {:?}) and {} duplicate particles (first indices: {:?}).",
+            validation.num_non_finite,
+            validation.non_finite_indices,
+            validation.num_duplicates,
+            validation.duplicate_indices
+        );
+        let valid_particles = validation.valid_particles();
+        particle_positions = validation.filter(particle_positions.as_slice());
+        for attribute in attributes.iter_mut() {
+            attribute.data = attribute.data.select(valid_particles.as_slice());
+        }
+    }
 
-    // Perform the surface reconstruction
-    let reconstruction =
-        splashsurf_lib::reconstruct_surface::<I, R>(particle_positions.as_slice(), &params)?;
+    let params = match args.grid_escape_policy {
+        Some(policy) => fixed_grid_params(&particle_positions, params, policy, &paths.input_file)?,
+        None => Cow::Borrowed(params),
+    };
+    let params = params.as_ref();
 
-    let grid = reconstruction.grid();
-    let mesh = reconstruction.mesh();
+    // Perform the surface reconstruction with the smallest index type that is sufficient for the background grid
+    let keep_density_map = paths.output_density_map_points_file.is_some()
+        || paths.output_density_map_grid_file.is_some();
+    let mut reconstruction = reconstruct_surface_dyn(
+        particle_positions.as_slice(),
+        params,
+        args.cache.as_ref(),
+        keep_density_map,
+        args.debug_cell_ids,
+        persistent.as_mut().and_then(|persistent| persistent.take()),
+    )?;
+    info!(
+        "Using {} as index type of the background grid.",
+        reconstruction.index_type().name()
+    );
 
-    // Add normals to mesh if requested
-    let mesh = if paths.compute_normals || !attributes.is_empty() {
-        profile!("compute normals");
+    // The normals and attributes are computed at the vertices of the post-processed mesh
+    let post_processing = args
+        .mesh_post_processing
+        .apply(reconstruction.mesh_mut(), params.enable_multi_threading);
 
-        info!(
-            "Constructing global acceleration structure for SPH interpolation to {} vertices...",
-            mesh.vertices.len()
-        );
+    let result = match &reconstruction {
+        SurfaceReconstructionDyn::I32(reconstruction) => write_reconstruction_output(
+            paths,
+            args,
+            params,
+            reconstruction,
+            &particle_positions,
+            &attributes,
+            region_of_interest.as_ref(),
+        ),
+        SurfaceReconstructionDyn::I64(reconstruction) => write_reconstruction_output(
+            paths,
+            args,
+            params,
+            reconstruction,
+            &particle_positions,
+            &attributes,
+            region_of_interest.as_ref(),
+        ),
+    };
 
-        let particle_rest_density = params.rest_density;
-        let particle_rest_volume = R::from_f64((4.0 / 3.0) * std::f64::consts::PI).unwrap()
-            * params.particle_radius.powi(3);
-        let particle_rest_mass = particle_rest_volume * particle_rest_density;
+    drop(timings_guard);
+    let timings = timings_collector.map(|collector| collector.timings());
+    if let Some(timings) = &timings {
+        let level = if args.print_stats {
+            Level::Info
+        } else {
+            Level::Debug
+        };
+        log!(
+            level,
+            "Timings of \"{}\":\n{}",
+            paths.input_file.display(),
+            timings
+        );
+    }
 
-        let particle_densities = reconstruction
-            .particle_densities()
-            .ok_or_else(|| anyhow::anyhow!("Particle densities were not returned by surface reconstruction but are required for SPH normal computation"))?
-            .as_slice();
-        assert_eq!(
-            particle_positions.len(),
-            particle_densities.len(),
-            "There has to be one density value per particle"
+    if args.print_stats && !post_processing.is_empty() {
+        info!(
+            "Post-processing of the surface mesh of \"{}\":",
+            paths.input_file.display()
         );
+        for step in &post_processing {
+            info!(
+                "  {}: {} -> {} vertices, {} -> {} triangles",
+                step.name,
+                step.vertices_before,
+                step.vertices_after,
+                step.triangles_before,
+                step.triangles_after
+            );
+        }
+    }
 
-        let interpolator = SphInterpolator::new(
-            &particle_positions,
-            particle_densities,
-            particle_rest_mass,
-            params.compact_support_radius,
+    if let (true, Some(leaf_timings)) = (args.print_stats, &reconstruction.stats().leaf_timings) {
+        info!(
+            "Timings of the octree leaves of \"{}\": {}",
+            paths.input_file.display(),
+            leaf_timings
         );
+        for leaf in leaf_timings.slowest(5) {
+            trace!(
+                "  leaf {}: {:.2}ms ({} particles)",
+                leaf.node_id,
+                leaf.duration.as_secs_f64() * 1000.0,
+                leaf.num_particles
+            );
+        }
+    }
 
-        let mut mesh_with_data = MeshWithData::new(mesh.clone());
+    let summary = result.map(|metrics| FrameSummary {
+        input_file: paths.input_file.clone(),
+        output_file: paths.output_file.clone(),
+        time: paths.time,
+        num_particles: particle_positions.len(),
+        num_removed_particles: num_input_particles - particle_positions.len(),
+        num_splash_particles: reconstruction
+            .splash_particle_indices()
+            .map_or(0, |indices| indices.len()),
+        index_type: reconstruction.index_type().name(),
+        num_grid_points: reconstruction.num_grid_points(),
+        num_vertices: metrics.num_vertices,
+        num_triangles: metrics.num_triangles,
+        surface_area: metrics.surface_area,
+        volume: metrics.volume,
+        post_processing,
+        warnings: reconstruction.stats().warnings,
+        timings,
+        leaf_timings: reconstruction.stats().leaf_timings.clone(),
+        duration: start.elapsed(),
+    })?;
 
-        // Compute normals if requested
-        if paths.compute_normals {
-            let normals = if paths.sph_normals {
-                info!("Using SPH interpolation to compute surface normals");
+    if let Some(persistent) = persistent {
+        *persistent = Some(reconstruction);
+    }
+    Ok(summary)
+}
 
-                let sph_normals = interpolator.interpolate_normals(mesh.vertices());
-                bytemuck::allocation::cast_vec::<Unit<Vector3<R>>, Vector3<R>>(sph_normals)
-            } else {
-                info!("Using area weighted triangle normals for surface normals");
-                profile!("mesh.par_vertex_normals");
-                let tri_normals = mesh.par_vertex_normals();
+/// Returns the parameters for an input file with `--fixed-grid=on`, particles outside of the fixed grid either abort the input file or result in a separate grid for this input file
+fn fixed_grid_params<'a, R: Real>(
+    particle_positions: &[Vector3<R>],
+    params: &'a splashsurf_lib::Parameters<R>,
+    grid_escape_policy: GridEscapePolicy,
+    input_file: &Path,
+) -> Result<Cow<'a, splashsurf_lib::Parameters<R>>, anyhow::Error> {
+    if params.domain_aabb.is_none() {
+        return Ok(Cow::Borrowed(params));
+    }
 
-                // Convert unit vectors to plain vectors
-                bytemuck::allocation::cast_vec::<Unit<Vector3<R>>, Vector3<R>>(tri_normals)
-            };
+    let grid = splashsurf_lib::grid_for_reconstruction::<i64, R>(
+        particle_positions,
+        params.particle_radius,
+        params.compact_support_radius,
+        params.cube_size,
+        params.domain_aabb.as_ref(),
+        params.enable_multi_threading,
+    )?;
+    let particles_outside_grid = density_map::count_particles_outside_allowed_domain(
+        &grid,
+        particle_positions,
+        params.compact_support_radius,
+        params.cube_size,
+        params.enable_multi_threading,
+    );
+    if particles_outside_grid == 0 {
+        return Ok(Cow::Borrowed(params));
+    }
 
-            mesh_with_data.point_attributes.push(MeshAttribute::new(
-                "normals".to_string(),
-                AttributeData::Vector3Real(normals),
-            ));
+    match grid_escape_policy {
+        GridEscapePolicy::Error => Err(anyhow!(
+            "{} of {} particles of \"{}\" are outside of the fixed background grid (use --grid-escape=regrid to compute a separate grid for such input files)",
+            particles_outside_grid,
+            particle_positions.len(),
+            input_file.display()
+        )),
+        GridEscapePolicy::Regrid => {
+            warn!(
+                "{} of {} particles of \"{}\" are outside of the fixed background grid, computing a separate grid for this input file.",
+                particles_outside_grid,
+                particle_positions.len(),
+                input_file.display()
+            );
+            Ok(Cow::Owned(splashsurf_lib::Parameters {
+                domain_aabb: None,
+                ..params.clone()
+            }))
         }
+    }
+}
 
-        // Interpolate attributes if requested
-        if !attributes.is_empty() {
-            for attribute in attributes.into_iter() {
-                info!("Interpolating attribute \"{}\"...", attribute.name);
-
-                match attribute.data {
-                    AttributeData::ScalarReal(values) => {
-                        let interpolated_values = interpolator.interpolate_scalar_quantity(
-                            values.as_slice(),
-                            mesh.vertices(),
-                            true,
-                        );
-                        mesh_with_data.point_attributes.push(MeshAttribute::new(
-                            attribute.name,
-                            AttributeData::ScalarReal(interpolated_values),
-                        ));
-                    }
-                    AttributeData::Vector3Real(values) => {
-                        let interpolated_values = interpolator.interpolate_vector_quantity(
-                            values.as_slice(),
-                            mesh.vertices(),
-                            true,
-                        );
-                        mesh_with_data.point_attributes.push(MeshAttribute::new(
-                            attribute.name,
-                            AttributeData::Vector3Real(interpolated_values),
-                        ));
+/// Performs the reconstruction with the index type selected by `select_index_type`, using the density map cache if given and keeping the density map and the triangle origins if requested
+///
+/// A previous reconstruction is reused if it has the selected index type and no cache is used.
+fn reconstruct_surface_dyn<R: Real>(
+    particle_positions: &[Vector3<R>],
+    params: &splashsurf_lib::Parameters<R>,
+    cache: Option<&DensityMapCache>,
+    keep_density_map: bool,
+    record_triangle_origins: bool,
+    previous: Option<SurfaceReconstructionDyn<R>>,
+) -> Result<SurfaceReconstructionDyn<R>, anyhow::Error> {
+    if cache.is_none() && !keep_density_map && !record_triangle_origins && previous.is_none() {
+        return Ok(splashsurf_lib::reconstruct_surface_auto(
+            particle_positions,
+            params,
+        )?);
+    }
+
+    fn reconstruct<I: Index, R: Real>(
+        particle_positions: &[Vector3<R>],
+        params: &splashsurf_lib::Parameters<R>,
+        cache: Option<&DensityMapCache>,
+        keep_density_map: bool,
+        record_triangle_origins: bool,
+        previous: Option<SurfaceReconstruction<I, R>>,
+    ) -> Result<SurfaceReconstruction<I, R>, anyhow::Error> {
+        if let Some(cache) = cache {
+            return cache.reconstruct_surface(particle_positions, params, record_triangle_origins);
+        }
+        let mut reconstruction = previous.unwrap_or_default();
+        reconstruction.set_keep_density_map(keep_density_map);
+        reconstruction.set_record_triangle_origins(record_triangle_origins);
+        splashsurf_lib::reconstruct_surface_inplace(
+            particle_positions,
+            params,
+            &mut reconstruction,
+        )?;
+        Ok(reconstruction)
+    }
+
+    params.validate()?;
+    Ok(match select_index_type(particle_positions, params) {
+        IndexType::I32 => SurfaceReconstructionDyn::I32(reconstruct(
+            particle_positions,
+            params,
+            cache,
+            keep_density_map,
+            record_triangle_origins,
+            match previous {
+                Some(SurfaceReconstructionDyn::I32(previous)) => Some(previous),
+                _ => None,
+            },
+        )?),
+        IndexType::I64 => SurfaceReconstructionDyn::I64(reconstruct(
+            particle_positions,
+            params,
+            cache,
+            keep_density_map,
+            record_triangle_origins,
+            match previous {
+                Some(SurfaceReconstructionDyn::I64(previous)) => Some(previous),
+                _ => None,
+            },
+        )?),
+    })
+}
+
+/// Computes the mesh attributes and stores the reconstructed surface and all other requested outputs, returns the metrics of the output surface
+fn write_reconstruction_output<I: Index, R: Real>(
+    paths: &ReconstructionRunnerPaths,
+    args: &ReconstructionRunnerArgs,
+    params: &splashsurf_lib::Parameters<R>,
+    reconstruction: &SurfaceReconstruction<I, R>,
+    particle_positions: &[Vector3<R>],
+    attributes: &[MeshAttribute<R>],
+    region_of_interest: Option<&AxisAlignedBoundingBox3d<R>>,
+) -> Result<SurfaceMetrics, anyhow::Error> {
+    let grid = reconstruction.grid();
+    let clip_region_of_interest = args.region_of_interest.as_ref().is_some_and(|roi| roi.clip);
+    let mesh_transform = CoordinateTransform::for_meshes(args.transform.as_ref());
+
+    // Originating particles of all splash particle sphere vertices
+    let splash_vertex_particles = reconstruction
+        .splash_vertex_particle_indices()
+        .map(|v| v.as_slice())
+        .unwrap_or(&[]);
+
+    // Add normals and interpolated attributes to the mesh if requested
+    let mesh = {
+        profile!("compute mesh attributes");
+
+        // Integer attributes have to be converted to real values for an explicitly selected SPH interpolation
+        let (attributes, interpolation): (Vec<_>, Vec<_>) = attributes
+            .iter()
+            .map(|attribute| {
+                let interpolation = paths
+                    .attributes
+                    .iter()
+                    .find(|selection| selection.name == attribute.name)
+                    .and_then(|selection| selection.interpolation)
+                    .unwrap_or_else(|| AttributeInterpolation::default_for(&attribute.data));
+                let attribute = match (&attribute.data, interpolation) {
+                    (AttributeData::ScalarU64(values), AttributeInterpolation::Sph) => {
+                        MeshAttribute::new_real_scalar(
+                            attribute.name.clone(),
+                            values
+                                .iter()
+                                .map(|&value| R::from_u64(value).unwrap())
+                                .collect::<Vec<_>>(),
+                        )
                     }
-                    _ => unimplemented!("Interpolation of this attribute type not implemented"),
-                }
+                    _ => attribute.clone(),
+                };
+                (attribute, interpolation)
+            })
+            .unzip();
+
+        let attribute_specs = AttributeSpecs {
+            normals: match (paths.compute_normals, paths.sph_normals) {
+                (false, _) => None,
+                (true, true) => Some(NormalsComputation::SphInterpolation),
+                (true, false) => Some(NormalsComputation::AreaWeighted),
+            },
+            particle_attributes: &attributes,
+            particle_attribute_interpolation: &interpolation,
+            density_attributes: args.density_attributes,
+            ..AttributeSpecs::default()
+        };
+
+        let mut mesh =
+            reconstruction.mesh_with_attributes(particle_positions, params, &attribute_specs)?;
+
+        // The triangles of the mesh are still in the order in which their origins were recorded
+        if let (true, Some(origins)) = (args.debug_cell_ids, reconstruction.triangle_origins()) {
+            let cell_ids: Vec<u64> = origins
+                .cells
+                .iter()
+                .map(|&cell| {
+                    (cell != I::max_value())
+                        .then(|| cell.to_u64())
+                        .flatten()
+                        .unwrap_or(u64::MAX)
+                })
+                .collect();
+            mesh.cell_attributes
+                .push(MeshAttribute::new("cell_id", cell_ids));
+
+            if !origins.nodes.is_empty() {
+                let node_ids: Vec<u64> = origins.nodes.iter().map(|&node| node as u64).collect();
+                mesh.cell_attributes
+                    .push(MeshAttribute::new("node_id", node_ids));
             }
         }
 
-        mesh_with_data
+        mesh
+    };
+
+    // The consistency check has to be performed before the surface is opened by the region of interest
+    let mesh_check = args.check_mesh.then(|| {
+        (
+            splashsurf_lib::marching_cubes::check_mesh_consistency(grid, &mesh.mesh),
+            mesh.mesh.validity_report(),
+        )
+    });
+
+    // Only keep the part of the surface inside of the region of interest
+    let mesh = match region_of_interest {
+        Some(region_of_interest) if clip_region_of_interest => {
+            profile!("clip mesh to region of interest");
+            let mut mesh = mesh;
+            splashsurf_lib::mesh::clipping::clip_aabb_with_data(&mut mesh, region_of_interest);
+            mesh
+        }
+        Some(region_of_interest) => {
+            profile!("cut mesh to region of interest");
+            restrict_mesh_to_region(mesh, region_of_interest)
+        }
+        None => mesh,
+    };
+
+    // The region of interest is given in the coordinates of the particles, the normals are not affected by a uniform scaling
+    let mesh = match mesh_transform {
+        Some(mesh_transform) => {
+            let mut mesh = mesh;
+            mesh_transform.apply(&mut mesh.mesh.vertices);
+            mesh
+        }
+        None => mesh,
+    };
+
+    // The curvatures are computed on the final geometry of the output mesh
+    let mesh = if args.curvature_attributes {
+        profile!("compute mesh curvature");
+        let mut mesh = mesh;
+        let connectivity = Connectivity::par_from_mesh(&mesh.mesh);
+        let mean_curvature = curvature::par_mean_curvature(&mesh.mesh, &connectivity);
+        let gaussian_curvature = curvature::par_gaussian_curvature(&mesh.mesh, &connectivity);
+        mesh.point_attributes.push(MeshAttribute::new_real_scalar(
+            "mean_curvature",
+            mean_curvature,
+        ));
+        mesh.point_attributes.push(MeshAttribute::new_real_scalar(
+            "gaussian_curvature",
+            gaussian_curvature,
+        ));
+        mesh
     } else {
-        MeshWithData::new(mesh.clone())
+        mesh
     };
 
     // Store the surface mesh
-    {
+    if args.write_meshes {
         profile!("write surface mesh to file");
         info!(
             "Writing surface mesh to \"{}\"...",
             paths.output_file.display()
         );
 
-        io::write_mesh(&mesh, paths.output_file.clone(), &io_params.output).with_context(|| {
-            anyhow!(
-                "Failed to write output mesh to file \"{}\"",
-                paths.output_file.display()
-            )
-        })?;
+        io::write_mesh(&mesh, paths.output_file.clone(), &args.io_params.output).with_context(
+            || {
+                anyhow!(
+                    "Failed to write output mesh to file \"{}\"",
+                    paths.output_file.display()
+                )
+            },
+        )?;
         info!("Done.");
     }
 
-    // Store octree leaf nodes as hex cells
-    if let Some(output_octree_file) = &paths.output_octree_file {
-        info!("Writing octree to \"{}\"...", output_octree_file.display());
-        io::vtk_format::write_vtk(
+    // Store the splash particle spheres with the attributes of their originating particles
+    if let Some(splash_mesh) = reconstruction.splash_mesh().filter(|_| args.write_meshes) {
+        profile!("write splash mesh to file");
+
+        let mut splash_mesh_with_data = MeshWithData::new(splash_mesh.clone());
+        if paths.compute_normals {
+            splash_mesh_with_data
+                .point_attributes
+                .push(MeshAttribute::new_real_vector3(
+                    "normals",
+                    splash_sphere_normals(
+                        splash_mesh.vertices(),
+                        splash_vertex_particles,
+                        particle_positions,
+                    ),
+                ));
+        }
+        for attribute in attributes.iter() {
+            splash_mesh_with_data
+                .point_attributes
+                .push(MeshAttribute::new(
+                    attribute.name.clone(),
+                    attribute.data.select(splash_vertex_particles),
+                ));
+        }
+
+        if let Some(mesh_transform) = mesh_transform {
+            mesh_transform.apply(&mut splash_mesh_with_data.mesh.vertices);
+        }
+
+        let splash_output_file = splash_output_file(&paths.output_file);
+        info!(
+            "Writing splash particle spheres to \"{}\"...",
+            splash_output_file.display()
+        );
+
+        io::write_mesh(
+            &splash_mesh_with_data,
+            splash_output_file.clone(),
+            &args.io_params.output,
+        )
+        .with_context(|| {
+            anyhow!(
+                "Failed to write splash mesh to file \"{}\"",
+                splash_output_file.display()
+            )
+        })?;
+        info!("Done.");
+    }
+
+    // Store octree leaf nodes as hex cells
+    if let Some(output_octree_file) = &paths.output_octree_file {
+        info!("Writing octree to \"{}\"...", output_octree_file.display());
+        io::vtk_format::write_vtk(
             reconstruction
                 .octree()
                 .unwrap()
@@ -813,13 +2609,1547 @@ pub(crate) fn reconstruction_pipeline_generic<I: Index, R: Real>(
         info!("Done.");
     }
 
-    if check_mesh {
-        if let Err(err) = splashsurf_lib::marching_cubes::check_mesh_consistency(grid, &mesh.mesh) {
+    if let Some((mesh_check, validity_report)) = mesh_check {
+        if validity_report.is_valid() {
+            info!("Mesh validity report: {}.", validity_report);
+        } else {
+            warn!("Mesh validity report: {}.", validity_report);
+        }
+
+        if let Err(err) = mesh_check {
             return Err(anyhow!("{}", err));
         } else {
             info!("Checked mesh for problems (holes, etc.), no problems were found.");
         }
     }
 
+    Ok(SurfaceMetrics::of_mesh(&mesh.mesh))
+}
+
+/// Removes all triangles with vertices outside of the given region and all vertices that are no longer referenced, leaves the surface open along the boundary of the region
+fn restrict_mesh_to_region<R: Real>(
+    mesh: MeshWithData<R, TriMesh3d<R>>,
+    region: &AxisAlignedBoundingBox3d<R>,
+) -> MeshWithData<R, TriMesh3d<R>> {
+    let is_inside = |vertex: &Vector3<R>| {
+        (0..3).all(|i| vertex[i] >= region.min()[i] && vertex[i] <= region.max()[i])
+    };
+    let vertex_inside: Vec<bool> = mesh.mesh.vertices.iter().map(is_inside).collect();
+
+    let kept_triangles: Vec<usize> = mesh
+        .mesh
+        .triangles
+        .iter()
+        .enumerate()
+        .filter(|(_, triangle)| triangle.iter().all(|&v| vertex_inside[v]))
+        .map(|(i, _)| i)
+        .collect();
+
+    // Compact the vertices referenced by the remaining triangles while keeping their order
+    let mut is_referenced = vec![false; mesh.mesh.vertices.len()];
+    for &i in &kept_triangles {
+        for &v in &mesh.mesh.triangles[i] {
+            is_referenced[v] = true;
+        }
+    }
+    let kept_vertices: Vec<usize> = (0..mesh.mesh.vertices.len())
+        .filter(|&v| is_referenced[v])
+        .collect();
+    let mut new_vertex_indices = vec![usize::MAX; mesh.mesh.vertices.len()];
+    for (new_index, &v) in kept_vertices.iter().enumerate() {
+        new_vertex_indices[v] = new_index;
+    }
+
+    let select_attributes = |attributes: &[MeshAttribute<R>], indices: &[usize]| {
+        attributes
+            .iter()
+            .map(|attribute| {
+                MeshAttribute::new(attribute.name.clone(), attribute.data.select(indices))
+            })
+            .collect()
+    };
+
+    MeshWithData {
+        mesh: TriMesh3d {
+            vertices: kept_vertices
+                .iter()
+                .map(|&v| mesh.mesh.vertices[v])
+                .collect(),
+            triangles: kept_triangles
+                .iter()
+                .map(|&i| mesh.mesh.triangles[i].map(|v| new_vertex_indices[v]))
+                .collect(),
+        },
+        point_attributes: select_attributes(&mesh.point_attributes, &kept_vertices),
+        cell_attributes: select_attributes(&mesh.cell_attributes, &kept_triangles),
+    }
+}
+
+/// Statistics of the reconstruction of a single input file collected for the JSON summary, see `--summary-json`
+pub(crate) struct FrameSummary {
+    pub input_file: PathBuf,
+    pub output_file: PathBuf,
+    /// Physical time of the frame if it is part of a sequence, see `--time-regex`
+    pub time: Option<f64>,
+    /// Number of particles used for the reconstruction (after the particle validation)
+    pub num_particles: usize,
+    /// Number of particles removed by the particle validation
+    pub num_removed_particles: usize,
+    pub num_splash_particles: usize,
+    /// Name of the index type selected for the background grid
+    pub index_type: &'static str,
+    pub num_grid_points: u64,
+    pub num_vertices: usize,
+    pub num_triangles: usize,
+    /// Total area of the triangles of the output mesh
+    pub surface_area: f64,
+    /// Volume enclosed by the output mesh
+    pub volume: f64,
+    /// Vertex and triangle counts of the applied steps of the mesh post-processing
+    pub post_processing: Vec<PostProcessingStep>,
+    pub warnings: ReconstructionWarnings,
+    /// Timings of the reconstruction stages (only recorded if requested)
+    pub timings: Option<Timings>,
+    pub leaf_timings: Option<LeafTimings>,
+    /// Wall-clock time of the entire pipeline for this file including IO
+    pub duration: Duration,
+}
+
+/// Machine-readable summary of a run of the `reconstruct` subcommand, see `--summary-json`
+struct RunSummary<'a> {
+    /// The parameters resolved from the defaults, the selected profile and the command line
+    config: &'a ResolvedConfig,
+    /// The resolved (absolute) parameters used for all input files
+    parameters: &'a splashsurf_lib::Parameters<f64>,
+    region_of_interest: Option<&'a RegionOfInterest>,
+    frames: &'a [FrameSummary],
+    /// Errors of the input files that failed to be reconstructed
+    failed_frames: &'a [FrameError],
+    /// Number of processed, skipped and failed input files
+    counts: FrameCounts,
+    /// Warnings aggregated over all input files
+    warnings: ReconstructionWarnings,
+}
+
+impl RunSummary<'_> {
+    /// Version of the schema of the JSON summary, has to be incremented for every incompatible change
+    const SCHEMA_VERSION: u32 = 1;
+
+    /// Converts the summary to a JSON value
+    fn to_json(&self) -> serde_json::Value {
+        fn duration_ms(duration: Duration) -> f64 {
+            duration.as_secs_f64() * 1000.0
+        }
+
+        fn warnings_to_json(warnings: &ReconstructionWarnings) -> serde_json::Value {
+            serde_json::json!({
+                "particles_outside_domain": warnings.particles_outside_domain,
+                "clamped_density_values": warnings.clamped_density_values,
+                "degenerate_triangles": warnings.degenerate_triangles,
+                "truncated_ghost_margins": warnings.truncated_ghost_margins,
+                "total": warnings.total(),
+            })
+        }
+
+        fn stage_to_json(stage: &StageTiming) -> serde_json::Value {
+            serde_json::json!({
+                "name": stage.name,
+                "num_calls": stage.num_calls,
+                "duration_ms": duration_ms(stage.duration),
+                "sub_stages": stage.sub_stages.iter().map(stage_to_json).collect::<Vec<_>>(),
+            })
+        }
+
+        let parameters = parameters_to_json(self.parameters, self.region_of_interest);
+
+        let frames = self
+            .frames
+            .iter()
+            .map(|frame| {
+                serde_json::json!({
+                    "input_file": frame.input_file.display().to_string(),
+                    "output_file": frame.output_file.display().to_string(),
+                    "time": frame.time,
+                    "num_particles": frame.num_particles,
+                    "num_removed_particles": frame.num_removed_particles,
+                    "num_splash_particles": frame.num_splash_particles,
+                    "index_type": frame.index_type,
+                    "num_grid_points": frame.num_grid_points,
+                    "num_vertices": frame.num_vertices,
+                    "num_triangles": frame.num_triangles,
+                    "surface_area": frame.surface_area,
+                    "volume": frame.volume,
+                    "post_processing": frame.post_processing.iter().map(|step| {
+                        serde_json::json!({
+                            "step": step.name,
+                            "vertices_before": step.vertices_before,
+                            "triangles_before": step.triangles_before,
+                            "vertices_after": step.vertices_after,
+                            "triangles_after": step.triangles_after,
+                        })
+                    }).collect::<Vec<_>>(),
+                    "warnings": warnings_to_json(&frame.warnings),
+                    "duration_ms": duration_ms(frame.duration),
+                    "timings": frame.timings.as_ref().map(|timings| {
+                        serde_json::json!({
+                            "total_ms": duration_ms(timings.total),
+                            "stages": timings.stages.iter().map(stage_to_json).collect::<Vec<_>>(),
+                        })
+                    }),
+                    "leaf_timings": frame.leaf_timings.as_ref().map(|leaf_timings| {
+                        serde_json::json!({
+                            "num_leaves": leaf_timings.leaves.len(),
+                            "slowest_ms": leaf_timings.slowest(1).first().map(|leaf| duration_ms(leaf.duration)),
+                            "p95_ms": duration_ms(leaf_timings.percentile(0.95)),
+                            "mean_ms": duration_ms(leaf_timings.mean()),
+                            "imbalance_ratio": leaf_timings.imbalance_ratio(),
+                        })
+                    }),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::json!({
+            "schema_version": Self::SCHEMA_VERSION,
+            "splashsurf_version": env!("CARGO_PKG_VERSION"),
+            "config": self.config.to_json(),
+            "parameters": parameters,
+            "frames": frames,
+            "failed_frames": self.failed_frames.iter().map(FrameError::to_json).collect::<Vec<_>>(),
+            "frame_counts": {
+                "processed": self.counts.processed,
+                "skipped": self.counts.skipped,
+                "failed": self.counts.failed,
+                "aborted": self.counts.aborted,
+            },
+            "warnings": warnings_to_json(&self.warnings),
+        })
+    }
+}
+
+/// Converts the resolved parameters of the reconstruction to a JSON value for the run summary
+fn parameters_to_json(
+    params: &splashsurf_lib::Parameters<f64>,
+    region_of_interest: Option<&RegionOfInterest>,
+) -> serde_json::Value {
+    let (splash_detection_radius, splash_detection_density_factor) = match params.splash_detection {
+        Some(SplashDetection::NeighborCount(radius)) => (Some(radius), None),
+        Some(SplashDetection::DensityThreshold(factor)) => (None, Some(factor)),
+        None => (None, None),
+    };
+    let spatial_decomposition = params.spatial_decomposition.as_ref().map(|decomposition| {
+        serde_json::json!({
+            "max_particles_per_leaf": match decomposition.subdivision_criterion {
+                SubdivisionCriterion::MaxParticleCount(max_particles) => Some(max_particles),
+                SubdivisionCriterion::MaxParticleCountAuto => None,
+            },
+            "ghost_particle_safety_factor": decomposition.ghost_particle_safety_factor,
+            "enable_stitching": decomposition.enable_stitching,
+            "particle_density_computation": format!("{:?}", decomposition.particle_density_computation),
+        })
+    });
+    serde_json::json!({
+        "particle_radius": params.particle_radius,
+        "rest_density": params.rest_density,
+        "compact_support_radius": params.compact_support_radius,
+        "cube_size": params.cube_size,
+        "iso_surface_threshold": params.iso_surface_threshold,
+        "splash_detection_radius": splash_detection_radius,
+        "splash_detection_density_factor": splash_detection_density_factor,
+        "domain_aabb": params.domain_aabb.as_ref().map(|aabb| {
+            serde_json::json!({
+                "min": aabb.min().as_slice(),
+                "max": aabb.max().as_slice(),
+            })
+        }),
+        "region_of_interest": region_of_interest.map(|roi| {
+            serde_json::json!({
+                "min": roi.aabb.min().as_slice(),
+                "max": roi.aabb.max().as_slice(),
+                "cap": roi.cap,
+                "clip": roi.clip,
+            })
+        }),
+        "enable_multi_threading": params.enable_multi_threading,
+        "deterministic": params.deterministic,
+        "spatial_decomposition": spatial_decomposition,
+    })
+}
+
+/// Writes a JSON summary to the given file or to stdout if the path is `-`
+fn write_summary_json(
+    output_file: &Path,
+    summary: &serde_json::Value,
+) -> Result<(), anyhow::Error> {
+    let json = serde_json::to_string_pretty(summary)?;
+    if output_file.as_os_str() == "-" {
+        println!("{}", json);
+    } else {
+        info!("Writing run summary to \"{}\"...", output_file.display());
+        fs::write(output_file, json).with_context(|| {
+            format!(
+                "Failed to write run summary to file \"{}\"",
+                output_file.display()
+            )
+        })?;
+    }
+
     Ok(())
 }
+
+/// Writes the profiling reports of all processed input files to a JSON file
+fn write_profiling_json(
+    output_file: &Path,
+    reports: &[(PathBuf, ProfilingReport)],
+) -> Result<(), anyhow::Error> {
+    fn scope_to_json(scope: &ProfilingScope) -> serde_json::Value {
+        serde_json::json!({
+            "name": scope.name,
+            "num_calls": scope.num_calls,
+            "total_ms": scope.total_duration.as_secs_f64() * 1000.0,
+            "mean_ms": scope.mean_duration.as_secs_f64() * 1000.0,
+            "children": scope.children.iter().map(scope_to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    let frames = reports
+        .iter()
+        .map(|(input_file, report)| {
+            serde_json::json!({
+                "input_file": input_file.display().to_string(),
+                "scopes": report.roots.iter().map(scope_to_json).collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    info!("Writing profiling data to \"{}\"...", output_file.display());
+    let json = serde_json::to_string_pretty(&serde_json::json!({ "frames": frames }))?;
+    fs::write(output_file, json).with_context(|| {
+        format!(
+            "Failed to write profiling data to file \"{}\"",
+            output_file.display()
+        )
+    })?;
+    info!("Done.");
+
+    Ok(())
+}
+
+/// Computes the normals of the splash particle sphere vertices, i.e. the directions from their originating particles
+fn splash_sphere_normals<R: Real>(
+    sphere_vertices: &[Vector3<R>],
+    vertex_particles: &[usize],
+    particle_positions: &[Vector3<R>],
+) -> Vec<Vector3<R>> {
+    sphere_vertices
+        .iter()
+        .zip(vertex_particles.iter())
+        .map(|(vertex, &particle_idx)| (vertex - particle_positions[particle_idx]).normalize())
+        .collect()
+}
+
+/// Returns the path of the file for the splash particle spheres, i.e. the output file with a "_splash" suffix
+fn splash_output_file(output_file: &Path) -> PathBuf {
+    let output_stem = output_file.file_stem().unwrap().to_string_lossy();
+    match output_file.extension() {
+        Some(extension) => output_file.with_file_name(format!(
+            "{}_splash.{}",
+            output_stem,
+            extension.to_string_lossy()
+        )),
+        None => output_file.with_file_name(format!("{}_splash", output_stem)),
+    }
+}
+
+#[test]
+fn test_reconstruct_subcommand_with_different_thread_counts() {
+    let input_file = crate::io::test_cube_particles_file();
+    let output_dir = std::env::temp_dir().join("splashsurf_test_thread_counts");
+
+    // Both invocations in the same process have to use their own thread pool
+    for num_threads in ["1", "2"] {
+        let output_file = output_dir.join(format!("cube_surface_{}_threads.vtk", num_threads));
+        let cmd_args = ReconstructSubcommandArgs::from_iter_safe(&[
+            "reconstruct",
+            "-i",
+            input_file,
+            "-o",
+            output_file.to_str().unwrap(),
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+            "--overwrite",
+            "-n",
+            num_threads,
+        ])
+        .unwrap();
+
+        reconstruct_subcommand(&cmd_args).unwrap();
+        assert!(output_file.exists());
+    }
+}
+
+#[test]
+fn test_summary_json() {
+    let input_file = crate::io::test_cube_particles_file();
+    let output_dir = std::env::temp_dir().join("splashsurf_test_summary_json");
+    let output_file = output_dir.join("cube_surface.vtk");
+    let summary_file = output_dir.join("summary.json");
+
+    let cmd_args = ReconstructSubcommandArgs::from_iter_safe(&[
+        "reconstruct",
+        "-i",
+        input_file,
+        "-o",
+        output_file.to_str().unwrap(),
+        "--particle-radius=0.025",
+        "--smoothing-length=2.0",
+        "--cube-size=0.5",
+        "--overwrite",
+        "--summary-json",
+        summary_file.to_str().unwrap(),
+    ])
+    .unwrap();
+    reconstruct_subcommand(&cmd_args).unwrap();
+
+    let summary: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&summary_file).unwrap()).unwrap();
+    for key in [
+        "schema_version",
+        "splashsurf_version",
+        "parameters",
+        "frames",
+        "warnings",
+    ] {
+        assert!(summary.get(key).is_some(), "missing key \"{}\"", key);
+    }
+    assert_eq!(summary["schema_version"], RunSummary::SCHEMA_VERSION);
+
+    // The parameters are stored after the conversion of the relative parameters
+    let parameters = &summary["parameters"];
+    assert_eq!(parameters["particle_radius"].as_f64(), Some(0.025));
+    assert_eq!(parameters["rest_density"].as_f64(), Some(1000.0));
+    assert_eq!(
+        parameters["compact_support_radius"].as_f64(),
+        Some(4.0 * 0.025)
+    );
+    assert_eq!(parameters["cube_size"].as_f64(), Some(0.5 * 0.025));
+    assert_eq!(parameters["iso_surface_threshold"].as_f64(), Some(0.6));
+
+    let frames = summary["frames"].as_array().unwrap();
+    assert_eq!(frames.len(), 1);
+    let frame = &frames[0];
+    assert_eq!(frame["input_file"].as_str(), Some(input_file));
+    assert_eq!(frame["num_particles"].as_u64(), Some(2366));
+    assert!(frame["num_triangles"].as_u64().unwrap() > 0);
+    assert!(frame["timings"]["stages"].is_array());
+    assert_eq!(frame["warnings"]["total"].as_u64(), Some(0));
+    assert_eq!(summary["frame_counts"]["processed"].as_u64(), Some(1));
+}
+
+#[test]
+fn test_parse_domain_and_region_of_interest() {
+    let parse = |extra_args: &[&str]| {
+        let mut cmd_args = vec![
+            "reconstruct",
+            "-i",
+            "particles.vtk",
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+        ];
+        cmd_args.extend_from_slice(extra_args);
+        ReconstructionRunnerArgs::try_from(
+            &ReconstructSubcommandArgs::from_iter_safe(&cmd_args).unwrap(),
+        )
+    };
+
+    // Comma-, semicolon- and space-separated triples
+    let args = parse(&["--domain-min=-1,0,0.5", "--domain-max", "1", "2", "3"]).unwrap();
+    let domain = args.params.domain_aabb.unwrap();
+    assert_eq!(domain.min(), &Vector3::new(-1.0, 0.0, 0.5));
+    assert_eq!(domain.max(), &Vector3::new(1.0, 2.0, 3.0));
+    let args = parse(&["--domain-min=0;0;0", "--domain-max=1 1 1"]).unwrap();
+    assert_eq!(
+        args.params.domain_aabb.unwrap().max(),
+        &Vector3::repeat(1.0)
+    );
+
+    // The min corner has to be smaller than the max corner in every dimension
+    assert!(parse(&["--domain-min=0,0,0", "--domain-max=1,0,1"]).is_err());
+    assert!(parse(&["--domain-min=0,0", "--domain-max=1,1,1"]).is_err());
+    assert!(parse(&["--roi-min=0,0,0", "--roi-max=1,1,x"]).is_err());
+
+    // Only a capped region of interest is realized with clipping planes
+    let args = parse(&["--roi-min=0,0,0", "--roi-max=1,1,1"]).unwrap();
+    assert!(!args.region_of_interest.unwrap().cap);
+    assert!(args.params.clipping_planes.is_empty());
+    let args = parse(&["--roi-min=0,0,0", "--roi-max=1,1,1", "--roi-cap"]).unwrap();
+    assert!(args.region_of_interest.unwrap().cap);
+    assert_eq!(args.params.clipping_planes.len(), 6);
+    let args = parse(&["--roi-min=0,0,0", "--roi-max=1,1,1", "--clip-aabb"]).unwrap();
+    assert!(args.region_of_interest.unwrap().clip);
+    assert!(args.params.clipping_planes.is_empty());
+}
+
+#[test]
+fn test_parse_scale_and_translate() {
+    let parse = |extra_args: &[&str]| {
+        let mut cmd_args = vec![
+            "reconstruct",
+            "-i",
+            "particles.vtk",
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+            "--domain-min=0,0,0",
+            "--domain-max=1,1,1",
+            "--roi-min=0,0,0",
+            "--roi-max=0.5,0.5,0.5",
+        ];
+        cmd_args.extend_from_slice(extra_args);
+        ReconstructionRunnerArgs::try_from(
+            &ReconstructSubcommandArgs::from_iter_safe(&cmd_args).unwrap(),
+        )
+    };
+
+    let args = parse(&[]).unwrap();
+    assert!(args.transform.is_none());
+
+    // By default, the output meshes are transformed and the parameters are left unchanged
+    let args = parse(&["--scale=2", "--translate=1,0,-1"]).unwrap();
+    let transform = args.transform.unwrap();
+    assert!(!transform.particles);
+    assert_eq!(transform.scaling, 2.0);
+    assert_eq!(transform.translation, Vector3::new(1.0, 0.0, -1.0));
+    assert_eq!(args.params.particle_radius, 0.025);
+    assert_eq!(
+        args.params.domain_aabb.unwrap().max(),
+        &Vector3::repeat(1.0)
+    );
+
+    // The parameters are given in the coordinates of the input file and are transformed with the particles
+    let args = parse(&[
+        "--scale=2",
+        "--translate=1,0,-1",
+        "--transform-target=particles",
+    ])
+    .unwrap();
+    assert!(args.transform.unwrap().particles);
+    assert_eq!(args.params.particle_radius, 0.05);
+    assert_eq!(args.params.cube_size, 0.025);
+    let domain = args.params.domain_aabb.unwrap();
+    assert_eq!(domain.min(), &Vector3::new(1.0, 0.0, -1.0));
+    assert_eq!(domain.max(), &Vector3::new(3.0, 2.0, 1.0));
+    assert_eq!(
+        args.region_of_interest.unwrap().aabb.max(),
+        &Vector3::new(2.0, 1.0, 0.0)
+    );
+
+    // A translation without scaling
+    let args = parse(&["--translate", "1", "2", "3"]).unwrap();
+    assert_eq!(args.transform.unwrap().scaling, 1.0);
+
+    assert!(parse(&["--scale=0"]).is_err());
+    assert!(parse(&["--scale=-1"]).is_err());
+    assert!(parse(&["--translate=1,2"]).is_err());
+}
+
+#[test]
+fn test_reconstruct_with_profile() {
+    let dir = std::env::temp_dir().join("splashsurf_test_reconstruct_with_profile");
+    fs::create_dir_all(&dir).unwrap();
+    let config_file = dir.join("profiles.toml");
+    fs::write(
+        &config_file,
+        r#"
+[profiles.final]
+particle-radius = 0.025
+smoothing-length = 2.0
+cube-size = 0.5
+surface-threshold = 0.7
+octree-decomposition = false
+"#,
+    )
+    .unwrap();
+
+    let parse = |extra_args: &[&str]| {
+        let mut cmd_args = vec![
+            "reconstruct",
+            "-i",
+            "particles.vtk",
+            "--profile=final",
+            "--config",
+            config_file.to_str().unwrap(),
+        ];
+        cmd_args.extend_from_slice(extra_args);
+        ReconstructionRunnerArgs::try_from(
+            &ReconstructSubcommandArgs::from_iter_safe(&cmd_args).unwrap(),
+        )
+        .unwrap()
+    };
+
+    // All parameters are taken from the profile
+    let args = parse(&[]);
+    assert_eq!(args.params.particle_radius, 0.025);
+    assert_eq!(args.params.cube_size, 0.5 * 0.025);
+    assert_eq!(args.params.iso_surface_threshold, 0.7);
+    assert_eq!(args.params.rest_density, 1000.0);
+    assert!(args.params.spatial_decomposition.is_none());
+    assert_eq!(args.config.profile.as_deref(), Some("final"));
+    assert_eq!(args.config.to_json()["cube-size"], 0.5);
+
+    // Command line arguments override the profile
+    let args = parse(&["--cube-size=0.75", "--octree-decomposition=on"]);
+    assert_eq!(args.params.cube_size, 0.75 * 0.025);
+    assert_eq!(args.params.iso_surface_threshold, 0.7);
+    assert!(args.params.spatial_decomposition.is_some());
+
+    // Parameters without a default have to be given by the profile or on the command line
+    let cmd_args = ReconstructSubcommandArgs::from_iter_safe(&[
+        "reconstruct",
+        "-i",
+        "particles.vtk",
+        "--particle-radius=0.025",
+        "--cube-size=0.5",
+    ])
+    .unwrap();
+    assert!(ReconstructionRunnerArgs::try_from(&cmd_args).is_err());
+}
+
+#[test]
+fn test_reconstruct_with_scene() {
+    let scene_file = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../data/splishsplash_dam_break_scene.json"
+    );
+    let parse = |extra_args: &[&str]| {
+        let mut cmd_args = vec![
+            "reconstruct",
+            "-i",
+            "particles.bgeo",
+            "--scene",
+            scene_file,
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+        ];
+        cmd_args.extend_from_slice(extra_args);
+        ReconstructionRunnerArgs::try_from(
+            &ReconstructSubcommandArgs::from_iter_safe(&cmd_args).unwrap(),
+        )
+        .unwrap()
+    };
+
+    // The particle radius and rest density are taken from the scene
+    let args = parse(&[]);
+    assert_eq!(args.params.particle_radius, 0.025);
+    assert_eq!(args.params.rest_density, 998.0);
+    assert_eq!(args.params.cube_size, 0.5 * 0.025);
+    assert_eq!(args.config.scene.as_deref(), Some(scene_file));
+
+    // Command line arguments override the scene
+    let args = parse(&["--particle-radius=0.05", "--rest-density=1000"]);
+    assert_eq!(args.params.particle_radius, 0.05);
+    assert_eq!(args.params.rest_density, 1000.0);
+}
+
+#[test]
+fn test_reconstruct_with_tight_domain() {
+    let input_file = crate::io::test_cube_particles_file();
+    let output_dir = std::env::temp_dir().join("splashsurf_test_tight_domain");
+    let reconstruct = |name: &str, extra_args: &[&str]| {
+        let output_file = output_dir.join(format!("{}.vtk", name));
+        let summary_file = output_dir.join(format!("{}.json", name));
+        let mut cmd_args = vec![
+            "reconstruct",
+            "-i",
+            input_file,
+            "-o",
+            output_file.to_str().unwrap(),
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+            "--overwrite",
+            "--summary-json",
+            summary_file.to_str().unwrap(),
+        ];
+        cmd_args.extend_from_slice(extra_args);
+        reconstruct_subcommand(&ReconstructSubcommandArgs::from_iter_safe(&cmd_args).unwrap())
+            .unwrap();
+
+        let mesh = io::vtk_format::surface_mesh_from_vtk::<f64, _>(&output_file).unwrap();
+        let summary: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&summary_file).unwrap()).unwrap();
+        (mesh.mesh, summary)
+    };
+
+    let (full_mesh, _) = reconstruct("full", &[]);
+    let full_aabb = AxisAlignedBoundingBox3d::from_points(&full_mesh.vertices);
+
+    // Restrict the domain to the lower half of the surface
+    let min = full_aabb.min();
+    let max = full_aabb.centroid();
+    let domain_min = format!("--domain-min={},{},{}", min.x, min.y, min.z);
+    let domain_max = format!("--domain-max={} {} {}", max.x, max.y, max.z);
+    let (domain_mesh, summary) = reconstruct("domain", &[&domain_min, &domain_max]);
+    assert!(!domain_mesh.triangles.is_empty());
+    assert!(domain_mesh.triangles.len() < full_mesh.triangles.len());
+
+    // The background grid may extend the domain by less than one cube
+    let mut domain = AxisAlignedBoundingBox3d::new(*min, max);
+    domain.grow_uniformly(0.5 * 0.025);
+    assert!(domain_mesh
+        .vertices
+        .iter()
+        .all(|vertex| domain.contains_point(vertex)));
+
+    let domain_json = &summary["parameters"]["domain_aabb"];
+    assert_eq!(domain_json["min"][0].as_f64(), Some(min.x));
+    assert_eq!(domain_json["max"][2].as_f64(), Some(max.z));
+
+    // An open region of interest only keeps the triangles inside of the box
+    let roi_min = format!("--roi-min={},{},{}", min.x, min.y, min.z);
+    let roi_max = format!("--roi-max={},{},{}", max.x, max.y, max.z);
+    let (roi_mesh, summary) = reconstruct("roi", &[&roi_min, &roi_max]);
+    // The mesh is cut in single precision
+    let mut roi = AxisAlignedBoundingBox3d::new(*min, max);
+    roi.grow_uniformly(1e-6);
+    assert!(roi_mesh.triangles.len() < full_mesh.triangles.len());
+    assert!(roi_mesh
+        .vertices
+        .iter()
+        .all(|vertex| roi.contains_point(vertex)));
+    assert_eq!(
+        summary["frames"][0]["num_triangles"].as_u64(),
+        Some(roi_mesh.triangles.len() as u64)
+    );
+    assert_eq!(
+        summary["parameters"]["region_of_interest"]["cap"].as_bool(),
+        Some(false)
+    );
+
+    // Clipping additionally keeps the parts of the triangles inside of the box
+    let (clipped_mesh, summary) = reconstruct("roi_clip", &[&roi_min, &roi_max, "--clip-aabb"]);
+    assert!(clipped_mesh.triangles.len() > roi_mesh.triangles.len());
+    assert!(clipped_mesh
+        .vertices
+        .iter()
+        .all(|vertex| roi.contains_point(vertex)));
+    assert!(clipped_mesh.area() > roi_mesh.area());
+    assert_eq!(
+        summary["parameters"]["region_of_interest"]["clip"].as_bool(),
+        Some(true)
+    );
+}
+
+#[test]
+fn test_reconstruct_with_mesh_smoothing() {
+    let input_file = crate::io::test_cube_particles_file();
+    let output_dir = std::env::temp_dir().join("splashsurf_test_mesh_smoothing");
+    let parse = |name: &str, extra_args: &[&str]| {
+        let output_file = output_dir.join(format!("{}.vtk", name));
+        let mut cmd_args = vec![
+            "reconstruct".to_string(),
+            "-i".to_string(),
+            input_file.to_string(),
+            "-o".to_string(),
+            output_file.to_str().unwrap().to_string(),
+            "--particle-radius=0.025".to_string(),
+            "--smoothing-length=2.0".to_string(),
+            "--cube-size=0.5".to_string(),
+            "--overwrite".to_string(),
+        ];
+        cmd_args.extend(extra_args.iter().map(|arg| arg.to_string()));
+        (
+            ReconstructSubcommandArgs::from_iter_safe(&cmd_args),
+            output_file,
+        )
+    };
+    let reconstruct = |name: &str, extra_args: &[&str]| {
+        let (cmd_args, output_file) = parse(name, extra_args);
+        reconstruct_subcommand(&cmd_args.unwrap()).unwrap();
+        io::vtk_format::surface_mesh_from_vtk::<f64, _>(&output_file)
+            .unwrap()
+            .mesh
+    };
+
+    // The smoothing only moves the vertices and reduces the area of the bumpy surface
+    let mesh = reconstruct("unsmoothed", &[]);
+    let smoothed_mesh = reconstruct("smoothed", &["--mesh-smoothing-iters=5"]);
+    assert_eq!(smoothed_mesh.vertices.len(), mesh.vertices.len());
+    assert_eq!(smoothed_mesh.triangles, mesh.triangles);
+    assert!(smoothed_mesh.area() < mesh.area());
+
+    // The Taubin smoothing moves the vertices differently than the Laplacian smoothing
+    let taubin_mesh = reconstruct(
+        "taubin",
+        &["--smoothing-iters=5", "--smoothing-method=taubin"],
+    );
+    assert_eq!(taubin_mesh.triangles, mesh.triangles);
+    assert_ne!(taubin_mesh.vertices, smoothed_mesh.vertices);
+
+    // The density attributes are only meaningful at the vertices extracted by marching cubes
+    assert!(parse(
+        "smoothed",
+        &["--mesh-smoothing-iters=5", "--output-density-attributes"]
+    )
+    .0
+    .is_err());
+    let (cmd_args, _) = parse(
+        "smoothed",
+        &["--mesh-smoothing-iters=5", "--mesh-smoothing-weight=1.5"],
+    );
+    assert!(reconstruct_subcommand(&cmd_args.unwrap()).is_err());
+}
+
+#[test]
+fn test_reconstruct_remove_small_components() {
+    let input_file = crate::io::test_cube_particles_file();
+    let output_dir = std::env::temp_dir().join("splashsurf_test_remove_small_components");
+    let parse = |name: &str, extra_args: &[&str]| {
+        let output_file = output_dir.join(format!("{}.vtk", name));
+        let mut cmd_args = vec![
+            "reconstruct".to_string(),
+            "-i".to_string(),
+            input_file.to_string(),
+            "-o".to_string(),
+            output_file.to_str().unwrap().to_string(),
+            "--particle-radius=0.025".to_string(),
+            "--smoothing-length=2.0".to_string(),
+            "--cube-size=0.5".to_string(),
+            "--overwrite".to_string(),
+        ];
+        cmd_args.extend(extra_args.iter().map(|arg| arg.to_string()));
+        (
+            ReconstructSubcommandArgs::from_iter_safe(&cmd_args).unwrap(),
+            output_file,
+        )
+    };
+    let reconstruct = |name: &str, extra_args: &[&str]| {
+        let (cmd_args, output_file) = parse(name, extra_args);
+        reconstruct_subcommand(&cmd_args).unwrap();
+        io::vtk_format::surface_mesh_from_vtk::<f64, _>(&output_file)
+            .unwrap()
+            .mesh
+    };
+
+    // The surface of the cube is a single component that is larger than the threshold
+    let mesh = reconstruct("all_components", &[]);
+    let filtered_mesh = reconstruct("large_components", &["--mesh-min-component-triangles=100"]);
+    assert_eq!(filtered_mesh.vertices, mesh.vertices);
+    assert_eq!(filtered_mesh.triangles, mesh.triangles);
+
+    // The vertex indices of the merged splash spheres would be invalidated by the removal
+    let (cmd_args, _) = parse(
+        "large_components",
+        &[
+            "--mesh-min-component-triangles=100",
+            "--splash-output=merged",
+        ],
+    );
+    assert!(reconstruct_subcommand(&cmd_args).is_err());
+}
+
+#[test]
+fn test_reconstruct_mesh_cleanup() {
+    let input_file = crate::io::test_cube_particles_file();
+    let output_dir = std::env::temp_dir().join("splashsurf_test_mesh_cleanup");
+    let parse = |name: &str, extra_args: &[&str]| {
+        let output_file = output_dir.join(format!("{}.vtk", name));
+        let mut cmd_args = vec![
+            "reconstruct".to_string(),
+            "-i".to_string(),
+            input_file.to_string(),
+            "-o".to_string(),
+            output_file.to_str().unwrap().to_string(),
+            "--particle-radius=0.025".to_string(),
+            "--smoothing-length=2.0".to_string(),
+            "--cube-size=0.5".to_string(),
+            "--overwrite".to_string(),
+        ];
+        cmd_args.extend(extra_args.iter().map(|arg| arg.to_string()));
+        (
+            ReconstructSubcommandArgs::from_iter_safe(&cmd_args).unwrap(),
+            output_file,
+        )
+    };
+    let reconstruct = |name: &str, extra_args: &[&str]| {
+        let (cmd_args, output_file) = parse(name, extra_args);
+        reconstruct_subcommand(&cmd_args).unwrap();
+        io::vtk_format::surface_mesh_from_vtk::<f64, _>(&output_file)
+            .unwrap()
+            .mesh
+    };
+
+    // The cleaned mesh does not contain any degenerate triangles and is not modified by a second cleanup
+    let mesh = reconstruct("uncleaned", &[]);
+    let mut cleaned_mesh = reconstruct(
+        "cleaned",
+        &["--mesh-cleanup", "--mesh-cleanup-area-epsilon=1e-4"],
+    );
+    assert!(cleaned_mesh.triangles.len() <= mesh.triangles.len());
+    assert_eq!(cleaned_mesh.count_degenerate_triangles(), 0);
+    // The epsilon is relative to the squared cube size, halved to account for the single precision reconstruction
+    let cube_size = 0.5 * 0.025;
+    let area_epsilon = 0.5 * 1e-4 * cube_size * cube_size;
+    assert!(cleaned_mesh
+        .remove_degenerate_triangles(area_epsilon)
+        .is_empty());
+
+    let (cmd_args, _) = parse(
+        "cleaned",
+        &["--mesh-cleanup", "--mesh-cleanup-area-epsilon=-1"],
+    );
+    assert!(reconstruct_subcommand(&cmd_args).is_err());
+    let (cmd_args, _) = parse("cleaned", &["--mesh-cleanup", "--splash-output=merged"]);
+    assert!(reconstruct_subcommand(&cmd_args).is_err());
+}
+
+#[test]
+fn test_reconstruct_with_mesh_decimation() {
+    let input_file = crate::io::test_cube_particles_file();
+    let output_dir = std::env::temp_dir().join("splashsurf_test_mesh_decimation");
+    let reconstruct = |name: &str, extra_args: &[&str]| {
+        let output_file = output_dir.join(format!("{}.vtk", name));
+        let mut cmd_args = vec![
+            "reconstruct",
+            "-i",
+            input_file,
+            "-o",
+            output_file.to_str().unwrap(),
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+            "--overwrite",
+        ];
+        cmd_args.extend_from_slice(extra_args);
+        reconstruct_subcommand(&ReconstructSubcommandArgs::from_iter_safe(&cmd_args).unwrap())
+            .unwrap();
+        io::vtk_format::surface_mesh_from_vtk::<f64, _>(&output_file)
+            .unwrap()
+            .mesh
+    };
+
+    let mesh = reconstruct("full", &[]);
+    let target = mesh.triangles.len() / 2;
+    let decimated_mesh = reconstruct("decimated", &[&format!("--decimate-target={}", target)]);
+    assert!(!decimated_mesh.triangles.is_empty());
+    assert!(decimated_mesh.triangles.len() < mesh.triangles.len());
+}
+
+#[test]
+fn test_reconstruct_debug_cell_ids() {
+    let input_file = crate::io::test_cube_particles_file();
+    let output_dir = std::env::temp_dir().join("splashsurf_test_debug_cell_ids");
+    let parse = |octree: &str, extra_args: &[&str]| {
+        let output_file = output_dir.join(format!("octree_{}.vtk", octree));
+        let mut cmd_args = vec![
+            "reconstruct".to_string(),
+            "-i".to_string(),
+            input_file.to_string(),
+            "-o".to_string(),
+            output_file.to_str().unwrap().to_string(),
+            "--particle-radius=0.025".to_string(),
+            "--smoothing-length=2.0".to_string(),
+            "--cube-size=0.5".to_string(),
+            format!("--octree-decomposition={}", octree),
+            "--debug-cell-ids".to_string(),
+            "--overwrite".to_string(),
+        ];
+        cmd_args.extend(extra_args.iter().map(|arg| arg.to_string()));
+        (
+            ReconstructSubcommandArgs::from_iter_safe(&cmd_args),
+            output_file,
+        )
+    };
+
+    // The node ids are only written with octree decomposition
+    for (octree, names) in [
+        ("off", &["cell_id"][..]),
+        ("on", &["cell_id", "node_id"][..]),
+    ] {
+        let (cmd_args, output_file) = parse(octree, &[]);
+        reconstruct_subcommand(&cmd_args.unwrap()).unwrap();
+
+        let piece = io::vtk_format::VtkFile::load_file(&output_file)
+            .unwrap()
+            .into_pieces()
+            .remove(0);
+        assert_eq!(piece.cell_attribute_names(), names);
+        let num_triangles = piece
+            .load_as_surface_mesh::<f64>()
+            .unwrap()
+            .mesh
+            .triangles
+            .len();
+        let names: Vec<_> = names.iter().map(|name| name.to_string()).collect();
+        for attribute in piece.load_cell_attributes::<f64>(&names).unwrap() {
+            assert_eq!(attribute.data.len(), num_triangles);
+        }
+    }
+
+    // Removing triangles would invalidate the recorded origins
+    assert!(parse("off", &["--mesh-cleanup"]).0.is_err());
+    assert!(parse("off", &["--mesh-min-component-triangles=100"])
+        .0
+        .is_err());
+}
+
+#[test]
+fn test_reconstruct_mc_variant() {
+    let input_file = crate::io::test_cube_particles_file();
+    let output_dir = std::env::temp_dir().join("splashsurf_test_mc_variant");
+    let parse = |variant: &str, octree: &str| {
+        let output_file = output_dir.join(format!("{}.vtk", variant));
+        let cmd_args = vec![
+            "reconstruct".to_string(),
+            "-i".to_string(),
+            input_file.to_string(),
+            "-o".to_string(),
+            output_file.to_str().unwrap().to_string(),
+            "--particle-radius=0.025".to_string(),
+            "--smoothing-length=2.0".to_string(),
+            "--cube-size=0.5".to_string(),
+            format!("--mc-variant={}", variant),
+            format!("--octree-decomposition={}", octree),
+            "--overwrite".to_string(),
+        ];
+        (
+            ReconstructSubcommandArgs::from_iter_safe(&cmd_args),
+            output_file,
+        )
+    };
+
+    for (variant, octree) in [("classic", "on"), ("mc33", "on"), ("surfacenets", "off")] {
+        let (cmd_args, output_file) = parse(variant, octree);
+        reconstruct_subcommand(&cmd_args.unwrap()).unwrap();
+        let mesh = io::vtk_format::surface_mesh_from_vtk::<f64, _>(&output_file)
+            .unwrap()
+            .mesh;
+        assert!(!mesh.triangles.is_empty());
+        assert!(mesh.find_boundary_edges().is_empty());
+    }
+
+    assert!(parse("mc42", "on").0.is_err());
+
+    // Surface nets do not support stitching of the octree subdomains
+    let (cmd_args, _) = parse("surfacenets", "on");
+    assert!(reconstruct_subcommand(&cmd_args.unwrap()).is_err());
+}
+
+#[test]
+fn test_reconstruct_mc_vertex_snapping() {
+    let input_file = crate::io::test_cube_particles_file();
+    let output_dir = std::env::temp_dir().join("splashsurf_test_mc_vertex_snapping");
+    let output_file = output_dir.join("cube_surface.vtk");
+    let parse = |snapping: &str| {
+        let cmd_args = vec![
+            "reconstruct".to_string(),
+            "-i".to_string(),
+            input_file.to_string(),
+            "-o".to_string(),
+            output_file.to_str().unwrap().to_string(),
+            "--particle-radius=0.025".to_string(),
+            "--smoothing-length=2.0".to_string(),
+            "--cube-size=0.5".to_string(),
+            format!("--mc-vertex-snapping={}", snapping),
+            "--overwrite".to_string(),
+        ];
+        ReconstructSubcommandArgs::from_iter_safe(&cmd_args)
+    };
+
+    reconstruct_subcommand(&parse("0.2").unwrap()).unwrap();
+    let mesh = io::vtk_format::surface_mesh_from_vtk::<f64, _>(&output_file)
+        .unwrap()
+        .mesh;
+    assert!(!mesh.triangles.is_empty());
+    assert!(mesh.find_boundary_edges().is_empty());
+
+    // The snapping distance has to be less than half of the cube size
+    assert!(reconstruct_subcommand(&parse("0.5").unwrap()).is_err());
+}
+
+#[test]
+fn test_dry_run() {
+    let input_file = crate::io::test_cube_particles_file();
+    let output_dir = std::env::temp_dir().join("splashsurf_test_dry_run");
+    let _ = fs::remove_dir_all(&output_dir);
+    fs::create_dir_all(&output_dir).unwrap();
+
+    // The output directory does not exist yet, it is only created by an actual reconstruction
+    let output_file = output_dir.join("surfaces").join("cube_surface.vtk");
+    let summary_file = output_dir.join("dry_run.json");
+    let dry_run_args = |extra_args: &[&str]| {
+        let mut cmd_args = vec![
+            "reconstruct",
+            "-i",
+            input_file,
+            "-o",
+            output_file.to_str().unwrap(),
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+            "--dry-run",
+            "--summary-json",
+            summary_file.to_str().unwrap(),
+        ];
+        cmd_args.extend_from_slice(extra_args);
+        ReconstructSubcommandArgs::from_iter_safe(&cmd_args).unwrap()
+    };
+
+    reconstruct_subcommand(&dry_run_args(&[])).unwrap();
+    assert!(!output_file.parent().unwrap().exists());
+
+    let summary: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&summary_file).unwrap()).unwrap();
+    assert_eq!(summary["dry_run"].as_bool(), Some(true));
+    assert_eq!(summary["num_frames"].as_u64(), Some(1));
+    assert_eq!(summary["num_particles"].as_u64(), Some(2366));
+    assert_eq!(summary["float_type"].as_str(), Some("f32"));
+    assert_eq!(summary["index_type"].as_str(), Some("i32"));
+    assert_eq!(
+        summary["parameters"]["cube_size"].as_f64(),
+        Some(0.5 * 0.025)
+    );
+    assert!(summary["grid"]["num_points"].as_u64().unwrap() > 0);
+    let triangles = summary["estimate"]["triangles"].as_u64().unwrap();
+    assert!(triangles > 0);
+    assert!(summary["estimate"]["memory_bytes"].as_u64().unwrap() > 0);
+    assert_eq!(summary["budget"]["satisfied"].as_bool(), Some(true));
+
+    // The dry run fails if the estimates exceed the limits
+    let max_triangles = format!("--max-triangles={}", triangles - 1);
+    assert!(reconstruct_subcommand(&dry_run_args(&[&max_triangles])).is_err());
+    let summary: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&summary_file).unwrap()).unwrap();
+    assert_eq!(summary["budget"]["satisfied"].as_bool(), Some(false));
+    assert!(!output_file.parent().unwrap().exists());
+
+    fs::remove_dir_all(&output_dir).unwrap();
+}
+
+#[test]
+fn test_sequence_with_existing_outputs() {
+    let input_file = crate::io::test_cube_particles_file();
+    let sequence_dir = std::env::temp_dir().join("splashsurf_test_existing_outputs");
+    let _ = fs::remove_dir_all(&sequence_dir);
+    fs::create_dir_all(&sequence_dir).unwrap();
+    for i in 1..=2 {
+        fs::copy(input_file, sequence_dir.join(format!("cube_{}.vtk", i))).unwrap();
+    }
+
+    let summary_file = sequence_dir.join("summary.json");
+    let reconstruct = |extra_args: &[&str]| {
+        let input_pattern = sequence_dir.join("cube_{}.vtk");
+        let mut cmd_args = vec![
+            "reconstruct",
+            "-s",
+            input_pattern.to_str().unwrap(),
+            "--output-dir",
+            sequence_dir.to_str().unwrap(),
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+            "--summary-json",
+            summary_file.to_str().unwrap(),
+        ];
+        cmd_args.extend_from_slice(extra_args);
+        reconstruct_subcommand(&ReconstructSubcommandArgs::from_iter_safe(&cmd_args).unwrap())
+    };
+    let frame_counts = || {
+        let summary: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&summary_file).unwrap()).unwrap();
+        ["processed", "skipped", "failed"].map(|key| summary["frame_counts"][key].as_u64().unwrap())
+    };
+
+    reconstruct(&[]).unwrap();
+    assert_eq!(frame_counts(), [2, 0, 0]);
+    assert!(sequence_dir.join("cube_surface_2.vtk").exists());
+
+    // Existing output files are neither overwritten nor skipped by default
+    assert!(reconstruct(&[]).is_err());
+    assert_eq!(frame_counts(), [0, 0, 2]);
+
+    reconstruct(&["--skip-existing"]).unwrap();
+    assert_eq!(frame_counts(), [0, 2, 0]);
+
+    reconstruct(&["--skip-existing", "--force"]).unwrap();
+    assert_eq!(frame_counts(), [2, 0, 0]);
+}
+
+#[test]
+fn test_sequence_with_corrupt_file() {
+    let input_file = crate::io::test_cube_particles_file();
+    let sequence_dir = std::env::temp_dir().join("splashsurf_test_corrupt_file");
+    let _ = fs::remove_dir_all(&sequence_dir);
+    fs::create_dir_all(&sequence_dir).unwrap();
+    for i in [1, 3] {
+        fs::copy(input_file, sequence_dir.join(format!("cube_{}.vtk", i))).unwrap();
+    }
+    fs::write(sequence_dir.join("cube_2.vtk"), "not a vtk file").unwrap();
+
+    let summary_file = sequence_dir.join("summary.json");
+    let reconstruct = |on_error: &str| {
+        let input_pattern = sequence_dir.join("cube_{}.vtk");
+        let on_error = format!("--on-error={}", on_error);
+        let cmd_args = [
+            "reconstruct",
+            "-s",
+            input_pattern.to_str().unwrap(),
+            "--output-dir",
+            sequence_dir.to_str().unwrap(),
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+            "--overwrite",
+            "--summary-json",
+            summary_file.to_str().unwrap(),
+            &on_error,
+        ];
+        reconstruct_subcommand(&ReconstructSubcommandArgs::from_iter_safe(&cmd_args).unwrap())
+    };
+    let summary = || -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(&summary_file).unwrap()).unwrap()
+    };
+
+    // The remaining input files are processed and the failed input file is reported
+    let err = reconstruct("skip").unwrap_err();
+    assert_eq!(crate::exit_code(&err), 1);
+    assert!(sequence_dir.join("cube_surface_1.vtk").exists());
+    assert!(!sequence_dir.join("cube_surface_2.vtk").exists());
+    assert!(sequence_dir.join("cube_surface_3.vtk").exists());
+
+    let report = summary();
+    assert_eq!(report["frame_counts"]["processed"], 2);
+    assert_eq!(report["frame_counts"]["failed"], 1);
+    let failed_frames = report["failed_frames"].as_array().unwrap();
+    assert_eq!(failed_frames.len(), 1);
+    assert!(failed_frames[0]["input_file"]
+        .as_str()
+        .unwrap()
+        .ends_with("cube_2.vtk"));
+    assert!(!failed_frames[0]["error"].as_str().unwrap().is_empty());
+
+    // The last input file is not processed after the failure by default
+    fs::remove_file(sequence_dir.join("cube_surface_3.vtk")).unwrap();
+    let err = reconstruct("abort").unwrap_err();
+    assert_eq!(crate::exit_code(&err), 1);
+    assert!(!sequence_dir.join("cube_surface_3.vtk").exists());
+    assert_eq!(summary()["frame_counts"]["aborted"], 1);
+
+    // Invalid arguments are fatal errors
+    assert!(ReconstructSubcommandArgs::from_iter_safe(&[
+        "reconstruct",
+        "-i",
+        input_file,
+        "--particle-radius=0.025",
+        "--smoothing-length=2.0",
+        "--cube-size=0.5",
+        "--on-error=retry:0",
+    ])
+    .is_err());
+    assert_eq!(
+        crate::exit_code(&anyhow!("Failed to build the thread pool")),
+        2
+    );
+}
+
+#[test]
+fn test_fixed_grid_sequence() {
+    let sequence_dir = std::env::temp_dir().join("splashsurf_test_fixed_grid_sequence");
+    let _ = fs::remove_dir_all(&sequence_dir);
+    fs::create_dir_all(&sequence_dir).unwrap();
+
+    // A block of particles that moves slightly in the second frame and far away in the third frame (binary XYZ files)
+    let particle_radius = 0.025f32;
+    for (frame, offset) in [
+        (1, 0.0),
+        (2, 0.5 * particle_radius),
+        (3, 10.0 * particle_radius),
+    ] {
+        let mut bytes = Vec::new();
+        for i in 0..5 {
+            for j in 0..5 {
+                for k in 0..5 {
+                    for coordinate in [i, j, k] {
+                        let value = coordinate as f32 * 2.0 * particle_radius + offset;
+                        bytes.extend_from_slice(&value.to_ne_bytes());
+                    }
+                }
+            }
+        }
+        fs::write(sequence_dir.join(format!("block_{}.xyz", frame)), bytes).unwrap();
+    }
+
+    let summary_file = sequence_dir.join("summary.json");
+    let reconstruct = |extra_args: &[&str]| {
+        let input_pattern = sequence_dir.join("block_{}.xyz");
+        let mut cmd_args = vec![
+            "reconstruct",
+            "-s",
+            input_pattern.to_str().unwrap(),
+            "--output-dir",
+            sequence_dir.to_str().unwrap(),
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+            "--overwrite",
+            "--fixed-grid=on",
+            "--summary-json",
+            summary_file.to_str().unwrap(),
+        ];
+        cmd_args.extend_from_slice(extra_args);
+        reconstruct_subcommand(&ReconstructSubcommandArgs::from_iter_safe(&cmd_args).unwrap())
+    };
+
+    // The particles of the third frame leave the grid computed from the first frame
+    assert!(reconstruct(&[]).is_err());
+
+    reconstruct(&["--grid-escape=regrid"]).unwrap();
+    let summary: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&summary_file).unwrap()).unwrap();
+    assert_eq!(summary["frame_counts"]["processed"].as_u64(), Some(3));
+    // The fixed grid is given by the domain computed from the first frame
+    let domain_json = &summary["parameters"]["domain_aabb"];
+    assert!(domain_json["min"][0].as_f64().unwrap() < 0.0);
+    assert!(domain_json["max"][0].as_f64().unwrap() > 8.0 * particle_radius as f64);
+}
+
+#[test]
+fn test_parse_attribute_selection() {
+    let args = ReconstructSubcommandArgs::from_iter_safe(&[
+        "reconstruct",
+        "-i",
+        "particles.vtk",
+        "--particle-radius=0.025",
+        "--smoothing-length=2.0",
+        "--cube-size=0.5",
+        "--interpolate-attributes",
+        "velocity:sph,id:Nearest,pressure",
+    ])
+    .unwrap();
+    assert_eq!(
+        args.interpolate_attributes,
+        vec![
+            AttributeSelection {
+                name: "velocity".to_string(),
+                interpolation: Some(AttributeInterpolation::Sph),
+            },
+            AttributeSelection {
+                name: "id".to_string(),
+                interpolation: Some(AttributeInterpolation::Nearest),
+            },
+            AttributeSelection {
+                name: "pressure".to_string(),
+                interpolation: None,
+            },
+        ]
+    );
+
+    // The flag can be repeated and is also accepted in singular form
+    let args = ReconstructSubcommandArgs::from_iter_safe(&[
+        "reconstruct",
+        "-i",
+        "particles.vtk",
+        "--particle-radius=0.025",
+        "--smoothing-length=2.0",
+        "--cube-size=0.5",
+        "--interpolate-attribute",
+        "velocity",
+        "--interpolate-attributes=temperature:sph",
+    ])
+    .unwrap();
+    assert_eq!(
+        args.interpolate_attributes,
+        vec![
+            AttributeSelection {
+                name: "velocity".to_string(),
+                interpolation: None,
+            },
+            AttributeSelection {
+                name: "temperature".to_string(),
+                interpolation: Some(AttributeInterpolation::Sph),
+            },
+        ]
+    );
+
+    assert!("id:linear".parse::<AttributeSelection>().is_err());
+    assert!(":nearest".parse::<AttributeSelection>().is_err());
+}
+
+#[test]
+fn test_reconstruct_with_cache() {
+    let input_file = crate::io::test_cube_particles_file();
+    let output_dir = std::env::temp_dir().join("splashsurf_test_reconstruct_with_cache");
+    let cache_dir = output_dir.join("cache");
+    let _ = fs::remove_dir_all(&output_dir);
+    fs::create_dir_all(&output_dir).unwrap();
+
+    let reconstruct = |output_name: &str, extra_args: &[&str]| {
+        let output_file = output_dir.join(output_name);
+        let mut args = vec![
+            "reconstruct",
+            "-i",
+            input_file,
+            "-o",
+            output_file.to_str().unwrap(),
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+            "--octree-decomposition=off",
+            "--deterministic=on",
+            "--stats",
+            "--cache-dir",
+            cache_dir.to_str().unwrap(),
+        ];
+        args.extend_from_slice(extra_args);
+        let cmd_args = ReconstructSubcommandArgs::from_iter_safe(&args).unwrap();
+        let args = ReconstructionRunnerArgs::try_from(&cmd_args).unwrap();
+        let paths = ReconstructionRunnerPathCollection::try_from(&cmd_args)
+            .unwrap()
+            .collect();
+        let summary = reconstruction_pipeline(&paths[0], &args).unwrap();
+
+        let timings = summary.timings.unwrap();
+        let has_stage = |name: &str| {
+            timings.stages.iter().any(|stage| {
+                stage.name == name || stage.sub_stages.iter().any(|stage| stage.name == name)
+            })
+        };
+        let computed_density_map = has_stage("reconstruct_surface_global");
+        assert_ne!(
+            computed_density_map,
+            has_stage("reconstruct_surface_from_density_map")
+        );
+        (computed_density_map, fs::read(&output_file).unwrap())
+    };
+    let num_cache_entries = || fs::read_dir(&cache_dir).unwrap().count();
+
+    // The first run computes and stores the density map, the second run only performs the triangulation
+    let (computed_first, mesh_first) = reconstruct("first.vtk", &[]);
+    assert!(computed_first);
+    assert_eq!(num_cache_entries(), 1);
+    let (computed_second, mesh_second) = reconstruct("second.vtk", &[]);
+    assert!(!computed_second);
+    assert_eq!(mesh_first, mesh_second);
+
+    // The iso-surface threshold is applied during the triangulation
+    let (computed_threshold, _) = reconstruct("threshold.vtk", &["--surface-threshold=0.7"]);
+    assert!(!computed_threshold);
+    assert_eq!(num_cache_entries(), 1);
+
+    // Other density parameters result in a new cache entry
+    let (computed_rest_density, _) = reconstruct("rest_density.vtk", &["--rest-density=1100"]);
+    assert!(computed_rest_density);
+    assert_eq!(num_cache_entries(), 2);
+
+    // The cache is not used at all with `--no-cache`
+    let (computed_no_cache, mesh_no_cache) = reconstruct("no_cache.vtk", &["--no-cache"]);
+    assert!(computed_no_cache);
+    assert_eq!(mesh_first, mesh_no_cache);
+
+    // A cache size of zero removes all entries after storing them
+    let (computed_limit, _) = reconstruct("limit.vtk", &["--cache-max-gb=0", "--rest-density=900"]);
+    assert!(computed_limit);
+    assert_eq!(num_cache_entries(), 0);
+
+    fs::remove_dir_all(&output_dir).unwrap();
+}
+
+#[test]
+fn test_measure_sequence() {
+    let sequence_dir = std::env::temp_dir().join("splashsurf_test_measure_sequence");
+    let _ = fs::remove_dir_all(&sequence_dir);
+    fs::create_dir_all(&sequence_dir).unwrap();
+
+    // Three frames with growing blocks of particles (binary XYZ files)
+    let particle_radius = 0.025f32;
+    for (frame, n) in [(1, 4), (2, 5), (3, 6)] {
+        let mut bytes = Vec::new();
+        for i in 0..n {
+            for j in 0..n {
+                for k in 0..n {
+                    for coordinate in [i, j, k] {
+                        let value = coordinate as f32 * 2.0 * particle_radius;
+                        bytes.extend_from_slice(&value.to_ne_bytes());
+                    }
+                }
+            }
+        }
+        fs::write(sequence_dir.join(format!("block_{}.xyz", frame)), bytes).unwrap();
+    }
+
+    let csv_file = sequence_dir.join("metrics.csv");
+    let summary_file = sequence_dir.join("summary.json");
+    let reconstruct = || {
+        let input_pattern = sequence_dir.join("block_{}.xyz");
+        reconstruct_subcommand(
+            &ReconstructSubcommandArgs::from_iter_safe(&[
+                "reconstruct",
+                "-s",
+                input_pattern.to_str().unwrap(),
+                "--output-dir",
+                sequence_dir.to_str().unwrap(),
+                "--particle-radius=0.025",
+                "--smoothing-length=2.0",
+                "--cube-size=0.5",
+                "--deterministic=on",
+                "--measure-csv=metrics.csv",
+                "--measure-only",
+                "--summary-json",
+                summary_file.to_str().unwrap(),
+            ])
+            .unwrap(),
+        )
+        .unwrap();
+        let summary: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&summary_file).unwrap()).unwrap();
+        ["processed", "skipped"].map(|key| summary["frame_counts"][key].as_u64().unwrap())
+    };
+
+    assert_eq!(reconstruct(), [3, 0]);
+    // No meshes are written with `--measure-only`
+    assert!(!sequence_dir.join("block_surface_1.vtk").exists());
+
+    let csv = fs::read_to_string(&csv_file).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some(measure::CSV_HEADER));
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows.len(), 3);
+
+    let mut previous_volume = 0.0;
+    for (row, n) in rows.iter().zip([4usize, 5, 6]) {
+        assert_eq!(row.len(), 8);
+        assert!(row[0].ends_with(&format!("block_{}.xyz", n - 3)));
+        assert_eq!(row[1].parse::<f64>().unwrap(), (n - 3) as f64);
+        assert_eq!(row[2].parse::<usize>().unwrap(), n * n * n);
+        assert_eq!(row[3], "0");
+        assert!(row[4].parse::<usize>().unwrap() > 0);
+        assert!(row[5].parse::<usize>().unwrap() > 0);
+
+        // The surface approximately encloses a cube with an edge length of n particle diameters
+        let edge_length = n as f64 * 2.0 * particle_radius as f64;
+        let surface_area = row[6].parse::<f64>().unwrap();
+        let volume = row[7].parse::<f64>().unwrap();
+        assert!((surface_area / (6.0 * edge_length.powi(2)) - 1.0).abs() < 0.2);
+        assert!((volume / edge_length.powi(3) - 1.0).abs() < 0.2);
+        assert!(volume > previous_volume);
+        previous_volume = volume;
+    }
+
+    // Resume after an interruption during the third frame, the incomplete row is replaced
+    let complete_rows: String = csv
+        .lines()
+        .take(3)
+        .map(|line| format!("{}\n", line))
+        .collect();
+    fs::write(&csv_file, format!("{}{}", complete_rows, "incomplete")).unwrap();
+    assert_eq!(reconstruct(), [1, 2]);
+    assert_eq!(fs::read_to_string(&csv_file).unwrap(), csv);
+
+    // All frames are complete
+    assert_eq!(reconstruct(), [0, 3]);
+    assert_eq!(fs::read_to_string(&csv_file).unwrap(), csv);
+
+    fs::remove_dir_all(&sequence_dir).unwrap();
+}
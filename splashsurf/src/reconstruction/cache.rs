@@ -0,0 +1,296 @@
+//! On-disk cache of the density maps of reconstructions, see `--cache-dir`, `--cache-max-gb` and `--no-cache`
+//!
+//! Every entry stores the density map, background grid and particle densities of a global reconstruction (without
+//! octree decomposition) as [`DensityMapSnapshot`]. Entries are keyed by a SHA-256 hash of the particle positions and
+//! all parameters that influence the density map, so reconstructions of the same frame with e.g. different output or
+//! attribute settings skip directly to the triangulation. If the total size of the cache exceeds its limit, the least
+//! recently used entries are removed.
+
+use anyhow::Context;
+use log::{debug, info, warn};
+use sha2::{Digest, Sha256};
+use splashsurf_lib::nalgebra::Vector3;
+use splashsurf_lib::{
+    reconstruct_surface_from_density_map, reconstruct_surface_inplace, DensityMapSnapshot, Index,
+    Parameters, Real, SplashDetection, SurfaceReconstruction,
+};
+use std::fs;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+/// File extension of the cache entries
+const ENTRY_EXTENSION: &str = "dms";
+/// Prefix of the hashed data, has to be changed if the key or the entry format changes
+const KEY_VERSION: &[u8] = b"splashsurf density map cache v1";
+
+/// Directory with cached density maps and its size limit
+#[derive(Clone, Debug)]
+pub(crate) struct DensityMapCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl DensityMapCache {
+    /// Creates a cache in the given directory (created on demand) with the given maximum total size of all entries
+    pub(crate) fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        Self { dir, max_bytes }
+    }
+
+    /// Returns the key of the density map of a reconstruction of the given particles with the given parameters
+    fn key<I: Index, R: Real>(
+        particle_positions: &[Vector3<R>],
+        parameters: &Parameters<R>,
+    ) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(KEY_VERSION);
+        hasher.update([
+            std::mem::size_of::<I>() as u8,
+            std::mem::size_of::<R>() as u8,
+        ]);
+
+        hasher.update((particle_positions.len() as u64).to_le_bytes());
+        for position in particle_positions {
+            hasher.update(bytemuck::cast_slice::<R, u8>(position.as_slice()));
+        }
+
+        let mut update_real = |value: R| hasher.update(bytemuck::bytes_of(&value));
+        update_real(parameters.particle_radius);
+        update_real(parameters.rest_density);
+        update_real(parameters.compact_support_radius);
+        update_real(parameters.cube_size);
+        // The iso-surface threshold only affects the clipping of the density map
+        if !parameters.clipping_planes.is_empty() {
+            update_real(parameters.iso_surface_threshold);
+        }
+        match parameters.splash_detection {
+            None => update_real(R::zero()),
+            Some(SplashDetection::NeighborCount(radius)) => {
+                update_real(R::one());
+                update_real(radius);
+            }
+            Some(SplashDetection::DensityThreshold(factor)) => {
+                update_real(-R::one());
+                update_real(factor);
+            }
+        }
+        if let Some(domain) = &parameters.domain_aabb {
+            domain.min().iter().copied().for_each(&mut update_real);
+            domain.max().iter().copied().for_each(&mut update_real);
+        }
+        for plane in &parameters.clipping_planes {
+            plane.point.iter().copied().for_each(&mut update_real);
+            plane.normal.iter().copied().for_each(&mut update_real);
+        }
+        // Avoid collisions between domains and clipping planes
+        hasher.update([
+            parameters.domain_aabb.is_some() as u8,
+            parameters.clipping_planes.len() as u8,
+        ]);
+
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Returns the path of the entry with the given key
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", key, ENTRY_EXTENSION))
+    }
+
+    /// Loads the entry with the given key and marks it as recently used, returns `None` if there is no valid entry
+    fn load<I: Index, R: Real>(&self, key: &str) -> Option<DensityMapSnapshot<I, R>> {
+        let path = self.entry_path(key);
+        let file = match fs::File::open(&path) {
+            Ok(file) => file,
+            Err(_) => return None,
+        };
+
+        match DensityMapSnapshot::read(BufReader::new(&file)) {
+            Ok(snapshot) => {
+                // The modification time of the entries is used for the least recently used eviction
+                if let Err(err) = touch(&path) {
+                    debug!(
+                        "Failed to update the modification time of cache entry \"{}\": {}",
+                        path.display(),
+                        err
+                    );
+                }
+                Some(snapshot)
+            }
+            Err(err) => {
+                warn!(
+                    "Removing invalid cache entry \"{}\": {}",
+                    path.display(),
+                    err
+                );
+                drop(file);
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Stores the snapshot as entry with the given key and evicts the least recently used entries if the cache is too large
+    fn store<I: Index, R: Real>(
+        &self,
+        key: &str,
+        snapshot: &DensityMapSnapshot<I, R>,
+    ) -> Result<(), anyhow::Error> {
+        // Unique name of the temporary file for concurrent reconstructions of the same input (e.g. multiple processes)
+        static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        fs::create_dir_all(&self.dir).with_context(|| {
+            format!(
+                "Failed to create the cache directory \"{}\"",
+                self.dir.display()
+            )
+        })?;
+
+        // Write to a temporary file first such that other processes never see incomplete entries
+        let path = self.entry_path(key);
+        let temp_path = self.dir.join(format!(
+            "{}.{}.{}.tmp",
+            key,
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let write = || -> Result<(), anyhow::Error> {
+            let mut writer = BufWriter::new(fs::File::create(&temp_path)?);
+            snapshot.write(&mut writer)?;
+            writer.flush()?;
+            fs::rename(&temp_path, &path)?;
+            Ok(())
+        };
+        if let Err(err) = write() {
+            let _ = fs::remove_file(&temp_path);
+            return Err(err)
+                .with_context(|| format!("Failed to write cache entry \"{}\"", path.display()));
+        }
+
+        self.evict()
+    }
+
+    /// Removes the least recently used entries until the total size of all entries does not exceed the limit
+    fn evict(&self) -> Result<(), anyhow::Error> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(ENTRY_EXTENSION) {
+                continue;
+            }
+            // Entries may be removed concurrently by other processes
+            if let Ok(metadata) = fs::metadata(&path) {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                entries.push((modified, metadata.len(), path));
+            }
+        }
+
+        let mut total_bytes: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        entries.sort();
+        for (_, len, path) in entries {
+            if total_bytes <= self.max_bytes {
+                break;
+            }
+            info!(
+                "Removing least recently used cache entry \"{}\" ({:.2} MB).",
+                path.display(),
+                len as f64 * 1e-6
+            );
+            let _ = fs::remove_file(&path);
+            total_bytes -= len;
+        }
+
+        Ok(())
+    }
+
+    /// Performs a global reconstruction (without octree decomposition), the density map is loaded from the cache if possible and stored in the cache otherwise
+    pub(crate) fn reconstruct_surface<I: Index, R: Real>(
+        &self,
+        particle_positions: &[Vector3<R>],
+        parameters: &Parameters<R>,
+        record_triangle_origins: bool,
+    ) -> Result<SurfaceReconstruction<I, R>, anyhow::Error> {
+        let key = Self::key::<I, R>(particle_positions, parameters);
+        let mut reconstruction = SurfaceReconstruction::default();
+        reconstruction.set_record_triangle_origins(record_triangle_origins);
+
+        if let Some(snapshot) = self.load::<I, R>(&key) {
+            if snapshot.particle_densities.len() == particle_positions.len() {
+                info!("Using the cached density map \"{}\".", key);
+                reconstruct_surface_from_density_map(
+                    particle_positions,
+                    parameters,
+                    snapshot,
+                    &mut reconstruction,
+                )?;
+                return Ok(reconstruction);
+            }
+            warn!(
+                "Ignoring the cached density map \"{}\" with a different number of particles.",
+                key
+            );
+        }
+
+        reconstruction.set_keep_density_map(true);
+        reconstruct_surface_inplace(particle_positions, parameters, &mut reconstruction)?;
+        if let Some(snapshot) = reconstruction.density_map_snapshot() {
+            // A failure to update the cache does not affect the reconstruction
+            match self.store(&key, &snapshot) {
+                Ok(()) => info!("Stored the density map in the cache as \"{}\".", key),
+                Err(err) => warn!("{:?}", err),
+            }
+        }
+
+        Ok(reconstruction)
+    }
+}
+
+/// Sets the modification time of the given file to the current time
+fn touch(path: &Path) -> std::io::Result<()> {
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(SystemTime::now())
+}
+
+#[test]
+fn test_cache_eviction() {
+    use std::time::Duration;
+
+    let dir = std::env::temp_dir().join(format!(
+        "splashsurf_test_cache_eviction_{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    // Three entries of 100 bytes each, "b" was used least recently
+    let now = SystemTime::now();
+    for (name, age) in [("a", 10), ("b", 30), ("c", 20)] {
+        let path = dir.join(format!("{}.{}", name, ENTRY_EXTENSION));
+        fs::write(&path, [0u8; 100]).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(now - Duration::from_secs(age))
+            .unwrap();
+    }
+    // Other files are not part of the cache
+    fs::write(dir.join("notes.txt"), [0u8; 1000]).unwrap();
+
+    let cache = DensityMapCache::new(dir.clone(), 250);
+    cache.evict().unwrap();
+    let exists = |name: &str| dir.join(name).exists();
+    assert!(exists("a.dms") && !exists("b.dms") && exists("c.dms"));
+    assert!(exists("notes.txt"));
+
+    DensityMapCache::new(dir.clone(), 100).evict().unwrap();
+    assert!(exists("a.dms") && !exists("c.dms"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,282 @@
+//! Handling of input files that fail to be reconstructed, see `--on-error`
+
+use super::arguments::ReconstructionRunnerPaths;
+use crate::io::vtk_format::VtkIoError;
+use crate::log_error;
+use anyhow::anyhow;
+use log::{error, warn};
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// What to do when the reconstruction of an input file fails with an error or panics
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ErrorPolicy {
+    /// Stop processing further input files
+    Abort,
+    /// Continue with the next input file
+    Skip,
+    /// Retry the input file up to the given number of times, then continue with the next input file
+    Retry(usize),
+}
+
+impl FromStr for ErrorPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "abort" => Ok(ErrorPolicy::Abort),
+            "skip" => Ok(ErrorPolicy::Skip),
+            policy => {
+                let retries = policy
+                    .strip_prefix("retry:")
+                    .and_then(|retries| retries.parse::<usize>().ok())
+                    .filter(|&retries| retries > 0)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Invalid error policy \"{}\", expected \"abort\", \"skip\" or \"retry:N\" with a positive number of retries N",
+                            s
+                        )
+                    })?;
+                Ok(ErrorPolicy::Retry(retries))
+            }
+        }
+    }
+}
+
+impl ErrorPolicy {
+    /// Returns the maximum number of attempts to reconstruct a single input file
+    fn max_attempts(&self) -> usize {
+        match self {
+            ErrorPolicy::Retry(retries) => retries + 1,
+            _ => 1,
+        }
+    }
+
+    /// Returns whether no further input files should be processed after a failed input file
+    pub(crate) fn aborts(&self) -> bool {
+        matches!(self, ErrorPolicy::Abort)
+    }
+}
+
+/// Error of an input file that could not be reconstructed, collected for the final report and the JSON summary
+#[derive(Clone, Debug)]
+pub(crate) struct FrameError {
+    pub input_file: PathBuf,
+    pub output_file: PathBuf,
+    /// Rendered error of the last attempt
+    pub error: String,
+    /// Rendered chain of the underlying causes of the error of the last attempt
+    pub causes: Vec<String>,
+    /// Whether the last attempt panicked instead of returning an error
+    pub panicked: bool,
+    /// Number of attempts to reconstruct the input file
+    pub attempts: usize,
+}
+
+impl FrameError {
+    fn from_error(paths: &ReconstructionRunnerPaths, err: &anyhow::Error, attempts: usize) -> Self {
+        Self {
+            input_file: paths.input_file.clone(),
+            output_file: paths.output_file.clone(),
+            error: err.to_string(),
+            causes: err.chain().skip(1).map(|cause| cause.to_string()).collect(),
+            panicked: false,
+            attempts,
+        }
+    }
+
+    fn from_panic(paths: &ReconstructionRunnerPaths, payload: &dyn Any, attempts: usize) -> Self {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic payload".to_string());
+        Self {
+            input_file: paths.input_file.clone(),
+            output_file: paths.output_file.clone(),
+            error: format!("panicked: {}", message),
+            causes: Vec::new(),
+            panicked: true,
+            attempts,
+        }
+    }
+
+    /// Converts the error to a JSON value for the run summary
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "input_file": self.input_file.display().to_string(),
+            "output_file": self.output_file.display().to_string(),
+            "error": self.error,
+            "causes": self.causes,
+            "panicked": self.panicked,
+            "attempts": self.attempts,
+        })
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\": {}", self.input_file.display(), self.error)?;
+        for cause in &self.causes {
+            write!(f, ": {}", cause)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether another attempt to reconstruct an input file may succeed after the given error
+///
+/// Errors of VTK files are only retried if they are caused by I/O errors or by files that appear to be truncated
+/// (e.g. because they are still being written), other errors like unsupported data sets are permanent.
+fn is_retryable(err: &anyhow::Error) -> bool {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<VtkIoError>())
+        .map_or(true, VtkIoError::is_retryable)
+}
+
+/// Runs the reconstruction of a single input file and retries it according to the policy if it fails
+///
+/// Panics of the reconstruction are caught and reported like errors. Every attempt creates a new surface
+/// reconstruction including its workspace (or takes the persistent reconstruction of `--watch`, which is only stored
+/// again after a successful attempt), i.e. no state of a panicked attempt is reused by later attempts or input files.
+/// Errors that are known to be permanent (see [`is_retryable`]) are not retried.
+pub(crate) fn run_frame<T, F>(
+    policy: ErrorPolicy,
+    paths: &ReconstructionRunnerPaths,
+    reconstruct: F,
+) -> Result<T, FrameError>
+where
+    F: Fn() -> Result<T, anyhow::Error>,
+{
+    let max_attempts = policy.max_attempts();
+    let mut attempt = 1;
+    loop {
+        let (frame_error, retryable) = match panic::catch_unwind(AssertUnwindSafe(&reconstruct)) {
+            Ok(Ok(result)) => return Ok(result),
+            Ok(Err(err)) => {
+                let frame_error = FrameError::from_error(paths, &err, attempt);
+                let retryable = is_retryable(&err);
+                log_error(&err.context(format!(
+                    "Error while processing input file \"{}\"",
+                    paths.input_file.display()
+                )));
+                (frame_error, retryable)
+            }
+            Err(payload) => {
+                let frame_error = FrameError::from_panic(paths, payload.as_ref(), attempt);
+                error!("Error occurred: {}", frame_error);
+                (frame_error, true)
+            }
+        };
+
+        if attempt >= max_attempts {
+            return Err(frame_error);
+        }
+        if !retryable {
+            warn!(
+                "Not retrying input file \"{}\" as the error is permanent.",
+                paths.input_file.display()
+            );
+            return Err(frame_error);
+        }
+        attempt += 1;
+        warn!(
+            "Retrying input file \"{}\" (attempt {} of {})...",
+            paths.input_file.display(),
+            attempt,
+            max_attempts
+        );
+    }
+}
+
+/// Error returned by the `reconstruct` subcommand if input files were not processed or failed, the process exits with code 1
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FramesFailed {
+    pub num_failed: usize,
+    pub num_frames: usize,
+}
+
+impl fmt::Display for FramesFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} input files were not processed successfully",
+            self.num_failed, self.num_frames
+        )
+    }
+}
+
+impl std::error::Error for FramesFailed {}
+
+#[test]
+fn test_error_policy() {
+    use std::cell::Cell;
+
+    assert_eq!("abort".parse::<ErrorPolicy>().unwrap(), ErrorPolicy::Abort);
+    assert_eq!("Skip".parse::<ErrorPolicy>().unwrap(), ErrorPolicy::Skip);
+    assert_eq!(
+        "retry:3".parse::<ErrorPolicy>().unwrap(),
+        ErrorPolicy::Retry(3)
+    );
+    for invalid in ["retry", "retry:0", "retry:-1", "continue"] {
+        assert!(invalid.parse::<ErrorPolicy>().is_err());
+    }
+
+    let paths = ReconstructionRunnerPaths::new(
+        PathBuf::from("fluid_2.vtk"),
+        PathBuf::from("fluid_surface_2.vtk"),
+        false,
+        false,
+        Vec::new(),
+    );
+
+    // Errors are retried until an attempt succeeds
+    let attempts = Cell::new(0);
+    let result = run_frame(ErrorPolicy::Retry(2), &paths, || {
+        attempts.set(attempts.get() + 1);
+        if attempts.get() < 3 {
+            Err(anyhow!("corrupt file").context("failed to load"))
+        } else {
+            Ok(attempts.get())
+        }
+    });
+    assert_eq!(result.unwrap(), 3);
+
+    // The error of the last attempt is reported with its causes
+    let err = run_frame(ErrorPolicy::Retry(1), &paths, || -> Result<(), _> {
+        Err(anyhow!("corrupt file").context("failed to load"))
+    })
+    .unwrap_err();
+    assert_eq!(err.error, "failed to load");
+    assert_eq!(err.causes, vec!["corrupt file".to_string()]);
+    assert_eq!(err.attempts, 2);
+    assert!(!err.panicked);
+
+    // Permanent errors of VTK files are not retried, truncated files are
+    let unsupported: fn() -> VtkIoError = || VtkIoError::UnsupportedDataSet {
+        found: "ImageData".to_string(),
+    };
+    let truncated: fn() -> VtkIoError = || VtkIoError::Parse {
+        context: "unexpected end of input".to_string(),
+        truncated: true,
+    };
+    for (vtk_error, expected_attempts) in [(unsupported, 1), (truncated, 3)] {
+        let err = run_frame(ErrorPolicy::Retry(2), &paths, || -> Result<(), _> {
+            Err(anyhow::Error::new(vtk_error()).context("Failed to load particle positions"))
+        })
+        .unwrap_err();
+        assert_eq!(err.attempts, expected_attempts);
+    }
+
+    // Panics are caught and not retried by the skip policy
+    let err = run_frame(ErrorPolicy::Skip, &paths, || -> Result<(), _> {
+        panic!("index out of bounds")
+    })
+    .unwrap_err();
+    assert!(err.panicked);
+    assert_eq!(err.attempts, 1);
+    assert_eq!(err.error, "panicked: index out of bounds");
+}
@@ -0,0 +1,189 @@
+//! Per frame metrics of the reconstructed surfaces written to a CSV file, see `--measure-csv` and `--measure-only`
+//!
+//! The CSV file starts with the header [`CSV_HEADER`] followed by one row per successfully reconstructed input
+//! file in the order in which the reconstructions finished. The columns are:
+//!  - `input_file`: path of the input file as given on the command line (quoted if it contains commas or quotes)
+//!  - `time`: physical time of the frame of a sequence (see `--time-regex` and `--time-step`), empty otherwise
+//!  - `num_particles`: number of particles used for the reconstruction (after the particle validation)
+//!  - `num_splash_particles`: number of particles classified as splash particles
+//!  - `num_vertices`, `num_triangles`: size of the output mesh
+//!  - `surface_area`: total area of the triangles of the output mesh
+//!  - `volume`: volume enclosed by the output mesh (only meaningful for closed meshes)
+//!
+//! The metrics refer to the output mesh, i.e. they include merged splash particle spheres and are computed after
+//! cutting the surface to the region of interest. If the CSV file already exists, its rows are kept and input
+//! files that already have a row are not reconstructed again, so an interrupted run can be resumed.
+
+use super::FrameSummary;
+use anyhow::{anyhow, Context};
+use splashsurf_lib::mesh::TriMesh3d;
+use splashsurf_lib::Real;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Header of the CSV file, has to stay stable for scripts that parse the file
+pub(crate) const CSV_HEADER: &str = "input_file,time,num_particles,num_splash_particles,num_vertices,num_triangles,surface_area,volume";
+
+/// Size and geometric metrics of an output mesh
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub(crate) struct SurfaceMetrics {
+    pub num_vertices: usize,
+    pub num_triangles: usize,
+    pub surface_area: f64,
+    pub volume: f64,
+}
+
+impl SurfaceMetrics {
+    /// Computes the metrics of the given mesh
+    pub(crate) fn of_mesh<R: Real>(mesh: &TriMesh3d<R>) -> Self {
+        Self {
+            num_vertices: mesh.vertices.len(),
+            num_triangles: mesh.triangles.len(),
+            surface_area: mesh.area().to_f64().unwrap(),
+            volume: mesh.volume().to_f64().unwrap(),
+        }
+    }
+}
+
+/// CSV file that is extended by one row per reconstructed frame
+pub(crate) struct MeasureCsv {
+    path: PathBuf,
+    file: Mutex<fs::File>,
+    /// Input files of the rows that were already present when the file was opened
+    completed: HashSet<String>,
+}
+
+impl MeasureCsv {
+    /// Opens the CSV file for appending rows, creates the file with the header if it does not exist
+    ///
+    /// An incomplete last row (e.g. of an interrupted run) is removed.
+    pub(crate) fn open(path: &Path) -> Result<Self, anyhow::Error> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to read CSV file \"{}\"", path.display()))
+            }
+        };
+
+        // Only rows terminated by a newline are complete
+        let complete_len = content.rfind('\n').map_or(0, |i| i + 1);
+        let mut lines = content[..complete_len].lines();
+        let completed = match lines.next() {
+            Some(header) if header == CSV_HEADER => {
+                lines.filter_map(first_field).collect::<HashSet<_>>()
+            }
+            Some(_) => {
+                return Err(anyhow!(
+                "The CSV file \"{}\" has a different header than expected (\"{}\"), use a new file",
+                path.display(),
+                CSV_HEADER
+            ))
+            }
+            None => HashSet::new(),
+        };
+
+        let open = || -> Result<fs::File, std::io::Error> {
+            if complete_len < content.len() {
+                fs::OpenOptions::new()
+                    .write(true)
+                    .open(path)?
+                    .set_len(complete_len as u64)?;
+            }
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            if complete_len == 0 {
+                writeln!(file, "{}", CSV_HEADER)?;
+            }
+            Ok(file)
+        };
+        let file =
+            open().with_context(|| format!("Failed to open CSV file \"{}\"", path.display()))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+            completed,
+        })
+    }
+
+    /// Returns whether the file already contained a row of the given input file when it was opened
+    pub(crate) fn is_completed(&self, input_file: &Path) -> bool {
+        self.completed
+            .contains(input_file.display().to_string().as_str())
+    }
+
+    /// Appends the row of the given frame to the file
+    pub(crate) fn append(&self, frame: &FrameSummary) -> Result<(), anyhow::Error> {
+        let input_file = frame.input_file.display().to_string();
+        let row = format!(
+            "{},{},{},{},{},{},{},{}\n",
+            csv_field(&input_file),
+            frame.time.map(|time| time.to_string()).unwrap_or_default(),
+            frame.num_particles,
+            frame.num_splash_particles,
+            frame.num_vertices,
+            frame.num_triangles,
+            frame.surface_area,
+            frame.volume
+        );
+
+        // The row is written at once such that rows of frames that are processed in parallel are not interleaved
+        let mut file = self.file.lock().unwrap();
+        file.write_all(row.as_bytes())
+            .and_then(|_| file.flush())
+            .with_context(|| format!("Failed to write to CSV file \"{}\"", self.path.display()))
+    }
+}
+
+/// Quotes a field of a CSV row if required
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Returns the unquoted first field of a CSV row
+fn first_field(row: &str) -> Option<String> {
+    match row.strip_prefix('"') {
+        Some(quoted) => {
+            let mut field = String::new();
+            let mut chars = quoted.chars().peekable();
+            while let Some(c) = chars.next() {
+                if c == '"' {
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                    } else {
+                        return Some(field);
+                    }
+                }
+                field.push(c);
+            }
+            None
+        }
+        None => row.split(',').next().map(str::to_string),
+    }
+}
+
+#[test]
+fn test_csv_fields() {
+    for value in [
+        "frame_1.vtk",
+        "dir, with comma/frame.vtk",
+        "\"quoted\".vtk",
+        "",
+    ] {
+        let row = format!("{},1.0,42", csv_field(value));
+        assert_eq!(first_field(&row).as_deref(), Some(value));
+    }
+    assert_eq!(first_field("\"unterminated,1.0"), None);
+}
@@ -0,0 +1,177 @@
+//! Handling of existing output files, see `--skip-existing`, `--overwrite` and `--force`
+
+use super::ReconstructSubcommandArgs;
+use crate::io;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// How input files with an existing output file are handled
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct OutputPolicy {
+    /// Skip input files with an output file that is newer than the input file
+    pub skip_existing: bool,
+    /// Allow to overwrite existing output files
+    pub overwrite: bool,
+    /// Reconstruct and overwrite all output files, even if they are up-to-date
+    pub force: bool,
+}
+
+impl From<&ReconstructSubcommandArgs> for OutputPolicy {
+    fn from(args: &ReconstructSubcommandArgs) -> Self {
+        Self {
+            skip_existing: args.skip_existing,
+            overwrite: args.overwrite,
+            force: args.force,
+        }
+    }
+}
+
+/// What to do with an input file depending on the state of its output file
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum FrameAction {
+    /// Reconstruct the input file, the output file does not exist yet
+    Process,
+    /// Reconstruct the input file and overwrite the existing output file
+    Overwrite,
+    /// Skip the input file because its output file is newer than the input file
+    SkipUpToDate,
+    /// Do not process the input file because its existing output file may not be overwritten
+    KeepExisting,
+}
+
+impl FrameAction {
+    /// Returns whether the input file has to be reconstructed
+    pub(crate) fn is_processed(&self) -> bool {
+        matches!(self, FrameAction::Process | FrameAction::Overwrite)
+    }
+}
+
+impl OutputPolicy {
+    /// Decides what to do with an input file based on the modification times of the input and output file (`None` if the file does not exist)
+    pub(crate) fn frame_action(
+        &self,
+        input_modified: Option<SystemTime>,
+        output_modified: Option<SystemTime>,
+    ) -> FrameAction {
+        let output_modified = match output_modified {
+            Some(output_modified) => output_modified,
+            None => return FrameAction::Process,
+        };
+
+        if self.force {
+            return FrameAction::Overwrite;
+        }
+
+        let is_up_to_date =
+            input_modified.is_some_and(|input_modified| output_modified >= input_modified);
+        if self.skip_existing && is_up_to_date {
+            FrameAction::SkipUpToDate
+        } else if self.overwrite {
+            FrameAction::Overwrite
+        } else {
+            FrameAction::KeepExisting
+        }
+    }
+
+    /// Decides what to do with the given input file based on the modification times of the files on disk, see [`OutputPolicy::frame_action`]
+    pub(crate) fn frame_action_for_files(
+        &self,
+        input_file: &Path,
+        output_file: &Path,
+    ) -> FrameAction {
+        // Writing to stdout never replaces an existing file
+        if io::is_stdio(output_file) {
+            return FrameAction::Process;
+        }
+
+        let modified = |path: &Path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        };
+        // The existence of the output file is checked separately because the modification time may not be available on every platform
+        let output_modified = if output_file.exists() {
+            Some(modified(output_file).unwrap_or(SystemTime::UNIX_EPOCH))
+        } else {
+            None
+        };
+        self.frame_action(modified(input_file), output_modified)
+    }
+}
+
+/// Number of input files by the way they were handled
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct FrameCounts {
+    pub processed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// Input files that were not processed because an earlier input file failed with `--on-error=abort`
+    pub aborted: usize,
+}
+
+#[test]
+fn test_frame_action() {
+    use std::time::Duration;
+
+    let older = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1000));
+    let newer = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(2000));
+    let policy = |skip_existing, overwrite, force| OutputPolicy {
+        skip_existing,
+        overwrite,
+        force,
+    };
+
+    // Missing output files are always processed
+    for (skip_existing, overwrite, force) in [
+        (false, false, false),
+        (true, false, false),
+        (false, true, false),
+        (true, true, true),
+    ] {
+        assert_eq!(
+            policy(skip_existing, overwrite, force).frame_action(older, None),
+            FrameAction::Process
+        );
+    }
+
+    // Existing output files are kept by default
+    assert_eq!(
+        policy(false, false, false).frame_action(older, newer),
+        FrameAction::KeepExisting
+    );
+    assert_eq!(
+        policy(false, true, false).frame_action(older, newer),
+        FrameAction::Overwrite
+    );
+
+    // Only up-to-date output files are skipped, outdated ones have to be overwritten explicitly
+    assert_eq!(
+        policy(true, false, false).frame_action(older, newer),
+        FrameAction::SkipUpToDate
+    );
+    assert_eq!(
+        policy(true, false, false).frame_action(older, older),
+        FrameAction::SkipUpToDate
+    );
+    assert_eq!(
+        policy(true, false, false).frame_action(newer, older),
+        FrameAction::KeepExisting
+    );
+    assert_eq!(
+        policy(true, true, false).frame_action(newer, older),
+        FrameAction::Overwrite
+    );
+    assert_eq!(
+        policy(true, false, false).frame_action(None, newer),
+        FrameAction::KeepExisting
+    );
+
+    // Forced reconstructions overwrite everything
+    assert_eq!(
+        policy(true, false, true).frame_action(older, newer),
+        FrameAction::Overwrite
+    );
+    assert!(FrameAction::Overwrite.is_processed());
+    assert!(!FrameAction::SkipUpToDate.is_processed());
+}
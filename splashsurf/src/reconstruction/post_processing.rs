@@ -0,0 +1,248 @@
+//! Post-processing of the reconstructed surface mesh, see `--mesh-cleanup`, `--remove-components`, `--smoothing-iters` and `--decimate-target`
+//!
+//! The steps are always applied in the same order, independent of the order of the arguments on the command line:
+//!  1. cleanup: welding of vertices closer than the weld tolerance and removal of degenerate triangles
+//!  2. removal of connected components with less than the given number of triangles
+//!  3. Laplacian or Taubin smoothing
+//!  4. decimation by edge collapses to the target number of triangles
+//!
+//! The post-processing is applied directly after the reconstruction, i.e. before the normals and the attributes are
+//! computed at the vertices of the mesh, such that all attributes of the output mesh are consistent with its vertices.
+
+use log::info;
+use splashsurf_lib::mesh::{decimation, smoothing, TriMesh3d};
+use splashsurf_lib::{profile, Real};
+use structopt::clap::arg_enum;
+
+arg_enum! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub enum SmoothingMethod {
+        Laplacian,
+        Taubin
+    }
+}
+
+/// Pass-band frequency of the Taubin smoothing used to compute the inflating weight `mu` from the smoothing weight `lambda`
+const TAUBIN_PASS_BAND: f64 = 0.1;
+
+/// Parameters of the welding and degenerate triangle removal, see `--mesh-cleanup`
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct MeshCleanup {
+    /// Maximum distance of vertices that are merged (absolute)
+    pub weld_tolerance: f64,
+    /// Area below which triangles are removed (absolute)
+    pub area_epsilon: f64,
+}
+
+/// Parameters of the smoothing of the surface meshes, see `--smoothing-iters`
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct MeshSmoothing {
+    pub method: SmoothingMethod,
+    pub iterations: usize,
+    pub weight: f64,
+    /// Whether vertices on boundary edges are kept fixed
+    pub keep_boundary: bool,
+}
+
+/// All steps of the mesh post-processing, disabled steps are `None`
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct MeshPostProcessing {
+    pub cleanup: Option<MeshCleanup>,
+    /// Minimum number of triangles of the connected components that are kept
+    pub min_component_triangles: Option<usize>,
+    pub smoothing: Option<MeshSmoothing>,
+    /// Maximum number of triangles after the decimation
+    pub decimate_target: Option<usize>,
+}
+
+/// Number of vertices and triangles of the surface mesh before and after a step of the post-processing
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PostProcessingStep {
+    pub name: &'static str,
+    pub vertices_before: usize,
+    pub triangles_before: usize,
+    pub vertices_after: usize,
+    pub triangles_after: usize,
+}
+
+impl MeshPostProcessing {
+    /// Applies all enabled steps to the mesh in the fixed order, returns the vertex and triangle counts of every applied step
+    pub(crate) fn apply<R: Real>(
+        &self,
+        mesh: &mut TriMesh3d<R>,
+        parallel: bool,
+    ) -> Vec<PostProcessingStep> {
+        let mut steps = Vec::new();
+
+        if let Some(cleanup) = &self.cleanup {
+            apply_step(&mut steps, "cleanup", mesh, |mesh| {
+                profile!("mesh cleanup");
+                let welded = mesh.weld_vertices(R::from_f64(cleanup.weld_tolerance).unwrap());
+                let removed =
+                    mesh.remove_degenerate_triangles(R::from_f64(cleanup.area_epsilon).unwrap());
+                info!(
+                    "Merged {} vertices with a distance of at most {} (removing {} collapsed triangles) and removed {} degenerate triangles ({} with repeated vertices, {} with an area of at most {}) and {} unreferenced vertices from the surface mesh.",
+                    welded.merged_vertices,
+                    cleanup.weld_tolerance,
+                    welded.degenerate_triangles,
+                    removed.triangles(),
+                    removed.repeated_vertices,
+                    removed.small_area,
+                    cleanup.area_epsilon,
+                    removed.unreferenced_vertices
+                );
+            });
+        }
+
+        if let Some(min_component_triangles) = self.min_component_triangles {
+            apply_step(&mut steps, "remove_components", mesh, |mesh| {
+                profile!("remove small components");
+                let removed = mesh.remove_small_components(min_component_triangles);
+                info!(
+                    "Removed {} connected components with a total of {} triangles from the surface mesh.",
+                    removed.components, removed.triangles
+                );
+            });
+        }
+
+        if let Some(mesh_smoothing) = &self.smoothing {
+            apply_step(&mut steps, "smoothing", mesh, |mesh| {
+                profile!("mesh smoothing");
+                mesh_smoothing.apply(mesh, parallel);
+            });
+        }
+
+        if let Some(decimate_target) = self.decimate_target {
+            apply_step(&mut steps, "decimation", mesh, |mesh| {
+                profile!("mesh decimation");
+                let decimation_map = decimation::decimate(mesh, decimate_target);
+                info!(
+                    "Decimated the surface mesh to {} triangles (target: {}).",
+                    decimation_map.triangles.len(),
+                    decimate_target
+                );
+            });
+        }
+
+        steps
+    }
+}
+
+impl MeshSmoothing {
+    /// Smoothes the vertices of the mesh with the selected method
+    fn apply<R: Real>(&self, mesh: &mut TriMesh3d<R>, parallel: bool) {
+        let lambda = R::from_f64(self.weight).unwrap();
+        match self.method {
+            SmoothingMethod::Laplacian => {
+                if parallel {
+                    smoothing::par_laplacian_smoothing(
+                        mesh,
+                        self.iterations,
+                        lambda,
+                        self.keep_boundary,
+                    );
+                } else {
+                    smoothing::laplacian_smoothing(
+                        mesh,
+                        self.iterations,
+                        lambda,
+                        self.keep_boundary,
+                    );
+                }
+            }
+            SmoothingMethod::Taubin => {
+                // The inflating weight follows from 1/lambda + 1/mu = pass-band frequency
+                let mu = R::from_f64(1.0 / (TAUBIN_PASS_BAND - 1.0 / self.weight)).unwrap();
+                let vertex_mask = self.keep_boundary.then(|| {
+                    smoothing::boundary_vertices(mesh)
+                        .into_iter()
+                        .map(|is_boundary| !is_boundary)
+                        .collect::<Vec<_>>()
+                });
+                if parallel {
+                    smoothing::par_taubin_smoothing(
+                        mesh,
+                        self.iterations,
+                        lambda,
+                        mu,
+                        vertex_mask.as_deref(),
+                    );
+                } else {
+                    smoothing::taubin_smoothing(
+                        mesh,
+                        self.iterations,
+                        lambda,
+                        mu,
+                        vertex_mask.as_deref(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Applies a single step of the post-processing and records the number of vertices and triangles before and after it
+fn apply_step<R: Real>(
+    steps: &mut Vec<PostProcessingStep>,
+    name: &'static str,
+    mesh: &mut TriMesh3d<R>,
+    step: impl FnOnce(&mut TriMesh3d<R>),
+) {
+    let vertices_before = mesh.vertices.len();
+    let triangles_before = mesh.triangles.len();
+    step(mesh);
+    steps.push(PostProcessingStep {
+        name,
+        vertices_before,
+        triangles_before,
+        vertices_after: mesh.vertices.len(),
+        triangles_after: mesh.triangles.len(),
+    });
+}
+
+#[test]
+fn test_post_processing_order() {
+    // Two separate triangles, the second one has collinear vertices
+    let mut mesh = TriMesh3d::<f64> {
+        vertices: vec![
+            [0.0, 0.0, 0.0].into(),
+            [1.0, 0.0, 0.0].into(),
+            [0.0, 1.0, 0.0].into(),
+            [5.0, 0.0, 0.0].into(),
+            [6.0, 0.0, 0.0].into(),
+            [7.0, 0.0, 0.0].into(),
+        ],
+        triangles: vec![[0, 1, 2], [3, 4, 5]],
+    };
+    let post_processing = MeshPostProcessing {
+        cleanup: Some(MeshCleanup {
+            weld_tolerance: 0.0,
+            area_epsilon: 0.0,
+        }),
+        min_component_triangles: Some(2),
+        smoothing: None,
+        decimate_target: None,
+    };
+
+    // The cleanup removes the degenerate triangle before the component removal removes the remaining triangle
+    let steps = post_processing.apply(&mut mesh, false);
+    assert_eq!(
+        steps,
+        vec![
+            PostProcessingStep {
+                name: "cleanup",
+                vertices_before: 6,
+                triangles_before: 2,
+                vertices_after: 3,
+                triangles_after: 1,
+            },
+            PostProcessingStep {
+                name: "remove_components",
+                vertices_before: 3,
+                triangles_before: 1,
+                vertices_after: 0,
+                triangles_after: 0,
+            },
+        ]
+    );
+}
@@ -0,0 +1,276 @@
+//! Physical time of the frames of a sequence (see `--time-regex` and `--time-step`) and ParaView collection output (see `--output-pvd`)
+
+use anyhow::{anyhow, Context};
+use log::{info, warn};
+use regex::Regex;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Determines the physical time of the frames of a sequence from their filenames or their frame index
+#[derive(Clone, Debug)]
+pub(crate) struct TimeExtraction {
+    /// Regex with a single capture group that matches the time in the filename
+    regex: Option<Regex>,
+    /// Time between two consecutive frame indices, used if no regex is given or the parsed times are invalid
+    time_step: f64,
+}
+
+impl TimeExtraction {
+    /// Validates the given regex and time step
+    pub(crate) fn new(regex: Option<&str>, time_step: f64) -> Result<Self, anyhow::Error> {
+        if !(time_step.is_finite() && time_step > 0.0) {
+            return Err(anyhow!(
+                "The time step has to be finite and positive (got {})",
+                time_step
+            ));
+        }
+
+        let regex = regex
+            .map(|regex| {
+                let compiled = Regex::new(regex)
+                    .with_context(|| format!("Invalid time regex \"{}\"", regex))?;
+                // The first capture group is the entire match
+                if compiled.captures_len() != 2 {
+                    return Err(anyhow!(
+                        "The time regex \"{}\" has to contain exactly one capture group (found {})",
+                        regex,
+                        compiled.captures_len() - 1
+                    ));
+                }
+                Ok(compiled)
+            })
+            .transpose()?;
+
+        Ok(Self { regex, time_step })
+    }
+
+    /// Returns the time of the frame with the given index that is used if no time can be parsed from the filename
+    fn fallback_time(&self, frame_index: usize) -> f64 {
+        frame_index as f64 * self.time_step
+    }
+
+    /// Parses the time from the filename of the given file
+    fn parse_time(&self, regex: &Regex, file: &Path) -> Result<f64, String> {
+        let file_name = file
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy())
+            .unwrap_or_default();
+        let captured = regex
+            .captures(&file_name)
+            .and_then(|captures| captures.get(1))
+            .ok_or_else(|| format!("the time regex does not match \"{}\"", file_name))?
+            .as_str();
+
+        match captured.parse::<f64>() {
+            Ok(time) if time.is_finite() => Ok(time),
+            _ => Err(format!(
+                "\"{}\" parsed from \"{}\" is not a valid time",
+                captured, file_name
+            )),
+        }
+    }
+
+    /// Returns the times of all frames of a sequence given by their frame index and file
+    ///
+    /// If the time cannot be parsed from any of the filenames or the parsed times are not strictly increasing,
+    /// a warning is printed and the times of all frames fall back to the frame index times the time step.
+    pub(crate) fn frame_times(&self, frames: &[(usize, &Path)]) -> Vec<f64> {
+        let fallback_times = || {
+            frames
+                .iter()
+                .map(|&(frame_index, _)| self.fallback_time(frame_index))
+                .collect()
+        };
+
+        let regex = match &self.regex {
+            Some(regex) => regex,
+            None => return fallback_times(),
+        };
+
+        let times = match frames
+            .iter()
+            .map(|(_, file)| self.parse_time(regex, file))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(times) => times,
+            Err(err) => {
+                warn!(
+                    "Failed to parse the time of all frames ({}), using the frame index times the time step ({}) instead.",
+                    err, self.time_step
+                );
+                return fallback_times();
+            }
+        };
+
+        if let Some(i) = (1..times.len()).find(|&i| times[i] <= times[i - 1]) {
+            warn!(
+                "The times parsed from the filenames are not strictly increasing ({} of \"{}\" follows {} of \"{}\"), using the frame index times the time step ({}) instead.",
+                times[i],
+                frames[i].1.display(),
+                times[i - 1],
+                frames[i - 1].1.display(),
+                self.time_step
+            );
+            return fallback_times();
+        }
+
+        times
+    }
+}
+
+/// Writes a ParaView collection file (`.pvd`) referencing the given output files at the given times
+///
+/// Files are referenced relative to the directory of the collection file if possible.
+pub(crate) fn write_pvd(pvd_file: &Path, entries: &[(f64, &Path)]) -> Result<(), anyhow::Error> {
+    let pvd_dir = pvd_file.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut pvd = String::new();
+    pvd.push_str("<?xml version=\"1.0\"?>\n");
+    pvd.push_str("<VTKFile type=\"Collection\" version=\"0.1\" byte_order=\"LittleEndian\">\n");
+    pvd.push_str("  <Collection>\n");
+    for (time, file) in entries {
+        let file = file.strip_prefix(pvd_dir).unwrap_or(file);
+        writeln!(
+            pvd,
+            "    <DataSet timestep=\"{}\" group=\"\" part=\"0\" file=\"{}\"/>",
+            time,
+            escape_xml(&file.to_string_lossy())
+        )?;
+    }
+    pvd.push_str("  </Collection>\n");
+    pvd.push_str("</VTKFile>\n");
+
+    info!(
+        "Writing ParaView collection with {} files to \"{}\"...",
+        entries.len(),
+        pvd_file.display()
+    );
+    fs::write(pvd_file, pvd).with_context(|| {
+        format!(
+            "Failed to write ParaView collection to file \"{}\"",
+            pvd_file.display()
+        )
+    })
+}
+
+/// Escapes the characters of the given string that are not allowed in XML attribute values
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[test]
+fn test_frame_times() {
+    fn times(regex: Option<&str>, time_step: f64, files: &[&str]) -> Vec<f64> {
+        let frames: Vec<_> = files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| (i + 1, Path::new(file)))
+            .collect();
+        TimeExtraction::new(regex, time_step)
+            .unwrap()
+            .frame_times(&frames)
+    }
+
+    // Fixed time step per frame index
+    assert_eq!(
+        times(None, 0.5, &["fluid_1.vtk", "fluid_2.vtk", "fluid_3.vtk"]),
+        vec![0.5, 1.0, 1.5]
+    );
+
+    // Decimal time in the filename
+    assert_eq!(
+        times(
+            Some(r"_t([0-9.]+)\.vtk$"),
+            1.0,
+            &["out/fluid_t0.0125.vtk", "out/fluid_t0.025.vtk"]
+        ),
+        vec![0.0125, 0.025]
+    );
+
+    // Scientific notation after the frame number
+    assert_eq!(
+        times(
+            Some(r"time_([-+0-9.eE]+)\.bgeo$"),
+            1.0,
+            &["frame_0001_time_5e-4.bgeo", "frame_0002_time_1.5e-3.bgeo"]
+        ),
+        vec![5e-4, 1.5e-3]
+    );
+
+    // Time with a unit suffix, the directory is not part of the match
+    assert_eq!(
+        times(
+            Some(r"^particles_(\d+(?:\.\d+)?)s\."),
+            1.0,
+            &["run_2.5s/particles_12s.ply", "run_2.5s/particles_12.5s.ply"]
+        ),
+        vec![12.0, 12.5]
+    );
+
+    // Filenames that do not match fall back to the time step for all frames
+    assert_eq!(
+        times(
+            Some(r"_t([0-9.]+)\.vtk$"),
+            0.1,
+            &["fluid_t0.5.vtk", "fluid_1.vtk"]
+        ),
+        vec![0.1, 0.2]
+    );
+
+    // Captured values that are not numbers fall back to the time step for all frames
+    assert_eq!(
+        times(
+            Some(r"_t([0-9.]+)\.vtk$"),
+            2.0,
+            &["fluid_t0.5.vtk", "fluid_t1.2.3.vtk"]
+        ),
+        vec![2.0, 4.0]
+    );
+
+    // Non-monotonic times fall back to the time step for all frames
+    assert_eq!(
+        times(
+            Some(r"_t([0-9.]+)\.vtk$"),
+            1.0,
+            &["fluid_t0.5.vtk", "fluid_t0.25.vtk", "fluid_t0.75.vtk"]
+        ),
+        vec![1.0, 2.0, 3.0]
+    );
+
+    // Invalid regexes and time steps are rejected
+    assert!(TimeExtraction::new(Some(r"_t[0-9.]+\.vtk$"), 1.0).is_err());
+    assert!(TimeExtraction::new(Some(r"_(t)([0-9.]+)\.vtk$"), 1.0).is_err());
+    assert!(TimeExtraction::new(Some(r"_t([0-9.]+\.vtk$"), 1.0).is_err());
+    assert!(TimeExtraction::new(None, 0.0).is_err());
+}
+
+#[test]
+fn test_write_pvd() {
+    let dir = std::env::temp_dir().join("splashsurf_test_write_pvd");
+    fs::create_dir_all(&dir).unwrap();
+    let pvd_file = dir.join("fluid.pvd");
+
+    let first = dir.join("fluid_surface_1.vtk");
+    let second = Path::new("/other/fluid & co.vtk");
+    write_pvd(&pvd_file, &[(0.0125, &first), (0.025, second)]).unwrap();
+
+    let pvd = fs::read_to_string(&pvd_file).unwrap();
+    assert!(pvd.contains(
+        "<DataSet timestep=\"0.0125\" group=\"\" part=\"0\" file=\"fluid_surface_1.vtk\"/>"
+    ));
+    assert!(pvd.contains(
+        "<DataSet timestep=\"0.025\" group=\"\" part=\"0\" file=\"/other/fluid &amp; co.vtk\"/>"
+    ));
+}
@@ -0,0 +1,448 @@
+//! Watch mode of the `reconstruct` subcommand, see `--watch`
+//!
+//! The watched directory is polled for new particle files. A file is only reconstructed once its size did not
+//! change between two polls (and is non-zero) to avoid reading files that are still being written by the
+//! simulation. Files with an output file that is newer than the input file are always skipped, so a watch can be
+//! restarted without reconstructing the frames that were already processed.
+
+use super::arguments::{ReconstructionRunnerArgs, ReconstructionRunnerPaths};
+use super::error_policy::{self, FramesFailed};
+use super::output_policy::{FrameAction, FrameCounts, OutputPolicy};
+use super::{
+    reconstruction_pipeline_persistent, PersistentReconstruction, ReconstructSubcommandArgs,
+};
+use anyhow::{anyhow, Context};
+use log::{error, info, warn};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// File extensions of the particle formats that are picked up when watching a directory
+const PARTICLE_EXTENSIONS: [&str; 5] = ["vtk", "xyz", "ply", "bgeo", "json"];
+
+/// Set by the Ctrl-C handler to stop watching after the frame that is currently reconstructed
+static STOP: AtomicBool = AtomicBool::new(false);
+
+/// Installs the Ctrl-C handler that sets [`STOP`]
+///
+/// A handler can only be installed once per process, so later calls (e.g. of another watch) keep the installed handler.
+fn install_ctrlc_handler() -> Result<(), anyhow::Error> {
+    static INSTALLED: Mutex<bool> = Mutex::new(false);
+
+    let mut installed = INSTALLED.lock().unwrap();
+    if !*installed {
+        ctrlc::set_handler(|| {
+            info!("Received Ctrl-C, stopping after the frame that is currently reconstructed...");
+            STOP.store(true, Ordering::SeqCst);
+        })
+        .context("Failed to install the Ctrl-C handler")?;
+        *installed = true;
+    }
+    Ok(())
+}
+
+/// Files that are watched for new particle data
+#[derive(Clone, Debug)]
+pub(crate) enum WatchTarget {
+    /// All particle files in a directory except for the surface meshes written by the watch mode (`{stem}_surface.vtk`)
+    Directory(PathBuf),
+    /// All files in a directory matching a filename pattern with a `{}` placeholder for the frame number
+    Pattern {
+        dir: PathBuf,
+        prefix: String,
+        suffix: String,
+    },
+}
+
+impl WatchTarget {
+    /// Interprets the given path as directory if it is an existing directory, otherwise as a filename pattern
+    pub(crate) fn new(path: &Path) -> Result<Self, anyhow::Error> {
+        if path.is_dir() {
+            return Ok(WatchTarget::Directory(path.to_path_buf()));
+        }
+
+        let filename = path
+            .file_name()
+            .ok_or_else(|| {
+                anyhow!(
+                    "The watch path \"{}\" does not end with a filename",
+                    path.display()
+                )
+            })?
+            .to_string_lossy();
+        let (prefix, suffix) = filename.split_once("{}").ok_or_else(|| {
+            anyhow!(
+                "The watch path \"{}\" is neither a directory nor a pattern with a place holder \"{{}}\"",
+                path.display()
+            )
+        })?;
+
+        let dir = match path.parent() {
+            Some(dir) if dir != Path::new("") => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        if !dir.is_dir() {
+            return Err(anyhow!(
+                "The watched directory \"{}\" does not exist",
+                dir.display()
+            ));
+        }
+
+        Ok(WatchTarget::Pattern {
+            dir,
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+        })
+    }
+
+    /// Returns the watched directory
+    fn dir(&self) -> &Path {
+        match self {
+            WatchTarget::Directory(dir) => dir,
+            WatchTarget::Pattern { dir, .. } => dir,
+        }
+    }
+
+    /// Returns the frame number if the filename matches a pattern, `Some(None)` for other matching files and `None` if the file should not be processed
+    fn matches(&self, filename: &str) -> Option<Option<u64>> {
+        match self {
+            WatchTarget::Directory(_) => {
+                let path = Path::new(filename);
+                let stem = path.file_stem()?.to_string_lossy();
+                let extension = path.extension()?.to_string_lossy().to_lowercase();
+                let is_output = stem.ends_with("_surface") || stem.ends_with("_surface_splash");
+                (PARTICLE_EXTENSIONS.contains(&extension.as_str()) && !is_output).then_some(None)
+            }
+            WatchTarget::Pattern { prefix, suffix, .. } => {
+                let frame = filename.strip_prefix(prefix.as_str())?;
+                let frame = frame.strip_suffix(suffix.as_str())?;
+                if frame.is_empty() || !frame.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                frame.parse().ok().map(Some)
+            }
+        }
+    }
+
+    /// Returns all files matching the target sorted by their frame number (or filename)
+    fn find_files(&self) -> Result<Vec<PathBuf>, anyhow::Error> {
+        let entries = fs::read_dir(self.dir()).with_context(|| {
+            format!(
+                "Failed to read the watched directory \"{}\"",
+                self.dir().display()
+            )
+        })?;
+
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+            let filename = path.file_name().unwrap().to_string_lossy().into_owned();
+            if let Some(frame) = self.matches(&filename) {
+                files.push((frame, filename, path));
+            }
+        }
+        files.sort();
+
+        Ok(files.into_iter().map(|(_, _, path)| path).collect())
+    }
+
+    /// Returns the path of the surface mesh of the given input file
+    ///
+    /// Like for input sequences, the placeholder of a pattern is replaced by `surface_{}`, e.g. `fluid_12.bgeo` is
+    /// reconstructed to `fluid_surface_12.vtk`. Files of a watched directory are reconstructed to `{stem}_surface.vtk`.
+    fn output_file(&self, input_file: &Path, output_dir: Option<&Path>) -> PathBuf {
+        let filename = input_file.file_name().unwrap().to_string_lossy();
+        let output_filename = match self {
+            WatchTarget::Directory(_) => format!(
+                "{}_surface.vtk",
+                input_file.file_stem().unwrap().to_string_lossy()
+            ),
+            WatchTarget::Pattern { prefix, suffix, .. } => {
+                let frame = &filename[prefix.len()..filename.len() - suffix.len()];
+                let pattern = format!("{}{{}}{}", prefix, suffix);
+                let pattern_stem = Path::new(&pattern).file_stem().unwrap().to_string_lossy();
+                format!(
+                    "{}.vtk",
+                    pattern_stem.replace("{}", &format!("surface_{}", frame))
+                )
+            }
+        };
+
+        output_dir
+            .unwrap_or_else(|| self.dir())
+            .join(output_filename)
+    }
+}
+
+/// Executes the `reconstruct` subcommand in watch mode until it is interrupted with Ctrl-C
+pub(crate) fn watch_subcommand(
+    cmd_args: &ReconstructSubcommandArgs,
+    watch_path: &Path,
+) -> Result<(), anyhow::Error> {
+    let target = WatchTarget::new(watch_path)?;
+    let args = ReconstructionRunnerArgs::try_from(cmd_args)
+        .context("Failed processing parameters from command line")?;
+
+    if !(cmd_args.watch_interval.is_finite() && cmd_args.watch_interval > 0.0) {
+        return Err(anyhow!(
+            "The watch interval has to be a finite, positive number of seconds (got {})",
+            cmd_args.watch_interval
+        ));
+    }
+    let poll_interval = Duration::from_secs_f64(cmd_args.watch_interval);
+
+    if let Some(output_dir) = &cmd_args.output_dir {
+        fs::create_dir_all(output_dir).with_context(|| {
+            format!(
+                "Unable to create output directory \"{}\"",
+                output_dir.display()
+            )
+        })?;
+    }
+
+    install_ctrlc_handler()?;
+    STOP.store(false, Ordering::SeqCst);
+
+    info!(
+        "Watching \"{}\" for new particle files (stop with Ctrl-C)...",
+        watch_path.display()
+    );
+
+    let summary = if let Some(thread_pool) = &args.thread_pool {
+        let thread_pool = thread_pool
+            .build_local()
+            .context("Failed to build the thread pool")?;
+        thread_pool.install(|| watch(&target, cmd_args, &args, poll_interval, &STOP))
+    } else {
+        watch(&target, cmd_args, &args, poll_interval, &STOP)
+    }?;
+
+    info!(
+        "Stopped watching: {} files processed, {} skipped, {} failed",
+        summary.processed, summary.skipped, summary.failed
+    );
+    if summary.failed > 0 {
+        return Err(FramesFailed {
+            num_failed: summary.failed,
+            num_frames: summary.processed + summary.skipped + summary.failed,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Polls the target for new files and reconstructs every file once its size is stable until `stop` is set
+///
+/// All files are reconstructed with the same persistent reconstruction. Failing files are handled according to
+/// `--on-error`, i.e. with `--on-error=abort` the watch stops after the first failing file.
+pub(crate) fn watch(
+    target: &WatchTarget,
+    cmd_args: &ReconstructSubcommandArgs,
+    args: &ReconstructionRunnerArgs,
+    poll_interval: Duration,
+    stop: &AtomicBool,
+) -> Result<FrameCounts, anyhow::Error> {
+    let mut summary = FrameCounts::default();
+    // Up-to-date output files of a previous watch are always skipped
+    let output_policy = OutputPolicy {
+        skip_existing: true,
+        ..OutputPolicy::from(cmd_args)
+    };
+    // Sizes of all unprocessed files at the last poll
+    let mut file_sizes: HashMap<PathBuf, u64> = HashMap::new();
+    let mut handled_files: HashSet<PathBuf> = HashSet::new();
+    // Reused by all files to avoid reallocating the workspace of the reconstruction for every frame
+    let persistent = RefCell::new(PersistentReconstruction::default());
+    let mut aborted = false;
+
+    while !stop.load(Ordering::SeqCst) && !aborted {
+        for input_file in target.find_files()? {
+            if stop.load(Ordering::SeqCst) {
+                break;
+            }
+            if handled_files.contains(&input_file) {
+                continue;
+            }
+
+            // Wait until the size of the file did not change since the last poll
+            let size = match fs::metadata(&input_file) {
+                Ok(metadata) => metadata.len(),
+                Err(_) => continue,
+            };
+            let previous_size = file_sizes.insert(input_file.clone(), size);
+            if size == 0 || previous_size != Some(size) {
+                continue;
+            }
+            file_sizes.remove(&input_file);
+            handled_files.insert(input_file.clone());
+
+            let output_file = target.output_file(&input_file, cmd_args.output_dir.as_deref());
+            match output_policy.frame_action_for_files(&input_file, &output_file) {
+                FrameAction::Process | FrameAction::Overwrite => {}
+                FrameAction::SkipUpToDate => {
+                    info!(
+                        "Skipping \"{}\", the output file \"{}\" is newer than the input file.",
+                        input_file.display(),
+                        output_file.display()
+                    );
+                    summary.skipped += 1;
+                    continue;
+                }
+                FrameAction::KeepExisting => {
+                    error!(
+                        "Not processing \"{}\", the outdated output file \"{}\" already exists (use --overwrite to replace it).",
+                        input_file.display(),
+                        output_file.display()
+                    );
+                    summary.failed += 1;
+                    continue;
+                }
+            }
+
+            let paths = ReconstructionRunnerPaths::new(
+                input_file.clone(),
+                output_file,
+                args.config.normals,
+                args.config.sph_normals,
+                cmd_args.interpolate_attributes.clone(),
+            );
+            let result = error_policy::run_frame(cmd_args.on_error, &paths, || {
+                reconstruction_pipeline_persistent(
+                    &paths,
+                    args,
+                    Some(&mut *persistent.borrow_mut()),
+                )
+            });
+            match result {
+                Ok(_) => summary.processed += 1,
+                Err(_) => {
+                    summary.failed += 1;
+                    if cmd_args.on_error.aborts() {
+                        error!("Stopped watching after the failed file (use --on-error=skip to keep watching).");
+                        aborted = true;
+                        break;
+                    }
+                }
+            }
+        }
+
+        // Sleep in short steps to react to Ctrl-C while waiting
+        let start = Instant::now();
+        while !stop.load(Ordering::SeqCst) && !aborted && start.elapsed() < poll_interval {
+            std::thread::sleep(poll_interval.min(Duration::from_millis(50)));
+        }
+    }
+
+    if !file_sizes.is_empty() {
+        warn!(
+            "{} files were not reconstructed because they were still being written",
+            file_sizes.len()
+        );
+    }
+
+    Ok(summary)
+}
+
+#[test]
+fn test_watch_target_matches() {
+    let target = WatchTarget::new(Path::new("fluid_{}.bgeo")).unwrap();
+    assert_eq!(target.matches("fluid_12.bgeo"), Some(Some(12)));
+    assert_eq!(target.matches("fluid_.bgeo"), None);
+    assert_eq!(target.matches("fluid_1a.bgeo"), None);
+    assert_eq!(target.matches("fluid_surface_12.vtk"), None);
+    assert_eq!(
+        target.output_file(Path::new("fluid_12.bgeo"), Some(Path::new("out"))),
+        Path::new("out/fluid_surface_12.vtk")
+    );
+
+    let target = WatchTarget::Directory(PathBuf::from("."));
+    assert_eq!(target.matches("fluid.vtk"), Some(None));
+    assert_eq!(target.matches("fluid_surface.vtk"), None);
+    assert_eq!(target.matches("notes.txt"), None);
+}
+
+#[test]
+fn test_watch_directory() {
+    use structopt::StructOpt;
+
+    let input_file = crate::io::test_cube_particles_file();
+    let watch_dir = std::env::temp_dir().join("splashsurf_test_watch");
+    let _ = fs::remove_dir_all(&watch_dir);
+    fs::create_dir_all(&watch_dir).unwrap();
+    fs::copy(input_file, watch_dir.join("cube.vtk")).unwrap();
+
+    let cmd_args = ReconstructSubcommandArgs::from_iter_safe(&[
+        "reconstruct",
+        "--watch",
+        watch_dir.to_str().unwrap(),
+        "--particle-radius=0.025",
+        "--smoothing-length=2.0",
+        "--cube-size=0.5",
+    ])
+    .unwrap();
+    let args = ReconstructionRunnerArgs::try_from(&cmd_args).unwrap();
+    let target = WatchTarget::new(&watch_dir).unwrap();
+    let output_file = watch_dir.join("cube_surface.vtk");
+
+    // Stops the watch as soon as the output file was written (or after a timeout)
+    let run_watch = || {
+        let stop = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            let watcher =
+                scope.spawn(|| watch(&target, &cmd_args, &args, Duration::from_millis(50), &stop));
+            let start = Instant::now();
+            while !(output_file.exists() && start.elapsed() > Duration::from_millis(500))
+                && start.elapsed() < Duration::from_secs(60)
+            {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            stop.store(true, Ordering::SeqCst);
+            watcher.join().unwrap().unwrap()
+        })
+    };
+
+    let summary = run_watch();
+    assert!(output_file.exists());
+    assert_eq!(
+        summary,
+        FrameCounts {
+            processed: 1,
+            skipped: 0,
+            failed: 0,
+            aborted: 0
+        }
+    );
+
+    // The output of the first watch is skipped after a restart
+    let summary = run_watch();
+    assert_eq!(
+        summary,
+        FrameCounts {
+            processed: 0,
+            skipped: 1,
+            failed: 0,
+            aborted: 0
+        }
+    );
+
+    // With the default `--on-error=abort` the watch stops at the first failing file
+    fs::write(watch_dir.join("broken.vtk"), "not a vtk file").unwrap();
+    let summary = run_watch();
+    assert_eq!(
+        summary,
+        FrameCounts {
+            processed: 0,
+            skipped: 0,
+            failed: 1,
+            aborted: 0
+        }
+    );
+}
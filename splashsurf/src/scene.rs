@@ -0,0 +1,144 @@
+//! Default reconstruction parameters extracted from SPlisHSPlasH scene files, see `--scene` of the `reconstruct` subcommand
+//!
+//! Only the subset of the scene that is relevant for the reconstruction is deserialized, all other fields of the
+//! scene are ignored. The particle radius is read from `Configuration.particleRadius` and the rest density from
+//! the `density0` of the fluid materials (or `Configuration.density0` of older scene files). If the scene specifies
+//! a kernel support radius (`Configuration.supportRadius`), it is converted to the corresponding smoothing length.
+
+use crate::config::ProfileParameters;
+use anyhow::{anyhow, Context};
+use log::warn;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Subset of a SPlisHSPlasH scene file that provides default values for the reconstruction
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+struct SceneFile {
+    configuration: SceneConfiguration,
+    materials: Vec<SceneMaterial>,
+}
+
+/// Global simulation parameters of a scene (`"Configuration"` block)
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct SceneConfiguration {
+    particle_radius: Option<f64>,
+    support_radius: Option<f64>,
+    density0: Option<f64>,
+}
+
+/// Parameters of a fluid material of a scene (entries of the `"Materials"` block)
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct SceneMaterial {
+    id: Option<String>,
+    density0: Option<f64>,
+}
+
+impl SceneFile {
+    /// Converts the scene values to reconstruction parameters, unspecified values are `None`
+    fn to_parameters(&self) -> Result<ProfileParameters, anyhow::Error> {
+        let configuration = &self.configuration;
+        if let Some(particle_radius) = configuration.particle_radius {
+            if !(particle_radius.is_finite() && particle_radius > 0.0) {
+                return Err(anyhow!(
+                    "The particle radius of the scene has to be positive (got {})",
+                    particle_radius
+                ));
+            }
+        }
+
+        // The rest density of the materials takes precedence over the global value
+        let mut material_densities = self
+            .materials
+            .iter()
+            .filter_map(|material| Some((material.id.as_deref(), material.density0?)));
+        let rest_density = match material_densities.next() {
+            Some((id, density)) => {
+                if material_densities.any(|(_, other)| other != density) {
+                    warn!(
+                        "The scene contains materials with different rest densities, using the rest density {} of material \"{}\".",
+                        density,
+                        id.unwrap_or("unnamed")
+                    );
+                }
+                Some(density)
+            }
+            None => configuration.density0,
+        };
+
+        // The smoothing length is given relative to the particle radius and the support radius is twice the smoothing length
+        let smoothing_length = match (configuration.support_radius, configuration.particle_radius) {
+            (Some(support_radius), Some(particle_radius)) => {
+                Some(support_radius / (2.0 * particle_radius))
+            }
+            (Some(_), None) => {
+                warn!("The scene specifies a support radius but no particle radius, ignoring the support radius.");
+                None
+            }
+            (None, _) => None,
+        };
+
+        Ok(ProfileParameters {
+            particle_radius: configuration.particle_radius,
+            rest_density,
+            smoothing_length,
+            ..ProfileParameters::default()
+        })
+    }
+}
+
+/// Parses the contents of a scene file and returns the reconstruction parameters specified by the scene
+fn parse_scene(content: &str) -> Result<ProfileParameters, anyhow::Error> {
+    let scene: SceneFile = serde_json::from_str(content)?;
+    scene.to_parameters()
+}
+
+/// Loads the reconstruction parameters specified by the given SPlisHSPlasH scene file
+pub(crate) fn load_scene(scene_file: &Path) -> Result<ProfileParameters, anyhow::Error> {
+    let content = fs::read_to_string(scene_file)
+        .with_context(|| format!("Failed to read scene file \"{}\"", scene_file.display()))?;
+    parse_scene(&content)
+        .with_context(|| format!("Failed to parse scene file \"{}\"", scene_file.display()))
+}
+
+#[test]
+fn test_load_scene() {
+    let scene_file = Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../data/splishsplash_dam_break_scene.json"
+    ));
+    let parameters = load_scene(scene_file).unwrap();
+
+    assert_eq!(parameters.particle_radius, Some(0.025));
+    // The density of the fluid material overrides the global value
+    assert_eq!(parameters.rest_density, Some(998.0));
+    assert_eq!(parameters.smoothing_length, None);
+    assert_eq!(parameters.cube_size, None);
+
+    assert!(load_scene(Path::new("missing_scene.json")).is_err());
+}
+
+#[test]
+fn test_parse_scene() {
+    // Support radius and global rest density, unknown fields are ignored
+    let parameters = parse_scene(
+        r#"{
+            "Configuration": { "particleRadius": 0.01, "supportRadius": 0.04, "density0": 1200, "timeStepSize": 0.001 },
+            "Materials": [{ "id": "Fluid", "viscosity": 0.01 }],
+            "FluidModels": []
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(parameters.particle_radius, Some(0.01));
+    assert_eq!(parameters.rest_density, Some(1200.0));
+    assert_eq!(parameters.smoothing_length, Some(2.0));
+
+    // An empty scene does not specify any parameters
+    assert_eq!(parse_scene("{}").unwrap(), ProfileParameters::default());
+
+    assert!(parse_scene(r#"{ "Configuration": { "particleRadius": -0.025 } }"#).is_err());
+    assert!(parse_scene(r#"{ "Configuration": { "particleRadius": "large" } }"#).is_err());
+}
@@ -0,0 +1,106 @@
+use splashsurf_lib::io::vtk_format::{particles_to_vtk, surface_mesh_from_vtk, VtkFile};
+use splashsurf_lib::nalgebra::Vector3;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Returns a path for a test file in a temporary directory that is unique for the test
+fn test_file(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "splashsurf_test_mesh_post_processing_{}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+/// Returns the particles of a cube of 10x10x10 particles with the given particle radius
+fn cube_particles(particle_radius: f64) -> Vec<Vector3<f64>> {
+    let mut particles = Vec::new();
+    for i in 0..10 {
+        for j in 0..10 {
+            for k in 0..10 {
+                particles.push(Vector3::new(i as f64, j as f64, k as f64) * 2.0 * particle_radius);
+            }
+        }
+    }
+    particles
+}
+
+#[test]
+fn test_reconstruct_with_all_post_processing_flags() {
+    let input_file = test_file("cube.vtk");
+    let output_file = test_file("surface.vtk");
+    let summary_file = test_file("summary.json");
+    particles_to_vtk(&cube_particles(0.025), &input_file).unwrap();
+
+    let decimate_target = 400;
+    let output = Command::new(env!("CARGO_BIN_EXE_splashsurf"))
+        .arg("reconstruct")
+        .arg(&input_file)
+        .arg("-o")
+        .arg(&output_file)
+        .arg(format!("--summary-json={}", summary_file.display()))
+        .args([
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+            "--normals=on",
+            // The post-processing is applied in a fixed order, independent of the order of the arguments
+            &format!("--decimate-target={}", decimate_target),
+            "--smoothing-iters=5",
+            "--smoothing-method=taubin",
+            "--remove-components=10",
+            "--mesh-cleanup",
+            "--check-mesh=on",
+            "--stats",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The output mesh is a closed surface and the normals are consistent with its vertices
+    let mesh = surface_mesh_from_vtk::<f64, _>(&output_file).unwrap();
+    assert!(!mesh.mesh.triangles.is_empty());
+    assert!(mesh.mesh.triangles.len() <= decimate_target);
+    assert!(mesh.mesh.validity_report().is_valid());
+    let normals = VtkFile::load_file(&output_file)
+        .unwrap()
+        .into_pieces()
+        .remove(0)
+        .load_point_attributes::<f64>(&["normals".to_string()])
+        .unwrap()
+        .remove(0);
+    assert_eq!(normals.data.len(), mesh.mesh.vertices.len());
+
+    // The summary lists the vertex and triangle counts of every step in the order in which they were applied
+    let summary: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&summary_file).unwrap()).unwrap();
+    let frame = &summary["frames"][0];
+    let steps = frame["post_processing"].as_array().unwrap();
+    let names: Vec<_> = steps
+        .iter()
+        .map(|step| step["step"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        ["cleanup", "remove_components", "smoothing", "decimation"]
+    );
+    for (step, next_step) in steps.iter().zip(steps.iter().skip(1)) {
+        assert_eq!(step["triangles_after"], next_step["triangles_before"]);
+        assert_eq!(step["vertices_after"], next_step["vertices_before"]);
+    }
+    let smoothing = &steps[2];
+    assert_eq!(smoothing["triangles_before"], smoothing["triangles_after"]);
+    let decimation = &steps[3];
+    assert!(decimation["triangles_after"].as_u64() < decimation["triangles_before"].as_u64());
+    assert_eq!(
+        decimation["triangles_after"].as_u64(),
+        Some(mesh.mesh.triangles.len() as u64)
+    );
+    assert_eq!(frame["num_triangles"], decimation["triangles_after"]);
+}
@@ -0,0 +1,99 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Returns the particles of a cube of 10x10x10 particles with the given particle radius as binary f32 XYZ data
+fn cube_xyz(particle_radius: f32) -> Vec<u8> {
+    let mut xyz = Vec::new();
+    for i in 0..10 {
+        for j in 0..10 {
+            for k in 0..10 {
+                for coord in [i, j, k] {
+                    let x = coord as f32 * 2.0 * particle_radius;
+                    xyz.extend_from_slice(&x.to_ne_bytes());
+                }
+            }
+        }
+    }
+    xyz
+}
+
+/// Runs the `reconstruct` subcommand with the given arguments and stdin, returns its exit status and stdout
+fn reconstruct_with_stdin(args: &[&str], stdin: &[u8]) -> (bool, Vec<u8>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_splashsurf"))
+        .arg("reconstruct")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Errors are ignored because the process may exit before reading its stdin
+    let _ = child.stdin.take().unwrap().write_all(stdin);
+    let output = child.wait_with_output().unwrap();
+    (output.status.success(), output.stdout)
+}
+
+#[test]
+fn test_reconstruct_stdin_to_stdout() {
+    let (success, stdout) = reconstruct_with_stdin(
+        &[
+            "-",
+            "-o",
+            "-",
+            "--input-format=xyz",
+            "--output-format=obj",
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+        ],
+        &cube_xyz(0.025),
+    );
+    assert!(success);
+
+    // The log is written to stderr, i.e. stdout only contains the OBJ file
+    let obj = String::from_utf8(stdout).unwrap();
+    let mut num_vertices = 0;
+    let mut faces = Vec::new();
+    for line in obj.lines() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let coords: Vec<f32> = tokens.map(|t| t.parse().unwrap()).collect();
+                assert_eq!(coords.len(), 3);
+                num_vertices += 1;
+            }
+            Some("f") => faces.push(
+                tokens
+                    .map(|t| t.parse::<usize>().unwrap())
+                    .collect::<Vec<_>>(),
+            ),
+            other => panic!("Unexpected line in OBJ output: {:?}", other),
+        }
+    }
+
+    assert!(num_vertices > 0);
+    assert!(!faces.is_empty());
+    for face in faces {
+        assert_eq!(face.len(), 3);
+        assert!(face.iter().all(|&v| v >= 1 && v <= num_vertices));
+    }
+}
+
+#[test]
+fn test_reconstruct_stdin_requires_format() {
+    // Without an extension the formats have to be given explicitly
+    let (success, stdout) = reconstruct_with_stdin(
+        &[
+            "-",
+            "-o",
+            "-",
+            "--particle-radius=0.025",
+            "--smoothing-length=2.0",
+            "--cube-size=0.5",
+        ],
+        &cube_xyz(0.025),
+    );
+    assert!(!success);
+    assert!(stdout.is_empty());
+}
@@ -0,0 +1,182 @@
+#ifndef SPLASHSURF_H
+#define SPLASHSURF_H
+
+/* Generated with cbindgen from the splashsurf_capi crate, do not edit manually */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+// Status codes returned by the functions of the C API
+typedef enum SplashsurfStatus {
+  // The function completed successfully
+  SPLASHSURF_STATUS_OK = 0,
+  // A required pointer argument is null
+  SPLASHSURF_STATUS_NULL_POINTER = 1,
+  // The reconstruction parameters or the particle positions are invalid
+  SPLASHSURF_STATUS_INVALID_ARGUMENT = 2,
+  // The surface reconstruction failed
+  SPLASHSURF_STATUS_RECONSTRUCTION_FAILED = 3,
+  // The context does not contain a mesh of the requested precision
+  SPLASHSURF_STATUS_NO_MESH = 4,
+  // The mesh has too many vertices to be indexed with 32 bit indices
+  SPLASHSURF_STATUS_MESH_TOO_LARGE = 5,
+  // A panic occurred inside of the library, the context should not be used anymore
+  SPLASHSURF_STATUS_PANIC = 6,
+} SplashsurfStatus;
+
+// Opaque reconstruction context storing the parameters and the mesh of the last reconstruction
+typedef struct SplashsurfContext SplashsurfContext;
+
+// Parameters of the surface reconstruction, all lengths are given in absolute distance units
+typedef struct SplashsurfParameters {
+  // Radius per particle (used to calculate the particle volume)
+  double particle_radius;
+  // Rest density of the fluid
+  double rest_density;
+  // Compact support radius of the kernel, i.e. distance from the particle where kernel reaches zero
+  double compact_support_radius;
+  // Edge length of the marching cubes implicit background grid
+  double cube_size;
+  // Density threshold value to distinguish between the inside (above threshold) and outside (below threshold) of the fluid
+  double iso_surface_threshold;
+  // Whether to allow multi threading within the surface reconstruction procedure
+  bool enable_multi_threading;
+  // Whether repeated reconstructions of the same input should produce bitwise identical meshes
+  bool deterministic;
+  // Whether to use the octree based spatial decomposition instead of a global reconstruction
+  bool enable_octree;
+  // Maximum number of particles per octree leaf, chosen automatically based on the number of threads if zero
+  size_t octree_max_particles;
+  // Whether to stitch the meshes of the octree leaves to a closed mesh
+  bool octree_enable_stitching;
+} SplashsurfParameters;
+
+// Triangle mesh with single precision vertex coordinates
+//
+// The buffers are owned by the caller and have to be released with `splashsurf_mesh_f32_free`.
+// The buffer pointers are null if the mesh is empty.
+typedef struct SplashsurfMeshF32 {
+  // Vertex coordinates with three consecutive values (x, y, z) per vertex
+  float *vertices;
+  // Number of vertices, i.e. `vertices` contains `3 * num_vertices` values
+  size_t num_vertices;
+  // Vertex indices with three consecutive indices per triangle
+  uint32_t *indices;
+  // Number of triangles, i.e. `indices` contains `3 * num_triangles` values
+  size_t num_triangles;
+} SplashsurfMeshF32;
+
+// Triangle mesh with double precision vertex coordinates
+//
+// The buffers are owned by the caller and have to be released with `splashsurf_mesh_f64_free`.
+// The buffer pointers are null if the mesh is empty.
+typedef struct SplashsurfMeshF64 {
+  // Vertex coordinates with three consecutive values (x, y, z) per vertex
+  double *vertices;
+  // Number of vertices, i.e. `vertices` contains `3 * num_vertices` values
+  size_t num_vertices;
+  // Vertex indices with three consecutive indices per triangle
+  uint32_t *indices;
+  // Number of triangles, i.e. `indices` contains `3 * num_triangles` values
+  size_t num_triangles;
+} SplashsurfMeshF64;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns the default parameters, the particle radius, compact support radius and cube size have to be set by the caller
+struct SplashsurfParameters splashsurf_parameters_default(void);
+
+// Creates a new reconstruction context with the default parameters, returns null on failure
+//
+// The context has to be destroyed with `splashsurf_context_free`.
+struct SplashsurfContext *splashsurf_context_new(void);
+
+// Destroys a reconstruction context including its mesh (if it was not taken out before)
+//
+// # Safety
+// The context has to be null or a pointer returned by `splashsurf_context_new` that was not destroyed before.
+void splashsurf_context_free(struct SplashsurfContext *context);
+
+// Validates the given parameters and applies them to the context for all following reconstructions
+//
+// # Safety
+// The context has to be a valid pointer returned by `splashsurf_context_new` and the parameters have to point to
+// a valid `SplashsurfParameters` struct.
+enum SplashsurfStatus splashsurf_context_set_parameters(struct SplashsurfContext *context,
+                                                        const struct SplashsurfParameters *parameters);
+
+// Reconstructs the surface of particles with single precision positions, the mesh is stored in the context
+//
+// The positions are given as `3 * num_particles` consecutive values (x, y, z per particle). The mesh of a
+// previous reconstruction that was not taken out of the context is discarded.
+//
+// # Safety
+// The context has to be a valid pointer returned by `splashsurf_context_new` and the positions have to point to
+// at least `3 * num_particles` values (or can be null if `num_particles` is zero).
+enum SplashsurfStatus splashsurf_reconstruct_f32(struct SplashsurfContext *context,
+                                                 const float *positions,
+                                                 size_t num_particles);
+
+// Reconstructs the surface of particles with double precision positions, the mesh is stored in the context
+//
+// The positions are given as `3 * num_particles` consecutive values (x, y, z per particle). The mesh of a
+// previous reconstruction that was not taken out of the context is discarded.
+//
+// # Safety
+// The context has to be a valid pointer returned by `splashsurf_context_new` and the positions have to point to
+// at least `3 * num_particles` values (or can be null if `num_particles` is zero).
+enum SplashsurfStatus splashsurf_reconstruct_f64(struct SplashsurfContext *context,
+                                                 const double *positions,
+                                                 size_t num_particles);
+
+// Moves the single precision mesh of the last reconstruction out of the context
+//
+// Fails with `SPLASHSURF_STATUS_NO_MESH` if the context does not contain a mesh or if the last reconstruction was
+// performed in double precision. Fails with `SPLASHSURF_STATUS_MESH_TOO_LARGE` if the mesh has too many vertices for
+// 32 bit indices, in this case the mesh is kept in the context. The buffers of the mesh have to be released with
+// `splashsurf_mesh_f32_free`.
+//
+// # Safety
+// The context has to be a valid pointer returned by `splashsurf_context_new` and the mesh has to point to a
+// `SplashsurfMeshF32` struct that is overwritten without releasing its previous buffers.
+enum SplashsurfStatus splashsurf_take_mesh_f32(struct SplashsurfContext *context,
+                                               struct SplashsurfMeshF32 *mesh);
+
+// Moves the double precision mesh of the last reconstruction out of the context
+//
+// Fails with `SPLASHSURF_STATUS_NO_MESH` if the context does not contain a mesh or if the last reconstruction was
+// performed in single precision. Fails with `SPLASHSURF_STATUS_MESH_TOO_LARGE` if the mesh has too many vertices for
+// 32 bit indices, in this case the mesh is kept in the context. The buffers of the mesh have to be released with
+// `splashsurf_mesh_f64_free`.
+//
+// # Safety
+// The context has to be a valid pointer returned by `splashsurf_context_new` and the mesh has to point to a
+// `SplashsurfMeshF64` struct that is overwritten without releasing its previous buffers.
+enum SplashsurfStatus splashsurf_take_mesh_f64(struct SplashsurfContext *context,
+                                               struct SplashsurfMeshF64 *mesh);
+
+// Releases the buffers of a single precision mesh and resets the mesh to an empty mesh
+//
+// # Safety
+// The mesh has to be null or point to a mesh returned by `splashsurf_take_mesh_f32` or reset by this function.
+void splashsurf_mesh_f32_free(struct SplashsurfMeshF32 *mesh);
+
+// Releases the buffers of a double precision mesh and resets the mesh to an empty mesh
+//
+// # Safety
+// The mesh has to be null or point to a mesh returned by `splashsurf_take_mesh_f64` or reset by this function.
+void splashsurf_mesh_f64_free(struct SplashsurfMeshF64 *mesh);
+
+// Returns the message of the last error that occurred on the calling thread, null if no error occurred yet
+//
+// The returned string is owned by the library and valid until the next failing call on the same thread.
+const char *splashsurf_last_error_message(void);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* SPLASHSURF_H */
@@ -0,0 +1,582 @@
+//!
+//! C API for the surface reconstruction of SPH particle data with [`splashsurf_lib`]
+//!
+//! The C header corresponding to this crate is located at `include/splashsurf.h`. It is generated with
+//! [cbindgen](https://github.com/eqrion/cbindgen) using the `cbindgen.toml` of this crate:
+//! ```text
+//! cbindgen --config cbindgen.toml --crate splashsurf_capi --output include/splashsurf.h
+//! ```
+//!
+//! A typical usage of the API looks as follows:
+//!  1. Create a reconstruction context with [`splashsurf_context_new`].
+//!  2. Obtain the default parameters with [`splashsurf_parameters_default`], adapt them and apply them to the
+//!     context with [`splashsurf_context_set_parameters`].
+//!  3. Reconstruct a surface from an array of particle positions with [`splashsurf_reconstruct_f32`] or
+//!     [`splashsurf_reconstruct_f64`].
+//!  4. Move the resulting mesh out of the context with [`splashsurf_take_mesh_f32`] or [`splashsurf_take_mesh_f64`].
+//!     The caller owns the returned buffers and has to release them with [`splashsurf_mesh_f32_free`] or
+//!     [`splashsurf_mesh_f64_free`].
+//!  5. Destroy the context with [`splashsurf_context_free`].
+//!
+//! All functions return a [`SplashsurfStatus`] code. If a function fails, a description of the error can be
+//! obtained with [`splashsurf_last_error_message`]. Panics are caught at the boundary of the API and reported
+//! with [`SplashsurfStatus::Panic`], they never unwind into the calling C code.
+
+use splashsurf_lib::mesh::TriMesh3d;
+use splashsurf_lib::nalgebra::Vector3;
+use splashsurf_lib::{
+    MarchingCubesVariant, Parameters, ParticleDensityComputationStrategy, Real,
+    SpatialDecompositionParameters, SplashOutput, SubdivisionCriterion,
+};
+use std::any::Any;
+use std::cell::RefCell;
+use std::error::Error;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use thiserror::Error as ThisError;
+
+/// Status codes returned by the functions of the C API
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SplashsurfStatus {
+    /// The function completed successfully
+    Ok = 0,
+    /// A required pointer argument is null
+    NullPointer = 1,
+    /// The reconstruction parameters or the particle positions are invalid
+    InvalidArgument = 2,
+    /// The surface reconstruction failed
+    ReconstructionFailed = 3,
+    /// The context does not contain a mesh of the requested precision
+    NoMesh = 4,
+    /// The mesh has too many vertices to be indexed with 32 bit indices
+    MeshTooLarge = 5,
+    /// A panic occurred inside of the library, the context should not be used anymore
+    Panic = 6,
+}
+
+/// Parameters of the surface reconstruction, all lengths are given in absolute distance units
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SplashsurfParameters {
+    /// Radius per particle (used to calculate the particle volume)
+    pub particle_radius: f64,
+    /// Rest density of the fluid
+    pub rest_density: f64,
+    /// Compact support radius of the kernel, i.e. distance from the particle where kernel reaches zero
+    pub compact_support_radius: f64,
+    /// Edge length of the marching cubes implicit background grid
+    pub cube_size: f64,
+    /// Density threshold value to distinguish between the inside (above threshold) and outside (below threshold) of the fluid
+    pub iso_surface_threshold: f64,
+    /// Whether to allow multi threading within the surface reconstruction procedure
+    pub enable_multi_threading: bool,
+    /// Whether repeated reconstructions of the same input should produce bitwise identical meshes
+    pub deterministic: bool,
+    /// Whether to use the octree based spatial decomposition instead of a global reconstruction
+    pub enable_octree: bool,
+    /// Maximum number of particles per octree leaf, chosen automatically based on the number of threads if zero
+    pub octree_max_particles: usize,
+    /// Whether to stitch the meshes of the octree leaves to a closed mesh
+    pub octree_enable_stitching: bool,
+}
+
+/// Triangle mesh with single precision vertex coordinates
+///
+/// The buffers are owned by the caller and have to be released with `splashsurf_mesh_f32_free`.
+/// The buffer pointers are null if the mesh is empty.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SplashsurfMeshF32 {
+    /// Vertex coordinates with three consecutive values (x, y, z) per vertex
+    pub vertices: *mut f32,
+    /// Number of vertices, i.e. `vertices` contains `3 * num_vertices` values
+    pub num_vertices: usize,
+    /// Vertex indices with three consecutive indices per triangle
+    pub indices: *mut u32,
+    /// Number of triangles, i.e. `indices` contains `3 * num_triangles` values
+    pub num_triangles: usize,
+}
+
+/// Triangle mesh with double precision vertex coordinates
+///
+/// The buffers are owned by the caller and have to be released with `splashsurf_mesh_f64_free`.
+/// The buffer pointers are null if the mesh is empty.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SplashsurfMeshF64 {
+    /// Vertex coordinates with three consecutive values (x, y, z) per vertex
+    pub vertices: *mut f64,
+    /// Number of vertices, i.e. `vertices` contains `3 * num_vertices` values
+    pub num_vertices: usize,
+    /// Vertex indices with three consecutive indices per triangle
+    pub indices: *mut u32,
+    /// Number of triangles, i.e. `indices` contains `3 * num_triangles` values
+    pub num_triangles: usize,
+}
+
+/// Opaque reconstruction context storing the parameters and the mesh of the last reconstruction
+pub struct SplashsurfContext {
+    parameters: Parameters<f64>,
+    mesh: Option<ContextMesh>,
+}
+
+/// Mesh of the last reconstruction of a context
+enum ContextMesh {
+    F32(TriMesh3d<f32>),
+    F64(TriMesh3d<f64>),
+}
+
+/// Error type of the C API, converted to a status code and the last error message at the boundary
+#[derive(Debug, ThisError)]
+enum CapiError {
+    #[error("the argument `{0}` is a null pointer")]
+    NullPointer(&'static str),
+    #[error("{0}")]
+    InvalidArgument(String),
+    #[error("surface reconstruction failed: {0}")]
+    ReconstructionFailed(String),
+    #[error("{0}")]
+    NoMesh(&'static str),
+    #[error("the mesh has {0} vertices, which cannot be indexed with 32 bit indices")]
+    MeshTooLarge(usize),
+}
+
+impl CapiError {
+    fn status(&self) -> SplashsurfStatus {
+        match self {
+            CapiError::NullPointer(_) => SplashsurfStatus::NullPointer,
+            CapiError::InvalidArgument(_) => SplashsurfStatus::InvalidArgument,
+            CapiError::ReconstructionFailed(_) => SplashsurfStatus::ReconstructionFailed,
+            CapiError::NoMesh(_) => SplashsurfStatus::NoMesh,
+            CapiError::MeshTooLarge(_) => SplashsurfStatus::MeshTooLarge,
+        }
+    }
+}
+
+thread_local! {
+    /// Message of the last error that occurred in a function of the C API on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Stores the message of the last error of this thread
+fn set_last_error(message: String) {
+    // Interior null bytes would truncate the message in C, so they are removed
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+/// Formats an error including all of its sources, e.g. `invalid parameters: invalid value of cube size`
+fn error_chain_message(error: &dyn Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+    message
+}
+
+/// Extracts the message of a panic payload
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
+/// Runs the body of an API function, catching panics and converting errors to status codes
+fn ffi_boundary<F: FnOnce() -> Result<(), CapiError>>(f: F) -> SplashsurfStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => SplashsurfStatus::Ok,
+        Ok(Err(err)) => {
+            set_last_error(err.to_string());
+            err.status()
+        }
+        Err(payload) => {
+            set_last_error(format!("panic in splashsurf: {}", panic_message(&*payload)));
+            SplashsurfStatus::Panic
+        }
+    }
+}
+
+/// Converts a raw pointer argument into a reference, returns an error if it is null
+unsafe fn deref_mut<'a, T>(ptr: *mut T, name: &'static str) -> Result<&'a mut T, CapiError> {
+    ptr.as_mut().ok_or(CapiError::NullPointer(name))
+}
+
+impl SplashsurfParameters {
+    /// Converts the C parameters to the parameters of the surface reconstruction
+    fn to_parameters(&self) -> Parameters<f64> {
+        let spatial_decomposition = self.enable_octree.then(|| SpatialDecompositionParameters {
+            subdivision_criterion: if self.octree_max_particles == 0 {
+                SubdivisionCriterion::MaxParticleCountAuto
+            } else {
+                SubdivisionCriterion::MaxParticleCount(self.octree_max_particles)
+            },
+            ghost_particle_safety_factor: None,
+            enable_stitching: self.octree_enable_stitching,
+            particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+        });
+
+        Parameters {
+            particle_radius: self.particle_radius,
+            rest_density: self.rest_density,
+            compact_support_radius: self.compact_support_radius,
+            cube_size: self.cube_size,
+            iso_surface_threshold: self.iso_surface_threshold,
+            triangulation_scheme: MarchingCubesVariant::Classic,
+            vertex_snapping: None,
+            splash_detection: None,
+            splash_particle_output: SplashOutput::Ignore,
+            domain_aabb: None,
+            clipping_planes: Vec::new(),
+            enable_multi_threading: self.enable_multi_threading,
+            deterministic: self.deterministic,
+            progress_interval: None,
+            temporal_density_smoothing: None,
+            spatial_decomposition,
+        }
+    }
+}
+
+/// Returns the default parameters, the particle radius, compact support radius and cube size have to be set by the caller
+#[no_mangle]
+pub extern "C" fn splashsurf_parameters_default() -> SplashsurfParameters {
+    SplashsurfParameters {
+        particle_radius: 0.0,
+        rest_density: 1000.0,
+        compact_support_radius: 0.0,
+        cube_size: 0.0,
+        iso_surface_threshold: 0.6,
+        enable_multi_threading: true,
+        deterministic: false,
+        enable_octree: true,
+        octree_max_particles: 0,
+        octree_enable_stitching: true,
+    }
+}
+
+/// Creates a new reconstruction context with the default parameters, returns null on failure
+///
+/// The context has to be destroyed with `splashsurf_context_free`.
+#[no_mangle]
+pub extern "C" fn splashsurf_context_new() -> *mut SplashsurfContext {
+    let mut context = ptr::null_mut();
+    ffi_boundary(|| {
+        context = Box::into_raw(Box::new(SplashsurfContext {
+            parameters: splashsurf_parameters_default().to_parameters(),
+            mesh: None,
+        }));
+        Ok(())
+    });
+    context
+}
+
+/// Destroys a reconstruction context including its mesh (if it was not taken out before)
+///
+/// # Safety
+/// The context has to be null or a pointer returned by `splashsurf_context_new` that was not destroyed before.
+#[no_mangle]
+pub unsafe extern "C" fn splashsurf_context_free(context: *mut SplashsurfContext) {
+    if !context.is_null() {
+        ffi_boundary(|| {
+            drop(Box::from_raw(context));
+            Ok(())
+        });
+    }
+}
+
+/// Validates the given parameters and applies them to the context for all following reconstructions
+///
+/// # Safety
+/// The context has to be a valid pointer returned by `splashsurf_context_new` and the parameters have to point to
+/// a valid `SplashsurfParameters` struct.
+#[no_mangle]
+pub unsafe extern "C" fn splashsurf_context_set_parameters(
+    context: *mut SplashsurfContext,
+    parameters: *const SplashsurfParameters,
+) -> SplashsurfStatus {
+    ffi_boundary(|| {
+        let context = deref_mut(context, "context")?;
+        let parameters = parameters
+            .as_ref()
+            .ok_or(CapiError::NullPointer("parameters"))?
+            .to_parameters();
+
+        parameters
+            .validate()
+            .map_err(|err| CapiError::InvalidArgument(error_chain_message(&err)))?;
+        context.parameters = parameters;
+        Ok(())
+    })
+}
+
+/// Reconstructs the surface of the given particles with the parameters of the context
+fn reconstruct<R: Real>(
+    context: &SplashsurfContext,
+    positions: *const R,
+    num_particles: usize,
+) -> Result<TriMesh3d<R>, CapiError> {
+    let positions: &[R] = if num_particles == 0 {
+        &[]
+    } else if positions.is_null() {
+        return Err(CapiError::NullPointer("positions"));
+    } else {
+        let len = num_particles.checked_mul(3).ok_or_else(|| {
+            CapiError::InvalidArgument(format!("too many particles ({})", num_particles))
+        })?;
+        // Safety: the caller guarantees that the pointer refers to `3 * num_particles` values
+        unsafe { std::slice::from_raw_parts(positions, len) }
+    };
+
+    let particles = positions
+        .chunks_exact(3)
+        .map(|p| Vector3::new(p[0], p[1], p[2]))
+        .collect::<Vec<_>>();
+
+    let parameters = context
+        .parameters
+        .try_convert_checked::<R>()
+        .map_err(|err| CapiError::InvalidArgument(error_chain_message(&err)))?;
+
+    let mut reconstruction = splashsurf_lib::reconstruct_surface::<i64, R>(&particles, &parameters)
+        .map_err(|err| CapiError::ReconstructionFailed(error_chain_message(&err)))?;
+    Ok(reconstruction.take_mesh())
+}
+
+/// Reconstructs the surface of particles with single precision positions, the mesh is stored in the context
+///
+/// The positions are given as `3 * num_particles` consecutive values (x, y, z per particle). The mesh of a
+/// previous reconstruction that was not taken out of the context is discarded.
+///
+/// # Safety
+/// The context has to be a valid pointer returned by `splashsurf_context_new` and the positions have to point to
+/// at least `3 * num_particles` values (or can be null if `num_particles` is zero).
+#[no_mangle]
+pub unsafe extern "C" fn splashsurf_reconstruct_f32(
+    context: *mut SplashsurfContext,
+    positions: *const f32,
+    num_particles: usize,
+) -> SplashsurfStatus {
+    ffi_boundary(|| {
+        let context = deref_mut(context, "context")?;
+        context.mesh = None;
+        context.mesh = Some(ContextMesh::F32(reconstruct(
+            context,
+            positions,
+            num_particles,
+        )?));
+        Ok(())
+    })
+}
+
+/// Reconstructs the surface of particles with double precision positions, the mesh is stored in the context
+///
+/// The positions are given as `3 * num_particles` consecutive values (x, y, z per particle). The mesh of a
+/// previous reconstruction that was not taken out of the context is discarded.
+///
+/// # Safety
+/// The context has to be a valid pointer returned by `splashsurf_context_new` and the positions have to point to
+/// at least `3 * num_particles` values (or can be null if `num_particles` is zero).
+#[no_mangle]
+pub unsafe extern "C" fn splashsurf_reconstruct_f64(
+    context: *mut SplashsurfContext,
+    positions: *const f64,
+    num_particles: usize,
+) -> SplashsurfStatus {
+    ffi_boundary(|| {
+        let context = deref_mut(context, "context")?;
+        context.mesh = None;
+        context.mesh = Some(ContextMesh::F64(reconstruct(
+            context,
+            positions,
+            num_particles,
+        )?));
+        Ok(())
+    })
+}
+
+/// Copies the vertex coordinates and triangle indices of the mesh into buffers owned by the caller
+fn into_raw_buffers<R: Real>(
+    mesh: &TriMesh3d<R>,
+) -> Result<(*mut R, usize, *mut u32, usize), CapiError> {
+    let num_vertices = mesh.vertices.len();
+    let num_triangles = mesh.triangles.len();
+    if u32::try_from(num_vertices).is_err() {
+        return Err(CapiError::MeshTooLarge(num_vertices));
+    }
+
+    let vertices = mesh
+        .vertices
+        .iter()
+        .flat_map(|v| [v.x, v.y, v.z])
+        .collect::<Vec<_>>();
+    let indices = mesh
+        .triangles
+        .iter()
+        .flat_map(|t| t.map(|i| i as u32))
+        .collect::<Vec<_>>();
+
+    Ok((
+        into_raw_buffer(vertices),
+        num_vertices,
+        into_raw_buffer(indices),
+        num_triangles,
+    ))
+}
+
+/// Leaks the vector as a raw buffer, returns null for empty vectors
+fn into_raw_buffer<T>(values: Vec<T>) -> *mut T {
+    if values.is_empty() {
+        ptr::null_mut()
+    } else {
+        Box::into_raw(values.into_boxed_slice()) as *mut T
+    }
+}
+
+/// Releases a buffer created by [`into_raw_buffer`]
+unsafe fn free_raw_buffer<T>(buffer: *mut T, len: usize) {
+    if !buffer.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer, len)));
+    }
+}
+
+/// Moves the single precision mesh of the last reconstruction out of the context
+///
+/// Fails with `SPLASHSURF_STATUS_NO_MESH` if the context does not contain a mesh or if the last reconstruction was
+/// performed in double precision. Fails with `SPLASHSURF_STATUS_MESH_TOO_LARGE` if the mesh has too many vertices for
+/// 32 bit indices, in this case the mesh is kept in the context. The buffers of the mesh have to be released with
+/// `splashsurf_mesh_f32_free`.
+///
+/// # Safety
+/// The context has to be a valid pointer returned by `splashsurf_context_new` and the mesh has to point to a
+/// `SplashsurfMeshF32` struct that is overwritten without releasing its previous buffers.
+#[no_mangle]
+pub unsafe extern "C" fn splashsurf_take_mesh_f32(
+    context: *mut SplashsurfContext,
+    mesh: *mut SplashsurfMeshF32,
+) -> SplashsurfStatus {
+    ffi_boundary(|| {
+        let context = deref_mut(context, "context")?;
+        let out = deref_mut(mesh, "mesh")?;
+        // The mesh is only removed from the context once its buffers were created successfully
+        let (vertices, num_vertices, indices, num_triangles) = match &context.mesh {
+            Some(ContextMesh::F32(mesh)) => into_raw_buffers(mesh)?,
+            Some(ContextMesh::F64(_)) => {
+                return Err(CapiError::NoMesh(
+                    "the last reconstruction was performed in double precision, use splashsurf_take_mesh_f64",
+                ));
+            }
+            None => return Err(CapiError::NoMesh("the context does not contain a mesh")),
+        };
+        context.mesh = None;
+        *out = SplashsurfMeshF32 {
+            vertices,
+            num_vertices,
+            indices,
+            num_triangles,
+        };
+        Ok(())
+    })
+}
+
+/// Moves the double precision mesh of the last reconstruction out of the context
+///
+/// Fails with `SPLASHSURF_STATUS_NO_MESH` if the context does not contain a mesh or if the last reconstruction was
+/// performed in single precision. Fails with `SPLASHSURF_STATUS_MESH_TOO_LARGE` if the mesh has too many vertices for
+/// 32 bit indices, in this case the mesh is kept in the context. The buffers of the mesh have to be released with
+/// `splashsurf_mesh_f64_free`.
+///
+/// # Safety
+/// The context has to be a valid pointer returned by `splashsurf_context_new` and the mesh has to point to a
+/// `SplashsurfMeshF64` struct that is overwritten without releasing its previous buffers.
+#[no_mangle]
+pub unsafe extern "C" fn splashsurf_take_mesh_f64(
+    context: *mut SplashsurfContext,
+    mesh: *mut SplashsurfMeshF64,
+) -> SplashsurfStatus {
+    ffi_boundary(|| {
+        let context = deref_mut(context, "context")?;
+        let out = deref_mut(mesh, "mesh")?;
+        // The mesh is only removed from the context once its buffers were created successfully
+        let (vertices, num_vertices, indices, num_triangles) = match &context.mesh {
+            Some(ContextMesh::F64(mesh)) => into_raw_buffers(mesh)?,
+            Some(ContextMesh::F32(_)) => {
+                return Err(CapiError::NoMesh(
+                    "the last reconstruction was performed in single precision, use splashsurf_take_mesh_f32",
+                ));
+            }
+            None => return Err(CapiError::NoMesh("the context does not contain a mesh")),
+        };
+        context.mesh = None;
+        *out = SplashsurfMeshF64 {
+            vertices,
+            num_vertices,
+            indices,
+            num_triangles,
+        };
+        Ok(())
+    })
+}
+
+/// Releases the buffers of a single precision mesh and resets the mesh to an empty mesh
+///
+/// # Safety
+/// The mesh has to be null or point to a mesh returned by `splashsurf_take_mesh_f32` or reset by this function.
+#[no_mangle]
+pub unsafe extern "C" fn splashsurf_mesh_f32_free(mesh: *mut SplashsurfMeshF32) {
+    if let Some(mesh) = mesh.as_mut() {
+        ffi_boundary(|| {
+            free_raw_buffer(mesh.vertices, 3 * mesh.num_vertices);
+            free_raw_buffer(mesh.indices, 3 * mesh.num_triangles);
+            *mesh = SplashsurfMeshF32 {
+                vertices: ptr::null_mut(),
+                num_vertices: 0,
+                indices: ptr::null_mut(),
+                num_triangles: 0,
+            };
+            Ok(())
+        });
+    }
+}
+
+/// Releases the buffers of a double precision mesh and resets the mesh to an empty mesh
+///
+/// # Safety
+/// The mesh has to be null or point to a mesh returned by `splashsurf_take_mesh_f64` or reset by this function.
+#[no_mangle]
+pub unsafe extern "C" fn splashsurf_mesh_f64_free(mesh: *mut SplashsurfMeshF64) {
+    if let Some(mesh) = mesh.as_mut() {
+        ffi_boundary(|| {
+            free_raw_buffer(mesh.vertices, 3 * mesh.num_vertices);
+            free_raw_buffer(mesh.indices, 3 * mesh.num_triangles);
+            *mesh = SplashsurfMeshF64 {
+                vertices: ptr::null_mut(),
+                num_vertices: 0,
+                indices: ptr::null_mut(),
+                num_triangles: 0,
+            };
+            Ok(())
+        });
+    }
+}
+
+/// Returns the message of the last error that occurred on the calling thread, null if no error occurred yet
+///
+/// The returned string is owned by the library and valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn splashsurf_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
@@ -0,0 +1,148 @@
+// Test program for the C API of splashsurf, compiled and run by the `capi` integration test of the crate
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "splashsurf.h"
+
+#define CHECK(condition)                                                               \
+    do {                                                                               \
+        if (!(condition)) {                                                            \
+            const char *message = splashsurf_last_error_message();                     \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #condition); \
+            fprintf(stderr, "last error: %s\n", message ? message : "(none)");         \
+            exit(1);                                                                   \
+        }                                                                              \
+    } while (0)
+
+#define N 10
+#define NUM_PARTICLES (N * N * N)
+
+static const double PARTICLE_RADIUS = 0.025;
+
+// Fills the positions with a cube of particles with a spacing of twice the particle radius
+static void particle_cube_f64(double *positions) {
+    size_t p = 0;
+    for (int i = 0; i < N; i++) {
+        for (int j = 0; j < N; j++) {
+            for (int k = 0; k < N; k++) {
+                positions[p++] = 2.0 * PARTICLE_RADIUS * i;
+                positions[p++] = 2.0 * PARTICLE_RADIUS * j;
+                positions[p++] = 2.0 * PARTICLE_RADIUS * k;
+            }
+        }
+    }
+}
+
+static SplashsurfParameters test_parameters(void) {
+    SplashsurfParameters parameters = splashsurf_parameters_default();
+    parameters.particle_radius = PARTICLE_RADIUS;
+    parameters.compact_support_radius = 4.0 * PARTICLE_RADIUS;
+    parameters.cube_size = 0.5 * PARTICLE_RADIUS;
+    return parameters;
+}
+
+// Checks that all indices refer to existing vertices
+static int indices_valid(const uint32_t *indices, size_t num_triangles, size_t num_vertices) {
+    for (size_t i = 0; i < 3 * num_triangles; i++) {
+        if (indices[i] >= num_vertices) {
+            return 0;
+        }
+    }
+    return 1;
+}
+
+static void test_reconstruct_f32(SplashsurfContext *context, const double *positions) {
+    float *positions_f32 = malloc(3 * NUM_PARTICLES * sizeof(float));
+    CHECK(positions_f32 != NULL);
+    for (size_t i = 0; i < 3 * NUM_PARTICLES; i++) {
+        positions_f32[i] = (float)positions[i];
+    }
+
+    CHECK(splashsurf_reconstruct_f32(context, positions_f32, NUM_PARTICLES) == SPLASHSURF_STATUS_OK);
+    free(positions_f32);
+
+    // The mesh has to be taken out with the matching precision
+    SplashsurfMeshF64 wrong_mesh = {0};
+    CHECK(splashsurf_take_mesh_f64(context, &wrong_mesh) == SPLASHSURF_STATUS_NO_MESH);
+
+    SplashsurfMeshF32 mesh = {0};
+    CHECK(splashsurf_take_mesh_f32(context, &mesh) == SPLASHSURF_STATUS_OK);
+    CHECK(mesh.num_vertices > 0 && mesh.vertices != NULL);
+    CHECK(mesh.num_triangles > 0 && mesh.indices != NULL);
+    CHECK(indices_valid(mesh.indices, mesh.num_triangles, mesh.num_vertices));
+
+    // All vertices have to be close to the particle cube
+    for (size_t i = 0; i < 3 * mesh.num_vertices; i++) {
+        CHECK(mesh.vertices[i] > -4.0 * PARTICLE_RADIUS);
+        CHECK(mesh.vertices[i] < 2.0 * PARTICLE_RADIUS * N + 2.0 * PARTICLE_RADIUS);
+    }
+
+    // The mesh was moved out of the context
+    SplashsurfMeshF32 no_mesh = {0};
+    CHECK(splashsurf_take_mesh_f32(context, &no_mesh) == SPLASHSURF_STATUS_NO_MESH);
+
+    splashsurf_mesh_f32_free(&mesh);
+    CHECK(mesh.vertices == NULL && mesh.indices == NULL);
+    // Releasing a mesh twice is a no-op
+    splashsurf_mesh_f32_free(&mesh);
+
+    printf("f32 reconstruction: ok\n");
+}
+
+static void test_reconstruct_f64(SplashsurfContext *context, const double *positions) {
+    CHECK(splashsurf_reconstruct_f64(context, positions, NUM_PARTICLES) == SPLASHSURF_STATUS_OK);
+
+    SplashsurfMeshF64 mesh = {0};
+    CHECK(splashsurf_take_mesh_f64(context, &mesh) == SPLASHSURF_STATUS_OK);
+    CHECK(mesh.num_vertices > 0 && mesh.vertices != NULL);
+    CHECK(mesh.num_triangles > 0 && mesh.indices != NULL);
+    CHECK(indices_valid(mesh.indices, mesh.num_triangles, mesh.num_vertices));
+
+    splashsurf_mesh_f64_free(&mesh);
+    CHECK(mesh.vertices == NULL && mesh.indices == NULL);
+
+    printf("f64 reconstruction: ok\n");
+}
+
+static void test_errors(SplashsurfContext *context) {
+    SplashsurfParameters parameters = test_parameters();
+    parameters.cube_size = -1.0;
+    CHECK(splashsurf_context_set_parameters(context, &parameters) == SPLASHSURF_STATUS_INVALID_ARGUMENT);
+    CHECK(splashsurf_last_error_message() != NULL);
+    printf("invalid parameters: %s\n", splashsurf_last_error_message());
+
+    CHECK(splashsurf_context_set_parameters(context, NULL) == SPLASHSURF_STATUS_NULL_POINTER);
+    CHECK(splashsurf_reconstruct_f64(NULL, NULL, 0) == SPLASHSURF_STATUS_NULL_POINTER);
+    CHECK(splashsurf_reconstruct_f64(context, NULL, NUM_PARTICLES) == SPLASHSURF_STATUS_NULL_POINTER);
+
+    SplashsurfMeshF64 mesh = {0};
+    CHECK(splashsurf_take_mesh_f64(context, &mesh) == SPLASHSURF_STATUS_NO_MESH);
+    CHECK(splashsurf_take_mesh_f64(context, NULL) == SPLASHSURF_STATUS_NULL_POINTER);
+
+    // Destroying null pointers is a no-op
+    splashsurf_context_free(NULL);
+    splashsurf_mesh_f64_free(NULL);
+
+    printf("error handling: ok\n");
+}
+
+int main(void) {
+    double *positions = malloc(3 * NUM_PARTICLES * sizeof(double));
+    CHECK(positions != NULL);
+    particle_cube_f64(positions);
+
+    SplashsurfContext *context = splashsurf_context_new();
+    CHECK(context != NULL);
+
+    SplashsurfParameters parameters = test_parameters();
+    CHECK(splashsurf_context_set_parameters(context, &parameters) == SPLASHSURF_STATUS_OK);
+
+    test_reconstruct_f32(context, positions);
+    test_reconstruct_f64(context, positions);
+    test_errors(context);
+
+    splashsurf_context_free(context);
+    free(positions);
+    return 0;
+}
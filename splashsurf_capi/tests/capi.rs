@@ -0,0 +1,53 @@
+//! Compiles the C test program `tests/c/test_capi.c` against the shared library of this crate and runs it
+
+#![cfg(unix)]
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Returns the directory containing the shared library of the crate built for this test
+fn library_dir() -> PathBuf {
+    // Test executables are placed next to the libraries of the crate (`target/<profile>/deps`)
+    env::current_exe()
+        .expect("unable to get path of the test executable")
+        .parent()
+        .expect("test executable has no parent directory")
+        .to_path_buf()
+}
+
+#[test]
+fn test_c_program() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib_dir = library_dir();
+    let out_dir = env::temp_dir().join(format!("splashsurf_capi_test_{}", std::process::id()));
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let executable = out_dir.join("test_capi");
+
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(&compiler)
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg(crate_dir.join("tests/c/test_capi.c"))
+        .arg("-o")
+        .arg(&executable)
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg("-lsplashsurf_capi")
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .status()
+        .unwrap_or_else(|err| panic!("failed to run C compiler `{}`: {}", compiler, err));
+    assert!(status.success(), "compilation of the C test program failed");
+
+    let output = Command::new(&executable)
+        .output()
+        .expect("failed to run the C test program");
+    println!("{}", String::from_utf8_lossy(&output.stdout));
+    eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.status.success(), "C test program failed");
+
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
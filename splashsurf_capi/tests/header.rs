@@ -0,0 +1,25 @@
+//! Checks that the committed header `include/splashsurf.h` matches the header generated by cbindgen from the crate
+
+use std::path::Path;
+
+#[test]
+fn test_header_up_to_date() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+        .expect("failed to read the cbindgen configuration");
+
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src/lib.rs"))
+        .generate()
+        .expect("failed to generate the header with cbindgen")
+        .write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+
+    let committed = std::fs::read_to_string(crate_dir.join("include/splashsurf.h")).unwrap();
+    assert!(
+        generated == committed.replace("\r\n", "\n"),
+        "the header include/splashsurf.h is outdated, regenerate it as described in the README of the crate"
+    );
+}
@@ -20,6 +20,7 @@ pub fn aabb_from_points(c: &mut Criterion) {
     group.finish();
 }
 
+#[cfg(feature = "parallel")]
 pub fn aabb_from_points_par(c: &mut Criterion) {
     let particle_positions: &Vec<Vector3<f32>> =
         &particles_from_vtk("../data/hilbert_46843_particles.vtk").unwrap();
@@ -36,4 +37,7 @@ pub fn aabb_from_points_par(c: &mut Criterion) {
     group.finish();
 }
 
+#[cfg(feature = "parallel")]
 criterion_group!(bench_aabb, aabb_from_points, aabb_from_points_par);
+#[cfg(not(feature = "parallel"))]
+criterion_group!(bench_aabb, aabb_from_points);
@@ -4,9 +4,9 @@ use splashsurf_lib::io::vtk_format::particles_from_vtk;
 #[allow(dead_code)]
 use splashsurf_lib::io::vtk_format::write_vtk;
 use splashsurf_lib::{
-    reconstruct_surface, reconstruct_surface_inplace, Parameters,
-    ParticleDensityComputationStrategy, SpatialDecompositionParameters, SubdivisionCriterion,
-    SurfaceReconstruction,
+    reconstruct_surface, reconstruct_surface_inplace, MarchingCubesVariant, Parameters,
+    ParticleDensityComputationStrategy, SpatialDecompositionParameters, SplashOutput,
+    SubdivisionCriterion, SurfaceReconstruction,
 };
 use std::time::Duration;
 
@@ -25,8 +25,16 @@ pub fn surface_reconstruction_canyon(c: &mut Criterion) {
         compact_support_radius: compact_support_radius,
         cube_size,
         iso_surface_threshold: 0.6,
+        triangulation_scheme: MarchingCubesVariant::Classic,
+        vertex_snapping: None,
+        splash_detection: None,
+        splash_particle_output: SplashOutput::Ignore,
         domain_aabb: None,
+        clipping_planes: Vec::new(),
         enable_multi_threading: true,
+        deterministic: false,
+        progress_interval: None,
+        temporal_density_smoothing: None,
         spatial_decomposition: None,
     };
 
@@ -101,8 +109,16 @@ pub fn surface_reconstruction_dam_break(c: &mut Criterion) {
         compact_support_radius: compact_support_radius,
         cube_size,
         iso_surface_threshold: 0.6,
+        triangulation_scheme: MarchingCubesVariant::Classic,
+        vertex_snapping: None,
+        splash_detection: None,
+        splash_particle_output: SplashOutput::Ignore,
         domain_aabb: None,
+        clipping_planes: Vec::new(),
         enable_multi_threading: true,
+        deterministic: false,
+        progress_interval: None,
+        temporal_density_smoothing: None,
         spatial_decomposition: None,
     };
 
@@ -182,8 +198,16 @@ pub fn surface_reconstruction_double_dam_break(c: &mut Criterion) {
         compact_support_radius: compact_support_radius,
         cube_size,
         iso_surface_threshold: 0.6,
+        triangulation_scheme: MarchingCubesVariant::Classic,
+        vertex_snapping: None,
+        splash_detection: None,
+        splash_particle_output: SplashOutput::Ignore,
         domain_aabb: None,
+        clipping_planes: Vec::new(),
         enable_multi_threading: true,
+        deterministic: false,
+        progress_interval: None,
+        temporal_density_smoothing: None,
         spatial_decomposition: None,
     };
 
@@ -263,8 +287,16 @@ pub fn surface_reconstruction_double_dam_break_inplace(c: &mut Criterion) {
         compact_support_radius: compact_support_radius,
         cube_size,
         iso_surface_threshold: 0.6,
+        triangulation_scheme: MarchingCubesVariant::Classic,
+        vertex_snapping: None,
+        splash_detection: None,
+        splash_particle_output: SplashOutput::Ignore,
         domain_aabb: None,
+        clipping_planes: Vec::new(),
         enable_multi_threading: true,
+        deterministic: false,
+        progress_interval: None,
+        temporal_density_smoothing: None,
         spatial_decomposition: None,
     };
 
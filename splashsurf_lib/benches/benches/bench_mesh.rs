@@ -2,8 +2,8 @@ use criterion::{criterion_group, Criterion};
 use splashsurf_lib::io::vtk_format::particles_from_vtk;
 use splashsurf_lib::nalgebra::Vector3;
 use splashsurf_lib::{
-    reconstruct_surface, Parameters, ParticleDensityComputationStrategy,
-    SpatialDecompositionParameters, SubdivisionCriterion, SurfaceReconstruction,
+    reconstruct_surface, MarchingCubesVariant, Parameters, ParticleDensityComputationStrategy,
+    SpatialDecompositionParameters, SplashOutput, SubdivisionCriterion, SurfaceReconstruction,
 };
 use std::path::Path;
 use std::time::Duration;
@@ -21,8 +21,16 @@ fn reconstruct_particles<P: AsRef<Path>>(particle_file: P) -> SurfaceReconstruct
         compact_support_radius: compact_support_radius,
         cube_size,
         iso_surface_threshold: 0.6,
+        triangulation_scheme: MarchingCubesVariant::Classic,
+        vertex_snapping: None,
+        splash_detection: None,
+        splash_particle_output: SplashOutput::Ignore,
         domain_aabb: None,
+        clipping_planes: Vec::new(),
         enable_multi_threading: true,
+        deterministic: false,
+        progress_interval: None,
+        temporal_density_smoothing: None,
         spatial_decomposition: Some(SpatialDecompositionParameters {
             subdivision_criterion: SubdivisionCriterion::MaxParticleCountAuto,
             ghost_particle_safety_factor: None,
@@ -54,6 +62,7 @@ pub fn mesh_vertex_normals(c: &mut Criterion) {
     group.finish();
 }
 
+#[cfg(feature = "parallel")]
 pub fn mesh_vertex_normals_parallel(c: &mut Criterion) {
     //let reconstruction = reconstruct_particles("../../canyon_13353401_particles.vtk");
     let reconstruction = reconstruct_particles("../data/hilbert_46843_particles.vtk");
@@ -74,8 +83,11 @@ pub fn mesh_vertex_normals_parallel(c: &mut Criterion) {
     group.finish();
 }
 
+#[cfg(feature = "parallel")]
 criterion_group!(
     bench_mesh,
     mesh_vertex_normals,
     mesh_vertex_normals_parallel
 );
+#[cfg(not(feature = "parallel"))]
+criterion_group!(bench_mesh, mesh_vertex_normals);
@@ -74,6 +74,7 @@ pub fn neighborhood_search_spatial_hashing(c: &mut Criterion) {
     group.finish();
 }
 
+#[cfg(feature = "parallel")]
 pub fn neighborhood_search_spatial_hashing_parallel(c: &mut Criterion) {
     let particle_positions: &Vec<Vector3<f32>> =
         &io::vtk_format::particles_from_vtk(PARTICLE_FILE).unwrap();
@@ -103,9 +104,16 @@ pub fn neighborhood_search_spatial_hashing_parallel(c: &mut Criterion) {
     group.finish();
 }
 
+#[cfg(feature = "parallel")]
 criterion_group!(
     bench_neighborhood,
     neighborhood_search_naive,
     neighborhood_search_spatial_hashing,
     neighborhood_search_spatial_hashing_parallel,
 );
+#[cfg(not(feature = "parallel"))]
+criterion_group!(
+    bench_neighborhood,
+    neighborhood_search_naive,
+    neighborhood_search_spatial_hashing,
+);
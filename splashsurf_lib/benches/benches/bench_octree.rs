@@ -1,7 +1,8 @@
 use criterion::{criterion_group, BatchSize, Criterion};
 use nalgebra::Vector3;
+use splashsurf_lib::generic_tree::ParVisitableTree;
 use splashsurf_lib::io::vtk_format::particles_from_vtk;
-use splashsurf_lib::octree::Octree;
+use splashsurf_lib::octree::{Octree, OctreeNode};
 use splashsurf_lib::{grid_for_reconstruction, SubdivisionCriterion, UniformGrid};
 use std::time::Duration;
 
@@ -50,4 +51,68 @@ pub fn subdivide_recursively_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(bench_octree, subdivide_recursively_benchmark);
+pub fn visit_small_leaves_benchmark(c: &mut Criterion) {
+    let particle_positions: &Vec<Vector3<f32>> =
+        &particles_from_vtk("../data/hilbert_46843_particles.vtk").unwrap();
+    // Results in an octree with many small leaves
+    let particles_per_cell = 50;
+
+    let particle_radius = 0.011;
+    let compact_support_radius = 4.0 * particle_radius;
+
+    let cube_size = 1.5 * particle_radius;
+    let grid: &UniformGrid<i64, _> = &grid_for_reconstruction(
+        particle_positions.as_slice(),
+        particle_radius,
+        compact_support_radius,
+        cube_size,
+        None,
+        true,
+    )
+    .unwrap();
+
+    let mut tree = Octree::new(&grid, particle_positions.len());
+    tree.par_subdivide_recursively_margin(
+        grid,
+        particle_positions.as_slice(),
+        SubdivisionCriterion::MaxParticleCount(particles_per_cell),
+        compact_support_radius,
+        false,
+    );
+    let tree = &tree;
+
+    // Small amount of work per leaf: computes the centroid of the leaf particles
+    let leaf_centroid = move |node: &OctreeNode<i64, f32>| {
+        if let Some(particle_set) = node.data().particle_set() {
+            let sum = particle_set
+                .particles
+                .iter()
+                .fold(Vector3::zeros(), |sum, &i| sum + particle_positions[i]);
+            criterion::black_box(sum / particle_set.particles.len().max(1) as f32);
+        }
+    };
+
+    let mut group = c.benchmark_group("octree visitation");
+    group.sample_size(50);
+    group.warm_up_time(Duration::from_secs(3));
+    group.measurement_time(Duration::from_secs(10));
+
+    group.bench_function("par_visit_bfs_small_leaves", move |b| {
+        b.iter(|| tree.root().par_visit_bfs(leaf_centroid))
+    });
+
+    group.bench_function("par_visit_bfs_with_granularity_small_leaves", move |b| {
+        b.iter(|| {
+            tree.root()
+                .par_visit_bfs_with_granularity(|node| node.is_small_subtree(256), leaf_centroid)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    bench_octree,
+    subdivide_recursively_benchmark,
+    visit_small_leaves_benchmark
+);
@@ -2,4 +2,5 @@ pub mod bench_aabb;
 pub mod bench_full;
 pub mod bench_mesh;
 pub mod bench_neighborhood;
+#[cfg(feature = "parallel")]
 pub mod bench_octree;
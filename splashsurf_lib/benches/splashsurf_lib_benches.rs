@@ -6,8 +6,10 @@ use benches::bench_aabb::bench_aabb;
 use benches::bench_full::bench_full;
 use benches::bench_mesh::bench_mesh;
 use benches::bench_neighborhood::bench_neighborhood;
+#[cfg(feature = "parallel")]
 use benches::bench_octree::bench_octree;
 
+#[cfg(feature = "parallel")]
 criterion_main!(
     bench_aabb,
     bench_mesh,
@@ -15,3 +17,5 @@ criterion_main!(
     bench_full,
     bench_neighborhood
 );
+#[cfg(not(feature = "parallel"))]
+criterion_main!(bench_aabb, bench_mesh, bench_full, bench_neighborhood);
@@ -4,9 +4,12 @@ use std::fmt;
 use std::fmt::Debug;
 
 use nalgebra::SVector;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
-use crate::{Real, ThreadSafe};
+#[cfg(feature = "parallel")]
+use crate::ThreadSafe;
+use crate::{ConversionError, Real};
 
 /// Type representing an axis aligned bounding box in arbitrary dimensions
 #[derive(Clone, Eq, PartialEq)]
@@ -20,6 +23,8 @@ pub type AxisAlignedBoundingBox2d<R> = AxisAlignedBoundingBox<R, 2>;
 /// Convenience type alias for an AABB in three dimensions
 pub type AxisAlignedBoundingBox3d<R> = AxisAlignedBoundingBox<R, 3>;
 
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
 impl<R, const D: usize> AxisAlignedBoundingBox<R, D>
 where
     R: Real,
@@ -97,9 +102,17 @@ where
     where
         T: Real,
     {
-        Some(AxisAlignedBoundingBox::new(
-            T::try_convert_vec_from(&self.min)?,
-            T::try_convert_vec_from(&self.max)?,
+        self.try_convert_checked().ok()
+    }
+
+    /// Tries to convert the AABB from one real type to another real type, returns an error naming the first coordinate that cannot be converted
+    pub fn try_convert_checked<T>(&self) -> Result<AxisAlignedBoundingBox<T, D>, ConversionError>
+    where
+        T: Real,
+    {
+        Ok(AxisAlignedBoundingBox::new(
+            ConversionError::convert_vec(&self.min, "min")?,
+            ConversionError::convert_vec(&self.max, "max")?,
         ))
     }
 
@@ -188,6 +201,11 @@ where
         return true;
     }
 
+    /// Checks if the given AABB intersects the AABB, both AABBs are considered to be half-open to their max coordinates
+    pub fn intersects_aabb(&self, other: &Self) -> bool {
+        (0..D).all(|i| other.max[i] > self.min[i] && other.min[i] < self.max[i])
+    }
+
     /// Translates the AABB by the given vector
     pub fn translate(&mut self, vector: &SVector<R, D>) {
         self.min += vector;
@@ -275,3 +293,24 @@ fn test_aabb_contains_point() {
     assert!(!aabb.contains_point(&Vector3::new(0.0, 0.0, 1.0)));
     assert!(!aabb.contains_point(&Vector3::new(1.0, 1.0, 1.0)));
 }
+
+#[test]
+fn test_aabb_intersects_aabb() {
+    use crate::nalgebra::Vector3;
+    let aabb = AxisAlignedBoundingBox3d::<f64>::new(
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(1.0, 1.0, 1.0),
+    );
+    let new_aabb = |min: [f64; 3], max: [f64; 3]| {
+        AxisAlignedBoundingBox3d::new(Vector3::from(min), Vector3::from(max))
+    };
+
+    assert!(aabb.intersects_aabb(&aabb));
+    assert!(aabb.intersects_aabb(&new_aabb([0.25; 3], [0.75; 3])));
+    assert!(aabb.intersects_aabb(&new_aabb([-1.0; 3], [2.0; 3])));
+    assert!(aabb.intersects_aabb(&new_aabb([0.5, -1.0, 0.5], [2.0, 0.5, 2.0])));
+    // Touching boxes do not intersect as the AABBs are half-open
+    assert!(!aabb.intersects_aabb(&new_aabb([1.0, 0.0, 0.0], [2.0, 1.0, 1.0])));
+    assert!(!aabb.intersects_aabb(&new_aabb([0.0, -1.0, 0.0], [1.0, 0.0, 1.0])));
+    assert!(!aabb.intersects_aabb(&new_aabb([2.0; 3], [3.0; 3])));
+}
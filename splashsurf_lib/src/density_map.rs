@@ -20,16 +20,29 @@
 
 use crate::aabb::AxisAlignedBoundingBox3d;
 use crate::kernel::DiscreteSquaredDistanceCubicKernel;
+#[cfg(feature = "parallel")]
+use crate::logging::info;
+use crate::logging::{trace, warn};
 use crate::mesh::{HexMesh3d, MeshAttribute, MeshWithData};
+use crate::progress::ProgressLogger;
 use crate::uniform_grid::{OwningSubdomainGrid, Subdomain, UniformGrid};
+#[cfg(feature = "parallel")]
 use crate::utils::{ChunkSize, ParallelPolicy};
-use crate::{new_map, profile, HashState, Index, MapType, ParallelMapType, Real};
+use crate::{new_map, profile, ClippingPlane, Index, MapType, Real};
+#[cfg(feature = "parallel")]
+use crate::{HashState, ParallelMapType};
+#[cfg(feature = "parallel")]
 use dashmap::ReadOnlyView as ReadDashMap;
-use log::{info, trace, warn};
 use nalgebra::Vector3;
+use num::Bounded;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
+#[cfg(feature = "parallel")]
 use std::cell::RefCell;
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicUsize, Ordering};
 use thiserror::Error as ThisError;
+#[cfg(feature = "parallel")]
 use thread_local::ThreadLocal;
 
 // TODO: Document formulas for the computation of the values
@@ -62,28 +75,20 @@ pub fn compute_particle_densities<I: Index, R: Real>(
     enable_multi_threading: bool,
 ) -> Vec<R> {
     let mut densities = Vec::new();
-    if enable_multi_threading {
-        parallel_compute_particle_densities::<I, R>(
-            particle_positions,
-            particle_neighbor_lists,
-            compact_support_radius,
-            particle_rest_mass,
-            &mut densities,
-        )
-    } else {
-        sequential_compute_particle_densities::<I, R>(
-            particle_positions,
-            particle_neighbor_lists,
-            compact_support_radius,
-            particle_rest_mass,
-            &mut densities,
-        )
-    }
+    compute_particle_densities_inplace::<I, R>(
+        particle_positions,
+        particle_neighbor_lists,
+        compact_support_radius,
+        particle_rest_mass,
+        enable_multi_threading,
+        &mut densities,
+    );
     densities
 }
 
 /// Computes the individual densities of particles inplace using a standard SPH sum
 #[inline(never)]
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
 pub fn compute_particle_densities_inplace<I: Index, R: Real>(
     particle_positions: &[Vector3<R>],
     particle_neighbor_lists: &[Vec<usize>],
@@ -92,23 +97,33 @@ pub fn compute_particle_densities_inplace<I: Index, R: Real>(
     enable_multi_threading: bool,
     densities: &mut Vec<R>,
 ) {
+    #[cfg(feature = "parallel")]
     if enable_multi_threading {
-        parallel_compute_particle_densities::<I, R>(
-            particle_positions,
-            particle_neighbor_lists,
-            compact_support_radius,
-            particle_rest_mass,
-            densities,
-        )
-    } else {
-        sequential_compute_particle_densities::<I, R>(
+        return parallel_compute_particle_densities::<I, R>(
             particle_positions,
             particle_neighbor_lists,
             compact_support_radius,
             particle_rest_mass,
             densities,
-        )
+        );
     }
+
+    sequential_compute_particle_densities::<I, R>(
+        particle_positions,
+        particle_neighbor_lists,
+        compact_support_radius,
+        particle_rest_mass,
+        densities,
+    )
+}
+
+/// Computes the density of an isolated particle without any neighbors, i.e. `m·W(0)`, using the same kernel as the particle density computation
+pub fn compute_isolated_particle_density<R: Real>(
+    compact_support_radius: R,
+    particle_rest_mass: R,
+) -> R {
+    let kernel = DiscreteSquaredDistanceCubicKernel::new::<f64>(1000, compact_support_radius);
+    kernel.evaluate(R::zero()) * particle_rest_mass
 }
 
 fn init_density_storage<R: Real>(densities: &mut Vec<R>, new_len: usize) {
@@ -150,6 +165,8 @@ pub fn sequential_compute_particle_densities<I: Index, R: Real>(
 
 /// Computes the individual densities of particles using a standard SPH sum, multi-threaded implementation
 #[inline(never)]
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
 pub fn parallel_compute_particle_densities<I: Index, R: Real>(
     particle_positions: &[Vector3<R>],
     particle_neighbor_lists: &[Vec<usize>],
@@ -191,6 +208,8 @@ pub fn parallel_compute_particle_densities<I: Index, R: Real>(
 #[derive(Clone, Debug)]
 pub enum DensityMap<I: Index, R: Real> {
     Standard(MapType<I, R>),
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
     DashMap(ReadDashMap<I, R, HashState>),
 }
 
@@ -200,6 +219,7 @@ impl<I: Index, R: Real> From<MapType<I, R>> for DensityMap<I, R> {
     }
 }
 
+#[cfg(feature = "parallel")]
 impl<I: Index, R: Real> From<ParallelMapType<I, R>> for DensityMap<I, R> {
     fn from(map: ParallelMapType<I, R>) -> Self {
         Self::DashMap(map.into_read_only())
@@ -211,6 +231,7 @@ impl<I: Index, R: Real> DensityMap<I, R> {
     pub fn to_vec(&self) -> Vec<(I, R)> {
         match self {
             DensityMap::Standard(map) => map.iter().map(|(&i, &r)| (i, r)).collect(),
+            #[cfg(feature = "parallel")]
             DensityMap::DashMap(map) => map.iter().map(|(&i, &r)| (i, r)).collect(),
         }
     }
@@ -219,27 +240,46 @@ impl<I: Index, R: Real> DensityMap<I, R> {
     pub fn len(&self) -> usize {
         match self {
             DensityMap::Standard(map) => map.len(),
+            #[cfg(feature = "parallel")]
             DensityMap::DashMap(map) => map.len(),
         }
     }
 
+    /// Returns the number of density entries the map can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        match self {
+            DensityMap::Standard(map) => map.capacity(),
+            #[cfg(feature = "parallel")]
+            DensityMap::DashMap(map) => map.capacity(),
+        }
+    }
+
+    /// Reserves capacity for at least the given number of additional entries, replaces itself by an empty standard map if not of standard type
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.standard_or_insert_mut().reserve(additional);
+    }
+
     /// Returns the density value at the specified flat point index
     pub fn get(&self, flat_point_index: I) -> Option<R> {
         match self {
             DensityMap::Standard(map) => map.get(&flat_point_index).copied(),
+            #[cfg(feature = "parallel")]
             DensityMap::DashMap(map) => map.get(&flat_point_index).copied(),
         }
     }
 
     /// Returns a mutable reference to the contained standard map, replaces itself if not of standard type
     fn standard_or_insert_mut(&mut self) -> &mut MapType<I, R> {
-        match self {
-            DensityMap::Standard(map) => return map,
-            _ => {}
+        #[cfg(feature = "parallel")]
+        if let DensityMap::DashMap(_) = self {
+            *self = new_map().into();
         }
 
-        *self = new_map().into();
-        self.standard_or_insert_mut()
+        match self {
+            DensityMap::Standard(map) => map,
+            #[cfg(feature = "parallel")]
+            DensityMap::DashMap(_) => unreachable!(),
+        }
     }
 
     /// Calls a closure for each `(flat_point_index, density_value)` tuple in the map
@@ -247,13 +287,204 @@ impl<I: Index, R: Real> DensityMap<I, R> {
         let mut f = f;
         match self {
             DensityMap::Standard(map) => map.iter().for_each(|(&i, &r)| f(i, r)),
+            #[cfg(feature = "parallel")]
             DensityMap::DashMap(map) => map.iter().for_each(|(&i, &r)| f(i, r)),
         }
     }
 }
 
+impl<I: Index, R: Real> FromIterator<(I, R)> for DensityMap<I, R> {
+    fn from_iter<T: IntoIterator<Item = (I, R)>>(iter: T) -> Self {
+        let mut map = new_map();
+        map.extend(iter);
+        map.into()
+    }
+}
+
+/// Intermediate results of a global reconstruction that are sufficient to triangulate its surface again
+///
+/// A snapshot can be obtained from a reconstruction using [`SurfaceReconstruction::density_map_snapshot`](crate::SurfaceReconstruction::density_map_snapshot),
+/// stored with [`DensityMapSnapshot::write`] and triangulated again with [`reconstruct_surface_from_density_map`](crate::reconstruct_surface_from_density_map),
+/// skipping the computation of the particle densities and of the density map.
+#[derive(Clone, Debug)]
+pub struct DensityMapSnapshot<I: Index, R: Real> {
+    /// Background grid of the density map
+    pub grid: UniformGrid<I, R>,
+    /// Density map that was triangulated, i.e. including the modifications by clipping planes
+    pub density_map: DensityMap<I, R>,
+    /// Per particle densities
+    pub particle_densities: Vec<R>,
+    /// Sorted indices of the particles that were classified as splash particles and excluded from the density map
+    pub splash_particle_indices: Option<Vec<usize>>,
+}
+
+/// Error type returned when reading a [`DensityMapSnapshot`] fails
+#[derive(Debug, ThisError)]
+#[non_exhaustive]
+pub enum DensityMapSnapshotError {
+    /// Error of the underlying reader
+    #[error("failed to read density map snapshot")]
+    Io(
+        #[source]
+        #[from]
+        std::io::Error,
+    ),
+    /// The data is not a density map snapshot or was written with an incompatible version
+    #[error("invalid density map snapshot: {0}")]
+    InvalidFormat(&'static str),
+}
+
+impl<I: Index, R: Real> DensityMapSnapshot<I, R> {
+    /// Magic bytes at the start of every snapshot
+    const MAGIC: &'static [u8; 8] = b"SPLSHDMS";
+    /// Version of the binary format, has to be increased for every incompatible change
+    const VERSION: u32 = 1;
+
+    /// Writes the snapshot in a compact binary format (little endian), the density values are sorted by their flat point index
+    pub fn write<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        profile!("DensityMapSnapshot::write");
+
+        let to_f64 = |value: R| value.to_f64().unwrap();
+        let to_i64 = |index: I| index.to_i64().unwrap();
+
+        writer.write_all(Self::MAGIC)?;
+        writer.write_all(&Self::VERSION.to_le_bytes())?;
+        writer.write_all(&[
+            std::mem::size_of::<I>() as u8,
+            std::mem::size_of::<R>() as u8,
+        ])?;
+
+        let grid_min = self.grid.aabb().min();
+        for i in 0..3 {
+            writer.write_all(&to_f64(grid_min[i]).to_le_bytes())?;
+        }
+        writer.write_all(&to_f64(self.grid.cell_size()).to_le_bytes())?;
+        for &n_cells in self.grid.cells_per_dim() {
+            writer.write_all(&to_i64(n_cells).to_le_bytes())?;
+        }
+
+        writer.write_all(&(self.particle_densities.len() as u64).to_le_bytes())?;
+        for &density in &self.particle_densities {
+            writer.write_all(&to_f64(density).to_le_bytes())?;
+        }
+
+        match &self.splash_particle_indices {
+            Some(splash_particles) => {
+                writer.write_all(&[1])?;
+                writer.write_all(&(splash_particles.len() as u64).to_le_bytes())?;
+                for &particle in splash_particles {
+                    writer.write_all(&(particle as u64).to_le_bytes())?;
+                }
+            }
+            None => writer.write_all(&[0])?,
+        }
+
+        let mut density_values = self.density_map.to_vec();
+        density_values.sort_unstable_by_key(|&(flat_point_index, _)| flat_point_index);
+        writer.write_all(&(density_values.len() as u64).to_le_bytes())?;
+        for (flat_point_index, density) in density_values {
+            writer.write_all(&to_i64(flat_point_index).to_le_bytes())?;
+            writer.write_all(&to_f64(density).to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a snapshot written by [`DensityMapSnapshot::write`] with the same index and real type
+    pub fn read<Rd: std::io::Read>(mut reader: Rd) -> Result<Self, DensityMapSnapshotError> {
+        profile!("DensityMapSnapshot::read");
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != Self::MAGIC {
+            return Err(DensityMapSnapshotError::InvalidFormat(
+                "missing magic bytes",
+            ));
+        }
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut version)?;
+        if u32::from_le_bytes(version) != Self::VERSION {
+            return Err(DensityMapSnapshotError::InvalidFormat(
+                "unsupported version",
+            ));
+        }
+        let mut type_sizes = [0u8; 2];
+        reader.read_exact(&mut type_sizes)?;
+        if type_sizes
+            != [
+                std::mem::size_of::<I>() as u8,
+                std::mem::size_of::<R>() as u8,
+            ]
+        {
+            return Err(DensityMapSnapshotError::InvalidFormat(
+                "index or real type does not match",
+            ));
+        }
+
+        let invalid_value = || DensityMapSnapshotError::InvalidFormat("value out of range");
+        let read_real = |bits: u64| R::from_f64(f64::from_bits(bits)).ok_or_else(invalid_value);
+        let read_index = |bits: u64| I::from_i64(bits as i64).ok_or_else(invalid_value);
+
+        let mut grid_min = Vector3::zeros();
+        for i in 0..3 {
+            grid_min[i] = read_real(read_u64(&mut reader)?)?;
+        }
+        let cell_size = read_real(read_u64(&mut reader)?)?;
+        let mut n_cells = [I::zero(); 3];
+        for n in n_cells.iter_mut() {
+            *n = read_index(read_u64(&mut reader)?)?;
+        }
+        let grid = UniformGrid::new(&grid_min, &n_cells, cell_size)
+            .map_err(|_| DensityMapSnapshotError::InvalidFormat("invalid grid"))?;
+
+        let num_particles = read_u64(&mut reader)?;
+        let particle_densities = (0..num_particles)
+            .map(|_| read_real(read_u64(&mut reader)?))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut has_splash_particles = [0u8; 1];
+        reader.read_exact(&mut has_splash_particles)?;
+        let splash_particle_indices = match has_splash_particles[0] {
+            0 => None,
+            1 => {
+                let num_splash_particles = read_u64(&mut reader)?;
+                Some(
+                    (0..num_splash_particles)
+                        .map(|_| Ok(read_u64(&mut reader)? as usize))
+                        .collect::<Result<Vec<_>, DensityMapSnapshotError>>()?,
+                )
+            }
+            _ => return Err(DensityMapSnapshotError::InvalidFormat("invalid flag")),
+        };
+
+        let num_values = read_u64(&mut reader)?;
+        let density_map = (0..num_values)
+            .map(|_| {
+                let flat_point_index = read_index(read_u64(&mut reader)?)?;
+                let density = read_real(read_u64(&mut reader)?)?;
+                Ok((flat_point_index, density))
+            })
+            .collect::<Result<DensityMap<I, R>, DensityMapSnapshotError>>()?;
+
+        Ok(Self {
+            grid,
+            density_map,
+            particle_densities,
+            splash_particle_indices,
+        })
+    }
+}
+
+/// Reads a little endian `u64` value
+fn read_u64<Rd: std::io::Read>(reader: &mut Rd) -> std::io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
 /// Computes a sparse density map for the fluid based on the specified background grid
 #[inline(never)]
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
 pub fn generate_sparse_density_map<I: Index, R: Real>(
     grid: &UniformGrid<I, R>,
     subdomain: Option<&OwningSubdomainGrid<I, R>>,
@@ -275,23 +506,23 @@ pub fn generate_sparse_density_map<I: Index, R: Real>(
         }
     );
 
-    if let Some(subdomain) = subdomain {
-        if allow_threading {
+    match subdomain {
+        #[cfg(feature = "parallel")]
+        Some(_) if allow_threading => {
             panic!("Multi threading not implemented for density map with subdomain");
-        } else {
-            sequential_generate_sparse_density_map_subdomain(
-                subdomain,
-                particle_positions,
-                particle_densities,
-                active_particles,
-                particle_rest_mass,
-                compact_support_radius,
-                cube_size,
-                density_map,
-            )?;
         }
-    } else {
-        if allow_threading {
+        Some(subdomain) => sequential_generate_sparse_density_map_subdomain(
+            subdomain,
+            particle_positions,
+            particle_densities,
+            active_particles,
+            particle_rest_mass,
+            compact_support_radius,
+            cube_size,
+            density_map,
+        )?,
+        #[cfg(feature = "parallel")]
+        None if allow_threading => {
             *density_map = parallel_generate_sparse_density_map(
                 grid,
                 particle_positions,
@@ -301,17 +532,17 @@ pub fn generate_sparse_density_map<I: Index, R: Real>(
                 compact_support_radius,
                 cube_size,
             )?
-        } else {
-            *density_map = sequential_generate_sparse_density_map(
-                grid,
-                particle_positions,
-                particle_densities,
-                active_particles,
-                particle_rest_mass,
-                compact_support_radius,
-                cube_size,
-            )?
         }
+        None => sequential_generate_sparse_density_map_inplace(
+            grid,
+            particle_positions,
+            particle_densities,
+            active_particles,
+            particle_rest_mass,
+            compact_support_radius,
+            cube_size,
+            density_map,
+        )?,
     };
 
     trace!(
@@ -333,9 +564,36 @@ pub fn sequential_generate_sparse_density_map<I: Index, R: Real>(
     compact_support_radius: R,
     cube_size: R,
 ) -> Result<DensityMap<I, R>, DensityMapError<R>> {
+    let mut density_map = new_map().into();
+    sequential_generate_sparse_density_map_inplace(
+        grid,
+        particle_positions,
+        particle_densities,
+        active_particles,
+        particle_rest_mass,
+        compact_support_radius,
+        cube_size,
+        &mut density_map,
+    )?;
+    Ok(density_map)
+}
+
+/// Computes a sparse density map for the fluid based on the specified background grid, sequential implementation reusing the memory of the given map
+#[inline(never)]
+pub fn sequential_generate_sparse_density_map_inplace<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    particle_positions: &[Vector3<R>],
+    particle_densities: &[R],
+    active_particles: Option<&[usize]>,
+    particle_rest_mass: R,
+    compact_support_radius: R,
+    cube_size: R,
+    density_map: &mut DensityMap<I, R>,
+) -> Result<(), DensityMapError<R>> {
     profile!("sequential_generate_sparse_density_map");
 
-    let mut sparse_densities = new_map();
+    let mut sparse_densities = density_map.standard_or_insert_mut();
+    sparse_densities.clear();
 
     let density_map_generator = SparseDensityMapGenerator::try_new(
         grid,
@@ -344,6 +602,10 @@ pub fn sequential_generate_sparse_density_map<I: Index, R: Real>(
         particle_rest_mass,
     )?;
 
+    let progress = ProgressLogger::start("density map", "particles", || {
+        active_particles.map_or(particle_positions.len(), |indices| indices.len())
+    });
+
     let process_particle = |particle_data: (&Vector3<R>, R)| {
         let (particle, particle_density) = particle_data;
         density_map_generator.compute_particle_density_contribution(
@@ -352,6 +614,9 @@ pub fn sequential_generate_sparse_density_map<I: Index, R: Real>(
             particle,
             particle_density,
         );
+        if let Some(progress) = &progress {
+            progress.add(1);
+        }
     };
 
     match active_particles {
@@ -366,7 +631,7 @@ pub fn sequential_generate_sparse_density_map<I: Index, R: Real>(
             .for_each(process_particle),
     }
 
-    Ok(sparse_densities.into())
+    Ok(())
 }
 
 /// Computes a sparse density map for the fluid restricted to the specified subdomain
@@ -393,6 +658,10 @@ pub fn sequential_generate_sparse_density_map_subdomain<I: Index, R: Real>(
         particle_rest_mass,
     )?;
 
+    let progress = ProgressLogger::start("density map", "particles", || {
+        active_particles.map_or(particle_positions.len(), |indices| indices.len())
+    });
+
     let process_particle = |particle_data: (&Vector3<R>, R)| {
         let (particle, particle_density) = particle_data;
         density_map_generator.compute_particle_density_contribution_subdomain(
@@ -401,6 +670,9 @@ pub fn sequential_generate_sparse_density_map_subdomain<I: Index, R: Real>(
             particle,
             particle_density,
         );
+        if let Some(progress) = &progress {
+            progress.add(1);
+        }
     };
 
     match active_particles {
@@ -420,6 +692,8 @@ pub fn sequential_generate_sparse_density_map_subdomain<I: Index, R: Real>(
 
 /// Computes a sparse density map for the fluid based on the specified background grid, multi-threaded implementation
 #[inline(never)]
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
 pub fn parallel_generate_sparse_density_map<I: Index, R: Real>(
     grid: &UniformGrid<I, R>,
     particle_positions: &[Vector3<R>],
@@ -444,6 +718,9 @@ pub fn parallel_generate_sparse_density_map<I: Index, R: Real>(
         )?;
 
         profile!("generate thread local maps");
+        let progress = ProgressLogger::start("density map", "particles", || {
+            active_particles.map_or(particle_positions.len(), |indices| indices.len())
+        });
 
         match active_particles {
             // Process particles, when no list of active particles was provided
@@ -477,6 +754,10 @@ pub fn parallel_generate_sparse_density_map<I: Index, R: Real>(
                             .iter()
                             .zip(density_chunk.iter().copied())
                             .for_each(process_particle_map);
+
+                        if let Some(progress) = &progress {
+                            progress.add(position_chunk.len());
+                        }
                     })
             }
             // Process particles, when only a subset is active
@@ -505,6 +786,10 @@ pub fn parallel_generate_sparse_density_map<I: Index, R: Real>(
                         .iter()
                         .map(|&i| (&particle_positions[i], particle_densities[i]))
                         .for_each(process_particle_map);
+
+                    if let Some(progress) = &progress {
+                        progress.add(index_chunk.len());
+                    }
                 });
             }
         }
@@ -537,6 +822,204 @@ pub fn parallel_generate_sparse_density_map<I: Index, R: Real>(
     }
 }
 
+/// Clips the density map with the given half-spaces such that all points on the clipped side of any plane are below the iso-surface threshold
+///
+/// The density values are replaced by the minimum of the density and a linear function of the signed
+/// distance to the closest plane that is equal to the iso-surface threshold on the plane and decreases
+/// by half the threshold value per `cube_size` towards the clipped side. Assuming the density in the
+/// fluid interior is at least 1.5 times the iso-surface threshold, marching cubes then places the
+/// vertices of the resulting cut exactly on the planes, closing the surface along the planes.
+///
+/// Returns the number of density values that were reduced by the clipping.
+pub fn clip_density_map<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    clipping_planes: &[ClippingPlane<R>],
+    iso_surface_threshold: R,
+    cube_size: R,
+    density_map: &mut DensityMap<I, R>,
+) -> usize {
+    if clipping_planes.is_empty() {
+        return 0;
+    }
+
+    profile!("clip_density_map");
+
+    let clip_value = |flat_point_index: I, value: R| -> R {
+        let point = grid
+            .try_unflatten_point_index(flat_point_index)
+            .expect("Density map contains point that is not part of the grid");
+        clip_density_value(
+            &grid.point_coordinates(&point),
+            value,
+            clipping_planes,
+            iso_surface_threshold,
+            cube_size,
+        )
+    };
+
+    match density_map {
+        DensityMap::Standard(map) => {
+            let mut num_clamped = 0;
+            for (&i, value) in map.iter_mut() {
+                let clipped = clip_value(i, *value);
+                if clipped < *value {
+                    num_clamped += 1;
+                }
+                *value = clipped;
+            }
+            num_clamped
+        }
+        #[cfg(feature = "parallel")]
+        DensityMap::DashMap(_) => {
+            let map = match std::mem::replace(density_map, new_map().into()) {
+                DensityMap::DashMap(map) => map.into_inner(),
+                DensityMap::Standard(_) => unreachable!(),
+            };
+            let num_clamped = AtomicUsize::new(0);
+            map.alter_all(|&i, value| {
+                let clipped = clip_value(i, value);
+                if clipped < value {
+                    num_clamped.fetch_add(1, Ordering::Relaxed);
+                }
+                clipped
+            });
+            *density_map = map.into();
+            num_clamped.into_inner()
+        }
+    }
+}
+
+/// Returns the density value at the given point after clipping with the given half-spaces, see [`clip_density_map`]
+pub(crate) fn clip_density_value<R: Real>(
+    x: &Vector3<R>,
+    value: R,
+    clipping_planes: &[ClippingPlane<R>],
+    iso_surface_threshold: R,
+    cube_size: R,
+) -> R {
+    if clipping_planes.is_empty() {
+        return value;
+    }
+
+    let slope = iso_surface_threshold / (cube_size + cube_size);
+    let distance = clipping_planes
+        .iter()
+        .map(|plane| plane.signed_distance(x))
+        .fold(<R as Bounded>::max_value(), |a, b| a.min(b));
+    value.min((iso_surface_threshold + slope * distance).max(R::zero()))
+}
+
+/// Blends the density map with the density map of a previous frame on the same grid: `d = α·d + (1 - α)·d_previous` per grid point
+///
+/// Grid points that are missing in one of the maps are treated as zero density, i.e. points that are only contained
+/// in the previous map are added to the density map with their weighted previous value. As the blended values are
+/// convex combinations, every cell with a point that is missing in both maps stays below the iso-surface threshold.
+/// Used for the temporal smoothing of the density field, see [`Parameters::temporal_density_smoothing`](crate::Parameters::temporal_density_smoothing).
+pub fn blend_density_maps<I: Index, R: Real>(
+    density_map: &mut DensityMap<I, R>,
+    previous_density_map: &DensityMap<I, R>,
+    alpha: R,
+) {
+    profile!("blend_density_maps");
+
+    // Points of the previous map are inserted, which requires a standard map
+    #[cfg(feature = "parallel")]
+    if let DensityMap::DashMap(map) = density_map {
+        let standard_map = map.iter().map(|(&i, &d)| (i, d)).collect();
+        *density_map = standard_map;
+    }
+
+    let map = density_map.standard_or_insert_mut();
+    for density in map.values_mut() {
+        *density *= alpha;
+    }
+
+    let previous_weight = R::one() - alpha;
+    previous_density_map.for_each(|i, previous_density| {
+        *map.entry(i).or_insert_with(R::zero) += previous_weight * previous_density;
+    });
+}
+
+/// Resamples a density map onto another grid with the same cell size using trilinear interpolation
+///
+/// Points of the source grid that are missing in the density map and points outside of the source grid are treated
+/// as zero density. The resampled map only contains the points of the target grid with a non-zero interpolation
+/// weight of at least one point of the source map. Returns `None` if the cell sizes of the grids differ.
+pub fn resample_density_map<I: Index, R: Real>(
+    source_grid: &UniformGrid<I, R>,
+    density_map: &DensityMap<I, R>,
+    target_grid: &UniformGrid<I, R>,
+) -> Option<DensityMap<I, R>> {
+    if source_grid.cell_size() != target_grid.cell_size() {
+        return None;
+    }
+
+    profile!("resample_density_map");
+
+    let cell_size = source_grid.cell_size();
+    // Lower corner of the enclosing cell and the local coordinates of a point in the cell of the given grid
+    let enclosing_cell = |grid: &UniformGrid<I, R>, x: &Vector3<R>| {
+        let normalized = (x - grid.aabb().min()) / cell_size;
+        let lower = normalized.map(|c| c.floor());
+        (lower, normalized - lower)
+    };
+    let interpolate = |x: &Vector3<R>| {
+        let (lower, t) = enclosing_cell(source_grid, x);
+        let mut value = R::zero();
+        for (offset, flat_point_index) in existing_cell_corners(source_grid, lower) {
+            let weight = (0..3)
+                .map(|d| {
+                    if offset[d] == 1 {
+                        t[d]
+                    } else {
+                        R::one() - t[d]
+                    }
+                })
+                .fold(R::one(), |a, b| a * b);
+            if weight > R::zero() {
+                value += weight * density_map.get(flat_point_index).unwrap_or(R::zero());
+            }
+        }
+        value
+    };
+
+    // With equal cell sizes, a source point only has a non-zero weight for the corners of its enclosing target cell
+    let mut resampled = new_map();
+    density_map.for_each(|flat_point_index, _| {
+        let point = source_grid
+            .try_unflatten_point_index(flat_point_index)
+            .expect("Density map contains point that is not part of the grid");
+        let (lower, _) = enclosing_cell(target_grid, &source_grid.point_coordinates(&point));
+        for (_, target_point_index) in existing_cell_corners(target_grid, lower) {
+            resampled.entry(target_point_index).or_insert_with(|| {
+                let target_point = target_grid
+                    .try_unflatten_point_index(target_point_index)
+                    .unwrap();
+                interpolate(&target_grid.point_coordinates(&target_point))
+            });
+        }
+    });
+
+    Some(resampled.into())
+}
+
+/// Returns the offsets and flat point indices of the corners of the cell with the given (real-valued) lower corner indices that are points of the grid
+fn existing_cell_corners<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    lower: Vector3<R>,
+) -> impl Iterator<Item = (Vector3<usize>, I)> + '_ {
+    (0..8).filter_map(move |corner: usize| {
+        let offset = Vector3::new(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+        let ijk = [
+            (lower.x + R::from_usize(offset.x)?).to_index()?,
+            (lower.y + R::from_usize(offset.y)?).to_index()?,
+            (lower.z + R::from_usize(offset.z)?).to_index()?,
+        ];
+        grid.get_point(ijk)
+            .map(|point| (offset, grid.flatten_point_index(&point)))
+    })
+}
+
 /// Internal helper type used to evaluate the density contribution for a particle
 struct SparseDensityMapGenerator<I: Index, R: Real> {
     particle_rest_mass: R,
@@ -581,6 +1064,51 @@ pub(crate) fn compute_kernel_evaluation_radius<I: Index, R: Real>(
     }
 }
 
+/// Returns the domain of particles that can contribute to a density map on the given grid, i.e. the grid AABB shrunk by the kernel evaluation radius
+pub(crate) fn allowed_particle_domain<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    kernel_evaluation_radius: R,
+) -> AxisAlignedBoundingBox3d<R> {
+    let mut aabb = grid.aabb().clone();
+    aabb.grow_uniformly(kernel_evaluation_radius.neg());
+    aabb
+}
+
+/// Returns the number of particles that are skipped by the density map generation on the given grid because they are outside of its allowed domain
+///
+/// The allowed domain is the AABB of the grid shrunk by the kernel evaluation radius, i.e. particles have to be far
+/// enough from the boundary of the grid to contribute their full kernel support to the density map.
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+pub fn count_particles_outside_allowed_domain<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    particle_positions: &[Vector3<R>],
+    compact_support_radius: R,
+    cube_size: R,
+    enable_multi_threading: bool,
+) -> usize {
+    profile!("count_particles_outside_allowed_domain");
+
+    let allowed_domain = allowed_particle_domain(
+        grid,
+        compute_kernel_evaluation_radius::<I, R>(compact_support_radius, cube_size)
+            .kernel_evaluation_radius,
+    );
+    let is_outside = |particle: &Vector3<R>| !allowed_domain.contains_point(particle);
+
+    #[cfg(feature = "parallel")]
+    if enable_multi_threading {
+        return particle_positions
+            .par_iter()
+            .filter(|particle| is_outside(particle))
+            .count();
+    }
+
+    particle_positions
+        .iter()
+        .filter(|particle| is_outside(particle))
+        .count()
+}
+
 // TODO: Maybe remove allowed domain check? And require this is done before, using the active_particles array?
 impl<I: Index, R: Real> SparseDensityMapGenerator<I, R> {
     fn try_new(
@@ -605,11 +1133,7 @@ impl<I: Index, R: Real> SparseDensityMapGenerator<I, R> {
         //
         // This also implies that this density map should always represent a closed surfaces.
         // If particles were closer to the AABB boundary than this margin, there could be holes in the resulting level-set.
-        let allowed_domain = {
-            let mut aabb = grid.aabb().clone();
-            aabb.grow_uniformly(kernel_evaluation_radius.neg());
-            aabb
-        };
+        let allowed_domain = allowed_particle_domain(grid, kernel_evaluation_radius);
 
         if allowed_domain.is_degenerate() || !allowed_domain.is_consistent() {
             warn!(
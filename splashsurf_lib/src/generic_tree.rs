@@ -4,25 +4,57 @@
 //! mutable access the [`TreeNodeMut`] trait. These traits only require to give access to a slice
 //! of child nodes of a node.
 //!
-//! This module provides algorithms for breadth-first and depth-first visitation.
+//! This module provides algorithms for breadth-first, level-order and depth-first visitation.
+//! All sequential algorithms use an explicit stack or queue instead of recursion, so deep trees
+//! cannot overflow the call stack.
 //! Overview of the traits:
 //!  - [`VisitableTree`] provides non-mutable sequential iteration.
 //!  - [`MutVisitableTree`] provides sequential visitation using a visitor function with mutable access to the current node.
 //!  - [`ParVisitableTree`] provides parallel visitation using a visitor function, parallelized using rayon.
 //!  - [`ParMutVisitableTree`] provides parallel visitation using a visitor function with mutable access to the current node, parallelized using rayon.
+//!  - [`TreeMetrics`] provides node/leaf counts, depths and linear node ids. It has to be implemented explicitly
+//!    (usually with an empty `impl` block) so that the default implementations can be overridden, e.g. by cached values.
+//!
+//! If the `parallel` feature is disabled, the "parallel" visitation functions process the nodes
+//! sequentially in the same order (breadth-first or depth-first post-order) instead.
 //!
 //! Note that the mutation of nodes during visitation is safe as the mutation is only possible either
 //! before the children are enqueued or after the children were already processed.
 //!
+//! The `*_controlled` visitation functions use visitors that return a [`ControlFlow`]: with
+//! `ControlFlow::Continue(Descend::No)` the children of the node are skipped (i.e. the subtree is pruned)
+//! and `ControlFlow::Break(value)` stops the visitation and returns the value.
+//!
+//! The parallel visitation functions have `*_with_granularity` variants that take a predicate in addition
+//! to the visitor: the subtree of every node for which the predicate returns `true` is visited sequentially in a
+//! single task instead of spawning a task per node. This avoids the task overhead for trees with many small nodes.
+//!
+//! To collect a result per node from a parallel visitation, use [`ParVisitableTree::par_visit_map`] or
+//! [`ParMutVisitableTree::par_visit_mut_map`]. The results are returned sorted by a key provided by the
+//! visitor (e.g. a stable node id), so their order does not depend on the scheduling of the tasks.
+//!
 
+use parking_lot::Mutex;
+#[cfg(feature = "parallel")]
 use parking_lot::RwLock;
+#[cfg(feature = "parallel")]
 use rayon::{Scope, ScopeFifo};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::iter::FusedIterator;
-use std::ops::{Deref, DerefMut};
+use std::ops::{ControlFlow, Deref, DerefMut};
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "parallel")]
 use std::sync::Arc;
 
-// TODO: Tests for the algorithms
+/// Returned by the visitors of the `*_controlled` visitation functions to decide whether the children of a node are visited
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Descend {
+    /// Visit the children of the node
+    Yes,
+    /// Skip the children of the node and all of their descendants
+    No,
+}
 
 /// Trait that has to be implemented by tree-like structures to make them visitable
 pub trait TreeNode {
@@ -47,6 +79,123 @@ pub trait VisitableTree: TreeNode {
     fn bfs_iter<'a>(&'a self) -> BfsIter<'a, Self> {
         BfsIter::new(self)
     }
+
+    /// Visits a node and its children in breadth-first order.
+    fn visit_bfs<F: FnMut(&Self)>(&self, visitor: F) {
+        self.bfs_iter().for_each(visitor);
+    }
+
+    /// Visits a node and its children level by level, i.e. all nodes of a depth are visited before the nodes of the next depth. The visitor gets the depth of each node relative to this node (depth 0).
+    fn visit_level_order<F: FnMut(usize, &Self)>(&self, mut visitor: F) {
+        let mut queue_down = VecDeque::new();
+        queue_down.push_back((0, self));
+
+        while let Some((depth, current_node)) = queue_down.pop_front() {
+            visitor(depth, current_node);
+            queue_down.extend(
+                current_node
+                    .children()
+                    .iter()
+                    .map(|child| (depth + 1, child.deref())),
+            );
+        }
+    }
+
+    /// Visits a node and its children in depth-first pre-order, the visitor can skip the children of a node and stop the visitation with a result.
+    fn visit_dfs_controlled<'a, B, F>(&'a self, mut visitor: F) -> ControlFlow<B>
+    where
+        F: FnMut(&'a Self) -> ControlFlow<B, Descend>,
+    {
+        let mut stack = vec![self];
+
+        while let Some(current_node) = stack.pop() {
+            if visitor(current_node)? == Descend::Yes {
+                stack.extend(current_node.children().iter().rev().map(Deref::deref));
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Visits a node and its children in breadth-first order, the visitor can skip the children of a node and stop the visitation with a result.
+    fn visit_bfs_controlled<'a, B, F>(&'a self, visitor: F) -> ControlFlow<B>
+    where
+        F: FnMut(&'a Self) -> ControlFlow<B, Descend>,
+    {
+        visit_bfs_controlled_impl(self, visitor)
+    }
+}
+
+/// Trait for simple metrics of trees, all methods have default implementations based on sequential traversal
+///
+/// Unlike the visitation traits, this trait is not implemented automatically so that types can override the
+/// default implementations, e.g. to return cached values.
+pub trait TreeMetrics: TreeNode + Sized {
+    /// Returns the number of nodes of the tree with this node as root (including this node)
+    fn node_count(&self) -> usize {
+        self.dfs_iter().count()
+    }
+
+    /// Returns the number of leaves (nodes without children) of the tree with this node as root
+    fn leaf_count(&self) -> usize {
+        self.dfs_iter()
+            .filter(|node| node.children().is_empty())
+            .count()
+    }
+
+    /// Returns the maximum depth of all nodes relative to this node, i.e. zero if this node has no children
+    fn max_depth(&self) -> usize {
+        let mut max_depth = 0;
+        self.visit_level_order(|depth, _| max_depth = depth);
+        max_depth
+    }
+
+    /// Returns the depth of the given node relative to this node or `None` if the node is not part of this tree (nodes are compared by address)
+    fn depth_of(&self, node: &Self) -> Option<usize> {
+        let mut stack = vec![(0, self)];
+
+        while let Some((depth, current_node)) = stack.pop() {
+            if std::ptr::eq(current_node, node) {
+                return Some(depth);
+            }
+            stack.extend(
+                current_node
+                    .children()
+                    .iter()
+                    .map(|child| (depth + 1, child.deref())),
+            );
+        }
+
+        None
+    }
+
+    /// Returns a map from the address of each node to its linear id, i.e. its index in breadth-first order
+    ///
+    /// The ids are stable as long as the tree is neither modified nor moved.
+    fn linear_ids(&self) -> HashMap<*const Self, usize> {
+        self.bfs_iter()
+            .enumerate()
+            .map(|(id, node)| (node as *const Self, id))
+            .collect()
+    }
+}
+
+/// Sequential breadth-first visitation with a visitor that controls the traversal, also used by [`ParVisitableTree`] without the `parallel` feature
+fn visit_bfs_controlled_impl<'a, T, B, F>(node: &'a T, mut visitor: F) -> ControlFlow<B>
+where
+    T: TreeNode + ?Sized,
+    F: FnMut(&'a T) -> ControlFlow<B, Descend>,
+{
+    let mut queue_down = VecDeque::new();
+    queue_down.push_back(node);
+
+    while let Some(current_node) = queue_down.pop_front() {
+        if visitor(current_node)? == Descend::Yes {
+            queue_down.extend(current_node.children().iter().map(Deref::deref));
+        }
+    }
+
+    ControlFlow::Continue(())
 }
 
 /// Trait for sequential tree visitation algorithms that support mutation during visitation. Automatically implemented for types that implement [`TreeNodeMut`].
@@ -83,6 +232,22 @@ pub trait MutVisitableTree: TreeNodeMut {
             );
         }
     }
+
+    /// Visits a node and its children level by level, i.e. all nodes of a depth are visited before the nodes of the next depth. The visitor gets the depth of each node relative to this node (depth 0) and is applied before enqueuing each node's children.
+    fn visit_mut_level_order<F: FnMut(usize, &mut Self)>(&mut self, mut visitor: F) {
+        let mut queue_down = VecDeque::new();
+        queue_down.push_back((0, self));
+
+        while let Some((depth, current_node)) = queue_down.pop_front() {
+            visitor(depth, current_node);
+            queue_down.extend(
+                current_node
+                    .children_mut()
+                    .iter_mut()
+                    .map(|child| (depth + 1, child.deref_mut())),
+            );
+        }
+    }
 }
 
 /// Depth-first search iterator returned by the [`VisitableTree::dfs_iter`] function
@@ -131,7 +296,7 @@ impl<'a, T: TreeNode + ?Sized> Iterator for BfsIter<'a, T> {
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(current_node) = self.queue.pop_front() {
             self.queue
-                .extend(current_node.children().iter().rev().map(Deref::deref));
+                .extend(current_node.children().iter().map(Deref::deref));
             Some(current_node)
         } else {
             None
@@ -144,51 +309,92 @@ impl<'a, T: TreeNode + ?Sized> FusedIterator for BfsIter<'a, T> {}
 /// Trait for non-mutable parallel tree visitation algorithms. Automatically implemented for types that implement [`TreeNode`] and [`ThreadSafe`](crate::ThreadSafe).
 pub trait ParVisitableTree: TreeNode {
     /// Visits a node and its children in breadth-first order. The visitor is applied in parallel to processing the children.
+    #[cfg(feature = "parallel")]
     fn par_visit_bfs<F>(&self, visitor: F)
     where
         Self: Sync,
         F: Fn(&Self) + Sync,
+    {
+        self.par_visit_bfs_with_granularity(|_| false, visitor);
+    }
+
+    /// Visits a node and its children in breadth-first order. The visitor is applied in parallel to processing the children.
+    /// The subtrees of all nodes for which `sequential` returns `true` are visited sequentially in a single task.
+    #[cfg(feature = "parallel")]
+    fn par_visit_bfs_with_granularity<P, F>(&self, sequential: P, visitor: F)
+    where
+        Self: Sync,
+        P: Fn(&Self) -> bool + Sync,
+        F: Fn(&Self) + Sync,
     {
         // Parallel implementation of recursive breadth-first visitation
-        fn par_visit_bfs_impl<'scope, T, F>(
+        fn par_visit_bfs_impl<'scope, T, P, F>(
             node: &'scope T,
             s: &ScopeFifo<'scope>,
+            sequential: &'scope P,
             visitor: &'scope F,
         ) where
             T: TreeNode + Sync + ?Sized,
+            P: Fn(&T) -> bool + Sync,
             F: Fn(&T) + Sync,
         {
+            // Spawn a single task for the whole subtree if it's small enough
+            if sequential(node) {
+                s.spawn_fifo(move |_| BfsIter::new(node).for_each(visitor));
+                return;
+            }
+
             // Spawn task for visitor
             s.spawn_fifo(move |_| visitor(node));
 
             // Spawn tasks for all children
             for child in node.children().iter().map(Deref::deref) {
-                s.spawn_fifo(move |s| par_visit_bfs_impl(child, s, visitor));
+                s.spawn_fifo(move |s| par_visit_bfs_impl(child, s, sequential, visitor));
             }
         }
 
-        let v = &visitor;
-        rayon::scope_fifo(move |s| par_visit_bfs_impl(self, s, v));
+        let (p, v) = (&sequential, &visitor);
+        rayon::scope_fifo(move |s| par_visit_bfs_impl(self, s, p, v));
     }
 
     /// Visits a node and its children in breadth-first order, stops visitation on first error and returns it. The visitor is applied in parallel to processing the children.
+    #[cfg(feature = "parallel")]
     fn try_par_visit_bfs<E, F>(&self, visitor: F) -> Result<(), E>
     where
         Self: Sync,
         E: Send + Sync,
         F: Fn(&Self) -> Result<(), E> + Sync,
+    {
+        self.try_par_visit_bfs_with_granularity(|_| false, visitor)
+    }
+
+    /// Visits a node and its children in breadth-first order, stops visitation on first error and returns it. The visitor is applied in parallel to processing the children.
+    /// The subtrees of all nodes for which `sequential` returns `true` are visited sequentially in a single task.
+    #[cfg(feature = "parallel")]
+    fn try_par_visit_bfs_with_granularity<E, P, F>(
+        &self,
+        sequential: P,
+        visitor: F,
+    ) -> Result<(), E>
+    where
+        Self: Sync,
+        E: Send + Sync,
+        P: Fn(&Self) -> bool + Sync,
+        F: Fn(&Self) -> Result<(), E> + Sync,
     {
         let error = Arc::new(RwLock::new(Ok(())));
 
         // Parallel implementation of recursive breadth-first visitation
-        fn try_par_visit_bfs_impl<'scope, T, E, F>(
+        fn try_par_visit_bfs_impl<'scope, T, E, P, F>(
             node: &'scope T,
             s: &ScopeFifo<'scope>,
             error: Arc<RwLock<Result<(), E>>>,
+            sequential: &'scope P,
             visitor: &'scope F,
         ) where
             T: TreeNode + Sync + ?Sized,
             E: Send + Sync + 'scope,
+            P: Fn(&T) -> bool + Sync,
             F: Fn(&T) -> Result<(), E> + Sync,
         {
             // Stop recursion if there is already an error
@@ -196,37 +402,61 @@ pub trait ParVisitableTree: TreeNode {
                 return;
             }
 
+            // Visit the whole subtree in a single task if it's small enough
+            let visit_subtree = sequential(node);
+
             // Spawn task for visitor
             {
                 let error = error.clone();
                 s.spawn_fifo(move |_| {
-                    // Only run visitor if there was no error in the meantime
-                    if error.read().is_ok() {
-                        // Run visitor and check returned result
-                        let res = visitor(node);
-                        if res.is_err() {
-                            let mut error_guard = error.write();
-                            // Don't overwrite error if there is already one
-                            if !error_guard.is_err() {
-                                *error_guard = res;
+                    // Returns whether the visitation should be continued
+                    let visit = |node: &T| -> bool {
+                        // Only run visitor if there was no error in the meantime
+                        if error.read().is_ok() {
+                            // Run visitor and check returned result
+                            let res = visitor(node);
+                            if res.is_err() {
+                                let mut error_guard = error.write();
+                                // Don't overwrite error if there is already one
+                                if !error_guard.is_err() {
+                                    *error_guard = res;
+                                }
+                                return false;
                             }
+                            true
+                        } else {
+                            false
                         }
+                    };
+
+                    if visit_subtree {
+                        for node in BfsIter::new(node) {
+                            if !visit(node) {
+                                break;
+                            }
+                        }
+                    } else {
+                        visit(node);
                     }
                 });
             }
 
+            if visit_subtree {
+                return;
+            }
+
             // Spawn tasks for all children
             for child in node.children().iter().map(Deref::deref) {
                 let error = error.clone();
-                s.spawn_fifo(move |s| try_par_visit_bfs_impl(child, s, error, visitor));
+                s.spawn_fifo(move |s| try_par_visit_bfs_impl(child, s, error, sequential, visitor));
             }
         }
 
         // Start the visitation
         {
-            let v = &visitor;
+            let (p, v) = (&sequential, &visitor);
             let e = error.clone();
-            rayon::scope_fifo(move |s| try_par_visit_bfs_impl(self, s, e, v));
+            rayon::scope_fifo(move |s| try_par_visit_bfs_impl(self, s, e, p, v));
         }
 
         // Return any potential error collected during visitation
@@ -239,83 +469,286 @@ pub trait ParVisitableTree: TreeNode {
             Ok(())
         }
     }
+
+    /// Visits a node and its children in breadth-first order, the visitor can skip the children of a node and stop the visitation with a result. The visitor is applied before spawning tasks for the children of a node. Parallel version.
+    ///
+    /// Stopping is best-effort: nodes that are already being visited in parallel when the visitation is stopped are
+    /// still processed, only the result of the first [`ControlFlow::Break`] is returned.
+    #[cfg(feature = "parallel")]
+    fn par_visit_bfs_controlled<'a, B, F>(&'a self, visitor: F) -> ControlFlow<B>
+    where
+        Self: Sync,
+        B: Send,
+        F: Fn(&'a Self) -> ControlFlow<B, Descend> + Sync,
+    {
+        // Parallel implementation of recursive breadth-first visitation
+        fn par_visit_bfs_controlled_impl<'a: 'scope, 'scope, T, B, F>(
+            node: &'a T,
+            s: &ScopeFifo<'scope>,
+            stopped: &'scope AtomicBool,
+            result: &'scope Mutex<Option<B>>,
+            visitor: &'scope F,
+        ) where
+            T: TreeNode + Sync + ?Sized,
+            B: Send,
+            F: Fn(&'a T) -> ControlFlow<B, Descend> + Sync,
+        {
+            // Stop recursion if another task already stopped the visitation
+            if stopped.load(Ordering::Relaxed) {
+                return;
+            }
+
+            match visitor(node) {
+                ControlFlow::Continue(Descend::Yes) => {
+                    // Spawn tasks for all children
+                    for child in node.children().iter().map(Deref::deref) {
+                        s.spawn_fifo(move |s| {
+                            par_visit_bfs_controlled_impl(child, s, stopped, result, visitor)
+                        });
+                    }
+                }
+                ControlFlow::Continue(Descend::No) => {}
+                ControlFlow::Break(value) => {
+                    let mut result = result.lock();
+                    // Don't overwrite the result of a task that stopped earlier
+                    if result.is_none() {
+                        *result = Some(value);
+                    }
+                    stopped.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+
+        let stopped = AtomicBool::new(false);
+        let result = Mutex::new(None);
+        {
+            let (st, r, v) = (&stopped, &result, &visitor);
+            rayon::scope_fifo(move |s| par_visit_bfs_controlled_impl(self, s, st, r, v));
+        }
+
+        match result.into_inner() {
+            Some(value) => ControlFlow::Break(value),
+            None => ControlFlow::Continue(()),
+        }
+    }
+
+    /// Visits a node and its children in breadth-first order. Sequential version as the `parallel` feature is disabled.
+    #[cfg(not(feature = "parallel"))]
+    fn par_visit_bfs<F>(&self, visitor: F)
+    where
+        Self: Sync,
+        F: Fn(&Self) + Sync,
+    {
+        BfsIter::new(self).for_each(visitor);
+    }
+
+    /// Visits a node and its children in breadth-first order, stops visitation on first error and returns it. Sequential version as the `parallel` feature is disabled.
+    #[cfg(not(feature = "parallel"))]
+    fn try_par_visit_bfs<E, F>(&self, visitor: F) -> Result<(), E>
+    where
+        Self: Sync,
+        E: Send + Sync,
+        F: Fn(&Self) -> Result<(), E> + Sync,
+    {
+        BfsIter::new(self).try_for_each(visitor)
+    }
+
+    /// Visits a node and its children in breadth-first order. Sequential version as the `parallel` feature is disabled, `sequential` is ignored.
+    #[cfg(not(feature = "parallel"))]
+    fn par_visit_bfs_with_granularity<P, F>(&self, _sequential: P, visitor: F)
+    where
+        Self: Sync,
+        P: Fn(&Self) -> bool + Sync,
+        F: Fn(&Self) + Sync,
+    {
+        self.par_visit_bfs(visitor);
+    }
+
+    /// Visits a node and its children in breadth-first order, stops visitation on first error and returns it. Sequential version as the `parallel` feature is disabled, `sequential` is ignored.
+    #[cfg(not(feature = "parallel"))]
+    fn try_par_visit_bfs_with_granularity<E, P, F>(
+        &self,
+        _sequential: P,
+        visitor: F,
+    ) -> Result<(), E>
+    where
+        Self: Sync,
+        E: Send + Sync,
+        P: Fn(&Self) -> bool + Sync,
+        F: Fn(&Self) -> Result<(), E> + Sync,
+    {
+        self.try_par_visit_bfs(visitor)
+    }
+
+    /// Visits a node and its children in breadth-first order, the visitor can skip the children of a node and stop the visitation with a result. Sequential version as the `parallel` feature is disabled.
+    #[cfg(not(feature = "parallel"))]
+    fn par_visit_bfs_controlled<'a, B, F>(&'a self, visitor: F) -> ControlFlow<B>
+    where
+        Self: Sync,
+        B: Send,
+        F: Fn(&'a Self) -> ControlFlow<B, Descend> + Sync,
+    {
+        visit_bfs_controlled_impl(self, visitor)
+    }
+
+    /// Visits a node and its children in breadth-first order and collects the results of the visitor sorted by their keys. Nodes for which the visitor returns `None` are skipped. Uses [`par_visit_bfs`](Self::par_visit_bfs).
+    fn par_visit_map<K, T, F>(&self, visitor: F) -> Vec<(K, T)>
+    where
+        Self: Sync,
+        K: Ord + Send,
+        T: Send,
+        F: Fn(&Self) -> Option<(K, T)> + Sync,
+    {
+        let results = Mutex::new(Vec::new());
+        self.par_visit_bfs(|node| {
+            if let Some(result) = visitor(node) {
+                results.lock().push(result);
+            }
+        });
+
+        let mut results = results.into_inner();
+        results.sort_by(|(key_a, _), (key_b, _)| key_a.cmp(key_b));
+        results
+    }
 }
 
 /// Trait for mutable parallel tree visitation algorithms. Automatically implemented for types that implement [`TreeNodeMut`] and [`ThreadSafe`](crate::ThreadSafe).
 pub trait ParMutVisitableTree: TreeNodeMut {
     /// Visits a node and its children in breadth-first order. The visitor is applied before enqueuing each node's children. Parallel version.
+    #[cfg(feature = "parallel")]
     fn par_visit_mut_bfs<F>(&mut self, visitor: F)
     where
         Self: Send + Sync,
         F: Fn(&mut Self) + Sync,
+    {
+        self.par_visit_mut_bfs_with_granularity(|_| false, visitor);
+    }
+
+    /// Visits a node and its children in breadth-first order. The visitor is applied before enqueuing each node's children. Parallel version.
+    /// The subtrees of all nodes for which `sequential` returns `true` (checked before applying the visitor) are visited sequentially in a single task.
+    #[cfg(feature = "parallel")]
+    fn par_visit_mut_bfs_with_granularity<P, F>(&mut self, sequential: P, visitor: F)
+    where
+        Self: Send + Sync,
+        P: Fn(&Self) -> bool + Sync,
+        F: Fn(&mut Self) + Sync,
     {
         // Parallel implementation of recursive breadth-first visitation
-        fn par_visit_mut_bfs_impl<'scope, T, F>(
+        fn par_visit_mut_bfs_impl<'scope, T, P, F>(
             node: &'scope mut T,
             s: &ScopeFifo<'scope>,
+            sequential: &'scope P,
             visitor: &'scope F,
         ) where
             T: TreeNodeMut + Send + Sync + ?Sized,
+            P: Fn(&T) -> bool + Sync,
             F: Fn(&mut T) + Sync,
         {
+            // Visit the whole subtree in the current task if it's small enough
+            if sequential(node) {
+                visit_mut_bfs_impl(node, visitor);
+                return;
+            }
+
             // Apply visitor before enqueuing children
             visitor(node);
 
             // Spawn tasks for all children
             for child in node.children_mut().iter_mut().map(DerefMut::deref_mut) {
-                s.spawn_fifo(move |s| par_visit_mut_bfs_impl(child, s, visitor));
+                s.spawn_fifo(move |s| par_visit_mut_bfs_impl(child, s, sequential, visitor));
             }
         }
 
-        let v = &visitor;
-        rayon::scope_fifo(move |s| par_visit_mut_bfs_impl(self, s, v));
+        let (p, v) = (&sequential, &visitor);
+        rayon::scope_fifo(move |s| par_visit_mut_bfs_impl(self, s, p, v));
     }
 
     /// Visits a node and its children in depth-first post-order. The visitor is applied after processing each node's children. Parallel version.
+    #[cfg(feature = "parallel")]
     fn par_visit_mut_dfs_post<F>(&mut self, visitor: F)
     where
         Self: Send + Sync,
         F: Fn(&mut Self) + Sync,
     {
-        fn par_visit_mut_dfs_post_impl<'scope, T, F>(
+        self.par_visit_mut_dfs_post_with_granularity(|_| false, visitor);
+    }
+
+    /// Visits a node and its children in depth-first post-order. The visitor is applied after processing each node's children. Parallel version.
+    /// The subtrees of all nodes for which `sequential` returns `true` are visited sequentially in a single task.
+    #[cfg(feature = "parallel")]
+    fn par_visit_mut_dfs_post_with_granularity<P, F>(&mut self, sequential: P, visitor: F)
+    where
+        Self: Send + Sync,
+        P: Fn(&Self) -> bool + Sync,
+        F: Fn(&mut Self) + Sync,
+    {
+        fn par_visit_mut_dfs_post_impl<'scope, T, P, F>(
             node: &'scope mut T,
             _s: &Scope<'scope>,
+            sequential: &'scope P,
             visitor: &'scope F,
         ) where
             T: TreeNodeMut + Send + Sync + ?Sized,
+            P: Fn(&T) -> bool + Sync,
             F: Fn(&mut T) + Sync,
         {
+            // Visit the whole subtree in the current task if it's small enough
+            if sequential(node) {
+                visit_mut_dfs_post_impl(node, visitor);
+                return;
+            }
+
             // Create a new scope to ensure that tasks are completed before the visitor runs
             rayon::scope(|s| {
                 for child in node.children_mut().iter_mut().map(DerefMut::deref_mut) {
-                    s.spawn(move |s| par_visit_mut_dfs_post_impl(child, s, visitor));
+                    s.spawn(move |s| par_visit_mut_dfs_post_impl(child, s, sequential, visitor));
                 }
             });
 
             visitor(node);
         }
 
-        let v = &visitor;
-        rayon::scope(move |s| par_visit_mut_dfs_post_impl(self, s, v));
+        let (p, v) = (&sequential, &visitor);
+        rayon::scope(move |s| par_visit_mut_dfs_post_impl(self, s, p, v));
     }
 
     /// Visits a node and its children in depth-first post-order, stops visitation on first error and returns it. The visitor is applied after processing each node's children. Parallel version.
+    #[cfg(feature = "parallel")]
     fn try_par_visit_mut_dfs_post<E, F>(&mut self, visitor: F) -> Result<(), E>
     where
         Self: Send + Sync,
         E: Send + Sync,
         F: Fn(&mut Self) -> Result<(), E> + Sync,
+    {
+        self.try_par_visit_mut_dfs_post_with_granularity(|_| false, visitor)
+    }
+
+    /// Visits a node and its children in depth-first post-order, stops visitation on first error and returns it. The visitor is applied after processing each node's children. Parallel version.
+    /// The subtrees of all nodes for which `sequential` returns `true` are visited sequentially in a single task.
+    #[cfg(feature = "parallel")]
+    fn try_par_visit_mut_dfs_post_with_granularity<E, P, F>(
+        &mut self,
+        sequential: P,
+        visitor: F,
+    ) -> Result<(), E>
+    where
+        Self: Send + Sync,
+        E: Send + Sync,
+        P: Fn(&Self) -> bool + Sync,
+        F: Fn(&mut Self) -> Result<(), E> + Sync,
     {
         let error = Arc::new(RwLock::new(Ok(())));
 
-        fn try_par_visit_mut_dfs_post_impl<'scope, T, E, F>(
+        fn try_par_visit_mut_dfs_post_impl<'scope, T, E, P, F>(
             node: &'scope mut T,
             _s: &Scope<'scope>,
             error: Arc<RwLock<Result<(), E>>>,
+            sequential: &'scope P,
             visitor: &'scope F,
         ) where
             T: TreeNodeMut + Send + Sync + ?Sized,
             E: Send + Sync,
+            P: Fn(&T) -> bool + Sync,
             F: Fn(&mut T) -> Result<(), E> + Sync,
         {
             // Stop recursion if there is already an error
@@ -323,11 +756,26 @@ pub trait ParMutVisitableTree: TreeNodeMut {
                 return;
             }
 
+            // Visit the whole subtree in the current task if it's small enough
+            if sequential(node) {
+                let res = try_visit_mut_dfs_post_impl(node, visitor);
+                if res.is_err() {
+                    let mut error_guard = error.write();
+                    // Don't overwrite error if there is already one
+                    if !error_guard.is_err() {
+                        *error_guard = res;
+                    }
+                }
+                return;
+            }
+
             // Create a new scope to ensure that tasks are completed before the visitor runs
             rayon::scope(|s| {
                 for child in node.children_mut().iter_mut().map(DerefMut::deref_mut) {
                     let error = error.clone();
-                    s.spawn(move |s| try_par_visit_mut_dfs_post_impl(child, s, error, visitor));
+                    s.spawn(move |s| {
+                        try_par_visit_mut_dfs_post_impl(child, s, error, sequential, visitor)
+                    });
                 }
             });
 
@@ -347,9 +795,9 @@ pub trait ParMutVisitableTree: TreeNodeMut {
 
         // Start the visitation
         {
-            let v = &visitor;
+            let (p, v) = (&sequential, &visitor);
             let e = error.clone();
-            rayon::scope(move |s| try_par_visit_mut_dfs_post_impl(self, s, e, v));
+            rayon::scope(move |s| try_par_visit_mut_dfs_post_impl(self, s, e, p, v));
         }
 
         // Return any potential error collected during visitation
@@ -362,9 +810,568 @@ pub trait ParMutVisitableTree: TreeNodeMut {
             Ok(())
         }
     }
+
+    /// Visits a node and its children in breadth-first order. The visitor is applied before enqueuing each node's children. Sequential version as the `parallel` feature is disabled.
+    #[cfg(not(feature = "parallel"))]
+    fn par_visit_mut_bfs<F>(&mut self, visitor: F)
+    where
+        Self: Send + Sync,
+        F: Fn(&mut Self) + Sync,
+    {
+        visit_mut_bfs_impl(self, &visitor);
+    }
+
+    /// Visits a node and its children in depth-first post-order. The visitor is applied after processing each node's children. Sequential version as the `parallel` feature is disabled.
+    #[cfg(not(feature = "parallel"))]
+    fn par_visit_mut_dfs_post<F>(&mut self, visitor: F)
+    where
+        Self: Send + Sync,
+        F: Fn(&mut Self) + Sync,
+    {
+        visit_mut_dfs_post_impl(self, &visitor);
+    }
+
+    /// Visits a node and its children in depth-first post-order, stops visitation on first error and returns it. The visitor is applied after processing each node's children. Sequential version as the `parallel` feature is disabled.
+    #[cfg(not(feature = "parallel"))]
+    fn try_par_visit_mut_dfs_post<E, F>(&mut self, visitor: F) -> Result<(), E>
+    where
+        Self: Send + Sync,
+        E: Send + Sync,
+        F: Fn(&mut Self) -> Result<(), E> + Sync,
+    {
+        try_visit_mut_dfs_post_impl(self, &visitor)
+    }
+
+    /// Visits a node and its children in breadth-first order. The visitor is applied before enqueuing each node's children. Sequential version as the `parallel` feature is disabled, `sequential` is ignored.
+    #[cfg(not(feature = "parallel"))]
+    fn par_visit_mut_bfs_with_granularity<P, F>(&mut self, _sequential: P, visitor: F)
+    where
+        Self: Send + Sync,
+        P: Fn(&Self) -> bool + Sync,
+        F: Fn(&mut Self) + Sync,
+    {
+        self.par_visit_mut_bfs(visitor);
+    }
+
+    /// Visits a node and its children in depth-first post-order. The visitor is applied after processing each node's children. Sequential version as the `parallel` feature is disabled, `sequential` is ignored.
+    #[cfg(not(feature = "parallel"))]
+    fn par_visit_mut_dfs_post_with_granularity<P, F>(&mut self, _sequential: P, visitor: F)
+    where
+        Self: Send + Sync,
+        P: Fn(&Self) -> bool + Sync,
+        F: Fn(&mut Self) + Sync,
+    {
+        self.par_visit_mut_dfs_post(visitor);
+    }
+
+    /// Visits a node and its children in depth-first post-order, stops visitation on first error and returns it. The visitor is applied after processing each node's children. Sequential version as the `parallel` feature is disabled, `sequential` is ignored.
+    #[cfg(not(feature = "parallel"))]
+    fn try_par_visit_mut_dfs_post_with_granularity<E, P, F>(
+        &mut self,
+        _sequential: P,
+        visitor: F,
+    ) -> Result<(), E>
+    where
+        Self: Send + Sync,
+        E: Send + Sync,
+        P: Fn(&Self) -> bool + Sync,
+        F: Fn(&mut Self) -> Result<(), E> + Sync,
+    {
+        self.try_par_visit_mut_dfs_post(visitor)
+    }
+
+    /// Visits a node and its children in breadth-first order and collects the results of the visitor sorted by their keys. Nodes for which the visitor returns `None` are skipped. The visitor is applied before enqueuing each node's children, see [`par_visit_mut_bfs`](Self::par_visit_mut_bfs).
+    fn par_visit_mut_map<K, T, F>(&mut self, visitor: F) -> Vec<(K, T)>
+    where
+        Self: Send + Sync,
+        K: Ord + Send,
+        T: Send,
+        F: Fn(&mut Self) -> Option<(K, T)> + Sync,
+    {
+        let results = Mutex::new(Vec::new());
+        self.par_visit_mut_bfs(|node| {
+            if let Some(result) = visitor(node) {
+                results.lock().push(result);
+            }
+        });
+
+        let mut results = results.into_inner();
+        results.sort_by(|(key_a, _), (key_b, _)| key_a.cmp(key_b));
+        results
+    }
+}
+
+/// Sequential breadth-first visitation with mutable access, the visitor is applied before enqueuing each node's children
+fn visit_mut_bfs_impl<T, F>(node: &mut T, visitor: &F)
+where
+    T: TreeNodeMut + ?Sized,
+    F: Fn(&mut T),
+{
+    let mut queue_down = VecDeque::new();
+    queue_down.push_back(node);
+
+    while let Some(current_node) = queue_down.pop_front() {
+        visitor(current_node);
+        queue_down.extend(
+            current_node
+                .children_mut()
+                .iter_mut()
+                .map(DerefMut::deref_mut),
+        );
+    }
+}
+
+/// Sequential recursive depth-first post-order visitation with mutable access
+fn visit_mut_dfs_post_impl<T, F>(node: &mut T, visitor: &F)
+where
+    T: TreeNodeMut + ?Sized,
+    F: Fn(&mut T),
+{
+    for child in node.children_mut().iter_mut().map(DerefMut::deref_mut) {
+        visit_mut_dfs_post_impl(child, visitor);
+    }
+
+    visitor(node);
+}
+
+/// Sequential recursive depth-first post-order visitation with mutable access, stops on the first error
+fn try_visit_mut_dfs_post_impl<T, E, F>(node: &mut T, visitor: &F) -> Result<(), E>
+where
+    T: TreeNodeMut + ?Sized,
+    F: Fn(&mut T) -> Result<(), E>,
+{
+    for child in node.children_mut().iter_mut().map(DerefMut::deref_mut) {
+        try_visit_mut_dfs_post_impl(child, visitor)?;
+    }
+
+    visitor(node)
 }
 
 impl<T: TreeNode> VisitableTree for T {}
 impl<T: TreeNodeMut> MutVisitableTree for T {}
 impl<T: TreeNode + Send + Sync> ParVisitableTree for T {}
 impl<T: TreeNodeMut + Send + Sync> ParMutVisitableTree for T {}
+
+#[cfg(test)]
+struct TestNode {
+    value: usize,
+    children: Vec<Box<TestNode>>,
+}
+
+#[cfg(test)]
+impl TestNode {
+    fn new(value: usize, children: Vec<TestNode>) -> Self {
+        Self {
+            value,
+            children: children.into_iter().map(Box::new).collect(),
+        }
+    }
+
+    /// Returns the tree
+    /// ```text
+    /// 0
+    /// ├─ 1
+    /// │  ├─ 3
+    /// │  └─ 4
+    /// │     └─ 6
+    /// └─ 2
+    ///    └─ 5
+    /// ```
+    fn example_tree() -> Self {
+        TestNode::new(
+            0,
+            vec![
+                TestNode::new(
+                    1,
+                    vec![
+                        TestNode::new(3, vec![]),
+                        TestNode::new(4, vec![TestNode::new(6, vec![])]),
+                    ],
+                ),
+                TestNode::new(2, vec![TestNode::new(5, vec![])]),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+impl TreeNode for TestNode {
+    fn children(&self) -> &[Box<Self>] {
+        &self.children
+    }
+}
+
+#[cfg(test)]
+impl TreeNodeMut for TestNode {
+    fn children_mut(&mut self) -> &mut [Box<Self>] {
+        &mut self.children
+    }
+}
+
+#[test]
+fn test_visitation_order() {
+    let tree = TestNode::example_tree();
+
+    let dfs: Vec<_> = tree.dfs_iter().map(|node| node.value).collect();
+    assert_eq!(dfs, vec![0, 1, 3, 4, 6, 2, 5]);
+
+    let bfs: Vec<_> = tree.bfs_iter().map(|node| node.value).collect();
+    assert_eq!(bfs, vec![0, 1, 2, 3, 4, 5, 6]);
+
+    let mut visited = Vec::new();
+    tree.visit_bfs(|node| visited.push(node.value));
+    assert_eq!(visited, bfs);
+
+    let mut levels = Vec::new();
+    tree.visit_level_order(|depth, node| levels.push((depth, node.value)));
+    assert_eq!(
+        levels,
+        vec![(0, 0), (1, 1), (1, 2), (2, 3), (2, 4), (2, 5), (3, 6)]
+    );
+
+    // A subtree starts at depth zero
+    let mut levels = Vec::new();
+    tree.children()[0].visit_level_order(|depth, node| levels.push((depth, node.value)));
+    assert_eq!(levels, vec![(0, 1), (1, 3), (1, 4), (2, 6)]);
+}
+
+#[test]
+fn test_mut_visitation_order() {
+    let mut tree = TestNode::example_tree();
+
+    let mut visited = Vec::new();
+    tree.visit_mut_bfs(|node| visited.push(node.value));
+    assert_eq!(visited, vec![0, 1, 2, 3, 4, 5, 6]);
+
+    // Nodes can be modified and children can be added before they are enqueued
+    tree.visit_mut_level_order(|depth, node| {
+        if depth == 3 {
+            node.children.push(Box::new(TestNode::new(7, vec![])));
+        }
+        node.value += 10 * depth;
+    });
+    let mut levels = Vec::new();
+    tree.visit_level_order(|depth, node| levels.push((depth, node.value)));
+    assert_eq!(
+        levels,
+        vec![
+            (0, 0),
+            (1, 11),
+            (1, 12),
+            (2, 23),
+            (2, 24),
+            (2, 25),
+            (3, 36),
+            (4, 47)
+        ]
+    );
+}
+
+#[test]
+fn test_deep_tree_visitation() {
+    // A degenerate tree that is a long chain of nodes
+    let depth = 1000;
+    let mut tree = TestNode::new(depth, vec![]);
+    for value in (0..depth).rev() {
+        tree = TestNode::new(value, vec![tree]);
+    }
+
+    let mut max_depth = 0;
+    tree.visit_level_order(|depth, node| {
+        assert_eq!(depth, node.value);
+        max_depth = depth;
+    });
+    assert_eq!(max_depth, depth);
+
+    let mut num_visited = 0;
+    tree.visit_mut_level_order(|_, _| num_visited += 1);
+    assert_eq!(num_visited, depth + 1);
+}
+
+#[cfg(test)]
+impl TestNode {
+    /// Returns a complete tree with the given branching factor and depth, the nodes are numbered in breadth-first order
+    fn complete_tree(branching: usize, depth: usize) -> Self {
+        let mut tree = TestNode::new(0, vec![]);
+        let mut next_value = 1;
+        tree.visit_mut_level_order(|node_depth, node| {
+            if node_depth < depth {
+                for _ in 0..branching {
+                    node.children
+                        .push(Box::new(TestNode::new(next_value, vec![])));
+                    next_value += 1;
+                }
+            }
+        });
+        tree
+    }
+}
+
+#[test]
+fn test_par_visit_map() {
+    let tree = TestNode::complete_tree(4, 7);
+    let num_nodes = tree.bfs_iter().count();
+    assert_eq!(num_nodes, (4usize.pow(8) - 1) / 3);
+
+    // Repeat the visitation to increase the chance of observing scheduling dependent results
+    for _ in 0..10 {
+        let results = tree.par_visit_map(|node| Some((node.value, node.children.len())));
+        assert_eq!(results.len(), num_nodes);
+        for (i, (value, num_children)) in results.into_iter().enumerate() {
+            assert_eq!(value, i);
+            assert_eq!(
+                num_children,
+                if i < (num_nodes - 4usize.pow(7)) {
+                    4
+                } else {
+                    0
+                }
+            );
+        }
+
+        // Only collect results of the leaves
+        let leaves = tree.par_visit_map(|node| node.children.is_empty().then(|| (node.value, ())));
+        assert_eq!(leaves.len(), 4usize.pow(7));
+        assert!(leaves.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+}
+
+#[test]
+fn test_par_visit_mut_map() {
+    let mut tree = TestNode::complete_tree(4, 7);
+    let num_nodes = tree.bfs_iter().count();
+
+    for _ in 0..10 {
+        // Each node marks its children before they are visited and removes its own mark
+        let results = tree.par_visit_mut_map(|node| {
+            for child in node.children.iter_mut() {
+                child.value += num_nodes;
+            }
+            let marked_by_parent = node.value >= num_nodes;
+            node.value %= num_nodes;
+            Some((node.value, marked_by_parent))
+        });
+
+        assert_eq!(results.len(), num_nodes);
+        for (i, (value, marked_by_parent)) in results.into_iter().enumerate() {
+            assert_eq!(value, i);
+            assert_eq!(marked_by_parent, i != 0);
+        }
+        assert!(tree.bfs_iter().enumerate().all(|(i, node)| node.value == i));
+    }
+}
+
+#[test]
+fn test_controlled_visitation() {
+    let tree = TestNode::example_tree();
+
+    // Pruning the subtree of node 1 skips nodes 3, 4 and 6
+    let mut visited = Vec::new();
+    let flow: ControlFlow<()> = tree.visit_dfs_controlled(|node| {
+        visited.push(node.value);
+        ControlFlow::Continue(if node.value == 1 {
+            Descend::No
+        } else {
+            Descend::Yes
+        })
+    });
+    assert_eq!(flow, ControlFlow::Continue(()));
+    assert_eq!(visited, vec![0, 1, 2, 5]);
+
+    let mut visited = Vec::new();
+    let flow: ControlFlow<()> = tree.visit_bfs_controlled(|node| {
+        visited.push(node.value);
+        ControlFlow::Continue(if node.value == 2 {
+            Descend::No
+        } else {
+            Descend::Yes
+        })
+    });
+    assert_eq!(flow, ControlFlow::Continue(()));
+    assert_eq!(visited, vec![0, 1, 2, 3, 4, 6]);
+
+    // Stopping returns the value and no further nodes are visited
+    let mut num_visited = 0;
+    let flow = tree.visit_dfs_controlled(|node| {
+        num_visited += 1;
+        if node.value == 4 {
+            ControlFlow::Break(node)
+        } else {
+            ControlFlow::Continue(Descend::Yes)
+        }
+    });
+    assert!(matches!(flow, ControlFlow::Break(node) if node.children.len() == 1));
+    assert_eq!(num_visited, 4);
+
+    let mut num_visited = 0;
+    let flow = tree.visit_bfs_controlled(|node| {
+        num_visited += 1;
+        if node.value == 4 {
+            ControlFlow::Break(node.value * 10)
+        } else {
+            ControlFlow::Continue(Descend::Yes)
+        }
+    });
+    assert_eq!(flow, ControlFlow::Break(40));
+    assert_eq!(num_visited, 5);
+}
+
+#[test]
+fn test_par_controlled_visitation() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let tree = TestNode::complete_tree(4, 7);
+    let num_nodes = tree.bfs_iter().count();
+
+    // Only visit the first child of each node, i.e. a single path from the root to a leaf
+    let num_visited = AtomicUsize::new(0);
+    let flow: ControlFlow<()> = tree.par_visit_bfs_controlled(|node| {
+        num_visited.fetch_add(1, Ordering::Relaxed);
+        let is_first_child = node.value == 0 || (node.value - 1) % 4 == 0;
+        ControlFlow::Continue(if is_first_child {
+            Descend::Yes
+        } else {
+            Descend::No
+        })
+    });
+    assert_eq!(flow, ControlFlow::Continue(()));
+    // The root and four children per visited inner node
+    assert_eq!(num_visited.into_inner(), 1 + 4 * 7);
+
+    // Search for a leaf, the result is propagated and (best-effort) not all nodes are visited
+    let num_visited = AtomicUsize::new(0);
+    let flow = tree.par_visit_bfs_controlled(|node| {
+        num_visited.fetch_add(1, Ordering::Relaxed);
+        if node.value == num_nodes - 1 {
+            ControlFlow::Break(node.value)
+        } else {
+            ControlFlow::Continue(Descend::Yes)
+        }
+    });
+    assert_eq!(flow, ControlFlow::Break(num_nodes - 1));
+    assert!(num_visited.into_inner() <= num_nodes);
+
+    // Every visitor may break, only one of the values is returned
+    let flow = tree.par_visit_bfs_controlled(|node| ControlFlow::Break(node.value));
+    assert_eq!(flow, ControlFlow::Break(0));
+}
+
+#[cfg(test)]
+impl TreeMetrics for TestNode {}
+
+#[test]
+fn test_tree_metrics() {
+    let tree = TestNode::example_tree();
+    assert_eq!(tree.node_count(), 7);
+    assert_eq!(tree.leaf_count(), 3);
+    assert_eq!(tree.max_depth(), 3);
+
+    let node_4 = &tree.children[0].children[1];
+    assert_eq!(tree.depth_of(&tree), Some(0));
+    assert_eq!(tree.depth_of(node_4), Some(2));
+    assert_eq!(tree.depth_of(&node_4.children[0]), Some(3));
+    assert_eq!(node_4.depth_of(&node_4.children[0]), Some(1));
+    assert_eq!(node_4.depth_of(&tree), None);
+
+    let ids = tree.linear_ids();
+    assert_eq!(ids.len(), 7);
+    for node in tree.bfs_iter() {
+        // The example tree is numbered in breadth-first order
+        assert_eq!(ids[&(node as *const TestNode)], node.value);
+    }
+
+    // Complete tree with 4 children per inner node
+    let tree = TestNode::complete_tree(4, 3);
+    assert_eq!(tree.node_count(), 1 + 4 + 16 + 64);
+    assert_eq!(tree.leaf_count(), 64);
+    assert_eq!(tree.max_depth(), 3);
+    assert_eq!(tree.children[3].max_depth(), 2);
+
+    // Degenerate tree with a single node
+    let tree = TestNode::new(0, vec![]);
+    assert_eq!(tree.node_count(), 1);
+    assert_eq!(tree.leaf_count(), 1);
+    assert_eq!(tree.max_depth(), 0);
+    assert_eq!(tree.depth_of(&tree), Some(0));
+    assert_eq!(
+        tree.linear_ids().into_iter().collect::<Vec<_>>(),
+        vec![(&tree as *const TestNode, 0)]
+    );
+}
+
+#[test]
+fn test_par_visitation_with_granularity() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let mut tree = TestNode::complete_tree(4, 6);
+    let num_nodes = tree.node_count();
+
+    // Visit all subtrees in parallel, no subtree, or only some subtrees sequentially
+    let predicates: [fn(&TestNode) -> bool; 3] = [|_| false, |_| true, |node| node.value % 3 == 1];
+
+    for sequential in predicates {
+        let visit_counts: Vec<_> = (0..num_nodes).map(|_| AtomicUsize::new(0)).collect();
+        let assert_visited_once = |visit_counts: &[AtomicUsize]| {
+            assert!(visit_counts
+                .iter()
+                .all(|count| count.swap(0, Ordering::SeqCst) == 1));
+        };
+
+        tree.par_visit_bfs_with_granularity(sequential, |node| {
+            visit_counts[node.value].fetch_add(1, Ordering::SeqCst);
+        });
+        assert_visited_once(&visit_counts);
+
+        let result: Result<(), ()> = tree.try_par_visit_bfs_with_granularity(sequential, |node| {
+            visit_counts[node.value].fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        });
+        assert!(result.is_ok());
+        assert_visited_once(&visit_counts);
+
+        // The mutable visitors move the values out of the range of the ids and back
+        tree.par_visit_mut_bfs_with_granularity(sequential, |node| {
+            visit_counts[node.value].fetch_add(1, Ordering::SeqCst);
+            node.value += num_nodes;
+        });
+        assert_visited_once(&visit_counts);
+
+        tree.par_visit_mut_dfs_post_with_granularity(sequential, |node| {
+            // All children were already visited
+            assert!(node.children.iter().all(|child| child.value < num_nodes));
+            node.value -= num_nodes;
+            visit_counts[node.value].fetch_add(1, Ordering::SeqCst);
+        });
+        assert_visited_once(&visit_counts);
+
+        let result: Result<(), ()> =
+            tree.try_par_visit_mut_dfs_post_with_granularity(sequential, |node| {
+                assert!(node.children.iter().all(|child| child.value < num_nodes));
+                visit_counts[node.value].fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        assert!(result.is_ok());
+        assert_visited_once(&visit_counts);
+
+        // Errors are still returned from sequentially visited subtrees
+        let result = tree.try_par_visit_bfs_with_granularity(sequential, |node| {
+            if node.value == num_nodes - 1 {
+                Err(node.value)
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(result, Err(num_nodes - 1));
+
+        let result = tree.try_par_visit_mut_dfs_post_with_granularity(sequential, |node| {
+            if node.value == num_nodes - 1 {
+                Err(node.value)
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(result, Err(num_nodes - 1));
+    }
+
+    assert!(tree.bfs_iter().enumerate().all(|(i, node)| node.value == i));
+}
@@ -1,8 +1,80 @@
 //! Convenience functions for importing particle data and meshes from various fileformats
 
+use crate::{AxisAlignedBoundingBox3d, Real};
+use nalgebra::Vector3;
+
 pub mod bgeo_format;
 pub mod json_format;
 pub mod obj_format;
 pub mod ply_format;
 pub mod vtk_format;
 pub mod xyz_format;
+
+/// Summary of the contents of a particle or mesh file without the actual data
+#[derive(Clone, Debug)]
+pub struct FileInfo {
+    /// Number of points stored in the file (i.e. particles or mesh vertices)
+    pub num_points: usize,
+    /// Number of cells stored in the file that are not single vertices (e.g. the triangles of a mesh)
+    pub num_cells: usize,
+    /// Bounding box of all points, `None` if the file does not contain any points
+    pub aabb: Option<AxisAlignedBoundingBox3d<f64>>,
+    /// All point and cell attributes stored in the file
+    pub attributes: Vec<AttributeInfo>,
+}
+
+/// Metadata of an attribute stored in a particle or mesh file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeInfo {
+    /// Name of the attribute
+    pub name: String,
+    /// Whether the attribute is attached to points or cells
+    pub location: AttributeLocation,
+    /// Name of the data type of the attribute values as stored in the file, e.g. `f32` or `i32`
+    pub data_type: String,
+    /// Number of components per value, `None` for variable length lists
+    pub num_components: Option<usize>,
+}
+
+/// The entities of a file that an attribute is attached to
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AttributeLocation {
+    Point,
+    Cell,
+}
+
+impl FileInfo {
+    /// Constructs the file info for the given points, computes their bounding box
+    fn from_points<R: Real>(
+        points: &[Vector3<R>],
+        num_cells: usize,
+        attributes: Vec<AttributeInfo>,
+    ) -> Self {
+        let aabb = if points.is_empty() {
+            None
+        } else {
+            AxisAlignedBoundingBox3d::from_points(points).try_convert()
+        };
+        Self {
+            num_points: points.len(),
+            num_cells,
+            aabb,
+            attributes,
+        }
+    }
+
+    /// Returns whether the file contains a mesh, i.e. cells that are not single vertices
+    pub fn is_mesh(&self) -> bool {
+        self.num_cells > 0
+    }
+}
+
+impl AttributeLocation {
+    /// Returns the name of the location in lower case
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttributeLocation::Point => "point",
+            AttributeLocation::Cell => "cell",
+        }
+    }
+}
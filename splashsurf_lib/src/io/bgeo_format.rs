@@ -1,5 +1,6 @@
 //! Helper functions for the BGEO file format
 
+use super::{AttributeInfo, AttributeLocation, FileInfo};
 use crate::Real;
 use anyhow::Context;
 use flate2::read::GzDecoder;
@@ -22,61 +23,69 @@ pub fn particles_from_bgeo<R: Real, P: AsRef<Path>>(
     bgeo_file: P,
 ) -> Result<Vec<Vector3<R>>, anyhow::Error> {
     // Load positions from BGEO file
-    let position_storage = {
-        let bgeo_file = load_bgeo_file(bgeo_file).context("Error while loading BGEO file")?;
+    let bgeo_file = load_bgeo_file(bgeo_file).context("Error while loading BGEO file")?;
 
-        //println!("header: {:?}", bgeo_file.header);
-        //println!("attrs: {:?}", bgeo_file.point_attributes);
+    //println!("header: {:?}", bgeo_file.header);
+    //println!("attrs: {:?}", bgeo_file.point_attributes);
 
-        let storage = bgeo_file.positions;
+    Ok(bgeo_file.particle_positions())
+}
 
-        if let AttributeStorage::Vector(dim, storage) = storage {
-            assert_eq!(dim, 3);
-            assert_eq!(storage.len() % dim, 0);
-            storage
-        } else {
-            panic!("Positions are not stored as vectors");
-        }
-    };
+/// Convenience function for loading particles from a BGEO file read from the given reader (e.g. stdin)
+pub fn particles_from_bgeo_reader<R: Real, Rd: Read>(
+    reader: Rd,
+) -> Result<Vec<Vector3<R>>, anyhow::Error> {
+    let bgeo_file = load_bgeo_from_reader(reader).context("Error while loading BGEO file")?;
+    Ok(bgeo_file.particle_positions())
+}
 
-    // Convert the array storage into individual vectors
-    let positions: Vec<_> = position_storage
-        .chunks(3)
-        .map(|p| {
-            Vector3::new(
-                R::from_f32(p[0]).unwrap(),
-                R::from_f32(p[1]).unwrap(),
-                R::from_f32(p[2]).unwrap(),
-            )
+/// Reads the number of particles, their bounding box and the point attributes of the BGEO file at the given path
+pub fn file_info_from_bgeo<P: AsRef<Path>>(bgeo_file: P) -> Result<FileInfo, anyhow::Error> {
+    let bgeo_file = load_bgeo_file(bgeo_file).context("Error while loading BGEO file")?;
+    let positions = bgeo_file.particle_positions::<f64>();
+
+    let attributes = bgeo_file
+        .attribute_definitions
+        .iter()
+        .map(|definition| AttributeInfo {
+            name: definition.name.clone(),
+            location: AttributeLocation::Point,
+            data_type: match definition.attr_type {
+                BgeoAttributeType::Float | BgeoAttributeType::Vector => "f32",
+                BgeoAttributeType::Int => "i32",
+                BgeoAttributeType::String | BgeoAttributeType::IndexedString => "string",
+            }
+            .to_string(),
+            num_components: Some(definition.size),
         })
         .collect();
 
-    Ok(positions)
+    Ok(FileInfo::from_points(&positions, 0, attributes))
 }
 
 /// Loads and parses a BGEO file to memory
 pub fn load_bgeo_file<P: AsRef<Path>>(bgeo_file: P) -> Result<BgeoFile, anyhow::Error> {
-    let mut buf = Vec::new();
-    let mut is_compressed = false;
-
-    // First check if the file is gzip compressed
-    {
-        let file = File::open(bgeo_file.as_ref()).context("Unable to open file for reading")?;
-        let mut gz = GzDecoder::new(file);
-        if gz.header().is_some() {
-            is_compressed = true;
-            gz.read_to_end(&mut buf)
-                .context("Error during gzip decompression")?;
-        }
-    }
+    let file = File::open(bgeo_file.as_ref()).context("Unable to open file for reading")?;
+    load_bgeo_from_reader(file)
+}
 
-    // Otherwise just read the raw file
-    if !is_compressed {
-        // File has to be opened again because the Gz header check already reads parts of the file
-        let mut file = File::open(bgeo_file).context("Unable to open file for reading")?;
-        file.read_to_end(&mut buf)
-            .context("Error while loading the file content")?;
-    }
+/// Loads and parses a (possibly gzip compressed) BGEO file from the given reader to memory
+pub fn load_bgeo_from_reader<Rd: Read>(mut reader: Rd) -> Result<BgeoFile, anyhow::Error> {
+    let mut raw = Vec::new();
+    reader
+        .read_to_end(&mut raw)
+        .context("Error while loading the file content")?;
+
+    // Decompress the content if it starts with the gzip magic bytes
+    let buf = if raw.starts_with(&[0x1f, 0x8b]) {
+        let mut buf = Vec::new();
+        GzDecoder::new(raw.as_slice())
+            .read_to_end(&mut buf)
+            .context("Error during gzip decompression")?;
+        buf
+    } else {
+        raw
+    };
 
     let (_, file) = bgeo_parser()
         .parse(&buf[..])
@@ -174,6 +183,30 @@ pub struct BgeoFile {
     pub attribute_data: Vec<(String, AttributeStorage)>,
 }
 
+impl BgeoFile {
+    /// Converts the position storage of the file into individual vectors
+    pub fn particle_positions<R: Real>(&self) -> Vec<Vector3<R>> {
+        let position_storage = if let AttributeStorage::Vector(dim, storage) = &self.positions {
+            assert_eq!(*dim, 3);
+            assert_eq!(storage.len() % dim, 0);
+            storage
+        } else {
+            panic!("Positions are not stored as vectors");
+        };
+
+        position_storage
+            .chunks(3)
+            .map(|p| {
+                Vector3::new(
+                    R::from_f32(p[0]).unwrap(),
+                    R::from_f32(p[1]).unwrap(),
+                    R::from_f32(p[2]).unwrap(),
+                )
+            })
+            .collect()
+    }
+}
+
 /// The header data of a BGEO file
 #[derive(Clone, Debug)]
 pub struct BgeoHeader {
@@ -815,3 +848,21 @@ fn test_bgeo_roundtrip_uncompressed() {
     assert_eq!(orig.len(), buffer.len());
     assert_eq!(&orig[0..buffer.len()], buffer.as_slice());
 }
+
+#[test]
+fn test_bgeo_read_from_reader() {
+    let input_file = Path::new("../data/dam_break_frame_9_6859_particles.bgeo");
+    let bgeo = load_bgeo_file(input_file).unwrap();
+
+    // Both compressed and uncompressed streams are detected without a file path
+    for enable_compression in [true, false] {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_bgeo_file(&bgeo, &mut buffer, enable_compression).unwrap();
+
+        let particles = particles_from_bgeo_reader::<f32, _>(buffer.as_slice()).unwrap();
+        assert_eq!(particles.len(), 6859);
+        assert_eq!(particles, bgeo.particle_positions::<f32>());
+    }
+
+    assert!(load_bgeo_from_reader(&b"not a bgeo file"[..]).is_err());
+}
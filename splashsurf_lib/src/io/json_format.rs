@@ -1,11 +1,12 @@
 //! Helper functions for the JSON file format
 
+use super::FileInfo;
 use crate::utils::IteratorExt;
 use crate::Real;
 use anyhow::{anyhow, Context};
 use nalgebra::Vector3;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Read};
 use std::path::Path;
 
 type ParticleVecF64 = Vec<[f64; 3]>;
@@ -21,8 +22,13 @@ pub fn particles_from_json<R: Real, P: AsRef<Path>>(
 ) -> Result<Vec<Vector3<R>>, anyhow::Error> {
     let path = json_file.as_ref();
     let file = File::open(path).context("Cannot open file for JSON parsing")?;
-    let reader = BufReader::new(file);
+    particles_from_json_reader(BufReader::new(file))
+}
 
+/// Loads particles in the JSON format (see [`particles_from_json`]) from the given reader (e.g. stdin)
+pub fn particles_from_json_reader<R: Real, Rd: Read>(
+    reader: Rd,
+) -> Result<Vec<Vector3<R>>, anyhow::Error> {
     // Read the JSON contents of the file as an instance of `ParticleVecF32`.
     let json = serde_json::from_reader(reader)
         .context("Reading of file to JSON structure failed. Not a valid JSON file.")?;
@@ -47,3 +53,9 @@ pub fn particles_from_json<R: Real, P: AsRef<Path>>(
 
     Ok(particles)
 }
+
+/// Reads the number of particles and their bounding box from the JSON file at the given path (the format does not support attributes)
+pub fn file_info_from_json<P: AsRef<Path>>(json_file: P) -> Result<FileInfo, anyhow::Error> {
+    let particles = particles_from_json::<f64, _>(json_file)?;
+    Ok(FileInfo::from_points(&particles, 0, Vec::new()))
+}
@@ -22,26 +22,36 @@ pub fn mesh_to_obj<R: Real, M: Mesh3d<R>, P: AsRef<Path>>(
         .open(filename)
         .context("Failed to open file handle for writing OBJ file")?;
     let mut writer = BufWriter::with_capacity(100000, file);
+    mesh_to_obj_writer(mesh, &mut writer)?;
+    writer
+        .flush()
+        .context("Failed to flush the OBJ file to disk")
+}
 
+/// Writes the given mesh in the OBJ format to the given writer (e.g. stdout), supports outputting normals
+pub fn mesh_to_obj_writer<R: Real, M: Mesh3d<R>, W: Write>(
+    mesh: &MeshWithData<R, M>,
+    mut writer: W,
+) -> Result<(), anyhow::Error> {
     let mesh_vertices = &mesh.mesh;
 
     for v in mesh_vertices.vertices() {
         write!(&mut writer, "v {} {} {}\n", v.x, v.y, v.z)?;
     }
 
+    // Only normals with three components can be written as `vn` records
     let normals = mesh
         .point_attributes
         .iter()
-        .find(|attrib| attrib.name == "normals");
+        .find(|attrib| attrib.name == "normals")
+        .and_then(|attrib| match &attrib.data {
+            AttributeData::Vector3Real(normals) => Some(normals),
+            _ => None,
+        });
 
     if let Some(normals) = normals {
-        match &normals.data {
-            AttributeData::Vector3Real(normals) => {
-                for n in normals {
-                    write!(&mut writer, "vn {} {} {}\n", n.x, n.y, n.z)?;
-                }
-            }
-            _ => {}
+        for n in normals {
+            write!(&mut writer, "vn {} {} {}\n", n.x, n.y, n.z)?;
         }
     }
 
@@ -61,3 +71,55 @@ pub fn mesh_to_obj<R: Real, M: Mesh3d<R>, P: AsRef<Path>>(
 
     Ok(())
 }
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::mesh::{MeshAttribute, TriMesh3d};
+    use nalgebra::Vector3;
+
+    fn triangle() -> TriMesh3d<f64> {
+        TriMesh3d {
+            vertices: vec![
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.5),
+            ],
+            triangles: vec![[0, 1, 2]],
+        }
+    }
+
+    fn to_obj_string(mesh: &MeshWithData<f64, TriMesh3d<f64>>) -> String {
+        let mut buffer = Vec::new();
+        mesh_to_obj_writer(mesh, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_obj_write_triangle() {
+        let mesh = MeshWithData::new(triangle());
+        assert_eq!(
+            to_obj_string(&mesh),
+            "v 0 0 0\nv 1 0 0\nv 0 1 0.5\nf 1 2 3\n"
+        );
+
+        let mesh_with_normals = mesh
+            .clone()
+            .with_point_data(MeshAttribute::new_real_vector3(
+                "normals",
+                vec![Vector3::z(); 3],
+            ));
+        assert_eq!(
+            to_obj_string(&mesh_with_normals),
+            "v 0 0 0\nv 1 0 0\nv 0 1 0.5\nvn 0 0 1\nvn 0 0 1\nvn 0 0 1\nf 1//1 2//2 3//3\n"
+        );
+
+        // Normals that are not 3D vectors are ignored
+        let mesh_with_scalar_normals =
+            mesh.with_point_data(MeshAttribute::new_real_scalar("normals", vec![1.0; 3]));
+        assert_eq!(
+            to_obj_string(&mesh_with_scalar_normals),
+            "v 0 0 0\nv 1 0 0\nv 0 1 0.5\nf 1 2 3\n"
+        );
+    }
+}
@@ -1,20 +1,33 @@
 //! Helper functions for the PLY file format
 
-use crate::mesh::{AttributeData, MeshAttribute, MeshWithData, TriMesh3d};
+use super::{AttributeInfo, AttributeLocation, FileInfo};
+use crate::mesh::{
+    AttributeData, CellConnectivity, Mesh3d, MeshAttribute, MeshWithData, TriMesh3d,
+};
 use crate::utils::IteratorExt;
 use crate::Real;
 use anyhow::{anyhow, Context};
 use nalgebra::Vector3;
 use ply_rs::parser::Parser as PlyParser;
-use ply_rs::ply::{DefaultElement, Ply, Property};
+use ply_rs::ply::{DefaultElement, Ply, Property, PropertyType, ScalarType};
+use std::fs;
+use std::io::{BufWriter, Read, Write};
 use std::path::Path;
 
 /// Tries to load the file at the given path as a PLY file and read particle positions from it
 pub fn particles_from_ply<R: Real, P: AsRef<Path>>(
     ply_path: P,
+) -> Result<Vec<Vector3<R>>, anyhow::Error> {
+    let file = std::fs::File::open(ply_path).context("Failed to open file for reading")?;
+    particles_from_ply_reader(file)
+}
+
+/// Tries to read particle positions from a PLY file read from the given reader (e.g. stdin)
+pub fn particles_from_ply_reader<R: Real, Rd: Read>(
+    mut reader: Rd,
 ) -> Result<Vec<Vector3<R>>, anyhow::Error> {
     let ply = PlyParser::new()
-        .read_ply(&mut std::fs::File::open(ply_path).context("Failed to open file for reading")?)
+        .read_ply(&mut reader)
         .context("Failed to parse PLY file")?;
     parse_particles_from_ply(&ply)
 }
@@ -29,6 +42,249 @@ pub fn surface_mesh_from_ply<R: Real, P: AsRef<Path>>(
     parse_mesh_from_ply(&ply)
 }
 
+/// Writes the given mesh to a binary PLY file, supports outputting point attributes
+pub fn mesh_to_ply<R: Real, M: Mesh3d<R>, P: AsRef<Path>>(
+    mesh: &MeshWithData<R, M>,
+    filename: P,
+) -> Result<(), anyhow::Error> {
+    let file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(filename)
+        .context("Failed to open file handle for writing PLY file")?;
+    let mut writer = BufWriter::with_capacity(100000, file);
+    mesh_to_ply_writer(mesh, &mut writer)?;
+    writer
+        .flush()
+        .context("Failed to flush the PLY file to disk")
+}
+
+/// Writes the given mesh in the binary little endian PLY format to the given writer (e.g. stdout), supports outputting point attributes
+///
+/// Vertex positions are stored as `x`, `y`, `z` properties and the cells as `vertex_indices` lists of the `face` element.
+/// Point attributes are stored as additional properties of the `vertex` element: vector attributes are split into
+/// one property per component (`<name>_x`, `<name>_y`, `<name>_z` or `<name>_0`, `<name>_1`, ...), except for
+/// `"normals"` which are stored as `nx`, `ny`, `nz` as expected by most tools. Cell attributes are not written.
+pub fn mesh_to_ply_writer<R: Real, M: Mesh3d<R>, W: Write>(
+    mesh: &MeshWithData<R, M>,
+    mut writer: W,
+) -> Result<(), anyhow::Error> {
+    let vertices = mesh.mesh.vertices();
+    let cells = mesh.mesh.cells();
+
+    let real_type = if std::mem::size_of::<R>() == 4 {
+        "float"
+    } else {
+        "double"
+    };
+    let write_real = |writer: &mut W, value: R| -> std::io::Result<()> {
+        if std::mem::size_of::<R>() == 4 {
+            writer.write_all(&value.to_f32().unwrap().to_le_bytes())
+        } else {
+            writer.write_all(&value.to_f64().unwrap().to_le_bytes())
+        }
+    };
+
+    write!(
+        writer,
+        "ply\nformat binary_little_endian 1.0\ncomment Created by splashsurf\n"
+    )?;
+    write!(writer, "element vertex {}\n", vertices.len())?;
+    for coordinate in ["x", "y", "z"] {
+        write!(writer, "property {} {}\n", real_type, coordinate)?;
+    }
+    for attribute in &mesh.point_attributes {
+        if attribute.data.len() != vertices.len() {
+            return Err(anyhow!(
+                "Point attribute '{}' has {} values but the mesh has {} vertices",
+                attribute.name,
+                attribute.data.len(),
+                vertices.len()
+            ));
+        }
+
+        match &attribute.data {
+            AttributeData::ScalarU64(_) => write!(writer, "property uint {}\n", attribute.name)?,
+            AttributeData::ScalarReal(_) => {
+                write!(writer, "property {} {}\n", real_type, attribute.name)?
+            }
+            AttributeData::Vector3Real(_) => {
+                for component in ["x", "y", "z"] {
+                    if attribute.name == "normals" {
+                        write!(writer, "property {} n{}\n", real_type, component)?;
+                    } else {
+                        write!(
+                            writer,
+                            "property {} {}_{}\n",
+                            real_type, attribute.name, component
+                        )?;
+                    }
+                }
+            }
+            AttributeData::VectorReal { num_components, .. } => {
+                for component in 0..*num_components {
+                    write!(
+                        writer,
+                        "property {} {}_{}\n",
+                        real_type, attribute.name, component
+                    )?;
+                }
+            }
+        }
+    }
+    write!(writer, "element face {}\n", cells.len())?;
+    write!(writer, "property list uchar uint vertex_indices\n")?;
+    write!(writer, "end_header\n")?;
+
+    for (i, v) in vertices.iter().enumerate() {
+        for &coordinate in v.iter() {
+            write_real(&mut writer, coordinate)?;
+        }
+
+        for attribute in &mesh.point_attributes {
+            match &attribute.data {
+                AttributeData::ScalarU64(values) => {
+                    let value = u32::try_from(values[i]).map_err(|_| {
+                        anyhow!(
+                            "Value {} of point attribute '{}' does not fit into a PLY uint",
+                            values[i],
+                            attribute.name
+                        )
+                    })?;
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+                AttributeData::ScalarReal(values) => write_real(&mut writer, values[i])?,
+                AttributeData::Vector3Real(values) => {
+                    for &component in values[i].iter() {
+                        write_real(&mut writer, component)?;
+                    }
+                }
+                AttributeData::VectorReal {
+                    num_components,
+                    values,
+                } => {
+                    for &component in &values[i * num_components..(i + 1) * num_components] {
+                        write_real(&mut writer, component)?;
+                    }
+                }
+            }
+        }
+    }
+
+    for cell in cells {
+        let num_cell_vertices = u8::try_from(cell.num_cell_vertices())
+            .context("Too many vertices per cell for the PLY format")?;
+        writer.write_all(&[num_cell_vertices])?;
+        cell.try_for_each_vertex(|v| {
+            let v = u32::try_from(v)
+                .map_err(|_| anyhow!("Vertex index {} does not fit into a PLY uint", v))?;
+            writer.write_all(&v.to_le_bytes())?;
+            Ok::<_, anyhow::Error>(())
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Reads the number of vertices and faces, the bounding box and the vertex and face properties of the PLY file at the given path
+pub fn file_info_from_ply<P: AsRef<Path>>(ply_path: P) -> Result<FileInfo, anyhow::Error> {
+    let ply = PlyParser::new()
+        .read_ply(&mut std::fs::File::open(ply_path).context("Failed to open file for reading")?)
+        .context("Failed to parse PLY file")?;
+    let points = parse_particles_from_ply::<f64>(&ply)?;
+
+    let mut num_faces = 0;
+    let mut attributes = Vec::new();
+    for element in ply.header.elements.values() {
+        // Coordinates and face connectivity are not reported as attributes
+        let (location, skipped_properties) = match element.name.as_str() {
+            "vertex" => (AttributeLocation::Point, ["x", "y", "z"].as_slice()),
+            "face" => {
+                num_faces = element.count;
+                (
+                    AttributeLocation::Cell,
+                    ["vertex_indices", "vertex_index"].as_slice(),
+                )
+            }
+            _ => continue,
+        };
+
+        for property in element.properties.values() {
+            if skipped_properties.contains(&property.name.as_str()) {
+                continue;
+            }
+
+            let (data_type, num_components) = match &property.data_type {
+                PropertyType::Scalar(scalar_type) => {
+                    (scalar_type_name(scalar_type).to_string(), Some(1))
+                }
+                PropertyType::List(_, scalar_type) => {
+                    (format!("list of {}", scalar_type_name(scalar_type)), None)
+                }
+            };
+            attributes.push(AttributeInfo {
+                name: property.name.clone(),
+                location,
+                data_type,
+                num_components,
+            });
+        }
+    }
+
+    Ok(FileInfo::from_points(&points, num_faces, attributes))
+}
+
+/// Returns the Rust name of the given PLY scalar type
+fn scalar_type_name(scalar_type: &ScalarType) -> &'static str {
+    match scalar_type {
+        ScalarType::Char => "i8",
+        ScalarType::UChar => "u8",
+        ScalarType::Short => "i16",
+        ScalarType::UShort => "u16",
+        ScalarType::Int => "i32",
+        ScalarType::UInt => "u32",
+        ScalarType::Float => "f32",
+        ScalarType::Double => "f64",
+    }
+}
+
+/// Returns the value of the given float or double property of a PLY element
+fn get_real_property<R: Real>(
+    element: &DefaultElement,
+    element_name: &str,
+    property_name: &str,
+) -> Result<R, anyhow::Error> {
+    match element.get(property_name) {
+        Some(Property::Float(value)) => Ok(R::from_f32(*value).unwrap()),
+        Some(Property::Double(value)) => Ok(R::from_f64(*value).unwrap()),
+        Some(_) => Err(anyhow!(
+            "Property '{}' of PLY element '{}' has wrong data type (expected float or double)",
+            property_name,
+            element_name
+        )),
+        None => Err(anyhow!(
+            "PLY element '{}' is missing the '{}' property",
+            element_name,
+            property_name
+        )),
+    }
+}
+
+/// Returns the vector made up of the three given float or double properties of a PLY element
+fn get_vector3_property<R: Real>(
+    element: &DefaultElement,
+    element_name: &str,
+    property_names: [&str; 3],
+) -> Result<Vector3<R>, anyhow::Error> {
+    Ok(Vector3::new(
+        get_real_property(element, element_name, property_names[0])?,
+        get_real_property(element, element_name, property_names[1])?,
+        get_real_property(element, element_name, property_names[2])?,
+    ))
+}
+
 /// Tries to extract particle positions from the given PLY structure
 fn parse_particles_from_ply<R: Real>(
     ply_file: &Ply<DefaultElement>,
@@ -38,33 +294,10 @@ fn parse_particles_from_ply<R: Real>(
         .get("vertex")
         .ok_or(anyhow!("PLY file is missing a 'vertex' element"))?;
 
-    let particles = elements
-        .into_iter()
-        .map(|e| {
-            let vertex = (
-                e.get("x").unwrap(),
-                e.get("y").unwrap(),
-                e.get("z").unwrap(),
-            );
-
-            let v = match vertex {
-                (Property::Float(x), Property::Float(y), Property::Float(z)) => Vector3::new(
-                    R::from_f32(*x).unwrap(),
-                    R::from_f32(*y).unwrap(),
-                    R::from_f32(*z).unwrap(),
-                ),
-                _ => {
-                    return Err(anyhow!(
-                        "Vertex properties have wrong PLY data type (expected float)"
-                    ))
-                }
-            };
-
-            Ok(v)
-        })
-        .collect::<Result<Vec<_>, anyhow::Error>>()?;
-
-    Ok(particles)
+    elements
+        .iter()
+        .map(|e| get_vector3_property(e, "vertex", ["x", "y", "z"]))
+        .try_collect_with_capacity(elements.len())
 }
 
 /// Tries to extract a surface mesh from the given PLY structure
@@ -83,55 +316,32 @@ fn parse_particles_from_ply<R: Real>(
 /// element face 12
 /// property list uchar uint vertex_indices
 /// ```
+/// The normals are optional and are stored as a `"normals"` point attribute if present.
 fn parse_mesh_from_ply<R: Real>(
     ply_file: &Ply<DefaultElement>,
 ) -> Result<MeshWithData<R, TriMesh3d<R>>, anyhow::Error> {
-    let vertices_normals = ply_file
+    let vertex_elements = ply_file
         .payload
         .get("vertex")
         .ok_or(anyhow!("PLY file is missing a 'vertex' element"))?;
 
-    let mut vertices = Vec::with_capacity(vertices_normals.len());
-    let mut normals = Vec::with_capacity(vertices_normals.len());
-
-    for e in vertices_normals {
-        let vertex = (
-            e.get("x").unwrap(),
-            e.get("y").unwrap(),
-            e.get("z").unwrap(),
-            e.get("nx").unwrap(),
-            e.get("ny").unwrap(),
-            e.get("nz").unwrap(),
-        );
+    let has_normals = ply_file
+        .header
+        .elements
+        .get("vertex")
+        .is_some_and(|element| element.properties.contains_key("nx"));
 
-        let (vertex, normal) = match vertex {
-            (
-                Property::Float(x),
-                Property::Float(y),
-                Property::Float(z),
-                Property::Float(nx),
-                Property::Float(ny),
-                Property::Float(nz),
-            ) => Ok((
-                Vector3::new(
-                    R::from_f32(*x).unwrap(),
-                    R::from_f32(*y).unwrap(),
-                    R::from_f32(*z).unwrap(),
-                ),
-                Vector3::new(
-                    R::from_f32(*nx).unwrap(),
-                    R::from_f32(*ny).unwrap(),
-                    R::from_f32(*nz).unwrap(),
-                ),
-            )),
-            _ => Err(anyhow!(
-                "Vertex properties have wrong PLY data type (expected float)"
-            )),
-        }?;
-
-        vertices.push(vertex);
-        normals.push(normal);
-    }
+    let vertices = parse_particles_from_ply(ply_file)?;
+    let normals = if has_normals {
+        Some(
+            vertex_elements
+                .iter()
+                .map(|e| get_vector3_property(e, "vertex", ["nx", "ny", "nz"]))
+                .try_collect_with_capacity(vertex_elements.len())?,
+        )
+    } else {
+        None
+    };
 
     let faces = ply_file
         .payload
@@ -150,23 +360,40 @@ fn parse_mesh_from_ply<R: Real>(
                     indices[1] as usize,
                     indices[2] as usize,
                 ]),
+                Property::ListInt(indices) if indices.len() == 3 => {
+                    let mut triangle = [0; 3];
+                    for (i, &index) in indices.iter().enumerate() {
+                        triangle[i] = usize::try_from(index)
+                            .map_err(|_| anyhow!("Negative vertex index in face: {}", index))?;
+                    }
+                    Ok(triangle)
+                }
                 Property::ListUInt(indices) => Err(anyhow!(
                     "Invalid number of vertex indices per face: {} (expected 3)",
                     indices.len()
                 )),
+                Property::ListInt(indices) => Err(anyhow!(
+                    "Invalid number of vertex indices per face: {} (expected 3)",
+                    indices.len()
+                )),
                 _ => Err(anyhow!(
-                    "Index properties have wrong PLY data type (expected uint)"
+                    "Index properties have wrong PLY data type (expected int or uint)"
                 )),
             }
         })
         .try_collect_with_capacity(faces.len())?;
 
-    let normals = MeshAttribute::new("normals".to_string(), AttributeData::Vector3Real(normals));
-    Ok(MeshWithData::new(TriMesh3d {
+    let mut mesh = MeshWithData::new(TriMesh3d {
         vertices,
         triangles,
-    })
-    .with_point_data(normals))
+    });
+    if let Some(normals) = normals {
+        mesh = mesh.with_point_data(MeshAttribute::new(
+            "normals".to_string(),
+            AttributeData::Vector3Real(normals),
+        ));
+    }
+    Ok(mesh)
 }
 
 #[cfg(test)]
@@ -195,4 +422,66 @@ pub mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_ply_write_read_roundtrip() -> Result<(), anyhow::Error> {
+        let sphere = TriMesh3d::<f32>::icosphere(&Vector3::zeros(), 1.0, 1);
+        let normals: Vec<_> = sphere.vertices.iter().map(|v| v.normalize()).collect();
+        let ids = (0..sphere.vertices.len() as u64).collect();
+        let mesh = MeshWithData::new(sphere.clone())
+            .with_point_data(MeshAttribute::new_real_vector3("normals", normals))
+            .with_point_data(MeshAttribute::new("id", AttributeData::ScalarU64(ids)));
+
+        let mut buffer = Vec::new();
+        mesh_to_ply_writer(&mesh, &mut buffer)?;
+        let header = "ply\nformat binary_little_endian 1.0\ncomment Created by splashsurf\n\
+            element vertex 42\nproperty float x\nproperty float y\nproperty float z\n\
+            property float nx\nproperty float ny\nproperty float nz\nproperty uint id\n\
+            element face 80\nproperty list uchar uint vertex_indices\nend_header\n";
+        assert!(buffer.starts_with(header.as_bytes()));
+
+        let ply = PlyParser::new().read_ply(&mut buffer.as_slice())?;
+        let read_mesh = parse_mesh_from_ply::<f32>(&ply)?;
+        assert_eq!(read_mesh.mesh.vertices, sphere.vertices);
+        assert_eq!(read_mesh.mesh.triangles, sphere.triangles);
+        assert_eq!(read_mesh.point_attributes.len(), 1);
+        assert_eq!(read_mesh.point_attributes[0].name, "normals");
+
+        let particles = particles_from_ply_reader::<f64, _>(buffer.as_slice())?;
+        assert_eq!(particles.len(), 42);
+        assert_eq!(particles[0], sphere.vertices[0].cast::<f64>());
+
+        // Meshes without normals can be read as well
+        let mut buffer = Vec::new();
+        mesh_to_ply_writer(&MeshWithData::new(sphere.clone()), &mut buffer)?;
+        let ply = PlyParser::new().read_ply(&mut buffer.as_slice())?;
+        let read_mesh = parse_mesh_from_ply::<f32>(&ply)?;
+        assert_eq!(read_mesh.mesh.triangles, sphere.triangles);
+        assert!(read_mesh.point_attributes.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ply_read_ascii_double() -> Result<(), anyhow::Error> {
+        let input = "ply\nformat ascii 1.0\nelement vertex 2\n\
+            property double x\nproperty double y\nproperty double z\nend_header\n\
+            0.5 1 2\n-1 0 0.25\n";
+        let particles = particles_from_ply_reader::<f32, _>(input.as_bytes())?;
+        assert_eq!(
+            particles,
+            vec![Vector3::new(0.5, 1.0, 2.0), Vector3::new(-1.0, 0.0, 0.25)]
+        );
+
+        // Missing coordinates are reported by name
+        let input = "ply\nformat ascii 1.0\nelement vertex 1\n\
+            property float x\nproperty float y\nend_header\n0 1\n";
+        let error = particles_from_ply_reader::<f32, _>(input.as_bytes()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "PLY element 'vertex' is missing the 'z' property"
+        );
+
+        Ok(())
+    }
 }
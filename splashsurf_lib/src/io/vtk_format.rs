@@ -1,19 +1,109 @@
 //! Helper functions for the VTK file format
 
+use super::{AttributeInfo, AttributeLocation, FileInfo};
 use crate::mesh::{AttributeData, MeshAttribute, MeshWithData, TriMesh3d};
 use crate::utils::IteratorExt;
 use crate::Real;
-use anyhow::{anyhow, Context};
 use nalgebra::Vector3;
 use std::borrow::Cow;
-use std::fs::create_dir_all;
+use std::fs::{create_dir_all, File};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use thiserror::Error as ThisError;
 use vtkio::model::{
     Attribute, Attributes, CellType, Cells, PolyDataPiece, UnstructuredGridPiece, VertexNumbers,
 };
 use vtkio::model::{ByteOrder, DataSet, Version, Vtk};
 use vtkio::IOBuffer;
 
+/// Error type of the functions for reading and writing VTK files
+#[derive(Debug, ThisError)]
+#[non_exhaustive]
+pub enum VtkIoError {
+    /// Error of the underlying file, reader or writer (e.g. the file does not exist)
+    #[error("failed to access VTK file")]
+    Io(
+        #[source]
+        #[from]
+        std::io::Error,
+    ),
+    /// The file could not be parsed as a VTK file
+    #[error("failed to parse VTK file{}: {context}", truncated_note(.truncated))]
+    Parse {
+        /// Description of the parser error
+        context: String,
+        /// Whether the file appears to be cut off, e.g. because it is still being written
+        truncated: bool,
+    },
+    /// The file contains a data set type that is not supported (only unstructured grids and poly data can be loaded)
+    #[error("unsupported VTK data set type {found}")]
+    UnsupportedDataSet {
+        /// Name of the data set type of the file
+        found: String,
+    },
+    /// A requested point or cell attribute does not exist in the file
+    #[error("attribute \"{name}\" not found in VTK file")]
+    AttributeMissing {
+        /// Name of the requested attribute
+        name: String,
+    },
+    /// A requested point or cell attribute has a data type or number of components that cannot be converted to [`AttributeData`]
+    #[error("attribute \"{name}\" has the unsupported type {found}")]
+    AttributeTypeUnsupported {
+        /// Name of the attribute
+        name: String,
+        /// Data type and number of components of the attribute in the file
+        found: String,
+    },
+    /// The data of the file is inconsistent or cannot be converted to the requested types
+    #[error("invalid data in VTK file: {0}")]
+    InvalidData(String),
+    /// Error while serializing the VTK data
+    #[error("failed to write VTK file: {0}")]
+    Write(String),
+}
+
+fn truncated_note(truncated: &bool) -> &'static str {
+    if *truncated {
+        " (the file appears to be truncated)"
+    } else {
+        ""
+    }
+}
+
+impl VtkIoError {
+    /// Returns whether reading the file again later may succeed, i.e. for I/O errors and for files that appear to be truncated
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            VtkIoError::Io(_)
+                | VtkIoError::Parse {
+                    truncated: true,
+                    ..
+                }
+        )
+    }
+
+    /// Converts an error of vtkio while reading the file at the given path (or from a reader if `None`)
+    fn from_read_error(err: vtkio::Error, file_path: Option<&Path>) -> Self {
+        match err {
+            vtkio::Error::IO(err) => VtkIoError::Io(err),
+            err => VtkIoError::Parse {
+                context: err.to_string(),
+                truncated: file_path.map_or(false, file_appears_truncated),
+            },
+        }
+    }
+
+    /// Converts an error of vtkio while writing a file
+    fn from_write_error(err: vtkio::Error) -> Self {
+        match err {
+            vtkio::Error::IO(err) => VtkIoError::Io(err),
+            err => VtkIoError::Write(err.to_string()),
+        }
+    }
+}
+
 pub struct VtkFile {
     pieces: Vec<DataPiece>,
 }
@@ -25,9 +115,8 @@ pub enum DataPiece {
 
 impl VtkFile {
     /// Loads all pieces of the given VTK struct
-    pub fn from_vtk(vtk_file: Vtk) -> Result<Self, anyhow::Error> {
-        let loaded_pieces =
-            load_pieces(vtk_file).context(anyhow!("Failed to load all pieces from VTK file"))?;
+    pub fn from_vtk(vtk_file: Vtk) -> Result<Self, VtkIoError> {
+        let loaded_pieces = load_pieces(vtk_file)?;
 
         Ok(Self {
             pieces: loaded_pieces,
@@ -35,14 +124,25 @@ impl VtkFile {
     }
 
     /// Loads a VTK file from the given path and loads all its data pieces
-    pub fn load_file<P: AsRef<Path>>(file_path: P) -> Result<Self, anyhow::Error> {
-        let file_path = file_path.as_ref();
-        let vtk_file = read_vtk(file_path)
-            .with_context(|| anyhow!("Failed to load VTK file \"{}\"", file_path.display()))?;
+    pub fn load_file<P: AsRef<Path>>(file_path: P) -> Result<Self, VtkIoError> {
+        Self::from_vtk(read_vtk(file_path)?)
+    }
+
+    /// Parses a big endian legacy VTK file from the given reader (e.g. stdin) and loads all its data pieces
+    pub fn load_from_reader<Rd: Read>(reader: Rd) -> Result<Self, VtkIoError> {
+        let vtk_file =
+            Vtk::parse_legacy_be(reader).map_err(|err| VtkIoError::from_read_error(err, None))?;
 
         Self::from_vtk(vtk_file)
     }
 
+    /// Returns the first piece of the file, fails if the file does not contain any pieces
+    fn into_first_piece(self) -> Result<DataPiece, VtkIoError> {
+        self.pieces.into_iter().next().ok_or_else(|| {
+            VtkIoError::InvalidData("the file does not contain any data pieces".to_string())
+        })
+    }
+
     /// Returns all pieces that could be loaded from the VTK file
     pub fn into_pieces(self) -> Vec<DataPiece> {
         self.pieces
@@ -58,97 +158,190 @@ impl DataPiece {
         }
     }
 
+    /// Returns a slice of all cell attributes of this data piece
+    fn cell_attributes(&self) -> &[Attribute] {
+        match self {
+            DataPiece::UnstructuredGrid(p) => &p.data.cell,
+            DataPiece::PolyData(p) => &p.data.cell,
+        }
+    }
+
     /// Returns the names of all supported point attributes of the given piece
     pub fn point_attribute_names(&self) -> Vec<String> {
         attribute_names(self.point_attributes())
     }
 
+    /// Returns the names of all supported cell attributes of the given piece
+    pub fn cell_attribute_names(&self) -> Vec<String> {
+        attribute_names(self.cell_attributes())
+    }
+
+    /// Returns the name, data type and number of components of all point and cell attributes of the given piece
+    pub fn attribute_info(&self) -> Vec<AttributeInfo> {
+        let mut info = attribute_info(self.point_attributes(), AttributeLocation::Point);
+        info.extend(attribute_info(
+            self.cell_attributes(),
+            AttributeLocation::Cell,
+        ));
+        info
+    }
+
+    /// Returns the number of cells of this piece that are not single vertices (e.g. the triangles of a mesh)
+    pub fn num_non_vertex_cells(&self) -> usize {
+        match self {
+            DataPiece::UnstructuredGrid(p) => p
+                .cells
+                .types
+                .iter()
+                .filter(|cell_type| !matches!(cell_type, CellType::Vertex | CellType::PolyVertex))
+                .count(),
+            DataPiece::PolyData(p) => [&p.lines, &p.polys, &p.strips]
+                .into_iter()
+                .map(|cells| cells.as_ref().map_or(0, num_cells))
+                .sum(),
+        }
+    }
+
     /// Tries to load a set of particles form this piece
-    pub fn load_as_particles<R: Real>(&self) -> Result<Vec<Vector3<R>>, anyhow::Error> {
+    pub fn load_as_particles<R: Real>(&self) -> Result<Vec<Vector3<R>>, VtkIoError> {
         let points = match self {
             DataPiece::UnstructuredGrid(p) => &p.points,
             DataPiece::PolyData(p) => &p.points,
         };
 
-        match points {
-            IOBuffer::F64(coords) => particles_from_coords(&coords),
-            IOBuffer::F32(coords) => particles_from_coords(&coords),
-            _ => Err(anyhow!(
-                "Point coordinate IOBuffer does not contain f32 or f64 values"
-            )),
-        }
+        points_from_io_buffer(points)
     }
 
     /// Tries to load a surface mesh from this piece
     pub fn load_as_surface_mesh<R: Real>(
         &self,
-    ) -> Result<MeshWithData<R, TriMesh3d<R>>, anyhow::Error> {
+    ) -> Result<MeshWithData<R, TriMesh3d<R>>, VtkIoError> {
         match self {
             DataPiece::UnstructuredGrid(p) => surface_mesh_from_unstructured_grid(p),
-            //DataPiece::PolyData(p) => unimplemented!(),
-            _ => Err(anyhow!("Unsupported piece type for loading surface mesh")),
+            DataPiece::PolyData(_) => Err(VtkIoError::UnsupportedDataSet {
+                found: "PolyData".to_string(),
+            }),
         }
     }
 
-    /// Tries to load attributes with the given names from the data piece, returns an error if the attribute does not exist
+    /// Tries to load point attributes with the given names from the data piece, returns an error if the attribute does not exist
     pub fn load_point_attributes<R: Real>(
         &self,
         names: &[String],
-    ) -> Result<Vec<MeshAttribute<R>>, anyhow::Error> {
-        let mut mesh_attributes = Vec::new();
-
-        'fields: for field_name in names {
-            for attribute in self.point_attributes() {
-                match attribute {
-                    Attribute::DataArray(data_array) if data_array.name == *field_name => {
-                        let attribute_data = try_convert_io_buffer_to_attribute(
-                            &data_array.data,
-                            data_array.num_comp(),
-                        )
-                        .with_context(|| anyhow!("Attribute \"{}\"", field_name))?;
-                        let mesh_attribute = MeshAttribute::new(field_name, attribute_data);
-                        mesh_attributes.push(mesh_attribute);
-                        continue 'fields;
-                    }
-                    Attribute::Field { data_array, .. } => {
-                        for field_array in data_array {
-                            if field_array.name == *field_name {
-                                let attribute_data = try_convert_io_buffer_to_attribute(
-                                    &field_array.data,
-                                    field_array.num_comp(),
-                                )
-                                .with_context(|| anyhow!("Attribute \"{}\"", field_name))?;
-                                let mesh_attribute = MeshAttribute::new(field_name, attribute_data);
-                                mesh_attributes.push(mesh_attribute);
-                                continue 'fields;
-                            }
+    ) -> Result<Vec<MeshAttribute<R>>, VtkIoError> {
+        load_attributes(self.point_attributes(), names, None)
+    }
+
+    /// Tries to load cell attributes with the given names from the data piece, returns an error if the attribute does not exist
+    ///
+    /// Fails with [`VtkIoError::InvalidData`] if the number of values of an attribute does not match the number of cells of the piece.
+    pub fn load_cell_attributes<R: Real>(
+        &self,
+        names: &[String],
+    ) -> Result<Vec<MeshAttribute<R>>, VtkIoError> {
+        load_attributes(self.cell_attributes(), names, Some(self.num_cells()))
+    }
+
+    /// Returns the number of all cells of this piece including single vertices
+    fn num_cells(&self) -> usize {
+        match self {
+            DataPiece::UnstructuredGrid(p) => p.cells.types.len(),
+            DataPiece::PolyData(p) => [&p.verts, &p.lines, &p.polys, &p.strips]
+                .into_iter()
+                .map(|cells| cells.as_ref().map_or(0, num_cells))
+                .sum(),
+        }
+    }
+}
+
+/// Tries to load the attributes with the given names from the given slice, returns an error if an attribute does not exist
+///
+/// If an expected number of values is given, every attribute is validated against it as soon as it is found.
+fn load_attributes<R: Real>(
+    attributes: &[Attribute],
+    names: &[String],
+    expected_len: Option<usize>,
+) -> Result<Vec<MeshAttribute<R>>, VtkIoError> {
+    let mut mesh_attributes = Vec::new();
+    let mut push_attribute = |name: &str, data: AttributeData<R>| {
+        if let Some(expected_len) = expected_len {
+            if data.len() != expected_len {
+                return Err(VtkIoError::InvalidData(format!(
+                    "attribute \"{}\" has {} values but {} values were expected",
+                    name,
+                    data.len(),
+                    expected_len
+                )));
+            }
+        }
+        mesh_attributes.push(MeshAttribute::new(name, data));
+        Ok(())
+    };
+
+    'fields: for field_name in names {
+        for attribute in attributes {
+            match attribute {
+                Attribute::DataArray(data_array) if data_array.name == *field_name => {
+                    let attribute_data = try_convert_io_buffer_to_attribute(
+                        field_name,
+                        &data_array.data,
+                        data_array.num_comp(),
+                    )?;
+                    push_attribute(field_name, attribute_data)?;
+                    continue 'fields;
+                }
+                Attribute::Field { data_array, .. } => {
+                    for field_array in data_array {
+                        if field_array.name == *field_name {
+                            let attribute_data = try_convert_io_buffer_to_attribute(
+                                field_name,
+                                &field_array.data,
+                                field_array.num_comp(),
+                            )?;
+                            push_attribute(field_name, attribute_data)?;
+                            continue 'fields;
                         }
                     }
-                    _ => {}
                 }
+                _ => {}
             }
-
-            return Err(anyhow!("Attribute {} not found in VTK file", field_name));
         }
 
-        Ok(mesh_attributes)
+        return Err(VtkIoError::AttributeMissing {
+            name: field_name.clone(),
+        });
     }
+
+    Ok(mesh_attributes)
+}
+
+/// Reads the number of points and cells, the bounding box and the attributes of the first piece of the VTK file at the given path
+pub fn file_info_from_vtk<P: AsRef<Path>>(file_path: P) -> Result<FileInfo, VtkIoError> {
+    let first_piece = VtkFile::load_file(file_path)?.into_first_piece()?;
+    let points = first_piece.load_as_particles::<f64>()?;
+
+    Ok(FileInfo::from_points(
+        &points,
+        first_piece.num_non_vertex_cells(),
+        first_piece.attribute_info(),
+    ))
 }
 
 /// Tries to read a set of particles from the VTK file at the given path
 pub fn particles_from_vtk<R: Real, P: AsRef<Path>>(
     file_path: P,
-) -> Result<Vec<Vector3<R>>, anyhow::Error> {
-    let file_path = file_path.as_ref();
+) -> Result<Vec<Vector3<R>>, VtkIoError> {
     VtkFile::load_file(file_path)?
-        .into_pieces()
-        .first()
-        .ok_or_else(|| {
-            anyhow!(
-                "No supported pieces in VTK file \"{}\"",
-                file_path.display()
-            )
-        })?
+        .into_first_piece()?
+        .load_as_particles()
+}
+
+/// Tries to read a set of particles from a big endian legacy VTK file read from the given reader (e.g. stdin)
+pub fn particles_from_vtk_reader<R: Real, Rd: Read>(
+    reader: Rd,
+) -> Result<Vec<Vector3<R>>, VtkIoError> {
+    VtkFile::load_from_reader(reader)?
+        .into_first_piece()?
         .load_as_particles()
 }
 
@@ -156,7 +349,7 @@ pub fn particles_from_vtk<R: Real, P: AsRef<Path>>(
 pub fn particles_to_vtk<R: Real, P: AsRef<Path>>(
     particles: &[Vector3<R>],
     vtk_file: P,
-) -> Result<(), anyhow::Error> {
+) -> Result<(), VtkIoError> {
     write_vtk(
         UnstructuredGridPiece::from(Particles(particles)),
         vtk_file,
@@ -167,17 +360,9 @@ pub fn particles_to_vtk<R: Real, P: AsRef<Path>>(
 /// Tries to read a surface mesh from the VTK file at the given path
 pub fn surface_mesh_from_vtk<R: Real, P: AsRef<Path>>(
     file_path: P,
-) -> Result<MeshWithData<R, TriMesh3d<R>>, anyhow::Error> {
-    let file_path = file_path.as_ref();
+) -> Result<MeshWithData<R, TriMesh3d<R>>, VtkIoError> {
     VtkFile::load_file(file_path)?
-        .into_pieces()
-        .first()
-        .ok_or_else(|| {
-            anyhow!(
-                "No supported pieces in VTK file \"{}\"",
-                file_path.display()
-            )
-        })?
+        .into_first_piece()?
         .load_as_surface_mesh()
 }
 
@@ -186,7 +371,7 @@ pub fn write_vtk<P: AsRef<Path>>(
     data: impl Into<DataSet>,
     filename: P,
     title: &str,
-) -> Result<(), anyhow::Error> {
+) -> Result<(), VtkIoError> {
     let vtk_file = Vtk {
         version: Version::new((4, 1)),
         title: title.to_string(),
@@ -197,39 +382,107 @@ pub fn write_vtk<P: AsRef<Path>>(
 
     let filename = filename.as_ref();
     if let Some(dir) = filename.parent() {
-        create_dir_all(dir).context("Failed to create parent directory of output file")?;
+        create_dir_all(dir)?;
     }
     vtk_file
         .export_be(filename)
-        .context("Error while writing VTK output to file")
+        .map_err(VtkIoError::from_write_error)
+}
+
+/// Tries to write `data` that is convertible to a VTK `DataSet` as a big endian legacy VTK file to the given writer (e.g. stdout)
+pub fn write_vtk_to_writer<W: Write>(
+    data: impl Into<DataSet>,
+    writer: W,
+    title: &str,
+) -> Result<(), VtkIoError> {
+    let vtk_file = Vtk {
+        version: Version::new((4, 1)),
+        title: title.to_string(),
+        file_path: None,
+        byte_order: ByteOrder::BigEndian,
+        data: data.into(),
+    };
+
+    vtk_file
+        .write_legacy(writer)
+        .map_err(VtkIoError::from_write_error)
 }
 
 /// Tries to read the given VTK file
-pub fn read_vtk<P: AsRef<Path>>(filename: P) -> Result<Vtk, anyhow::Error> {
+pub fn read_vtk<P: AsRef<Path>>(filename: P) -> Result<Vtk, VtkIoError> {
     let filename = filename.as_ref();
-    let mut vtk_file = Vtk::import(filename)?;
-    vtk_file.load_all_pieces()?;
-    Ok(vtk_file)
+    let read = || -> Result<Vtk, vtkio::Error> {
+        let mut vtk_file = Vtk::import(filename)?;
+        vtk_file.load_all_pieces().map_err(vtkio::Error::Load)?;
+        Ok(vtk_file)
+    };
+    read().map_err(|err| VtkIoError::from_read_error(err, Some(filename)))
+}
+
+/// Returns whether the file at the given path appears to be cut off, e.g. because it is still being written
+///
+/// Complete legacy VTK files end with a line break and complete XML VTK files with the closing `VTKFile` tag.
+fn file_appears_truncated(file_path: &Path) -> bool {
+    let read_tail = || -> std::io::Result<Vec<u8>> {
+        let mut file = File::open(file_path)?;
+        let len = file.metadata()?.len();
+        file.seek(SeekFrom::Start(len.saturating_sub(64)))?;
+        let mut tail = Vec::new();
+        file.read_to_end(&mut tail)?;
+        Ok(tail)
+    };
+    let tail = match read_tail() {
+        Ok(tail) => tail,
+        Err(_) => return false,
+    };
+
+    let is_legacy = file_path
+        .extension()
+        .map_or(false, |extension| extension.eq_ignore_ascii_case("vtk"));
+    if is_legacy {
+        tail.last() != Some(&b'\n')
+    } else {
+        !String::from_utf8_lossy(&tail)
+            .trim_end()
+            .ends_with("</VTKFile>")
+    }
+}
+
+/// Returns the name of the type of the given data set
+fn data_set_type(data_set: &DataSet) -> &'static str {
+    match data_set {
+        DataSet::ImageData { .. } => "ImageData",
+        DataSet::StructuredGrid { .. } => "StructuredGrid",
+        DataSet::RectilinearGrid { .. } => "RectilinearGrid",
+        DataSet::UnstructuredGrid { .. } => "UnstructuredGrid",
+        DataSet::PolyData { .. } => "PolyData",
+        DataSet::Field { .. } => "Field",
+    }
 }
 
 /// Loads all supported pieces of the given VTK file
-fn load_pieces(vtk_file: Vtk) -> Result<Vec<DataPiece>, anyhow::Error> {
+fn load_pieces(vtk_file: Vtk) -> Result<Vec<DataPiece>, VtkIoError> {
     let file_path = vtk_file.file_path.as_ref().map(PathBuf::as_path);
+    let load_error = |err| VtkIoError::from_read_error(vtkio::Error::Load(err), None);
 
     let loaded_pieces = match vtk_file.data {
         DataSet::UnstructuredGrid { pieces, .. } => pieces
             .into_iter()
             .map(|p| p.into_loaded_piece_data(file_path))
             .map(|p| p.map(|p| DataPiece::UnstructuredGrid(p)))
-            .collect::<Result<Vec<_>, _>>()?,
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(load_error)?,
         DataSet::PolyData { pieces, .. } => pieces
             .into_iter()
             .map(|p| p.into_loaded_piece_data(file_path))
             .map(|p| p.map(|p| DataPiece::PolyData(p)))
-            .collect::<Result<Vec<_>, _>>()?,
-        _ => Err(anyhow!(
-            "VTK file does not contain supported data set pieces"
-        ))?,
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(load_error)?,
+        data_set => {
+            return Err(VtkIoError::UnsupportedDataSet {
+                found: data_set_type(&data_set).to_string(),
+            })
+        }
     };
 
     Ok(loaded_pieces)
@@ -255,17 +508,45 @@ fn attribute_names(attributes: &[Attribute]) -> Vec<String> {
     attribute_names
 }
 
+/// Collects the metadata of all supported attributes in the given slice
+fn attribute_info(attributes: &[Attribute], location: AttributeLocation) -> Vec<AttributeInfo> {
+    let info = |name: &str, data: &IOBuffer, num_comp: usize| AttributeInfo {
+        name: name.to_string(),
+        location,
+        data_type: format!("{:?}", data.scalar_type()).to_lowercase(),
+        num_components: Some(num_comp),
+    };
+
+    let mut attribute_info = Vec::new();
+    for attribute in attributes {
+        match attribute {
+            Attribute::DataArray(data) => {
+                attribute_info.push(info(&data.name, &data.data, data.num_comp()))
+            }
+            Attribute::Field { data_array, .. } => {
+                for data in data_array {
+                    attribute_info.push(info(&data.name, &data.data, data.num_comp()));
+                }
+            }
+        }
+    }
+
+    attribute_info
+}
+
+/// Returns the number of cells described by the given vertex numbers
+fn num_cells(vertex_numbers: &VertexNumbers) -> usize {
+    match vertex_numbers {
+        VertexNumbers::Legacy { num_cells, .. } => *num_cells as usize,
+        xml @ VertexNumbers::XML { .. } => xml.clone().into_legacy().0 as usize,
+    }
+}
+
 /// Tries to construct a surface mesh from the given grid piece
 fn surface_mesh_from_unstructured_grid<R: Real>(
     piece: &UnstructuredGridPiece,
-) -> Result<MeshWithData<R, TriMesh3d<R>>, anyhow::Error> {
-    let vertices = match &piece.points {
-        IOBuffer::F64(coords) => particles_from_coords(coords),
-        IOBuffer::F32(coords) => particles_from_coords(coords),
-        _ => Err(anyhow!(
-            "Point coordinate IOBuffer does not contain f32 or f64 values"
-        )),
-    }?;
+) -> Result<MeshWithData<R, TriMesh3d<R>>, VtkIoError> {
+    let vertices = points_from_io_buffer(&piece.points)?;
 
     let triangles = {
         let (num_cells, cell_verts) = match &piece.cells.cell_verts {
@@ -280,7 +561,7 @@ fn surface_mesh_from_unstructured_grid<R: Real>(
         };
 
         if cell_verts.len() % 4 != 0 {
-            return Err(anyhow!("Length of cell vertex array is invalid. Expected 4 values per cell (3 for each triangle vertex index + 1 for vertex count). There are {} values for {} cells.", cell_verts.len(), num_cells));
+            return Err(VtkIoError::InvalidData(format!("length of cell vertex array is invalid. Expected 4 values per cell (3 for each triangle vertex index + 1 for vertex count). There are {} values for {} cells.", cell_verts.len(), num_cells)));
         }
 
         let cells = cell_verts
@@ -290,7 +571,7 @@ fn surface_mesh_from_unstructured_grid<R: Real>(
                 let is_triangle = cell[0] == 3;
                 is_triangle
                     .then(|| [cell[1] as usize, cell[2] as usize, cell[3] as usize])
-                    .ok_or_else(|| anyhow!("Expected only triangle cells. Invalid number of vertex indices ({}) of cell {}", cell[0], cell_idx))
+                    .ok_or_else(|| VtkIoError::InvalidData(format!("expected only triangle cells. Invalid number of vertex indices ({}) of cell {}", cell[0], cell_idx)))
             })
             .try_collect_with_capacity(num_cells as usize)?;
         cells
@@ -302,67 +583,108 @@ fn surface_mesh_from_unstructured_grid<R: Real>(
     }))
 }
 
-/// Converts a VTK IOBuffer to the corresponding supported AttributeData
+/// Converts a VTK IOBuffer of the attribute with the given name to the corresponding supported AttributeData
 fn try_convert_io_buffer_to_attribute<R: Real>(
+    name: &str,
     io_buffer: &vtkio::model::IOBuffer,
     num_comp: usize,
-) -> Result<AttributeData<R>, anyhow::Error> {
-    match num_comp {
-        1 => match &io_buffer {
-            IOBuffer::U32(vec) => try_map_scalars_to_real(&vec, |val| {
-                R::from_u32(val).ok_or_else(|| {
-                    anyhow!("Cannot convert an attribute value from u32 to Real type")
-                })
-            })
-            .map(|v| AttributeData::ScalarReal(v)),
-            IOBuffer::F32(vec) => try_map_scalars_to_real(&vec, |val| {
-                R::from_f32(val).ok_or_else(|| {
-                    anyhow!("Cannot convert an attribute value from f32 to Real type")
-                })
+) -> Result<AttributeData<R>, VtkIoError> {
+    let invalid_value = |err: VtkIoError| match err {
+        VtkIoError::InvalidData(message) => {
+            VtkIoError::InvalidData(format!("attribute \"{}\": {}", name, message))
+        }
+        err => err,
+    };
+
+    match (num_comp, io_buffer) {
+        // Integer values (e.g. ids or phase labels) are kept as integers
+        (1, IOBuffer::U8(vec)) => try_map_integers_to_u64(vec).map(AttributeData::ScalarU64),
+        (1, IOBuffer::I8(vec)) => try_map_integers_to_u64(vec).map(AttributeData::ScalarU64),
+        (1, IOBuffer::U16(vec)) => try_map_integers_to_u64(vec).map(AttributeData::ScalarU64),
+        (1, IOBuffer::I16(vec)) => try_map_integers_to_u64(vec).map(AttributeData::ScalarU64),
+        (1, IOBuffer::U32(vec)) => try_map_integers_to_u64(vec).map(AttributeData::ScalarU64),
+        (1, IOBuffer::I32(vec)) => try_map_integers_to_u64(vec).map(AttributeData::ScalarU64),
+        (1, IOBuffer::U64(vec)) => Ok(AttributeData::ScalarU64(vec.clone())),
+        (1, IOBuffer::I64(vec)) => try_map_integers_to_u64(vec).map(AttributeData::ScalarU64),
+        (1, IOBuffer::F32(vec)) => try_map_scalars_to_real(vec).map(AttributeData::ScalarReal),
+        (1, IOBuffer::F64(vec)) => try_map_scalars_to_real(vec).map(AttributeData::ScalarReal),
+        (3, IOBuffer::F32(coords)) => particles_from_coords(coords).map(AttributeData::Vector3Real),
+        (3, IOBuffer::F64(coords)) => particles_from_coords(coords).map(AttributeData::Vector3Real),
+        // Values with any other number of components are stored as flat arrays
+        (num_components, IOBuffer::F32(values)) if num_components > 0 => {
+            try_map_scalars_to_real(values).map(|values| AttributeData::VectorReal {
+                num_components,
+                values,
             })
-            .map(|v| AttributeData::ScalarReal(v)),
-            IOBuffer::F64(vec) => try_map_scalars_to_real(&vec, |val| {
-                R::from_f64(val).ok_or_else(|| {
-                    anyhow!("Cannot convert an attribute value from f64 to Real type")
-                })
+        }
+        (num_components, IOBuffer::F64(values)) if num_components > 0 => {
+            try_map_scalars_to_real(values).map(|values| AttributeData::VectorReal {
+                num_components,
+                values,
             })
-            .map(|v| AttributeData::ScalarReal(v)),
-            _ => Err(anyhow!("Unsupported IOBuffer scalar data type")),
-        },
-        3 => match &io_buffer {
-            IOBuffer::F32(coords) => {
-                particles_from_coords(coords).map(|p| AttributeData::Vector3Real(p))
-            }
-            IOBuffer::F64(coords) => {
-                particles_from_coords(coords).map(|p| AttributeData::Vector3Real(p))
-            }
-            _ => Err(anyhow!("Unsupported IOBuffer vector data type")),
-        },
-        _ => Err(anyhow!(
-            "Unsupported number of components ({}) in VTK IO buffer",
-            num_comp
-        )),
+        }
+        _ => Err(VtkIoError::AttributeTypeUnsupported {
+            name: name.to_string(),
+            found: format!(
+                "{:?} with {} component(s)",
+                io_buffer.scalar_type(),
+                num_comp
+            )
+            .to_lowercase(),
+        }),
     }
+    .map_err(invalid_value)
+}
+
+/// Converts scalar attribute values to the Real type, fails for values that are out of range
+fn try_map_scalars_to_real<R: Real, T: Real>(io_buffer: &[T]) -> Result<Vec<R>, VtkIoError> {
+    io_buffer
+        .iter()
+        .map(|&val| {
+            val.try_convert().ok_or_else(|| {
+                VtkIoError::InvalidData(
+                    "cannot convert an attribute value to the Real type".to_string(),
+                )
+            })
+        })
+        .try_collect_with_capacity(io_buffer.len())
 }
 
-fn try_map_scalars_to_real<R: Real, T: Copy, F: Fn(T) -> Result<R, anyhow::Error>>(
+/// Converts integer attribute values to unsigned integers, fails for negative values
+fn try_map_integers_to_u64<T: Copy + TryInto<u64> + std::fmt::Display>(
     io_buffer: &[T],
-    f: F,
-) -> Result<Vec<R>, anyhow::Error> {
+) -> Result<Vec<u64>, VtkIoError> {
     io_buffer
         .iter()
-        .copied()
-        .map(f)
+        .map(|&val| {
+            val.try_into().map_err(|_| {
+                VtkIoError::InvalidData(format!(
+                    "cannot convert the attribute value {} to an unsigned integer",
+                    val
+                ))
+            })
+        })
         .try_collect_with_capacity(io_buffer.len())
 }
 
+/// Tries to convert the point coordinates stored in the given buffer into a vector of `Vector3`
+fn points_from_io_buffer<R: Real>(points: &IOBuffer) -> Result<Vec<Vector3<R>>, VtkIoError> {
+    match points {
+        IOBuffer::F64(coords) => particles_from_coords(coords),
+        IOBuffer::F32(coords) => particles_from_coords(coords),
+        _ => Err(VtkIoError::InvalidData(
+            "point coordinate IOBuffer does not contain f32 or f64 values".to_string(),
+        )),
+    }
+}
+
 /// Tries to convert a vector of consecutive coordinate triplets into a vector of `Vector3`, also converts between floating point types
 fn particles_from_coords<RealOut: Real, RealIn: Real>(
-    coords: &Vec<RealIn>,
-) -> Result<Vec<Vector3<RealOut>>, anyhow::Error> {
+    coords: &[RealIn],
+) -> Result<Vec<Vector3<RealOut>>, VtkIoError> {
     if coords.len() % 3 != 0 {
-        return Err(anyhow!(
-            "Particle point buffer length is not divisible by 3"
+        return Err(VtkIoError::InvalidData(
+            "particle point buffer length is not divisible by 3".to_string(),
         ));
     }
 
@@ -378,7 +700,7 @@ fn particles_from_coords<RealOut: Real, RealIn: Real>(
         })
         .map(|vec| {
             vec.ok_or_else(|| {
-                anyhow!("Failed to convert coordinate from input to output float type, value out of range?")
+                VtkIoError::InvalidData("failed to convert coordinate from input to output float type, value out of range?".to_string())
             })
         })
         .try_collect_with_capacity(num_points)?;
@@ -1,5 +1,6 @@
 //! Helper functions for the binary `.xyz` float coordinate format
 
+use super::FileInfo;
 use crate::Real;
 use anyhow::Context;
 use nalgebra::Vector3;
@@ -7,12 +8,18 @@ use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::Path;
 
+/// Loads particles from the XYZ file at the given path
 pub fn particles_from_xyz<R: Real, P: AsRef<Path>>(
     xyz_file: P,
 ) -> Result<Vec<Vector3<R>>, anyhow::Error> {
     let file = File::open(xyz_file).context("Unable to open XYZ file for reading")?;
-    let mut reader = BufReader::new(file);
+    particles_from_xyz_reader(BufReader::new(file))
+}
 
+/// Loads particles in the XYZ format from the given reader (e.g. stdin), reads until the end of the stream
+pub fn particles_from_xyz_reader<R: Real, Rd: Read>(
+    mut reader: Rd,
+) -> Result<Vec<Vector3<R>>, anyhow::Error> {
     let mut buffer = [0u8; 3 * 4];
 
     let get_four_bytes = |buffer: &[u8], offset: usize| -> [u8; 4] {
@@ -39,3 +46,9 @@ pub fn particles_from_xyz<R: Real, P: AsRef<Path>>(
 
     Ok(particles)
 }
+
+/// Reads the number of particles and their bounding box from the XYZ file at the given path (the format does not support attributes)
+pub fn file_info_from_xyz<P: AsRef<Path>>(xyz_file: P) -> Result<FileInfo, anyhow::Error> {
+    let particles = particles_from_xyz::<f64, _>(xyz_file)?;
+    Ok(FileInfo::from_points(&particles, 0, Vec::new()))
+}
@@ -144,6 +144,85 @@ fn test_cubic_kernel_r_integral() {
     }
 }
 
+/// The commonly used cubic spline kernel in two dimensions, e.g. for planar SPH simulations
+pub struct CubicSplineKernel2d<R: Real> {
+    /// Compact support radius of the kernel
+    compact_support_radius: R,
+    /// Kernel normalization factor (sigma)
+    normalization: R,
+}
+
+impl<R: Real> CubicSplineKernel2d<R> {
+    /// Initializes a two dimensional cubic spline kernel with the given compact support radius
+    #[replace_float_literals(R::from_f64(literal).expect("Literal must fit in R"))]
+    pub fn new(compact_support_radius: R) -> Self {
+        let h = compact_support_radius;
+        // Corresponds to the normalization 10/(7π) divided by the squared smoothing length h/2
+        let sigma = 40.0 / (7.0 * R::pi() * h * h);
+
+        Self {
+            compact_support_radius,
+            normalization: sigma,
+        }
+    }
+
+    /// The cubic spline function used by the two dimensional cubic spline kernel
+    #[replace_float_literals(R::from_f64(literal).expect("Literal must fit in R"))]
+    fn cubic_function(q: R) -> R {
+        if q < R::one() {
+            return 1.0 - 1.5 * q * q + 0.75 * q * q * q;
+        } else if q < 2.0 {
+            let x = 2.0 - q;
+            return 0.25 * x * x * x;
+        } else {
+            return 0.0;
+        }
+    }
+
+    /// Evaluates the two dimensional cubic spline kernel at the radial distance `r`
+    pub fn evaluate(&self, r: R) -> R {
+        let q = (r + r) / self.compact_support_radius;
+        self.normalization * Self::cubic_function(q)
+    }
+}
+
+#[test]
+fn test_cubic_kernel_2d_r_compact_support() {
+    let hs = [0.025, 0.1, 2.0];
+    for &h in hs.iter() {
+        let kernel = CubicSplineKernel2d::new(h);
+        assert_eq!(kernel.evaluate(h), 0.0);
+        assert_eq!(kernel.evaluate(2.0 * h), 0.0);
+        assert_eq!(kernel.evaluate(10.0 * h), 0.0);
+    }
+}
+
+#[test]
+fn test_cubic_kernel_2d_r_integral() {
+    let hs = [0.025, 0.1, 2.0];
+    let n = 100;
+
+    for &h in hs.iter() {
+        let kernel = CubicSplineKernel2d::new(h);
+
+        let dr = h / (n as f64);
+        let darea = dr * dr;
+
+        let mut integral = 0.0;
+        for i in -n..n {
+            for j in -n..n {
+                let r_in = nalgebra::Vector2::new(i as f64, j as f64) * dr;
+                let r_out = nalgebra::Vector2::new((i + 1) as f64, (j + 1) as f64) * dr;
+                let r = ((r_in + r_out) * 0.5).norm();
+
+                integral += darea * kernel.evaluate(r);
+            }
+        }
+
+        assert!((integral - 1.0).abs() <= 1e-5);
+    }
+}
+
 /// Accelerator for efficient evaluation of a precomputed cubic kernel
 ///
 /// This structure is used to pre-compute a discrete representation of the cubic kernel function.
@@ -3,11 +3,19 @@
 //!
 //! Library for surface reconstruction of SPH particle data using marching cubes.
 //!
-//! Entry points are the [`reconstruct_surface`] or [`reconstruct_surface_inplace`] functions.
+//! Entry points are the [`reconstruct_surface`] or [`reconstruct_surface_inplace`] functions. [`reconstruct_surface_auto`]
+//! selects the index type of the background grid automatically.
 //!
 //! ## Feature flags
-//! The following features are all non-default features to reduce the amount of additional dependencies.
+//! Only the `parallel` feature is enabled by default. All other features are non-default features to reduce the
+//! amount of additional dependencies.
 //!
+//! - **`parallel`** (default): Enables the multi-threaded code paths of the reconstruction using
+//!  [`rayon`](https://github.com/rayon-rs/rayon) as well as the thread pool configuration functions
+//!  (e.g. [`initialize_thread_pool`]). Disabling the feature (`default-features = false`) removes the dependencies
+//!  on `rayon`, `dashmap` and `thread_local` (the latter is still required by the `profiling` feature) and only the
+//!  sequential code paths are compiled. In this case, the [`Parameters::enable_multi_threading`] flag is ignored
+//!  and a warning is logged if it is set.
 //! - **`vtk_extras`**: Enables helper functions and trait implementations to export meshes using [`vtkio`](https://github.com/elrnv/vtkio).
 //!  In particular it adds `From` impls for the [mesh](crate::mesh) types used by this crate to convert them to
 //!  [`vtkio::model::UnstructuredGridPiece`](https://docs.rs/vtkio/0.6.*/vtkio/model/struct.UnstructuredGridPiece.html) and [`vtkio::model::DataSet`](https://docs.rs/vtkio/0.6.*/vtkio/model/enum.DataSet.html)
@@ -21,29 +29,73 @@
 //!  by binary crates calling into this library to add their own profiling scopes to the measurements.
 //!  If this features is not enabled, the macro will just expend to a no-op and remove the (small)
 //!  performance overhead of the profiling.
+//! - **`tracing`**: Integrates the crate with the [`tracing`](https://github.com/tokio-rs/tracing) ecosystem. The [`profile`]
+//!  macro additionally opens a `tracing` span with the name of the profiling scope and all log messages of the crate
+//!  are emitted as `tracing` events instead of using the `log` crate. The spans of the reconstruction contain fields
+//!  such as the number of particles and the ids of the visited octree nodes. The crate exposes its `tracing` dependency
+//!  as `splashsurf_lib::tracing`.
+//! - **`rstar`**: Implements the [`ParticleNeighborhoodProvider`](neighborhood_search::ParticleNeighborhoodProvider) trait for
+//!  R-trees of the [`rstar`](https://github.com/georust/rstar) crate, e.g. to use an existing R-tree of the particles for the
+//!  SPH interpolation of surface attributes. The crate exposes its `rstar` dependency as `splashsurf_lib::rstar`.
+//! - **`serde`**: Implements `Serialize` and `Deserialize` of [`serde`](https://serde.rs) for the mesh types
+//!  [`TriMesh3d`](mesh::TriMesh3d), [`MeshWithData`](mesh::MeshWithData), [`MeshAttribute`](mesh::MeshAttribute) and
+//!  [`AttributeData`](mesh::AttributeData), e.g. to store reconstructed meshes in a binary format without going through
+//!  a mesh file format. The crate exposes its `serde` dependency as `splashsurf_lib::serde`.
+//! - **`wasm`**: Enables the [`wasm`] module with a [`wasm-bindgen`](https://github.com/rustwasm/wasm-bindgen) entry
+//!  point to run small reconstructions in the browser. The crate compiles for the `wasm32-unknown-unknown` target
+//!  if the default `parallel` feature is disabled, timings then use [`web-time`](https://crates.io/crates/web-time)
+//!  instead of `std::time::Instant`.
+//! - **`reference-impl`**: Enables the [`reference`] module with a simple and slow reference implementation of the
+//!  surface reconstruction (brute-force densities, dense grid evaluation and marching cubes over all cells). It is
+//!  intended for testing the optimized code paths on small particle sets and does not add any dependencies.
 //!
 
-use log::info;
+use crate::logging::{info, warn};
 /// Re-export the version of `nalgebra` used by this crate
 pub use nalgebra;
-use nalgebra::Vector3;
+use nalgebra::{SVector, Vector3};
+/// Re-export the version of `rstar` used by this crate, if the R-tree neighborhood provider is enabled
+#[cfg(feature = "rstar")]
+pub use rstar;
+/// Re-export the version of `serde` used by this crate, if serialization support is enabled
+#[cfg(feature = "serde")]
+pub use serde;
+use std::fmt;
+use std::ops;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error as ThisError;
+/// Re-export the version of `tracing` used by this crate, if tracing support is enabled
+#[cfg(feature = "tracing")]
+pub use tracing;
 /// Re-export the version of `vtkio` used by this crate, if vtk support is enabled
 #[cfg(feature = "vtk_extras")]
 pub use vtkio;
 
 pub use crate::aabb::{AxisAlignedBoundingBox, AxisAlignedBoundingBox2d, AxisAlignedBoundingBox3d};
-pub use crate::density_map::DensityMap;
+pub use crate::density_map::{DensityMap, DensityMapSnapshot};
+pub use crate::marching_cubes::MarchingCubesVariant;
 pub use crate::octree::SubdivisionCriterion;
+pub use crate::parameter_suggestion::{suggest_parameters, SuggestedParameters};
+pub use crate::particle_validation::{validate_particles, ValidationPolicy};
+pub use crate::reconstruction_2d::{reconstruct_contour_2d, Parameters2d};
+pub use crate::reconstruction_auto::{
+    reconstruct_surface_auto, select_index_type, IndexType, SurfaceReconstructionDyn,
+};
+pub use crate::timings::{LeafTimings, Timings, TimingsCollector};
 pub use crate::traits::{Index, Real, ThreadSafe};
-pub use crate::uniform_grid::UniformGrid;
+pub use crate::uniform_grid::{UniformGrid, UniformGrid2d};
+pub use crate::workspace::{
+    LeafBufferPoolMemoryReport, LocalWorkspaceMemoryReport, ReconstructionWorkspace,
+    WorkspaceMemoryReport,
+};
 
 use crate::density_map::DensityMapError;
 use crate::marching_cubes::MarchingCubesError;
-use crate::mesh::TriMesh3d;
-use crate::octree::Octree;
+use crate::mesh::{AttributeData, MeshAttribute, MeshWithData, TriMesh3d};
+use crate::octree::{Octree, OctreeError};
 use crate::uniform_grid::GridConstructionError;
-use crate::workspace::ReconstructionWorkspace;
 
 #[cfg(feature = "profiling")]
 #[cfg_attr(doc_cfg, doc(cfg(feature = "profiling")))]
@@ -58,29 +110,41 @@ pub mod generic_tree;
 #[cfg_attr(doc_cfg, doc(cfg(feature = "io")))]
 pub mod io;
 pub mod kernel;
+mod logging;
 pub mod marching_cubes;
 pub mod mesh;
 pub mod neighborhood_search;
 pub mod octree;
+pub mod parameter_suggestion;
+pub mod particle_validation;
+mod progress;
 mod reconstruction;
+pub mod reconstruction_2d;
+pub mod reconstruction_auto;
+#[cfg(feature = "reference-impl")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "reference-impl")))]
+pub mod reference;
 pub mod sph_interpolation;
+pub mod stages;
+pub mod timings;
 pub mod topology;
 mod traits;
+pub mod transform;
 pub mod uniform_grid;
+#[cfg(feature = "wasm")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "wasm")))]
+pub mod wasm;
 #[macro_use]
 mod utils;
 pub(crate) mod workspace;
 
 // TODO: Add documentation of feature flags
-// TODO: Feature flag for multi threading
 // TODO: Feature flag to disable (debug level) logging?
 
 // TODO: Remove anyhow/thiserror from lib?
 // TODO: Write more unit tests (e.g. AABB, UniformGrid, neighborhood search)
 // TODO: Test kernels with property based testing?
-// TODO: More and better error messages with distinct types
 // TODO: Make flat indices strongly typed
-// TODO: Function that detects smallest usable index type
 
 pub(crate) type HashState = fxhash::FxBuildHasher;
 pub(crate) type MapType<K, V> = std::collections::HashMap<K, V, HashState>;
@@ -106,6 +170,7 @@ pub(crate) fn new_map<K, V>() -> MapType<K, V> {
 }
 */
 
+#[cfg(feature = "parallel")]
 pub(crate) type ParallelMapType<K, V> = dashmap::DashMap<K, V, HashState>;
 
 /// Parameters for the spatial decomposition
@@ -160,17 +225,27 @@ pub enum ParticleDensityComputationStrategy {
     /// If the ghost-particle margin is not set wide enough, this may lead to density differences on subdomain
     /// boundaries. Otherwise this approach robust with respect to the classification of particles into the
     /// subdomains.
+    ///
+    /// As no global densities are available with this approach, it cannot be combined with the
+    /// [`SplashDetection::DensityThreshold`] splash detection.
     IndependentSubdomains,
 }
 
 impl<R: Real> SpatialDecompositionParameters<R> {
     /// Tries to convert the parameters from one [`Real`] type to another [`Real`] type, returns `None` if conversion fails
     pub fn try_convert<T: Real>(&self) -> Option<SpatialDecompositionParameters<T>> {
-        Some(SpatialDecompositionParameters {
+        self.try_convert_checked().ok()
+    }
+
+    /// Tries to convert the parameters from one [`Real`] type to another [`Real`] type, returns an error naming the first field that cannot be converted
+    pub fn try_convert_checked<T: Real>(
+        &self,
+    ) -> Result<SpatialDecompositionParameters<T>, ConversionError> {
+        Ok(SpatialDecompositionParameters {
             subdivision_criterion: self.subdivision_criterion.clone(),
             ghost_particle_safety_factor: map_option!(
                 &self.ghost_particle_safety_factor,
-                r => r.try_convert()?
+                r => ConversionError::convert_value(*r, "ghost_particle_safety_factor")?
             ),
             enable_stitching: self.enable_stitching,
             particle_density_computation: self.particle_density_computation,
@@ -178,6 +253,113 @@ impl<R: Real> SpatialDecompositionParameters<R> {
     }
 }
 
+/// Available criteria for the detection of splash or free particles that are excluded from the reconstruction
+#[derive(Clone, Debug)]
+pub enum SplashDetection<R: Real> {
+    /// Classify particles without any neighbors within the given radius as splash particles (in distance units, not relative to particle radius).
+    ///
+    /// This requires an additional neighborhood search with the given radius.
+    NeighborCount(R),
+    /// Classify particles with a density below the given factor times the density of an isolated particle as splash particles.
+    ///
+    /// The density of an isolated particle is given by `m·W(0)`, i.e. the contribution of the particle
+    /// to its own density. Therefore, the factor should be larger than one. As the classification
+    /// uses the per particle densities computed for the reconstruction anyway, no additional
+    /// neighborhood search is required.
+    ///
+    /// The classification requires globally available densities and therefore cannot be combined with the
+    /// [`ParticleDensityComputationStrategy::IndependentSubdomains`] strategy.
+    DensityThreshold(R),
+}
+
+impl<R: Real> SplashDetection<R> {
+    /// Tries to convert the splash detection criterion from one [`Real`] type to another [`Real`] type, returns `None` if conversion fails
+    pub fn try_convert<T: Real>(&self) -> Option<SplashDetection<T>> {
+        self.try_convert_checked().ok()
+    }
+
+    /// Tries to convert the splash detection criterion from one [`Real`] type to another [`Real`] type, returns an error naming the variant that cannot be converted
+    pub fn try_convert_checked<T: Real>(&self) -> Result<SplashDetection<T>, ConversionError> {
+        Ok(match self {
+            SplashDetection::NeighborCount(radius) => SplashDetection::NeighborCount(
+                ConversionError::convert_value(*radius, "NeighborCount")?,
+            ),
+            SplashDetection::DensityThreshold(factor) => SplashDetection::DensityThreshold(
+                ConversionError::convert_value(*factor, "DensityThreshold")?,
+            ),
+        })
+    }
+}
+
+/// Available options for the output of the splash particles that were excluded from the reconstruction
+#[derive(Copy, Clone, Debug)]
+pub enum SplashOutput {
+    /// Splash particles are not part of the output
+    Ignore,
+    /// Splash particles are returned as spheres in a separate mesh, see [`SurfaceReconstruction::splash_mesh`]
+    SeparateMesh {
+        /// Number of subdivisions of the icosahedron used as the sphere geometry for each splash particle
+        sphere_subdivisions: usize,
+    },
+    /// Splash particles are added as spheres to the end of the reconstructed surface mesh
+    MergedSpheres {
+        /// Number of subdivisions of the icosahedron used as the sphere geometry for each splash particle
+        sphere_subdivisions: usize,
+    },
+}
+
+impl Default for SplashOutput {
+    fn default() -> Self {
+        SplashOutput::Ignore
+    }
+}
+
+/// Policy for particles that leave the fixed background grid of a sequence of reconstructions, see [`SurfaceReconstruction::set_fixed_grid`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GridEscapePolicy {
+    /// The reconstruction is aborted with a [`ReconstructionError::ParticlesOutsideGrid`] error
+    Error,
+    /// A new grid is computed from the particles of the current frame and reused by the subsequent frames
+    Regrid,
+}
+
+/// A plane that bounds a half-space of the domain retained by the surface reconstruction
+///
+/// Points `x` with `(x - point)·normal < 0` are on the clipped side of the plane, i.e. the normal
+/// points into the retained half-space.
+#[derive(Clone, Debug)]
+pub struct ClippingPlane<R: Real> {
+    /// An arbitrary point on the plane
+    pub point: Vector3<R>,
+    /// Normal of the plane pointing into the retained half-space (does not have to be normalized)
+    pub normal: Vector3<R>,
+}
+
+impl<R: Real> ClippingPlane<R> {
+    /// Constructs a clipping plane through the given point with the given normal pointing into the retained half-space
+    pub fn new(point: Vector3<R>, normal: Vector3<R>) -> Self {
+        Self { point, normal }
+    }
+
+    /// Returns the signed distance of the given point to the plane, negative values are on the clipped side
+    pub fn signed_distance(&self, x: &Vector3<R>) -> R {
+        (x - self.point).dot(&self.normal) / self.normal.norm()
+    }
+
+    /// Tries to convert the clipping plane from one [`Real`] type to another [`Real`] type, returns `None` if conversion fails
+    pub fn try_convert<T: Real>(&self) -> Option<ClippingPlane<T>> {
+        self.try_convert_checked().ok()
+    }
+
+    /// Tries to convert the clipping plane from one [`Real`] type to another [`Real`] type, returns an error naming the first component that cannot be converted
+    pub fn try_convert_checked<T: Real>(&self) -> Result<ClippingPlane<T>, ConversionError> {
+        Ok(ClippingPlane {
+            point: ConversionError::convert_vec(&self.point, "point")?,
+            normal: ConversionError::convert_vec(&self.normal, "normal")?,
+        })
+    }
+}
+
 /// Parameters for the surface reconstruction
 #[derive(Clone, Debug)]
 pub struct Parameters<R: Real> {
@@ -191,101 +373,1110 @@ pub struct Parameters<R: Real> {
     pub cube_size: R,
     /// Density threshold value to distinguish between the inside (above threshold) and outside (below threshold) of the fluid
     pub iso_surface_threshold: R,
+    /// Variant of the marching cubes triangulation, i.e. how cells with an ambiguous configuration of corners above and below the threshold are triangulated
+    ///
+    /// The default [`MarchingCubesVariant::Classic`] only uses the above/below flags of the cell corners, while
+    /// [`MarchingCubesVariant::Mc33`] additionally evaluates the density values at the corners of ambiguous cells to
+    /// produce meshes with a topology that matches the density field (e.g. without tiny holes in thin sheets of fluid)
+    /// at a slightly higher cost of the triangulation. The dual [`MarchingCubesVariant::SurfaceNets`] triangulation
+    /// produces more regular triangles but cannot be combined with [`SpatialDecompositionParameters::enable_stitching`].
+    pub triangulation_scheme: MarchingCubesVariant,
+    /// Distance (relative to the cube size) within which interpolated iso-surface vertices are snapped onto the closest grid point, no snapping is performed if not provided
+    ///
+    /// When the iso-surface passes very close to a grid point, marching cubes generates needle-shaped triangles with
+    /// vertices that are almost coincident. If set, every vertex that is closer than this fraction of the cube size to
+    /// one of the end points of its grid edge is moved onto this point, and all vertices snapped onto the same point are
+    /// merged. Triangles that collapse due to the merging are removed from the mesh. Vertices are only merged if the
+    /// mesh stays manifold, otherwise they are left unchanged. Typical values are in the order of `1e-4`, the value has
+    /// to be larger than zero and less than `0.5`. Snapping is not applied to the [`MarchingCubesVariant::SurfaceNets`]
+    /// triangulation which does not place vertices on the grid edges.
+    pub vertex_snapping: Option<R>,
+    /// Criterion used for the detection of splash or free particles that are excluded from the reconstruction.
+    /// If not provided, no splash detection is performed and all particles contribute to the surface.
+    pub splash_detection: Option<SplashDetection<R>>,
+    /// Whether and how the detected splash particles should be added to the output as spheres with the particle radius
+    pub splash_particle_output: SplashOutput,
     /// Manually restrict the domain to the surface reconstruction.
     /// If not provided, the smallest AABB enclosing all particles is computed instead.
     pub domain_aabb: Option<AxisAlignedBoundingBox3d<R>>,
-    /// Whether to allow multi threading within the surface reconstruction procedure
+    /// Half-spaces that restrict the domain of the surface reconstruction, e.g. to cut the fluid along tilted container walls.
+    ///
+    /// Grid points on the clipped side of any plane are treated as below the iso-surface threshold,
+    /// so the reconstructed surface is cut (and closed) along the planes. Particles that are further
+    /// than the compact support radius on the clipped side of any plane are skipped.
+    pub clipping_planes: Vec<ClippingPlane<R>>,
+    /// Whether to allow multi threading within the surface reconstruction procedure, ignored if the `parallel` feature is disabled
     pub enable_multi_threading: bool,
+    /// Whether the reconstruction should be deterministic, i.e. repeated reconstructions of the same input produce bitwise identical meshes independent of the number of threads.
+    ///
+    /// In this mode the neighbor lists of the parallel neighborhood search are sorted before the particle densities
+    /// are summed up, the density map is generated and triangulated sequentially (always in a newly allocated map
+    /// instead of the map of the workspace) and the meshes of the octree leaves are assembled in a fixed order.
+    /// Furthermore, [`SubdivisionCriterion::MaxParticleCountAuto`] selects the number of particles per leaf
+    /// independent of the number of threads. The sequential density map generation makes the global reconstruction (without
+    /// spatial decomposition) noticeably slower on many threads, the octree based reconstruction (where the
+    /// leaves are already processed sequentially) is only slightly affected.
+    pub deterministic: bool,
+    /// Interval for periodic progress messages (`info` log level) of long-running stages, no progress is logged if not provided
+    ///
+    /// If set, the density map generation, the processing of the octree leaves and the marching cubes stages
+    /// log their progress and throughput at most once per interval, e.g. `density map: 42% (1.2M/2.9M particles, 3.1M particles/s)`.
+    pub progress_interval: Option<Duration>,
+    /// Blending factor `α` in `(0, 1]` of the temporal smoothing of the density map across subsequent reconstructions, no smoothing is performed if not provided
+    ///
+    /// Small density fluctuations around the iso-surface threshold lead to triangles that pop in and out between
+    /// subsequent frames of a nearly static fluid. If set, a [`SurfaceReconstruction`] that is reused for a sequence of
+    /// frames (see [`reconstruct_surface_inplace`]) keeps the (blended) density map of the previous frame and blends
+    /// it with the density map of the current frame before triangulation: `d = α·d_new + (1 - α)·d_old` per grid point,
+    /// where grid points that are missing in one of the maps have zero density. Smaller factors result in stronger smoothing
+    /// but also in a lag of the surface behind moving fluid.
+    ///
+    /// The previous density map is resampled if the background grid changed between the frames (e.g. because the particles
+    /// moved and no [`domain_aabb`](Self::domain_aabb) is given) and discarded if the cube size changed. See
+    /// [`SurfaceReconstruction::reset_temporal_smoothing`] to discard it manually, e.g. at a cut of the simulated scene.
+    /// Only supported by the global reconstruction, the smoothing is ignored when using a [`spatial_decomposition`](Self::spatial_decomposition).
+    pub temporal_density_smoothing: Option<R>,
     /// Parameters for the spatial decomposition (octree subdivision) of the particles.
     /// If not provided, no octree is generated and a global approach is used instead.
     pub spatial_decomposition: Option<SpatialDecompositionParameters<R>>,
 }
 
 impl<R: Real> Parameters<R> {
+    /// Transforms all lengths and positions of the parameters like particles that are scaled uniformly relative to the origin and then translated, i.e. `x' = scaling * x + translation`
+    ///
+    /// This allows to reconstruct the surface of transformed particles (e.g. converted from centimeters to meters with
+    /// [`transform::scale_points`]) with parameters specified for the original particles. The particle radius, the
+    /// compact support radius, the cube size and the splash detection radius are scaled, the domain AABB and the points on the
+    /// clipping planes are transformed. The rest density and all relative parameters are not affected by the transformation.
+    ///
+    /// # Panics
+    /// Panics if the scaling factor is not positive.
+    pub fn scale_and_translate(&mut self, scaling: R, translation: &Vector3<R>) {
+        assert!(
+            scaling > R::zero(),
+            "Scaling factor of the parameters has to be positive!"
+        );
+
+        self.particle_radius *= scaling;
+        self.compact_support_radius *= scaling;
+        self.cube_size *= scaling;
+        if let Some(SplashDetection::NeighborCount(radius)) = &mut self.splash_detection {
+            *radius *= scaling;
+        }
+        if let Some(aabb) = &mut self.domain_aabb {
+            *aabb = AxisAlignedBoundingBox3d::new(
+                aabb.min() * scaling + translation,
+                aabb.max() * scaling + translation,
+            );
+        }
+        for plane in self.clipping_planes.iter_mut() {
+            plane.point = plane.point * scaling + translation;
+        }
+    }
+
     /// Tries to convert the parameters from one [Real] type to another [Real] type, returns None if conversion fails
     pub fn try_convert<T: Real>(&self) -> Option<Parameters<T>> {
-        Some(Parameters {
-            particle_radius: self.particle_radius.try_convert()?,
-            rest_density: self.rest_density.try_convert()?,
-            compact_support_radius: self.compact_support_radius.try_convert()?,
-            cube_size: self.cube_size.try_convert()?,
-            iso_surface_threshold: self.iso_surface_threshold.try_convert()?,
-            domain_aabb: map_option!(&self.domain_aabb, aabb => aabb.try_convert()?),
+        self.try_convert_checked().ok()
+    }
+
+    /// Tries to convert the parameters from one [Real] type to another [Real] type, returns an error naming the first field that cannot be converted
+    pub fn try_convert_checked<T: Real>(&self) -> Result<Parameters<T>, ConversionError> {
+        Ok(Parameters {
+            particle_radius: ConversionError::convert_value(
+                self.particle_radius,
+                "particle_radius",
+            )?,
+            rest_density: ConversionError::convert_value(self.rest_density, "rest_density")?,
+            compact_support_radius: ConversionError::convert_value(
+                self.compact_support_radius,
+                "compact_support_radius",
+            )?,
+            cube_size: ConversionError::convert_value(self.cube_size, "cube_size")?,
+            iso_surface_threshold: ConversionError::convert_value(
+                self.iso_surface_threshold,
+                "iso_surface_threshold",
+            )?,
+            triangulation_scheme: self.triangulation_scheme,
+            vertex_snapping: map_option!(&self.vertex_snapping, snapping => ConversionError::convert_value(*snapping, "vertex_snapping")?),
+            splash_detection: map_option!(&self.splash_detection, sd => sd
+                .try_convert_checked()
+                .map_err(|err| err.in_field("splash_detection"))?),
+            splash_particle_output: self.splash_particle_output,
+            domain_aabb: map_option!(&self.domain_aabb, aabb => aabb
+                .try_convert_checked()
+                .map_err(|err| err.in_field("domain_aabb"))?),
+            clipping_planes: convert_clipping_planes(&self.clipping_planes)?,
             enable_multi_threading: self.enable_multi_threading,
-            spatial_decomposition: map_option!(&self.spatial_decomposition, sd => sd.try_convert()?),
+            deterministic: self.deterministic,
+            progress_interval: self.progress_interval,
+            temporal_density_smoothing: map_option!(&self.temporal_density_smoothing, alpha => ConversionError::convert_value(*alpha, "temporal_density_smoothing")?),
+            spatial_decomposition: map_option!(&self.spatial_decomposition, sd => sd
+                .try_convert_checked()
+                .map_err(|err| err.in_field("spatial_decomposition"))?),
         })
     }
+
+    /// Checks that all quantities of the parameters are finite and positive, returns an error naming the first invalid quantity
+    pub fn validate(&self) -> Result<(), ParameterError> {
+        let check = |value: R, name: &'static str| {
+            if value.is_finite() && value > R::zero() {
+                Ok(())
+            } else {
+                Err(ParameterError::InvalidValue(name))
+            }
+        };
+
+        check(self.particle_radius, "particle radius")?;
+        check(self.rest_density, "rest density")?;
+        check(self.compact_support_radius, "compact support radius")?;
+        check(self.cube_size, "cube size")?;
+        check(self.iso_surface_threshold, "iso-surface threshold")?;
+        match self.splash_detection {
+            Some(SplashDetection::NeighborCount(radius)) => {
+                check(radius, "splash detection radius")?
+            }
+            Some(SplashDetection::DensityThreshold(factor)) => {
+                check(factor, "splash detection density factor")?
+            }
+            None => {}
+        }
+        if let Some(alpha) = self.temporal_density_smoothing {
+            if !(alpha > R::zero() && alpha <= R::one()) {
+                return Err(ParameterError::InvalidFraction(
+                    "temporal density smoothing factor",
+                ));
+            }
+        }
+        if let Some(snapping) = self.vertex_snapping {
+            if !(snapping > R::zero() && snapping < R::from_f64(0.5).unwrap()) {
+                return Err(ParameterError::InvalidRange(
+                    "vertex snapping distance",
+                    "(0, 0.5)",
+                ));
+            }
+        }
+        if self.triangulation_scheme == MarchingCubesVariant::SurfaceNets
+            && self
+                .spatial_decomposition
+                .as_ref()
+                .is_some_and(|decomposition| decomposition.enable_stitching)
+        {
+            return Err(ParameterError::IncompatibleOptions(
+                "surface nets triangulation",
+                "stitching of subdomains",
+            ));
+        }
+        if matches!(
+            self.splash_detection,
+            Some(SplashDetection::DensityThreshold(_))
+        ) && self
+            .spatial_decomposition
+            .as_ref()
+            .is_some_and(|decomposition| {
+                matches!(
+                    decomposition.particle_density_computation,
+                    ParticleDensityComputationStrategy::IndependentSubdomains
+                )
+            })
+        {
+            return Err(ParameterError::IncompatibleOptions(
+                "density threshold splash detection",
+                "independent density computation of subdomains",
+            ));
+        }
+
+        Ok(())
+    }
 }
 
-/// Result data returned when the surface reconstruction was successful
+/// Converts all clipping planes to another [`Real`] type, the field path of an error contains the index of the failing plane
+fn convert_clipping_planes<R: Real, T: Real>(
+    planes: &[ClippingPlane<R>],
+) -> Result<Vec<ClippingPlane<T>>, ConversionError> {
+    planes
+        .iter()
+        .enumerate()
+        .map(|(i, plane)| {
+            plane
+                .try_convert_checked()
+                .map_err(|err| err.in_field(&format!("clipping_planes[{}]", i)))
+        })
+        .collect()
+}
+
+/// Error type returned when a value cannot be converted from one [`Real`] type to another [`Real`] type
+#[derive(Clone, PartialEq, Debug, ThisError)]
+#[error("the value {value} of the field `{field}` cannot be represented by the target type")]
+pub struct ConversionError {
+    /// Path of the field that failed to convert, e.g. `spatial_decomposition.ghost_particle_safety_factor`
+    pub field: String,
+    /// The value that could not be converted (converted to `f64`)
+    pub value: f64,
+}
+
+impl ConversionError {
+    /// Converts a single value, fails if the value cannot be represented or would overflow to infinity in the target type
+    pub(crate) fn convert_value<R: Real, T: Real>(value: R, field: &str) -> Result<T, Self> {
+        value
+            .try_convert::<T>()
+            .filter(|converted| converted.is_finite() || !value.is_finite())
+            .ok_or_else(|| ConversionError {
+                field: field.to_string(),
+                value: value.to_f64().unwrap_or(f64::NAN),
+            })
+    }
+
+    /// Converts all components of a vector, the field path of an error contains the index of the failing component
+    pub(crate) fn convert_vec<R: Real, T: Real, const D: usize>(
+        vec: &SVector<R, D>,
+        field: &str,
+    ) -> Result<SVector<T, D>, Self> {
+        let mut converted = SVector::<T, D>::zeros();
+        for i in 0..D {
+            converted[i] = Self::convert_value(vec[i], &format!("{}[{}]", field, i))?;
+        }
+        Ok(converted)
+    }
+
+    /// Prepends the name of the enclosing field to the field path of this error
+    pub(crate) fn in_field(mut self, parent: &str) -> Self {
+        self.field = format!("{}.{}", parent, self.field);
+        self
+    }
+}
+
+/// Parameters for the surface reconstruction where the kernel radius and cube size may be given relative to the particle radius
+///
+/// For each of these two quantities either the absolute or the relative value has to be specified.
+/// The actual [`Parameters`] for a specific particle radius are obtained using [`RelativeParameters::resolve`].
+/// All other fields correspond to the fields of [`Parameters`] with the same name.
 #[derive(Clone, Debug)]
+pub struct RelativeParameters<R: Real> {
+    /// Rest density of the fluid
+    pub rest_density: R,
+    /// Compact support radius of the kernel (in distance units)
+    pub compact_support_radius: Option<R>,
+    /// Compact support radius of the kernel (in multiplies of the particle radius)
+    pub relative_compact_support_radius: Option<R>,
+    /// Edge length of the marching cubes implicit background grid (in distance units)
+    pub cube_size: Option<R>,
+    /// Edge length of the marching cubes implicit background grid (in multiplies of the particle radius)
+    pub relative_cube_size: Option<R>,
+    /// Density threshold value to distinguish between the inside (above threshold) and outside (below threshold) of the fluid
+    pub iso_surface_threshold: R,
+    /// Variant of the marching cubes triangulation, see [`Parameters::triangulation_scheme`]
+    pub triangulation_scheme: MarchingCubesVariant,
+    /// Distance (relative to the cube size) within which iso-surface vertices are snapped onto grid points, see [`Parameters::vertex_snapping`]
+    pub vertex_snapping: Option<R>,
+    /// Criterion used for the detection of splash or free particles that are excluded from the reconstruction
+    pub splash_detection: Option<SplashDetection<R>>,
+    /// Whether and how the detected splash particles should be added to the output as spheres with the particle radius
+    pub splash_particle_output: SplashOutput,
+    /// Manually restrict the domain to the surface reconstruction
+    pub domain_aabb: Option<AxisAlignedBoundingBox3d<R>>,
+    /// Half-spaces that restrict the domain of the surface reconstruction
+    pub clipping_planes: Vec<ClippingPlane<R>>,
+    /// Whether to allow multi threading within the surface reconstruction procedure, ignored if the `parallel` feature is disabled
+    pub enable_multi_threading: bool,
+    /// Whether the reconstruction should be deterministic, see [`Parameters::deterministic`]
+    pub deterministic: bool,
+    /// Interval for periodic progress messages of long-running stages, see [`Parameters::progress_interval`]
+    pub progress_interval: Option<Duration>,
+    /// Blending factor of the temporal smoothing of the density map, see [`Parameters::temporal_density_smoothing`]
+    pub temporal_density_smoothing: Option<R>,
+    /// Parameters for the spatial decomposition (octree subdivision) of the particles
+    pub spatial_decomposition: Option<SpatialDecompositionParameters<R>>,
+}
+
+/// Error type returned when [`RelativeParameters`] cannot be resolved to absolute [`Parameters`]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, ThisError)]
+pub enum ParameterError {
+    /// Both an absolute and a relative value were specified for the same quantity
+    #[error("both an absolute and a relative value were specified for the {0}, specify only one of them")]
+    AbsoluteAndRelativeValue(&'static str),
+    /// Neither an absolute nor a relative value was specified for a quantity
+    #[error("neither an absolute nor a relative value was specified for the {0}")]
+    MissingValue(&'static str),
+    /// A quantity is not finite or not positive
+    #[error("the {0} has to be finite and larger than zero")]
+    InvalidValue(&'static str),
+    /// A fraction is not larger than zero or larger than one
+    #[error("the {0} has to be larger than zero and at most one")]
+    InvalidFraction(&'static str),
+    /// A quantity is outside of its valid range (given as interval)
+    #[error("the {0} has to be in the range {1}")]
+    InvalidRange(&'static str, &'static str),
+    /// Two options were enabled that cannot be used together
+    #[error("the {0} cannot be combined with the {1}")]
+    IncompatibleOptions(&'static str, &'static str),
+}
+
+impl<R: Real> RelativeParameters<R> {
+    /// Resolves the relative quantities using the given particle radius and returns the resulting absolute parameters
+    pub fn resolve(&self, particle_radius: R) -> Result<Parameters<R>, ParameterError> {
+        let resolve_length = |absolute: Option<R>, relative: Option<R>, name: &'static str| match (
+            absolute, relative,
+        ) {
+            (Some(_), Some(_)) => Err(ParameterError::AbsoluteAndRelativeValue(name)),
+            (Some(absolute), None) => Ok(absolute),
+            (None, Some(relative)) => Ok(relative * particle_radius),
+            (None, None) => Err(ParameterError::MissingValue(name)),
+        };
+
+        Ok(Parameters {
+            particle_radius,
+            rest_density: self.rest_density,
+            compact_support_radius: resolve_length(
+                self.compact_support_radius,
+                self.relative_compact_support_radius,
+                "compact support radius",
+            )?,
+            cube_size: resolve_length(self.cube_size, self.relative_cube_size, "cube size")?,
+            iso_surface_threshold: self.iso_surface_threshold,
+            triangulation_scheme: self.triangulation_scheme,
+            vertex_snapping: self.vertex_snapping,
+            splash_detection: self.splash_detection.clone(),
+            splash_particle_output: self.splash_particle_output,
+            domain_aabb: self.domain_aabb.clone(),
+            clipping_planes: self.clipping_planes.clone(),
+            enable_multi_threading: self.enable_multi_threading,
+            deterministic: self.deterministic,
+            progress_interval: self.progress_interval,
+            temporal_density_smoothing: self.temporal_density_smoothing,
+            spatial_decomposition: self.spatial_decomposition.clone(),
+        })
+    }
+
+    /// Tries to convert the parameters from one [Real] type to another [Real] type, returns None if conversion fails
+    pub fn try_convert<T: Real>(&self) -> Option<RelativeParameters<T>> {
+        self.try_convert_checked().ok()
+    }
+
+    /// Tries to convert the parameters from one [Real] type to another [Real] type, returns an error naming the first field that cannot be converted
+    pub fn try_convert_checked<T: Real>(&self) -> Result<RelativeParameters<T>, ConversionError> {
+        Ok(RelativeParameters {
+            rest_density: ConversionError::convert_value(self.rest_density, "rest_density")?,
+            compact_support_radius: map_option!(&self.compact_support_radius, r => ConversionError::convert_value(*r, "compact_support_radius")?),
+            relative_compact_support_radius: map_option!(&self.relative_compact_support_radius, r => ConversionError::convert_value(*r, "relative_compact_support_radius")?),
+            cube_size: map_option!(&self.cube_size, r => ConversionError::convert_value(*r, "cube_size")?),
+            relative_cube_size: map_option!(&self.relative_cube_size, r => ConversionError::convert_value(*r, "relative_cube_size")?),
+            iso_surface_threshold: ConversionError::convert_value(
+                self.iso_surface_threshold,
+                "iso_surface_threshold",
+            )?,
+            triangulation_scheme: self.triangulation_scheme,
+            vertex_snapping: map_option!(&self.vertex_snapping, snapping => ConversionError::convert_value(*snapping, "vertex_snapping")?),
+            splash_detection: map_option!(&self.splash_detection, sd => sd
+                .try_convert_checked()
+                .map_err(|err| err.in_field("splash_detection"))?),
+            splash_particle_output: self.splash_particle_output,
+            domain_aabb: map_option!(&self.domain_aabb, aabb => aabb
+                .try_convert_checked()
+                .map_err(|err| err.in_field("domain_aabb"))?),
+            clipping_planes: convert_clipping_planes(&self.clipping_planes)?,
+            enable_multi_threading: self.enable_multi_threading,
+            deterministic: self.deterministic,
+            progress_interval: self.progress_interval,
+            temporal_density_smoothing: map_option!(&self.temporal_density_smoothing, alpha => ConversionError::convert_value(*alpha, "temporal_density_smoothing")?),
+            spatial_decomposition: map_option!(&self.spatial_decomposition, sd => sd
+                .try_convert_checked()
+                .map_err(|err| err.in_field("spatial_decomposition"))?),
+        })
+    }
+}
+
+/// Available methods for the computation of surface normals
+#[derive(Copy, Clone, Debug)]
+pub enum NormalsComputation {
+    /// Normals are computed by SPH interpolation of the gradient of the fluid's indicator function
+    SphInterpolation,
+    /// Normals are computed by averaging the area weighted normals of the adjacent triangles
+    AreaWeighted,
+}
+
+/// Methods for transferring per particle attributes to the vertices of a reconstructed surface mesh
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AttributeInterpolation {
+    /// SPH interpolation with first order correction, suited for smooth fields like velocities (not supported for integer attributes)
+    Sph,
+    /// Every vertex receives the value of its nearest particle, suited for discrete values like ids or phase labels
+    Nearest,
+}
+
+impl AttributeInterpolation {
+    /// Returns the method suited for the given attribute data: nearest-particle transfer for integer attributes, SPH interpolation otherwise
+    pub fn default_for<R: Real>(data: &AttributeData<R>) -> Self {
+        match data {
+            AttributeData::ScalarU64(_) => AttributeInterpolation::Nearest,
+            AttributeData::ScalarReal(_)
+            | AttributeData::Vector3Real(_)
+            | AttributeData::VectorReal { .. } => AttributeInterpolation::Sph,
+        }
+    }
+}
+
+/// Selection of the attributes that should be attached to a reconstructed surface mesh, see [`reconstruct_surface_with_attributes`]
+#[derive(Clone, Debug, Default)]
+pub struct AttributeSpecs<'a, R: Real> {
+    /// If set, vertex normals are computed using the given method (stored as point attribute `"normals"`)
+    pub normals: Option<NormalsComputation>,
+    /// Whether to interpolate the particle densities to the vertices (stored as point attribute `"density"`)
+    pub vertex_densities: bool,
+    /// Whether to store the value of the density map at which each vertex was extracted by marching cubes (stored as point
+    /// attribute `"mc_density"`, approximately the iso-surface threshold) and the SPH interpolation of the same field at
+    /// the vertex (stored as point attribute `"sph_density"`), e.g. to find regions where both disagree
+    ///
+    /// The `mc_density` of a vertex is the linear interpolation of the density map values at the end points of the grid
+    /// edge containing the vertex. Vertices of merged splash particle spheres do not lie on grid edges and get `NaN`.
+    pub density_attributes: bool,
+    /// Whether to store the flat index of the background grid cell containing the centroid of each triangle (stored as cell attribute `"cell_id"`)
+    pub triangle_cell_ids: bool,
+    /// Whether to store the id of the octree leaf containing the centroid of each triangle (stored as cell attribute `"leaf_id"`, ignored without spatial decomposition)
+    pub triangle_leaf_ids: bool,
+    /// Per particle attributes that are transferred to the vertices (stored as point attributes with the same names)
+    pub particle_attributes: &'a [MeshAttribute<R>],
+    /// Method used to transfer each of the particle attributes, either one entry per particle attribute or empty to interpolate all attributes using [`AttributeInterpolation::Sph`]
+    pub particle_attribute_interpolation: &'a [AttributeInterpolation],
+}
+
+/// Result data returned when the surface reconstruction was successful
+///
+/// The surface mesh and the background grid are stored behind an [`Arc`], see [`snapshot_mesh`](Self::snapshot_mesh)
+/// and [`share_grid`](Self::share_grid) to share them with other threads without copying them.
+#[derive(Debug)]
 pub struct SurfaceReconstruction<I: Index, R: Real> {
     /// Background grid that was used as a basis for generating the density map for marching cubes
-    grid: UniformGrid<I, R>,
+    grid: Arc<UniformGrid<I, R>>,
     /// Octree constructed for domain decomposition
     octree: Option<Octree<I, R>>,
     /// Point-based density map generated from the particles that was used as input to marching cubes
     density_map: Option<DensityMap<I, R>>,
+    /// Blended density map of the previous frame and its grid for the temporal smoothing, see [`Parameters::temporal_density_smoothing`]
+    temporal_density_state: Option<TemporalDensityState<I, R>>,
+    /// Policy for particles outside of the grid if the grid is reused by subsequent reconstructions, see [`set_fixed_grid`](Self::set_fixed_grid)
+    grid_escape_policy: Option<GridEscapePolicy>,
+    /// Parameters that determined the fixed grid, a reconstruction with different parameters computes a new grid
+    fixed_grid_key: Option<FixedGridKey<R>>,
     /// Per particle densities
     particle_densities: Option<Vec<R>>,
+    /// Indices of all particles that were classified as splash particles and excluded from the reconstruction
+    splash_particle_indices: Option<Vec<usize>>,
+    /// Sphere geometry of all splash particles if they are output as a separate mesh
+    splash_mesh: Option<TriMesh3d<R>>,
+    /// Index of the originating splash particle for every vertex of the splash particle spheres
+    splash_vertex_particle_indices: Option<Vec<usize>>,
     /// Surface mesh that is the result of the surface reconstruction
-    mesh: TriMesh3d<R>,
+    mesh: Arc<TriMesh3d<R>>,
+    /// Origin of every triangle of the surface mesh if it is recorded, see [`set_record_triangle_origins`](Self::set_record_triangle_origins)
+    triangle_origins: Option<TriangleOrigins<I>>,
     /// Workspace with allocated memory for subsequent surface reconstructions
     workspace: ReconstructionWorkspace<I, R>,
+    /// Token that is checked between the stages of the reconstruction to abort it early
+    cancellation_token: CancellationToken,
+    /// Collector that records the timings of the reconstruction stages
+    timings_collector: Option<TimingsCollector>,
+    /// Whether the density map of a global reconstruction is kept in the result instead of the workspace
+    keep_density_map: bool,
+    /// Whether the origins of the triangles are recorded during the triangulation
+    record_triangle_origins: bool,
+    /// Whether the thread local workspaces are consolidated after every reconstruction
+    consolidate_workspace: bool,
+    /// Whether the reconstruction is appended to the mesh instead of replacing it, see [`reconstruct_surface_append`]
+    append_to_mesh: bool,
+    /// Statistics of the last reconstruction
+    stats: ReconstructionStats,
+}
+
+/// Blended density map of the previous frame that is kept for the temporal smoothing, see [`Parameters::temporal_density_smoothing`]
+#[derive(Clone, Debug)]
+pub(crate) struct TemporalDensityState<I: Index, R: Real> {
+    /// Background grid of the density map
+    pub grid: Arc<UniformGrid<I, R>>,
+    /// The blended density map of the previous frame
+    pub density_map: DensityMap<I, R>,
+}
+
+/// Parameters that determine the background grid of a reconstruction, see [`SurfaceReconstruction::set_fixed_grid`]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FixedGridKey<R: Real> {
+    pub particle_radius: R,
+    pub compact_support_radius: R,
+    pub cube_size: R,
+    pub domain_aabb: Option<AxisAlignedBoundingBox3d<R>>,
+}
+
+impl<R: Real> FixedGridKey<R> {
+    pub(crate) fn new(parameters: &Parameters<R>) -> Self {
+        Self {
+            particle_radius: parameters.particle_radius,
+            compact_support_radius: parameters.compact_support_radius,
+            cube_size: parameters.cube_size,
+            domain_aabb: parameters.domain_aabb.clone(),
+        }
+    }
+}
+
+/// Statistics of the last reconstruction performed with a [`SurfaceReconstruction`]
+#[derive(Clone, Debug, Default)]
+pub struct ReconstructionStats {
+    /// Wall-clock timings of the reconstruction stages (only recorded if a collector was set using [`SurfaceReconstruction::set_timings_collector`])
+    pub timings: Option<Timings>,
+    /// Wall-clock times of the individual octree leaves, only recorded by the octree based reconstruction if a
+    /// [`TimingsCollector`] is attached to the calling thread (e.g. using [`SurfaceReconstruction::set_timings_collector`])
+    pub leaf_timings: Option<LeafTimings>,
+    /// Counters of soft issues that were detected during the reconstruction but did not abort it
+    pub warnings: ReconstructionWarnings,
+    /// Whether the background grid of the previous reconstruction was reused, see [`SurfaceReconstruction::set_fixed_grid`]
+    pub grid_reused: bool,
+}
+
+/// Counters of soft issues detected during a reconstruction, aggregated over all threads
+///
+/// None of these issues abort the reconstruction but they may indicate unsuitable parameters (e.g. a too small
+/// domain) or lead to artifacts in the resulting mesh.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReconstructionWarnings {
+    /// Number of particles that were skipped because they are outside of the user provided domain AABB
+    /// (or too close to its boundary to contribute their full kernel support to the density map)
+    pub particles_outside_domain: usize,
+    /// Number of density values that were reduced by the clipping planes, possibly counted multiple times for
+    /// grid points in the overlap of octree leaves
+    pub clamped_density_values: usize,
+    /// Number of triangles with zero area in the resulting mesh, these triangles are kept in the mesh
+    pub degenerate_triangles: usize,
+    /// Number of octree leaves whose ghost particle margin reaches beyond the domain boundary where particles were skipped
+    pub truncated_ghost_margins: usize,
+}
+
+impl ReconstructionWarnings {
+    /// Returns the sum of all counters
+    pub fn total(&self) -> usize {
+        self.particles_outside_domain
+            + self.clamped_density_values
+            + self.degenerate_triangles
+            + self.truncated_ghost_margins
+    }
+
+    /// Returns whether no warnings were recorded
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// Returns the names and values of all counters
+    pub fn counters(&self) -> [(&'static str, usize); 4] {
+        [
+            ("particles outside of domain", self.particles_outside_domain),
+            ("clamped density values", self.clamped_density_values),
+            ("degenerate triangles", self.degenerate_triangles),
+            ("truncated ghost margins", self.truncated_ghost_margins),
+        ]
+    }
+}
+
+impl ops::AddAssign for ReconstructionWarnings {
+    fn add_assign(&mut self, other: Self) {
+        self.particles_outside_domain += other.particles_outside_domain;
+        self.clamped_density_values += other.clamped_density_values;
+        self.degenerate_triangles += other.degenerate_triangles;
+        self.truncated_ghost_margins += other.truncated_ghost_margins;
+    }
+}
+
+impl ops::Add for ReconstructionWarnings {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl fmt::Display for ReconstructionWarnings {
+    /// Lists all non-zero counters, e.g. `12 particles outside of domain, 3 degenerate triangles`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no warnings");
+        }
+
+        let mut first = true;
+        for (name, count) in self.counters() {
+            if count > 0 {
+                if !first {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{} {}", count, name)?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<I: Index, R: Real> Default for SurfaceReconstruction<I, R> {
     /// Returns an empty [SurfaceReconstruction] to pass into the inplace surface reconstruction
     fn default() -> Self {
         Self {
-            grid: UniformGrid::new_zero(),
+            grid: Arc::new(UniformGrid::new_zero()),
             octree: None,
             density_map: None,
+            temporal_density_state: None,
+            grid_escape_policy: None,
+            fixed_grid_key: None,
             particle_densities: None,
-            mesh: TriMesh3d::default(),
+            splash_particle_indices: None,
+            splash_mesh: None,
+            splash_vertex_particle_indices: None,
+            mesh: Arc::default(),
+            triangle_origins: None,
+            workspace: ReconstructionWorkspace::default(),
+            cancellation_token: CancellationToken::default(),
+            timings_collector: None,
+            keep_density_map: false,
+            record_triangle_origins: false,
+            consolidate_workspace: false,
+            append_to_mesh: false,
+            stats: ReconstructionStats::default(),
+        }
+    }
+}
+
+impl<I: Index, R: Real> Clone for SurfaceReconstruction<I, R> {
+    /// Returns a copy of the results of the reconstruction without the workspace
+    ///
+    /// The surface mesh and the background grid are shared with the original (as with [`snapshot_mesh`](Self::snapshot_mesh)
+    /// and [`share_grid`](Self::share_grid)), all other results are copied. The workspace is not copied: the clone starts
+    /// with an empty workspace that is allocated on demand by subsequent reconstructions. The clone also gets a new
+    /// [`CancellationToken`], so cancelling the reconstructions of the original does not affect the clone.
+    fn clone(&self) -> Self {
+        Self {
+            grid: self.grid.clone(),
+            octree: self.octree.clone(),
+            density_map: self.density_map.clone(),
+            temporal_density_state: self.temporal_density_state.clone(),
+            grid_escape_policy: self.grid_escape_policy,
+            fixed_grid_key: self.fixed_grid_key.clone(),
+            particle_densities: self.particle_densities.clone(),
+            splash_particle_indices: self.splash_particle_indices.clone(),
+            splash_mesh: self.splash_mesh.clone(),
+            splash_vertex_particle_indices: self.splash_vertex_particle_indices.clone(),
+            mesh: self.mesh.clone(),
+            triangle_origins: self.triangle_origins.clone(),
             workspace: ReconstructionWorkspace::default(),
+            cancellation_token: CancellationToken::default(),
+            timings_collector: self.timings_collector.clone(),
+            keep_density_map: self.keep_density_map,
+            record_triangle_origins: self.record_triangle_origins,
+            consolidate_workspace: self.consolidate_workspace,
+            append_to_mesh: self.append_to_mesh,
+            stats: self.stats.clone(),
         }
     }
 }
 
+/// All results of a surface reconstruction moved out of a [`SurfaceReconstruction`], see [`SurfaceReconstruction::into_parts`]
+#[derive(Clone, Debug)]
+pub struct SurfaceReconstructionParts<I: Index, R: Real> {
+    /// Background grid that was used as a basis for generating the density map for marching cubes
+    pub grid: UniformGrid<I, R>,
+    /// Octree constructed for domain decomposition
+    pub octree: Option<Octree<I, R>>,
+    /// Point-based density map generated from the particles that was used as input to marching cubes
+    pub density_map: Option<DensityMap<I, R>>,
+    /// Per particle densities
+    pub particle_densities: Option<Vec<R>>,
+    /// Indices of all particles that were classified as splash particles and excluded from the reconstruction
+    pub splash_particle_indices: Option<Vec<usize>>,
+    /// Sphere geometry of all splash particles if they are output as a separate mesh
+    pub splash_mesh: Option<TriMesh3d<R>>,
+    /// Index of the originating splash particle for every vertex of the splash particle spheres
+    pub splash_vertex_particle_indices: Option<Vec<usize>>,
+    /// Surface mesh that is the result of the surface reconstruction
+    pub mesh: TriMesh3d<R>,
+    /// Origin of every triangle of the surface mesh if it was recorded
+    pub triangle_origins: Option<TriangleOrigins<I>>,
+    /// Statistics of the reconstruction
+    pub stats: ReconstructionStats,
+}
+
+/// Origin of every triangle of a reconstructed surface mesh for debugging, see [`SurfaceReconstruction::set_record_triangle_origins`]
+///
+/// Triangles that were not created by the triangulation of the density map (the spheres of splash particles merged
+/// into the mesh or triangles of a mesh that the reconstruction was appended to) have the cell index `I::max_value()`
+/// and the node id `usize::MAX`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TriangleOrigins<I: Index> {
+    /// Flat index of the background grid cell whose triangulation created each triangle
+    pub cells: Vec<I>,
+    /// Id of the octree node that created each triangle (empty without spatial decomposition)
+    ///
+    /// This is the leaf for triangles of its surface patch and the parent node for triangles created by stitching the
+    /// surface patches of its children.
+    pub nodes: Vec<usize>,
+}
+
+impl<I: Index> TriangleOrigins<I> {
+    /// Resizes the origins to the given number of triangles, additional triangles get an unknown origin
+    pub(crate) fn resize(&mut self, num_triangles: usize) {
+        self.cells.resize(num_triangles, I::max_value());
+        if !self.nodes.is_empty() {
+            self.nodes.resize(num_triangles, usize::MAX);
+        }
+    }
+
+    /// Appends the origins of the triangles of another mesh that was appended to the mesh of these origins
+    pub(crate) fn append(&mut self, other: &mut Self) {
+        self.cells.append(&mut other.cells);
+        self.nodes.append(&mut other.nodes);
+    }
+}
+
 impl<I: Index, R: Real> SurfaceReconstruction<I, R> {
     /// Returns a reference to the actual triangulated surface mesh that is the result of the reconstruction
     pub fn mesh(&self) -> &TriMesh3d<R> {
         &self.mesh
     }
 
+    /// Returns a mutable reference to the reconstructed surface mesh, e.g. for in-place post-processing
+    ///
+    /// If the mesh is still shared with a snapshot (see [`snapshot_mesh`](Self::snapshot_mesh)), it is copied first
+    /// such that the snapshot is not modified.
+    ///
+    /// Note that changing the vertices or triangles of the mesh may invalidate the [`splash_vertex_particle_indices`](Self::splash_vertex_particle_indices)
+    /// (when using [`SplashOutput::MergedSpheres`]) and the results of [`mesh_with_attributes`](Self::mesh_with_attributes).
+    pub fn mesh_mut(&mut self) -> &mut TriMesh3d<R> {
+        Arc::make_mut(&mut self.mesh)
+    }
+
+    /// Returns a shared reference to the reconstructed surface mesh without copying it, e.g. to hand it to another thread
+    ///
+    /// The snapshot is not affected by subsequent reconstructions: [`reconstruct_surface_inplace`] replaces a mesh
+    /// that is still shared by a snapshot with a new mesh instead of overwriting it.
+    pub fn snapshot_mesh(&self) -> Arc<TriMesh3d<R>> {
+        self.mesh.clone()
+    }
+
+    /// Moves the reconstructed surface mesh out of the reconstruction without cloning it, leaving an empty mesh behind
+    ///
+    /// All other results and the allocated workspace are kept, so the reconstruction can still be passed to
+    /// [`reconstruct_surface_inplace`] to reconstruct the next frame of a sequence. The next reconstruction
+    /// replaces the empty mesh (it does not reuse the memory of the taken mesh). The mesh is only copied if it
+    /// is still shared with a snapshot (see [`snapshot_mesh`](Self::snapshot_mesh)).
+    pub fn take_mesh(&mut self) -> TriMesh3d<R> {
+        Arc::unwrap_or_clone(std::mem::take(&mut self.mesh))
+    }
+
+    /// Reserves workspace memory for subsequent reconstructions with the given number of particles, e.g. to avoid allocations during a latency sensitive first frame
+    ///
+    /// This reserves the global particle density vector (used by the octree based reconstruction) as well as
+    /// the per particle buffers and the density map of the workspace of the calling thread. The density map
+    /// is only reused by the global reconstruction (without spatial decomposition) when multi-threading is disabled,
+    /// `estimated_grid_points` should be an estimate of the number of grid points in the sparse density map
+    /// (see [`parameter_suggestion::ReconstructionEstimate::density_map_points`]).
+    /// Workspaces of other threads (e.g. the workers of the octree based reconstruction) are allocated on demand.
+    pub fn preallocate(&mut self, num_particles: usize, estimated_grid_points: usize) {
+        self.workspace
+            .preallocate(num_particles, estimated_grid_points);
+    }
+
+    /// Frees the memory of the workspace including the workspaces of all threads, the results of the last reconstruction are kept
+    ///
+    /// The reconstruction can still be passed to [`reconstruct_surface_inplace`] afterwards, the workspace is allocated again on demand.
+    pub fn clear_workspace(&mut self) {
+        self.workspace.clear();
+    }
+
+    /// Drops the workspaces of all threads except for the one with the most allocated memory, which is retained for subsequent reconstructions
+    ///
+    /// After a reconstruction with many threads (e.g. using spatial decomposition), every thread keeps its own grown
+    /// workspace. Consolidation reduces the retained memory to the needs of a single thread, e.g. for long-lived
+    /// reconstruction objects. The retained workspace is taken over by the first thread that requires a workspace in
+    /// the next reconstruction, the other threads allocate their workspaces again on demand.
+    /// See [`set_consolidate_workspace`](Self::set_consolidate_workspace) to consolidate after every reconstruction.
+    pub fn consolidate_workspace(&mut self) {
+        self.workspace.consolidate();
+    }
+
+    /// Sets whether the workspace is consolidated at the end of subsequent reconstructions, see [`consolidate_workspace`](Self::consolidate_workspace)
+    ///
+    /// Disabled by default, i.e. the workspaces of all threads are kept.
+    pub fn set_consolidate_workspace(&mut self, consolidate_workspace: bool) {
+        self.consolidate_workspace = consolidate_workspace;
+    }
+
+    /// Returns the number of bytes currently allocated by the workspace per buffer, including the workspaces of all threads
+    pub fn workspace_memory_usage(&mut self) -> WorkspaceMemoryReport {
+        self.workspace.memory_usage()
+    }
+
+    /// Sets the token that is checked by subsequent reconstructions using this object, see [`CancellationToken`]
+    pub fn set_cancellation_token(&mut self, cancellation_token: CancellationToken) {
+        self.cancellation_token = cancellation_token;
+    }
+
+    /// Returns a reference to the token that is checked by reconstructions using this object
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation_token
+    }
+
+    /// Sets the collector that records the timings of the stages of subsequent reconstructions using this object, see [`timings`]
+    ///
+    /// The collector is reset and attached to the calling thread at the start of every reconstruction, the recorded
+    /// timings are available in the [`stats`](Self::stats) afterwards. Passing `None` disables the timings.
+    pub fn set_timings_collector(&mut self, timings_collector: Option<TimingsCollector>) {
+        self.timings_collector = timings_collector;
+    }
+
+    /// Sets whether subsequent global reconstructions (without spatial decomposition) keep their density map, see [`density_map`](Self::density_map)
+    ///
+    /// By default, the density map stays in the workspace to reuse its memory in subsequent reconstructions.
+    pub fn set_keep_density_map(&mut self, keep_density_map: bool) {
+        self.keep_density_map = keep_density_map;
+    }
+
+    /// Sets whether subsequent reconstructions record the grid cell and octree node that created each triangle, see [`triangle_origins`](Self::triangle_origins)
+    ///
+    /// The recording is disabled by default and does not cause any overhead in this case.
+    pub fn set_record_triangle_origins(&mut self, record_triangle_origins: bool) {
+        self.record_triangle_origins = record_triangle_origins;
+    }
+
+    /// Discards the density map of the previous frame that is kept for the temporal smoothing, see [`Parameters::temporal_density_smoothing`]
+    ///
+    /// The next reconstruction with temporal smoothing starts a new sequence of frames, e.g. after a cut in the simulated scene.
+    pub fn reset_temporal_smoothing(&mut self) {
+        self.temporal_density_state = None;
+    }
+
+    /// Sets whether subsequent reconstructions reuse the background grid instead of computing a new grid for every frame
+    ///
+    /// The grid is computed by the next reconstruction (from the particles or the [`domain_aabb`](Parameters::domain_aabb))
+    /// and reused as long as the particle radius, compact support radius, cube size and domain of the parameters do not
+    /// change. This keeps the origin of the grid fixed over a sequence of frames instead of moving it with the fluid.
+    /// Particles that are outside of the grid or too close to its boundary to contribute their full kernel support are
+    /// handled according to the given policy, see [`stats`](Self::stats) whether the grid was reused.
+    /// Passing `None` computes a new grid for every reconstruction (default). A previously fixed grid is discarded in any case.
+    pub fn set_fixed_grid(&mut self, grid_escape_policy: Option<GridEscapePolicy>) {
+        self.grid_escape_policy = grid_escape_policy;
+        self.fixed_grid_key = None;
+    }
+
+    /// Returns the statistics of the last reconstruction
+    pub fn stats(&self) -> &ReconstructionStats {
+        &self.stats
+    }
+
+    /// Consumes the reconstruction and returns all of its results without cloning them, the workspace is dropped
+    ///
+    /// Only the mesh and the grid are copied if they are still shared (see [`snapshot_mesh`](Self::snapshot_mesh) and [`share_grid`](Self::share_grid)).
+    pub fn into_parts(self) -> SurfaceReconstructionParts<I, R> {
+        SurfaceReconstructionParts {
+            grid: Arc::unwrap_or_clone(self.grid),
+            octree: self.octree,
+            density_map: self.density_map,
+            particle_densities: self.particle_densities,
+            splash_particle_indices: self.splash_particle_indices,
+            splash_mesh: self.splash_mesh,
+            splash_vertex_particle_indices: self.splash_vertex_particle_indices,
+            mesh: Arc::unwrap_or_clone(self.mesh),
+            triangle_origins: self.triangle_origins,
+            stats: self.stats,
+        }
+    }
+
     /// Returns a reference to the octree generated for spatial decomposition of the input particles (mostly useful for debugging visualization)
     pub fn octree(&self) -> Option<&Octree<I, R>> {
         self.octree.as_ref()
     }
 
-    /// Returns a reference to the sparse density map (discretized on the vertices of the background grid) that is used as input for marching cubes (always `None` when using domain decomposition)
+    /// Returns the grid cell and octree node that created each triangle of the surface mesh (mostly useful to trace artifacts of the mesh back to their origin)
+    ///
+    /// The origins are only available if their recording was enabled with [`set_record_triangle_origins`](Self::set_record_triangle_origins).
+    /// They are not updated by modifications of the mesh, e.g. using [`mesh_mut`](Self::mesh_mut).
+    pub fn triangle_origins(&self) -> Option<&TriangleOrigins<I>> {
+        self.triangle_origins.as_ref()
+    }
+
+    /// Returns a reference to the sparse density map (discretized on the vertices of the background grid) that is used as input for marching cubes
+    ///
+    /// The density map is only available if it was enabled with [`set_keep_density_map`](Self::set_keep_density_map)
+    /// (always `None` when using domain decomposition).
     pub fn density_map(&self) -> Option<&DensityMap<I, R>> {
         self.density_map.as_ref()
     }
 
+    /// Returns a copy of the density map, grid, particle densities and splash particles of the last reconstruction, see [`reconstruct_surface_from_density_map`]
+    ///
+    /// Returns `None` if the density map was not kept (see [`set_keep_density_map`](Self::set_keep_density_map)).
+    pub fn density_map_snapshot(&self) -> Option<DensityMapSnapshot<I, R>> {
+        Some(DensityMapSnapshot {
+            grid: UniformGrid::clone(&self.grid),
+            density_map: self.density_map.clone()?,
+            particle_densities: self.particle_densities.clone()?,
+            splash_particle_indices: self.splash_particle_indices.clone(),
+        })
+    }
+
     /// Returns a reference to the global particle density vector if it was computed during the reconstruction (always `None` when using independent subdomains with domain decomposition)
     pub fn particle_densities(&self) -> Option<&Vec<R>> {
         self.particle_densities.as_ref()
     }
 
+    /// Returns a reference to the sorted indices of all particles that were classified as splash particles and excluded from the reconstruction (always `None` if splash detection is disabled)
+    pub fn splash_particle_indices(&self) -> Option<&Vec<usize>> {
+        self.splash_particle_indices.as_ref()
+    }
+
+    /// Returns a reference to the mesh consisting of one sphere per splash particle (only available if [`SplashOutput::SeparateMesh`] is selected)
+    pub fn splash_mesh(&self) -> Option<&TriMesh3d<R>> {
+        self.splash_mesh.as_ref()
+    }
+
+    /// Returns a reference to the indices of the originating particles of all splash particle sphere vertices (always `None` if [`SplashOutput::Ignore`] is selected)
+    ///
+    /// With [`SplashOutput::SeparateMesh`] there is one entry per vertex of the [`splash_mesh`](Self::splash_mesh).
+    /// With [`SplashOutput::MergedSpheres`] the spheres are appended to the end of the surface [`mesh`](Self::mesh),
+    /// i.e. the entries correspond to the last vertices of the mesh.
+    /// This mapping can be used to assign per particle attributes to the spheres, e.g. using [`AttributeData::select`](mesh::AttributeData::select).
+    pub fn splash_vertex_particle_indices(&self) -> Option<&Vec<usize>> {
+        self.splash_vertex_particle_indices.as_ref()
+    }
+
     /// Returns a reference to the virtual background grid that was used as a basis for discretization of the density map for marching cubes, can be used to convert the density map to a hex mesh (using [`density_map::sparse_density_map_to_hex_mesh`])
     pub fn grid(&self) -> &UniformGrid<I, R> {
         &self.grid
     }
+
+    /// Returns a shared reference to the background grid without copying it, e.g. to hand it to another thread
+    pub fn share_grid(&self) -> Arc<UniformGrid<I, R>> {
+        self.grid.clone()
+    }
+
+    /// Returns a copy of the reconstructed mesh with all attributes selected by the given specification, see [`reconstruct_surface_with_attributes`]
+    ///
+    /// The particle positions and parameters have to be the same as used for the reconstruction.
+    /// Cell attributes refer to the triangles of [`mesh`](Self::mesh) (including merged splash particle spheres).
+    /// Merged splash particle spheres receive the exact sphere normals and the values of their originating particles
+    /// instead of interpolated values.
+    pub fn mesh_with_attributes(
+        &self,
+        particle_positions: &[Vector3<R>],
+        parameters: &Parameters<R>,
+        attribute_specs: &AttributeSpecs<R>,
+    ) -> Result<MeshWithData<R, TriMesh3d<R>>, ReconstructionError<I, R>> {
+        reconstruction::compute_surface_attributes(
+            self,
+            particle_positions,
+            parameters,
+            attribute_specs,
+        )
+    }
 }
 
 impl<I: Index, R: Real> From<SurfaceReconstruction<I, R>> for TriMesh3d<R> {
     /// Extracts the reconstructed mesh
     fn from(result: SurfaceReconstruction<I, R>) -> Self {
-        result.mesh
+        Arc::unwrap_or_clone(result.mesh)
+    }
+}
+
+/// Token that can be used to cancel a running surface reconstruction from another thread
+///
+/// The token is checked between the stages of the reconstruction and before processing each
+/// octree node when using spatial decomposition. A cancelled reconstruction returns
+/// [`ReconstructionError::Cancelled`]. Clones of a token share the same state.
+/// To cancel a reconstruction, pass a clone of the token to [`SurfaceReconstruction::set_cancellation_token`]
+/// and use [`reconstruct_surface_inplace`].
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Returns a new token that is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests the cancellation of all reconstructions using this token (or a clone of it)
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether the cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns [`ReconstructionError::Cancelled`] if the cancellation was requested
+    pub(crate) fn check<I: Index, R: Real>(&self) -> Result<(), ReconstructionError<I, R>> {
+        if self.is_cancelled() {
+            Err(ReconstructionError::Cancelled)
+        } else {
+            Ok(())
+        }
     }
 }
 
+/// Reason why the particle data passed to the surface reconstruction is invalid
+#[derive(Copy, Clone, Eq, PartialEq, Debug, ThisError)]
+pub enum InvalidParticleReason {
+    /// At least one coordinate of the particle position is NaN
+    #[error("the particle position contains a NaN coordinate")]
+    NanPosition,
+    /// At least one coordinate of the particle position is infinite
+    #[error("the particle position contains an infinite coordinate")]
+    InfinitePosition,
+}
+
+/// Error type returned when the particle attributes passed to the attribute computation are invalid
+#[derive(Clone, Eq, PartialEq, Debug, ThisError)]
+pub enum AttributeError {
+    /// The attribute has a type that cannot be interpolated using SPH interpolation
+    #[error("SPH interpolation of integer attributes is not supported, use nearest-particle transfer instead (attribute \"{0}\")")]
+    UnsupportedType(String),
+    /// The number of values of the attribute does not match the number of particles
+    #[error("attribute \"{name}\" has {len} values but there are {expected} particles")]
+    LengthMismatch {
+        /// Name of the attribute
+        name: String,
+        /// Number of values of the attribute
+        len: usize,
+        /// Number of particles
+        expected: usize,
+    },
+    /// The number of interpolation methods does not match the number of particle attributes
+    #[error("{len} interpolation methods were given for {expected} particle attributes")]
+    InterpolationMismatch {
+        /// Number of interpolation methods
+        len: usize,
+        /// Number of particle attributes
+        expected: usize,
+    },
+    /// An attribute exists only in the mesh or only in the appended reconstruction or has different types in both
+    #[error("attribute \"{0}\" of the mesh does not match the attributes of the appended reconstruction")]
+    AppendMismatch(String),
+}
+
 /// Error type returned when the surface reconstruction fails
 #[non_exhaustive]
 #[derive(Debug, ThisError)]
 pub enum ReconstructionError<I: Index, R: Real> {
+    /// The parameters passed to the reconstruction are invalid
+    #[error("invalid parameters")]
+    InvalidParameters(
+        #[source]
+        #[from]
+        ParameterError,
+    ),
+    /// The particle with the given index has an invalid position
+    #[error("invalid data of particle {index}")]
+    InvalidParticleData {
+        /// Index of the first particle with invalid data
+        index: usize,
+        /// Reason why the data of the particle is invalid
+        #[source]
+        reason: InvalidParticleReason,
+    },
     /// Error that occurred during the initialization of the implicit background grid used for all subsequent stages
     #[error("grid construction")]
     GridConstructionError(
@@ -295,32 +1486,188 @@ pub enum ReconstructionError<I: Index, R: Real> {
     ),
     /// Error that occurred during the construction of the density map
     #[error("density map generation")]
-    DensityMapGenerationError(
+    DensityMapError(
         #[source]
         #[from]
         DensityMapError<R>,
     ),
-    /// Error that occurred during the marching cubes stage of the reconstruction
-    #[error("marching cubes")]
-    MarchingCubesError(
+    /// Error that occurred during the marching cubes triangulation or stitching of surface patches
+    #[error("triangulation")]
+    TriangulationError(
         #[source]
         #[from]
         MarchingCubesError,
     ),
-    /// Any error that is not represented by some other explicit variant
-    #[error(transparent)]
-    Unknown(#[from] anyhow::Error),
+    /// Error that occurred during the construction or processing of the octree used for spatial decomposition
+    #[error("octree based reconstruction")]
+    OctreeError(
+        #[source]
+        #[from]
+        OctreeError<I, R>,
+    ),
+    /// Error that occurred while computing the attributes of the reconstructed mesh
+    #[error("surface attributes")]
+    AttributeError(
+        #[source]
+        #[from]
+        AttributeError,
+    ),
+    /// Particles left the fixed background grid of a sequence of reconstructions, see [`SurfaceReconstruction::set_fixed_grid`]
+    #[error("{count} of {total} particles are outside of the fixed background grid")]
+    ParticlesOutsideGrid {
+        /// Number of particles outside of the grid or too close to its boundary
+        count: usize,
+        /// Total number of particles
+        total: usize,
+    },
+    /// The reconstruction was cancelled using a [`CancellationToken`]
+    #[error("the reconstruction was cancelled")]
+    Cancelled,
 }
 
-/// Initializes the global thread pool used by this library with the given parameters.
+impl<I: Index, R: Real> ReconstructionError<I, R> {
+    /// Converts the error to another index type, index values that cannot be represented by the target type are replaced by its maximum value
+    pub(crate) fn convert_index<J: Index>(self) -> ReconstructionError<J, R> {
+        match self {
+            Self::InvalidParameters(err) => ReconstructionError::InvalidParameters(err),
+            Self::InvalidParticleData { index, reason } => {
+                ReconstructionError::InvalidParticleData { index, reason }
+            }
+            Self::GridConstructionError(err) => {
+                ReconstructionError::GridConstructionError(err.convert_index())
+            }
+            Self::DensityMapError(err) => ReconstructionError::DensityMapError(err),
+            Self::TriangulationError(err) => ReconstructionError::TriangulationError(err),
+            Self::OctreeError(err) => ReconstructionError::OctreeError(err.convert_index()),
+            Self::AttributeError(err) => ReconstructionError::AttributeError(err),
+            Self::ParticlesOutsideGrid { count, total } => {
+                ReconstructionError::ParticlesOutsideGrid { count, total }
+            }
+            Self::Cancelled => ReconstructionError::Cancelled,
+        }
+    }
+}
+
+/// Callback invoked with the index of a worker thread of the thread pool, see [`ThreadPoolConfig`]
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub type ThreadCallback = Arc<dyn Fn(usize) + Send + Sync>;
+
+/// Configuration of the thread pool used for the parallel parts of the surface reconstruction, see [`initialize_thread_pool_with`]
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+#[derive(Clone, Default)]
+pub struct ThreadPoolConfig {
+    /// Number of worker threads, if zero the number of threads is chosen automatically (see `rayon::ThreadPoolBuilder::num_threads`)
+    pub num_threads: usize,
+    /// Prefix of the names of the worker threads, the threads are named `"{prefix}-{index}"`
+    pub thread_name_prefix: Option<String>,
+    /// Stack size of the worker threads in bytes, if not provided the default stack size of the standard library is used
+    pub stack_size: Option<usize>,
+    /// Callback invoked on every worker thread when it is started, e.g. to pin the thread to specific cores
+    pub start_handler: Option<ThreadCallback>,
+    /// Callback invoked on every worker thread just before it terminates
+    pub exit_handler: Option<ThreadCallback>,
+}
+
+#[cfg(feature = "parallel")]
+impl ThreadPoolConfig {
+    /// Returns a configuration with the given number of threads and otherwise default settings
+    pub fn with_num_threads(num_threads: usize) -> Self {
+        Self {
+            num_threads,
+            ..Self::default()
+        }
+    }
+
+    /// Builds a local thread pool with this configuration that is independent of the global thread pool
+    ///
+    /// Parallel reconstructions can be run on the returned pool using `ThreadPool::install`.
+    pub fn build_local(&self) -> Result<rayon::ThreadPool, ThreadPoolError> {
+        self.builder().build().map_err(ThreadPoolError::BuildError)
+    }
+
+    /// Returns a `rayon` thread pool builder with all options of this configuration applied
+    fn builder(&self) -> rayon::ThreadPoolBuilder {
+        let mut builder = rayon::ThreadPoolBuilder::new().num_threads(self.num_threads);
+        if let Some(prefix) = self.thread_name_prefix.clone() {
+            builder = builder.thread_name(move |index| format!("{}-{}", prefix, index));
+        }
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        if let Some(start_handler) = self.start_handler.clone() {
+            builder = builder.start_handler(move |index| start_handler(index));
+        }
+        if let Some(exit_handler) = self.exit_handler.clone() {
+            builder = builder.exit_handler(move |index| exit_handler(index));
+        }
+        builder
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl fmt::Debug for ThreadPoolConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadPoolConfig")
+            .field("num_threads", &self.num_threads)
+            .field("thread_name_prefix", &self.thread_name_prefix)
+            .field("stack_size", &self.stack_size)
+            .field("start_handler", &self.start_handler.is_some())
+            .field("exit_handler", &self.exit_handler.is_some())
+            .finish()
+    }
+}
+
+/// Error type returned when a thread pool cannot be initialized
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+#[derive(Debug, ThisError)]
+pub enum ThreadPoolError {
+    /// The global thread pool was already initialized by a previous call of [`initialize_thread_pool`] or [`initialize_thread_pool_with`]
+    #[error("the global thread pool was already initialized by this library")]
+    AlreadyInitialized,
+    /// The global thread pool could not be initialized, most likely because it was already initialized outside of this library (e.g. by another crate using `rayon`)
+    #[error("failed to initialize the global thread pool, it might have already been initialized outside of this library")]
+    GlobalPoolUnavailable(#[source] rayon::ThreadPoolBuildError),
+    /// A local thread pool could not be built
+    #[error("failed to build thread pool")]
+    BuildError(#[source] rayon::ThreadPoolBuildError),
+}
+
+/// Whether the global thread pool was successfully initialized by this library
+#[cfg(feature = "parallel")]
+static GLOBAL_THREAD_POOL_INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Initializes the global thread pool used by this library with the given number of threads, see [`initialize_thread_pool_with`]
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub fn initialize_thread_pool(num_threads: usize) -> Result<(), ThreadPoolError> {
+    initialize_thread_pool_with(&ThreadPoolConfig::with_num_threads(num_threads))
+}
+
+/// Initializes the global thread pool used by this library with the given configuration.
 ///
 /// Initialization of the global thread pool happens exactly once.
-/// Therefore, if you call `initialize_thread_pool` a second time, it will return an error.
+/// Therefore, if you call `initialize_thread_pool_with` a second time, it will return [`ThreadPoolError::AlreadyInitialized`].
+/// If the global pool was already initialized in some other way, [`ThreadPoolError::GlobalPoolUnavailable`] is returned.
 /// An `Ok` result indicates that this is the first initialization of the thread pool.
-pub fn initialize_thread_pool(num_threads: usize) -> Result<(), anyhow::Error> {
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(num_threads)
-        .build_global()?;
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub fn initialize_thread_pool_with(config: &ThreadPoolConfig) -> Result<(), ThreadPoolError> {
+    if GLOBAL_THREAD_POOL_INITIALIZED.load(Ordering::SeqCst) {
+        return Err(ThreadPoolError::AlreadyInitialized);
+    }
+
+    config.builder().build_global().map_err(|err| {
+        if GLOBAL_THREAD_POOL_INITIALIZED.load(Ordering::SeqCst) {
+            ThreadPoolError::AlreadyInitialized
+        } else {
+            ThreadPoolError::GlobalPoolUnavailable(err)
+        }
+    })?;
+
+    GLOBAL_THREAD_POOL_INITIALIZED.store(true, Ordering::SeqCst);
     Ok(())
 }
 
@@ -341,20 +1688,137 @@ pub fn reconstruct_surface_inplace<'a, I: Index, R: Real>(
     parameters: &Parameters<R>,
     output_surface: &'a mut SurfaceReconstruction<I, R>,
 ) -> Result<(), ReconstructionError<I, R>> {
-    // Clear the existing mesh
-    output_surface.mesh.clear();
+    #[cfg(not(feature = "parallel"))]
+    if parameters.enable_multi_threading {
+        warn!("Multi-threading was requested but splashsurf_lib was compiled without the \"parallel\" feature, falling back to sequential reconstruction.");
+    }
 
-    // Initialize grid for the reconstruction
-    output_surface.grid = grid_for_reconstruction(
-        particle_positions,
-        parameters.particle_radius,
-        parameters.compact_support_radius,
-        parameters.cube_size,
-        parameters.domain_aabb.as_ref(),
-        parameters.enable_multi_threading,
-    )?;
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!(
+        "reconstruct_surface",
+        num_particles = particle_positions.len(),
+        deterministic = parameters.deterministic
+    )
+    .entered();
+
+    output_surface.stats = ReconstructionStats::default();
+    let timings_collector = output_surface.timings_collector.clone();
+    let result = {
+        let _timings_guard = timings_collector.as_ref().map(|collector| {
+            collector.reset();
+            collector.attach()
+        });
+        let _progress_guard = progress::enable(parameters.progress_interval);
+        reconstruct_surface_inplace_stages(particle_positions, parameters, output_surface)
+    };
+    output_surface.stats.timings = timings_collector.map(|collector| collector.timings());
+
+    if output_surface.consolidate_workspace {
+        output_surface.workspace.consolidate();
+    }
+
+    result
+}
+
+/// Clears the mesh for a new reconstruction, a mesh that is still shared by a snapshot is replaced instead
+pub(crate) fn clear_shared_mesh<R: Real>(mesh: &mut Arc<TriMesh3d<R>>) {
+    match Arc::get_mut(mesh) {
+        Some(mesh) => mesh.clear(),
+        None => *mesh = Arc::default(),
+    }
+}
+
+/// Performs all stages of the surface reconstruction, see [`reconstruct_surface_inplace`]
+fn reconstruct_surface_inplace_stages<I: Index, R: Real>(
+    particle_positions: &[Vector3<R>],
+    parameters: &Parameters<R>,
+    output_surface: &mut SurfaceReconstruction<I, R>,
+) -> Result<(), ReconstructionError<I, R>> {
+    parameters.validate()?;
+    reconstruction::check_particle_positions(particle_positions)?;
+    output_surface.cancellation_token.check()?;
+
+    // Clear the existing mesh unless the reconstruction is appended to it
+    if !output_surface.append_to_mesh {
+        clear_shared_mesh(&mut output_surface.mesh);
+    }
+    let vertex_offset = output_surface.mesh.vertices.len();
+    let triangle_offset = output_surface.mesh.triangles.len();
+
+    // Triangles of a mesh that the reconstruction is appended to have no recorded origin
+    output_surface.triangle_origins = output_surface.record_triangle_origins.then(|| {
+        let mut origins = output_surface.triangle_origins.take().unwrap_or_default();
+        origins.resize(triangle_offset);
+        origins
+    });
+
+    // Initialize grid for the reconstruction, a fixed grid is reused as long as it was computed with the same parameters
+    let grid_key = FixedGridKey::new(parameters);
+    let fixed_grid = output_surface.grid_escape_policy.is_some();
+    output_surface.stats.grid_reused =
+        fixed_grid && output_surface.fixed_grid_key.as_ref() == Some(&grid_key);
+    if !output_surface.stats.grid_reused {
+        output_surface.grid = Arc::new(grid_for_reconstruction(
+            particle_positions,
+            parameters.particle_radius,
+            parameters.compact_support_radius,
+            parameters.cube_size,
+            parameters.domain_aabb.as_ref(),
+            parameters.enable_multi_threading,
+        )?);
+        output_surface.fixed_grid_key = fixed_grid.then_some(grid_key);
+    }
 
     output_surface.grid.log_grid_info();
+    output_surface.cancellation_token.check()?;
+
+    // Particles outside of a user provided domain or a reused grid do not contribute to the density map
+    if parameters.domain_aabb.is_some() || output_surface.stats.grid_reused {
+        let mut particles_outside_domain = density_map::count_particles_outside_allowed_domain(
+            &output_surface.grid,
+            particle_positions,
+            parameters.compact_support_radius,
+            parameters.cube_size,
+            parameters.enable_multi_threading,
+        );
+        if particles_outside_domain > 0 {
+            match output_surface.grid_escape_policy {
+                Some(GridEscapePolicy::Error) => {
+                    return Err(ReconstructionError::ParticlesOutsideGrid {
+                        count: particles_outside_domain,
+                        total: particle_positions.len(),
+                    });
+                }
+                Some(GridEscapePolicy::Regrid) => {
+                    info!(
+                        "{} of {} particles are outside of the fixed grid, computing a new grid from the particles.",
+                        particles_outside_domain,
+                        particle_positions.len()
+                    );
+                    output_surface.grid = Arc::new(grid_for_reconstruction(
+                        particle_positions,
+                        parameters.particle_radius,
+                        parameters.compact_support_radius,
+                        parameters.cube_size,
+                        None,
+                        parameters.enable_multi_threading,
+                    )?);
+                    output_surface.grid.log_grid_info();
+                    output_surface.stats.grid_reused = false;
+                    particles_outside_domain = 0;
+                }
+                None => {}
+            }
+        }
+        if particles_outside_domain > 0 {
+            warn!(
+                "{} of {} particles are outside of the domain and are skipped by the reconstruction.",
+                particles_outside_domain,
+                particle_positions.len()
+            );
+        }
+        output_surface.stats.warnings.particles_outside_domain = particles_outside_domain;
+    }
 
     if parameters.spatial_decomposition.is_some() {
         reconstruction::reconstruct_surface_domain_decomposition(
@@ -366,10 +1830,214 @@ pub fn reconstruct_surface_inplace<'a, I: Index, R: Real>(
         reconstruction::reconstruct_surface_global(particle_positions, parameters, output_surface)?;
     }
 
+    output_surface.cancellation_token.check()?;
+    reconstruction::generate_splash_spheres(particle_positions, parameters, output_surface);
+    if let Some(origins) = output_surface.triangle_origins.as_mut() {
+        origins.resize(output_surface.mesh.triangles.len());
+    }
+    output_surface.workspace.record_mesh_sizes(
+        output_surface.mesh.vertices.len() - vertex_offset,
+        output_surface.mesh.triangles.len() - triangle_offset,
+    );
+
+    output_surface.stats.warnings.degenerate_triangles = output_surface
+        .mesh
+        .count_degenerate_triangles_from(triangle_offset);
+    if !output_surface.stats.warnings.is_empty() {
+        warn!(
+            "Reconstruction finished with warnings: {}",
+            output_surface.stats.warnings
+        );
+    }
+
     Ok(())
 }
 
+/// Triangulates the density map of a snapshot of a previous global reconstruction, skipping the computation of the density map
+///
+/// Only the triangulation and the subsequent stages (e.g. the output of splash particles) are performed using the
+/// given parameters. To obtain the same surface as a full reconstruction, the snapshot has to be computed from the
+/// same particles with the same parameters, except for the iso-surface threshold if no clipping planes are used
+/// (the clipping of the density map depends on the threshold). Panics if the snapshot does not contain one density
+/// per particle.
+pub fn reconstruct_surface_from_density_map<I: Index, R: Real>(
+    particle_positions: &[Vector3<R>],
+    parameters: &Parameters<R>,
+    snapshot: DensityMapSnapshot<I, R>,
+    output_surface: &mut SurfaceReconstruction<I, R>,
+) -> Result<(), ReconstructionError<I, R>> {
+    assert_eq!(
+        snapshot.particle_densities.len(),
+        particle_positions.len(),
+        "There has to be one density value per particle"
+    );
+
+    output_surface.stats = ReconstructionStats::default();
+    let timings_collector = output_surface.timings_collector.clone();
+    let result = {
+        let _timings_guard = timings_collector.as_ref().map(|collector| {
+            collector.reset();
+            collector.attach()
+        });
+        reconstruct_surface_from_density_map_stages(
+            particle_positions,
+            parameters,
+            snapshot,
+            output_surface,
+        )
+    };
+    output_surface.stats.timings = timings_collector.map(|collector| collector.timings());
+
+    result
+}
+
+/// Performs all stages of the triangulation of a snapshot, see [`reconstruct_surface_from_density_map`]
+fn reconstruct_surface_from_density_map_stages<I: Index, R: Real>(
+    particle_positions: &[Vector3<R>],
+    parameters: &Parameters<R>,
+    snapshot: DensityMapSnapshot<I, R>,
+    output_surface: &mut SurfaceReconstruction<I, R>,
+) -> Result<(), ReconstructionError<I, R>> {
+    profile!("reconstruct_surface_from_density_map");
+    parameters.validate()?;
+    output_surface.cancellation_token.check()?;
+
+    let DensityMapSnapshot {
+        grid,
+        density_map,
+        particle_densities,
+        splash_particle_indices,
+    } = snapshot;
+
+    clear_shared_mesh(&mut output_surface.mesh);
+    output_surface.triangle_origins = output_surface
+        .record_triangle_origins
+        .then(TriangleOrigins::default);
+    reconstruction::triangulate_global_density_map(
+        &grid,
+        &density_map,
+        parameters,
+        Arc::make_mut(&mut output_surface.mesh),
+        output_surface
+            .triangle_origins
+            .as_mut()
+            .map(|origins| &mut origins.cells),
+    )?;
+
+    output_surface.grid = Arc::new(grid);
+    output_surface.fixed_grid_key = None;
+    output_surface.octree = None;
+    output_surface.density_map = Some(density_map);
+    output_surface.particle_densities = Some(particle_densities);
+    output_surface.splash_particle_indices = splash_particle_indices;
+
+    output_surface.cancellation_token.check()?;
+    reconstruction::generate_splash_spheres(particle_positions, parameters, output_surface);
+    if let Some(origins) = output_surface.triangle_origins.as_mut() {
+        origins.resize(output_surface.mesh.triangles.len());
+    }
+
+    output_surface.stats.warnings.degenerate_triangles =
+        output_surface.mesh.count_degenerate_triangles();
+
+    Ok(())
+}
+
+/// Performs a marching cubes surface construction and attaches the requested attributes (e.g. normals or interpolated particle quantities) to the resulting mesh
+///
+/// This is a convenience function combining [`reconstruct_surface`] and [`SurfaceReconstruction::mesh_with_attributes`].
+pub fn reconstruct_surface_with_attributes<I: Index, R: Real>(
+    particle_positions: &[Vector3<R>],
+    parameters: &Parameters<R>,
+    attribute_specs: &AttributeSpecs<R>,
+) -> Result<MeshWithData<R, TriMesh3d<R>>, ReconstructionError<I, R>> {
+    let surface = reconstruct_surface::<I, R>(particle_positions, parameters)?;
+    surface.mesh_with_attributes(particle_positions, parameters, attribute_specs)
+}
+
+/// Ranges of the vertices and triangles that were appended to a mesh by [`reconstruct_surface_append`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppendedMeshRange {
+    /// Indices of the appended vertices
+    pub vertices: ops::Range<usize>,
+    /// Indices of the appended triangles
+    pub triangles: ops::Range<usize>,
+}
+
+/// Performs a marching cubes surface construction of the given particles and appends the surface to the given mesh
+///
+/// In contrast to [`reconstruct_surface_inplace`], the mesh is not cleared: the vertices and triangles of the
+/// reconstruction are appended to the existing ones (with vertex indices of the triangles offset accordingly)
+/// without intermediate buffers. This allows to accumulate the surfaces of several independent particle sets
+/// (e.g. of multiple emitters) in a single mesh. Spheres of splash particles are only appended with
+/// [`SplashOutput::MergedSpheres`]. Returns the ranges of the appended vertices and triangles, in case of an error
+/// the mesh is left unchanged.
+///
+/// To reconstruct all particle sets on the same background grid (e.g. to obtain the same vertices as a
+/// reconstruction of all particles at once), a common [`Parameters::domain_aabb`] has to be specified.
+pub fn reconstruct_surface_append<I: Index, R: Real>(
+    particle_positions: &[Vector3<R>],
+    parameters: &Parameters<R>,
+    mesh: &mut TriMesh3d<R>,
+) -> Result<AppendedMeshRange, ReconstructionError<I, R>> {
+    let vertex_offset = mesh.vertices.len();
+    let triangle_offset = mesh.triangles.len();
+
+    let mut surface = SurfaceReconstruction::<I, R> {
+        mesh: Arc::new(std::mem::take(mesh)),
+        append_to_mesh: true,
+        ..Default::default()
+    };
+    let result = reconstruct_surface_inplace(particle_positions, parameters, &mut surface);
+    *mesh = surface.take_mesh();
+
+    match result {
+        Ok(()) => Ok(AppendedMeshRange {
+            vertices: vertex_offset..mesh.vertices.len(),
+            triangles: triangle_offset..mesh.triangles.len(),
+        }),
+        Err(err) => {
+            // Remove a partially appended reconstruction
+            mesh.vertices.truncate(vertex_offset);
+            mesh.triangles.truncate(triangle_offset);
+            Err(err)
+        }
+    }
+}
+
+/// Performs a marching cubes surface construction with the requested attributes and appends the surface to the given mesh
+///
+/// This is the attribute-aware variant of [`reconstruct_surface_append`]. The point and cell attributes of the
+/// reconstruction are appended to the attributes of the mesh with the same names, such that all attributes
+/// stay aligned with the vertices and triangles. If the mesh is empty and has no attributes, the attributes of
+/// the reconstruction are adopted. Otherwise, the mesh has to have the same set of attributes (with the same
+/// types) as requested by the attribute specification, else an [`AttributeError::AppendMismatch`] is returned
+/// and the mesh is left unchanged.
+pub fn reconstruct_surface_append_with_attributes<I: Index, R: Real>(
+    particle_positions: &[Vector3<R>],
+    parameters: &Parameters<R>,
+    attribute_specs: &AttributeSpecs<R>,
+    mesh: &mut MeshWithData<R, TriMesh3d<R>>,
+) -> Result<AppendedMeshRange, ReconstructionError<I, R>> {
+    let appended = reconstruct_surface_with_attributes::<I, R>(
+        particle_positions,
+        parameters,
+        attribute_specs,
+    )?;
+
+    let vertex_offset = mesh.mesh.vertices.len();
+    let triangle_offset = mesh.mesh.triangles.len();
+    mesh.append(appended)
+        .map_err(|err| AttributeError::AppendMismatch(err.attribute_name().to_string()))?;
+
+    Ok(AppendedMeshRange {
+        vertices: vertex_offset..mesh.mesh.vertices.len(),
+        triangles: triangle_offset..mesh.mesh.triangles.len(),
+    })
+}
+
 /// Constructs the background grid for marching cubes based on the parameters supplied to the surface reconstruction
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
 pub fn grid_for_reconstruction<I: Index, R: Real>(
     particle_positions: &[Vector3<R>],
     particle_radius: R,
@@ -384,11 +2052,14 @@ pub fn grid_for_reconstruction<I: Index, R: Real>(
         profile!("compute minimum enclosing aabb");
 
         let mut domain_aabb = {
+            #[cfg(feature = "parallel")]
             let mut aabb = if enable_multi_threading {
                 AxisAlignedBoundingBox3d::par_from_points(particle_positions)
             } else {
                 AxisAlignedBoundingBox3d::from_points(particle_positions)
             };
+            #[cfg(not(feature = "parallel"))]
+            let mut aabb = AxisAlignedBoundingBox3d::from_points(particle_positions);
             aabb.grow_uniformly(particle_radius);
             aabb
         };
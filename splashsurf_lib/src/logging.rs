@@ -0,0 +1,6 @@
+//! Logging macros used by this crate, emitting `tracing` events instead of `log` records if the `tracing` feature is enabled
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) use log::{debug, info, trace, warn};
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::{debug, info, trace, warn};
@@ -3,20 +3,28 @@
 use crate::marching_cubes::narrow_band_extraction::{
     construct_mc_input, construct_mc_input_with_stitching_data,
 };
+use crate::marching_cubes::surface_nets::triangulate_dual;
 use crate::marching_cubes::triangulation::{
-    triangulate, triangulate_with_criterion, DebugTriangleGenerator, TriangulationSkipBoundaryCells,
+    triangulate, triangulate_with_criterion, CornerValues, DebugTriangleGenerator,
+    TriangulationSkipBoundaryCells,
 };
 use crate::mesh::TriMesh3d;
-use crate::uniform_grid::{DummySubdomain, OwningSubdomainGrid, Subdomain};
-use crate::{new_map, profile, DensityMap, Index, MapType, Real, UniformGrid};
+use crate::uniform_grid::{DummySubdomain, OwningSubdomainGrid, PointIndex, Subdomain};
+use crate::{new_map, profile, DensityMap, Index, MapType, Real, TriangleOrigins, UniformGrid};
 use nalgebra::Vector3;
 use thiserror::Error as ThisError;
 
+mod disambiguation;
 pub mod marching_cubes_lut;
 mod narrow_band_extraction;
+#[cfg(feature = "parallel")]
+mod slabs;
+mod snapping;
 mod stitching;
+mod surface_nets;
 mod triangulation;
 
+pub(crate) use snapping::{retain_appended, snap_vertices_to_grid};
 pub(crate) use stitching::{stitch_surface_patches, SurfacePatch};
 pub use triangulation::TriangulationError;
 
@@ -32,6 +40,32 @@ pub enum MarchingCubesError {
     ),
 }
 
+/// Variant of the marching cubes triangulation, see [`Parameters::triangulation_scheme`](crate::Parameters::triangulation_scheme)
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MarchingCubesVariant {
+    /// The classic lookup table with 256 cases that only depends on the above/below threshold flags of the cell corners
+    ///
+    /// Ambiguous configurations (e.g. two diagonally opposite corners of a face above the threshold) are always
+    /// resolved in the same way independent of the density values. The resulting mesh is closed, but its topology
+    /// may not match the density field, e.g. thin sheets of fluid can get tiny holes or thin filaments can break up.
+    #[default]
+    Classic,
+    /// Topologically correct triangulation of ambiguous cells following the idea of Chernyaev's "Marching Cubes 33"
+    ///
+    /// Ambiguous faces and the interior of cells are resolved using the density values at the cell corners (asymptotic
+    /// decider and interior test), such that the topology of the mesh matches the trilinear interpolation of the density
+    /// values. Neighboring cells always agree on the contour of their common face, so the resulting mesh stays closed.
+    /// Only cells with an ambiguous configuration are affected, all other cells are triangulated with the classic table.
+    /// Some ambiguous cells require an additional vertex inside of the cell.
+    Mc33,
+    /// Dual triangulation ("surface nets") that places a single vertex per cell and connects the vertices of neighboring cells
+    ///
+    /// The vertex of a cell is placed at the mean of the interpolated iso-surface crossings on its edges, and every edge
+    /// crossing the iso-surface is triangulated by a quad between the four cells around it. This results in considerably
+    /// more regular triangles than marching cubes. Not supported in combination with subdomain stitching.
+    SurfaceNets,
+}
+
 /// Input data required by the marching cubes triangulation
 #[derive(Clone, Debug)]
 pub(crate) struct MarchingCubesInput<I: Index> {
@@ -117,11 +151,19 @@ pub fn triangulate_density_map<I: Index, R: Real>(
     grid: &UniformGrid<I, R>,
     density_map: &DensityMap<I, R>,
     iso_surface_threshold: R,
+    variant: MarchingCubesVariant,
 ) -> Result<TriMesh3d<R>, MarchingCubesError> {
     profile!("triangulate_density_map");
 
     let mut mesh = TriMesh3d::default();
-    triangulate_density_map_append(grid, None, density_map, iso_surface_threshold, &mut mesh)?;
+    triangulate_density_map_append(
+        grid,
+        None,
+        density_map,
+        iso_surface_threshold,
+        variant,
+        &mut mesh,
+    )?;
     Ok(mesh)
 }
 
@@ -131,10 +173,69 @@ pub fn triangulate_density_map_append<I: Index, R: Real>(
     subdomain: Option<&OwningSubdomainGrid<I, R>>,
     density_map: &DensityMap<I, R>,
     iso_surface_threshold: R,
+    variant: MarchingCubesVariant,
+    mesh: &mut TriMesh3d<R>,
+) -> Result<(), MarchingCubesError> {
+    triangulate_density_map_append_with_cells(
+        grid,
+        subdomain,
+        density_map,
+        iso_surface_threshold,
+        None,
+        variant,
+        mesh,
+        None,
+    )
+}
+
+/// Performs a marching cubes triangulation of a density map, appends triangles to the given mesh and optionally records the flat index of the originating cell of every new triangle
+///
+/// If `vertex_snapping` is provided, see [`Parameters::vertex_snapping`](crate::Parameters::vertex_snapping), the new
+/// vertices close to grid points are snapped onto them and the triangles that collapse are removed (ignored for the
+/// [`MarchingCubesVariant::SurfaceNets`] triangulation).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn triangulate_density_map_append_with_cells<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    subdomain: Option<&OwningSubdomainGrid<I, R>>,
+    density_map: &DensityMap<I, R>,
+    iso_surface_threshold: R,
+    vertex_snapping: Option<R>,
+    variant: MarchingCubesVariant,
     mesh: &mut TriMesh3d<R>,
+    mut triangle_cells: Option<&mut Vec<I>>,
 ) -> Result<(), MarchingCubesError> {
     profile!("triangulate_density_map_append");
 
+    if variant == MarchingCubesVariant::SurfaceNets {
+        let mut edge_vertices = Vec::new();
+        let marching_cubes_data = if let Some(subdomain) = subdomain {
+            construct_mc_input(
+                subdomain,
+                density_map,
+                iso_surface_threshold,
+                &mut edge_vertices,
+            )
+        } else {
+            let subdomain = DummySubdomain::new(grid);
+            construct_mc_input(
+                &subdomain,
+                density_map,
+                iso_surface_threshold,
+                &mut edge_vertices,
+            )
+        };
+
+        triangulate_dual(
+            grid,
+            marching_cubes_data,
+            &edge_vertices,
+            mesh,
+            triangle_cells,
+        );
+        return Ok(());
+    }
+
+    let (vertex_offset, triangle_offset) = (mesh.vertices.len(), mesh.triangles.len());
     let marching_cubes_data = if let Some(subdomain) = subdomain {
         construct_mc_input(
             subdomain,
@@ -152,21 +253,206 @@ pub fn triangulate_density_map_append<I: Index, R: Real>(
         )
     };
 
-    triangulate(marching_cubes_data, mesh)?;
+    let corner_values =
+        CornerValues::for_variant(variant, grid, density_map, iso_surface_threshold);
+    triangulate(
+        marching_cubes_data,
+        corner_values,
+        mesh,
+        triangle_cells.as_deref_mut(),
+    )?;
+
+    if let Some(snapping) = vertex_snapping {
+        let kept_triangles =
+            snap_vertices_to_grid(grid, snapping, mesh, vertex_offset, triangle_offset);
+        if let Some(triangle_cells) = triangle_cells {
+            retain_appended(triangle_cells, &kept_triangles);
+        }
+    }
+    Ok(())
+}
+
+/// Performs a multi-threaded marching cubes triangulation of a density map on the given background grid
+///
+/// The iso-surface vertices are interpolated in parallel for chunks of the density map, afterwards the cells are
+/// partitioned into slabs along the first axis of the grid which are triangulated in parallel. The resulting mesh
+/// is identical to the mesh of [`triangulate_density_map`] up to the order of the vertices and triangles. The
+/// [`MarchingCubesVariant::SurfaceNets`] triangulation is always performed sequentially.
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub fn par_triangulate_density_map<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    density_map: &DensityMap<I, R>,
+    iso_surface_threshold: R,
+    variant: MarchingCubesVariant,
+) -> Result<TriMesh3d<R>, MarchingCubesError> {
+    profile!("par_triangulate_density_map");
+
+    let mut mesh = TriMesh3d::default();
+    par_triangulate_density_map_append_with_cells(
+        grid,
+        density_map,
+        iso_surface_threshold,
+        None,
+        variant,
+        &mut mesh,
+        None,
+    )?;
+    Ok(mesh)
+}
+
+/// Multi-threaded version of [`triangulate_density_map_append_with_cells`] for the whole background grid
+#[cfg(feature = "parallel")]
+pub(crate) fn par_triangulate_density_map_append_with_cells<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    density_map: &DensityMap<I, R>,
+    iso_surface_threshold: R,
+    vertex_snapping: Option<R>,
+    variant: MarchingCubesVariant,
+    mesh: &mut TriMesh3d<R>,
+    triangle_cells: Option<&mut Vec<I>>,
+) -> Result<(), MarchingCubesError> {
+    if variant == MarchingCubesVariant::SurfaceNets {
+        return triangulate_density_map_append_with_cells(
+            grid,
+            None,
+            density_map,
+            iso_surface_threshold,
+            vertex_snapping,
+            variant,
+            mesh,
+            triangle_cells,
+        );
+    }
+
+    let (vertex_offset, triangle_offset) = (mesh.vertices.len(), mesh.triangles.len());
+    let mut triangle_cells = triangle_cells;
+    slabs::par_triangulate_slabs(
+        grid,
+        density_map,
+        iso_surface_threshold,
+        variant,
+        mesh,
+        triangle_cells.as_deref_mut(),
+    )?;
+
+    if let Some(snapping) = vertex_snapping {
+        let kept_triangles =
+            snap_vertices_to_grid(grid, snapping, mesh, vertex_offset, triangle_offset);
+        if let Some(triangle_cells) = triangle_cells {
+            retain_appended(triangle_cells, &kept_triangles);
+        }
+    }
     Ok(())
 }
 
+/// Performs a marching cubes triangulation of a scalar field given at the points of the background grid, appends triangles to the given mesh
+///
+/// This allows to use the marching cubes triangulation independently of the particle based reconstruction, e.g. for
+/// level-set values. The field is evaluated once for every point of the grid. Like for density maps, values above the
+/// threshold are inside of the surface and the normals of the resulting triangles point outwards.
+///
+/// Points where the field returns `None` are considered to be outside of the surface, which allows to triangulate sparse
+/// fields that only provide values close to or inside of the surface. On an edge between a point inside of the surface
+/// and a point without value, the iso-surface is placed at most halfway along the edge.
+///
+/// ```
+/// use splashsurf_lib::marching_cubes::{triangulate_scalar_field, MarchingCubesVariant};
+/// use splashsurf_lib::mesh::TriMesh3d;
+/// use splashsurf_lib::nalgebra::Vector3;
+/// use splashsurf_lib::UniformGrid;
+///
+/// let grid = UniformGrid::<i32, f64>::new(&Vector3::zeros(), &[10, 10, 10], 0.1).unwrap();
+/// let center = Vector3::new(0.5, 0.5, 0.5);
+/// // Signed distance to a sphere, only available inside of the sphere
+/// let sphere = |point: &_| {
+///     let distance = 0.3 - (grid.point_coordinates(point) - center).norm();
+///     (distance > 0.0).then_some(distance)
+/// };
+///
+/// let mut mesh = TriMesh3d::default();
+/// triangulate_scalar_field(&grid, sphere, 0.0, MarchingCubesVariant::Classic, &mut mesh).unwrap();
+/// assert!(mesh.find_boundary_edges().is_empty());
+/// ```
+pub fn triangulate_scalar_field<I: Index, R: Real, F: Fn(&PointIndex<I>) -> Option<R>>(
+    grid: &UniformGrid<I, R>,
+    values: F,
+    iso_surface_threshold: R,
+    variant: MarchingCubesVariant,
+    mesh: &mut TriMesh3d<R>,
+) -> Result<(), MarchingCubesError> {
+    profile!("triangulate_scalar_field");
+
+    let mut point_values: MapType<I, R> = new_map();
+    let [ni, nj, nk] = grid.points_per_dim().map(|n| n.to_usize().unwrap());
+    for i in 0..ni {
+        for j in 0..nj {
+            for k in 0..nk {
+                let ijk = [i, j, k].map(|n| I::from_usize(n).unwrap());
+                let point = grid.get_point(ijk).unwrap();
+                if let Some(value) = values(&point) {
+                    point_values.insert(grid.flatten_point_index(&point), value);
+                }
+            }
+        }
+    }
+
+    // The narrow band extraction requires values for all points of cells that cross the iso-surface,
+    // missing points next to the inside are filled with the value mirrored at the threshold
+    let mut outside_values: MapType<I, R> = new_map();
+    for (&flat_point_index, &value) in point_values.iter() {
+        if value <= iso_surface_threshold {
+            continue;
+        }
+
+        let outside_value = iso_surface_threshold + iso_surface_threshold - value;
+        let point = grid.try_unflatten_point_index(flat_point_index).unwrap();
+        let neighborhood = grid.get_point_neighborhood(&point);
+        for cell in grid.cells_adjacent_to_point(&neighborhood).iter().flatten() {
+            for local_point_index in 0..8 {
+                let corner = cell.global_point_index_of(local_point_index).unwrap();
+                let flat_corner_index = grid.flatten_point_index(&corner);
+                if !point_values.contains_key(&flat_corner_index) {
+                    outside_values
+                        .entry(flat_corner_index)
+                        .and_modify(|corner_value| *corner_value = corner_value.min(outside_value))
+                        .or_insert(outside_value);
+                }
+            }
+        }
+    }
+    point_values.extend(outside_values);
+
+    triangulate_density_map_append(
+        grid,
+        None,
+        &point_values.into(),
+        iso_surface_threshold,
+        variant,
+        mesh,
+    )
+}
+
 /// Performs triangulation of the given density map to a surface patch
+///
+/// If the id of the octree node of the patch is provided, the origins of all triangles are recorded in the patch.
 pub(crate) fn triangulate_density_map_to_surface_patch<I: Index, R: Real>(
     subdomain: &OwningSubdomainGrid<I, R>,
     density_map: &DensityMap<I, R>,
     iso_surface_threshold: R,
+    variant: MarchingCubesVariant,
+    origins_node_id: Option<usize>,
 ) -> Result<SurfacePatch<I, R>, MarchingCubesError> {
     profile!("triangulate_density_map_append");
 
     let mut mesh = TriMesh3d::default();
     let subdomain = subdomain.clone();
 
+    assert_ne!(
+        variant,
+        MarchingCubesVariant::SurfaceNets,
+        "Surface nets triangulation does not support stitching of surface patches!"
+    );
     assert!(
         subdomain.subdomain_grid().cells_per_dim().iter().all(|&n_cells| n_cells > I::one() + I::one()),
         "Interpolation procedure with stitching support only works on grids & subdomains with more than 2 cells in each dimension!"
@@ -179,19 +465,36 @@ pub(crate) fn triangulate_density_map_to_surface_patch<I: Index, R: Real>(
         &mut mesh.vertices,
     );
 
+    let corner_values = CornerValues::for_variant(
+        variant,
+        subdomain.global_grid(),
+        density_map,
+        iso_surface_threshold,
+    );
+    let mut triangle_cells = origins_node_id.map(|_| Vec::new());
     triangulate_with_criterion(
         &subdomain,
         marching_cubes_data,
+        corner_values,
         &mut mesh,
+        triangle_cells.as_mut(),
         TriangulationSkipBoundaryCells,
         DebugTriangleGenerator,
     )?;
 
+    let origins = triangle_cells
+        .zip(origins_node_id)
+        .map(|(cells, node_id)| TriangleOrigins {
+            nodes: vec![node_id; cells.len()],
+            cells,
+        });
+
     Ok(SurfacePatch {
         mesh,
         subdomain,
         data: boundary_data,
         stitching_level: 0,
+        origins,
     })
 }
 
@@ -212,8 +515,7 @@ pub fn check_mesh_consistency<I: Index, R: Real>(
         let v0 = mesh.vertices[edge[0]];
         let v1 = mesh.vertices[edge[1]];
         let center = (v0 + v1) / (R::one() + R::one());
-        let cell = grid.enclosing_cell(&center);
-        if let Some(cell_index) = grid.get_cell(cell) {
+        if let Some(cell_index) = grid.get_enclosing_cell(&center) {
             let point_index = grid
                 .get_point(*cell_index.index())
                 .expect("Unable to get point index of cell");
@@ -251,8 +553,7 @@ fn check_mesh_with_cell_data<I: Index, R: Real>(
         let v0 = mesh.vertices[edge[0]];
         let v1 = mesh.vertices[edge[1]];
         let center = (v0 + v1) / (R::one() + R::one());
-        let cell = grid.enclosing_cell(&center);
-        if let Some(cell_index) = grid.get_cell(cell) {
+        if let Some(cell_index) = grid.get_enclosing_cell(&center) {
             let point_index = grid
                 .get_point(*cell_index.index())
                 .expect("Unable to get point index of cell");
@@ -404,6 +705,462 @@ fn test_interpolate_cell_data() {
     assert!(cell.iso_surface_vertices[11].is_some());
 
     // TODO: Continue writing test
-    let _mesh = triangulate(marching_cubes_data, &mut trimesh);
+    let _mesh = triangulate(marching_cubes_data, None, &mut trimesh, None);
     //println!("{:?}", mesh)
 }
+
+/// Triangulates a grid of 3x3x3 cells where only the points of the central cell have a non-zero density
+#[cfg(test)]
+fn triangulate_central_cell(
+    corner_values: &[f64; 8],
+    iso_surface_threshold: f64,
+    variant: MarchingCubesVariant,
+) -> TriMesh3d<f64> {
+    let grid = UniformGrid::<i32, f64>::new(&Vector3::new(0.0, 0.0, 0.0), &[3, 3, 3], 1.0).unwrap();
+    // Same order of the corners as in the marching cubes lookup table
+    let corners = [
+        [1, 1, 1],
+        [2, 1, 1],
+        [2, 2, 1],
+        [1, 2, 1],
+        [1, 1, 2],
+        [2, 1, 2],
+        [2, 2, 2],
+        [1, 2, 2],
+    ];
+
+    // The density map has to contain all points of cells crossing the iso-surface
+    let mut density_map = new_map();
+    for i in 0..4 {
+        for j in 0..4 {
+            for k in 0..4 {
+                density_map.insert(grid.flatten_point_index_array(&[i, j, k]), 0.0);
+            }
+        }
+    }
+    for (ijk, &value) in corners.iter().zip(corner_values.iter()) {
+        density_map.insert(grid.flatten_point_index_array(ijk), value);
+    }
+
+    triangulate_density_map(&grid, &density_map.into(), iso_surface_threshold, variant).unwrap()
+}
+
+/// Asserts that every edge of the mesh is shared by exactly two triangles with opposite orientation
+#[cfg(test)]
+fn assert_closed_and_consistently_oriented(mesh: &TriMesh3d<f64>) {
+    let mut directed_edges = new_map();
+    for tri in mesh.triangles.iter() {
+        for (i, j) in [(0, 1), (1, 2), (2, 0)] {
+            *directed_edges.entry((tri[i], tri[j])).or_insert(0) += 1;
+        }
+    }
+
+    for (&(v0, v1), &count) in directed_edges.iter() {
+        assert_eq!(count, 1, "directed edge {:?} used more than once", (v0, v1));
+        assert!(
+            directed_edges.contains_key(&(v1, v0)),
+            "edge {:?} has no opposite edge",
+            (v0, v1)
+        );
+    }
+}
+
+#[test]
+fn test_mc33_watertight_ambiguous_cells() {
+    let iso_surface_threshold = 0.5;
+
+    // Simple LCG for reproducible corner magnitudes
+    let mut state = 0x2545f4914f6cdd1d_u64;
+    let mut random = move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+
+    for case in 1..255_usize {
+        for _ in 0..20 {
+            let mut corner_values = [0.0; 8];
+            for (i, value) in corner_values.iter_mut().enumerate() {
+                *value = if case & (1 << i) != 0 {
+                    iso_surface_threshold + 0.01 + random()
+                } else {
+                    iso_surface_threshold * (0.02 + 0.96 * random())
+                };
+            }
+
+            let mesh = triangulate_central_cell(
+                &corner_values,
+                iso_surface_threshold,
+                MarchingCubesVariant::Mc33,
+            );
+            assert!(!mesh.triangles.is_empty());
+            assert!(
+                mesh.find_boundary_edges().is_empty(),
+                "mesh of case {} with values {:?} is not closed",
+                case,
+                corner_values
+            );
+            assert_closed_and_consistently_oriented(&mesh);
+            assert!(mesh.volume() > 0.0);
+        }
+    }
+}
+
+#[test]
+fn test_mc33_interior_tunnel() {
+    let iso_surface_threshold = 0.5;
+    // Two diagonally opposite corners far above the threshold connected by a tunnel through the cell interior
+    let corner_values = [1.5, 0.3, 0.3, 0.3, 0.3, 0.3, 1.5, 0.3];
+
+    let classic = triangulate_central_cell(
+        &corner_values,
+        iso_surface_threshold,
+        MarchingCubesVariant::Classic,
+    );
+    assert_eq!(classic.connected_components().1, 2);
+
+    let mc33 = triangulate_central_cell(
+        &corner_values,
+        iso_surface_threshold,
+        MarchingCubesVariant::Mc33,
+    );
+    assert_closed_and_consistently_oriented(&mc33);
+    assert_eq!(mc33.connected_components().1, 1);
+
+    // The tunnel connects the two blobs to a single surface that is topologically a sphere
+    let mut edges = new_map();
+    for tri in mc33.triangles.iter() {
+        for (i, j) in [(0, 1), (1, 2), (2, 0)] {
+            edges.insert((tri[i].min(tri[j]), tri[i].max(tri[j])), ());
+        }
+    }
+    let euler_characteristic =
+        mc33.vertices.len() as i64 - edges.len() as i64 + mc33.triangles.len() as i64;
+    assert_eq!(euler_characteristic, 2);
+}
+
+#[test]
+fn test_surface_nets_sphere() {
+    let grid =
+        UniformGrid::<i32, f64>::new(&Vector3::new(0.0, 0.0, 0.0), &[12, 12, 12], 1.0).unwrap();
+    let center = Vector3::new(6.1, 5.9, 6.05);
+    let radius = 3.7;
+    let iso_surface_threshold = 0.5;
+
+    let mut density_map = new_map();
+    for i in 0..=12 {
+        for j in 0..=12 {
+            for k in 0..=12 {
+                let point = grid.point_coordinates_array(&[i, j, k]);
+                let density = 1.0 - (point - center).norm() / (2.0 * radius);
+                density_map.insert(grid.flatten_point_index_array(&[i, j, k]), density);
+            }
+        }
+    }
+    let density_map = density_map.into();
+
+    // Returns the smallest interior angle of every triangle
+    let min_angles = |mesh: &TriMesh3d<f64>| -> Vec<f64> {
+        mesh.triangles
+            .iter()
+            .map(|tri| {
+                let [a, b, c] = tri.map(|v| mesh.vertices[v]);
+                [(a, b, c), (b, c, a), (c, a, b)]
+                    .iter()
+                    .map(|(p, q, r)| (q - p).angle(&(r - p)))
+                    .fold(f64::MAX, f64::min)
+            })
+            .collect()
+    };
+
+    let classic = triangulate_density_map(
+        &grid,
+        &density_map,
+        iso_surface_threshold,
+        MarchingCubesVariant::Classic,
+    )
+    .unwrap();
+    let surface_nets = triangulate_density_map(
+        &grid,
+        &density_map,
+        iso_surface_threshold,
+        MarchingCubesVariant::SurfaceNets,
+    )
+    .unwrap();
+
+    assert!(surface_nets.find_boundary_edges().is_empty());
+    assert_closed_and_consistently_oriented(&surface_nets);
+    assert_eq!(surface_nets.connected_components().1, 1);
+
+    // Same orientation as marching cubes, the averaging of the crossings slightly shrinks convex surfaces on coarse grids
+    let sphere_volume = 4.0 / 3.0 * std::f64::consts::PI * radius.powi(3);
+    assert!((classic.volume() - sphere_volume).abs() < 0.05 * sphere_volume);
+    assert!((surface_nets.volume() - sphere_volume).abs() < 0.1 * sphere_volume);
+    assert!(surface_nets.volume() < classic.volume());
+
+    // The dual triangulation avoids the sliver triangles of marching cubes
+    let classic_angles = min_angles(&classic);
+    let surface_nets_angles = min_angles(&surface_nets);
+    let mean = |angles: &[f64]| angles.iter().sum::<f64>() / angles.len() as f64;
+    let worst = |angles: &[f64]| angles.iter().copied().fold(f64::MAX, f64::min);
+    assert!(mean(&surface_nets_angles) > mean(&classic_angles));
+    assert!(worst(&surface_nets_angles) > worst(&classic_angles));
+}
+
+#[test]
+fn test_vertex_snapping() {
+    let grid =
+        UniformGrid::<i32, f64>::new(&Vector3::new(0.0, 0.0, 0.0), &[12, 12, 12], 1.0).unwrap();
+    // The iso-surface passes very close to the grid points on the axes through the center
+    let center = Vector3::new(6.0, 6.0, 6.0);
+    let radius = 3.0 + 1e-7;
+    let iso_surface_threshold = 0.5;
+
+    let mut density_map = new_map();
+    for i in 0..=12 {
+        for j in 0..=12 {
+            for k in 0..=12 {
+                let point = grid.point_coordinates_array(&[i, j, k]);
+                let density = 1.0 - (point - center).norm() / (2.0 * radius);
+                density_map.insert(grid.flatten_point_index_array(&[i, j, k]), density);
+            }
+        }
+    }
+    let density_map = density_map.into();
+
+    let triangulate_with_snapping = |snapping: Option<f64>, variant: MarchingCubesVariant| {
+        let mut mesh = TriMesh3d::default();
+        triangulate_density_map_append_with_cells(
+            &grid,
+            None,
+            &density_map,
+            iso_surface_threshold,
+            snapping,
+            variant,
+            &mut mesh,
+            None,
+        )
+        .unwrap();
+        mesh
+    };
+
+    // Returns the length of the shortest edge of the mesh
+    let min_edge_length = |mesh: &TriMesh3d<f64>| -> f64 {
+        mesh.triangles
+            .iter()
+            .flat_map(|tri| {
+                [(0, 1), (1, 2), (2, 0)]
+                    .map(|(i, j)| (mesh.vertices[tri[i]] - mesh.vertices[tri[j]]).norm())
+            })
+            .fold(f64::MAX, f64::min)
+    };
+
+    for variant in [MarchingCubesVariant::Classic, MarchingCubesVariant::Mc33] {
+        let plain =
+            triangulate_density_map(&grid, &density_map, iso_surface_threshold, variant).unwrap();
+        let unsnapped = triangulate_with_snapping(None, variant);
+        assert_eq!(unsnapped.vertices, plain.vertices);
+        assert_eq!(unsnapped.triangles, plain.triangles);
+        assert!(min_edge_length(&plain) < 1e-4);
+
+        for snapping in [1e-4, 0.3] {
+            let snapped = triangulate_with_snapping(Some(snapping), variant);
+            assert!(snapped.find_boundary_edges().is_empty());
+            assert_closed_and_consistently_oriented(&snapped);
+            assert_eq!(snapped.connected_components().1, 1);
+
+            // The almost coincident vertices are merged without leaving unreferenced vertices behind
+            assert!(snapped.vertices.len() < plain.vertices.len());
+            assert!(snapped.triangles.len() < plain.triangles.len());
+            let mut referenced = vec![false; snapped.vertices.len()];
+            snapped
+                .triangles
+                .iter()
+                .flatten()
+                .for_each(|&v| referenced[v] = true);
+            assert!(referenced.iter().all(|&r| r));
+            assert!(min_edge_length(&snapped) > snapping);
+            assert!((snapped.volume() - plain.volume()).abs() < 0.05 * plain.volume());
+        }
+    }
+}
+
+#[test]
+fn test_triangulate_scalar_field_sparse() {
+    let grid =
+        UniformGrid::<i32, f64>::new(&Vector3::new(0.0, 0.0, 0.0), &[12, 12, 12], 1.0).unwrap();
+    let center = Vector3::new(6.1, 5.9, 6.05);
+    let radius = 3.7;
+    // Signed distance, positive inside of the sphere
+    let distance =
+        |point: &PointIndex<i32>| radius - (grid.point_coordinates(point) - center).norm();
+
+    let mut dense = TriMesh3d::default();
+    triangulate_scalar_field(
+        &grid,
+        |point| Some(distance(point)),
+        0.0,
+        MarchingCubesVariant::Classic,
+        &mut dense,
+    )
+    .unwrap();
+
+    // Only provide values inside of the sphere, all other points are outside
+    let mut sparse = TriMesh3d::default();
+    triangulate_scalar_field(
+        &grid,
+        |point| Some(distance(point)).filter(|&d| d > 0.0),
+        0.0,
+        MarchingCubesVariant::Classic,
+        &mut sparse,
+    )
+    .unwrap();
+
+    for mesh in [&dense, &sparse] {
+        assert!(mesh.find_boundary_edges().is_empty());
+        assert_closed_and_consistently_oriented(mesh);
+        assert_eq!(mesh.connected_components().1, 1);
+    }
+
+    // Without values outside of the sphere only the position of the vertices along the edges is approximate
+    let sphere_volume = 4.0 / 3.0 * std::f64::consts::PI * radius.powi(3);
+    assert_eq!(dense.triangles.len(), sparse.triangles.len());
+    assert!((dense.volume() - sphere_volume).abs() < 0.05 * sphere_volume);
+    assert!((sparse.volume() - sphere_volume).abs() < 0.25 * sphere_volume);
+
+    // Fields without any point inside of the surface result in an empty mesh
+    let mut empty = TriMesh3d::default();
+    triangulate_scalar_field(
+        &grid,
+        |_| None,
+        0.0,
+        MarchingCubesVariant::Classic,
+        &mut empty,
+    )
+    .unwrap();
+    assert!(empty.triangles.is_empty());
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_par_triangulate_density_map() {
+    let grid =
+        UniformGrid::<i32, f64>::new(&Vector3::new(0.0, 0.0, 0.0), &[40, 16, 16], 1.0).unwrap();
+    let iso_surface_threshold = 0.5;
+
+    // Simple LCG for reproducible noise resulting in many ambiguous cells
+    let mut state = 0x9e3779b97f4a7c15_u64;
+    let mut random = move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+
+    // A noisy cylinder along the first axis of the grid, the points outside of it are not part of the density map
+    let mut density_map = new_map();
+    for i in 0..=40 {
+        for j in 0..=16 {
+            for k in 0..=16 {
+                let point = grid.point_coordinates_array(&[i, j, k]);
+                let distance = (Vector3::new(0.0, point.y, point.z) - Vector3::new(0.0, 8.0, 8.0))
+                    .norm()
+                    / 6.0;
+                if distance < 1.0 {
+                    // The caps of the cylinder are closed by points with zero density
+                    let density = if i > 0 && i < 40 {
+                        1.0 - distance + 0.3 * (random() - 0.5)
+                    } else {
+                        0.0
+                    };
+                    density_map.insert(grid.flatten_point_index_array(&[i, j, k]), density);
+                }
+            }
+        }
+    }
+    let density_map = density_map.into();
+
+    // Returns the sorted vertex coordinates of all triangles together with their cells
+    let canonical_triangles = |mesh: &TriMesh3d<f64>, cells: &[i32]| {
+        let mut triangles = mesh
+            .triangles
+            .iter()
+            .zip(cells.iter())
+            .map(|(triangle, &cell)| {
+                let coordinates = triangle.map(|v| mesh.vertices[v].map(f64::to_bits));
+                (coordinates.map(|c| [c.x, c.y, c.z]), cell)
+            })
+            .collect::<Vec<_>>();
+        triangles.sort_unstable();
+        triangles
+    };
+
+    for variant in [
+        MarchingCubesVariant::Classic,
+        MarchingCubesVariant::Mc33,
+        MarchingCubesVariant::SurfaceNets,
+    ] {
+        for vertex_snapping in [None, Some(0.2)] {
+            let mut mesh = TriMesh3d::default();
+            let mut cells = Vec::new();
+            triangulate_density_map_append_with_cells(
+                &grid,
+                None,
+                &density_map,
+                iso_surface_threshold,
+                vertex_snapping,
+                variant,
+                &mut mesh,
+                Some(&mut cells),
+            )
+            .unwrap();
+
+            // Append to a mesh that already contains a triangle
+            let mut par_mesh = TriMesh3d {
+                vertices: vec![Vector3::zeros(); 3],
+                triangles: vec![[0, 1, 2]],
+            };
+            let mut par_cells = vec![-1];
+            par_triangulate_density_map_append_with_cells(
+                &grid,
+                &density_map,
+                iso_surface_threshold,
+                vertex_snapping,
+                variant,
+                &mut par_mesh,
+                Some(&mut par_cells),
+            )
+            .unwrap();
+            assert_eq!(par_mesh.triangles[0], [0, 1, 2]);
+            assert_eq!(par_cells.len(), par_mesh.triangles.len());
+
+            // Identical up to the order of the vertices and triangles, without duplicated vertices at the slab borders
+            let appended = TriMesh3d {
+                vertices: par_mesh.vertices[3..].to_vec(),
+                triangles: par_mesh.triangles[1..]
+                    .iter()
+                    .map(|triangle| triangle.map(|v| v - 3))
+                    .collect(),
+            };
+            assert!(!mesh.triangles.is_empty());
+            assert_eq!(appended.vertices.len(), mesh.vertices.len());
+            assert_eq!(
+                canonical_triangles(&appended, &par_cells[1..]),
+                canonical_triangles(&mesh, &cells)
+            );
+            // Surface nets are not guaranteed to be manifold for noisy density maps
+            if variant != MarchingCubesVariant::SurfaceNets {
+                assert_closed_and_consistently_oriented(&appended);
+            }
+        }
+
+        let par_mesh =
+            par_triangulate_density_map(&grid, &density_map, iso_surface_threshold, variant)
+                .unwrap();
+        let mesh =
+            triangulate_density_map(&grid, &density_map, iso_surface_threshold, variant).unwrap();
+        assert_eq!(par_mesh.vertices.len(), mesh.vertices.len());
+        assert_eq!(par_mesh.triangles.len(), mesh.triangles.len());
+    }
+}
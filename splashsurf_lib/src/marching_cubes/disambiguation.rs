@@ -0,0 +1,660 @@
+//! Topologically correct triangulation of ambiguous marching cubes configurations (MC33 variant)
+//!
+//! The classic lookup table (see [`marching_cubes_lut`](super::marching_cubes_lut)) only depends on the
+//! above/below flags of the cell corners. For configurations where two diagonally opposite corners of a face are
+//! above and the other two corners are below the threshold, the contour on the face is ambiguous and the table
+//! always resolves it in the same way, independent of the actual values. Similarly, the table never
+//! connects the parts of the surface inside of a cell by a tunnel. The resulting surface is closed, but its topology
+//! may not match the density field, e.g. thin sheets of fluid can get holes.
+//!
+//! Following the idea of Chernyaev's "Marching Cubes 33" (1995), the triangulation of a cell is resolved here using
+//! the trilinear interpolant of the actual values at the cell corners:
+//!  - Face test: the contour on an ambiguous face is determined by the asymptotic decider, i.e. the two corners
+//!    above the threshold are connected if the value at the saddle point of the bilinear interpolant on the face is
+//!    above the threshold. As the test only depends on the four values of the face, both cells adjacent to the face
+//!    come to the same decision and the resulting surface is watertight.
+//!  - Interior test: two parts of the surface are connected by a tunnel through the cell if two corners that are
+//!    separated on the boundary of the cell are connected through the interior of the cell. This is checked on slices
+//!    of the cell orthogonal to each axis, where the trilinear interpolant reduces to a bilinear function and the
+//!    connectivity of its corners can be evaluated with the same saddle point test as on the faces.
+//!
+//! Instead of storing the extended lookup tables of all subcases, the contour polygons on the boundary of the cell are
+//! constructed from the results of the face tests and triangulated directly. Only cells with an ambiguous face or
+//! a tunnel use this construction, all other cells are triangulated with the classic table. Polygons that cannot be
+//! triangulated without a diagonal lying in a face of the cell (where it could coincide with an edge of the
+//! neighboring cell) are triangulated around an additional vertex inside of the cell, like some subcases of MC33.
+
+use crate::topology::cube::{LOCAL_EDGES_BY_AXIS, LOCAL_EDGE_VERTICES, LOCAL_VERTEX_OFFSETS};
+use crate::Real;
+use nalgebra::Vector3;
+
+/// Local vertices of the six faces of a cell in counter-clockwise order seen from outside of the cell
+const FACE_VERTICES: [[usize; 4]; 6] = [
+    [0, 3, 2, 1], // z = 0
+    [4, 5, 6, 7], // z = 1
+    [0, 1, 5, 4], // y = 0
+    [3, 7, 6, 2], // y = 1
+    [0, 4, 7, 3], // x = 0
+    [1, 2, 6, 5], // x = 1
+];
+
+/// Local edges of the six faces of a cell, the `k`-th edge connects the `k`-th and the `(k + 1)`-th vertex of [`FACE_VERTICES`]
+const FACE_EDGES: [[usize; 4]; 6] = [
+    [3, 2, 1, 0],
+    [4, 5, 6, 7],
+    [0, 9, 4, 8],
+    [11, 6, 10, 2],
+    [8, 7, 11, 3],
+    [1, 10, 5, 9],
+];
+
+/// Triangulation of an ambiguous cell, all triangles have the same winding order as [`marching_cubes_triangulation_iter`](super::marching_cubes_lut::marching_cubes_triangulation_iter)
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AmbiguousCellTriangulation {
+    /// Triangles given by the local edge indices of their vertices
+    pub(crate) triangles: Vec<[i32; 3]>,
+    /// Contour polygons given by local edge indices that are triangulated by a fan around an additional vertex at their centroid
+    ///
+    /// The fan consists of the triangles `[centroid, polygon[k], polygon[k + 1]]` (cyclic). Such a vertex inside of
+    /// the cell is required for polygons that cannot be triangulated without a diagonal lying in a face of the cell.
+    pub(crate) centroid_fans: Vec<Vec<usize>>,
+}
+
+/// Returns the triangulation of a cell with the given corner values relative to the iso-surface threshold (i.e. `value - threshold`) if it is ambiguous
+///
+/// Returns `None` if the triangulation of the classic lookup table is topologically correct for the cell, i.e. if
+/// no face of the cell is ambiguous and the surface inside of the cell has no tunnel.
+pub(crate) fn triangulate_ambiguous_cell<R: Real>(
+    values: &[R; 8],
+) -> Option<AmbiguousCellTriangulation> {
+    let contour = CellContour::new(values);
+    let tunnel = contour.find_tunnel();
+    if !contour.has_ambiguous_face && tunnel.is_none() {
+        return None;
+    }
+    Some(contour.triangulate(tunnel))
+}
+
+/// The contour polygons of the iso-surface on the boundary of a cell after resolving all ambiguous faces
+struct CellContour<'a, R: Real> {
+    values: &'a [R; 8],
+    /// Closed contour polygons given by the local edges of their vertices, the part of the boundary above the threshold is on the left side seen from outside of the cell
+    polygons: Vec<Vec<usize>>,
+    /// Index of the connected part of the boundary of the cell above or below the threshold containing each corner
+    regions: [usize; 8],
+    has_ambiguous_face: bool,
+}
+
+impl<'a, R: Real> CellContour<'a, R> {
+    /// Constructs the contour polygons by connecting the contour segments on all faces of the cell
+    fn new(values: &'a [R; 8]) -> Self {
+        let above = values.map(|v| v > R::zero());
+
+        // Corners that are connected on the boundary of the cell, i.e. by an edge or diagonally over a face
+        let mut regions = [0, 1, 2, 3, 4, 5, 6, 7];
+        for [v0, v1] in LOCAL_EDGE_VERTICES {
+            if above[v0] == above[v1] {
+                union(&mut regions, v0, v1);
+            }
+        }
+
+        // The contour segment starting at a vertex on an edge, a segment leaves the region above the threshold when
+        // walking counter-clockwise along the face boundary and ends where it enters this region again
+        let mut segment_end = [None; 12];
+        let mut has_ambiguous_face = false;
+        for (corners, edges) in FACE_VERTICES.iter().zip(FACE_EDGES.iter()) {
+            let exits = (0..4)
+                .filter(|&k| above[corners[k]] && !above[corners[(k + 1) % 4]])
+                .collect::<Vec<_>>();
+            let entries = (0..4)
+                .filter(|&k| !above[corners[k]] && above[corners[(k + 1) % 4]])
+                .collect::<Vec<_>>();
+
+            match exits.len() {
+                1 => segment_end[edges[exits[0]]] = Some(edges[entries[0]]),
+                2 => {
+                    has_ambiguous_face = true;
+                    // The first corner of an exit edge is above the threshold
+                    let [a, b] = [corners[exits[0]], corners[exits[1]]];
+                    let [c, d] = [corners[entries[0]], corners[entries[1]]];
+                    let above_connected =
+                        saddle_connects_above(values[a], values[b], values[c], values[d]);
+                    if above_connected {
+                        union(&mut regions, a, b);
+                    } else {
+                        union(&mut regions, c, d);
+                    }
+                    for &k in &exits {
+                        // Cut off the corner below the threshold after the exit or the corner above before the exit
+                        let end = if above_connected {
+                            (k + 1) % 4
+                        } else {
+                            (k + 3) % 4
+                        };
+                        segment_end[edges[k]] = Some(edges[end]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Connect the segments to closed polygons
+        let mut polygons = Vec::new();
+        let mut visited = [false; 12];
+        for start in 0..12 {
+            if visited[start] || segment_end[start].is_none() {
+                continue;
+            }
+            let mut polygon = Vec::new();
+            let mut edge = start;
+            while !visited[edge] {
+                visited[edge] = true;
+                polygon.push(edge);
+                edge = segment_end[edge].expect("contour segments have to form closed polygons");
+            }
+            polygons.push(polygon);
+        }
+
+        let regions = [0, 1, 2, 3, 4, 5, 6, 7].map(|v| find(&mut regions, v));
+        Self {
+            values,
+            polygons,
+            regions,
+            has_ambiguous_face,
+        }
+    }
+
+    /// Returns the regions of the cell boundary above and below the threshold adjacent to a contour polygon
+    fn adjacent_regions(&self, polygon: &[usize]) -> (usize, usize) {
+        let [v0, v1] = LOCAL_EDGE_VERTICES[polygon[0]];
+        if self.values[v0] > R::zero() {
+            (self.regions[v0], self.regions[v1])
+        } else {
+            (self.regions[v1], self.regions[v0])
+        }
+    }
+
+    /// Returns a pair of contour polygons that are connected by a tunnel through the interior of the cell, if any
+    ///
+    /// Two polygons are connected by a tunnel if they are adjacent to the same region of the boundary and their
+    /// regions on the other side are separated on the boundary but connected through the interior of the cell.
+    fn find_tunnel(&self) -> Option<(usize, usize)> {
+        for i in 0..self.polygons.len() {
+            for j in (i + 1)..self.polygons.len() {
+                let (above_i, below_i) = self.adjacent_regions(&self.polygons[i]);
+                let (above_j, below_j) = self.adjacent_regions(&self.polygons[j]);
+                let connected = if below_i == below_j && above_i != above_j {
+                    self.connected_through_interior(true, above_i, above_j)
+                } else if above_i == above_j && below_i != below_j {
+                    self.connected_through_interior(false, below_i, below_j)
+                } else {
+                    false
+                };
+
+                if connected {
+                    return Some((i, j));
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns whether the two boundary regions above (or below) the threshold are connected through the interior of the cell
+    fn connected_through_interior(&self, above: bool, region_a: usize, region_b: usize) -> bool {
+        for edges in LOCAL_EDGES_BY_AXIS {
+            // Values at the corners of the slices along the axis are linear functions `w(t) = v_start + t·(v_end - v_start)`
+            let starts = edges.map(|e| self.values[LOCAL_EDGE_VERTICES[e][0]]);
+            let ends = edges.map(|e| self.values[LOCAL_EDGE_VERTICES[e][1]]);
+            let slopes = [0, 1, 2, 3].map(|j| ends[j] - starts[j]);
+
+            // The connectivity of the slice corners only changes where a corner value crosses the threshold or
+            // where the saddle point of the slice crosses the threshold, i.e. where `w0·w2 - w1·w3` changes its sign
+            let mut breakpoints = vec![R::zero(), R::one()];
+            for j in 0..4 {
+                if (starts[j] > R::zero()) != (ends[j] > R::zero()) {
+                    breakpoints.push(starts[j] / (starts[j] - ends[j]));
+                }
+            }
+            let a = slopes[0] * slopes[2] - slopes[1] * slopes[3];
+            let b = starts[0] * slopes[2] + starts[2] * slopes[0]
+                - starts[1] * slopes[3]
+                - starts[3] * slopes[1];
+            let c = starts[0] * starts[2] - starts[1] * starts[3];
+            breakpoints.extend(quadratic_roots(a, b, c));
+            breakpoints.retain(|t| *t >= R::zero() && *t <= R::one());
+            breakpoints.sort_unstable_by(|t1, t2| t1.partial_cmp(t2).unwrap());
+
+            for window in breakpoints.windows(2) {
+                if window[1] > window[0] {
+                    let t = (window[0] + window[1]) / (R::one() + R::one());
+                    if self.slice_connects(&edges, starts, slopes, t, above, region_a, region_b) {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns whether the slice at parameter `t` along the given edges connects the two regions above (or below) the threshold
+    #[allow(clippy::too_many_arguments)]
+    fn slice_connects(
+        &self,
+        edges: &[usize; 4],
+        starts: [R; 4],
+        slopes: [R; 4],
+        t: R,
+        above: bool,
+        region_a: usize,
+        region_b: usize,
+    ) -> bool {
+        let w = [0, 1, 2, 3].map(|j| starts[j] + slopes[j] * t);
+        let selected = w.map(|w| (w > R::zero()) == above);
+
+        // Two diagonally opposite selected corners are only connected through the slice if the saddle point is on their side
+        let is_diagonal =
+            selected == [true, false, true, false] || selected == [false, true, false, true];
+        if is_diagonal {
+            let (a, b, c, d) = if selected[0] {
+                (w[0], w[2], w[1], w[3])
+            } else {
+                (w[1], w[3], w[0], w[2])
+            };
+            let above_connected = if above {
+                saddle_connects_above(a, b, c, d)
+            } else {
+                saddle_connects_above(c, d, a, b)
+            };
+            if above_connected != above {
+                return false;
+            }
+        }
+
+        // Otherwise all selected corners of the slice are connected, a selected point on an edge belongs to the region of the selected end of the edge
+        let mut has_a = false;
+        let mut has_b = false;
+        for (j, &e) in edges.iter().enumerate() {
+            if selected[j] {
+                let [v0, v1] = LOCAL_EDGE_VERTICES[e];
+                let v = if (self.values[v0] > R::zero()) == above {
+                    v0
+                } else {
+                    v1
+                };
+                has_a |= self.regions[v] == region_a;
+                has_b |= self.regions[v] == region_b;
+            }
+        }
+        has_a && has_b
+    }
+
+    /// Triangulates all contour polygons, the two polygons of the tunnel are connected by a tube instead of being closed individually
+    fn triangulate(&self, tunnel: Option<(usize, usize)>) -> AmbiguousCellTriangulation {
+        let mut triangulation = AmbiguousCellTriangulation::default();
+        for (i, polygon) in self.polygons.iter().enumerate() {
+            if let Some((_, j)) = tunnel.filter(|&(first, _)| first == i) {
+                self.triangulate_tube(polygon, &self.polygons[j], &mut triangulation.triangles);
+            } else if !matches!(tunnel, Some((_, second)) if second == i) {
+                triangulate_polygon(polygon, &mut triangulation);
+            }
+        }
+        triangulation
+    }
+
+    /// Connects two contour polygons by a band of triangles
+    ///
+    /// The band is given by a monotone path over the pairs of vertices of the two polygons, where every visited pair
+    /// is connected by an edge of the band. As for the triangulation of single polygons, edges between two vertices on
+    /// the same face of the cell are avoided. Among the remaining bands, the band with the shortest edges is chosen.
+    fn triangulate_tube(&self, first: &[usize], second: &[usize], triangles: &mut Vec<[i32; 3]>) {
+        // The first polygon is traversed in reverse order, the second polygon in its order, such that the
+        // boundary of the tube is consistently oriented with the triangles of the neighboring cells
+        let first = first.iter().rev().copied().collect::<Vec<_>>();
+        let (n, m) = (first.len(), second.len());
+        let positions_first = first
+            .iter()
+            .map(|&e| self.edge_vertex(e))
+            .collect::<Vec<_>>();
+        let positions_second = second
+            .iter()
+            .map(|&e| self.edge_vertex(e))
+            .collect::<Vec<_>>();
+
+        let mut best_total = None;
+        let mut best_band = Vec::new();
+        for offset_first in 0..n {
+            for offset_second in 0..m {
+                let a = |i: usize| (i + offset_first) % n;
+                let b = |k: usize| (k + offset_second) % m;
+                // Edges of the band are scored by whether they lie in a face of the cell and by their length
+                let score = |i: usize, k: usize| {
+                    let length = (positions_first[a(i)] - positions_second[b(k)]).norm();
+                    (share_face(first[a(i)], second[b(k)]) as usize, length)
+                };
+                let add = |(c1, l1): (usize, R), (c2, l2): (usize, R)| (c1 + c2, l1 + l2);
+
+                // The path starts by advancing on the first polygon and ends by advancing on the second polygon
+                // to ensure that no pair of vertices is connected twice by the closed band
+                let mut path_score = vec![vec![None; m + 1]; n + 1];
+                let mut advanced_first = vec![vec![false; m + 1]; n + 1];
+                path_score[0][0] = Some(score(0, 0));
+                for i in 1..=n {
+                    for k in 0..=m {
+                        // The pairs `(n, 0)` and `(i, m)` are the pairs `(0, 0)` and `(i, 0)` respectively
+                        if (i, k) == (n, 0) || (k == m && i < n) {
+                            continue;
+                        }
+                        let via_first = path_score[i - 1][k];
+                        let via_second = if k > 0 { path_score[i][k - 1] } else { None };
+                        let (previous, first_advanced) = match (via_first, via_second) {
+                            (Some(f), Some(s)) if s < f => (s, false),
+                            (Some(f), _) => (f, true),
+                            (None, Some(s)) => (s, false),
+                            (None, None) => continue,
+                        };
+                        path_score[i][k] = Some(if (i, k) == (n, m) {
+                            previous
+                        } else {
+                            add(previous, score(i, k))
+                        });
+                        advanced_first[i][k] = first_advanced;
+                    }
+                }
+
+                let total = match path_score[n][m] {
+                    Some(total) => total,
+                    None => continue,
+                };
+                if best_total.is_some_and(|best_total| best_total <= total) {
+                    continue;
+                }
+
+                best_total = Some(total);
+                best_band.clear();
+                let (mut i, mut k) = (n, m);
+                while (i, k) != (0, 0) {
+                    if advanced_first[i][k] {
+                        best_band
+                            .push([first[a(i - 1)], first[a(i)], second[b(k)]].map(|e| e as i32));
+                        i -= 1;
+                    } else {
+                        best_band
+                            .push([first[a(i)], second[b(k)], second[b(k - 1)]].map(|e| e as i32));
+                        k -= 1;
+                    }
+                }
+            }
+        }
+
+        triangles.extend(best_band);
+    }
+
+    /// Returns the position of the linearly interpolated iso-surface vertex on the given edge in local coordinates of the cell
+    fn edge_vertex(&self, edge: usize) -> Vector3<R> {
+        let [v0, v1] = LOCAL_EDGE_VERTICES[edge];
+        let position = |v: usize| {
+            LOCAL_VERTEX_OFFSETS[v]
+                .map(|x| R::from_u8(x).unwrap())
+                .into()
+        };
+        let (p0, p1): (Vector3<R>, Vector3<R>) = (position(v0), position(v1));
+        let t = self.values[v0] / (self.values[v0] - self.values[v1]);
+        p0 + (p1 - p0) * t
+    }
+}
+
+/// Triangulates a contour polygon in reverse order of the polygon to get normals pointing away from the fluid
+///
+/// Diagonals between two vertices on the same face of the cell are avoided: such a diagonal lies in the face and the
+/// cell on the other side of the face may produce the same edge or even the same triangle with opposite orientation.
+/// A triangulation without such diagonals is searched by dynamic programming over the sub-polygons (the polygons have
+/// at most twelve vertices). If there is none, e.g. for polygons passing an ambiguous face twice, the polygon is
+/// triangulated by a fan around an additional vertex inside of the cell.
+fn triangulate_polygon(polygon: &[usize], triangulation: &mut AmbiguousCellTriangulation) {
+    let vertices = polygon.iter().rev().copied().collect::<Vec<_>>();
+    let n = vertices.len();
+    let diagonal_cost = |i: usize, j: usize| -> usize {
+        let is_polygon_edge = j == i + 1 || (i == 0 && j == n - 1);
+        (!is_polygon_edge && share_face(vertices[i], vertices[j])) as usize
+    };
+
+    // Minimal cost and the apex of the triangle on the diagonal `(i, j)` of the sub-polygon `i, i + 1, ..., j`
+    let mut cost = vec![vec![0; n]; n];
+    let mut apex = vec![vec![0; n]; n];
+    for length in 2..n {
+        for i in 0..n - length {
+            let j = i + length;
+            let (best_cost, best_apex) = ((i + 1)..j)
+                .map(|k| {
+                    let cost = cost[i][k] + cost[k][j] + diagonal_cost(i, k) + diagonal_cost(k, j);
+                    (cost, k)
+                })
+                .min()
+                .unwrap();
+            cost[i][j] = best_cost;
+            apex[i][j] = best_apex;
+        }
+    }
+
+    if cost[0][n - 1] > 0 {
+        triangulation.centroid_fans.push(vertices);
+        return;
+    }
+
+    let mut stack = vec![(0, n - 1)];
+    while let Some((i, j)) = stack.pop() {
+        if j > i + 1 {
+            let k = apex[i][j];
+            triangulation
+                .triangles
+                .push([vertices[i], vertices[k], vertices[j]].map(|e| e as i32));
+            stack.push((i, k));
+            stack.push((k, j));
+        }
+    }
+}
+
+/// Returns whether the two local edges are part of the same face of the cell
+fn share_face(edge_a: usize, edge_b: usize) -> bool {
+    FACE_EDGES
+        .iter()
+        .any(|edges| edges.contains(&edge_a) && edges.contains(&edge_b))
+}
+
+/// Returns whether the two diagonally opposite corners above the threshold (with values `a` and `b`) are connected on a bilinear face (asymptotic decider)
+///
+/// The corners with the values `c` and `d` are below the threshold. The value of the bilinear interpolant at its
+/// saddle point is `(a·b - c·d) / (a + b - c - d)` where the denominator is positive, so the corners above the
+/// threshold are connected if `a·b > c·d`. The test is symmetric in the corners of each pair, so both cells adjacent
+/// to a face always come to the same decision.
+fn saddle_connects_above<R: Real>(a: R, b: R, c: R, d: R) -> bool {
+    a * b > c * d
+}
+
+/// Returns the real roots of `a·t² + b·t + c`
+fn quadratic_roots<R: Real>(a: R, b: R, c: R) -> Vec<R> {
+    if a == R::zero() {
+        if b == R::zero() {
+            Vec::new()
+        } else {
+            vec![-c / b]
+        }
+    } else {
+        let four = R::from_f64(4.0).unwrap();
+        let discriminant = b * b - four * a * c;
+        if discriminant < R::zero() {
+            Vec::new()
+        } else {
+            let sqrt = discriminant.sqrt();
+            let two_a = a + a;
+            vec![(-b - sqrt) / two_a, (-b + sqrt) / two_a]
+        }
+    }
+}
+
+/// Returns the representative of the set containing `i` of a union-find structure
+fn find(parents: &mut [usize; 8], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[i] = root;
+    root
+}
+
+/// Merges the sets containing `i` and `j` of a union-find structure
+fn union(parents: &mut [usize; 8], i: usize, j: usize) {
+    let (root_i, root_j) = (find(parents, i), find(parents, j));
+    parents[root_i] = root_j;
+}
+
+#[cfg(test)]
+mod test_disambiguation {
+    use super::*;
+    use crate::marching_cubes::marching_cubes_lut::marching_cubes_triangulation_iter;
+    use std::collections::HashMap;
+
+    /// Returns all triangles of the triangulation, the vertices of the centroid fans get the indices `12, 13, ...`
+    fn all_triangles(triangulation: &AmbiguousCellTriangulation) -> Vec<[i32; 3]> {
+        let mut triangles = triangulation.triangles.clone();
+        for (m, polygon) in triangulation.centroid_fans.iter().enumerate() {
+            for k in 0..polygon.len() {
+                let next = polygon[(k + 1) % polygon.len()];
+                triangles.push([12 + m as i32, polygon[k] as i32, next as i32]);
+            }
+        }
+        triangles
+    }
+
+    /// Returns the number of triangles adjacent to each undirected edge and checks that every directed edge is used at most once
+    fn edge_valences(triangles: &[[i32; 3]]) -> HashMap<[i32; 2], usize> {
+        let mut directed = HashMap::new();
+        for tri in triangles {
+            for k in 0..3 {
+                *directed.entry([tri[k], tri[(k + 1) % 3]]).or_insert(0) += 1;
+            }
+        }
+        assert!(
+            directed.values().all(|&n| n == 1),
+            "inconsistent orientation"
+        );
+
+        let mut valences = HashMap::new();
+        for [a, b] in directed.keys() {
+            *valences.entry([*a.min(b), *a.max(b)]).or_insert(0) += 1;
+        }
+        valences
+    }
+
+    /// Returns the directed edges of the triangles that are only used by one triangle, i.e. the boundary of the triangulation inside of the cell
+    fn boundary_edges(triangles: &[[i32; 3]]) -> Vec<[i32; 2]> {
+        let valences = edge_valences(triangles);
+        let mut boundary = triangles
+            .iter()
+            .flat_map(|tri| (0..3).map(move |k| [tri[k], tri[(k + 1) % 3]]))
+            .filter(|[a, b]| valences[&[*a.min(b), *a.max(b)]] == 1)
+            .collect::<Vec<_>>();
+        boundary.sort_unstable();
+        boundary
+    }
+
+    #[test]
+    fn test_face_tables_consistent_with_cube_topology() {
+        let mut faces_per_edge = [0; 12];
+        for (corners, edges) in FACE_VERTICES.iter().zip(FACE_EDGES.iter()) {
+            let offset = |v: usize| Vector3::from(LOCAL_VERTEX_OFFSETS[v].map(|x| x as f64));
+            for k in 0..4 {
+                let mut vertices = LOCAL_EDGE_VERTICES[edges[k]];
+                let mut expected = [corners[k], corners[(k + 1) % 4]];
+                vertices.sort_unstable();
+                expected.sort_unstable();
+                assert_eq!(vertices, expected);
+                faces_per_edge[edges[k]] += 1;
+            }
+
+            // The vertices are ordered counter-clockwise seen from outside
+            let normal = (offset(corners[1]) - offset(corners[0]))
+                .cross(&(offset(corners[2]) - offset(corners[0])));
+            let center = corners.iter().map(|&v| offset(v)).sum::<Vector3<f64>>() / 4.0;
+            assert!(normal.dot(&(center - Vector3::repeat(0.5))) > 0.0);
+        }
+        assert_eq!(faces_per_edge, [2; 12]);
+    }
+
+    #[test]
+    fn test_contour_matches_classic_table() {
+        // The boundary of the triangulation of every case has to agree with the classic table if no face is ambiguous
+        for case in 0..256 {
+            let values =
+                [0, 1, 2, 3, 4, 5, 6, 7].map(|v| if case & (1 << v) != 0 { 1.0 } else { -1.0 });
+            let flags = values.map(|v| v > 0.0);
+            let contour = CellContour::new(&values);
+            let triangles = all_triangles(&contour.triangulate(None));
+
+            let classic = marching_cubes_triangulation_iter(&flags).collect::<Vec<_>>();
+            if !contour.has_ambiguous_face {
+                assert_eq!(
+                    boundary_edges(&triangles),
+                    boundary_edges(&classic),
+                    "case {}",
+                    case
+                );
+            }
+
+            // Every contour vertex is on an edge crossing the iso-surface
+            for polygon in &contour.polygons {
+                for &e in polygon {
+                    let [v0, v1] = LOCAL_EDGE_VERTICES[e];
+                    assert_ne!(flags[v0], flags[v1]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_face_test() {
+        // Vertices 0 and 2 above the threshold, only the face z = 0 is ambiguous
+        let separated = [1.0, -1.0, 1.0, -1.0, -1.0, -1.0, -1.0, -1.0];
+        let triangles = all_triangles(&triangulate_ambiguous_cell(&separated).unwrap());
+        assert_eq!(triangles.len(), 2);
+
+        let connected = [1.0, -0.2, 1.0, -0.2, -1.0, -1.0, -1.0, -1.0];
+        let triangles = all_triangles(&triangulate_ambiguous_cell(&connected).unwrap());
+        let valences = edge_valences(&triangles);
+        // A single polygon with the vertices on the edges 0, 1, 2, 3, 8 and 10
+        assert_eq!(triangles.len(), 4);
+        assert_eq!(valences.values().filter(|&&n| n == 1).count(), 6);
+
+        // Cells that are not ambiguous use the classic table
+        assert!(
+            triangulate_ambiguous_cell(&[1.0, 1.0, -1.0, -1.0, -1.0, -1.0, -1.0, -1.0]).is_none()
+        );
+        assert!(triangulate_ambiguous_cell(&[-1.0; 8]).is_none());
+    }
+
+    #[test]
+    fn test_interior_test() {
+        // Vertices 0 and 6 above the threshold, no face is ambiguous
+        let separated = [1.0, -1.0, -1.0, -1.0, -1.0, -1.0, 1.0, -1.0];
+        assert!(triangulate_ambiguous_cell(&separated).is_none());
+
+        // A tunnel connects the two corners through the interior of the cell
+        let tunnel = [1.0, -0.2, -0.2, -0.2, -0.2, -0.2, 1.0, -0.2];
+        let triangles = all_triangles(&triangulate_ambiguous_cell(&tunnel).unwrap());
+        assert_eq!(triangles.len(), 6);
+        let boundary = boundary_edges(&triangles);
+        assert_eq!(boundary.len(), 6);
+        // The boundary of the tube is the same as the boundary of the two separate triangles
+        let classic =
+            marching_cubes_triangulation_iter(&tunnel.map(|v| v > 0.0)).collect::<Vec<_>>();
+        assert_eq!(boundary, boundary_edges(&classic));
+
+        // The same for the complementary configuration
+        let inverted = tunnel.map(|v: f64| -v);
+        let triangles = all_triangles(&triangulate_ambiguous_cell(&inverted).unwrap());
+        assert_eq!(triangles.len(), 6);
+        let classic =
+            marching_cubes_triangulation_iter(&inverted.map(|v| v > 0.0)).collect::<Vec<_>>();
+        assert_eq!(boundary_edges(&triangles), boundary_edges(&classic));
+    }
+}
@@ -23,7 +23,7 @@
 //! the reconstructed surface, the resulting triangles have to be flipped. This is already taken
 //! into account by the [`marching_cubes_triangulation_iter`] function.
 //!
-//! Cube description:
+//! Cube description (see [`topology::cube`](crate::topology::cube) for typed local vertex and edge indices):
 //!
 //! ```text
 //!         7 ________ 6           _____6__
@@ -426,6 +426,35 @@ mod test_lut {
         }
     }
 
+    #[test]
+    fn test_lut_edges_consistent_with_cube_topology() {
+        use crate::topology::cube::LocalEdgeIndex;
+
+        for i in 0..256 {
+            let flags = index_to_flags(i);
+            let raw = get_marching_cubes_triangulation_raw(&flags);
+
+            let used_edges = raw
+                .iter()
+                .take_while(|&&e| e != -1)
+                .map(|&e| LocalEdgeIndex::new(e as usize).expect("invalid edge index in LUT"))
+                .collect::<Vec<_>>();
+
+            // Exactly the edges with end points on different sides of the iso-surface are used by the triangulation
+            for edge in LocalEdgeIndex::all() {
+                let [origin, target] = edge.vertices();
+                let straddles = flags[origin.index()] != flags[target.index()];
+                assert_eq!(
+                    used_edges.contains(&edge),
+                    straddles,
+                    "case {}, edge {}",
+                    i,
+                    edge.index()
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_marching_cubes_triangulation_iter() {
         assert!(marching_cubes_triangulation_iter(&[
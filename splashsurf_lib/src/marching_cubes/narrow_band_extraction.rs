@@ -1,9 +1,10 @@
+use crate::logging::trace;
 use crate::marching_cubes::stitching::{collect_boundary_cell_data, BoundaryData};
 use crate::marching_cubes::{CellData, MarchingCubesInput, RelativeToThreshold};
+use crate::progress::ProgressLogger;
 use crate::topology::{Axis, DirectedAxisArray};
 use crate::uniform_grid::{CellIndex, GridBoundaryFaceFlags, PointIndex, Subdomain};
 use crate::{profile, DensityMap, Index, MapType, Real};
-use log::trace;
 use nalgebra::Vector3;
 
 /// Trait used by [interpolate_points_to_cell_data_generic] to filter out points and edges during construction of iso-surface vertices in narrow-band cells
@@ -54,6 +55,7 @@ pub(crate) fn construct_mc_input<I: Index, R: Real, S: Subdomain<I, R>>(
     let _ = interpolate_points_to_cell_data_generic::<I, R, _, _>(
         subdomain,
         density_map,
+        None,
         iso_surface_threshold,
         vertices,
         &mut marching_cubes_data,
@@ -82,6 +84,7 @@ pub(crate) fn construct_mc_input_with_stitching_data<I: Index, R: Real, S: Subdo
     let boundary_filter = interpolate_points_to_cell_data_generic(
         subdomain,
         density_map,
+        None,
         iso_surface_threshold,
         vertices,
         &mut marching_cubes_data,
@@ -119,6 +122,7 @@ pub(crate) fn update_mc_input_for_stitching_domain<I: Index, R: Real, S: Subdoma
     let _ = interpolate_points_to_cell_data_generic(
         subdomain,
         density_map,
+        None,
         iso_surface_threshold,
         vertices,
         marching_cubes_input,
@@ -134,6 +138,32 @@ pub(crate) fn update_mc_input_for_stitching_domain<I: Index, R: Real, S: Subdoma
     );
 }
 
+/// Interpolates the iso-surface vertices on the edges of the given subset of the points of the density map and returns the marching cubes input data of the adjacent cells
+///
+/// Only edges from the given points to neighbors above the iso-surface threshold are processed, the neighbors can be
+/// any point of the density map. The threshold flags of the resulting cell data are not complete and still have to be
+/// updated using [update_cell_data_threshold_flags] once the data of all points adjacent to the cells was collected.
+#[cfg(feature = "parallel")]
+pub(crate) fn interpolate_points_to_cell_data<I: Index, R: Real, S: Subdomain<I, R>>(
+    subdomain: &S,
+    density_map: &DensityMap<I, R>,
+    points: &[(I, R)],
+    iso_surface_threshold: R,
+    vertices: &mut Vec<Vector3<R>>,
+) -> MarchingCubesInput<I> {
+    let mut marching_cubes_data = MarchingCubesInput::default();
+    let _ = interpolate_points_to_cell_data_generic::<I, R, _, _>(
+        subdomain,
+        density_map,
+        Some(points),
+        iso_surface_threshold,
+        vertices,
+        &mut marching_cubes_data,
+        IdentityDensityMapFilter,
+    );
+    marching_cubes_data
+}
+
 /// Generates input data for performing the actual marching cubes triangulation
 ///
 /// This function interpolates iso-surface vertices for cells in the narrow band around the iso-surface.
@@ -148,6 +178,8 @@ pub(crate) fn update_mc_input_for_stitching_domain<I: Index, R: Real, S: Subdoma
 /// Note: The threshold flags in the resulting cell data are not complete and still have to be updated after
 /// this procedure using the [update_cell_data_threshold_flags] function.
 ///
+/// If a subset of the points of the density map is given, only the edges of these points are processed.
+///
 /// Note: This functions assumes that the default value for missing point data is below the iso-surface threshold.
 #[inline(never)]
 fn interpolate_points_to_cell_data_generic<
@@ -158,6 +190,7 @@ fn interpolate_points_to_cell_data_generic<
 >(
     subdomain: &S,
     density_map: &DensityMap<I, R>,
+    points: Option<&[(I, R)]>,
     iso_surface_threshold: R,
     vertices: &mut Vec<Vector3<R>>,
     marching_cubes_data: &mut MarchingCubesInput<I>,
@@ -175,7 +208,14 @@ fn interpolate_points_to_cell_data_generic<
     // Generate iso-surface vertices and identify affected cells & edges
     {
         profile!("generate_iso_surface_vertices");
-        density_map.for_each(|flat_point_index, point_value| {
+        let progress = ProgressLogger::start("marching cubes", "points", || {
+            points.map_or(density_map.len(), |points| points.len())
+        });
+        let mut process_point = |flat_point_index: I, point_value: R| {
+            if let Some(progress) = &progress {
+                progress.add(1);
+            }
+
             let global_point = grid.try_unflatten_point_index(flat_point_index).unwrap();
             let point = subdomain
                 .map_point(&global_point)
@@ -284,7 +324,15 @@ fn interpolate_points_to_cell_data_generic<
                         RelativeToThreshold::Above;
                 }
             }
-        });
+        };
+
+        if let Some(points) = points {
+            for &(flat_point_index, point_value) in points {
+                process_point(flat_point_index, point_value);
+            }
+        } else {
+            density_map.for_each(process_point);
+        }
     }
 
     trace!(
@@ -297,7 +345,7 @@ fn interpolate_points_to_cell_data_generic<
 }
 
 /// Loops through all corner vertices in the given marching cubes input and updates the above/below threshold flags
-fn update_cell_data_threshold_flags<I: Index, R: Real, S: Subdomain<I, R>>(
+pub(crate) fn update_cell_data_threshold_flags<I: Index, R: Real, S: Subdomain<I, R>>(
     subdomain: &S,
     density_map: &DensityMap<I, R>,
     iso_surface_threshold: R,
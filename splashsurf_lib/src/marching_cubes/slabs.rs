@@ -0,0 +1,293 @@
+//! Multi-threaded marching cubes triangulation of a density map in slabs of cells
+//!
+//! The triangulation is performed in two parallel passes:
+//!  1. The points of the density map below the iso-surface threshold are split into chunks in the iteration order
+//!     of the map (which keeps the neighbor lookups of consecutive points close in memory) and the iso-surface
+//!     vertices on their edges are interpolated per chunk. Each vertex is only created by the point below the
+//!     threshold of its edge, the vertices of all chunks can therefore be concatenated without duplicates and no
+//!     welding is required.
+//!  2. The cells collected by the chunks are merged into slabs of cells along the first axis of the grid which are
+//!     triangulated independently. The triangles of all slabs are concatenated in the order of the slabs.
+//!
+//! The slabs are chosen to balance the number of cells between the slabs. The number of chunks and slabs only
+//! depends on the density map (and not on the number of threads), the resulting mesh is therefore deterministic
+//! if the iteration order of the density map is deterministic.
+
+use crate::marching_cubes::narrow_band_extraction::{
+    interpolate_points_to_cell_data, update_cell_data_threshold_flags,
+};
+use crate::marching_cubes::triangulation::{triangulate, CornerValues, TriangulationError};
+use crate::marching_cubes::{
+    CellData, MarchingCubesInput, MarchingCubesVariant, RelativeToThreshold,
+};
+use crate::mesh::TriMesh3d;
+use crate::progress::ProgressLogger;
+use crate::uniform_grid::DummySubdomain;
+use crate::{new_map, profile, DensityMap, Index, MapType, Real, UniformGrid};
+use rayon::prelude::*;
+
+/// Maximum number of chunks and slabs, independent of the number of threads to keep the result deterministic
+const MAX_NUM_SLABS: usize = 64;
+
+/// Triangulation of a single slab
+struct SlabMesh<I: Index, R: Real> {
+    /// Copies of the vertices referenced by the cells of the slab, followed by the additional vertices of the triangulation
+    mesh: TriMesh3d<R>,
+    /// Flat cell index of every triangle
+    triangle_cells: Vec<I>,
+    /// Index of every copied vertex in the global mesh
+    sources: Vec<usize>,
+}
+
+/// Performs a multi-threaded marching cubes triangulation of the density map, appends triangles to the given mesh
+///
+/// Results in the same mesh as the sequential triangulation up to the order of the vertices and triangles. The
+/// [`MarchingCubesVariant::SurfaceNets`] triangulation is not supported by this function.
+pub(crate) fn par_triangulate_slabs<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    density_map: &DensityMap<I, R>,
+    iso_surface_threshold: R,
+    variant: MarchingCubesVariant,
+    mesh: &mut TriMesh3d<R>,
+    triangle_cells: Option<&mut Vec<I>>,
+) -> Result<(), TriangulationError> {
+    profile!("par_triangulate_slabs");
+    assert_ne!(variant, MarchingCubesVariant::SurfaceNets);
+
+    let subdomain = DummySubdomain::new(grid);
+
+    // Only points below the threshold create vertices on the edges to their neighbors
+    let mut points = Vec::new();
+    density_map.for_each(|flat_point_index, point_value| {
+        if point_value <= iso_surface_threshold {
+            points.push((flat_point_index, point_value));
+        }
+    });
+
+    let chunks = {
+        profile!("interpolate chunk vertices");
+        let progress = ProgressLogger::start("marching cubes", "points", || points.len());
+        let chunk_size = points.len().div_ceil(MAX_NUM_SLABS).max(1);
+        points
+            .par_chunks(chunk_size)
+            .map(|points| {
+                let mut vertices = Vec::new();
+                let input = interpolate_points_to_cell_data(
+                    &subdomain,
+                    density_map,
+                    points,
+                    iso_surface_threshold,
+                    &mut vertices,
+                );
+
+                if let Some(progress) = &progress {
+                    progress.add(points.len());
+                }
+                (vertices, input)
+            })
+            .collect::<Vec<_>>()
+    };
+    drop(points);
+
+    // Append the vertices of all chunks to the mesh and merge their cells into slabs with a similar number of cells
+    let slab_inputs = {
+        profile!("merge chunks into slabs");
+        let mut cells_per_plane = vec![0; grid.cells_per_dim()[0].to_usize().unwrap()];
+        for (_, input) in &chunks {
+            for &flat_cell_index in input.cell_data.keys() {
+                cells_per_plane[first_index_of_cell(grid, flat_cell_index)] += 1;
+            }
+        }
+        let slab_of_plane = partition_planes(&cells_per_plane, MAX_NUM_SLABS);
+        let num_slabs = slab_of_plane.last().map_or(1, |&slab| slab + 1);
+
+        let mut slab_cells = (0..num_slabs)
+            .map(|_| new_map())
+            .collect::<Vec<MapType<I, CellData>>>();
+        for (vertices, input) in chunks {
+            let vertex_offset = mesh.vertices.len();
+            mesh.vertices.extend_from_slice(&vertices);
+            for (flat_cell_index, chunk_data) in input.cell_data {
+                let slab = slab_of_plane[first_index_of_cell(grid, flat_cell_index)];
+                let cell_data = slab_cells[slab].entry(flat_cell_index).or_default();
+                merge_cell_data(cell_data, &chunk_data, vertex_offset);
+            }
+        }
+        slab_cells
+    };
+
+    // Triangulate the slabs using local copies of the vertices referenced by their cells
+    let mut slab_meshes = {
+        profile!("triangulate slabs");
+        let progress = ProgressLogger::start("triangulation", "cells", || {
+            slab_inputs.iter().map(|cell_data| cell_data.len()).sum()
+        });
+        let vertices = mesh.vertices.as_slice();
+        slab_inputs
+            .into_par_iter()
+            .map(|cell_data| {
+                let num_cells = cell_data.len();
+                let mut input = MarchingCubesInput { cell_data };
+                update_cell_data_threshold_flags(
+                    &subdomain,
+                    density_map,
+                    iso_surface_threshold,
+                    true,
+                    &mut input,
+                );
+
+                let mut sources = Vec::new();
+                let mut local_indices: MapType<usize, usize> = new_map();
+                for cell_data in input.cell_data.values_mut() {
+                    for v in cell_data.iso_surface_vertices.iter_mut().flatten() {
+                        *v = *local_indices.entry(*v).or_insert_with(|| {
+                            sources.push(*v);
+                            sources.len() - 1
+                        });
+                    }
+                }
+
+                let mut slab_mesh = TriMesh3d {
+                    vertices: sources.iter().map(|&v| vertices[v]).collect(),
+                    triangles: Vec::new(),
+                };
+                let mut triangle_cells = Vec::new();
+                let corner_values =
+                    CornerValues::for_variant(variant, grid, density_map, iso_surface_threshold);
+                triangulate(
+                    input,
+                    corner_values,
+                    &mut slab_mesh,
+                    Some(&mut triangle_cells),
+                )?;
+
+                if let Some(progress) = &progress {
+                    progress.add(num_cells);
+                }
+                Ok(SlabMesh {
+                    mesh: slab_mesh,
+                    triangle_cells,
+                    sources,
+                })
+            })
+            .collect::<Result<Vec<_>, TriangulationError>>()?
+    };
+
+    {
+        profile!("concatenate slab meshes");
+
+        // Additional vertices of the triangulation (e.g. in ambiguous cells of MC33) are appended in the order of the slabs
+        let mut additional_vertex_offsets = Vec::with_capacity(slab_meshes.len());
+        let mut num_vertices = mesh.vertices.len();
+        for slab_mesh in &slab_meshes {
+            additional_vertex_offsets.push(num_vertices);
+            num_vertices += slab_mesh.mesh.vertices.len() - slab_mesh.sources.len();
+        }
+
+        slab_meshes
+            .par_iter_mut()
+            .zip(additional_vertex_offsets.par_iter())
+            .for_each(|(slab_mesh, &additional_vertex_offset)| {
+                let sources = &slab_mesh.sources;
+                let global_index = |v: usize| {
+                    sources
+                        .get(v)
+                        .copied()
+                        .unwrap_or_else(|| additional_vertex_offset + v - sources.len())
+                };
+
+                for triangle in slab_mesh.mesh.triangles.iter_mut() {
+                    *triangle = triangle.map(global_index);
+                }
+            });
+
+        let num_triangles = slab_meshes.iter().map(|m| m.mesh.triangles.len()).sum();
+        mesh.vertices.reserve(num_vertices - mesh.vertices.len());
+        mesh.triangles.reserve(num_triangles);
+        let mut triangle_cells = triangle_cells;
+        if let Some(triangle_cells) = triangle_cells.as_deref_mut() {
+            triangle_cells.reserve(num_triangles);
+        }
+
+        for slab_mesh in slab_meshes {
+            mesh.vertices
+                .extend_from_slice(&slab_mesh.mesh.vertices[slab_mesh.sources.len()..]);
+            mesh.triangles.extend_from_slice(&slab_mesh.mesh.triangles);
+            if let Some(triangle_cells) = triangle_cells.as_deref_mut() {
+                triangle_cells.extend_from_slice(&slab_mesh.triangle_cells);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Assigns every plane of cells to a slab such that the number of cells per slab is balanced, returns the slab of every plane
+///
+/// Every slab consists of at least one plane and the slab indices are increasing along the planes.
+fn partition_planes(cells_per_plane: &[usize], max_num_slabs: usize) -> Vec<usize> {
+    let total_cells: usize = cells_per_plane.iter().sum();
+    let cells_per_slab = total_cells.div_ceil(max_num_slabs).max(1);
+
+    let mut slab_of_plane = Vec::with_capacity(cells_per_plane.len());
+    let mut slab = 0;
+    let mut cells_in_slab = 0;
+    for &cells in cells_per_plane {
+        if cells_in_slab >= cells_per_slab && slab + 1 < max_num_slabs {
+            slab += 1;
+            cells_in_slab = 0;
+        }
+        slab_of_plane.push(slab);
+        cells_in_slab += cells;
+    }
+    slab_of_plane
+}
+
+/// Returns the index of the given cell along the first axis of the grid
+fn first_index_of_cell<I: Index, R: Real>(grid: &UniformGrid<I, R>, flat_cell_index: I) -> usize {
+    grid.try_unflatten_cell_index(flat_cell_index)
+        .unwrap()
+        .index()[0]
+        .to_usize()
+        .unwrap()
+}
+
+/// Adds the vertices and threshold flags of the cell data of a chunk to the cell data of a slab, the vertex indices of the chunk are shifted by the given offset
+fn merge_cell_data(cell_data: &mut CellData, chunk_data: &CellData, vertex_offset: usize) {
+    for (vertex, chunk_vertex) in cell_data
+        .iso_surface_vertices
+        .iter_mut()
+        .zip(chunk_data.iso_surface_vertices.iter())
+    {
+        if let Some(chunk_vertex) = chunk_vertex {
+            debug_assert!(vertex.is_none());
+            *vertex = Some(vertex_offset + chunk_vertex);
+        }
+    }
+
+    for (flag, chunk_flag) in cell_data
+        .corner_above_threshold
+        .iter_mut()
+        .zip(chunk_data.corner_above_threshold.iter())
+    {
+        if *chunk_flag == RelativeToThreshold::Above {
+            *flag = RelativeToThreshold::Above;
+        }
+    }
+}
+
+#[test]
+fn test_partition_planes() {
+    assert_eq!(partition_planes(&[0, 0, 0], 4), vec![0, 0, 0]);
+    assert_eq!(partition_planes(&[1, 1, 1, 1], 4), vec![0, 1, 2, 3]);
+    assert_eq!(
+        partition_planes(&[1, 1, 1, 1, 1, 1], 2),
+        vec![0, 0, 0, 1, 1, 1]
+    );
+    // Planes without cells are added to the current slab
+    assert_eq!(
+        partition_planes(&[0, 5, 0, 0, 5, 0], 2),
+        vec![0, 0, 1, 1, 1, 1]
+    );
+    assert_eq!(partition_planes(&[3, 1, 1, 1], 2), vec![0, 1, 1, 1]);
+}
@@ -0,0 +1,206 @@
+//! Snapping of iso-surface vertices that are very close to points of the background grid
+//!
+//! When the iso-surface passes very close to a grid point, marching cubes places several almost coincident vertices on
+//! the edges around this point and connects them by needle-shaped triangles. Snapping merges all vertices close to a
+//! grid point into a single vertex on the point and removes the triangles that collapse. A group of vertices is only
+//! merged if the mesh stays manifold, i.e. if the triangles around the group form a disk that is bounded by a single
+//! loop of edges. Otherwise (e.g. if two sheets of the surface touch at the grid point) the vertices are left unchanged.
+
+use crate::mesh::TriMesh3d;
+use crate::uniform_grid::PointIndex;
+use crate::{new_map, profile, Index, MapType, Real, UniformGrid};
+use nalgebra::Vector3;
+
+/// Snaps the vertices after `vertex_offset` that are close to a grid point onto this point and merges them
+///
+/// Vertices closer than `snapping` (relative to the cell size, in the maximum norm) to a grid point are merged into a
+/// single vertex on this point if the mesh stays manifold. Triangles that collapse are removed and vertices that are no
+/// longer referenced are removed from the mesh. The snapped vertices may only be referenced by the triangles after
+/// `triangle_offset`. Returns for every triangle after `triangle_offset` whether it was kept.
+pub(crate) fn snap_vertices_to_grid<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    snapping: R,
+    mesh: &mut TriMesh3d<R>,
+    vertex_offset: usize,
+    triangle_offset: usize,
+) -> Vec<bool> {
+    profile!("snap_vertices_to_grid");
+
+    let num_new_vertices = mesh.vertices.len() - vertex_offset;
+    let mut kept_triangles = vec![true; mesh.triangles.len() - triangle_offset];
+
+    // Group the vertices by the grid point they are close to, sorted by the grid point for a deterministic result
+    let mut groups: Vec<(I, Vec<usize>)> = {
+        let mut groups: MapType<I, Vec<usize>> = new_map();
+        for (v, vertex) in mesh.vertices.iter().enumerate().skip(vertex_offset) {
+            if let Some(point) = closest_grid_point(grid, vertex, snapping) {
+                groups
+                    .entry(grid.flatten_point_index(&point))
+                    .or_default()
+                    .push(v);
+            }
+        }
+        groups.into_iter().collect()
+    };
+    if groups.is_empty() {
+        return kept_triangles;
+    }
+    groups.sort_unstable_by_key(|(flat_point_index, _)| *flat_point_index);
+
+    // Group of every new vertex and all triangles adjacent to the vertices of each group
+    let mut group_of_vertex = vec![usize::MAX; num_new_vertices];
+    for (group, (_, vertices)) in groups.iter().enumerate() {
+        for &v in vertices {
+            group_of_vertex[v - vertex_offset] = group;
+        }
+    }
+    let mut adjacent_triangles = vec![Vec::new(); groups.len()];
+    for (t, triangle) in mesh.triangles.iter().enumerate().skip(triangle_offset) {
+        for &v in triangle {
+            if let Some(&group) = v
+                .checked_sub(vertex_offset)
+                .and_then(|i| group_of_vertex.get(i))
+                .filter(|&&group| group != usize::MAX)
+            {
+                adjacent_triangles[group].push(t);
+            }
+        }
+    }
+
+    // Merge every group into its first vertex, triangles with more than one vertex of the group collapse
+    let mut kept_vertices = vec![true; num_new_vertices];
+    for (group, (flat_point_index, vertices)) in groups.iter().enumerate() {
+        let in_group = |v: usize| v >= vertex_offset && group_of_vertex[v - vertex_offset] == group;
+
+        let triangles = &mut adjacent_triangles[group];
+        triangles.sort_unstable();
+        triangles.dedup();
+        triangles.retain(|&t| kept_triangles[t - triangle_offset]);
+        if !is_contractible(&mesh.triangles, triangles, in_group) {
+            continue;
+        }
+
+        let representative = vertices[0];
+        for &t in triangles.iter() {
+            let triangle = &mut mesh.triangles[t];
+            if triangle.iter().filter(|&&v| in_group(v)).count() > 1 {
+                kept_triangles[t - triangle_offset] = false;
+            } else {
+                for v in triangle.iter_mut().filter(|v| in_group(**v)) {
+                    *v = representative;
+                }
+            }
+        }
+
+        let point = grid.try_unflatten_point_index(*flat_point_index).unwrap();
+        mesh.vertices[representative] = grid.point_coordinates(&point);
+        for &v in &vertices[1..] {
+            kept_vertices[v - vertex_offset] = false;
+        }
+    }
+
+    // Remove the merged vertices and update the indices of the remaining vertices
+    let mut new_vertex_indices = Vec::with_capacity(num_new_vertices);
+    let mut next_index = vertex_offset;
+    for &kept in &kept_vertices {
+        new_vertex_indices.push(next_index);
+        if kept {
+            next_index += 1;
+        }
+    }
+    retain_appended(&mut mesh.vertices, &kept_vertices);
+    for triangle in mesh.triangles[triangle_offset..].iter_mut() {
+        for v in triangle.iter_mut().filter(|v| **v >= vertex_offset) {
+            *v = new_vertex_indices[*v - vertex_offset];
+        }
+    }
+
+    retain_appended(&mut mesh.triangles, &kept_triangles);
+    kept_triangles
+}
+
+/// Removes all values after the first `values.len() - kept.len()` values that are not marked as kept
+pub(crate) fn retain_appended<T>(values: &mut Vec<T>, kept: &[bool]) {
+    let offset = values.len() - kept.len();
+    let mut i = 0;
+    values.retain(|_| {
+        let keep = i < offset || kept[i - offset];
+        i += 1;
+        keep
+    });
+}
+
+/// Returns the closest grid point if the given vertex is within the snapping distance (relative to the cell size) of it
+fn closest_grid_point<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    vertex: &Vector3<R>,
+    snapping: R,
+) -> Option<PointIndex<I>> {
+    let relative = (vertex - grid.aabb().min()).unscale(grid.cell_size());
+    let rounded = relative.map(|x| x.round());
+    if (relative - rounded).amax() > snapping {
+        return None;
+    }
+
+    let ijk = [
+        I::from_f64(rounded.x.to_f64()?)?,
+        I::from_f64(rounded.y.to_f64()?)?,
+        I::from_f64(rounded.z.to_f64()?)?,
+    ];
+    grid.get_point(ijk)
+}
+
+/// Returns whether the given triangles around a group of vertices can be contracted into a single vertex without making the mesh non-manifold
+///
+/// This is the case if the edges opposite to the group vertex of all triangles with a single vertex of the group form a
+/// single closed loop that contains every vertex of the triangles that is not part of the group.
+fn is_contractible(
+    triangles: &[[usize; 3]],
+    adjacent_triangles: &[usize],
+    in_group: impl Fn(usize) -> bool,
+) -> bool {
+    // Directed edges of the loop around the group, every vertex can only be the start of a single edge
+    let mut loop_edges: Vec<(usize, usize)> = Vec::new();
+    for &t in adjacent_triangles {
+        let triangle = triangles[t];
+        if triangle.iter().filter(|&&v| in_group(v)).count() != 1 {
+            continue;
+        }
+
+        let k = triangle.iter().position(|&v| in_group(v)).unwrap();
+        let edge = (triangle[(k + 1) % 3], triangle[(k + 2) % 3]);
+        if loop_edges.iter().any(|&(start, _)| start == edge.0) {
+            return false;
+        }
+        loop_edges.push(edge);
+    }
+
+    // A closed loop needs at least three edges
+    if loop_edges.len() < 3 {
+        return false;
+    }
+
+    // Vertices that are only connected to the group by collapsing triangles would be left without triangles
+    let on_loop = |v: usize| loop_edges.iter().any(|&(start, _)| start == v);
+    if adjacent_triangles
+        .iter()
+        .flat_map(|&t| triangles[t])
+        .any(|v| !in_group(v) && !on_loop(v))
+    {
+        return false;
+    }
+
+    // Following the edges from any vertex has to visit all edges before returning to this vertex
+    let start = loop_edges[0].0;
+    let mut current = start;
+    for step in 1..=loop_edges.len() {
+        current = match loop_edges.iter().find(|&&(v, _)| v == current) {
+            Some(&(_, next)) => next,
+            None => return false,
+        };
+        if current == start {
+            return step == loop_edges.len();
+        }
+    }
+    false
+}
@@ -1,18 +1,25 @@
+use crate::logging::{debug, trace};
 use crate::marching_cubes::narrow_band_extraction::update_mc_input_for_stitching_domain;
 use crate::marching_cubes::triangulation::{
-    triangulate_with_criterion, DebugTriangleGenerator, TriangulationStitchingInterior,
+    triangulate_with_criterion, CornerValues, DebugTriangleGenerator,
+    TriangulationStitchingInterior,
+};
+use crate::marching_cubes::{
+    CellData, MarchingCubesError, MarchingCubesInput, MarchingCubesVariant,
 };
-use crate::marching_cubes::{CellData, MarchingCubesError, MarchingCubesInput};
 use crate::mesh::TriMesh3d;
 use crate::topology::{Axis, DirectedAxis, DirectedAxisArray, Direction};
 use crate::uniform_grid::{GridBoundaryFaceFlags, OwningSubdomainGrid, Subdomain, UniformGrid};
-use crate::{profile, Index, MapType, Real, ReconstructionError};
-use log::{debug, trace};
+use crate::{profile, Index, MapType, Real, ReconstructionError, TriangleOrigins};
 
 /// Stitches the two given surface patches by triangulating the domain between them
+///
+/// If the patches record the origins of their triangles, the new triangles are attributed to the given octree node.
 pub(crate) fn stitch_surface_patches<I: Index, R: Real>(
     iso_surface_threshold: R,
+    variant: MarchingCubesVariant,
     stitching_axis: Axis,
+    node_id: usize,
     mut negative_side: SurfacePatch<I, R>,
     mut positive_side: SurfacePatch<I, R>,
 ) -> Result<SurfacePatch<I, R>, ReconstructionError<I, R>> {
@@ -50,18 +57,22 @@ pub(crate) fn stitch_surface_patches<I: Index, R: Real>(
     );
 
     // Merge the two input meshes structures and get vertex offset for all vertices of the positive side
-    let (mut output_mesh, negative_vertex_offset, positive_vertex_offset) = {
+    let (mut output_mesh, mut output_origins, negative_vertex_offset, positive_vertex_offset) = {
         let mut negative_mesh = std::mem::take(&mut negative_side.mesh);
         let mut positive_mesh = std::mem::take(&mut positive_side.mesh);
+        let negative_origins = negative_side.origins.take();
+        let positive_origins = positive_side.origins.take();
 
         if negative_mesh.vertices.len() > positive_mesh.vertices.len() {
             let positive_vertex_offset = negative_mesh.vertices.len();
             negative_mesh.append(&mut positive_mesh);
-            (negative_mesh, None, Some(positive_vertex_offset))
+            let origins = append_origins(negative_origins, positive_origins);
+            (negative_mesh, origins, None, Some(positive_vertex_offset))
         } else {
             let negative_vertex_offset = positive_mesh.vertices.len();
             positive_mesh.append(&mut negative_mesh);
-            (positive_mesh, Some(negative_vertex_offset), None)
+            let origins = append_origins(positive_origins, negative_origins);
+            (positive_mesh, origins, Some(negative_vertex_offset), None)
         }
     };
 
@@ -97,10 +108,11 @@ pub(crate) fn stitch_surface_patches<I: Index, R: Real>(
     };
 
     // Perform marching cubes on the stitching domain
+    let boundary_density_map = boundary_density_map.into();
     let mut boundary_cell_data = {
         update_mc_input_for_stitching_domain(
             &stitching_subdomain,
-            &boundary_density_map.into(),
+            &boundary_density_map,
             iso_surface_threshold,
             stitching_axis,
             &mut output_mesh.vertices,
@@ -111,15 +123,27 @@ pub(crate) fn stitch_surface_patches<I: Index, R: Real>(
         let boundary_cell_data =
             collect_boundary_cell_data(&stitching_subdomain, &marching_cubes_input);
 
+        let corner_values = CornerValues::for_variant(
+            variant,
+            global_grid,
+            &boundary_density_map,
+            iso_surface_threshold,
+        );
         triangulate_with_criterion(
             &stitching_subdomain,
             marching_cubes_input,
+            corner_values,
             &mut output_mesh,
+            output_origins.as_mut().map(|origins| &mut origins.cells),
             TriangulationStitchingInterior::new(stitching_axis),
             DebugTriangleGenerator,
         )
         .map_err(MarchingCubesError::from)?;
 
+        if let Some(origins) = output_origins.as_mut() {
+            origins.nodes.resize(origins.cells.len(), node_id);
+        }
+
         boundary_cell_data
     };
 
@@ -211,9 +235,20 @@ pub(crate) fn stitch_surface_patches<I: Index, R: Real>(
         stitching_level: negative_side
             .stitching_level
             .max(positive_side.stitching_level),
+        origins: output_origins,
     })
 }
 
+/// Appends the triangle origins of a mesh to the origins of the mesh it was appended to, `None` if one of them is not recorded
+fn append_origins<I: Index>(
+    origins: Option<TriangleOrigins<I>>,
+    appended_origins: Option<TriangleOrigins<I>>,
+) -> Option<TriangleOrigins<I>> {
+    let (mut origins, mut appended_origins) = origins.zip(appended_origins)?;
+    origins.append(&mut appended_origins);
+    Some(origins)
+}
+
 /// A surface patch representing a local part of a larger surface reconstruction
 #[derive(Clone, Debug)]
 pub(crate) struct SurfacePatch<I: Index, R: Real> {
@@ -225,16 +260,19 @@ pub(crate) struct SurfacePatch<I: Index, R: Real> {
     pub(crate) data: DirectedAxisArray<BoundaryData<I, R>>,
     /// The maximum number of times parts of this patch where stitched together
     pub(crate) stitching_level: usize,
+    /// The origins of all triangles of the local mesh if they are recorded
+    pub(crate) origins: Option<TriangleOrigins<I>>,
 }
 
 impl<I: Index, R: Real> SurfacePatch<I, R> {
-    /// Creates an empty surface patch for the given subdomain
-    pub(crate) fn new_empty(subdomain: OwningSubdomainGrid<I, R>) -> Self {
+    /// Creates an empty surface patch for the given subdomain, optionally with (empty) recorded triangle origins
+    pub(crate) fn new_empty(subdomain: OwningSubdomainGrid<I, R>, record_origins: bool) -> Self {
         Self {
             mesh: Default::default(),
             subdomain,
             data: Default::default(),
             stitching_level: 0,
+            origins: record_origins.then(TriangleOrigins::default),
         }
     }
 }
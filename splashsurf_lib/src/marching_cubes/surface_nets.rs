@@ -0,0 +1,104 @@
+//! Dual triangulation of the narrow band cells using surface nets
+//!
+//! Instead of triangulating every cell individually, surface nets place a single vertex per cell of
+//! the narrow band and connect the vertices of the four cells around each grid edge crossing the
+//! iso-surface by a quad. This avoids the thin sliver triangles that marching cubes produces when
+//! the iso-surface passes close to a cell corner.
+
+use crate::marching_cubes::MarchingCubesInput;
+use crate::mesh::TriMesh3d;
+use crate::topology::Axis;
+use crate::{new_map, profile, Index, MapType, Real, UniformGrid};
+use nalgebra::Vector3;
+
+/// Local edges of a cell that originate in the cell's minimum corner, indexed by the axis they are parallel to
+const ORIGIN_EDGES: [usize; 3] = [0, 3, 8];
+
+/// Offsets of the four cells around an edge along the two remaining axes, ordered counter-clockwise around the edge
+const CELLS_AROUND_EDGE: [[u8; 2]; 4] = [[0, 0], [1, 0], [1, 1], [0, 1]];
+
+/// Converts the narrow band cell data into a dual surface mesh, appends the vertices and triangles to the given mesh
+///
+/// The `edge_vertices` are the interpolated iso-surface crossings referenced by the cell data. Every cell
+/// gets one vertex at the mean of the crossings on its edges. Every edge crossing the iso-surface results
+/// in a quad, which is split along its shorter diagonal into two triangles. Edges that are not surrounded by
+/// four cells of the narrow band (i.e. at the boundary of the domain) are skipped.
+///
+/// If provided, the flat index of the cell in which the edge of a quad starts is appended to `triangle_cells` for every new triangle.
+pub(crate) fn triangulate_dual<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    input: MarchingCubesInput<I>,
+    edge_vertices: &[Vector3<R>],
+    mesh: &mut TriMesh3d<R>,
+    mut triangle_cells: Option<&mut Vec<I>>,
+) {
+    profile!("surface_nets::triangulate_dual");
+
+    let MarchingCubesInput { cell_data } = input;
+
+    // Place one vertex per cell at the mean of its edge crossings
+    let mut cell_vertices: MapType<I, usize> = new_map();
+    for (&flat_cell_index, cell_data) in &cell_data {
+        let mut vertex = Vector3::zeros();
+        let mut count = 0;
+        for &edge_vertex in cell_data.iso_surface_vertices.iter().flatten() {
+            vertex += edge_vertices[edge_vertex];
+            count += 1;
+        }
+
+        if count > 0 {
+            cell_vertices.insert(flat_cell_index, mesh.vertices.len());
+            mesh.vertices
+                .push(vertex.unscale(R::from_usize(count).unwrap()));
+        }
+    }
+
+    // Connect the vertices of the cells around every edge crossing the iso-surface
+    for (&flat_cell_index, cell_data) in &cell_data {
+        let cell = grid.try_unflatten_cell_index(flat_cell_index).unwrap();
+
+        for &axis in Axis::all_possible() {
+            // Every edge is only processed by the cell in which it starts at the minimum corner
+            let dim = axis.dim();
+            if cell_data.iso_surface_vertices[ORIGIN_EDGES[dim]].is_none() {
+                continue;
+            }
+
+            let quad = CELLS_AROUND_EDGE.map(|[db, dc]| {
+                let mut ijk = *cell.index();
+                ijk[(dim + 1) % 3] = ijk[(dim + 1) % 3].checked_sub(&I::from_u8(db)?)?;
+                ijk[(dim + 2) % 3] = ijk[(dim + 2) % 3].checked_sub(&I::from_u8(dc)?)?;
+                let neighbor = grid.get_cell(ijk)?;
+                cell_vertices
+                    .get(&grid.flatten_cell_index(&neighbor))
+                    .copied()
+            });
+
+            let [Some(a), Some(b), Some(c), Some(d)] = quad else {
+                continue;
+            };
+
+            // The normal of the quad has to point from the region above the threshold to the region below
+            let [a, b, c, d] = if cell_data.corner_above_threshold[0].is_above_unchecked() {
+                [a, b, c, d]
+            } else {
+                [a, d, c, b]
+            };
+
+            let diagonal_ac = (mesh.vertices[c] - mesh.vertices[a]).norm_squared();
+            let diagonal_bd = (mesh.vertices[d] - mesh.vertices[b]).norm_squared();
+            if diagonal_ac <= diagonal_bd {
+                mesh.triangles.push([a, b, c]);
+                mesh.triangles.push([a, c, d]);
+            } else {
+                mesh.triangles.push([a, b, d]);
+                mesh.triangles.push([b, c, d]);
+            }
+        }
+
+        // The quads of all edges processed by this cell are attributed to it
+        if let Some(triangle_cells) = triangle_cells.as_deref_mut() {
+            triangle_cells.resize(mesh.triangles.len(), flat_cell_index);
+        }
+    }
+}
@@ -1,11 +1,13 @@
+use crate::logging::trace;
+use crate::marching_cubes::disambiguation::triangulate_ambiguous_cell;
 use crate::marching_cubes::marching_cubes_lut::marching_cubes_triangulation_iter;
-use crate::marching_cubes::{CellData, MarchingCubesInput};
+use crate::marching_cubes::{CellData, MarchingCubesInput, MarchingCubesVariant};
 use crate::mesh::TriMesh3d;
+use crate::progress::ProgressLogger;
 use crate::topology::Axis;
 use crate::uniform_grid::{DummySubdomain, GridBoundaryFaceFlags, Subdomain, UniformGrid};
-use crate::{profile, Index, Real};
+use crate::{profile, DensityMap, Index, Real};
 use anyhow::Context;
-use log::trace;
 use nalgebra::Vector3;
 use std::marker::PhantomData;
 use thiserror::Error as ThisError;
@@ -46,6 +48,22 @@ pub(crate) trait TriangleGenerator<I: Index, R: Real, S: Subdomain<I, R>> {
         cell_data: &CellData,
         edge_indices: [i32; 3],
     ) -> Result<[usize; 3], anyhow::Error>;
+
+    /// Returns the index of the iso-surface vertex on the given local edge of the cell
+    fn vertex(
+        &self,
+        subdomain: &S,
+        flat_cell_index: I,
+        cell_data: &CellData,
+        edge_index: i32,
+    ) -> Result<usize, anyhow::Error>;
+}
+
+/// Density values at the corners of the cells used to resolve ambiguous cells for the [`MarchingCubesVariant::Mc33`] triangulation
+pub(crate) struct CornerValues<'a, I: Index, R: Real> {
+    grid: &'a UniformGrid<I, R>,
+    density_map: &'a DensityMap<I, R>,
+    iso_surface_threshold: R,
 }
 
 /// Maps the edges indices directly to the vertex indices in the cell data
@@ -57,18 +75,24 @@ pub(crate) struct DebugTriangleGenerator;
 #[inline(never)]
 pub(crate) fn triangulate<I: Index, R: Real>(
     input: MarchingCubesInput<I>,
+    corner_values: Option<CornerValues<I, R>>,
     mesh: &mut TriMesh3d<R>,
+    triangle_cells: Option<&mut Vec<I>>,
 ) -> Result<(), TriangulationError> {
     triangulate_with_criterion(
         &DummySubdomain::new(&UniformGrid::new_zero()),
         input,
+        corner_values,
         mesh,
+        triangle_cells,
         TriangulationIdentityCriterion,
         DebugTriangleGenerator,
     )
 }
 
 /// Converts the marching cubes input cell data into a triangle surface mesh, appends triangles to existing mesh with custom criterion to filter out cells during triangulation
+///
+/// If provided, the flat index of the originating cell of every new triangle is appended to `triangle_cells`.
 #[inline(never)]
 pub(crate) fn triangulate_with_criterion<
     I: Index,
@@ -79,7 +103,9 @@ pub(crate) fn triangulate_with_criterion<
 >(
     subdomain: &S,
     input: MarchingCubesInput<I>,
+    corner_values: Option<CornerValues<I, R>>,
     mesh: &mut TriMesh3d<R>,
+    mut triangle_cells: Option<&mut Vec<I>>,
     triangulation_criterion: C,
     triangle_generator: G,
 ) -> Result<(), TriangulationError> {
@@ -95,20 +121,73 @@ pub(crate) fn triangulate_with_criterion<
     );
 
     // Triangulate affected cells
+    let progress = ProgressLogger::start("triangulation", "cells", || cell_data.len());
     for (&flat_cell_index, cell_data) in &cell_data {
+        if let Some(progress) = &progress {
+            progress.add(1);
+        }
+
         // Skip cells that don't fulfill triangulation criterion
         if !triangulation_criterion.triangulate_cell(subdomain, flat_cell_index, cell_data) {
             continue;
         }
 
-        // TODO: Replace `are_vertices_above_unchecked` with something that can return an error
-        for triangle in marching_cubes_triangulation_iter(&cell_data.are_vertices_above_unchecked())
-        {
+        let mut push_triangle = |triangle: [i32; 3]| -> Result<(), TriangulationError> {
             // TODO: Allow user to set option to skip invalid triangles?
             let global_triangle = triangle_generator
                 .triangle_connectivity(subdomain, flat_cell_index, cell_data, triangle)
                 .map_err(|e| TriangulationError::TriangleConnectivityError(e))?;
             mesh.triangles.push(global_triangle);
+            Ok(())
+        };
+
+        // Ambiguous cells are resolved using the corner values if they are required by the marching cubes variant
+        if let Some(triangulation) = corner_values.as_ref().and_then(|corner_values| {
+            triangulate_ambiguous_cell(&corner_values.relative_values(flat_cell_index))
+        }) {
+            for triangle in triangulation.triangles {
+                push_triangle(triangle)?;
+            }
+
+            for polygon in triangulation.centroid_fans {
+                let polygon_vertices = polygon
+                    .iter()
+                    .map(|&edge| {
+                        triangle_generator.vertex(
+                            subdomain,
+                            flat_cell_index,
+                            cell_data,
+                            edge as i32,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let centroid_vertex = mesh.vertices.len();
+                let centroid = polygon_vertices
+                    .iter()
+                    .map(|&v| mesh.vertices[v])
+                    .sum::<Vector3<R>>()
+                    .unscale(R::from_usize(polygon_vertices.len()).unwrap());
+                mesh.vertices.push(centroid);
+
+                for k in 0..polygon_vertices.len() {
+                    let next = polygon_vertices[(k + 1) % polygon_vertices.len()];
+                    mesh.triangles
+                        .push([centroid_vertex, polygon_vertices[k], next]);
+                }
+            }
+        } else {
+            // TODO: Replace `are_vertices_above_unchecked` with something that can return an error
+            for triangle in
+                marching_cubes_triangulation_iter(&cell_data.are_vertices_above_unchecked())
+            {
+                push_triangle(triangle)?;
+            }
+        }
+
+        // All triangles that were added for this cell originate from it
+        if let Some(triangle_cells) = triangle_cells.as_deref_mut() {
+            triangle_cells.resize(mesh.triangles.len(), flat_cell_index);
         }
     }
 
@@ -121,6 +200,41 @@ pub(crate) fn triangulate_with_criterion<
     Ok(())
 }
 
+impl<'a, I: Index, R: Real> CornerValues<'a, I, R> {
+    /// Returns the corner values of the density map if they are required by the given marching cubes variant
+    pub(crate) fn for_variant(
+        variant: MarchingCubesVariant,
+        grid: &'a UniformGrid<I, R>,
+        density_map: &'a DensityMap<I, R>,
+        iso_surface_threshold: R,
+    ) -> Option<Self> {
+        match variant {
+            MarchingCubesVariant::Classic | MarchingCubesVariant::SurfaceNets => None,
+            MarchingCubesVariant::Mc33 => Some(Self {
+                grid,
+                density_map,
+                iso_surface_threshold,
+            }),
+        }
+    }
+
+    /// Returns the values at the corners of the cell relative to the iso-surface threshold, points that are not in the density map have zero density
+    fn relative_values(&self, flat_cell_index: I) -> [R; 8] {
+        let cell = self
+            .grid
+            .try_unflatten_cell_index(flat_cell_index)
+            .expect("Cell is not part of the grid");
+        [0, 1, 2, 3, 4, 5, 6, 7].map(|local_point_index| {
+            let point = cell.global_point_index_of(local_point_index).unwrap();
+            let value = self
+                .density_map
+                .get(self.grid.flatten_point_index(&point))
+                .unwrap_or_else(R::zero);
+            value - self.iso_surface_threshold
+        })
+    }
+}
+
 /// Forwards to the wrapped triangulation criterion but first makes some assertions on the cell data
 struct DebugTriangulationCriterion<
     I: Index,
@@ -242,6 +356,17 @@ impl<I: Index, R: Real, S: Subdomain<I, R>> TriangleGenerator<I, R, S>
         //  If this happens, it's a bug in the cell data map generation.
         get_triangle(cell_data, edge_indices)
     }
+
+    #[inline(always)]
+    fn vertex(
+        &self,
+        _subdomain: &S,
+        _flat_cell_index: I,
+        cell_data: &CellData,
+        edge_index: i32,
+    ) -> Result<usize, anyhow::Error> {
+        get_vertex(cell_data, edge_index)
+    }
 }
 
 impl<I: Index, R: Real, S: Subdomain<I, R>> TriangleGenerator<I, R, S> for DebugTriangleGenerator {
@@ -256,6 +381,18 @@ impl<I: Index, R: Real, S: Subdomain<I, R>> TriangleGenerator<I, R, S> for Debug
         get_triangle(cell_data, edge_indices)
             .with_context(|| cell_debug_string(subdomain, flat_cell_index, cell_data))
     }
+
+    #[inline(always)]
+    fn vertex(
+        &self,
+        subdomain: &S,
+        flat_cell_index: I,
+        cell_data: &CellData,
+        edge_index: i32,
+    ) -> Result<usize, anyhow::Error> {
+        get_vertex(cell_data, edge_index)
+            .with_context(|| cell_debug_string(subdomain, flat_cell_index, cell_data))
+    }
 }
 
 /// Helper function that extracts vertex indices from the [`CellData`] for the triangle with the given edge indices
@@ -263,39 +400,26 @@ fn get_triangle(cell_data: &CellData, edge_indices: [i32; 3]) -> Result<[usize;
     let [edge_idx_0, edge_idx_1, edge_idx_2] = edge_indices;
 
     Ok([
-        cell_data
-            .iso_surface_vertices
-            .get(edge_idx_0 as usize)
-            .with_context(|| "Invalid edge index. This is a bug.")?
-            .with_context(|| {
-                format!(
-                    "Missing iso surface vertex at edge {}. This is a bug.",
-                    edge_idx_0
-                )
-            })?,
-        cell_data
-            .iso_surface_vertices
-            .get(edge_idx_1 as usize)
-            .with_context(|| "Invalid edge index. This is a bug.")?
-            .with_context(|| {
-                format!(
-                    "Missing iso surface vertex at edge {}. This is a bug.",
-                    edge_idx_1
-                )
-            })?,
-        cell_data
-            .iso_surface_vertices
-            .get(edge_idx_2 as usize)
-            .with_context(|| "Invalid edge index. This is a bug.")?
-            .with_context(|| {
-                format!(
-                    "Missing iso surface vertex at edge {}. This is a bug.",
-                    edge_idx_2
-                )
-            })?,
+        get_vertex(cell_data, edge_idx_0)?,
+        get_vertex(cell_data, edge_idx_1)?,
+        get_vertex(cell_data, edge_idx_2)?,
     ])
 }
 
+/// Helper function that extracts the vertex index from the [`CellData`] for the given edge index
+fn get_vertex(cell_data: &CellData, edge_index: i32) -> Result<usize, anyhow::Error> {
+    cell_data
+        .iso_surface_vertices
+        .get(edge_index as usize)
+        .with_context(|| "Invalid edge index. This is a bug.")?
+        .with_context(|| {
+            format!(
+                "Missing iso surface vertex at edge {}. This is a bug.",
+                edge_index
+            )
+        })
+}
+
 /// Helper function that returns a formatted string to debug triangulation failures
 fn cell_debug_string<I: Index, R: Real, S: Subdomain<I, R>>(
     subdomain: &S,
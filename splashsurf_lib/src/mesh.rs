@@ -6,9 +6,23 @@
 //!  - [`HexMesh3d`]
 //!  - [`PointCloud3d`]
 //!
+//! The [`MixedTriQuadMesh3d`] with triangle and quadrilateral cells is obtained from a triangle mesh by merging flat pairs of triangles, see [`TriMesh3d::to_tri_quad`].
+//!
+//! Additionally, the [`LineMesh2d`] type represents the line segments of contours in two dimensional spaces.
+//!
+//! The [`Connectivity`] of a triangle mesh provides the adjacency of its vertices and triangles to the mesh processing algorithms.
+//! The [`HalfEdgeMesh`] represents manifold triangle meshes for traversal of one-rings and boundaries and local edge flips and collapses.
+//!
 //! Furthermore, it provides the [`MeshWithData`] type that is used when additional attributes are
 //! attached to the vertices (e.g. normals) or cells (e.g. some identifiers) of the mesh.
 //!
+//! The [`check`] module provides a report of topological problems of triangle meshes (e.g. non-manifold edges), the
+//! [`smoothing`] module provides post-processing of triangle meshes to reduce the bumpiness of a reconstructed surface,
+//! the [`decimation`] module reduces the number of triangles of a mesh and the [`clipping`] module cuts meshes to a box.
+//! The [`curvature`] module estimates the mean and Gaussian curvature at the vertices of triangle meshes and the
+//! [`distance`] module compares two triangle meshes by point-sampled Hausdorff, mean and RMS distances.
+//! The [`vertex_cache`] module reorders triangle meshes to improve the vertex cache efficiency of GPUs for rendering.
+//!
 //! If the `vtk_extras` feature is enabled, this module also provides features for conversion of these
 //! meshes to [`vtkio`](https://docs.rs/vtkio/0.6.*/vtkio/index.html) data structures. For example:
 //!  - [`MeshWithData::to_unstructured_grid`] to convert a mesh together with all attached attributes
@@ -16,20 +30,48 @@
 //!  - `From<T> for UnstructuredGridPiece` implementations for the basic mesh types
 //!  - `Into<DataSet>` implementations for the basic mesh types
 
-use crate::{new_map, Real};
+use crate::neighborhood_search::{
+    ParticleNeighborhoodProvider, SpatialHashingNeighborhoodProvider,
+};
+use crate::{new_map, profile, transform, AxisAlignedBoundingBox3d, Real};
 use bytemuck_derive::{Pod, Zeroable};
-use nalgebra::{Unit, Vector3};
+use nalgebra::{Isometry3, Vector2, Vector3};
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
+#[cfg(feature = "parallel")]
 use std::cell::RefCell;
 use std::fmt::Debug;
+use thiserror::Error as ThisError;
+#[cfg(feature = "parallel")]
 use thread_local::ThreadLocal;
 #[cfg(feature = "vtk_extras")]
 use vtkio::model::{Attribute, DataSet, UnstructuredGridPiece};
 
+pub mod check;
+pub mod clipping;
+pub mod connectivity;
+pub mod curvature;
+pub mod decimation;
+pub mod distance;
+pub mod half_edge;
+pub mod smoothing;
+pub mod vertex_cache;
+
+pub use connectivity::Connectivity;
+pub use half_edge::HalfEdgeMesh;
+
 // TODO: Rename/restructure VTK helper implementations
 
 /// A named attribute with data that can be attached to the vertices or cells of a mesh
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "R: serde::Serialize",
+        deserialize = "R: serde::de::DeserializeOwned"
+    ))
+)]
 pub struct MeshAttribute<R: Real> {
     /// Name of the attribute
     pub name: String,
@@ -41,14 +83,35 @@ pub struct MeshAttribute<R: Real> {
 ///
 /// One value in the data-set corresponds is associated to a point or cell of the mesh.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "R: serde::Serialize",
+        deserialize = "R: serde::de::DeserializeOwned"
+    ))
+)]
 pub enum AttributeData<R: Real> {
     ScalarU64(Vec<u64>),
     ScalarReal(Vec<R>),
     Vector3Real(Vec<Vector3<R>>),
+    /// Values with an arbitrary number of components (e.g. 2D vectors or tensors) stored consecutively in a flat array
+    VectorReal {
+        num_components: usize,
+        values: Vec<R>,
+    },
 }
 
 /// A triangle (surface) mesh in 3D
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "R: serde::Serialize",
+        deserialize = "R: serde::de::DeserializeOwned"
+    ))
+)]
 pub struct TriMesh3d<R: Real> {
     /// Coordinates of all vertices of the mesh
     pub vertices: Vec<Vector3<R>>,
@@ -65,6 +128,24 @@ pub struct HexMesh3d<R: Real> {
     pub cells: Vec<[usize; 8]>,
 }
 
+/// A surface mesh in 3D consisting of triangles and quadrilaterals, see [`TriMesh3d::to_tri_quad`]
+#[derive(Clone, Debug, Default)]
+pub struct MixedTriQuadMesh3d<R: Real> {
+    /// Coordinates of all vertices of the mesh
+    pub vertices: Vec<Vector3<R>>,
+    /// The triangle and quadrilateral cells of the mesh identified by their vertex indices
+    pub cells: Vec<TriangleOrQuadCell>,
+}
+
+/// A line segment mesh in 2D, e.g. the contour lines resulting from a 2D reconstruction
+#[derive(Clone, Debug, Default)]
+pub struct LineMesh2d<R: Real> {
+    /// Coordinates of all vertices of the mesh
+    pub vertices: Vec<Vector2<R>>,
+    /// The line segments of the mesh identified by their vertex indices
+    pub lines: Vec<[usize; 2]>,
+}
+
 /// A point cloud in 3D
 #[derive(Clone, Debug, Default)]
 pub struct PointCloud3d<R: Real> {
@@ -98,8 +179,12 @@ pub trait Mesh3d<R: Real> {
 
 /// Basic interface for mesh cells consisting of a collection of vertex indices
 pub trait CellConnectivity {
-    /// Returns the number of vertices per cell
+    /// Returns the number of vertices per cell (the maximum number for cells with a varying number of vertices)
     fn num_vertices() -> usize;
+    /// Returns the number of vertices of this cell, only differs from [`Self::num_vertices`] for cells with a varying number of vertices
+    fn num_cell_vertices(&self) -> usize {
+        Self::num_vertices()
+    }
     /// Calls the given closure with each vertex index that is part of this cell, stopping at the first error and returning that error
     fn try_for_each_vertex<E, F: FnMut(usize) -> Result<(), E>>(&self, f: F) -> Result<(), E>;
     /// Calls the given closure with each vertex index that is part of this cell
@@ -124,6 +209,24 @@ pub struct HexCell(pub [usize; 8]);
 #[derive(Copy, Clone, Pod, Zeroable)]
 #[repr(transparent)]
 pub struct PointCell(pub usize);
+/// Cell type for the [`MixedTriQuadMesh3d`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TriangleOrQuadCell {
+    /// A triangle identified by its vertex indices
+    Tri([usize; 3]),
+    /// A quadrilateral identified by its vertex indices, in the same orientation as the triangles
+    Quad([usize; 4]),
+}
+
+impl TriangleOrQuadCell {
+    /// Returns the vertex indices of the cell
+    pub fn vertices(&self) -> &[usize] {
+        match self {
+            TriangleOrQuadCell::Tri(tri) => tri,
+            TriangleOrQuadCell::Quad(quad) => quad,
+        }
+    }
+}
 
 impl CellConnectivity for TriangleCell {
     fn num_vertices() -> usize {
@@ -155,6 +258,20 @@ impl CellConnectivity for PointCell {
     }
 }
 
+impl CellConnectivity for TriangleOrQuadCell {
+    fn num_vertices() -> usize {
+        4
+    }
+
+    fn num_cell_vertices(&self) -> usize {
+        self.vertices().len()
+    }
+
+    fn try_for_each_vertex<E, F: FnMut(usize) -> Result<(), E>>(&self, f: F) -> Result<(), E> {
+        self.vertices().iter().copied().try_for_each(f)
+    }
+}
+
 impl<R: Real> Mesh3d<R> for TriMesh3d<R> {
     type Cell = TriangleCell;
 
@@ -191,7 +308,236 @@ impl<R: Real> Mesh3d<R> for PointCloud3d<R> {
     }
 }
 
+impl<R: Real> Mesh3d<R> for MixedTriQuadMesh3d<R> {
+    type Cell = TriangleOrQuadCell;
+
+    fn vertices(&self) -> &[Vector3<R>] {
+        self.vertices.as_slice()
+    }
+
+    fn cells(&self) -> &[TriangleOrQuadCell] {
+        self.cells.as_slice()
+    }
+}
+
+/// Number of connected components and triangles that were removed by [`TriMesh3d::remove_small_components`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RemovedComponents {
+    /// Number of removed connected components
+    pub components: usize,
+    /// Total number of triangles of the removed components
+    pub triangles: usize,
+}
+
+/// Number of vertices and triangles that were removed by [`TriMesh3d::weld_vertices`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WeldedVertices {
+    /// Number of vertices that were merged into another vertex
+    pub merged_vertices: usize,
+    /// Number of triangles that were removed because they became degenerate after merging
+    pub degenerate_triangles: usize,
+}
+
+/// Number of triangles and vertices that were removed by [`TriMesh3d::remove_degenerate_triangles`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RemovedDegenerateTriangles {
+    /// Number of removed triangles that refer to the same vertex more than once
+    pub repeated_vertices: usize,
+    /// Number of removed triangles with three distinct vertices but an area less than or equal to the area epsilon
+    pub small_area: usize,
+    /// Number of vertices that were removed because they were only referenced by removed triangles
+    pub unreferenced_vertices: usize,
+}
+
+impl RemovedDegenerateTriangles {
+    /// Returns the total number of removed triangles
+    pub fn triangles(&self) -> usize {
+        self.repeated_vertices + self.small_area
+    }
+
+    /// Returns whether the mesh was not modified
+    pub fn is_empty(&self) -> bool {
+        self.triangles() == 0
+    }
+}
+
+/// Error type for the construction of a [`TriMesh3d`] from existing buffers
+#[rustfmt::skip]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, ThisError)]
+pub enum MeshError {
+    /// A triangle references a vertex index that is not smaller than the number of vertices
+    #[error("triangle {triangle} references vertex {vertex_index} but the mesh only has {num_vertices} vertices")]
+    VertexIndexOutOfRange {
+        /// Index of the offending triangle
+        triangle: usize,
+        /// The out of range vertex index referenced by the triangle
+        vertex_index: usize,
+        /// Number of vertices of the mesh
+        num_vertices: usize,
+    },
+}
+
+/// Error type of [`MeshWithData::append`] if the attributes of the two meshes do not match
+#[rustfmt::skip]
+#[derive(Clone, Eq, PartialEq, Debug, ThisError)]
+pub enum MeshAppendError {
+    /// A point attribute exists only in one of the two meshes
+    #[error("point attribute \"{0}\" exists only in one of the meshes")]
+    PointAttributeMissing(String),
+    /// A cell attribute exists only in one of the two meshes
+    #[error("cell attribute \"{0}\" exists only in one of the meshes")]
+    CellAttributeMissing(String),
+    /// A point attribute has different types (or numbers of components) in the two meshes
+    #[error("point attribute \"{0}\" has different types in the two meshes")]
+    PointAttributeTypeMismatch(String),
+    /// A cell attribute has different types (or numbers of components) in the two meshes
+    #[error("cell attribute \"{0}\" has different types in the two meshes")]
+    CellAttributeTypeMismatch(String),
+}
+
+impl MeshAppendError {
+    /// Returns the name of the attribute that does not match
+    pub fn attribute_name(&self) -> &str {
+        match self {
+            MeshAppendError::PointAttributeMissing(name)
+            | MeshAppendError::CellAttributeMissing(name)
+            | MeshAppendError::PointAttributeTypeMismatch(name)
+            | MeshAppendError::CellAttributeTypeMismatch(name) => name,
+        }
+    }
+}
+
 impl<R: Real> TriMesh3d<R> {
+    /// Constructs a mesh from existing vertex and triangle buffers without copying them
+    ///
+    /// Returns an error with the index of the first triangle that references a vertex that does not exist.
+    pub fn from_buffers(
+        vertices: Vec<Vector3<R>>,
+        triangles: Vec<[usize; 3]>,
+    ) -> Result<Self, MeshError> {
+        let num_vertices = vertices.len();
+        for (triangle, tri) in triangles.iter().enumerate() {
+            if let Some(&vertex_index) = tri.iter().find(|&&v| v >= num_vertices) {
+                return Err(MeshError::VertexIndexOutOfRange {
+                    triangle,
+                    vertex_index,
+                    num_vertices,
+                });
+            }
+        }
+
+        Ok(Self {
+            vertices,
+            triangles,
+        })
+    }
+
+    /// Constructs a mesh from existing vertex and triangle buffers without copying or validating them
+    ///
+    /// # Safety
+    ///
+    /// All vertex indices referenced by the triangles have to be smaller than the number of vertices.
+    /// The methods of the mesh and the functions operating on it rely on this invariant and may
+    /// panic or produce meaningless results if it is violated.
+    pub unsafe fn from_buffers_unchecked(
+        vertices: Vec<Vector3<R>>,
+        triangles: Vec<[usize; 3]>,
+    ) -> Self {
+        Self {
+            vertices,
+            triangles,
+        }
+    }
+
+    /// Consumes the mesh and returns its vertex and triangle buffers without copying them
+    pub fn into_buffers(self) -> (Vec<Vector3<R>>, Vec<[usize; 3]>) {
+        (self.vertices, self.triangles)
+    }
+
+    /// Constructs an icosphere with the given center and radius by recursively subdividing the faces of an icosahedron
+    ///
+    /// Every subdivision splits each triangle into four triangles. Therefore, the resulting mesh
+    /// has `20·4^n` triangles and `10·4^n + 2` vertices for `n` subdivisions.
+    /// The triangles are oriented such that their normals point outwards.
+    pub fn icosphere(center: &Vector3<R>, radius: R, subdivisions: usize) -> Self {
+        let t = (1.0 + 5.0f64.sqrt()) / 2.0;
+        let mut vertices: Vec<Vector3<f64>> = vec![
+            Vector3::new(-1.0, t, 0.0),
+            Vector3::new(1.0, t, 0.0),
+            Vector3::new(-1.0, -t, 0.0),
+            Vector3::new(1.0, -t, 0.0),
+            Vector3::new(0.0, -1.0, t),
+            Vector3::new(0.0, 1.0, t),
+            Vector3::new(0.0, -1.0, -t),
+            Vector3::new(0.0, 1.0, -t),
+            Vector3::new(t, 0.0, -1.0),
+            Vector3::new(t, 0.0, 1.0),
+            Vector3::new(-t, 0.0, -1.0),
+            Vector3::new(-t, 0.0, 1.0),
+        ];
+        vertices.iter_mut().for_each(|v| {
+            v.normalize_mut();
+        });
+
+        let mut triangles = vec![
+            [0, 11, 5],
+            [0, 5, 1],
+            [0, 1, 7],
+            [0, 7, 10],
+            [0, 10, 11],
+            [1, 5, 9],
+            [5, 11, 4],
+            [11, 10, 2],
+            [10, 7, 6],
+            [7, 1, 8],
+            [3, 9, 4],
+            [3, 4, 2],
+            [3, 2, 6],
+            [3, 6, 8],
+            [3, 8, 9],
+            [4, 9, 5],
+            [2, 4, 11],
+            [6, 2, 10],
+            [8, 6, 7],
+            [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            // Maps each edge to the index of its midpoint vertex, ensures that vertices are shared between adjacent triangles
+            let mut midpoints = new_map();
+            let mut midpoint = |a: usize, b: usize, vertices: &mut Vec<Vector3<f64>>| -> usize {
+                let edge = if a < b { (a, b) } else { (b, a) };
+                *midpoints.entry(edge).or_insert_with(|| {
+                    vertices.push((vertices[a] + vertices[b]).normalize());
+                    vertices.len() - 1
+                })
+            };
+
+            let mut subdivided_triangles = Vec::with_capacity(4 * triangles.len());
+            for &[v0, v1, v2] in triangles.iter() {
+                let m01 = midpoint(v0, v1, &mut vertices);
+                let m12 = midpoint(v1, v2, &mut vertices);
+                let m20 = midpoint(v2, v0, &mut vertices);
+
+                subdivided_triangles.push([v0, m01, m20]);
+                subdivided_triangles.push([v1, m12, m01]);
+                subdivided_triangles.push([v2, m20, m12]);
+                subdivided_triangles.push([m01, m12, m20]);
+            }
+            triangles = subdivided_triangles;
+        }
+
+        let vertices = vertices
+            .into_iter()
+            .map(|v| center + v.map(|x| R::from_f64(x).unwrap()) * radius)
+            .collect();
+
+        Self {
+            vertices,
+            triangles,
+        }
+    }
+
     /// Clears the vertex and triangle storage, preserves allocated memory
     pub fn clear(&mut self) {
         self.vertices.clear();
@@ -223,6 +569,162 @@ impl<R: Real> TriMesh3d<R> {
         }
     }
 
+    /// Returns the number of degenerate triangles of the mesh, i.e. triangles with zero area (e.g. due to coinciding vertices)
+    pub fn count_degenerate_triangles(&self) -> usize {
+        self.count_degenerate_triangles_from(0)
+    }
+
+    /// Returns the number of degenerate triangles starting at the given triangle index, e.g. of the triangles appended to a mesh
+    pub(crate) fn count_degenerate_triangles_from(&self, first_triangle: usize) -> usize {
+        self.triangles[first_triangle..]
+            .iter()
+            .filter(|tri| {
+                let v0 = &self.vertices[tri[0]];
+                let v1 = &self.vertices[tri[1]];
+                let v2 = &self.vertices[tri[2]];
+                (v1 - v0).cross(&(v2 - v0)).norm_squared() == R::zero()
+            })
+            .count()
+    }
+
+    /// Returns the total area of all triangles of the mesh
+    pub fn area(&self) -> R {
+        self.triangles
+            .iter()
+            .map(|tri| self.triangle_area_doubled(tri))
+            .fold(R::zero(), |acc, area| acc + area)
+            * R::from_f64(0.5).unwrap()
+    }
+
+    /// Returns the total area of all triangles of the mesh (parallelized version)
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+    pub fn par_area(&self) -> R {
+        self.triangles
+            .par_iter()
+            .map(|tri| self.triangle_area_doubled(tri))
+            .reduce(R::zero, |acc, area| acc + area)
+            * R::from_f64(0.5).unwrap()
+    }
+
+    /// Returns the signed volume enclosed by the mesh, computed with the divergence theorem
+    ///
+    /// The volume is positive for closed meshes with outward oriented triangles (counter-clockwise vertex order
+    /// seen from outside) like the reconstructed surfaces and negative for inward oriented triangles. For meshes
+    /// that are not closed (e.g. surfaces cut by a region of interest), the result depends on the origin,
+    /// see [`Self::enclosed_volume`] for a checked version.
+    pub fn volume(&self) -> R {
+        self.triangles
+            .iter()
+            .map(|tri| self.signed_tetrahedron_volume_times_six(tri))
+            .fold(R::zero(), |acc, volume| acc + volume)
+            / R::from_f64(6.0).unwrap()
+    }
+
+    /// Returns the signed volume enclosed by the mesh (parallelized version), see [`Self::volume`]
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+    pub fn par_volume(&self) -> R {
+        self.triangles
+            .par_iter()
+            .map(|tri| self.signed_tetrahedron_volume_times_six(tri))
+            .reduce(R::zero, |acc, volume| acc + volume)
+            / R::from_f64(6.0).unwrap()
+    }
+
+    /// Returns the signed volume enclosed by the mesh if it is closed, i.e. if it has no boundary edges, otherwise `None`
+    ///
+    /// See [`Self::volume`] for the sign of the volume and [`Self::find_boundary_edges`] for the definition of boundary edges.
+    pub fn enclosed_volume(&self) -> Option<R> {
+        self.find_boundary_edges().is_empty().then(|| self.volume())
+    }
+
+    /// Returns the signed volume enclosed by the mesh if it is closed, otherwise `None` (parallelized version), see [`Self::enclosed_volume`]
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+    pub fn par_enclosed_volume(&self) -> Option<R> {
+        self.find_boundary_edges()
+            .is_empty()
+            .then(|| self.par_volume())
+    }
+
+    /// Returns whether the closed components of the mesh are dominantly oriented outward, `None` if this cannot be determined
+    ///
+    /// The signed volume (see [`Self::volume`]) of every closed connected component (see [`Self::connected_components`]) is
+    /// positive if its triangles are oriented outward and negative if they are oriented inward. The dominant orientation
+    /// is the one with the larger total volume of its components. For example, the surface of a bubble inside of a fluid
+    /// encloses a negative volume if the mesh is oriented outward but does not change the result as it is enclosed by
+    /// the larger outer surface. The triangles of every component are assumed to be oriented consistently, see
+    /// [`Self::orient_consistently`].
+    ///
+    /// Components with boundary edges (see [`Self::find_boundary_edges`]) do not enclose a well-defined volume and are
+    /// ignored. Returns `None` if the mesh has no closed components or the volumes of both orientations are equal.
+    pub fn is_outward_oriented(&self) -> Option<bool> {
+        profile!("is_outward_oriented");
+
+        let (component_ids, num_components) = self.connected_components();
+        let mut is_closed = vec![true; num_components];
+        for (_, tri_idx, _) in self.find_boundary_edges() {
+            is_closed[component_ids[tri_idx]] = false;
+        }
+
+        // The volumes are computed relative to a vertex of every component to reduce cancellation for meshes far from the origin
+        let mut reference_points = vec![None; num_components];
+        let mut volumes = vec![R::zero(); num_components];
+        for (tri, &component) in self.triangles.iter().zip(component_ids.iter()) {
+            if is_closed[component] {
+                let p = *reference_points[component].get_or_insert(self.vertices[tri[0]]);
+                let [v0, v1, v2] = tri.map(|v| self.vertices[v] - p);
+                volumes[component] += v0.dot(&v1.cross(&v2));
+            }
+        }
+
+        let mut outward_volume = R::zero();
+        let mut inward_volume = R::zero();
+        for (&volume, _) in volumes
+            .iter()
+            .zip(is_closed.iter())
+            .filter(|(_, &closed)| closed)
+        {
+            if volume > R::zero() {
+                outward_volume += volume;
+            } else {
+                inward_volume -= volume;
+            }
+        }
+
+        if outward_volume > inward_volume {
+            Some(true)
+        } else if inward_volume > outward_volume {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Reverses the orientation of all triangles by swapping two of their vertices, i.e. flips the direction of their normals
+    pub fn flip_orientation(&mut self) {
+        for tri in self.triangles.iter_mut() {
+            tri.swap(1, 2);
+        }
+    }
+
+    /// Returns twice the area of the given triangle
+    fn triangle_area_doubled(&self, tri: &[usize; 3]) -> R {
+        let v0 = &self.vertices[tri[0]];
+        let v1 = &self.vertices[tri[1]];
+        let v2 = &self.vertices[tri[2]];
+        (v1 - v0).cross(&(v2 - v0)).norm()
+    }
+
+    /// Returns six times the signed volume of the tetrahedron formed by the given triangle and the origin
+    fn signed_tetrahedron_volume_times_six(&self, tri: &[usize; 3]) -> R {
+        let v0 = &self.vertices[tri[0]];
+        let v1 = &self.vertices[tri[1]];
+        let v2 = &self.vertices[tri[2]];
+        v0.dot(&v1.cross(v2))
+    }
+
     /// Same as [`Self::vertex_normal_directions_inplace`] but assumes that the output is already zeroed
     fn vertex_normal_directions_inplace_assume_zeroed(&self, normal_directions: &mut [Vector3<R>]) {
         assert_eq!(normal_directions.len(), self.vertices.len());
@@ -240,6 +742,7 @@ impl<R: Real> TriMesh3d<R> {
     }
 
     /// Same as [`Self::vertex_normal_directions_inplace`] but assumes that the output is already zeroed
+    #[cfg(feature = "parallel")]
     fn par_vertex_normal_directions_inplace_assume_zeroed(
         &self,
         normal_directions: &mut [Vector3<R>],
@@ -310,78 +813,76 @@ impl<R: Real> TriMesh3d<R> {
     }
 
     /// Same as [`Self::vertex_normals_inplace`] but assumes that the output is already zeroed
-    fn vertex_normals_inplace_assume_zeroed<'a>(&self, normals: &'a mut [Unit<Vector3<R>>]) {
+    fn vertex_normals_inplace_assume_zeroed(&self, normals: &mut [Vector3<R>]) {
         assert_eq!(normals.len(), self.vertices.len());
 
         // First, compute the directions of the normals...
-        {
-            let normal_directions =
-                bytemuck::cast_slice_mut::<Unit<Vector3<R>>, Vector3<R>>(normals);
-            self.vertex_normal_directions_inplace_assume_zeroed(normal_directions);
-        }
+        self.vertex_normal_directions_inplace_assume_zeroed(normals);
 
         // ...then actually normalize them.
         for normal in normals.iter_mut() {
-            let norm = (normal.x * normal.x + normal.y * normal.y + normal.z * normal.z).sqrt();
-            *normal.as_mut_unchecked() /= norm;
-            //normal.renormalize();
+            normalize_or_zero(normal);
         }
     }
 
     /// Same as [`Self::par_vertex_normals_inplace`] but assumes that the output is already zeroed
-    fn par_vertex_normals_inplace_assume_zeroed<'a>(&self, normals: &'a mut [Unit<Vector3<R>>]) {
+    #[cfg(feature = "parallel")]
+    fn par_vertex_normals_inplace_assume_zeroed(&self, normals: &mut [Vector3<R>]) {
         assert_eq!(normals.len(), self.vertices.len());
 
         // First, compute the directions of the normals...
-        {
-            let normal_directions =
-                bytemuck::cast_slice_mut::<Unit<Vector3<R>>, Vector3<R>>(normals);
-            self.par_vertex_normal_directions_inplace_assume_zeroed(normal_directions);
-        }
+        self.par_vertex_normal_directions_inplace_assume_zeroed(normals);
 
         // ...then actually normalize them.
         normals.par_chunks_mut(256).for_each(|normal_chunk| {
             for normal in normal_chunk.iter_mut() {
-                let norm = (normal.x * normal.x + normal.y * normal.y + normal.z * normal.z).sqrt();
-                *normal.as_mut_unchecked() /= norm;
-                //normal.renormalize();
+                normalize_or_zero(normal);
             }
         });
     }
 
     /// Computes the mesh's vertex normals inplace using an area weighted average of the adjacent triangle faces
     ///
+    /// Degenerate triangles (with zero area) do not contribute to the normals. Vertices without adjacent triangles
+    /// of non-zero area get a zero vector instead of a unit vector, so the normals are not stored as [`nalgebra::Unit`].
+    ///
     /// The method will panic if the length of the output slice is different from the number of vertices of the mesh.
     ///
     /// The method does not make any assumptions about the values in the output slice.
-    pub fn vertex_normals_inplace(&self, normals: &mut [Unit<Vector3<R>>]) {
+    pub fn vertex_normals_inplace(&self, normals: &mut [Vector3<R>]) {
         assert_eq!(normals.len(), self.vertices.len());
 
         for normal in normals.iter_mut() {
-            normal.as_mut_unchecked().fill(R::zero());
+            normal.fill(R::zero());
         }
 
         self.vertex_normals_inplace_assume_zeroed(normals);
     }
 
     /// Computes the mesh's vertex normals using an area weighted average of the adjacent triangle faces
-    pub fn vertex_normals(&self) -> Vec<Unit<Vector3<R>>> {
-        let mut normals = vec![Unit::new_unchecked(Vector3::zeros()); self.vertices.len()];
+    ///
+    /// Vertices without adjacent triangles of non-zero area get a zero vector, see [`Self::vertex_normals_inplace`].
+    pub fn vertex_normals(&self) -> Vec<Vector3<R>> {
+        let mut normals = vec![Vector3::zeros(); self.vertices.len()];
         self.vertex_normals_inplace_assume_zeroed(normals.as_mut_slice());
         normals
     }
 
     /// Computes the mesh's vertex normals inplace using an area weighted average of the adjacent triangle faces (parallelized version)
     ///
+    /// Vertices without adjacent triangles of non-zero area get a zero vector, see [`Self::vertex_normals_inplace`].
+    ///
     /// The method will panic if the length of the output slice is different from the number of vertices of the mesh.
     ///
     /// The method does not make any assumptions about the values in the output slice.
-    pub fn par_vertex_normals_inplace(&self, normals: &mut [Unit<Vector3<R>>]) {
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+    pub fn par_vertex_normals_inplace(&self, normals: &mut [Vector3<R>]) {
         assert_eq!(normals.len(), self.vertices.len());
 
         normals.par_chunks_mut(256).for_each(|normal_chunk| {
             for normal in normal_chunk {
-                normal.as_mut_unchecked().fill(R::zero())
+                normal.fill(R::zero())
             }
         });
 
@@ -389,8 +890,12 @@ impl<R: Real> TriMesh3d<R> {
     }
 
     /// Computes the mesh's vertex normals using an area weighted average of the adjacent triangle faces (parallelized version)
-    pub fn par_vertex_normals(&self) -> Vec<Unit<Vector3<R>>> {
-        let mut normals = vec![Unit::new_unchecked(Vector3::zeros()); self.vertices.len()];
+    ///
+    /// Vertices without adjacent triangles of non-zero area get a zero vector, see [`Self::vertex_normals_inplace`].
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+    pub fn par_vertex_normals(&self) -> Vec<Vector3<R>> {
+        let mut normals = vec![Vector3::zeros(); self.vertices.len()];
         self.par_vertex_normals_inplace_assume_zeroed(normals.as_mut_slice());
         normals
     }
@@ -447,132 +952,1771 @@ impl<R: Real> TriMesh3d<R> {
             .map(move |(edge_idx, _)| edge_info[edge_idx].clone())
             .collect()
     }
-}
-
-#[test]
-fn test_find_boundary() {
-    // TODO: Needs a test with a real mesh
-    let mesh = TriMesh3d::<f64> {
-        vertices: vec![
-            Vector3::new_random(),
-            Vector3::new_random(),
-            Vector3::new_random(),
-        ],
-        triangles: vec![[0, 1, 2]],
-    };
 
-    let mut boundary = mesh.find_boundary_edges();
-    boundary.sort_unstable();
+    /// Splits the mesh into one mesh per connected component, see [`Self::connected_components`]
+    ///
+    /// The meshes are returned in the order of the component ids. Vertices that are not referenced
+    /// by a triangle are not part of any of the meshes, the relative order of the vertices and triangles is preserved.
+    pub fn split_components(&self) -> Vec<TriMesh3d<R>> {
+        let (component_ids, num_components) = self.connected_components();
+        let mut components = vec![TriMesh3d::default(); num_components];
+
+        // Every vertex referenced by a triangle belongs to exactly one component
+        let mut vertex_components = vec![usize::MAX; self.vertices.len()];
+        for (tri, &component_id) in self.triangles.iter().zip(component_ids.iter()) {
+            for &v in tri {
+                vertex_components[v] = component_id;
+            }
+        }
 
-    assert_eq!(
-        boundary,
-        vec![
-            ([0usize, 1usize], 0, 0),
-            ([1usize, 2usize], 0, 1),
-            ([2usize, 0usize], 0, 2),
-        ]
-    );
-}
+        let mut new_indices = vec![usize::MAX; self.vertices.len()];
+        for (v, &component_id) in vertex_components.iter().enumerate() {
+            if component_id != usize::MAX {
+                let component = &mut components[component_id];
+                new_indices[v] = component.vertices.len();
+                component.vertices.push(self.vertices[v]);
+            }
+        }
 
-/// Wrapper type for meshes with attached point or cell data
-#[derive(Clone, Debug)]
-pub struct MeshWithData<R: Real, MeshT: Mesh3d<R>> {
-    /// The mesh geometry itself
-    pub mesh: MeshT,
-    /// Data attached to each vertex or point of the mesh
-    pub point_attributes: Vec<MeshAttribute<R>>,
-    /// Data attached to each cell of the mesh
-    pub cell_attributes: Vec<MeshAttribute<R>>,
-}
+        for (tri, &component_id) in self.triangles.iter().zip(component_ids.iter()) {
+            components[component_id]
+                .triangles
+                .push(tri.map(|v| new_indices[v]));
+        }
 
-/// Returns an mesh data wrapper with a default mesh and without attached attributes
-impl<R: Real, MeshT: Mesh3d<R> + Default> Default for MeshWithData<R, MeshT> {
-    fn default() -> Self {
-        Self::new(MeshT::default())
+        components
     }
-}
 
-impl<R: Real, MeshT: Mesh3d<R>> MeshWithData<R, MeshT> {
-    /// Wraps the given mesh such that point and cell data can be attached
-    pub fn new(mesh: MeshT) -> Self {
-        Self {
-            mesh,
-            point_attributes: Vec::new(),
-            cell_attributes: Vec::new(),
+    /// Removes all connected components with less than `min_triangles` triangles and all vertices that are not referenced by a triangle
+    ///
+    /// The connected components are determined by [`Self::connected_components`].
+    /// The vertex indices of the remaining triangles are adjusted to the compacted vertex storage,
+    /// the relative order of the remaining vertices and triangles is preserved.
+    pub fn remove_small_components(&mut self, min_triangles: usize) -> RemovedComponents {
+        let (component_ids, num_components) = self.connected_components();
+
+        let mut component_sizes = vec![0; num_components];
+        for &component in &component_ids {
+            component_sizes[component] += 1;
         }
-    }
 
-    /// Attaches an attribute to the points of the mesh, panics if the length of the data does not match the mesh's number of points
-    pub fn with_point_data(mut self, point_attribute: impl Into<MeshAttribute<R>>) -> Self {
-        let point_attribute = point_attribute.into();
-        assert_eq!(point_attribute.data.len(), self.mesh.vertices().len());
-        self.point_attributes.push(point_attribute);
-        self
+        let mut removed = RemovedComponents::default();
+        for &size in component_sizes.iter().filter(|&&size| size < min_triangles) {
+            removed.components += 1;
+            removed.triangles += size;
+        }
+
+        if removed.triangles > 0 {
+            let mut component_ids = component_ids.iter();
+            self.triangles.retain(|_| {
+                let component = *component_ids.next().unwrap();
+                component_sizes[component] >= min_triangles
+            });
+        }
+
+        self.remove_unused_vertices();
+        removed
     }
 
-    /// Attaches an attribute to the cells of the mesh, panics if the length of the data does not match the mesh's number of cells
-    pub fn with_cell_data(mut self, cell_attribute: impl Into<MeshAttribute<R>>) -> Self {
-        let cell_attribute = cell_attribute.into();
-        assert_eq!(cell_attribute.data.len(), self.mesh.cells().len());
-        self.cell_attributes.push(cell_attribute);
-        self
+    /// Removes all vertices that are not referenced by any triangle and returns the number of removed vertices
+    ///
+    /// The vertex indices of the triangles are adjusted accordingly, the relative order of the remaining vertices is preserved.
+    pub fn remove_unused_vertices(&mut self) -> usize {
+        let is_used = self.referenced_vertices();
+        self.retain_vertices(&is_used)
     }
-}
 
-impl<R: Real> MeshAttribute<R> {
-    /// Creates a new named mesh attribute with the given data
-    pub fn new<S: Into<String>>(name: S, data: impl Into<AttributeData<R>>) -> Self {
-        Self {
-            name: name.into(),
-            data: data.into(),
+    /// Returns for every vertex whether it is referenced by at least one triangle
+    fn referenced_vertices(&self) -> Vec<bool> {
+        let mut is_used = vec![false; self.vertices.len()];
+        for tri in &self.triangles {
+            for &v in tri {
+                is_used[v] = true;
+            }
         }
+        is_used
     }
 
-    /// Creates a new named mesh attribute with scalar values implementing the [`Real`](crate::Real) trait
-    pub fn new_real_scalar<S: Into<String>>(name: S, data: impl Into<Vec<R>>) -> Self {
-        Self {
-            name: name.into(),
-            data: AttributeData::ScalarReal(data.into()),
+    /// Removes all vertices that are not marked to be kept and returns the number of removed vertices, the kept vertices have to include all vertices referenced by triangles
+    fn retain_vertices(&mut self, keep: &[bool]) -> usize {
+        // Move all kept vertices to the front and store their new indices
+        let mut new_indices = vec![usize::MAX; self.vertices.len()];
+        let mut num_kept = 0;
+        for old_idx in 0..self.vertices.len() {
+            if keep[old_idx] {
+                self.vertices.swap(num_kept, old_idx);
+                new_indices[old_idx] = num_kept;
+                num_kept += 1;
+            }
         }
-    }
 
-    /// Creates a new named mesh attribute with scalar values implementing the [`Real`](crate::Real) trait
-    pub fn new_real_vector3<S: Into<String>>(name: S, data: impl Into<Vec<Vector3<R>>>) -> Self {
-        Self {
-            name: name.into(),
-            data: AttributeData::Vector3Real(data.into()),
+        let num_removed = self.vertices.len() - num_kept;
+        if num_removed > 0 {
+            self.vertices.truncate(num_kept);
+            for tri in self.triangles.iter_mut() {
+                for v in tri.iter_mut() {
+                    *v = new_indices[*v];
+                }
+            }
         }
+        num_removed
     }
 
-    /// Converts the mesh attribute to a [`vtkio::model::Attribute`](https://docs.rs/vtkio/0.6.*/vtkio/model/enum.Attribute.html)
-    #[cfg(feature = "vtk_extras")]
-    #[cfg_attr(doc_cfg, doc(cfg(feature = "vtk_extras")))]
-    fn to_vtk_attribute(&self) -> Attribute {
-        match &self.data {
-            AttributeData::ScalarU64(u64_vec) => {
-                Attribute::scalars(&self.name, 1).with_data(u64_vec.clone())
+    /// Removes triangles with repeated vertex indices or an area less than or equal to `area_epsilon` and the vertices only referenced by them
+    ///
+    /// Very flat marching cubes configurations can produce triangles with (almost) zero area that cause problems in
+    /// further geometry processing, e.g. undefined normals. Vertices that were only referenced by removed triangles
+    /// are removed as well and the vertex indices of the remaining triangles are adjusted accordingly, while vertices
+    /// that were not referenced by any triangle before are kept. The relative order of the remaining vertices and
+    /// triangles is preserved and a mesh without degenerate triangles is not modified at all.
+    ///
+    /// An epsilon of zero only removes triangles with an area of exactly zero (see [`Self::count_degenerate_triangles`]).
+    ///
+    /// # Panics
+    /// Panics if the area epsilon is negative or NaN.
+    pub fn remove_degenerate_triangles(&mut self, area_epsilon: R) -> RemovedDegenerateTriangles {
+        profile!("remove_degenerate_triangles");
+        assert!(
+            area_epsilon >= R::zero(),
+            "Area epsilon for removing degenerate triangles has to be non-negative!"
+        );
+
+        let mut removed = RemovedDegenerateTriangles::default();
+        let was_used = self.referenced_vertices();
+        let vertices = &self.vertices;
+        // Compare doubled areas to avoid a multiplication per triangle
+        let double_epsilon = area_epsilon + area_epsilon;
+        self.triangles.retain(|tri| {
+            if tri[0] == tri[1] || tri[1] == tri[2] || tri[2] == tri[0] {
+                removed.repeated_vertices += 1;
+                return false;
             }
-            AttributeData::ScalarReal(real_vec) => {
-                Attribute::scalars(&self.name, 1).with_data(real_vec.clone())
+
+            let [v0, v1, v2] = tri.map(|v| vertices[v]);
+            if (v1 - v0).cross(&(v2 - v0)).norm() <= double_epsilon {
+                removed.small_area += 1;
+                return false;
             }
-            AttributeData::Vector3Real(vec3r_vec) => Attribute::scalars(&self.name, 3)
-                .with_data(vec3r_vec.iter().flatten().copied().collect::<Vec<R>>()),
+            true
+        });
+
+        if !removed.is_empty() {
+            // Keep the vertices that are still used and the ones that were not used before
+            let mut keep = self.referenced_vertices();
+            for (keep, was_used) in keep.iter_mut().zip(was_used.iter()) {
+                *keep |= !was_used;
+            }
+            removed.unreferenced_vertices = self.retain_vertices(&keep);
         }
+        removed
     }
-}
 
-impl<R: Real> AttributeData<R> {
-    /// Returns the number of entries in the data set
-    fn len(&self) -> usize {
-        match self {
-            AttributeData::ScalarU64(v) => v.len(),
-            AttributeData::ScalarReal(v) => v.len(),
-            AttributeData::Vector3Real(v) => v.len(),
+    /// Merges all vertices with a distance less than or equal to the given tolerance, e.g. duplicated vertices along the boundaries of subdomains
+    ///
+    /// The vertices are processed in the order of their indices: every vertex that was not merged yet is kept and all
+    /// other vertices within the tolerance around it that were not merged yet are merged into it. Therefore, the result is
+    /// deterministic and the kept vertices retain their positions and relative order. Triangles that refer to the same vertex
+    /// more than once after merging are removed, all other triangles are updated to the new vertex indices.
+    ///
+    /// A tolerance of zero only merges vertices with bitwise identical positions without any spatial search.
+    /// For positive tolerances, the vertices are stored in a [`SpatialHashingNeighborhoodProvider`](crate::neighborhood_search::SpatialHashingNeighborhoodProvider).
+    ///
+    /// # Panics
+    /// Panics if the tolerance is negative or NaN.
+    pub fn weld_vertices(&mut self, tolerance: R) -> WeldedVertices {
+        assert!(
+            tolerance >= R::zero(),
+            "Tolerance for welding vertices has to be non-negative!"
+        );
+
+        // Index of the vertex that every vertex is merged into, kept vertices are their own representative
+        let mut representatives = vec![usize::MAX; self.vertices.len()];
+        if tolerance == R::zero() {
+            let mut first_occurrence = new_map();
+            for (i, v) in self.vertices.iter().enumerate() {
+                representatives[i] = *first_occurrence.entry(bytemuck::bytes_of(v)).or_insert(i);
+            }
+        } else if !self.vertices.is_empty() {
+            // Limit the number of cells of the grid for very small tolerances relative to the size of the mesh
+            let max_extent = AxisAlignedBoundingBox3d::from_points(&self.vertices).max_extent();
+            let cell_size = tolerance.max(max_extent / R::from_f64(1e5).unwrap());
+            let neighborhood = SpatialHashingNeighborhoodProvider::new(&self.vertices, cell_size);
+
+            for i in 0..self.vertices.len() {
+                if representatives[i] != usize::MAX {
+                    continue;
+                }
+                representatives[i] = i;
+                neighborhood.visit_particles_within(&self.vertices[i], tolerance, &mut |j| {
+                    if representatives[j] == usize::MAX {
+                        representatives[j] = i;
+                    }
+                });
+            }
         }
-    }
-}
 
-impl<R: Real, V: Into<Vec<u64>>> From<V> for AttributeData<R> {
+        // Compact the kept vertices, representatives always precede the vertices merged into them
+        let mut new_indices = vec![usize::MAX; self.vertices.len()];
+        let mut num_kept = 0;
+        for i in 0..self.vertices.len() {
+            if representatives[i] == i {
+                self.vertices.swap(num_kept, i);
+                new_indices[i] = num_kept;
+                num_kept += 1;
+            } else {
+                new_indices[i] = new_indices[representatives[i]];
+            }
+        }
+
+        let num_triangles = self.triangles.len();
+        self.triangles.retain_mut(|tri| {
+            *tri = tri.map(|v| new_indices[v]);
+            tri[0] != tri[1] && tri[1] != tri[2] && tri[2] != tri[0]
+        });
+
+        let welded = WeldedVertices {
+            merged_vertices: self.vertices.len() - num_kept,
+            degenerate_triangles: num_triangles - self.triangles.len(),
+        };
+        self.vertices.truncate(num_kept);
+        welded
+    }
+
+    /// Flips triangles such that all triangles sharing an edge are oriented consistently, returns the number of flipped triangles
+    ///
+    /// Every component of triangles connected by shared edges is traversed starting at its first triangle, whose
+    /// orientation is kept. A triangle that traverses an edge in the same direction as the already oriented neighbor it
+    /// was reached from is flipped by swapping two of its vertices. The components are oriented independently of each other.
+    ///
+    /// If `orient_outward` is set, all triangles of a component are flipped afterwards if the signed volume enclosed by
+    /// the component (relative to the centroid of its triangles, see [`Self::volume`]) is negative, such that the normals of
+    /// closed components point outward. Triangles that are flipped twice are not counted.
+    ///
+    /// Components that are not orientable (e.g. a Möbius strip) or contain non-manifold edges may retain edges with inconsistent orientation.
+    pub fn orient_consistently(&mut self, orient_outward: bool) -> usize {
+        profile!("orient_consistently");
+
+        let connectivity = Connectivity::from_mesh(self);
+        let mut flipped = vec![false; self.triangles.len()];
+        let mut visited = vec![false; self.triangles.len()];
+        let mut component = Vec::new();
+
+        for first in 0..self.triangles.len() {
+            if visited[first] {
+                continue;
+            }
+
+            // Breadth-first traversal of the component, the triangles are oriented when they are reached
+            visited[first] = true;
+            component.clear();
+            component.push(first);
+            let mut next = 0;
+            while next < component.len() {
+                let t = component[next];
+                next += 1;
+                for &n in connectivity.triangle_neighbors(t) {
+                    if visited[n] {
+                        continue;
+                    }
+                    visited[n] = true;
+                    if shares_directed_edge(&self.triangles[t], &self.triangles[n]) {
+                        self.triangles[n].swap(1, 2);
+                        flipped[n] = true;
+                    }
+                    component.push(n);
+                }
+            }
+
+            if orient_outward {
+                let num_corners = R::from_usize(3 * component.len()).unwrap();
+                let centroid = component
+                    .iter()
+                    .flat_map(|&t| self.triangles[t])
+                    .fold(Vector3::zeros(), |acc, v| acc + self.vertices[v])
+                    / num_corners;
+                let volume = component
+                    .iter()
+                    .map(|&t| {
+                        let [v0, v1, v2] = self.triangles[t].map(|v| self.vertices[v] - centroid);
+                        v0.dot(&v1.cross(&v2))
+                    })
+                    .fold(R::zero(), |acc, volume| acc + volume);
+
+                if volume < R::zero() {
+                    for &t in component.iter() {
+                        self.triangles[t].swap(1, 2);
+                        flipped[t] = !flipped[t];
+                    }
+                }
+            }
+        }
+
+        flipped.into_iter().filter(|&flipped| flipped).count()
+    }
+
+    /// Checks the mesh for topological problems like non-manifold edges, holes or isolated vertices, see [`check::MeshValidityReport`]
+    pub fn validity_report(&self) -> check::MeshValidityReport {
+        check::MeshValidityReport::from_mesh(self)
+    }
+
+    /// Clips the triangles of the mesh against the faces of the given box, see [`clipping::clip_aabb`]
+    pub fn clip_aabb(&mut self, aabb: &AxisAlignedBoundingBox3d<R>) -> clipping::ClipMap<R> {
+        clipping::clip_aabb(self, aabb)
+    }
+
+    /// Applies the rigid transformation (rotation followed by translation) to all vertices of the mesh, see [`transform::transform_points`](crate::transform::transform_points)
+    pub fn transform(&mut self, isometry: &Isometry3<R>) {
+        transform::transform_points(&mut self.vertices, isometry);
+    }
+
+    /// Applies the rigid transformation to all vertices of the mesh (parallelized version), see [`Self::transform`]
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+    pub fn par_transform(&mut self, isometry: &Isometry3<R>) {
+        transform::par_transform_points(&mut self.vertices, isometry);
+    }
+
+    /// Scales the mesh uniformly relative to the coordinate origin by multiplying all vertices with the given factor
+    ///
+    /// A negative factor mirrors the mesh at the origin, note that this inverts the orientation of the triangles.
+    pub fn scale(&mut self, scaling: R) {
+        transform::scale_points(&mut self.vertices, scaling);
+    }
+
+    /// Scales the mesh uniformly relative to the coordinate origin (parallelized version), see [`Self::scale`]
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+    pub fn par_scale(&mut self, scaling: R) {
+        transform::par_scale_points(&mut self.vertices, scaling);
+    }
+
+    /// Translates all vertices of the mesh by the given vector
+    pub fn translate(&mut self, translation: &Vector3<R>) {
+        transform::translate_points(&mut self.vertices, translation);
+    }
+
+    /// Translates all vertices of the mesh by the given vector (parallelized version)
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+    pub fn par_translate(&mut self, translation: &Vector3<R>) {
+        transform::par_translate_points(&mut self.vertices, translation);
+    }
+
+    /// Labels the connected components of the mesh, returns the component id of every triangle and the number of components
+    ///
+    /// Two triangles are part of the same connected component if they are connected by a shared vertex.
+    /// The components are numbered in the order of their first triangle. The labeling uses a union-find
+    /// over the vertices of the triangles and runs in almost linear time in the number of triangles.
+    pub fn connected_components(&self) -> (Vec<usize>, usize) {
+        let mut vertex_sets = DisjointSets::new(self.vertices.len());
+        for tri in &self.triangles {
+            vertex_sets.union(tri[0], tri[1]);
+            vertex_sets.union(tri[0], tri[2]);
+        }
+
+        let mut component_of_root = vec![usize::MAX; self.vertices.len()];
+        let mut num_components = 0;
+        let component_ids = self
+            .triangles
+            .iter()
+            .map(|tri| {
+                let root = vertex_sets.find(tri[0]);
+                if component_of_root[root] == usize::MAX {
+                    component_of_root[root] = num_components;
+                    num_components += 1;
+                }
+                component_of_root[root]
+            })
+            .collect();
+
+        (component_ids, num_components)
+    }
+
+    /// Converts the mesh to a mixed triangle and quadrilateral mesh by merging pairs of flat triangles into quads
+    ///
+    /// Two triangles that share an edge (traversed in opposite directions, i.e. oriented consistently) are merged if the angle
+    /// between their normals is at most `angle_tolerance` (in radians) and the resulting quad is strictly convex. Every
+    /// triangle is merged at most once, pairs with the smallest angle between their normals are merged first. The quads
+    /// replace the first triangle of their pair, all other triangles are kept in their order. The vertices are not modified.
+    pub fn to_tri_quad(&self, angle_tolerance: R) -> MixedTriQuadMesh3d<R> {
+        profile!("to_tri_quad");
+
+        let connectivity = Connectivity::from_mesh(self);
+        let normals: Vec<_> = self
+            .triangles
+            .iter()
+            .map(|tri| {
+                let [v0, v1, v2] = tri.map(|v| self.vertices[v]);
+                let normal = (v1 - v0).cross(&(v2 - v0));
+                (normal.norm() > R::zero()).then(|| normal.normalize())
+            })
+            .collect();
+        let min_cos = angle_tolerance.cos();
+
+        let mut candidates = Vec::new();
+        for (t0, tri0) in self.triangles.iter().enumerate() {
+            for &t1 in connectivity.triangle_neighbors(t0) {
+                if t1 < t0 {
+                    continue;
+                }
+                let (n0, n1) = match (normals[t0], normals[t1]) {
+                    (Some(n0), Some(n1)) => (n0, n1),
+                    _ => continue,
+                };
+                let cos = n0.dot(&n1);
+                if cos < min_cos {
+                    continue;
+                }
+                if let Some(quad) = merged_quad(tri0, &self.triangles[t1]) {
+                    if self.is_convex_quad(&quad, &(n0 + n1)) {
+                        candidates.push((cos, t0, t1, quad));
+                    }
+                }
+            }
+        }
+
+        // Merge the flattest pairs first, ties are broken by the triangle indices to be deterministic
+        candidates.sort_by(|(cos_a, t0_a, t1_a, _), (cos_b, t0_b, t1_b, _)| {
+            cos_b
+                .partial_cmp(cos_a)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then((t0_a, t1_a).cmp(&(t0_b, t1_b)))
+        });
+        let mut merged = vec![false; self.triangles.len()];
+        let mut quads = vec![None; self.triangles.len()];
+        for (_, t0, t1, quad) in candidates {
+            if !merged[t0] && !merged[t1] {
+                merged[t0] = true;
+                merged[t1] = true;
+                quads[t0] = Some(quad);
+            }
+        }
+
+        let cells = self
+            .triangles
+            .iter()
+            .enumerate()
+            .filter_map(|(t, tri)| match quads[t] {
+                Some(quad) => Some(TriangleOrQuadCell::Quad(quad)),
+                None => (!merged[t]).then_some(TriangleOrQuadCell::Tri(*tri)),
+            })
+            .collect();
+
+        MixedTriQuadMesh3d {
+            vertices: self.vertices.clone(),
+            cells,
+        }
+    }
+
+    /// Returns whether the quad turns in the same direction as the normal at each of its corners
+    fn is_convex_quad(&self, quad: &[usize; 4], normal: &Vector3<R>) -> bool {
+        let p = quad.map(|v| self.vertices[v]);
+        (0..4).all(|i| {
+            let e0 = p[(i + 1) % 4] - p[i];
+            let e1 = p[(i + 2) % 4] - p[(i + 1) % 4];
+            e0.cross(&e1).dot(normal) > R::zero()
+        })
+    }
+}
+
+impl<R: Real> MixedTriQuadMesh3d<R> {
+    /// Returns the number of quadrilateral cells of the mesh
+    pub fn num_quads(&self) -> usize {
+        self.cells
+            .iter()
+            .filter(|cell| matches!(cell, TriangleOrQuadCell::Quad(_)))
+            .count()
+    }
+
+    /// Converts the mesh back to a triangle mesh by splitting every quad along the diagonal between its first and third vertex
+    ///
+    /// For a mesh returned by [`TriMesh3d::to_tri_quad`], this restores the original triangles (up to their order and the
+    /// first vertex of each triangle).
+    pub fn to_tri_mesh(&self) -> TriMesh3d<R> {
+        let mut triangles = Vec::with_capacity(2 * self.cells.len());
+        for cell in &self.cells {
+            match *cell {
+                TriangleOrQuadCell::Tri(tri) => triangles.push(tri),
+                TriangleOrQuadCell::Quad([v0, v1, v2, v3]) => {
+                    triangles.push([v0, v1, v2]);
+                    triangles.push([v0, v2, v3]);
+                }
+            }
+        }
+
+        TriMesh3d {
+            vertices: self.vertices.clone(),
+            triangles,
+        }
+    }
+
+    /// Returns the total area of all cells of the mesh, quads are split into two triangles, see [`Self::to_tri_mesh`]
+    pub fn area(&self) -> R {
+        self.to_tri_mesh().area()
+    }
+}
+
+/// Returns the quad formed by two triangles that share exactly one edge in opposite directions
+fn merged_quad(a: &[usize; 3], b: &[usize; 3]) -> Option<[usize; 4]> {
+    for i in 0..3 {
+        let (v0, v1) = (a[i], a[(i + 1) % 3]);
+        if let Some(j) = (0..3).find(|&j| b[j] == v1 && b[(j + 1) % 3] == v0) {
+            let opposite_a = a[(i + 2) % 3];
+            let opposite_b = b[(j + 2) % 3];
+            return (!a.contains(&opposite_b)).then_some([v0, opposite_b, v1, opposite_a]);
+        }
+    }
+    None
+}
+
+/// Returns whether both triangles contain an edge from the same vertex to the same vertex, i.e. they are oriented inconsistently
+fn shares_directed_edge(a: &[usize; 3], b: &[usize; 3]) -> bool {
+    (0..3).any(|i| (0..3).any(|j| a[i] == b[j] && a[(i + 1) % 3] == b[(j + 1) % 3]))
+}
+
+/// Disjoint-set forest (union-find) with path halving and union by size
+struct DisjointSets {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl DisjointSets {
+    /// Creates `n` singleton sets
+    fn new(n: usize) -> Self {
+        Self {
+            parents: (0..n).collect(),
+            sizes: vec![1; n],
+        }
+    }
+
+    /// Returns the representative element of the set containing `i`
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    /// Merges the sets containing `i` and `j`
+    fn union(&mut self, i: usize, j: usize) {
+        let (mut i, mut j) = (self.find(i), self.find(j));
+        if i == j {
+            return;
+        }
+        if self.sizes[i] < self.sizes[j] {
+            std::mem::swap(&mut i, &mut j);
+        }
+        self.parents[j] = i;
+        self.sizes[i] += self.sizes[j];
+    }
+}
+
+/// Closes all holes of the mesh with at most `max_loop_len` boundary edges and returns the number of filled holes
+///
+/// The boundary loops are traced along the boundary edges of the mesh (see [`TriMesh3d::find_boundary_edges`]) and
+/// every loop with at least three and at most `max_loop_len` edges is closed by a fan of triangles around its first vertex.
+/// The new triangles are appended to the mesh and oriented consistently with the adjacent triangles, no vertices are added.
+/// Longer loops (e.g. the open boundary of a surface clipped by the domain) and loops passing through a vertex with more
+/// than one outgoing boundary edge are left untouched.
+pub fn fill_small_holes<R: Real>(mesh: &mut TriMesh3d<R>, max_loop_len: usize) -> usize {
+    profile!("fill_small_holes");
+
+    // Sort the boundary edges for a deterministic order of the loops and new triangles
+    let mut boundary_edges = mesh
+        .find_boundary_edges()
+        .into_iter()
+        .map(|(edge, _, _)| edge)
+        .collect::<Vec<_>>();
+    boundary_edges.sort_unstable();
+
+    // Boundary edge that leaves every vertex in the orientation of its triangle, `None` if the vertex has several
+    let mut outgoing_edge = new_map();
+    for (edge_idx, edge) in boundary_edges.iter().enumerate() {
+        outgoing_edge
+            .entry(edge[0])
+            .and_modify(|outgoing| *outgoing = None)
+            .or_insert(Some(edge_idx));
+    }
+
+    let mut visited = vec![false; boundary_edges.len()];
+    let mut boundary_loop = Vec::new();
+    let mut filled_holes = 0;
+    for start_edge in 0..boundary_edges.len() {
+        if visited[start_edge] {
+            continue;
+        }
+
+        // Follow the boundary edges until the loop is closed or a vertex without a unique outgoing edge is reached
+        boundary_loop.clear();
+        let mut edge_idx = start_edge;
+        let is_closed = loop {
+            visited[edge_idx] = true;
+            boundary_loop.push(boundary_edges[edge_idx][0]);
+            let next_vertex = boundary_edges[edge_idx][1];
+            match outgoing_edge.get(&next_vertex).copied().flatten() {
+                Some(next_edge) if next_edge == start_edge => break true,
+                Some(next_edge) if !visited[next_edge] => edge_idx = next_edge,
+                _ => break false,
+            }
+        };
+
+        let loop_len = boundary_loop.len();
+        if is_closed && loop_len >= 3 && loop_len <= max_loop_len {
+            // The boundary edges of the adjacent triangles are traversed in the opposite direction by the new triangles
+            let v0 = boundary_loop[0];
+            for i in 1..loop_len - 1 {
+                mesh.triangles
+                    .push([v0, boundary_loop[i + 1], boundary_loop[i]]);
+            }
+            filled_holes += 1;
+        }
+    }
+
+    filled_holes
+}
+
+/// Normalizes the vector inplace, a zero vector (e.g. of a vertex without adjacent triangles) is kept as it is
+fn normalize_or_zero<R: Real>(normal: &mut Vector3<R>) {
+    let norm = normal.norm();
+    if norm > R::zero() {
+        *normal /= norm;
+    }
+}
+
+#[test]
+fn test_find_boundary() {
+    // TODO: Needs a test with a real mesh
+    let mesh = TriMesh3d::<f64> {
+        vertices: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        ],
+        triangles: vec![[0, 1, 2]],
+    };
+
+    let mut boundary = mesh.find_boundary_edges();
+    boundary.sort_unstable();
+
+    assert_eq!(
+        boundary,
+        vec![
+            ([0usize, 1usize], 0, 0),
+            ([1usize, 2usize], 0, 1),
+            ([2usize, 0usize], 0, 2),
+        ]
+    );
+}
+
+#[test]
+fn test_icosphere() {
+    let center = Vector3::new(1.0, -2.0, 0.5);
+    let radius = 0.25;
+
+    for subdivisions in 0..4 {
+        let sphere = TriMesh3d::<f64>::icosphere(&center, radius, subdivisions);
+        assert_eq!(
+            sphere.vertices.len(),
+            10 * 4usize.pow(subdivisions as u32) + 2
+        );
+        assert_eq!(sphere.triangles.len(), 20 * 4usize.pow(subdivisions as u32));
+        assert!(sphere.find_boundary_edges().is_empty());
+
+        for v in sphere.vertices.iter() {
+            assert!(((v - center).norm() - radius).abs() < 1e-12);
+        }
+
+        // All triangles should be oriented outwards
+        for tri in sphere.triangles.iter() {
+            let (v0, v1, v2) = (
+                sphere.vertices[tri[0]],
+                sphere.vertices[tri[1]],
+                sphere.vertices[tri[2]],
+            );
+            let normal = (v1 - v0).cross(&(v2 - v0));
+            assert!(normal.dot(&((v0 + v1 + v2) / 3.0 - center)) > 0.0);
+        }
+    }
+}
+
+#[test]
+fn test_count_degenerate_triangles() {
+    let mesh = TriMesh3d::<f64> {
+        vertices: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+        ],
+        // Regular triangle, collinear vertices and repeated vertex
+        triangles: vec![[0, 1, 2], [0, 1, 3], [0, 2, 2]],
+    };
+
+    assert_eq!(mesh.count_degenerate_triangles(), 2);
+    assert_eq!(
+        TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 2).count_degenerate_triangles(),
+        0
+    );
+}
+
+#[test]
+fn test_area_and_volume() {
+    // Tetrahedron with outward oriented triangles
+    let tetrahedron = TriMesh3d::<f64> {
+        vertices: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+        ],
+        triangles: vec![[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]],
+    };
+    assert!((tetrahedron.area() - (1.5 + 0.75f64.sqrt())).abs() < 1e-12);
+    assert!((tetrahedron.volume() - 1.0 / 6.0).abs() < 1e-12);
+
+    // The volume of a closed mesh does not depend on its position, inverted triangles result in a negative volume
+    let mut inverted = tetrahedron.clone();
+    for v in inverted.vertices.iter_mut() {
+        *v += Vector3::new(-3.0, 2.0, 5.0);
+    }
+    for tri in inverted.triangles.iter_mut() {
+        tri.swap(1, 2);
+    }
+    assert!((inverted.area() - tetrahedron.area()).abs() < 1e-12);
+    assert!((inverted.volume() + 1.0 / 6.0).abs() < 1e-12);
+
+    // The icosphere approaches the area and volume of the sphere from below
+    let radius = 0.5;
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::new(1.0, 2.0, 3.0), radius, 4);
+    let sphere_area = 4.0 * std::f64::consts::PI * radius * radius;
+    let sphere_volume = 4.0 / 3.0 * std::f64::consts::PI * radius.powi(3);
+    assert!(sphere.area() < sphere_area && sphere.area() > 0.99 * sphere_area);
+    assert!(sphere.volume() < sphere_volume && sphere.volume() > 0.99 * sphere_volume);
+
+    assert_eq!(TriMesh3d::<f64>::default().volume(), 0.0);
+
+    // Only closed meshes have an enclosed volume
+    assert_eq!(sphere.enclosed_volume(), Some(sphere.volume()));
+    let mut open_sphere = sphere.clone();
+    open_sphere.triangles.pop();
+    assert_eq!(open_sphere.enclosed_volume(), None);
+
+    #[cfg(feature = "parallel")]
+    {
+        assert!((sphere.par_area() - sphere.area()).abs() < 1e-12);
+        assert!((sphere.par_volume() - sphere.volume()).abs() < 1e-12);
+        assert!((sphere.par_enclosed_volume().unwrap() - sphere.volume()).abs() < 1e-12);
+        assert_eq!(open_sphere.par_enclosed_volume(), None);
+    }
+}
+
+#[test]
+fn test_from_buffers() {
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 1);
+    let vertices_ptr = sphere.vertices.as_ptr();
+    let triangles_ptr = sphere.triangles.as_ptr();
+
+    // The buffers are moved in and out of the mesh without reallocation
+    let (vertices, triangles) = sphere.clone().into_buffers();
+    let mesh = TriMesh3d::from_buffers(vertices, triangles).unwrap();
+    assert_eq!(mesh.vertices, sphere.vertices);
+    assert_eq!(mesh.triangles, sphere.triangles);
+    let (vertices, triangles) = sphere.into_buffers();
+    assert_eq!(vertices.as_ptr(), vertices_ptr);
+    assert_eq!(triangles.as_ptr(), triangles_ptr);
+    let mesh = TriMesh3d::from_buffers(vertices, triangles).unwrap();
+    assert_eq!(mesh.vertices.as_ptr(), vertices_ptr);
+    assert_eq!(mesh.triangles.as_ptr(), triangles_ptr);
+
+    let vertices = vec![Vector3::zeros(), Vector3::x(), Vector3::y()];
+    assert_eq!(
+        TriMesh3d::<f64>::from_buffers(vertices.clone(), vec![[0, 1, 2], [2, 3, 0]]).unwrap_err(),
+        MeshError::VertexIndexOutOfRange {
+            triangle: 1,
+            vertex_index: 3,
+            num_vertices: 3
+        }
+    );
+    assert!(TriMesh3d::<f64>::from_buffers(Vec::new(), vec![[0, 0, 0]]).is_err());
+    assert!(TriMesh3d::<f64>::from_buffers(Vec::new(), Vec::new()).is_ok());
+
+    let mesh = unsafe { TriMesh3d::from_buffers_unchecked(vertices, vec![[0, 1, 2]]) };
+    assert_eq!(mesh.triangles, vec![[0, 1, 2]]);
+}
+
+#[test]
+fn test_orientation() {
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::new(1e4, -2e4, 3e4), 1.0, 2);
+    assert_eq!(sphere.is_outward_oriented(), Some(true));
+    assert_eq!(TriMesh3d::<f64>::default().is_outward_oriented(), None);
+
+    let mut flipped = sphere.clone();
+    flipped.flip_orientation();
+    assert_eq!(flipped.is_outward_oriented(), Some(false));
+    assert!((flipped.volume() + sphere.volume()).abs() < 1e-6 * sphere.volume().abs());
+    flipped.flip_orientation();
+    assert_eq!(flipped.triangles, sphere.triangles);
+
+    // An open mesh does not enclose a volume
+    let mut open = sphere.clone();
+    open.triangles.pop();
+    assert_eq!(open.is_outward_oriented(), None);
+
+    // An inward oriented bubble inside of the sphere and an open component do not change the result
+    let mut bubble = TriMesh3d::icosphere(&Vector3::new(1e4, -2e4, 3e4), 0.5, 2);
+    bubble.flip_orientation();
+    let mut with_bubble = sphere.clone();
+    with_bubble.append(&mut bubble);
+    with_bubble.append(&mut open.clone());
+    assert_eq!(with_bubble.is_outward_oriented(), Some(true));
+    with_bubble.flip_orientation();
+    assert_eq!(with_bubble.is_outward_oriented(), Some(false));
+
+    // The normals of a mesh with data are negated as well
+    let mut mesh_with_data = MeshWithData::new(sphere.clone())
+        .with_vertex_normals()
+        .with_point_data(MeshAttribute::new_real_scalar(
+            "id",
+            vec![1.0; sphere.vertices.len()],
+        ));
+    mesh_with_data.flip_orientation();
+    assert_eq!(mesh_with_data.mesh.is_outward_oriented(), Some(false));
+    match &mesh_with_data.point_attributes[0].data {
+        AttributeData::Vector3Real(normals) => {
+            for (n, expected) in normals.iter().zip(mesh_with_data.mesh.vertex_normals()) {
+                assert!((n - expected).norm() < 1e-12);
+            }
+        }
+        _ => panic!("unexpected attribute type"),
+    }
+    assert!(matches!(
+        &mesh_with_data.point_attributes[1].data,
+        AttributeData::ScalarReal(values) if values.iter().all(|&v| v == 1.0)
+    ));
+}
+
+#[test]
+fn test_vertex_normals() {
+    // The normals of the icosphere approximate the normals of the sphere
+    let center = Vector3::new(1.0, -2.0, 0.5);
+    let sphere = TriMesh3d::<f64>::icosphere(&center, 0.25, 3);
+    let normals = sphere.vertex_normals();
+    assert_eq!(normals.len(), sphere.vertices.len());
+    for (v, n) in sphere.vertices.iter().zip(normals.iter()) {
+        assert!((n.norm() - 1.0).abs() < 1e-12);
+        assert!(n.dot(&(v - center).normalize()) > 0.999);
+    }
+
+    #[cfg(feature = "parallel")]
+    for (n, par_n) in normals.iter().zip(sphere.par_vertex_normals().iter()) {
+        assert!((n - par_n).norm() < 1e-12);
+    }
+
+    // A degenerate triangle does not contribute, vertices without triangles get a zero vector
+    let mesh = TriMesh3d::<f64> {
+        vertices: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(5.0, 5.0, 5.0),
+        ],
+        triangles: vec![[0, 1, 2], [0, 1, 3]],
+    };
+    let normals = mesh.vertex_normals();
+    for n in &normals[0..3] {
+        assert_eq!(*n, Vector3::z());
+    }
+    assert_eq!(normals[3], Vector3::zeros());
+    assert_eq!(normals[4], Vector3::zeros());
+
+    let mesh_with_normals = MeshWithData::new(mesh).with_vertex_normals();
+    assert_eq!(mesh_with_normals.point_attributes[0].name, "normals");
+    assert_eq!(mesh_with_normals.point_attributes[0].data.len(), 5);
+}
+
+#[test]
+fn test_connected_components() {
+    let mut mesh = TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 2);
+    let sphere = mesh.clone();
+    // An unreferenced vertex
+    mesh.vertices.push(Vector3::new(-5.0, 0.0, 0.0));
+    // Two triangles that only share a single vertex form one component
+    mesh.append(&mut TriMesh3d {
+        vertices: vec![
+            Vector3::new(5.0, 0.0, 0.0),
+            Vector3::new(6.0, 0.0, 0.0),
+            Vector3::new(5.0, 1.0, 0.0),
+            Vector3::new(4.0, 0.0, 0.0),
+            Vector3::new(5.0, -1.0, 0.0),
+        ],
+        triangles: vec![[0, 1, 2], [0, 3, 4]],
+    });
+    mesh.append(&mut TriMesh3d::icosphere(
+        &Vector3::new(0.0, 5.0, 0.0),
+        0.5,
+        0,
+    ));
+    let (component_ids, num_components) = mesh.connected_components();
+    assert_eq!(num_components, 3);
+    assert_eq!(component_ids.len(), 342);
+    assert!(component_ids[..320].iter().all(|&c| c == 0));
+    assert_eq!(component_ids[320..322], [1, 1]);
+    assert!(component_ids[322..].iter().all(|&c| c == 2));
+
+    let components = mesh.split_components();
+    assert_eq!(components.len(), 3);
+    assert_eq!(components[0].vertices, sphere.vertices);
+    assert_eq!(components[0].triangles, sphere.triangles);
+    assert_eq!(components[1].vertices.len(), 5);
+    assert_eq!(components[1].triangles, vec![[0, 1, 2], [0, 3, 4]]);
+    assert_eq!(components[2].vertices.len(), 12);
+    assert!((components[2].volume() - mesh.volume() + sphere.volume()).abs() < 1e-12);
+
+    let mut unchanged = mesh.clone();
+    assert_eq!(
+        unchanged.remove_small_components(2),
+        RemovedComponents::default()
+    );
+    assert_eq!(unchanged.triangles.len(), mesh.triangles.len());
+    assert_eq!(unchanged.vertices.len(), mesh.vertices.len() - 1);
+
+    assert_eq!(
+        mesh.remove_small_components(21),
+        RemovedComponents {
+            components: 2,
+            triangles: 22,
+        }
+    );
+    assert_eq!(mesh.vertices, sphere.vertices);
+    assert_eq!(mesh.triangles, sphere.triangles);
+
+    assert_eq!(mesh.remove_small_components(1000).triangles, 320);
+    assert!(mesh.vertices.is_empty() && mesh.triangles.is_empty());
+}
+
+#[test]
+fn test_remove_degenerate_triangles() {
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 1);
+
+    // A clean mesh with an isolated vertex is not modified
+    let mut mesh = sphere.clone();
+    mesh.vertices.push(Vector3::new(5.0, 5.0, 5.0));
+    let clean = mesh.clone();
+    assert_eq!(
+        mesh.remove_degenerate_triangles(1e-6),
+        RemovedDegenerateTriangles::default()
+    );
+    assert_eq!(mesh.vertices, clean.vertices);
+    assert_eq!(mesh.triangles, clean.triangles);
+
+    // A triangle with a repeated vertex, a collinear triangle and a sliver below the area epsilon
+    let first_new = mesh.vertices.len();
+    mesh.vertices.extend([
+        Vector3::new(2.0, 0.0, 0.0),
+        Vector3::new(3.0, 0.0, 0.0),
+        Vector3::new(4.0, 0.0, 0.0),
+        Vector3::new(2.0, 1.0, 0.0),
+        Vector3::new(3.0, 1.0, 0.0),
+        Vector3::new(2.5, 1.0 + 1e-9, 0.0),
+    ]);
+    mesh.triangles.push([0, 0, 1]);
+    mesh.triangles
+        .push([first_new, first_new + 1, first_new + 2]);
+    mesh.triangles
+        .push([first_new + 3, first_new + 4, first_new + 5]);
+    // A regular triangle that shares a vertex with the sliver
+    mesh.triangles
+        .push([first_new + 3, first_new + 5, first_new + 2]);
+
+    let mut exact = mesh.clone();
+    assert_eq!(
+        exact.remove_degenerate_triangles(0.0),
+        RemovedDegenerateTriangles {
+            repeated_vertices: 1,
+            small_area: 1,
+            unreferenced_vertices: 2,
+        }
+    );
+    assert_eq!(exact.triangles.len(), sphere.triangles.len() + 2);
+    assert_eq!(exact.count_degenerate_triangles(), 0);
+
+    let removed = mesh.remove_degenerate_triangles(1e-6);
+    assert_eq!(
+        removed,
+        RemovedDegenerateTriangles {
+            repeated_vertices: 1,
+            small_area: 2,
+            unreferenced_vertices: 3,
+        }
+    );
+    assert_eq!(removed.triangles(), 3);
+    // The isolated vertex is kept and the indices of the remaining triangle are adjusted
+    assert_eq!(mesh.vertices.len(), clean.vertices.len() + 3);
+    assert_eq!(mesh.vertices[..clean.vertices.len()], clean.vertices[..]);
+    assert_eq!(
+        mesh.triangles[..sphere.triangles.len()],
+        sphere.triangles[..]
+    );
+    let last = mesh.triangles.last().unwrap().map(|v| mesh.vertices[v]);
+    assert_eq!(
+        last,
+        [
+            Vector3::new(2.0, 1.0, 0.0),
+            Vector3::new(2.5, 1.0 + 1e-9, 0.0),
+            Vector3::new(4.0, 0.0, 0.0)
+        ]
+    );
+
+    // Calling it again does not change anything
+    let cleaned = mesh.clone();
+    assert!(mesh.remove_degenerate_triangles(1e-6).is_empty());
+    assert_eq!(mesh.vertices, cleaned.vertices);
+    assert_eq!(mesh.triangles, cleaned.triangles);
+}
+
+#[test]
+fn test_weld_vertices() {
+    // Every triangle of the sphere gets its own copy of its vertices
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::new(1.0, -2.0, 0.5), 0.25, 2);
+    let separated = TriMesh3d {
+        vertices: sphere
+            .triangles
+            .iter()
+            .flat_map(|tri| tri.map(|v| sphere.vertices[v]))
+            .collect(),
+        triangles: (0..sphere.triangles.len())
+            .map(|i| [3 * i, 3 * i + 1, 3 * i + 2])
+            .collect(),
+    };
+    assert_eq!(separated.connected_components().1, sphere.triangles.len());
+
+    for tolerance in [0.0, 1e-9, 1e-3] {
+        let mut welded = separated.clone();
+        assert_eq!(
+            welded.weld_vertices(tolerance),
+            WeldedVertices {
+                merged_vertices: separated.vertices.len() - sphere.vertices.len(),
+                degenerate_triangles: 0,
+            }
+        );
+        assert_eq!(welded.vertices.len(), sphere.vertices.len());
+        assert_eq!(welded.triangles.len(), sphere.triangles.len());
+        assert!(welded.find_boundary_edges().is_empty());
+        assert!((welded.volume() - sphere.volume()).abs() < 1e-12);
+
+        // Welding is deterministic and idempotent
+        let mut welded_again = separated.clone();
+        welded_again.weld_vertices(tolerance);
+        assert_eq!(welded_again.vertices, welded.vertices);
+        assert_eq!(welded_again.triangles, welded.triangles);
+        assert_eq!(
+            welded_again.weld_vertices(tolerance),
+            WeldedVertices::default()
+        );
+    }
+
+    // Only bitwise identical vertices are merged with zero tolerance, triangles connecting merged vertices are removed
+    let mut mesh = TriMesh3d::<f64> {
+        vertices: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(1.0, 0.0, 1e-4),
+            Vector3::new(0.0, 1.0, 0.0),
+        ],
+        triangles: vec![[0, 1, 2], [1, 3, 4], [2, 3, 0]],
+    };
+    let mut zero_tolerance = mesh.clone();
+    assert_eq!(
+        zero_tolerance.weld_vertices(0.0),
+        WeldedVertices {
+            merged_vertices: 1,
+            degenerate_triangles: 0,
+        }
+    );
+    assert_eq!(
+        zero_tolerance.triangles,
+        vec![[0, 1, 2], [1, 3, 2], [2, 3, 0]]
+    );
+
+    assert_eq!(
+        mesh.weld_vertices(1e-3),
+        WeldedVertices {
+            merged_vertices: 2,
+            degenerate_triangles: 1,
+        }
+    );
+    assert_eq!(mesh.vertices, zero_tolerance.vertices[0..3]);
+    assert_eq!(mesh.triangles, vec![[0, 1, 2], [2, 1, 0]]);
+}
+
+#[test]
+fn test_fill_small_holes() {
+    let center = Vector3::new(1.0, -2.0, 0.5);
+    let sphere = TriMesh3d::<f64>::icosphere(&center, 1.0, 2);
+
+    // A hole of a single triangle and a hole of all six triangles around a vertex
+    let removed_vertex = 30;
+    let mut mesh = sphere.clone();
+    mesh.triangles = sphere
+        .triangles
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(i, tri)| i != 0 && !tri.contains(&removed_vertex))
+        .map(|(_, tri)| tri)
+        .collect();
+    assert_eq!(mesh.triangles.len(), sphere.triangles.len() - 7);
+    assert_eq!(mesh.find_boundary_edges().len(), 9);
+
+    // Holes longer than the limit are not filled
+    let mut unchanged = mesh.clone();
+    assert_eq!(fill_small_holes(&mut unchanged, 5), 1);
+    assert_eq!(unchanged.find_boundary_edges().len(), 6);
+
+    assert_eq!(fill_small_holes(&mut mesh, 6), 2);
+    assert_eq!(mesh.triangles.len(), sphere.triangles.len() - 2);
+    assert!(mesh.find_boundary_edges().is_empty());
+    assert_eq!(
+        mesh.validity_report().isolated_vertices.examples,
+        vec![removed_vertex]
+    );
+    // All new triangles are oriented outwards like the remaining triangles
+    assert!((mesh.volume() - sphere.volume()).abs() < 0.01 * sphere.volume());
+    for tri in mesh.triangles.iter() {
+        let [v0, v1, v2] = tri.map(|v| mesh.vertices[v]);
+        let normal = (v1 - v0).cross(&(v2 - v0));
+        assert!(normal.dot(&((v0 + v1 + v2) / 3.0 - center)) > 0.0);
+    }
+
+    // The open boundary of a hemisphere is not filled
+    let mut hemisphere = sphere.clone();
+    hemisphere
+        .triangles
+        .retain(|tri| tri.iter().all(|&v| sphere.vertices[v].z >= center.z));
+    let num_triangles = hemisphere.triangles.len();
+    assert_eq!(fill_small_holes(&mut hemisphere, 10), 0);
+    assert_eq!(hemisphere.triangles.len(), num_triangles);
+}
+
+#[test]
+fn test_orient_consistently() {
+    let center = Vector3::new(1.0, -2.0, 0.5);
+    let sphere = TriMesh3d::<f64>::icosphere(&center, 1.0, 2);
+    let mut mesh = sphere.clone();
+    assert_eq!(mesh.orient_consistently(true), 0);
+    assert_eq!(mesh.triangles, sphere.triangles);
+
+    // Flip some triangles, including the first triangle that the traversal starts at
+    let inverted = [0, 7, 100, 101, 319];
+    for &t in inverted.iter() {
+        mesh.triangles[t].swap(0, 1);
+    }
+    let mut consistent = mesh.clone();
+    assert_eq!(
+        consistent.orient_consistently(false),
+        sphere.triangles.len() - inverted.len()
+    );
+    assert!(consistent.validity_report().is_valid());
+    assert!((consistent.volume() + sphere.volume()).abs() < 1e-12);
+
+    assert_eq!(mesh.orient_consistently(true), inverted.len());
+    assert!((mesh.volume() - sphere.volume()).abs() < 1e-12);
+    for tri in mesh.triangles.iter() {
+        let [v0, v1, v2] = tri.map(|v| mesh.vertices[v]);
+        let normal = (v1 - v0).cross(&(v2 - v0));
+        assert!(normal.dot(&((v0 + v1 + v2) / 3.0 - center)) > 0.0);
+    }
+
+    // Disconnected components are oriented independently, also if they are open or inverted as a whole
+    let mut inverted_sphere = TriMesh3d::<f64>::icosphere(&Vector3::new(5.0, 0.0, 0.0), 0.5, 1);
+    for tri in inverted_sphere.triangles.iter_mut() {
+        tri.swap(1, 2);
+    }
+    let mut hemisphere = TriMesh3d::<f64>::icosphere(&Vector3::new(-5.0, 0.0, 0.0), 0.5, 1);
+    hemisphere
+        .triangles
+        .retain(|tri| tri.iter().all(|&v| hemisphere.vertices[v].z >= 0.0));
+    hemisphere.triangles[3].swap(1, 2);
+    let num_hemisphere_triangles = hemisphere.triangles.len();
+
+    let mut components = sphere.clone();
+    components.append(&mut inverted_sphere);
+    components.append(&mut hemisphere);
+    assert_eq!(components.orient_consistently(true), 80 + 1);
+    let volumes = components
+        .split_components()
+        .iter()
+        .map(|component| component.volume())
+        .collect::<Vec<_>>();
+    let [sphere_volume, inverted_volume, hemisphere_volume] = volumes[..] else {
+        panic!("expected three components, got {}", volumes.len());
+    };
+    assert!((sphere_volume - sphere.volume()).abs() < 1e-12);
+    assert!(inverted_volume > 0.0);
+    assert!(hemisphere_volume > 0.0);
+    assert_eq!(
+        components.triangles.len(),
+        sphere.triangles.len() + 80 + num_hemisphere_triangles
+    );
+}
+
+#[test]
+fn test_to_tri_quad() {
+    let tolerance = 1.0f64.to_radians();
+
+    // A 4x4 grid of squares split into two triangles each, folded by 30 degrees along x = 2
+    let n = 4;
+    let mut grid = TriMesh3d::<f64>::default();
+    for j in 0..=n {
+        for i in 0..=n {
+            let x = i as f64;
+            let z = (x - 2.0).max(0.0) * 30.0f64.to_radians().tan();
+            grid.vertices.push(Vector3::new(x, j as f64, z));
+        }
+    }
+    let index = |i: usize, j: usize| j * (n + 1) + i;
+    for j in 0..n {
+        for i in 0..n {
+            grid.triangles
+                .push([index(i, j), index(i + 1, j), index(i + 1, j + 1)]);
+            grid.triangles
+                .push([index(i, j), index(i + 1, j + 1), index(i, j + 1)]);
+        }
+    }
+
+    let tri_quad = grid.to_tri_quad(tolerance);
+    assert_eq!(tri_quad.vertices, grid.vertices);
+    assert_eq!(tri_quad.cells.len(), n * n);
+    assert_eq!(tri_quad.num_quads(), n * n);
+    assert_eq!(
+        tri_quad.cells[0],
+        TriangleOrQuadCell::Quad([index(1, 1), index(0, 1), index(0, 0), index(1, 0)])
+    );
+    assert!((tri_quad.area() - grid.area()).abs() < 1e-12);
+    assert_eq!(tri_quad.to_tri_mesh().triangles.len(), grid.triangles.len());
+
+    // Lifting a vertex bends the squares around it, they are only merged with a larger tolerance
+    let mut bumpy = grid.clone();
+    bumpy.vertices[index(1, 2)].z += 0.3;
+    let tri_quad = bumpy.to_tri_quad(tolerance);
+    assert!(tri_quad.num_quads() < n * n);
+    assert_eq!(tri_quad.cells.len(), 2 * n * n - tri_quad.num_quads());
+    assert!((tri_quad.area() - bumpy.area()).abs() < 1e-12);
+    let tri_quad = bumpy.to_tri_quad(std::f64::consts::FRAC_PI_2);
+    assert!(tri_quad.num_quads() >= n * n - 2);
+    assert!((tri_quad.area() - bumpy.area()).abs() < 1e-12);
+
+    // Coplanar triangles that would form a non-convex quad or are oriented inconsistently are not merged
+    let dart = TriMesh3d::<f64> {
+        vertices: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(1.0, 1.0, 0.0),
+            Vector3::new(3.0, -0.1, 0.0),
+        ],
+        triangles: vec![[0, 1, 2], [1, 0, 3]],
+    };
+    assert_eq!(dart.to_tri_quad(tolerance).num_quads(), 0);
+    let mut square = dart.clone();
+    square.vertices[3] = Vector3::new(1.0, -1.0, 0.0);
+    assert_eq!(
+        square.to_tri_quad(tolerance).cells,
+        vec![TriangleOrQuadCell::Quad([0, 3, 1, 2])]
+    );
+    square.triangles[1].swap(1, 2);
+    assert_eq!(square.to_tri_quad(tolerance).num_quads(), 0);
+}
+
+#[test]
+fn test_mesh_transform() {
+    use nalgebra::{Translation3, UnitQuaternion};
+
+    let center = Vector3::new(1.0, -2.0, 0.5);
+    let sphere = TriMesh3d::<f64>::icosphere(&center, 2.0, 2);
+
+    let mut scaled = sphere.clone();
+    scaled.scale(0.5);
+    scaled.translate(&(-center * 0.5));
+    assert!((scaled.volume() - sphere.volume() / 8.0).abs() < 1e-12);
+    assert!(scaled
+        .vertices
+        .iter()
+        .all(|v| (v.norm() - 1.0).abs() < 1e-12));
+
+    // Normals are rotated with the mesh but not translated
+    let isometry = Isometry3::from_parts(
+        Translation3::new(10.0, 0.0, 0.0),
+        UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.3),
+    );
+    let mut mesh = MeshWithData::new(sphere.clone())
+        .with_vertex_normals()
+        .with_point_data(MeshAttribute::new_real_vector3(
+            "velocity",
+            vec![Vector3::x(); sphere.vertices.len()],
+        ));
+    mesh.transform(&isometry);
+    let new_center = isometry.transform_point(&center.into()).coords;
+    assert!((mesh.mesh.volume() - sphere.volume()).abs() < 1e-9);
+    match (
+        &mesh.point_attributes[0].data,
+        &mesh.point_attributes[1].data,
+    ) {
+        (AttributeData::Vector3Real(normals), AttributeData::Vector3Real(velocities)) => {
+            for (v, n) in mesh.mesh.vertices.iter().zip(normals.iter()) {
+                assert!((n.norm() - 1.0).abs() < 1e-12);
+                assert!(n.dot(&(v - new_center).normalize()) > 0.99);
+            }
+            assert!(velocities.iter().all(|v| *v == Vector3::x()));
+        }
+        data => panic!("unexpected attribute data {:?}", data),
+    }
+
+    #[cfg(feature = "parallel")]
+    {
+        let mut par_mesh = MeshWithData::new(sphere.clone()).with_vertex_normals();
+        par_mesh.par_transform(&isometry);
+        assert_eq!(par_mesh.mesh.vertices, mesh.mesh.vertices);
+
+        let mut par_scaled = sphere.clone();
+        par_scaled.par_scale(0.5);
+        par_scaled.par_translate(&(-center * 0.5));
+        assert_eq!(par_scaled.vertices, scaled.vertices);
+    }
+}
+
+#[test]
+fn test_mesh_with_data_append() {
+    let triangle = |offset: f64, id: u64| {
+        MeshWithData::new(TriMesh3d::<f64> {
+            vertices: vec![
+                Vector3::new(offset, 0.0, 0.0),
+                Vector3::new(offset + 1.0, 0.0, 0.0),
+                Vector3::new(offset, 1.0, 0.0),
+            ],
+            triangles: vec![[0, 1, 2]],
+        })
+        .with_point_data(MeshAttribute::new_real_scalar(
+            "temperature",
+            vec![offset; 3],
+        ))
+        .with_vertex_normals()
+        .with_cell_data(MeshAttribute::new("id", vec![id]))
+    };
+
+    // Appending to an empty mesh adopts the attributes
+    let mut mesh = MeshWithData::new(TriMesh3d::default());
+    mesh.append(triangle(0.0, 7)).unwrap();
+    mesh.append(MeshWithData::new(TriMesh3d::default()))
+        .unwrap();
+    assert_eq!(mesh.mesh.triangles, vec![[0, 1, 2]]);
+
+    // Attributes are matched by name, the order of this mesh is kept
+    let mut reordered = triangle(5.0, 8);
+    reordered.point_attributes.reverse();
+    mesh.append(reordered).unwrap();
+    mesh.append(triangle(10.0, 9)).unwrap();
+    assert_eq!(mesh.mesh.vertices.len(), 9);
+    assert_eq!(mesh.mesh.triangles, vec![[0, 1, 2], [3, 4, 5], [6, 7, 8]]);
+    assert_eq!(mesh.point_attributes[0].name, "temperature");
+    match &mesh.point_attributes[0].data {
+        AttributeData::ScalarReal(values) => assert_eq!(
+            values,
+            &vec![0.0, 0.0, 0.0, 5.0, 5.0, 5.0, 10.0, 10.0, 10.0]
+        ),
+        data => panic!("unexpected attribute data {:?}", data),
+    }
+    assert_eq!(mesh.point_attributes[1].data.len(), 9);
+    match &mesh.cell_attributes[0].data {
+        AttributeData::ScalarU64(ids) => assert_eq!(ids, &vec![7, 8, 9]),
+        data => panic!("unexpected attribute data {:?}", data),
+    }
+
+    // Mismatching attributes are reported and leave the mesh unchanged
+    let mut missing = triangle(0.0, 1);
+    missing.point_attributes.remove(0);
+    assert_eq!(
+        mesh.append(missing).unwrap_err(),
+        MeshAppendError::PointAttributeMissing("temperature".to_string())
+    );
+    let mut extra = triangle(0.0, 1);
+    extra
+        .cell_attributes
+        .push(MeshAttribute::new_real_scalar("quality", vec![1.0]));
+    assert_eq!(
+        mesh.append(extra).unwrap_err(),
+        MeshAppendError::CellAttributeMissing("quality".to_string())
+    );
+    let mut wrong_type = triangle(0.0, 1);
+    wrong_type.cell_attributes[0] = MeshAttribute::new_real_scalar("id", vec![1.0]);
+    let err = mesh.append(wrong_type).unwrap_err();
+    assert_eq!(
+        err,
+        MeshAppendError::CellAttributeTypeMismatch("id".to_string())
+    );
+    assert_eq!(err.attribute_name(), "id");
+    assert_eq!(mesh.mesh.vertices.len(), 9);
+    assert_eq!(mesh.point_attributes[0].data.len(), 9);
+    assert_eq!(mesh.cell_attributes[0].data.len(), 3);
+}
+
+impl<R: Real> LineMesh2d<R> {
+    /// Clears the vertex and line storage, preserves allocated memory
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.lines.clear();
+    }
+
+    /// Returns the total length of all line segments of the mesh
+    pub fn total_length(&self) -> R {
+        self.lines
+            .iter()
+            .map(|line| (self.vertices[line[1]] - self.vertices[line[0]]).norm())
+            .fold(R::zero(), |acc, length| acc + length)
+    }
+}
+
+/// Wrapper type for meshes with attached point or cell data
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "R: serde::Serialize, MeshT: serde::Serialize",
+        deserialize = "R: serde::de::DeserializeOwned, MeshT: serde::de::DeserializeOwned"
+    ))
+)]
+pub struct MeshWithData<R: Real, MeshT: Mesh3d<R>> {
+    /// The mesh geometry itself
+    pub mesh: MeshT,
+    /// Data attached to each vertex or point of the mesh
+    pub point_attributes: Vec<MeshAttribute<R>>,
+    /// Data attached to each cell of the mesh
+    pub cell_attributes: Vec<MeshAttribute<R>>,
+}
+
+/// Returns an mesh data wrapper with a default mesh and without attached attributes
+impl<R: Real, MeshT: Mesh3d<R> + Default> Default for MeshWithData<R, MeshT> {
+    fn default() -> Self {
+        Self::new(MeshT::default())
+    }
+}
+
+impl<R: Real, MeshT: Mesh3d<R>> MeshWithData<R, MeshT> {
+    /// Wraps the given mesh such that point and cell data can be attached
+    pub fn new(mesh: MeshT) -> Self {
+        Self {
+            mesh,
+            point_attributes: Vec::new(),
+            cell_attributes: Vec::new(),
+        }
+    }
+
+    /// Attaches an attribute to the points of the mesh, panics if the length of the data does not match the mesh's number of points
+    pub fn with_point_data(mut self, point_attribute: impl Into<MeshAttribute<R>>) -> Self {
+        let point_attribute = point_attribute.into();
+        assert_eq!(
+            point_attribute.data.len(),
+            self.mesh.vertices().len(),
+            "number of values of the point attribute \"{}\" does not match the number of vertices",
+            point_attribute.name
+        );
+        self.point_attributes.push(point_attribute);
+        self
+    }
+
+    /// Attaches an attribute to the cells of the mesh, panics if the length of the data does not match the mesh's number of cells
+    pub fn with_cell_data(mut self, cell_attribute: impl Into<MeshAttribute<R>>) -> Self {
+        let cell_attribute = cell_attribute.into();
+        assert_eq!(
+            cell_attribute.data.len(),
+            self.mesh.cells().len(),
+            "number of values of the cell attribute \"{}\" does not match the number of cells",
+            cell_attribute.name
+        );
+        self.cell_attributes.push(cell_attribute);
+        self
+    }
+}
+
+impl<R: Real> MeshWithData<R, TriMesh3d<R>> {
+    /// Attaches the area weighted vertex normals of the mesh as point attribute `"normals"`, see [`TriMesh3d::vertex_normals`]
+    pub fn with_vertex_normals(self) -> Self {
+        let normals = self.mesh.vertex_normals();
+        self.with_point_data(MeshAttribute::new_real_vector3("normals", normals))
+    }
+
+    /// Applies the rigid transformation to the mesh and rotates the `"normals"` point attribute accordingly, see [`TriMesh3d::transform`]
+    ///
+    /// Only the normals are rotated, all other attributes are not modified. Uniform scaling and translation of the mesh
+    /// (with [`TriMesh3d::scale`] with a positive factor and [`TriMesh3d::translate`]) do not change the normals.
+    pub fn transform(&mut self, isometry: &Isometry3<R>) {
+        self.transform_impl(isometry, false);
+    }
+
+    /// Applies the rigid transformation to the mesh and rotates the `"normals"` point attribute accordingly (parallelized version), see [`Self::transform`]
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+    pub fn par_transform(&mut self, isometry: &Isometry3<R>) {
+        self.transform_impl(isometry, true);
+    }
+
+    fn transform_impl(&mut self, isometry: &Isometry3<R>, parallel: bool) {
+        transform::transform_points_impl(&mut self.mesh.vertices, isometry, parallel);
+        for attribute in self.point_attributes.iter_mut() {
+            if let (true, AttributeData::Vector3Real(normals)) =
+                (attribute.name == "normals", &mut attribute.data)
+            {
+                transform::for_each_point_impl(normals, parallel, |n| *n = isometry.rotation * *n);
+            }
+        }
+    }
+
+    /// Reverses the orientation of all triangles and negates the `"normals"` point and cell attributes, see [`TriMesh3d::flip_orientation`]
+    pub fn flip_orientation(&mut self) {
+        self.mesh.flip_orientation();
+        for attribute in self
+            .point_attributes
+            .iter_mut()
+            .chain(self.cell_attributes.iter_mut())
+        {
+            if let (true, AttributeData::Vector3Real(normals)) =
+                (attribute.name == "normals", &mut attribute.data)
+            {
+                for n in normals.iter_mut() {
+                    *n = -*n;
+                }
+            }
+        }
+    }
+
+    /// Appends the vertices, triangles and attributes of the other mesh to this mesh, see [`TriMesh3d::append`]
+    ///
+    /// The values of every point and cell attribute of the other mesh are appended to the attribute of this mesh with
+    /// the same name, such that all attributes stay aligned with the vertices and triangles. The order of the attributes
+    /// of this mesh is preserved, so repeatedly appending meshes gives the same result independent of the attribute order
+    /// of the appended meshes. If this mesh is empty and has no attributes, the attributes of the other mesh are adopted,
+    /// appending an empty mesh without attributes does not change this mesh.
+    ///
+    /// Otherwise, both meshes have to have attributes with the same names and types, else an error is returned and this mesh is left unchanged.
+    pub fn append(
+        &mut self,
+        mut other: MeshWithData<R, TriMesh3d<R>>,
+    ) -> Result<(), MeshAppendError> {
+        let is_empty = |mesh: &Self| {
+            mesh.mesh.vertices.is_empty()
+                && mesh.mesh.triangles.is_empty()
+                && mesh.point_attributes.is_empty()
+                && mesh.cell_attributes.is_empty()
+        };
+        if is_empty(&other) {
+            return Ok(());
+        }
+        if is_empty(self) {
+            *self = other;
+            return Ok(());
+        }
+
+        check_matching_attributes(
+            &self.point_attributes,
+            &other.point_attributes,
+            MeshAppendError::PointAttributeMissing,
+            MeshAppendError::PointAttributeTypeMismatch,
+        )?;
+        check_matching_attributes(
+            &self.cell_attributes,
+            &other.cell_attributes,
+            MeshAppendError::CellAttributeMissing,
+            MeshAppendError::CellAttributeTypeMismatch,
+        )?;
+
+        self.mesh.append(&mut other.mesh);
+        for (attributes, other_attributes) in [
+            (&mut self.point_attributes, &mut other.point_attributes),
+            (&mut self.cell_attributes, &mut other.cell_attributes),
+        ] {
+            for attribute in attributes.iter_mut() {
+                let other_attribute = other_attributes
+                    .iter_mut()
+                    .find(|a| a.name == attribute.name)
+                    .expect("attributes were checked before");
+                attribute.data.append(&mut other_attribute.data);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that both sets of attributes have the same names and types, the attributes of `attributes` are checked first in their order
+fn check_matching_attributes<R: Real>(
+    attributes: &[MeshAttribute<R>],
+    other_attributes: &[MeshAttribute<R>],
+    missing: fn(String) -> MeshAppendError,
+    type_mismatch: fn(String) -> MeshAppendError,
+) -> Result<(), MeshAppendError> {
+    for attribute in attributes {
+        match other_attributes.iter().find(|a| a.name == attribute.name) {
+            None => return Err(missing(attribute.name.clone())),
+            Some(other) if !other.data.has_same_type(&attribute.data) => {
+                return Err(type_mismatch(attribute.name.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some(extra) = other_attributes
+        .iter()
+        .find(|a| !attributes.iter().any(|b| b.name == a.name))
+    {
+        return Err(missing(extra.name.clone()));
+    }
+    Ok(())
+}
+
+impl<R: Real> MeshAttribute<R> {
+    /// Creates a new named mesh attribute with the given data
+    pub fn new<S: Into<String>>(name: S, data: impl Into<AttributeData<R>>) -> Self {
+        Self {
+            name: name.into(),
+            data: data.into(),
+        }
+    }
+
+    /// Creates a new named mesh attribute with scalar values implementing the [`Real`](crate::Real) trait
+    pub fn new_real_scalar<S: Into<String>>(name: S, data: impl Into<Vec<R>>) -> Self {
+        Self {
+            name: name.into(),
+            data: AttributeData::ScalarReal(data.into()),
+        }
+    }
+
+    /// Creates a new named mesh attribute with scalar values implementing the [`Real`](crate::Real) trait
+    pub fn new_real_vector3<S: Into<String>>(name: S, data: impl Into<Vec<Vector3<R>>>) -> Self {
+        Self {
+            name: name.into(),
+            data: AttributeData::Vector3Real(data.into()),
+        }
+    }
+
+    /// Creates a new named mesh attribute with values consisting of the given number of components stored consecutively in a flat array
+    pub fn new_real_vector<S: Into<String>>(
+        name: S,
+        num_components: usize,
+        values: impl Into<Vec<R>>,
+    ) -> Self {
+        let values = values.into();
+        assert!(num_components > 0 && values.len() % num_components == 0);
+        Self {
+            name: name.into(),
+            data: AttributeData::VectorReal {
+                num_components,
+                values,
+            },
+        }
+    }
+
+    /// Converts the mesh attribute to a [`vtkio::model::Attribute`](https://docs.rs/vtkio/0.6.*/vtkio/model/enum.Attribute.html)
+    #[cfg(feature = "vtk_extras")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "vtk_extras")))]
+    fn to_vtk_attribute(&self) -> Attribute {
+        match &self.data {
+            AttributeData::ScalarU64(u64_vec) => {
+                Attribute::scalars(&self.name, 1).with_data(u64_vec.clone())
+            }
+            AttributeData::ScalarReal(real_vec) => {
+                Attribute::scalars(&self.name, 1).with_data(real_vec.clone())
+            }
+            AttributeData::Vector3Real(vec3r_vec) => Attribute::scalars(&self.name, 3)
+                .with_data(vec3r_vec.iter().flatten().copied().collect::<Vec<R>>()),
+            AttributeData::VectorReal {
+                num_components,
+                values,
+            } => Attribute::scalars(&self.name, *num_components as u32).with_data(values.clone()),
+        }
+    }
+}
+
+impl<R: Real> AttributeData<R> {
+    /// Returns the number of entries in the data set (e.g. the number of vertices or cells it belongs to)
+    pub fn len(&self) -> usize {
+        match self {
+            AttributeData::ScalarU64(v) => v.len(),
+            AttributeData::ScalarReal(v) => v.len(),
+            AttributeData::Vector3Real(v) => v.len(),
+            AttributeData::VectorReal {
+                num_components,
+                values,
+            } => values.len() / num_components,
+        }
+    }
+
+    /// Returns whether the data set has no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether both data sets have the same type (and number of components)
+    pub(crate) fn has_same_type(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                AttributeData::VectorReal { num_components, .. },
+                AttributeData::VectorReal {
+                    num_components: other_num_components,
+                    ..
+                },
+            ) => num_components == other_num_components,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+
+    /// Moves all entries of the other data set to the end of this data set, panics if the data sets have different types
+    pub(crate) fn append(&mut self, other: &mut Self) {
+        assert!(
+            self.has_same_type(other),
+            "Attribute data can only be appended to data of the same type"
+        );
+        match (self, other) {
+            (AttributeData::ScalarU64(v), AttributeData::ScalarU64(o)) => v.append(o),
+            (AttributeData::ScalarReal(v), AttributeData::ScalarReal(o)) => v.append(o),
+            (AttributeData::Vector3Real(v), AttributeData::Vector3Real(o)) => v.append(o),
+            (
+                AttributeData::VectorReal { values, .. },
+                AttributeData::VectorReal { values: o, .. },
+            ) => values.append(o),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns a new data set containing the entries at the given indices, e.g. to assign per particle values to the vertices of the splash particle spheres
+    pub fn select(&self, indices: &[usize]) -> Self {
+        match self {
+            AttributeData::ScalarU64(v) => {
+                AttributeData::ScalarU64(indices.iter().map(|&i| v[i]).collect())
+            }
+            AttributeData::ScalarReal(v) => {
+                AttributeData::ScalarReal(indices.iter().map(|&i| v[i]).collect())
+            }
+            AttributeData::Vector3Real(v) => {
+                AttributeData::Vector3Real(indices.iter().map(|&i| v[i]).collect())
+            }
+            AttributeData::VectorReal {
+                num_components,
+                values,
+            } => AttributeData::VectorReal {
+                num_components: *num_components,
+                values: indices
+                    .iter()
+                    .flat_map(|&i| &values[i * num_components..(i + 1) * num_components])
+                    .copied()
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl<R: Real, V: Into<Vec<u64>>> From<V> for AttributeData<R> {
     fn from(data: V) -> Self {
         Self::ScalarU64(data.into())
     }
@@ -621,13 +2765,13 @@ where
 #[cfg_attr(doc_cfg, doc(cfg(feature = "vtk_extras")))]
 pub mod vtk_helper {
     use vtkio::model::{
-        Attributes, CellType, Cells, DataSet, UnstructuredGridPiece, VertexNumbers,
+        Attributes, CellType, Cells, DataSet, PolyDataPiece, UnstructuredGridPiece, VertexNumbers,
     };
     use vtkio::IOBuffer;
 
     use super::{
-        CellConnectivity, HexCell, HexMesh3d, Mesh3d, PointCell, PointCloud3d, Real, TriMesh3d,
-        TriangleCell,
+        CellConnectivity, HexCell, HexMesh3d, LineMesh2d, Mesh3d, MixedTriQuadMesh3d, PointCell,
+        PointCloud3d, Real, TriMesh3d, TriangleCell, TriangleOrQuadCell,
     };
 
     /// Trait that can be implemented by mesh cells to return the corresponding [`vtkio::model::CellType`](https://docs.rs/vtkio/0.6.*/vtkio/model/enum.CellType.html)
@@ -635,6 +2779,10 @@ pub mod vtk_helper {
     pub trait HasVtkCellType {
         /// Returns the corresponding [`vtkio::model::CellType`](https://docs.rs/vtkio/0.6.*/vtkio/model/enum.CellType.html) of the cell
         fn vtk_cell_type() -> CellType;
+        /// Returns the [`vtkio::model::CellType`](https://docs.rs/vtkio/0.6.*/vtkio/model/enum.CellType.html) of this cell, only differs from [`Self::vtk_cell_type`] for meshes with different cell types
+        fn cell_type(&self) -> CellType {
+            Self::vtk_cell_type()
+        }
     }
 
     #[cfg_attr(doc_cfg, doc(cfg(feature = "vtk_extras")))]
@@ -658,6 +2806,20 @@ pub mod vtk_helper {
         }
     }
 
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "vtk_extras")))]
+    impl HasVtkCellType for TriangleOrQuadCell {
+        fn vtk_cell_type() -> CellType {
+            CellType::Quad
+        }
+
+        fn cell_type(&self) -> CellType {
+            match self {
+                TriangleOrQuadCell::Tri(_) => CellType::Triangle,
+                TriangleOrQuadCell::Quad(_) => CellType::Quad,
+            }
+        }
+    }
+
     /// Converts any supported mesh to a [`vtkio::model::UnstructuredGridPiece`](https://docs.rs/vtkio/0.6.*/vtkio/model/struct.UnstructuredGridPiece.html)
     #[cfg_attr(doc_cfg, doc(cfg(feature = "vtk_extras")))]
     pub fn mesh_to_unstructured_grid<'a, R, MeshT>(mesh: &'a MeshT) -> UnstructuredGridPiece
@@ -678,13 +2840,13 @@ pub mod vtk_helper {
         let vertices = {
             let mut vertices = Vec::with_capacity(mesh.cells().len() * (vertices_per_cell + 1));
             for cell in mesh.cells().iter() {
-                vertices.push(3);
+                vertices.push(cell.num_cell_vertices() as u32);
                 cell.for_each_vertex(|v| vertices.push(v as u32));
             }
             vertices
         };
 
-        let cell_types = vec![<MeshT::Cell as HasVtkCellType>::vtk_cell_type(); mesh.cells().len()];
+        let cell_types = mesh.cells().iter().map(|cell| cell.cell_type()).collect();
 
         new_unstructured_grid_piece(points, vertices, cell_types)
     }
@@ -711,6 +2873,17 @@ pub mod vtk_helper {
         }
     }
 
+    /// Creates a [`vtkio::model::UnstructuredGridPiece`](https://docs.rs/vtkio/0.6.*/vtkio/model/struct.UnstructuredGridPiece.html) representing this mesh
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "vtk_extras")))]
+    impl<'a, R> From<&'a MixedTriQuadMesh3d<R>> for UnstructuredGridPiece
+    where
+        R: Real,
+    {
+        fn from(mesh: &'a MixedTriQuadMesh3d<R>) -> Self {
+            mesh_to_unstructured_grid(mesh)
+        }
+    }
+
     /// Creates a [`vtkio::model::UnstructuredGridPiece`](https://docs.rs/vtkio/0.6.*/vtkio/model/struct.UnstructuredGridPiece.html) representing this point cloud
     #[cfg_attr(doc_cfg, doc(cfg(feature = "vtk_extras")))]
     impl<'a, R> From<&'a PointCloud3d<R>> for UnstructuredGridPiece
@@ -738,6 +2911,14 @@ pub mod vtk_helper {
         }
     }
 
+    /// Creates a [`vtkio::model::UnstructuredGridPiece`](https://docs.rs/vtkio/0.6.*/vtkio/model/struct.UnstructuredGridPiece.html) representing this mesh and wraps it into a [`vtkio::model::DataSet`](https://docs.rs/vtkio/0.6.*/vtkio/model/enum.DataSet.html)
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "vtk_extras")))]
+    impl<R: Real> Into<DataSet> for &MixedTriQuadMesh3d<R> {
+        fn into(self) -> DataSet {
+            DataSet::inline(UnstructuredGridPiece::from(self))
+        }
+    }
+
     /// Creates a [`vtkio::model::UnstructuredGridPiece`](https://docs.rs/vtkio/0.6.*/vtkio/model/struct.UnstructuredGridPiece.html) representing this point cloud and wraps it into a [`vtkio::model::DataSet`](https://docs.rs/vtkio/0.6.*/vtkio/model/enum.DataSet.html)
     #[cfg_attr(doc_cfg, doc(cfg(feature = "vtk_extras")))]
     impl<R: Real> Into<DataSet> for &PointCloud3d<R> {
@@ -746,6 +2927,54 @@ pub mod vtk_helper {
         }
     }
 
+    /// Creates a [`vtkio::model::PolyDataPiece`](https://docs.rs/vtkio/0.6.*/vtkio/model/struct.PolyDataPiece.html) with line cells representing this mesh, the vertices are embedded into the xy-plane
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "vtk_extras")))]
+    impl<'a, R> From<&'a LineMesh2d<R>> for PolyDataPiece
+    where
+        R: Real,
+    {
+        fn from(mesh: &'a LineMesh2d<R>) -> Self {
+            let points = {
+                let mut points: Vec<R> = Vec::with_capacity(mesh.vertices.len() * 3);
+                for v in mesh.vertices.iter() {
+                    points.extend(v.as_slice());
+                    points.push(R::zero());
+                }
+                points
+            };
+
+            let vertices = {
+                let mut vertices = Vec::with_capacity(mesh.lines.len() * 3);
+                for line in mesh.lines.iter() {
+                    vertices.push(2);
+                    vertices.push(line[0] as u32);
+                    vertices.push(line[1] as u32);
+                }
+                vertices
+            };
+
+            PolyDataPiece {
+                points: points.into(),
+                verts: None,
+                lines: Some(VertexNumbers::Legacy {
+                    num_cells: mesh.lines.len() as u32,
+                    vertices,
+                }),
+                polys: None,
+                strips: None,
+                data: Attributes::new(),
+            }
+        }
+    }
+
+    /// Creates a [`vtkio::model::PolyDataPiece`](https://docs.rs/vtkio/0.6.*/vtkio/model/struct.PolyDataPiece.html) representing this mesh and wraps it into a [`vtkio::model::DataSet`](https://docs.rs/vtkio/0.6.*/vtkio/model/enum.DataSet.html)
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "vtk_extras")))]
+    impl<R: Real> Into<DataSet> for &LineMesh2d<R> {
+        fn into(self) -> DataSet {
+            DataSet::inline(PolyDataPiece::from(self))
+        }
+    }
+
     fn new_unstructured_grid_piece<B: Into<IOBuffer>>(
         points: B,
         vertices: Vec<u32>,
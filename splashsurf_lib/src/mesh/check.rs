@@ -0,0 +1,236 @@
+//! Validity checks of triangle meshes, e.g. before passing a reconstructed surface to other tools
+//!
+//! The [`MeshValidityReport`] returned by [`TriMesh3d::validity_report`] counts the topological problems
+//! of a mesh that typically cause trouble in downstream tools (non-manifold and boundary edges, invalid indices,
+//! duplicate triangles and isolated vertices) and stores the first few occurrences of every problem for debugging.
+
+use crate::mesh::TriMesh3d;
+use crate::{profile, Real};
+use std::fmt;
+
+/// Maximum number of examples stored per kind of problem in a [`MeshValidityReport`]
+pub const MAX_EXAMPLES: usize = 10;
+
+/// Number of occurrences of one kind of problem in a mesh together with the first few examples
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProblemOccurrences<T> {
+    /// Total number of occurrences
+    pub count: usize,
+    /// The first (at most [`MAX_EXAMPLES`]) occurrences in ascending order
+    pub examples: Vec<T>,
+}
+
+impl<T> Default for ProblemOccurrences<T> {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            examples: Vec::new(),
+        }
+    }
+}
+
+impl<T> ProblemOccurrences<T> {
+    /// Returns whether the problem does not occur at all
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    fn push(&mut self, occurrence: T) {
+        self.count += 1;
+        if self.examples.len() < MAX_EXAMPLES {
+            self.examples.push(occurrence);
+        }
+    }
+}
+
+/// Report of the topological problems of a triangle mesh, see [`TriMesh3d::validity_report`]
+///
+/// Edges are identified by their vertex indices in ascending order, triangles and vertices by their index.
+/// Triangles with out-of-range vertex indices are ignored for all other checks.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MeshValidityReport {
+    /// Edges that are shared by more than two triangles
+    pub non_manifold_edges: ProblemOccurrences<[usize; 2]>,
+    /// Edges that are part of only one triangle, i.e. the boundaries of holes or of an open surface
+    pub boundary_edges: ProblemOccurrences<[usize; 2]>,
+    /// Triangles with at least one vertex index that is not a valid index into the vertices of the mesh
+    pub out_of_range_indices: ProblemOccurrences<usize>,
+    /// Triangles that connect the same vertices as a triangle with a smaller index (independent of their orientation)
+    pub duplicate_triangles: ProblemOccurrences<usize>,
+    /// Vertices that are not referenced by any triangle
+    pub isolated_vertices: ProblemOccurrences<usize>,
+}
+
+impl MeshValidityReport {
+    /// Checks the given mesh for all problems listed in the report
+    pub fn from_mesh<R: Real>(mesh: &TriMesh3d<R>) -> Self {
+        profile!("MeshValidityReport::from_mesh");
+
+        let mut report = Self::default();
+        let num_vertices = mesh.vertices.len();
+
+        let mut is_referenced = vec![false; num_vertices];
+        let mut edges = Vec::with_capacity(3 * mesh.triangles.len());
+        let mut sorted_triangles = Vec::with_capacity(mesh.triangles.len());
+        for (tri_idx, tri) in mesh.triangles.iter().enumerate() {
+            if tri.iter().any(|&v| v >= num_vertices) {
+                report.out_of_range_indices.push(tri_idx);
+                continue;
+            }
+
+            for i in 0..3 {
+                let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                is_referenced[a] = true;
+                if a != b {
+                    edges.push([a.min(b), a.max(b)]);
+                }
+            }
+
+            let mut sorted_tri = *tri;
+            sorted_tri.sort_unstable();
+            sorted_triangles.push((sorted_tri, tri_idx));
+        }
+
+        // Count the triangles per edge by sorting the edges
+        edges.sort_unstable();
+        for group in edges.chunk_by(|a, b| a == b) {
+            match group.len() {
+                1 => report.boundary_edges.push(group[0]),
+                2 => {}
+                _ => report.non_manifold_edges.push(group[0]),
+            }
+        }
+
+        // All but the first triangle of every group with the same vertices are duplicates
+        sorted_triangles.sort_unstable();
+        let mut duplicate_triangles = sorted_triangles
+            .chunk_by(|(a, _), (b, _)| a == b)
+            .flat_map(|group| group[1..].iter().map(|&(_, tri_idx)| tri_idx))
+            .collect::<Vec<_>>();
+        duplicate_triangles.sort_unstable();
+        for tri_idx in duplicate_triangles {
+            report.duplicate_triangles.push(tri_idx);
+        }
+
+        for (v, _) in is_referenced
+            .iter()
+            .enumerate()
+            .filter(|(_, &is_referenced)| !is_referenced)
+        {
+            report.isolated_vertices.push(v);
+        }
+
+        report
+    }
+
+    /// Returns whether none of the problems occur, i.e. the mesh is a closed two-manifold without unused vertices
+    pub fn is_valid(&self) -> bool {
+        self.counters().iter().all(|&(_, count)| count == 0)
+    }
+
+    /// Returns the names and numbers of occurrences of all problems
+    pub fn counters(&self) -> [(&'static str, usize); 5] {
+        self.problems().map(|(name, count, _)| (name, count))
+    }
+
+    /// Returns the names, numbers of occurrences and examples of all problems
+    fn problems(&self) -> [(&'static str, usize, &dyn fmt::Debug); 5] {
+        [
+            (
+                "non-manifold edges",
+                self.non_manifold_edges.count,
+                &self.non_manifold_edges.examples,
+            ),
+            (
+                "boundary edges",
+                self.boundary_edges.count,
+                &self.boundary_edges.examples,
+            ),
+            (
+                "triangles with out-of-range indices",
+                self.out_of_range_indices.count,
+                &self.out_of_range_indices.examples,
+            ),
+            (
+                "duplicate triangles",
+                self.duplicate_triangles.count,
+                &self.duplicate_triangles.examples,
+            ),
+            (
+                "isolated vertices",
+                self.isolated_vertices.count,
+                &self.isolated_vertices.examples,
+            ),
+        ]
+    }
+}
+
+impl fmt::Display for MeshValidityReport {
+    /// Lists all problems that occur with their first examples, e.g. `2 boundary edges (e.g. [[0, 1], [1, 2]])`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "no problems");
+        }
+
+        let mut first = true;
+        for (name, count, examples) in self.problems() {
+            if count > 0 {
+                if !first {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{} {} (e.g. {:?})", count, name, examples)?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_validity_report() {
+    use nalgebra::Vector3;
+
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 2);
+    let report = sphere.validity_report();
+    assert!(report.is_valid());
+    assert_eq!(report.to_string(), "no problems");
+
+    // A fin attached to an edge of the sphere, a duplicate (inverted) triangle, an invalid index and an unused vertex
+    let mut mesh = sphere.clone();
+    let [a, b, _] = mesh.triangles[0];
+    let fin_vertex = mesh.vertices.len();
+    mesh.vertices.push(Vector3::new(2.0, 0.0, 0.0));
+    mesh.vertices.push(Vector3::new(3.0, 0.0, 0.0));
+    mesh.triangles.push([a, b, fin_vertex]);
+    let [c, d, e] = mesh.triangles[5];
+    mesh.triangles.push([c, e, d]);
+    mesh.triangles.push([0, 1, fin_vertex + 2]);
+
+    let report = mesh.validity_report();
+    assert!(!report.is_valid());
+    // The edge of the sphere with the fin and the three edges of the duplicated triangle are shared by three triangles
+    assert_eq!(report.non_manifold_edges.count, 4);
+    assert!(report
+        .non_manifold_edges
+        .examples
+        .contains(&[a.min(b), a.max(b)]));
+    assert_eq!(report.boundary_edges.count, 2);
+    assert_eq!(report.out_of_range_indices.examples, vec![322]);
+    assert_eq!(report.duplicate_triangles.examples, vec![321]);
+    assert_eq!(report.isolated_vertices.examples, vec![fin_vertex + 1]);
+    assert!(report
+        .to_string()
+        .starts_with("4 non-manifold edges (e.g. [["));
+
+    // Only the first examples are stored
+    let mut unused_vertices = sphere.clone();
+    unused_vertices
+        .vertices
+        .extend(std::iter::repeat_n(Vector3::zeros(), 2 * MAX_EXAMPLES));
+    let report = unused_vertices.validity_report();
+    assert_eq!(report.isolated_vertices.count, 2 * MAX_EXAMPLES);
+    assert_eq!(
+        report.isolated_vertices.examples,
+        (162..162 + MAX_EXAMPLES).collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,498 @@
+//! Clipping of triangle meshes against axis-aligned boxes, e.g. to cut a reconstructed surface to a region of interest
+//!
+//! The [`clip_aabb`] function clips every triangle that intersects a face of the box against the six planes of the box
+//! (Sutherland–Hodgman). Vertices are inserted where the edges of the triangles cross the faces of the box and the
+//! remaining convex polygons are triangulated again. Triangles that are completely inside of the box are kept unchanged,
+//! triangles that are completely outside are removed. New vertices on the edges of the input mesh are shared by the
+//! adjacent triangles such that the clipped surface is only opened along the faces of the box.
+//!
+//! Attributes attached to the vertices or triangles of a [`MeshWithData`] can be carried along with
+//! [`clip_aabb_with_data`], the values of new vertices are interpolated from the vertices of the clipped triangle.
+
+use crate::mesh::{AttributeData, MeshWithData, TriMesh3d};
+use crate::{new_map, profile, AxisAlignedBoundingBox3d, MapType, Real};
+use nalgebra::Vector3;
+
+/// Origin in the input mesh of all vertices and triangles of a mesh clipped with [`clip_aabb`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClipMap<R: Real> {
+    /// Vertices of the input mesh and their weights that every vertex of the clipped mesh is interpolated from
+    ///
+    /// Vertices of the input mesh that are kept have a weight of one for themselves, unused entries have a weight of zero.
+    pub vertices: Vec<[(usize, R); 3]>,
+    /// Index in the input mesh of the triangle that every triangle of the clipped mesh was cut from
+    pub triangles: Vec<usize>,
+}
+
+/// Clips the mesh against the faces of the given box, inserting new vertices on the faces of the box
+///
+/// The remaining vertices of the input mesh come first and keep their relative order, followed by the new vertices.
+/// Vertices that are not referenced by any remaining triangle are removed. Returns the origin of all vertices and
+/// triangles of the clipped mesh in the input mesh, e.g. to interpolate additional data attached to the mesh.
+pub fn clip_aabb<R: Real>(
+    mesh: &mut TriMesh3d<R>,
+    aabb: &AxisAlignedBoundingBox3d<R>,
+) -> ClipMap<R> {
+    profile!("clip_aabb");
+
+    let mut clipping = Clipping::new(aabb, &mesh.vertices);
+    let mut triangles = Vec::new();
+    let mut triangle_origins = Vec::new();
+
+    let mut polygon = Vec::with_capacity(9);
+    let mut clipped_polygon = Vec::with_capacity(9);
+    for (tri_idx, tri) in mesh.triangles.iter().enumerate() {
+        let is_outside =
+            |plane: usize, v: usize| clipping.signed_distance(plane, &mesh.vertices[v]) < R::zero();
+
+        if (0..6).all(|plane| tri.iter().all(|&v| !is_outside(plane, v))) {
+            triangles.push(tri.map(VertexRef::Input));
+            triangle_origins.push(tri_idx);
+            continue;
+        }
+        if (0..6).any(|plane| tri.iter().all(|&v| is_outside(plane, v))) {
+            continue;
+        }
+
+        polygon.clear();
+        polygon.extend((0..3).map(|i| {
+            let mut weights = [R::zero(); 3];
+            weights[i] = R::one();
+            PolygonVertex {
+                vertex: VertexRef::Input(tri[i]),
+                position: mesh.vertices[tri[i]],
+                weights,
+            }
+        }));
+
+        for plane in 0..6 {
+            clipped_polygon.clear();
+            for (i, p) in polygon.iter().enumerate() {
+                let q = &polygon[(i + 1) % polygon.len()];
+                let dp = clipping.signed_distance(plane, &p.position);
+                let dq = clipping.signed_distance(plane, &q.position);
+
+                if dp >= R::zero() {
+                    clipped_polygon.push(*p);
+                }
+                // Vertices on the plane are kept as they are, so only strict sign changes result in new vertices
+                if (dp > R::zero() && dq < R::zero()) || (dp < R::zero() && dq > R::zero()) {
+                    clipped_polygon.push(clipping.intersection(tri, plane, p, q));
+                }
+            }
+            std::mem::swap(&mut polygon, &mut clipped_polygon);
+
+            if polygon.len() < 3 {
+                break;
+            }
+        }
+
+        // The clipped triangle is convex and can be triangulated as a fan
+        for i in 1..polygon.len().saturating_sub(1) {
+            triangles.push([polygon[0].vertex, polygon[i].vertex, polygon[i + 1].vertex]);
+            triangle_origins.push(tri_idx);
+        }
+    }
+
+    // Compact the referenced vertices, the input vertices first
+    let num_input_vertices = mesh.vertices.len();
+    let ref_index = |vertex: VertexRef| match vertex {
+        VertexRef::Input(v) => v,
+        VertexRef::New(v) => num_input_vertices + v,
+    };
+    let mut new_indices = vec![usize::MAX; num_input_vertices + clipping.new_vertices.len()];
+    for tri in &triangles {
+        for &vertex in tri {
+            new_indices[ref_index(vertex)] = 0;
+        }
+    }
+
+    let mut vertices = Vec::new();
+    let mut vertex_origins = Vec::new();
+    for (v, new_index) in new_indices.iter_mut().enumerate() {
+        if *new_index == usize::MAX {
+            continue;
+        }
+
+        *new_index = vertices.len();
+        if v < num_input_vertices {
+            vertices.push(mesh.vertices[v]);
+            vertex_origins.push([(v, R::one()), (v, R::zero()), (v, R::zero())]);
+        } else {
+            vertices.push(clipping.new_vertices[v - num_input_vertices]);
+            vertex_origins.push(clipping.new_vertex_weights[v - num_input_vertices]);
+        }
+    }
+
+    mesh.vertices = vertices;
+    mesh.triangles = triangles
+        .iter()
+        .map(|tri| tri.map(|vertex| new_indices[ref_index(vertex)]))
+        .collect();
+
+    ClipMap {
+        vertices: vertex_origins,
+        triangles: triangle_origins,
+    }
+}
+
+/// Clips the mesh like [`clip_aabb`] and interpolates the point attributes and selects the cell attributes of the clipped mesh
+///
+/// Integer point attributes (e.g. ids) cannot be interpolated and are taken from the input vertex with the largest weight.
+/// Interpolated `"normals"` are normalized again.
+pub fn clip_aabb_with_data<R: Real>(
+    mesh: &mut MeshWithData<R, TriMesh3d<R>>,
+    aabb: &AxisAlignedBoundingBox3d<R>,
+) -> ClipMap<R> {
+    let clip_map = clip_aabb(&mut mesh.mesh, aabb);
+    for attribute in mesh.point_attributes.iter_mut() {
+        attribute.data = interpolate(&attribute.data, &clip_map.vertices);
+        if let ("normals", AttributeData::Vector3Real(normals)) =
+            (attribute.name.as_str(), &mut attribute.data)
+        {
+            for normal in normals.iter_mut() {
+                let norm = normal.norm();
+                if norm > R::zero() {
+                    *normal /= norm;
+                }
+            }
+        }
+    }
+    for attribute in mesh.cell_attributes.iter_mut() {
+        attribute.data = attribute.data.select(&clip_map.triangles);
+    }
+    clip_map
+}
+
+/// Returns the values interpolated with the given weights from the given data
+fn interpolate<R: Real>(data: &AttributeData<R>, weights: &[[(usize, R); 3]]) -> AttributeData<R> {
+    let weighted_sum = |values: &[R], w: &[(usize, R); 3]| {
+        w.iter()
+            .fold(R::zero(), |sum, &(v, weight)| sum + values[v] * weight)
+    };
+
+    match data {
+        AttributeData::ScalarU64(values) => AttributeData::ScalarU64(
+            weights
+                .iter()
+                .map(|w| {
+                    let (v, _) = w.iter().fold(
+                        w[0],
+                        |max, &entry| {
+                            if entry.1 > max.1 {
+                                entry
+                            } else {
+                                max
+                            }
+                        },
+                    );
+                    values[v]
+                })
+                .collect(),
+        ),
+        AttributeData::ScalarReal(values) => {
+            AttributeData::ScalarReal(weights.iter().map(|w| weighted_sum(values, w)).collect())
+        }
+        AttributeData::Vector3Real(values) => AttributeData::Vector3Real(
+            weights
+                .iter()
+                .map(|w| {
+                    w.iter().fold(Vector3::zeros(), |sum, &(v, weight)| {
+                        sum + values[v] * weight
+                    })
+                })
+                .collect(),
+        ),
+        AttributeData::VectorReal {
+            num_components,
+            values,
+        } => AttributeData::VectorReal {
+            num_components: *num_components,
+            values: weights
+                .iter()
+                .flat_map(|w| {
+                    (0..*num_components).map(move |c| {
+                        w.iter().fold(R::zero(), |sum, &(v, weight)| {
+                            sum + values[v * num_components + c] * weight
+                        })
+                    })
+                })
+                .collect(),
+        },
+    }
+}
+
+/// Reference to a vertex of the input mesh or to a vertex inserted by the clipping
+#[derive(Copy, Clone, Debug)]
+enum VertexRef {
+    Input(usize),
+    New(usize),
+}
+
+/// Vertex of a triangle during the clipping against the planes of the box
+#[derive(Copy, Clone, Debug)]
+struct PolygonVertex<R: Real> {
+    vertex: VertexRef,
+    position: Vector3<R>,
+    /// Barycentric coordinates of the vertex with respect to the vertices of the input triangle
+    weights: [R; 3],
+}
+
+/// State of the clipping: the box and the vertices inserted so far
+struct Clipping<'a, R: Real> {
+    aabb: &'a AxisAlignedBoundingBox3d<R>,
+    vertices: &'a [Vector3<R>],
+    /// New vertices on edges of the input mesh by the vertices of the edge (in ascending order) and the plane
+    edge_vertices: MapType<(usize, usize, usize), usize>,
+    new_vertices: Vec<Vector3<R>>,
+    /// Vertices of the input mesh and their weights that every new vertex is interpolated from
+    new_vertex_weights: Vec<[(usize, R); 3]>,
+}
+
+impl<'a, R: Real> Clipping<'a, R> {
+    fn new(aabb: &'a AxisAlignedBoundingBox3d<R>, vertices: &'a [Vector3<R>]) -> Self {
+        Self {
+            aabb,
+            vertices,
+            edge_vertices: new_map(),
+            new_vertices: Vec::new(),
+            new_vertex_weights: Vec::new(),
+        }
+    }
+
+    /// Returns the coordinate of the given plane (min and max planes of the x, y and z axes) along its axis
+    fn plane_coordinate(&self, plane: usize) -> R {
+        if plane % 2 == 1 {
+            self.aabb.max()[plane / 2]
+        } else {
+            self.aabb.min()[plane / 2]
+        }
+    }
+
+    /// Returns the signed distance of the point to the given plane, positive on the side of the plane inside of the box
+    fn signed_distance(&self, plane: usize, x: &Vector3<R>) -> R {
+        let distance = x[plane / 2] - self.plane_coordinate(plane);
+        if plane % 2 == 1 {
+            -distance
+        } else {
+            distance
+        }
+    }
+
+    /// Returns the vertex where the segment between the given vertices of the input triangle crosses the plane
+    fn intersection(
+        &mut self,
+        tri: &[usize; 3],
+        plane: usize,
+        p: &PolygonVertex<R>,
+        q: &PolygonVertex<R>,
+    ) -> PolygonVertex<R> {
+        let axis = plane / 2;
+        let plane_coordinate = self.plane_coordinate(plane);
+
+        // Segments on an edge of the input triangle are intersected using the end points of the edge in the order of
+        // their indices, such that the adjacent triangle obtains the same vertex
+        if let Some(k) = (0..3).find(|&k| p.weights[k] == R::zero() && q.weights[k] == R::zero()) {
+            let (i, j) = if tri[(k + 1) % 3] < tri[(k + 2) % 3] {
+                ((k + 1) % 3, (k + 2) % 3)
+            } else {
+                ((k + 2) % 3, (k + 1) % 3)
+            };
+            let (a, b) = (tri[i], tri[j]);
+            let da = self.signed_distance(plane, &self.vertices[a]);
+            let db = self.signed_distance(plane, &self.vertices[b]);
+            let t = da / (da - db);
+
+            let index = match self.edge_vertices.get(&(a, b, plane)) {
+                Some(&index) => index,
+                None => {
+                    let mut position = self.vertices[a] + (self.vertices[b] - self.vertices[a]) * t;
+                    position[axis] = plane_coordinate;
+                    self.new_vertices.push(position);
+                    self.new_vertex_weights
+                        .push([(a, R::one() - t), (b, t), (b, R::zero())]);
+                    self.edge_vertices
+                        .insert((a, b, plane), self.new_vertices.len() - 1);
+                    self.new_vertices.len() - 1
+                }
+            };
+
+            let mut weights = [R::zero(); 3];
+            weights[i] = R::one() - t;
+            weights[j] = t;
+            return PolygonVertex {
+                vertex: VertexRef::New(index),
+                position: self.new_vertices[index],
+                weights,
+            };
+        }
+
+        // Vertices in the interior of the input triangle are not shared
+        let dp = self.signed_distance(plane, &p.position);
+        let dq = self.signed_distance(plane, &q.position);
+        let t = dp / (dp - dq);
+        let mut position = p.position + (q.position - p.position) * t;
+        position[axis] = plane_coordinate;
+        let weights = [0, 1, 2].map(|i| p.weights[i] + (q.weights[i] - p.weights[i]) * t);
+
+        self.new_vertices.push(position);
+        self.new_vertex_weights.push([
+            (tri[0], weights[0]),
+            (tri[1], weights[1]),
+            (tri[2], weights[2]),
+        ]);
+        PolygonVertex {
+            vertex: VertexRef::New(self.new_vertices.len() - 1),
+            position,
+            weights,
+        }
+    }
+}
+
+#[test]
+fn test_clip_aabb_sphere() {
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 3);
+    let aabb = AxisAlignedBoundingBox3d::new(Vector3::repeat(-2.0), Vector3::new(2.0, 2.0, 0.3));
+
+    let mut mesh = sphere.clone();
+    let clip_map = clip_aabb(&mut mesh, &aabb);
+    assert_eq!(clip_map.vertices.len(), mesh.vertices.len());
+    assert_eq!(clip_map.triangles.len(), mesh.triangles.len());
+
+    assert!(mesh.vertices.iter().all(|v| v.z <= 0.3));
+    // The surface is only opened along the clipping plane
+    let report = mesh.validity_report();
+    assert!(report.non_manifold_edges.is_empty());
+    assert!(report.isolated_vertices.is_empty());
+    assert!(!report.boundary_edges.is_empty());
+    assert!(mesh
+        .find_boundary_edges()
+        .iter()
+        .all(|([a, b], _, _)| mesh.vertices[*a].z == 0.3 && mesh.vertices[*b].z == 0.3));
+
+    // Triangles completely inside of the box are kept unchanged
+    let num_inside = sphere
+        .triangles
+        .iter()
+        .filter(|tri| tri.iter().all(|&v| sphere.vertices[v].z <= 0.3))
+        .count();
+    let unchanged = clip_map
+        .triangles
+        .iter()
+        .zip(&mesh.triangles)
+        .filter(|(&tri_idx, tri)| {
+            tri.map(|v| mesh.vertices[v]) == sphere.triangles[tri_idx].map(|v| sphere.vertices[v])
+        })
+        .count();
+    assert_eq!(unchanged, num_inside);
+
+    // The area of a spherical cap is 2 pi r h
+    let cap_area = 2.0 * std::f64::consts::PI * 0.7;
+    assert!((sphere.area() - mesh.area() - cap_area).abs() < 0.02 * cap_area);
+
+    // Boxes around and outside of the mesh
+    let mut mesh = sphere.clone();
+    let clip_map = clip_aabb(
+        &mut mesh,
+        &AxisAlignedBoundingBox3d::new(Vector3::repeat(-1.0), Vector3::repeat(1.0)),
+    );
+    assert_eq!(mesh.vertices, sphere.vertices);
+    assert_eq!(mesh.triangles, sphere.triangles);
+    assert_eq!(
+        clip_map.triangles,
+        (0..sphere.triangles.len()).collect::<Vec<_>>()
+    );
+
+    let mut mesh = sphere.clone();
+    clip_aabb(
+        &mut mesh,
+        &AxisAlignedBoundingBox3d::new(Vector3::repeat(2.0), Vector3::repeat(3.0)),
+    );
+    assert!(mesh.vertices.is_empty());
+    assert!(mesh.triangles.is_empty());
+}
+
+#[test]
+fn test_clip_aabb_with_data() {
+    use crate::mesh::MeshAttribute;
+
+    let triangle = TriMesh3d {
+        vertices: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 2.0, 0.0),
+        ],
+        triangles: vec![[0, 1, 2]],
+    };
+    let linear_field = |v: &Vector3<f64>| v.x + 2.0 * v.y;
+    let mut mesh = MeshWithData::new(triangle.clone())
+        .with_point_data(MeshAttribute::new_real_scalar(
+            "field",
+            triangle
+                .vertices
+                .iter()
+                .map(linear_field)
+                .collect::<Vec<_>>(),
+        ))
+        .with_point_data(MeshAttribute::new_real_vector3(
+            "normals",
+            vec![Vector3::x(), Vector3::y(), Vector3::z()],
+        ))
+        .with_point_data(MeshAttribute::new(
+            "id",
+            AttributeData::ScalarU64(vec![10, 11, 12]),
+        ))
+        .with_cell_data(MeshAttribute::new(
+            "cell_id",
+            AttributeData::ScalarU64(vec![7]),
+        ));
+
+    let aabb = AxisAlignedBoundingBox3d::new(Vector3::repeat(-1.0), Vector3::repeat(1.0));
+    let clip_map = clip_aabb_with_data(&mut mesh, &aabb);
+
+    // The unit square remains of the triangle
+    assert_eq!(
+        mesh.mesh.vertices,
+        vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(1.0, 1.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        ]
+    );
+    assert_eq!(mesh.mesh.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+    assert_eq!(clip_map.triangles, vec![0, 0]);
+    assert!((mesh.mesh.area() - 1.0).abs() < 1e-12);
+
+    let attribute = |name: &str| {
+        &mesh
+            .point_attributes
+            .iter()
+            .find(|a| a.name == name)
+            .unwrap()
+            .data
+    };
+    match attribute("field") {
+        AttributeData::ScalarReal(values) => {
+            for (v, value) in mesh.mesh.vertices.iter().zip(values) {
+                assert!((linear_field(v) - value).abs() < 1e-12);
+            }
+        }
+        _ => panic!("unexpected attribute type"),
+    }
+    match attribute("normals") {
+        AttributeData::Vector3Real(normals) => {
+            assert!(normals.iter().all(|n| (n.norm() - 1.0).abs() < 1e-12));
+            assert_eq!(normals[0], Vector3::x());
+        }
+        _ => panic!("unexpected attribute type"),
+    }
+    match attribute("id") {
+        AttributeData::ScalarU64(ids) => assert_eq!(ids[..2], [10, 10]),
+        _ => panic!("unexpected attribute type"),
+    }
+    match &mesh.cell_attributes[0].data {
+        AttributeData::ScalarU64(ids) => assert_eq!(ids, &vec![7, 7]),
+        _ => panic!("unexpected attribute type"),
+    }
+}
@@ -0,0 +1,286 @@
+//! Adjacency of the vertices and triangles of triangle meshes, shared by the mesh processing algorithms
+//!
+//! The [`Connectivity`] of a mesh is built once from its triangles and can then be queried repeatedly, e.g. for
+//! the neighbors of every vertex in each iteration of a smoothing. All adjacency lists are stored consecutively in
+//! flat arrays (compressed sparse row format) instead of one allocation per vertex or triangle.
+
+use crate::mesh::TriMesh3d;
+use crate::{profile, Real};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Vertex-triangle, vertex-vertex and triangle-triangle adjacency of a triangle mesh
+///
+/// All adjacency lists are sorted in ascending order and contain every entry only once. Edges of degenerate
+/// triangles that connect a vertex with itself are ignored. The connectivity is not updated if the mesh changes.
+#[derive(Clone, Debug, Default)]
+pub struct Connectivity {
+    vertex_triangles: CompressedRows,
+    vertex_neighbors: CompressedRows,
+    triangle_neighbors: CompressedRows,
+}
+
+impl Connectivity {
+    /// Builds the connectivity from the triangles of the mesh
+    pub fn from_mesh<R: Real>(mesh: &TriMesh3d<R>) -> Self {
+        Self::from_mesh_impl(mesh, false)
+    }
+
+    /// Builds the connectivity from the triangles of the mesh (parallelized version)
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+    pub fn par_from_mesh<R: Real>(mesh: &TriMesh3d<R>) -> Self {
+        Self::from_mesh_impl(mesh, true)
+    }
+
+    pub(crate) fn from_mesh_impl<R: Real>(mesh: &TriMesh3d<R>, parallel: bool) -> Self {
+        profile!("Connectivity::from_mesh");
+
+        let num_vertices = mesh.vertices.len();
+        let num_triangles = mesh.triangles.len();
+
+        let vertex_triangles = CompressedRows::from_pairs(
+            num_vertices,
+            collect_per_triangle(&mesh.triangles, parallel, |t, tri| {
+                tri.into_iter().map(move |v| (v, t))
+            }),
+            parallel,
+        );
+
+        let vertex_neighbors = CompressedRows::from_pairs(
+            num_vertices,
+            collect_per_triangle(&mesh.triangles, parallel, |_, tri| {
+                triangle_edges(tri).flat_map(|(a, b)| [(a, b), (b, a)])
+            }),
+            parallel,
+        );
+
+        // Triangles are adjacent if they share an edge, i.e. all triangles of a group with the same edge are pairwise adjacent
+        let mut edges = collect_per_triangle(&mesh.triangles, parallel, |t, tri| {
+            triangle_edges(tri).map(move |(a, b)| (a.min(b), a.max(b), t))
+        });
+        sort_unstable(&mut edges, parallel);
+        let mut triangle_pairs = Vec::with_capacity(2 * edges.len());
+        for group in edges.chunk_by(|(a0, b0, _), (a1, b1, _)| (a0, b0) == (a1, b1)) {
+            for &(_, _, t0) in group {
+                for &(_, _, t1) in group {
+                    if t0 != t1 {
+                        triangle_pairs.push((t0, t1));
+                    }
+                }
+            }
+        }
+        let triangle_neighbors =
+            CompressedRows::from_pairs(num_triangles, triangle_pairs, parallel);
+
+        Self {
+            vertex_triangles,
+            vertex_neighbors,
+            triangle_neighbors,
+        }
+    }
+
+    /// Returns the number of vertices of the mesh that the connectivity was built from
+    pub fn num_vertices(&self) -> usize {
+        self.vertex_neighbors.num_rows()
+    }
+
+    /// Returns the number of triangles of the mesh that the connectivity was built from
+    pub fn num_triangles(&self) -> usize {
+        self.triangle_neighbors.num_rows()
+    }
+
+    /// Returns the indices of all triangles that contain the given vertex
+    pub fn vertex_triangles(&self, vertex: usize) -> &[usize] {
+        self.vertex_triangles.row(vertex)
+    }
+
+    /// Returns the indices of all vertices that share an edge with the given vertex
+    pub fn vertex_neighbors(&self, vertex: usize) -> &[usize] {
+        self.vertex_neighbors.row(vertex)
+    }
+
+    /// Returns the indices of all triangles that share an edge with the given triangle
+    pub fn triangle_neighbors(&self, triangle: usize) -> &[usize] {
+        self.triangle_neighbors.row(triangle)
+    }
+}
+
+/// Lists of indices for a number of rows stored consecutively in one array
+#[derive(Clone, Debug, Default)]
+struct CompressedRows {
+    /// Offset of every row into `values`, one more entry than the number of rows
+    offsets: Vec<usize>,
+    values: Vec<usize>,
+}
+
+impl CompressedRows {
+    /// Builds the rows from `(row, value)` pairs, duplicate pairs are only stored once
+    fn from_pairs(num_rows: usize, mut pairs: Vec<(usize, usize)>, parallel: bool) -> Self {
+        sort_unstable(&mut pairs, parallel);
+        pairs.dedup();
+
+        let mut offsets = vec![0; num_rows + 1];
+        for &(row, _) in pairs.iter() {
+            offsets[row + 1] += 1;
+        }
+        for i in 0..num_rows {
+            offsets[i + 1] += offsets[i];
+        }
+
+        Self {
+            offsets,
+            values: pairs.into_iter().map(|(_, value)| value).collect(),
+        }
+    }
+
+    fn num_rows(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    fn row(&self, row: usize) -> &[usize] {
+        &self.values[self.offsets[row]..self.offsets[row + 1]]
+    }
+}
+
+/// Returns the edges of the triangle that connect two different vertices
+fn triangle_edges(tri: [usize; 3]) -> impl Iterator<Item = (usize, usize)> {
+    (0..3)
+        .map(move |i| (tri[i], tri[(i + 1) % 3]))
+        .filter(|(a, b)| a != b)
+}
+
+/// Collects the items returned for every triangle (with its index) in the order of the triangles
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+fn collect_per_triangle<T, I, F>(triangles: &[[usize; 3]], parallel: bool, f: F) -> Vec<T>
+where
+    T: Send,
+    I: Iterator<Item = T>,
+    F: Fn(usize, [usize; 3]) -> I + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    if parallel {
+        return triangles
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(t, tri)| f(t, *tri))
+            .collect();
+    }
+
+    triangles
+        .iter()
+        .enumerate()
+        .flat_map(|(t, tri)| f(t, *tri))
+        .collect()
+}
+
+/// Sorts the values, in parallel if requested
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+fn sort_unstable<T: Ord + Send>(values: &mut [T], parallel: bool) {
+    #[cfg(feature = "parallel")]
+    if parallel {
+        values.par_sort_unstable();
+        return;
+    }
+
+    values.sort_unstable();
+}
+
+#[test]
+fn test_connectivity_tetrahedron() {
+    use nalgebra::Vector3;
+
+    let tetrahedron = TriMesh3d::<f64> {
+        vertices: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+        ],
+        triangles: vec![[0, 2, 1], [0, 1, 3], [1, 2, 3], [0, 3, 2]],
+    };
+
+    let connectivity = Connectivity::from_mesh(&tetrahedron);
+    assert_eq!(connectivity.num_vertices(), 4);
+    assert_eq!(connectivity.num_triangles(), 4);
+    assert_eq!(connectivity.vertex_triangles(0), &[0, 1, 3]);
+    assert_eq!(connectivity.vertex_triangles(2), &[0, 2, 3]);
+    assert_eq!(connectivity.vertex_neighbors(0), &[1, 2, 3]);
+    assert_eq!(connectivity.vertex_neighbors(3), &[0, 1, 2]);
+    assert_eq!(connectivity.triangle_neighbors(0), &[1, 2, 3]);
+    assert_eq!(connectivity.triangle_neighbors(2), &[0, 1, 3]);
+
+    // A fin on the edge (0, 1), a degenerate triangle and an isolated vertex
+    let mut mesh = tetrahedron.clone();
+    mesh.vertices.push(Vector3::new(1.0, -1.0, 0.0));
+    mesh.vertices.push(Vector3::new(5.0, 5.0, 5.0));
+    mesh.triangles.push([1, 0, 4]);
+    mesh.triangles.push([2, 2, 3]);
+
+    let connectivity = Connectivity::from_mesh(&mesh);
+    assert_eq!(connectivity.vertex_neighbors(0), &[1, 2, 3, 4]);
+    assert_eq!(connectivity.vertex_neighbors(4), &[0, 1]);
+    assert!(connectivity.vertex_neighbors(5).is_empty());
+    assert!(connectivity.vertex_triangles(5).is_empty());
+    assert_eq!(connectivity.vertex_triangles(2), &[0, 2, 3, 5]);
+    assert_eq!(connectivity.triangle_neighbors(0), &[1, 2, 3, 4]);
+    assert_eq!(connectivity.triangle_neighbors(4), &[0, 1]);
+    // The degenerate triangle only has the edge (2, 3)
+    assert_eq!(connectivity.triangle_neighbors(5), &[2, 3]);
+}
+
+#[test]
+fn test_connectivity_icosphere() {
+    use nalgebra::Vector3;
+
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 2);
+    let connectivity = Connectivity::from_mesh(&sphere);
+    assert_eq!(connectivity.num_vertices(), sphere.vertices.len());
+    assert_eq!(connectivity.num_triangles(), sphere.triangles.len());
+
+    // Closed manifold: every triangle has three neighbors and every vertex as many neighbors as triangles
+    let mut num_neighbors = 0;
+    for v in 0..sphere.vertices.len() {
+        let neighbors = connectivity.vertex_neighbors(v);
+        assert!(neighbors.len() == 5 || neighbors.len() == 6);
+        assert_eq!(connectivity.vertex_triangles(v).len(), neighbors.len());
+        for &t in connectivity.vertex_triangles(v) {
+            assert!(sphere.triangles[t].contains(&v));
+        }
+        num_neighbors += neighbors.len();
+    }
+    assert_eq!(num_neighbors, 3 * sphere.triangles.len());
+
+    for (t, tri) in sphere.triangles.iter().enumerate() {
+        let neighbors = connectivity.triangle_neighbors(t);
+        assert_eq!(neighbors.len(), 3);
+        for &n in neighbors {
+            let shared = sphere.triangles[n]
+                .iter()
+                .filter(|v| tri.contains(v))
+                .count();
+            assert_eq!(shared, 2);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    {
+        let par_connectivity = Connectivity::par_from_mesh(&sphere);
+        for v in 0..sphere.vertices.len() {
+            assert_eq!(
+                par_connectivity.vertex_triangles(v),
+                connectivity.vertex_triangles(v)
+            );
+            assert_eq!(
+                par_connectivity.vertex_neighbors(v),
+                connectivity.vertex_neighbors(v)
+            );
+        }
+        for t in 0..sphere.triangles.len() {
+            assert_eq!(
+                par_connectivity.triangle_neighbors(t),
+                connectivity.triangle_neighbors(t)
+            );
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! Discrete curvature of triangle meshes, e.g. to color reconstructed fluid surfaces for foam and spray shading
+//!
+//! The curvatures are computed per vertex from the triangles around the vertex (see [`Connectivity::vertex_triangles`])
+//! and returned with one value per vertex of the mesh, such that they can be attached to the mesh as point attributes
+//! with [`MeshAttribute::new_real_scalar`](crate::mesh::MeshAttribute::new_real_scalar). Both curvatures are normalized
+//! by the mixed Voronoi area of the vertex (Meyer et al. 2003, "Discrete Differential-Geometry Operators for
+//! Triangulated 2-Manifolds").
+//!
+//! The curvature at the boundary of an open surface is not well-defined by the triangles around a vertex. Therefore,
+//! vertices on boundary edges (edges with only one adjacent triangle) get a curvature of zero. The same applies to
+//! vertices without (non-degenerate) triangles.
+
+use crate::mesh::{Connectivity, TriMesh3d};
+use crate::{profile, Real};
+use nalgebra::Vector3;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Computes the mean curvature at every vertex of the mesh using the cotangent Laplacian
+///
+/// The mean curvature is the average of the two principal curvatures, i.e. `1/r` for a sphere with radius `r`. It
+/// is positive for convex regions of surfaces with outward oriented triangles (counter-clockwise vertex order seen
+/// from outside) like the reconstructed surfaces and negative for concave regions. Vertices on the boundary of the
+/// mesh get a mean curvature of zero.
+///
+/// Panics if the connectivity was not built from the same mesh.
+pub fn mean_curvature<R: Real>(mesh: &TriMesh3d<R>, connectivity: &Connectivity) -> Vec<R> {
+    profile!("mean_curvature");
+    per_vertex(mesh, connectivity, false, |star| star.mean_curvature())
+}
+
+/// Computes the mean curvature at every vertex of the mesh (parallelized over the vertices), see [`mean_curvature`]
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub fn par_mean_curvature<R: Real>(mesh: &TriMesh3d<R>, connectivity: &Connectivity) -> Vec<R> {
+    profile!("par_mean_curvature");
+    per_vertex(mesh, connectivity, true, |star| star.mean_curvature())
+}
+
+/// Computes the Gaussian curvature at every vertex of the mesh from the angle defect
+///
+/// The Gaussian curvature is the product of the two principal curvatures, i.e. `1/r²` for a sphere with radius `r`.
+/// It is computed as `2π` minus the sum of the angles of all triangles at the vertex divided by the area of the
+/// vertex, such that its integral over a closed mesh is `2π` times the Euler characteristic of the mesh. Vertices
+/// on the boundary of the mesh get a Gaussian curvature of zero.
+///
+/// Panics if the connectivity was not built from the same mesh.
+pub fn gaussian_curvature<R: Real>(mesh: &TriMesh3d<R>, connectivity: &Connectivity) -> Vec<R> {
+    profile!("gaussian_curvature");
+    per_vertex(mesh, connectivity, false, |star| star.gaussian_curvature())
+}
+
+/// Computes the Gaussian curvature at every vertex of the mesh (parallelized over the vertices), see [`gaussian_curvature`]
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub fn par_gaussian_curvature<R: Real>(mesh: &TriMesh3d<R>, connectivity: &Connectivity) -> Vec<R> {
+    profile!("par_gaussian_curvature");
+    per_vertex(mesh, connectivity, true, |star| star.gaussian_curvature())
+}
+
+/// Evaluates the function for the triangles around every interior vertex, boundary and isolated vertices get zero
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+fn per_vertex<R: Real, F>(
+    mesh: &TriMesh3d<R>,
+    connectivity: &Connectivity,
+    parallel: bool,
+    f: F,
+) -> Vec<R>
+where
+    F: Fn(&VertexStar<R>) -> R + Sync + Send,
+{
+    assert_eq!(
+        connectivity.num_vertices(),
+        mesh.vertices.len(),
+        "The connectivity has to be built from the same mesh"
+    );
+
+    let curvature = |v: usize| {
+        VertexStar::new(mesh, connectivity, v)
+            .map(|star| f(&star))
+            .unwrap_or_else(R::zero)
+    };
+
+    #[cfg(feature = "parallel")]
+    if parallel {
+        return (0..mesh.vertices.len())
+            .into_par_iter()
+            .with_min_len(256)
+            .map(curvature)
+            .collect();
+    }
+
+    (0..mesh.vertices.len()).map(curvature).collect()
+}
+
+/// Quantities accumulated over the triangles around a vertex
+struct VertexStar<R: Real> {
+    /// Sum of the cotangent weighted edge vectors, approximately `4 A H n` with the area `A`, mean curvature `H` and normal `n`
+    laplacian: Vector3<R>,
+    /// Area weighted normal direction of the triangles
+    normal: Vector3<R>,
+    /// Sum of the angles of the triangles at the vertex
+    angle_sum: R,
+    /// Mixed Voronoi area of the vertex
+    area: R,
+}
+
+impl<R: Real> VertexStar<R> {
+    /// Accumulates the triangles around the vertex, returns `None` for boundary vertices and vertices without area
+    fn new(mesh: &TriMesh3d<R>, connectivity: &Connectivity, v: usize) -> Option<Self> {
+        let triangles = connectivity.vertex_triangles(v);
+
+        // Every edge to a neighbor of an interior vertex is shared by (at least) two of its triangles
+        let is_boundary = connectivity.vertex_neighbors(v).iter().any(|&n| {
+            triangles
+                .iter()
+                .filter(|&&t| mesh.triangles[t].contains(&n))
+                .count()
+                < 2
+        });
+        if is_boundary {
+            return None;
+        }
+
+        let half = R::from_f64(0.5).unwrap();
+        let mut star = Self {
+            laplacian: Vector3::zeros(),
+            normal: Vector3::zeros(),
+            angle_sum: R::zero(),
+            area: R::zero(),
+        };
+
+        for &t in triangles {
+            let tri = mesh.triangles[t];
+            let i = tri.iter().position(|&u| u == v).unwrap();
+            let (j, k) = (tri[(i + 1) % 3], tri[(i + 2) % 3]);
+            let (xi, xj, xk) = (mesh.vertices[v], mesh.vertices[j], mesh.vertices[k]);
+
+            let normal = (xj - xi).cross(&(xk - xi));
+            let double_area = normal.norm();
+            if double_area <= R::zero() {
+                continue;
+            }
+
+            // Cotangents of the angles at the other two vertices that are opposite to the edges of the vertex
+            let cot = |a: Vector3<R>, b: Vector3<R>| a.dot(&b) / double_area;
+            let cot_j = cot(xi - xj, xk - xj);
+            let cot_k = cot(xi - xk, xj - xk);
+            let cos_i = (xj - xi).normalize().dot(&(xk - xi).normalize());
+            let angle_i = cos_i.max(-R::one()).min(R::one()).acos();
+
+            star.laplacian += (xi - xj) * cot_k + (xi - xk) * cot_j;
+            star.normal += normal;
+            star.angle_sum += angle_i;
+
+            // Voronoi area for non-obtuse triangles, otherwise a fixed fraction of the triangle area
+            let area = double_area * half;
+            star.area += if angle_i > R::frac_pi_2() {
+                area * half
+            } else if cot_j < R::zero() || cot_k < R::zero() {
+                area * half * half
+            } else {
+                ((xj - xi).norm_squared() * cot_k + (xk - xi).norm_squared() * cot_j)
+                    * R::from_f64(0.125).unwrap()
+            };
+        }
+
+        (star.area > R::zero()).then_some(star)
+    }
+
+    fn mean_curvature(&self) -> R {
+        let normal = self
+            .normal
+            .try_normalize(R::zero())
+            .unwrap_or_else(Vector3::zeros);
+        self.laplacian.dot(&normal) / (self.area * R::from_f64(4.0).unwrap())
+    }
+
+    fn gaussian_curvature(&self) -> R {
+        (R::two_pi() - self.angle_sum) / self.area
+    }
+}
+
+#[test]
+fn test_curvature_sphere() {
+    let radius = 2.0;
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::new(1.0, -1.0, 0.5), radius, 3);
+    let connectivity = Connectivity::from_mesh(&sphere);
+
+    let mean = mean_curvature(&sphere, &connectivity);
+    let gaussian = gaussian_curvature(&sphere, &connectivity);
+    assert_eq!(mean.len(), sphere.vertices.len());
+    assert_eq!(gaussian.len(), sphere.vertices.len());
+    for (&h, &k) in mean.iter().zip(gaussian.iter()) {
+        assert!((h - 1.0 / radius).abs() < 0.05 / radius, "{}", h);
+        assert!(
+            (k - 1.0 / (radius * radius)).abs() < 0.1 / (radius * radius),
+            "{}",
+            k
+        );
+    }
+
+    // Gauss-Bonnet: the angle defects of a closed mesh sum up to 4π for a sphere
+    let star_areas = (0..sphere.vertices.len())
+        .map(|v| VertexStar::new(&sphere, &connectivity, v).unwrap().area)
+        .collect::<Vec<_>>();
+    let total_area = star_areas.iter().sum::<f64>();
+    assert!((total_area - sphere.area()).abs() < 1e-9 * total_area);
+    let total_curvature = gaussian
+        .iter()
+        .zip(star_areas.iter())
+        .map(|(k, area)| k * area)
+        .sum::<f64>();
+    assert!((total_curvature - 4.0 * std::f64::consts::PI).abs() < 1e-9);
+
+    // The mean curvature changes its sign with the orientation, the Gaussian curvature does not
+    let mut inverted = sphere.clone();
+    for tri in inverted.triangles.iter_mut() {
+        tri.swap(1, 2);
+    }
+    let connectivity = Connectivity::from_mesh(&inverted);
+    for (h, h_inverted) in mean.iter().zip(mean_curvature(&inverted, &connectivity)) {
+        assert!((h + h_inverted).abs() < 1e-12);
+    }
+
+    #[cfg(feature = "parallel")]
+    {
+        let connectivity = Connectivity::par_from_mesh(&sphere);
+        assert_eq!(par_mean_curvature(&sphere, &connectivity), mean);
+        assert_eq!(par_gaussian_curvature(&sphere, &connectivity), gaussian);
+    }
+}
+
+#[test]
+fn test_curvature_open_surface() {
+    // A flat 3x3 grid of squares has no curvature, the boundary vertices are set to zero
+    let n = 3;
+    let mut grid = TriMesh3d::<f64>::default();
+    for j in 0..=n {
+        for i in 0..=n {
+            grid.vertices.push(Vector3::new(i as f64, j as f64, 0.0));
+        }
+    }
+    let index = |i: usize, j: usize| j * (n + 1) + i;
+    for j in 0..n {
+        for i in 0..n {
+            grid.triangles
+                .push([index(i, j), index(i + 1, j), index(i + 1, j + 1)]);
+            grid.triangles
+                .push([index(i, j), index(i + 1, j + 1), index(i, j + 1)]);
+        }
+    }
+    // An isolated vertex
+    grid.vertices.push(Vector3::new(10.0, 10.0, 10.0));
+
+    let connectivity = Connectivity::from_mesh(&grid);
+    assert!(mean_curvature(&grid, &connectivity)
+        .iter()
+        .all(|&h| h.abs() < 1e-12));
+    assert!(gaussian_curvature(&grid, &connectivity)
+        .iter()
+        .all(|&k| k.abs() < 1e-12));
+
+    // Lifting an interior vertex creates a peak with positive mean and Gaussian curvature, the boundary stays at zero
+    let peak = index(1, 1);
+    grid.vertices[peak].z = 0.5;
+    let mean = mean_curvature(&grid, &connectivity);
+    let gaussian = gaussian_curvature(&grid, &connectivity);
+    assert!(mean[peak] > 0.0);
+    assert!(gaussian[peak] > 0.0);
+    assert_eq!(mean[index(0, 1)], 0.0);
+    assert_eq!(gaussian[index(1, 0)], 0.0);
+    assert_eq!(mean[grid.vertices.len() - 1], 0.0);
+}
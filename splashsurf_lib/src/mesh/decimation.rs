@@ -0,0 +1,449 @@
+//! Decimation of triangle meshes by edge collapses, e.g. to reduce the size of surfaces reconstructed with small cube sizes
+//!
+//! The [`decimate`] function repeatedly collapses the edge with the smallest quadric error (Garland & Heckbert:
+//! sum of the squared distances to the planes of the triangles that were merged into a vertex). The position of the
+//! remaining vertex is chosen among the two end points and the midpoint of the edge. Collapses are rejected if they would
+//! result in non-manifold configurations, flipped or degenerate triangles. Vertices on boundary or non-manifold edges of the
+//! input mesh are never moved or removed.
+//!
+//! Attributes attached to the vertices or triangles of a [`MeshWithData`] can be carried along with
+//! [`decimate_with_data`], the remaining vertices keep the values of the respective input vertex.
+
+use crate::mesh::{MeshWithData, TriMesh3d};
+use crate::{profile, Real};
+use nalgebra::{Matrix4, Vector3, Vector4};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Indices in the input mesh of all vertices and triangles that remain after a decimation, see [`decimate`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecimationMap {
+    /// Index before the decimation of every remaining vertex
+    pub vertices: Vec<usize>,
+    /// Index before the decimation of every remaining triangle
+    pub triangles: Vec<usize>,
+}
+
+/// Collapses edges of the mesh until it has at most the given number of triangles or no valid collapse is left
+///
+/// Every collapse removes one vertex and two triangles. The remaining vertices and triangles keep their relative order,
+/// vertices that are not referenced by any triangle are kept. Returns the indices of the remaining vertices and triangles
+/// in the input mesh, e.g. to update additional data attached to the mesh.
+pub fn decimate<R: Real>(mesh: &mut TriMesh3d<R>, target_triangle_count: usize) -> DecimationMap {
+    profile!("decimate");
+
+    let mut decimation = EdgeCollapses::new(mesh);
+    while decimation.num_triangles > target_triangle_count {
+        if !decimation.collapse_next_edge() {
+            break;
+        }
+    }
+    decimation.write_into(mesh)
+}
+
+/// Decimates the mesh like [`decimate`] and selects the point and cell attributes of the remaining vertices and triangles
+pub fn decimate_with_data<R: Real>(
+    mesh: &mut MeshWithData<R, TriMesh3d<R>>,
+    target_triangle_count: usize,
+) -> DecimationMap {
+    let decimation_map = decimate(&mut mesh.mesh, target_triangle_count);
+    for attribute in mesh.point_attributes.iter_mut() {
+        attribute.data = attribute.data.select(&decimation_map.vertices);
+    }
+    for attribute in mesh.cell_attributes.iter_mut() {
+        attribute.data = attribute.data.select(&decimation_map.triangles);
+    }
+    decimation_map
+}
+
+/// Candidate collapse of the edge between two vertices, ordered such that the heap returns the smallest error first
+struct Collapse<R: Real> {
+    error: f64,
+    /// Vertex that remains at the new position
+    keep: usize,
+    /// Vertex that is merged into the remaining vertex
+    remove: usize,
+    /// Versions of the two vertices when the candidate was computed, the candidate is outdated if a vertex changed since
+    versions: [u32; 2],
+    position: Vector3<R>,
+}
+
+impl<R: Real> PartialEq for Collapse<R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<R: Real> Eq for Collapse<R> {}
+
+impl<R: Real> PartialOrd for Collapse<R> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<R: Real> Ord for Collapse<R> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed for a min-heap, the vertex indices make the order of equal errors deterministic
+        other
+            .error
+            .total_cmp(&self.error)
+            .then_with(|| (other.keep, other.remove).cmp(&(self.keep, self.remove)))
+    }
+}
+
+/// State of the decimation: the mesh with lazily removed triangles and the adjacency of the vertices
+struct EdgeCollapses<R: Real> {
+    vertices: Vec<Vector3<R>>,
+    triangles: Vec<[usize; 3]>,
+    triangle_alive: Vec<bool>,
+    /// Triangles adjacent to every vertex, may contain removed triangles
+    vertex_triangles: Vec<Vec<usize>>,
+    vertex_removed: Vec<bool>,
+    /// Vertices on boundary or non-manifold edges of the input mesh
+    vertex_fixed: Vec<bool>,
+    versions: Vec<u32>,
+    quadrics: Vec<Matrix4<R>>,
+    queue: BinaryHeap<Collapse<R>>,
+    num_triangles: usize,
+}
+
+impl<R: Real> EdgeCollapses<R> {
+    fn new(mesh: &TriMesh3d<R>) -> Self {
+        let num_vertices = mesh.vertices.len();
+
+        let mut vertex_triangles = vec![Vec::new(); num_vertices];
+        let mut quadrics = vec![Matrix4::zeros(); num_vertices];
+        let mut edges = Vec::with_capacity(3 * mesh.triangles.len());
+        for (tri_idx, tri) in mesh.triangles.iter().enumerate() {
+            for i in 0..3 {
+                let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                vertex_triangles[a].push(tri_idx);
+                edges.push([a.min(b), a.max(b)]);
+            }
+
+            // Quadric of the triangle's plane weighted by its area
+            let [x0, x1, x2] = tri.map(|v| mesh.vertices[v]);
+            let normal = (x1 - x0).cross(&(x2 - x0));
+            let norm = normal.norm();
+            if norm > R::zero() {
+                let n = normal / norm;
+                let plane = Vector4::new(n.x, n.y, n.z, -n.dot(&x0));
+                let quadric = plane * plane.transpose() * norm.times_f64(0.5);
+                for &v in tri {
+                    quadrics[v] += quadric;
+                }
+            }
+        }
+
+        // Edges with exactly two adjacent triangles are interior edges of a manifold
+        edges.sort_unstable();
+        let mut vertex_fixed = vec![false; num_vertices];
+        let mut interior_edges = Vec::new();
+        for group in edges.chunk_by(|a, b| a == b) {
+            let [a, b] = group[0];
+            if group.len() == 2 && a != b {
+                interior_edges.push([a, b]);
+            } else {
+                vertex_fixed[a] = true;
+                vertex_fixed[b] = true;
+            }
+        }
+
+        let mut decimation = Self {
+            vertices: mesh.vertices.clone(),
+            triangles: mesh.triangles.clone(),
+            triangle_alive: vec![true; mesh.triangles.len()],
+            vertex_triangles,
+            vertex_removed: vec![false; num_vertices],
+            vertex_fixed,
+            versions: vec![0; num_vertices],
+            quadrics,
+            queue: BinaryHeap::with_capacity(interior_edges.len()),
+            num_triangles: mesh.triangles.len(),
+        };
+        for [a, b] in interior_edges {
+            decimation.push_candidate(a, b);
+        }
+        decimation
+    }
+
+    /// Computes the best position and the error of the collapse of the given edge and adds it to the queue
+    fn push_candidate(&mut self, a: usize, b: usize) {
+        if self.vertex_fixed[a] || self.vertex_fixed[b] {
+            return;
+        }
+
+        let quadric = self.quadrics[a] + self.quadrics[b];
+        let error = |x: &Vector3<R>| {
+            let h = x.push(R::one());
+            h.dot(&(quadric * h)).to_f64().unwrap_or(f64::INFINITY)
+        };
+
+        let (xa, xb) = (self.vertices[a], self.vertices[b]);
+        let midpoint = (xa + xb).scale(R::from_f64(0.5).unwrap());
+        let (keep, remove, position) = [(a, b, xa), (b, a, xb), (a.min(b), a.max(b), midpoint)]
+            .into_iter()
+            .min_by(|(_, _, x), (_, _, y)| error(x).total_cmp(&error(y)))
+            .unwrap();
+
+        self.queue.push(Collapse {
+            error: error(&position),
+            keep,
+            remove,
+            versions: [self.versions[keep], self.versions[remove]],
+            position,
+        });
+    }
+
+    /// Performs the valid collapse with the smallest error, returns `false` if no valid collapse is left
+    fn collapse_next_edge(&mut self) -> bool {
+        while let Some(collapse) = self.queue.pop() {
+            let Collapse {
+                keep,
+                remove,
+                versions,
+                position,
+                ..
+            } = collapse;
+            if versions != [self.versions[keep], self.versions[remove]]
+                || self.vertex_removed[keep]
+                || self.vertex_removed[remove]
+            {
+                continue;
+            }
+
+            if self.is_valid_collapse(keep, remove, &position) {
+                self.collapse(keep, remove, position);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn alive_triangles(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        self.vertex_triangles[v]
+            .iter()
+            .copied()
+            .filter(|&t| self.triangle_alive[t])
+    }
+
+    /// Returns the sorted vertices connected to the given vertex by an edge
+    fn neighbors(&self, v: usize) -> Vec<usize> {
+        let mut neighbors = self
+            .alive_triangles(v)
+            .flat_map(|t| self.triangles[t])
+            .filter(|&w| w != v)
+            .collect::<Vec<_>>();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+
+    fn is_valid_collapse(&self, keep: usize, remove: usize, position: &Vector3<R>) -> bool {
+        // The edge has to be an interior edge of a manifold
+        let shared_triangles = self
+            .alive_triangles(remove)
+            .filter(|&t| self.triangles[t].contains(&keep))
+            .collect::<Vec<_>>();
+        if shared_triangles.len() != 2 {
+            return false;
+        }
+
+        // Link condition: the only common neighbors of the two vertices are the opposite vertices of the shared triangles
+        let keep_neighbors = self.neighbors(keep);
+        let num_common_neighbors = self
+            .neighbors(remove)
+            .iter()
+            .filter(|&&w| w != keep && keep_neighbors.binary_search(&w).is_ok())
+            .count();
+        if num_common_neighbors != 2 {
+            return false;
+        }
+
+        for v in [keep, remove] {
+            for t in self.alive_triangles(v) {
+                if shared_triangles.contains(&t) {
+                    continue;
+                }
+
+                let tri = self.triangles[t];
+                let new_tri = tri.map(|w| if w == remove { keep } else { w });
+
+                // The collapse must not create a triangle that already exists (e.g. when collapsing a tetrahedron)
+                if v == remove
+                    && self.alive_triangles(keep).any(|other| {
+                        let other = self.triangles[other];
+                        new_tri.iter().all(|w| other.contains(w))
+                    })
+                {
+                    return false;
+                }
+
+                // The moved triangles must not flip or become degenerate
+                let old_normal = self.triangle_normal(tri.map(|w| self.vertices[w]));
+                let new_normal = self.triangle_normal(new_tri.map(|w| {
+                    if w == keep {
+                        *position
+                    } else {
+                        self.vertices[w]
+                    }
+                }));
+                if new_normal.dot(&old_normal) <= R::zero() {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    fn triangle_normal(&self, [x0, x1, x2]: [Vector3<R>; 3]) -> Vector3<R> {
+        (x1 - x0).cross(&(x2 - x0))
+    }
+
+    fn collapse(&mut self, keep: usize, remove: usize, position: Vector3<R>) {
+        for t in std::mem::take(&mut self.vertex_triangles[remove]) {
+            if !self.triangle_alive[t] {
+                continue;
+            }
+
+            let tri = &mut self.triangles[t];
+            if tri.contains(&keep) {
+                self.triangle_alive[t] = false;
+                self.num_triangles -= 1;
+            } else {
+                for w in tri.iter_mut().filter(|w| **w == remove) {
+                    *w = keep;
+                }
+                self.vertex_triangles[keep].push(t);
+            }
+        }
+
+        let triangle_alive = &self.triangle_alive;
+        self.vertex_triangles[keep].retain(|&t| triangle_alive[t]);
+        self.vertex_removed[remove] = true;
+        self.vertices[keep] = position;
+        self.quadrics[keep] = self.quadrics[keep] + self.quadrics[remove];
+        self.versions[keep] += 1;
+        self.versions[remove] += 1;
+
+        for neighbor in self.neighbors(keep) {
+            self.push_candidate(keep, neighbor);
+        }
+    }
+
+    /// Stores the remaining vertices and triangles in the given mesh
+    fn write_into(self, mesh: &mut TriMesh3d<R>) -> DecimationMap {
+        let mut decimation_map = DecimationMap::default();
+        let mut new_indices = vec![usize::MAX; self.vertices.len()];
+        mesh.vertices.clear();
+        for (v, vertex) in self.vertices.iter().enumerate() {
+            if !self.vertex_removed[v] {
+                new_indices[v] = mesh.vertices.len();
+                mesh.vertices.push(*vertex);
+                decimation_map.vertices.push(v);
+            }
+        }
+
+        mesh.triangles.clear();
+        for (t, tri) in self.triangles.iter().enumerate() {
+            if self.triangle_alive[t] {
+                mesh.triangles.push(tri.map(|v| new_indices[v]));
+                decimation_map.triangles.push(t);
+            }
+        }
+
+        decimation_map
+    }
+}
+
+#[test]
+fn test_decimate_sphere() {
+    use crate::mesh::{AttributeData, MeshAttribute};
+
+    let center = Vector3::new(1.0, -2.0, 0.5);
+    let sphere = TriMesh3d::<f64>::icosphere(&center, 1.0, 4);
+    let mut mesh = MeshWithData::new(sphere.clone())
+        .with_point_data(MeshAttribute::new(
+            "id",
+            AttributeData::ScalarU64((0..sphere.vertices.len() as u64).collect()),
+        ))
+        .with_cell_data(MeshAttribute::new(
+            "id",
+            AttributeData::ScalarU64((0..sphere.triangles.len() as u64).collect()),
+        ));
+
+    let decimation_map = decimate_with_data(&mut mesh, 1000);
+    assert_eq!(mesh.mesh.triangles.len(), 1000);
+    assert_eq!(mesh.mesh.vertices.len(), 502);
+    assert!(mesh.mesh.validity_report().is_valid());
+    assert!((mesh.mesh.volume() - sphere.volume()).abs() < 0.05 * sphere.volume());
+
+    // All triangles are still oriented outwards
+    for tri in mesh.mesh.triangles.iter() {
+        let [x0, x1, x2] = tri.map(|v| mesh.mesh.vertices[v]);
+        let normal = (x1 - x0).cross(&(x2 - x0));
+        assert!(normal.dot(&((x0 + x1 + x2) / 3.0 - center)) > 0.0);
+    }
+
+    // The attributes are taken from the remaining vertices and triangles
+    let ids = |attributes: &[MeshAttribute<f64>]| match &attributes[0].data {
+        AttributeData::ScalarU64(ids) => ids.iter().map(|&id| id as usize).collect::<Vec<_>>(),
+        _ => unreachable!(),
+    };
+    assert_eq!(ids(&mesh.point_attributes), decimation_map.vertices);
+    assert_eq!(ids(&mesh.cell_attributes), decimation_map.triangles);
+
+    // The decimation is deterministic
+    let mut repeated = sphere.clone();
+    assert_eq!(decimate(&mut repeated, 1000), decimation_map);
+    assert_eq!(repeated.vertices, mesh.mesh.vertices);
+    assert_eq!(repeated.triangles, mesh.mesh.triangles);
+
+    // A tetrahedron cannot be decimated any further
+    let mut tetrahedron = TriMesh3d {
+        vertices: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+        ],
+        triangles: vec![[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]],
+    };
+    decimate(&mut tetrahedron, 0);
+    assert_eq!(tetrahedron.triangles.len(), 4);
+    assert!(tetrahedron.validity_report().is_valid());
+}
+
+#[test]
+fn test_decimate_keeps_boundary() {
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 3);
+    let mut hemisphere = sphere.clone();
+    hemisphere
+        .triangles
+        .retain(|tri| tri.iter().all(|&v| sphere.vertices[v].z >= 0.0));
+    hemisphere.remove_unused_vertices();
+
+    let boundary_vertices = |mesh: &TriMesh3d<f64>| {
+        let mut vertices = mesh
+            .find_boundary_edges()
+            .into_iter()
+            .map(|(edge, _, _)| mesh.vertices[edge[0]])
+            .collect::<Vec<_>>();
+        vertices.sort_by(|a, b| a.as_slice().partial_cmp(b.as_slice()).unwrap());
+        vertices
+    };
+
+    let mut decimated = hemisphere.clone();
+    decimate(&mut decimated, hemisphere.triangles.len() / 4);
+    assert!(decimated.triangles.len() < hemisphere.triangles.len() / 2);
+    assert_eq!(
+        boundary_vertices(&decimated),
+        boundary_vertices(&hemisphere)
+    );
+
+    let report = decimated.validity_report();
+    assert_eq!(report.non_manifold_edges.count, 0);
+    assert_eq!(report.isolated_vertices.count, 0);
+}
@@ -0,0 +1,524 @@
+//! Point-sampled distances between triangle meshes, e.g. for regression tests of reconstructed surfaces
+//!
+//! The distance from one mesh to another is evaluated by sampling points on the triangles of the first mesh and
+//! computing the distance of each sample point to the closest triangle of the second mesh. The closest triangles are
+//! found with a uniform grid that stores every triangle of the second mesh in all cells overlapped by its bounding box.
+//!
+//! The samples are the vertices of the triangles and the centroids of the `n²` triangles obtained by subdividing every
+//! triangle into `n` segments per edge. The maximum is taken over all samples, i.e. it approximates the (one-sided)
+//! Hausdorff distance from below. The mean and root mean square (RMS) distances are area weighted averages over the
+//! centroids, i.e. they approximate the corresponding integrals over the surface of the first mesh.
+
+use crate::aabb::AxisAlignedBoundingBox3d;
+use crate::mesh::TriMesh3d;
+use crate::uniform_grid::UniformGrid;
+use crate::{new_map, profile, Index, MapType, Real};
+use nalgebra::Vector3;
+use num::Bounded;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Statistics of the distances from the sample points of one mesh to the closest points on another mesh
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MeshDistance<R: Real> {
+    /// Largest distance of all sample points, approximates the Hausdorff distance
+    pub max: R,
+    /// Area weighted mean distance
+    pub mean: R,
+    /// Area weighted root mean square distance
+    pub rms: R,
+}
+
+/// Computes the distances from sample points on the triangles of `from` to the closest points on the mesh `to`
+///
+/// Every triangle of `from` is subdivided into `samples_per_edge²` smaller triangles whose centroids are used
+/// as sample points in addition to the vertices (see the [module level documentation](self)). Returns `None` if one
+/// of the meshes does not contain any triangle with a positive area.
+///
+/// Note that this distance is not symmetric, e.g. a small patch of a large mesh has a distance of zero to the
+/// large mesh but not the other way round. See [`symmetric_distance`] for a symmetric version.
+pub fn one_sided_distance<R: Real>(
+    from: &TriMesh3d<R>,
+    to: &TriMesh3d<R>,
+    samples_per_edge: usize,
+) -> Option<MeshDistance<R>> {
+    profile!("one_sided_distance");
+    one_sided_distance_impl(from, to, samples_per_edge, false)
+}
+
+/// Computes the distances from sample points on the triangles of `from` to the closest points on the mesh `to` (parallelized version), see [`one_sided_distance`]
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub fn par_one_sided_distance<R: Real>(
+    from: &TriMesh3d<R>,
+    to: &TriMesh3d<R>,
+    samples_per_edge: usize,
+) -> Option<MeshDistance<R>> {
+    profile!("par_one_sided_distance");
+    one_sided_distance_impl(from, to, samples_per_edge, true)
+}
+
+/// Computes the symmetric distance between the two meshes from the one-sided distances in both directions
+///
+/// The maximum is the larger of the two one-sided maxima (i.e. it approximates the Hausdorff distance), the mean
+/// and RMS distances are averaged over both directions. Returns `None` if one of the meshes does not contain any
+/// triangle with a positive area.
+pub fn symmetric_distance<R: Real>(
+    a: &TriMesh3d<R>,
+    b: &TriMesh3d<R>,
+    samples_per_edge: usize,
+) -> Option<MeshDistance<R>> {
+    profile!("symmetric_distance");
+    Some(combine_one_sided(
+        one_sided_distance_impl(a, b, samples_per_edge, false)?,
+        one_sided_distance_impl(b, a, samples_per_edge, false)?,
+    ))
+}
+
+/// Computes the symmetric distance between the two meshes (parallelized version), see [`symmetric_distance`]
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub fn par_symmetric_distance<R: Real>(
+    a: &TriMesh3d<R>,
+    b: &TriMesh3d<R>,
+    samples_per_edge: usize,
+) -> Option<MeshDistance<R>> {
+    profile!("par_symmetric_distance");
+    Some(combine_one_sided(
+        one_sided_distance_impl(a, b, samples_per_edge, true)?,
+        one_sided_distance_impl(b, a, samples_per_edge, true)?,
+    ))
+}
+
+fn combine_one_sided<R: Real>(ab: MeshDistance<R>, ba: MeshDistance<R>) -> MeshDistance<R> {
+    let half = R::from_f64(0.5).unwrap();
+    MeshDistance {
+        max: ab.max.max(ba.max),
+        mean: (ab.mean + ba.mean) * half,
+        rms: ((ab.rms * ab.rms + ba.rms * ba.rms) * half).sqrt(),
+    }
+}
+
+/// Running maximum and area weighted sums of the distances of the sample points
+#[derive(Copy, Clone)]
+struct DistanceSums<R: Real> {
+    max: R,
+    area: R,
+    weighted_sum: R,
+    weighted_squared_sum: R,
+}
+
+impl<R: Real> DistanceSums<R> {
+    fn zero() -> Self {
+        Self {
+            max: R::zero(),
+            area: R::zero(),
+            weighted_sum: R::zero(),
+            weighted_squared_sum: R::zero(),
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        Self {
+            max: self.max.max(other.max),
+            area: self.area + other.area,
+            weighted_sum: self.weighted_sum + other.weighted_sum,
+            weighted_squared_sum: self.weighted_squared_sum + other.weighted_squared_sum,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+fn one_sided_distance_impl<R: Real>(
+    from: &TriMesh3d<R>,
+    to: &TriMesh3d<R>,
+    samples_per_edge: usize,
+    parallel: bool,
+) -> Option<MeshDistance<R>> {
+    assert!(
+        samples_per_edge > 0,
+        "At least one sample per triangle edge is required"
+    );
+
+    let grid = TriangleGrid::new(to)?;
+    let n = samples_per_edge;
+    let n_real = R::from_usize(n).unwrap();
+    let third = R::one() / R::from_f64(3.0).unwrap();
+    let half = R::from_f64(0.5).unwrap();
+
+    let triangle_sums = |tri: &[usize; 3]| {
+        let [a, b, c] = tri.map(|v| from.vertices[v]);
+        let (ab, ac) = (b - a, c - a);
+        let weight = ab.cross(&ac).norm() * half / (n_real * n_real);
+
+        let mut sums = DistanceSums::<R>::zero();
+        for p in [a, b, c] {
+            sums.max = sums.max.max(grid.closest_distance_squared(&p).sqrt());
+        }
+
+        let mut add_sample = |u: R, v: R, weight: R| {
+            let distance = grid.closest_distance_squared(&(a + ab * u + ac * v)).sqrt();
+            sums.max = sums.max.max(distance);
+            sums.area += weight;
+            sums.weighted_sum += distance * weight;
+            sums.weighted_squared_sum += distance * distance * weight;
+        };
+
+        // Centroids of the upward and downward pointing triangles of the subdivision in barycentric coordinates
+        for i in 0..n {
+            for j in 0..(n - i) {
+                let (i_real, j_real) = (R::from_usize(i).unwrap(), R::from_usize(j).unwrap());
+                add_sample((i_real + third) / n_real, (j_real + third) / n_real, weight);
+                if i + j + 1 < n {
+                    let two_thirds = third + third;
+                    add_sample(
+                        (i_real + two_thirds) / n_real,
+                        (j_real + two_thirds) / n_real,
+                        weight,
+                    );
+                }
+            }
+        }
+        sums
+    };
+
+    let sequential_sums = || {
+        from.triangles
+            .iter()
+            .map(triangle_sums)
+            .fold(DistanceSums::zero(), DistanceSums::merge)
+    };
+
+    #[cfg(feature = "parallel")]
+    let sums = if parallel {
+        from.triangles
+            .par_iter()
+            .with_min_len(256)
+            .map(triangle_sums)
+            .reduce(DistanceSums::zero, DistanceSums::merge)
+    } else {
+        sequential_sums()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let sums = sequential_sums();
+
+    if sums.area <= R::zero() {
+        return None;
+    }
+
+    Some(MeshDistance {
+        max: sums.max,
+        mean: sums.weighted_sum / sums.area,
+        rms: (sums.weighted_squared_sum / sums.area).sqrt(),
+    })
+}
+
+/// Uniform grid storing the triangles of a mesh in all cells overlapped by their bounding boxes
+struct TriangleGrid<'a, R: Real> {
+    mesh: &'a TriMesh3d<R>,
+    grid: UniformGrid<i64, R>,
+    triangles_per_cell: MapType<i64, Vec<usize>>,
+}
+
+impl<'a, R: Real> TriangleGrid<'a, R> {
+    /// Builds the grid from all non-degenerate triangles of the mesh, returns `None` if there is no such triangle
+    fn new(mesh: &'a TriMesh3d<R>) -> Option<Self> {
+        let triangles = mesh
+            .triangles
+            .iter()
+            .enumerate()
+            .filter(|(_, tri)| {
+                let [a, b, c] = tri.map(|v| mesh.vertices[v]);
+                (b - a).cross(&(c - a)).norm_squared() > R::zero()
+            })
+            .map(|(t, _)| t)
+            .collect::<Vec<_>>();
+        if triangles.is_empty() {
+            return None;
+        }
+
+        // Cells of about the average edge length keep the number of triangles per cell small
+        let edge_length_sum = triangles
+            .iter()
+            .map(|&t| {
+                let [a, b, c] = mesh.triangles[t].map(|v| mesh.vertices[v]);
+                (b - a).norm() + (c - b).norm() + (a - c).norm()
+            })
+            .fold(R::zero(), |sum, l| sum + l);
+        let cell_size =
+            edge_length_sum / R::from_usize(triangles.len()).unwrap() / R::from_f64(3.0).unwrap();
+
+        let mut domain = AxisAlignedBoundingBox3d::from_points(&mesh.vertices);
+        domain.grow_uniformly(cell_size);
+        let grid = UniformGrid::from_aabb(&domain, cell_size).ok()?;
+
+        let mut triangle_grid = Self {
+            mesh,
+            grid,
+            triangles_per_cell: new_map(),
+        };
+        for t in triangles {
+            let [a, b, c] = mesh.triangles[t].map(|v| mesh.vertices[v]);
+            let ranges = [0, 1, 2].map(|axis| {
+                let min = a[axis].min(b[axis]).min(c[axis]);
+                let max = a[axis].max(b[axis]).max(c[axis]);
+                (
+                    triangle_grid.cell_coordinate(axis, min),
+                    triangle_grid.cell_coordinate(axis, max),
+                )
+            });
+            for i in ranges[0].0..=ranges[0].1 {
+                for j in ranges[1].0..=ranges[1].1 {
+                    for k in ranges[2].0..=ranges[2].1 {
+                        let cell = triangle_grid.grid.flatten_cell_indices(i, j, k);
+                        triangle_grid
+                            .triangles_per_cell
+                            .entry(cell)
+                            .or_default()
+                            .push(t);
+                    }
+                }
+            }
+        }
+
+        Some(triangle_grid)
+    }
+
+    /// Returns the index of the cell containing the coordinate along the axis, clamped to the grid
+    fn cell_coordinate(&self, axis: usize, x: R) -> i64 {
+        let last_cell = (self.grid.cells_per_dim()[axis] - 1).to_real_unchecked::<R>();
+        let cell = ((x - self.grid.aabb().min()[axis]) / self.grid.cell_size()).floor();
+        cell.max(R::zero()).min(last_cell).to_index_unchecked()
+    }
+
+    /// Returns the squared distance of the point to the closest triangle of the grid
+    ///
+    /// The cells are visited in rings of growing Chebyshev distance around the cell of the point (projected to the
+    /// grid) until the rings cannot contain any triangle closer than the closest triangle found so far.
+    fn closest_distance_squared(&self, p: &Vector3<R>) -> R {
+        let grid_min = self.grid.aabb().min();
+        let grid_max = self.grid.aabb().max();
+        // Every point of the grid is at least as far away from the point as its projection to the grid
+        let projected = Vector3::from_fn(|axis, _| p[axis].max(grid_min[axis]).min(grid_max[axis]));
+        let outside_squared = (p - projected).norm_squared();
+
+        let center = [0, 1, 2].map(|axis| self.cell_coordinate(axis, projected[axis]));
+        let n_cells = *self.grid.cells_per_dim();
+        let max_ring = n_cells.iter().copied().max().unwrap();
+
+        let cell_distance_squared = |i: i64, j: i64, k: i64| {
+            let cell = self.grid.flatten_cell_indices(i, j, k);
+            let mut best = <R as Bounded>::max_value();
+            if let Some(triangles) = self.triangles_per_cell.get(&cell) {
+                for &t in triangles {
+                    let [a, b, c] = self.mesh.triangles[t].map(|v| self.mesh.vertices[v]);
+                    best = best.min(point_triangle_distance_squared(p, &a, &b, &c));
+                }
+            }
+            best
+        };
+
+        let mut best = <R as Bounded>::max_value();
+
+        for r in 0..=max_ring {
+            // Offsets of the ring clamped to the grid
+            let range =
+                |axis: usize| (-r).max(-center[axis])..=r.min(n_cells[axis] - 1 - center[axis]);
+            for di in range(0) {
+                for dj in range(1) {
+                    if di.abs() == r || dj.abs() == r {
+                        for dk in range(2) {
+                            best = best.min(cell_distance_squared(
+                                center[0] + di,
+                                center[1] + dj,
+                                center[2] + dk,
+                            ));
+                        }
+                    } else {
+                        for dk in [-r, r] {
+                            if range(2).contains(&dk) {
+                                best = best.min(cell_distance_squared(
+                                    center[0] + di,
+                                    center[1] + dj,
+                                    center[2] + dk,
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+
+            // All cells outside of the rings visited so far are beyond the faces of the box of the visited cells
+            let cell_size = self.grid.cell_size();
+            let box_distance = (0..3)
+                .flat_map(|axis| {
+                    let lower = grid_min[axis] + cell_size * R::from_i64(center[axis] - r).unwrap();
+                    let upper =
+                        grid_min[axis] + cell_size * R::from_i64(center[axis] + r + 1).unwrap();
+                    // Faces at the boundary of the grid do not have any cells behind them
+                    let lower = (center[axis] - r > 0).then(|| projected[axis] - lower);
+                    let upper =
+                        (center[axis] + r + 1 < n_cells[axis]).then(|| upper - projected[axis]);
+                    lower.into_iter().chain(upper)
+                })
+                .fold(None, |min: Option<R>, d| {
+                    Some(min.map_or(d, |min| min.min(d)))
+                });
+            match box_distance {
+                Some(d) if best > outside_squared + d * d => {}
+                // Either all cells were visited or no unvisited cell can contain a closer triangle
+                _ => break,
+            }
+        }
+
+        best
+    }
+}
+
+/// Returns the squared distance of the point to the closest point on the (non-degenerate) triangle `abc`
+///
+/// Determines the Voronoi region of the triangle that contains the point, see Ericson, "Real-Time Collision Detection", 2004.
+fn point_triangle_distance_squared<R: Real>(
+    p: &Vector3<R>,
+    a: &Vector3<R>,
+    b: &Vector3<R>,
+    c: &Vector3<R>,
+) -> R {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(&ap);
+    let d2 = ac.dot(&ap);
+    if d1 <= R::zero() && d2 <= R::zero() {
+        return ap.norm_squared();
+    }
+
+    let bp = p - b;
+    let d3 = ab.dot(&bp);
+    let d4 = ac.dot(&bp);
+    if d3 >= R::zero() && d4 <= d3 {
+        return bp.norm_squared();
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= R::zero() && d1 >= R::zero() && d3 <= R::zero() {
+        let v = d1 / (d1 - d3);
+        return (ap - ab * v).norm_squared();
+    }
+
+    let cp = p - c;
+    let d5 = ab.dot(&cp);
+    let d6 = ac.dot(&cp);
+    if d6 >= R::zero() && d5 <= d6 {
+        return cp.norm_squared();
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= R::zero() && d2 >= R::zero() && d6 <= R::zero() {
+        let w = d2 / (d2 - d6);
+        return (ap - ac * w).norm_squared();
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= R::zero() && (d4 - d3) >= R::zero() && (d5 - d6) >= R::zero() {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return (bp - (c - b) * w).norm_squared();
+    }
+
+    let denom = R::one() / (va + vb + vc);
+    let v = vb * denom;
+    let w = vc * denom;
+    (ap - ab * v - ac * w).norm_squared()
+}
+
+#[cfg(test)]
+fn flat_grid(n: usize, z: f64) -> TriMesh3d<f64> {
+    let mut grid = TriMesh3d::default();
+    for j in 0..=n {
+        for i in 0..=n {
+            grid.vertices.push(Vector3::new(i as f64, j as f64, z));
+        }
+    }
+    let index = |i: usize, j: usize| j * (n + 1) + i;
+    for j in 0..n {
+        for i in 0..n {
+            grid.triangles
+                .push([index(i, j), index(i + 1, j), index(i + 1, j + 1)]);
+            grid.triangles
+                .push([index(i, j), index(i + 1, j + 1), index(i, j + 1)]);
+        }
+    }
+    grid
+}
+
+#[test]
+fn test_point_triangle_distance() {
+    let (a, b, c) = (Vector3::zeros(), Vector3::x(), Vector3::y());
+    let check = |p: Vector3<f64>, expected: f64| {
+        let d = point_triangle_distance_squared(&p, &a, &b, &c).sqrt();
+        assert!((d - expected).abs() < 1e-12, "{} at {:?}", d, p);
+    };
+    // Face, vertex and edge regions
+    check(Vector3::new(0.25, 0.25, 2.0), 2.0);
+    check(Vector3::new(-1.0, -1.0, 0.0), 2.0f64.sqrt());
+    check(Vector3::new(2.0, 0.0, 0.0), 1.0);
+    check(Vector3::new(0.0, 3.0, 0.0), 2.0);
+    check(Vector3::new(0.5, -1.0, 0.0), 1.0);
+    check(Vector3::new(-0.5, 0.5, 0.0), 0.5);
+    check(Vector3::new(1.0, 1.0, 0.0), 0.5f64.sqrt());
+}
+
+#[test]
+fn test_mesh_distance_planes() {
+    let plane = flat_grid(8, 0.0);
+    let distance = symmetric_distance(&plane, &plane, 3).unwrap();
+    assert!(distance.max < 1e-12 && distance.rms < 1e-12);
+
+    // A parallel plane has a constant distance
+    let shifted = flat_grid(8, 0.5);
+    let distance = symmetric_distance(&plane, &shifted, 2).unwrap();
+    assert!((distance.max - 0.5).abs() < 1e-12);
+    assert!((distance.mean - 0.5).abs() < 1e-12);
+    assert!((distance.rms - 0.5).abs() < 1e-12);
+
+    // A patch of the plane is close to the plane but not the other way round
+    let patch = flat_grid(2, 0.0);
+    let patch_to_plane = one_sided_distance(&patch, &plane, 2).unwrap();
+    assert!(patch_to_plane.max < 1e-12);
+    let plane_to_patch = one_sided_distance(&plane, &patch, 2).unwrap();
+    assert!((plane_to_patch.max - 72.0f64.sqrt()).abs() < 1e-12);
+    assert!(plane_to_patch.mean > 1.0);
+    assert!(plane_to_patch.rms > plane_to_patch.mean);
+
+    // Meshes far away from each other
+    let mut far = plane.clone();
+    far.translate(&Vector3::new(0.0, 0.0, 1000.0));
+    let distance = symmetric_distance(&plane, &far, 1).unwrap();
+    assert!((distance.max - 1000.0).abs() < 1e-9);
+
+    // Meshes without triangles
+    assert_eq!(one_sided_distance(&plane, &TriMesh3d::default(), 1), None);
+    assert_eq!(one_sided_distance(&TriMesh3d::default(), &plane, 1), None);
+}
+
+#[test]
+fn test_mesh_distance_spheres() {
+    let center = Vector3::new(1.0, 2.0, -0.5);
+    let sphere = TriMesh3d::<f64>::icosphere(&center, 1.0, 2);
+    let larger = TriMesh3d::<f64>::icosphere(&center, 1.2, 2);
+
+    // The vertices of the larger sphere are exactly 0.2 away from the smaller sphere, the triangles slightly less
+    let distance = symmetric_distance(&sphere, &larger, 2).unwrap();
+    assert!(distance.max <= 0.2 + 1e-12 && distance.max > 0.19);
+    assert!(distance.mean < distance.max && distance.mean > 0.17);
+    assert!(distance.rms >= distance.mean && distance.rms < distance.max);
+
+    #[cfg(feature = "parallel")]
+    {
+        let par_distance = par_symmetric_distance(&sphere, &larger, 2).unwrap();
+        assert!((par_distance.max - distance.max).abs() < 1e-12);
+        assert!((par_distance.mean - distance.mean).abs() < 1e-12);
+        assert!((par_distance.rms - distance.rms).abs() < 1e-12);
+        assert!(par_one_sided_distance(&sphere, &sphere, 2).unwrap().max < 1e-12);
+    }
+}
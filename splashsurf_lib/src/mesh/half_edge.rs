@@ -0,0 +1,736 @@
+//! Half-edge representation of manifold triangle meshes for topological queries and local modifications
+//!
+//! A [`HalfEdgeMesh`] stores every edge of a triangle mesh as two opposite [`HalfEdge`]s. The half-edges of a face
+//! are linked in the order of its vertices, the boundaries of open meshes are represented by half-edges without a face
+//! that are linked to loops around the holes. This allows to iterate the one-ring of a vertex or the boundary loops
+//! without searching and to modify the mesh locally with edge flips and collapses.
+//!
+//! The construction with [`HalfEdgeMesh::from_tri_mesh`] fails for triangle meshes that cannot be represented
+//! this way, i.e. meshes with degenerate triangles, non-manifold edges or vertices, or inconsistently oriented triangles.
+
+use crate::mesh::TriMesh3d;
+use crate::{new_map, profile, Real};
+use nalgebra::Vector3;
+use thiserror::Error as ThisError;
+
+/// A directed edge of a [`HalfEdgeMesh`], the start vertex is the end vertex of its twin
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HalfEdge {
+    /// Vertex that the half-edge points to
+    pub to: usize,
+    /// Face that the half-edge belongs to, `None` for half-edges on the boundary of the mesh
+    pub face: Option<usize>,
+    /// Next half-edge of the face or of the boundary loop
+    pub next: usize,
+    /// Half-edge in the opposite direction
+    pub twin: usize,
+}
+
+/// Error type for the construction of a [`HalfEdgeMesh`] from a triangle mesh
+#[rustfmt::skip]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, ThisError)]
+pub enum HalfEdgeMeshError {
+    /// A triangle references a vertex that does not exist
+    #[error("triangle {0} references a vertex index that is out of range")]
+    InvalidVertexIndex(usize),
+    /// A triangle references the same vertex more than once
+    #[error("triangle {0} is degenerate, it references the same vertex more than once")]
+    DegenerateTriangle(usize),
+    /// An edge is shared by more than two triangles or by two triangles with inconsistent orientation
+    #[error("the edge from vertex {0} to vertex {1} is shared by more than two triangles or by inconsistently oriented triangles")]
+    NonManifoldEdge(usize, usize),
+    /// The triangles around a vertex do not form a single fan
+    #[error("vertex {0} is non-manifold, its adjacent triangles do not form a single fan")]
+    NonManifoldVertex(usize),
+}
+
+/// Half-edge representation of a manifold triangle mesh, see the [module level documentation](self)
+///
+/// Vertices, faces and half-edges are identified by their index. Indices stay valid when the mesh is modified,
+/// removed elements are only marked as removed and skipped by the conversion back with [`Self::to_tri_mesh`].
+#[derive(Clone, Debug)]
+pub struct HalfEdgeMesh<R: Real> {
+    vertices: Vec<Vector3<R>>,
+    /// Outgoing half-edge of every vertex, a boundary half-edge for vertices on the boundary, `None` for isolated or removed vertices
+    vertex_half_edges: Vec<Option<usize>>,
+    removed_vertices: Vec<bool>,
+    half_edges: Vec<HalfEdge>,
+    removed_half_edges: Vec<bool>,
+    /// First half-edge of every face, `None` for removed faces
+    face_half_edges: Vec<Option<usize>>,
+}
+
+impl<R: Real> HalfEdgeMesh<R> {
+    /// Builds the half-edge representation of the triangle mesh, fails if the mesh is not a consistently oriented manifold
+    ///
+    /// The half-edge `3 * f + i` of face `f` points from vertex `i` to vertex `(i + 1) % 3` of the triangle `f`.
+    /// Isolated vertices are allowed.
+    pub fn from_tri_mesh(mesh: &TriMesh3d<R>) -> Result<Self, HalfEdgeMeshError> {
+        profile!("HalfEdgeMesh::from_tri_mesh");
+
+        let num_vertices = mesh.vertices.len();
+        let mut half_edges = Vec::with_capacity(3 * mesh.triangles.len());
+        let mut directed_edges = new_map();
+        for (f, tri) in mesh.triangles.iter().enumerate() {
+            if tri.iter().any(|&v| v >= num_vertices) {
+                return Err(HalfEdgeMeshError::InvalidVertexIndex(f));
+            }
+            if tri[0] == tri[1] || tri[1] == tri[2] || tri[2] == tri[0] {
+                return Err(HalfEdgeMeshError::DegenerateTriangle(f));
+            }
+
+            for i in 0..3 {
+                let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                if directed_edges.insert((a, b), 3 * f + i).is_some() {
+                    return Err(HalfEdgeMeshError::NonManifoldEdge(a, b));
+                }
+                half_edges.push(HalfEdge {
+                    to: b,
+                    face: Some(f),
+                    next: 3 * f + (i + 1) % 3,
+                    twin: usize::MAX,
+                });
+            }
+        }
+
+        // Link the twins, edges with only one face get a boundary half-edge in the opposite direction
+        let num_face_half_edges = half_edges.len();
+        let mut boundary_half_edges_from = new_map();
+        for h in 0..num_face_half_edges {
+            if half_edges[h].twin != usize::MAX {
+                continue;
+            }
+
+            let (a, b) = (half_edges[h - h % 3 + (h + 2) % 3].to, half_edges[h].to);
+            match directed_edges.get(&(b, a)) {
+                Some(&twin) => {
+                    half_edges[h].twin = twin;
+                    half_edges[twin].twin = h;
+                }
+                None => {
+                    let twin = half_edges.len();
+                    half_edges.push(HalfEdge {
+                        to: a,
+                        face: None,
+                        next: usize::MAX,
+                        twin: h,
+                    });
+                    half_edges[h].twin = twin;
+                    // A vertex on more than one boundary loop is the tip of several fans
+                    if boundary_half_edges_from.insert(b, twin).is_some() {
+                        return Err(HalfEdgeMeshError::NonManifoldVertex(b));
+                    }
+                }
+            }
+        }
+
+        for half_edge in half_edges.iter_mut().skip(num_face_half_edges) {
+            half_edge.next = *boundary_half_edges_from
+                .get(&half_edge.to)
+                .ok_or(HalfEdgeMeshError::NonManifoldVertex(half_edge.to))?;
+        }
+
+        // Boundary vertices start at their boundary half-edge such that iterating around them covers the whole fan
+        let mut vertex_half_edges = vec![None; num_vertices];
+        let mut num_outgoing = vec![0; num_vertices];
+        for (&v, &h) in boundary_half_edges_from.iter() {
+            vertex_half_edges[v] = Some(h);
+        }
+        for h in 0..half_edges.len() {
+            let from = half_edges[half_edges[h].twin].to;
+            num_outgoing[from] += 1;
+            vertex_half_edges[from].get_or_insert(h);
+        }
+
+        let half_edge_mesh = Self {
+            vertices: mesh.vertices.clone(),
+            vertex_half_edges,
+            removed_vertices: vec![false; num_vertices],
+            removed_half_edges: vec![false; half_edges.len()],
+            half_edges,
+            face_half_edges: (0..mesh.triangles.len()).map(|f| Some(3 * f)).collect(),
+        };
+
+        // All outgoing half-edges of a manifold vertex are reached by iterating around it
+        for (v, &num_outgoing) in num_outgoing.iter().enumerate() {
+            if half_edge_mesh.outgoing_half_edges(v).count() != num_outgoing {
+                return Err(HalfEdgeMeshError::NonManifoldVertex(v));
+            }
+        }
+
+        Ok(half_edge_mesh)
+    }
+
+    /// Converts the mesh back to a triangle mesh, skipping all removed vertices and faces
+    ///
+    /// The remaining vertices and faces keep their relative order. Every triangle starts at the first half-edge of its face.
+    pub fn to_tri_mesh(&self) -> TriMesh3d<R> {
+        let mut new_indices = vec![usize::MAX; self.vertices.len()];
+        let mut vertices = Vec::with_capacity(self.vertices.len());
+        for (v, vertex) in self.vertices.iter().enumerate() {
+            if !self.removed_vertices[v] {
+                new_indices[v] = vertices.len();
+                vertices.push(*vertex);
+            }
+        }
+
+        let triangles = (0..self.face_half_edges.len())
+            .filter_map(|f| self.face_vertices(f))
+            .map(|tri| tri.map(|v| new_indices[v]))
+            .collect();
+
+        TriMesh3d {
+            vertices,
+            triangles,
+        }
+    }
+
+    /// Returns the number of vertices including removed vertices
+    pub fn num_vertices(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// Returns the number of faces including removed faces
+    pub fn num_faces(&self) -> usize {
+        self.face_half_edges.len()
+    }
+
+    /// Returns the number of half-edges including removed half-edges
+    pub fn num_half_edges(&self) -> usize {
+        self.half_edges.len()
+    }
+
+    /// Returns the position of the vertex
+    pub fn vertex(&self, v: usize) -> &Vector3<R> {
+        &self.vertices[v]
+    }
+
+    /// Returns a mutable reference to the position of the vertex, e.g. to move a vertex after an edge collapse
+    pub fn vertex_mut(&mut self, v: usize) -> &mut Vector3<R> {
+        &mut self.vertices[v]
+    }
+
+    /// Returns the half-edge with the given index
+    pub fn half_edge(&self, h: usize) -> &HalfEdge {
+        &self.half_edges[h]
+    }
+
+    /// Returns the vertex that the half-edge starts at
+    pub fn from_vertex(&self, h: usize) -> usize {
+        self.half_edges[self.half_edges[h].twin].to
+    }
+
+    /// Returns whether the vertex was removed by an edge collapse
+    pub fn is_vertex_removed(&self, v: usize) -> bool {
+        self.removed_vertices[v]
+    }
+
+    /// Returns whether the face was removed by an edge collapse
+    pub fn is_face_removed(&self, f: usize) -> bool {
+        self.face_half_edges[f].is_none()
+    }
+
+    /// Returns whether the half-edge was removed by an edge collapse
+    pub fn is_half_edge_removed(&self, h: usize) -> bool {
+        self.removed_half_edges[h]
+    }
+
+    /// Returns an outgoing half-edge of the vertex (a boundary half-edge for boundary vertices), `None` for isolated or removed vertices
+    pub fn vertex_half_edge(&self, v: usize) -> Option<usize> {
+        self.vertex_half_edges[v]
+    }
+
+    /// Returns the first half-edge of the face, `None` for removed faces
+    pub fn face_half_edge(&self, f: usize) -> Option<usize> {
+        self.face_half_edges[f]
+    }
+
+    /// Returns the vertices of the face starting with the start vertex of its first half-edge, `None` for removed faces
+    pub fn face_vertices(&self, f: usize) -> Option<[usize; 3]> {
+        let h = self.face_half_edges[f]?;
+        let next = self.half_edges[h].next;
+        Some([
+            self.from_vertex(h),
+            self.half_edges[h].to,
+            self.half_edges[next].to,
+        ])
+    }
+
+    /// Returns whether the half-edge lies on the boundary of the mesh, i.e. it does not belong to a face
+    pub fn is_boundary_half_edge(&self, h: usize) -> bool {
+        self.half_edges[h].face.is_none()
+    }
+
+    /// Returns whether the edge of the half-edge lies on the boundary of the mesh, i.e. the half-edge or its twin does not belong to a face
+    pub fn is_boundary_edge(&self, h: usize) -> bool {
+        self.is_boundary_half_edge(h) || self.is_boundary_half_edge(self.half_edges[h].twin)
+    }
+
+    /// Returns whether the vertex lies on the boundary of the mesh
+    pub fn is_boundary_vertex(&self, v: usize) -> bool {
+        self.vertex_half_edges[v].is_some_and(|h| self.is_boundary_half_edge(h))
+    }
+
+    /// Iterates over all outgoing half-edges of the vertex in the order of the faces around the vertex
+    ///
+    /// For boundary vertices, the iteration starts with the outgoing boundary half-edge.
+    pub fn outgoing_half_edges(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        let start = self.vertex_half_edges[v];
+        let mut current = start;
+        std::iter::from_fn(move || {
+            let h = current?;
+            let next = self.half_edges[self.half_edges[h].twin].next;
+            current = Some(next).filter(|&next| Some(next) != start);
+            Some(h)
+        })
+    }
+
+    /// Iterates over the one-ring of the vertex, i.e. all vertices that share an edge with the vertex, in the order of the faces around the vertex
+    pub fn vertex_one_ring(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        self.outgoing_half_edges(v).map(|h| self.half_edges[h].to)
+    }
+
+    /// Returns the half-edge from vertex `from` to vertex `to` if the vertices share an edge
+    pub fn find_half_edge(&self, from: usize, to: usize) -> Option<usize> {
+        self.outgoing_half_edges(from)
+            .find(|&h| self.half_edges[h].to == to)
+    }
+
+    /// Returns the vertices of all boundary loops of the mesh, every loop runs opposite to the orientation of the adjacent faces
+    pub fn boundary_loops(&self) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.half_edges.len()];
+        let mut loops = Vec::new();
+        for start in 0..self.half_edges.len() {
+            if visited[start]
+                || self.removed_half_edges[start]
+                || !self.is_boundary_half_edge(start)
+            {
+                continue;
+            }
+
+            let mut boundary_loop = Vec::new();
+            let mut h = start;
+            while !visited[h] {
+                visited[h] = true;
+                boundary_loop.push(self.from_vertex(h));
+                h = self.half_edges[h].next;
+            }
+            loops.push(boundary_loop);
+        }
+        loops
+    }
+
+    /// Returns whether the edge of the half-edge can be flipped, i.e. it is shared by two faces and the flip does not result in a non-manifold mesh
+    ///
+    /// The edge is not flipped if the opposite vertices of its faces are already connected or if an interior end point of the edge only has three neighbors.
+    pub fn can_flip_edge(&self, h: usize) -> bool {
+        if self.removed_half_edges[h] || self.is_boundary_edge(h) {
+            return false;
+        }
+
+        let t = self.half_edges[h].twin;
+        let c = self.half_edges[self.half_edges[h].next].to;
+        let d = self.half_edges[self.half_edges[t].next].to;
+        let has_enough_neighbors =
+            |v: usize| self.is_boundary_vertex(v) || self.outgoing_half_edges(v).count() > 3;
+
+        c != d
+            && self.find_half_edge(c, d).is_none()
+            && has_enough_neighbors(self.half_edges[h].to)
+            && has_enough_neighbors(self.half_edges[t].to)
+    }
+
+    /// Replaces the edge of the half-edge by the edge between the opposite vertices of its two faces, returns `false` and leaves the mesh unchanged if the edge cannot be flipped (see [`Self::can_flip_edge`])
+    ///
+    /// The half-edge and its twin are reused for the new edge, the half-edge then points from the opposite vertex of the face of its twin to the opposite vertex of its own face.
+    pub fn flip_edge(&mut self, h: usize) -> bool {
+        if !self.can_flip_edge(h) {
+            return false;
+        }
+
+        let t = self.half_edges[h].twin;
+        let (h1, t1) = (self.half_edges[h].next, self.half_edges[t].next);
+        let (h2, t2) = (self.half_edges[h1].next, self.half_edges[t1].next);
+        let (f0, f1) = (self.half_edges[h].face, self.half_edges[t].face);
+        let (c, d) = (self.half_edges[h1].to, self.half_edges[t1].to);
+        let (u, v) = (self.half_edges[t].to, self.half_edges[h].to);
+
+        // Face of `h`: (d, c, u), face of `t`: (c, d, v)
+        self.half_edges[h].to = c;
+        self.half_edges[h].next = h2;
+        self.half_edges[h2].next = t1;
+        self.half_edges[t1].next = h;
+        self.half_edges[t1].face = f0;
+
+        self.half_edges[t].to = d;
+        self.half_edges[t].next = t2;
+        self.half_edges[t2].next = h1;
+        self.half_edges[h1].next = t;
+        self.half_edges[h1].face = f1;
+
+        self.face_half_edges[f0.unwrap()] = Some(h);
+        self.face_half_edges[f1.unwrap()] = Some(t);
+        self.reset_vertex_half_edge(u, t1);
+        self.reset_vertex_half_edge(v, h1);
+        true
+    }
+
+    /// Returns whether the edge of the half-edge can be collapsed without resulting in a non-manifold mesh
+    ///
+    /// The collapse has to fulfill the link condition (the only common neighbors of the end points are the opposite vertices of
+    /// the faces of the edge), interior edges between two boundary vertices are not collapsed and no edge without faces may remain.
+    /// Additionally, edges are conservatively not collapsed if the opposite vertices of their faces share an edge (e.g. in a tetrahedron).
+    pub fn can_collapse_edge(&self, h: usize) -> bool {
+        if self.removed_half_edges[h] {
+            return false;
+        }
+
+        let t = self.half_edges[h].twin;
+        let (u, v) = (self.half_edges[t].to, self.half_edges[h].to);
+
+        let mut opposite_vertices = Vec::with_capacity(2);
+        for e in [h, t] {
+            if self.half_edges[e].face.is_some() {
+                let e1 = self.half_edges[e].next;
+                let e2 = self.half_edges[e1].next;
+                // Both other edges of the removed face would be left without a face
+                if self.is_boundary_half_edge(self.half_edges[e1].twin)
+                    && self.is_boundary_half_edge(self.half_edges[e2].twin)
+                {
+                    return false;
+                }
+                opposite_vertices.push(self.half_edges[e1].to);
+            }
+        }
+
+        if opposite_vertices.len() == 2 {
+            if self.is_boundary_vertex(u) && self.is_boundary_vertex(v) {
+                return false;
+            }
+            if self
+                .find_half_edge(opposite_vertices[0], opposite_vertices[1])
+                .is_some()
+            {
+                return false;
+            }
+        }
+
+        let num_common_neighbors = self
+            .vertex_one_ring(u)
+            .filter(|&w| self.vertex_one_ring(v).any(|x| x == w))
+            .count();
+        num_common_neighbors == opposite_vertices.len()
+    }
+
+    /// Collapses the edge of the half-edge by merging its start vertex into its end vertex, returns `false` and leaves the mesh unchanged if the edge cannot be collapsed (see [`Self::can_collapse_edge`])
+    ///
+    /// The start vertex, the faces of the edge and their half-edges are removed. The end vertex keeps its position,
+    /// it can be moved afterwards with [`Self::vertex_mut`].
+    pub fn collapse_edge(&mut self, h: usize) -> bool {
+        if !self.can_collapse_edge(h) {
+            return false;
+        }
+
+        let t = self.half_edges[h].twin;
+        let (u, v) = (self.half_edges[t].to, self.half_edges[h].to);
+        let u_outgoing = self.outgoing_half_edges(u).collect::<Vec<_>>();
+        let u_incoming = u_outgoing
+            .iter()
+            .map(|&o| self.half_edges[o].twin)
+            .collect::<Vec<_>>();
+
+        // Boundary half-edges of the edge are removed from their loop by linking their predecessor to their successor
+        let boundary_predecessors = [h, t].map(|e| {
+            let from = self.half_edges[self.half_edges[e].twin].to;
+            self.outgoing_half_edges(from)
+                .map(|o| self.half_edges[o].twin)
+                .find(|&i| self.is_boundary_half_edge(i) && self.half_edges[i].next == e)
+        });
+
+        let mut affected_vertices = Vec::with_capacity(2);
+        for (e, predecessor) in [h, t].into_iter().zip(boundary_predecessors) {
+            match self.half_edges[e].face {
+                Some(f) => {
+                    // The two remaining edges of the face are merged into one edge
+                    let e1 = self.half_edges[e].next;
+                    let e2 = self.half_edges[e1].next;
+                    let (o1, o2) = (self.half_edges[e1].twin, self.half_edges[e2].twin);
+                    self.half_edges[o1].twin = o2;
+                    self.half_edges[o2].twin = o1;
+                    self.removed_half_edges[e1] = true;
+                    self.removed_half_edges[e2] = true;
+                    self.face_half_edges[f] = None;
+                    affected_vertices.push((self.half_edges[e1].to, o1));
+                }
+                None => {
+                    let predecessor = predecessor.expect("boundary half-edges are part of a loop");
+                    self.half_edges[predecessor].next = self.half_edges[e].next;
+                }
+            }
+        }
+        self.removed_half_edges[h] = true;
+        self.removed_half_edges[t] = true;
+
+        for i in u_incoming {
+            if !self.removed_half_edges[i] {
+                self.half_edges[i].to = v;
+            }
+        }
+        self.removed_vertices[u] = true;
+        self.vertex_half_edges[u] = None;
+
+        let v_outgoing = self.vertex_half_edges[v]
+            .into_iter()
+            .chain(u_outgoing)
+            .find(|&o| !self.removed_half_edges[o])
+            .expect("the remaining vertex of a collapse keeps at least one edge");
+        self.reset_vertex_half_edge(v, v_outgoing);
+        for (w, outgoing) in affected_vertices {
+            self.reset_vertex_half_edge(w, outgoing);
+        }
+        true
+    }
+
+    /// Sets the outgoing half-edge of the vertex to its boundary half-edge if it has one or otherwise to the given half-edge
+    fn reset_vertex_half_edge(&mut self, v: usize, outgoing: usize) {
+        self.vertex_half_edges[v] = Some(outgoing);
+        let boundary_half_edge = self
+            .outgoing_half_edges(v)
+            .find(|&h| self.is_boundary_half_edge(h));
+        if let Some(boundary_half_edge) = boundary_half_edge {
+            self.vertex_half_edges[v] = Some(boundary_half_edge);
+        }
+    }
+}
+
+#[cfg(test)]
+fn assert_manifold<R: Real>(mesh: &TriMesh3d<R>) {
+    let report = mesh.validity_report();
+    assert!(report.non_manifold_edges.is_empty(), "{}", report);
+    assert!(report.duplicate_triangles.is_empty(), "{}", report);
+    assert!(report.isolated_vertices.is_empty(), "{}", report);
+    assert!(HalfEdgeMesh::from_tri_mesh(mesh).is_ok());
+}
+
+#[test]
+fn test_half_edge_mesh_queries() {
+    use crate::mesh::Connectivity;
+
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 2);
+    let mesh = HalfEdgeMesh::from_tri_mesh(&sphere).unwrap();
+    assert_eq!(mesh.num_half_edges(), 3 * sphere.triangles.len());
+    assert!(mesh.boundary_loops().is_empty());
+
+    let roundtrip = mesh.to_tri_mesh();
+    assert_eq!(roundtrip.vertices, sphere.vertices);
+    assert_eq!(roundtrip.triangles, sphere.triangles);
+
+    let connectivity = Connectivity::from_mesh(&sphere);
+    for v in 0..sphere.vertices.len() {
+        assert!(!mesh.is_boundary_vertex(v));
+        let mut one_ring = mesh.vertex_one_ring(v).collect::<Vec<_>>();
+        // Consecutive vertices of the one-ring are connected
+        for i in 0..one_ring.len() {
+            assert!(mesh
+                .find_half_edge(one_ring[i], one_ring[(i + 1) % one_ring.len()])
+                .is_some());
+        }
+        one_ring.sort_unstable();
+        assert_eq!(one_ring, connectivity.vertex_neighbors(v));
+    }
+
+    // An open mesh with two holes
+    let mut open_sphere = sphere.clone();
+    let [a, b, c] = open_sphere.triangles.remove(0);
+    let far_triangle = open_sphere
+        .triangles
+        .iter()
+        .position(|tri| tri.iter().all(|v| ![a, b, c].contains(v)))
+        .unwrap();
+    open_sphere.triangles.remove(far_triangle);
+    let mesh = HalfEdgeMesh::from_tri_mesh(&open_sphere).unwrap();
+    assert_eq!(mesh.to_tri_mesh().triangles, open_sphere.triangles);
+
+    let loops = mesh.boundary_loops();
+    assert_eq!(loops.len(), 2);
+    assert!(loops.iter().all(|boundary_loop| boundary_loop.len() == 3));
+    let mut first_loop = loops
+        .iter()
+        .find(|boundary_loop| boundary_loop.contains(&a))
+        .unwrap()
+        .clone();
+    first_loop.sort_unstable();
+    let mut removed_triangle = [a, b, c];
+    removed_triangle.sort_unstable();
+    assert_eq!(first_loop, removed_triangle);
+    assert!(mesh.is_boundary_vertex(a));
+    assert!(mesh.is_boundary_edge(mesh.find_half_edge(a, b).unwrap()));
+    // The one-ring of a boundary vertex starts and ends on the boundary
+    let one_ring = mesh.vertex_one_ring(a).collect::<Vec<_>>();
+    assert_eq!(
+        (one_ring[0], one_ring[one_ring.len() - 1]),
+        (b, c),
+        "one-ring of the boundary vertex: {:?}",
+        one_ring
+    );
+}
+
+#[test]
+fn test_half_edge_mesh_errors() {
+    let vertices = vec![Vector3::<f64>::zeros(); 7];
+    let build = |triangles: Vec<[usize; 3]>| {
+        HalfEdgeMesh::from_tri_mesh(&TriMesh3d {
+            vertices: vertices.clone(),
+            triangles,
+        })
+    };
+
+    assert!(build(vec![[0, 1, 2], [2, 1, 3], [0, 2, 3]]).is_ok());
+    assert_eq!(
+        build(vec![[0, 1, 7]]).unwrap_err(),
+        HalfEdgeMeshError::InvalidVertexIndex(0)
+    );
+    assert_eq!(
+        build(vec![[0, 1, 2], [2, 1, 1]]).unwrap_err(),
+        HalfEdgeMeshError::DegenerateTriangle(1)
+    );
+    // A fin on the edge (1, 2)
+    assert_eq!(
+        build(vec![[0, 1, 2], [2, 1, 3], [1, 2, 4]]).unwrap_err(),
+        HalfEdgeMeshError::NonManifoldEdge(1, 2)
+    );
+    // Inconsistent orientation
+    assert_eq!(
+        build(vec![[0, 1, 2], [1, 2, 3]]).unwrap_err(),
+        HalfEdgeMeshError::NonManifoldEdge(1, 2)
+    );
+    // Two open fans touching at vertex 0
+    assert_eq!(
+        build(vec![[0, 1, 2], [0, 3, 4]]).unwrap_err(),
+        HalfEdgeMeshError::NonManifoldVertex(0)
+    );
+
+    // Two closed surfaces (double-sided triangle fans) touching at vertex 0
+    let fan = |v: [usize; 3]| {
+        [
+            [0, v[0], v[1]],
+            [0, v[1], v[2]],
+            [0, v[2], v[0]],
+            [v[0], v[2], v[1]],
+        ]
+    };
+    let mut triangles = fan([1, 2, 3]).to_vec();
+    assert!(build(triangles.clone()).is_ok());
+    triangles.extend(fan([4, 5, 6]));
+    assert_eq!(
+        build(triangles).unwrap_err(),
+        HalfEdgeMeshError::NonManifoldVertex(0)
+    );
+}
+
+#[test]
+fn test_half_edge_mesh_flip() {
+    // A planar quad split along the diagonal (0, 2)
+    let quad = TriMesh3d::<f64> {
+        vertices: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(1.0, 1.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        ],
+        triangles: vec![[0, 1, 2], [0, 2, 3]],
+    };
+    let mut mesh = HalfEdgeMesh::from_tri_mesh(&quad).unwrap();
+    assert!(!mesh.flip_edge(mesh.find_half_edge(0, 1).unwrap()));
+
+    let diagonal = mesh.find_half_edge(0, 2).unwrap();
+    assert!(mesh.flip_edge(diagonal));
+    assert!(mesh.find_half_edge(0, 2).is_none());
+    assert!(mesh.find_half_edge(1, 3).is_some());
+
+    let flipped = mesh.to_tri_mesh();
+    assert_eq!(flipped.triangles, vec![[3, 1, 2], [1, 3, 0]]);
+    assert_eq!(flipped.area(), quad.area());
+    assert_eq!(mesh.boundary_loops(), vec![vec![1, 0, 3, 2]]);
+    assert!((0..4).all(|v| mesh.is_boundary_vertex(v)));
+
+    // Flipping all edges of a sphere once keeps it manifold
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 2);
+    let mut mesh = HalfEdgeMesh::from_tri_mesh(&sphere).unwrap();
+    let mut num_flips = 0;
+    for h in (0..mesh.num_half_edges()).step_by(7) {
+        if mesh.flip_edge(h) {
+            num_flips += 1;
+        }
+    }
+    assert!(num_flips > 0);
+    let flipped = mesh.to_tri_mesh();
+    assert_eq!(flipped.triangles.len(), sphere.triangles.len());
+    assert_manifold(&flipped);
+}
+
+#[test]
+fn test_half_edge_mesh_collapse() {
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 2);
+    let mut mesh = HalfEdgeMesh::from_tri_mesh(&sphere).unwrap();
+
+    let h = mesh
+        .find_half_edge(0, mesh.vertex_one_ring(0).next().unwrap())
+        .unwrap();
+    let v = mesh.half_edge(h).to;
+    assert!(mesh.collapse_edge(h));
+    assert!(mesh.is_vertex_removed(0));
+    assert!(mesh.is_half_edge_removed(h));
+    assert!(!mesh.vertex_one_ring(v).any(|w| w == 0));
+    let collapsed = mesh.to_tri_mesh();
+    assert_eq!(collapsed.vertices.len(), sphere.vertices.len() - 1);
+    assert_eq!(collapsed.triangles.len(), sphere.triangles.len() - 2);
+    assert_manifold(&collapsed);
+
+    // Collapse as many edges as possible, the result is still a closed manifold
+    let mut num_collapses = 1;
+    while let Some(h) = (0..mesh.num_half_edges()).find(|&h| mesh.can_collapse_edge(h)) {
+        assert!(mesh.collapse_edge(h));
+        num_collapses += 1;
+    }
+    let collapsed = mesh.to_tri_mesh();
+    assert_eq!(
+        collapsed.vertices.len(),
+        sphere.vertices.len() - num_collapses
+    );
+    assert_eq!(
+        collapsed.triangles.len(),
+        sphere.triangles.len() - 2 * num_collapses
+    );
+    assert_manifold(&collapsed);
+    assert!(collapsed.find_boundary_edges().is_empty());
+    assert!(collapsed.vertices.len() < sphere.vertices.len() / 2);
+
+    // Edges of a triangular hole cannot be collapsed without closing the hole to a single edge
+    let triangle_hole = TriMesh3d {
+        vertices: sphere.vertices.clone(),
+        triangles: sphere.triangles[1..].to_vec(),
+    };
+    let mesh = HalfEdgeMesh::from_tri_mesh(&triangle_hole).unwrap();
+    assert!((0..mesh.num_half_edges())
+        .filter(|&h| mesh.is_boundary_edge(h))
+        .all(|h| !mesh.can_collapse_edge(h)));
+
+    // Collapses on the boundary of an open mesh keep the boundary loop intact
+    let mut open_sphere = sphere.clone();
+    let [a, b, _] = open_sphere.triangles.remove(0);
+    open_sphere
+        .triangles
+        .retain(|tri| !(tri.contains(&a) && tri.contains(&b)));
+    let mut mesh = HalfEdgeMesh::from_tri_mesh(&open_sphere).unwrap();
+    assert_eq!(mesh.boundary_loops()[0].len(), 4);
+    let boundary_half_edge = (0..mesh.num_half_edges())
+        .find(|&h| mesh.is_boundary_half_edge(h))
+        .unwrap();
+    assert!(mesh.collapse_edge(boundary_half_edge));
+    let collapsed = mesh.to_tri_mesh();
+    assert_eq!(collapsed.triangles.len(), open_sphere.triangles.len() - 1);
+    assert_manifold(&collapsed);
+    assert_eq!(mesh.boundary_loops().len(), 1);
+    assert_eq!(mesh.boundary_loops()[0].len(), 3);
+}
@@ -0,0 +1,300 @@
+//! Smoothing of triangle meshes, e.g. as post-processing of a reconstructed surface
+//!
+//! The marching cubes triangulation of a density map computed from SPH particles is often quite bumpy at
+//! typical cube sizes. The functions of this module move every vertex towards the average position of its
+//! neighbors (uniform weights). The vertex-vertex adjacency is built once from the triangles of the mesh and
+//! reused for all iterations, see [`Connectivity`].
+//!
+//! Plain [`laplacian_smoothing`] shrinks the mesh with every iteration, which is noticeable for thin fluid
+//! sheets. [`taubin_smoothing`] alternates shrinking and inflating steps and approximately preserves the volume.
+
+use crate::mesh::{Connectivity, TriMesh3d};
+use crate::{profile, Real};
+use nalgebra::Vector3;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Returns a flag for every vertex of the mesh whether it is part of a boundary edge (an edge with only one adjacent triangle)
+pub fn boundary_vertices<R: Real>(mesh: &TriMesh3d<R>) -> Vec<bool> {
+    let mut is_boundary = vec![false; mesh.vertices.len()];
+    for (edge, _, _) in mesh.find_boundary_edges() {
+        is_boundary[edge[0]] = true;
+        is_boundary[edge[1]] = true;
+    }
+    is_boundary
+}
+
+/// Performs uniform Laplacian smoothing of the vertices of the mesh inplace
+///
+/// In every iteration, each vertex is moved by `lambda` times the difference between the average position of its
+/// neighbors and its own position, i.e. `lambda` should be in `(0, 1]`. Vertices without neighbors are not moved.
+/// If `fix_boundary` is set, vertices on boundary edges are kept fixed, e.g. to preserve the outline of a surface
+/// that was cut open. Note that Laplacian smoothing shrinks the mesh with every iteration.
+pub fn laplacian_smoothing<R: Real>(
+    mesh: &mut TriMesh3d<R>,
+    iterations: usize,
+    lambda: R,
+    fix_boundary: bool,
+) {
+    laplacian_smoothing_impl(mesh, iterations, lambda, fix_boundary, false);
+}
+
+/// Performs uniform Laplacian smoothing of the vertices of the mesh inplace (parallelized over the vertices), see [`laplacian_smoothing`]
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub fn par_laplacian_smoothing<R: Real>(
+    mesh: &mut TriMesh3d<R>,
+    iterations: usize,
+    lambda: R,
+    fix_boundary: bool,
+) {
+    laplacian_smoothing_impl(mesh, iterations, lambda, fix_boundary, true);
+}
+
+fn laplacian_smoothing_impl<R: Real>(
+    mesh: &mut TriMesh3d<R>,
+    iterations: usize,
+    lambda: R,
+    fix_boundary: bool,
+    parallel: bool,
+) {
+    profile!("laplacian_smoothing");
+    if iterations == 0 {
+        return;
+    }
+
+    let connectivity = Connectivity::from_mesh_impl(mesh, parallel);
+    let vertex_mask = fix_boundary.then(|| {
+        boundary_vertices(mesh)
+            .into_iter()
+            .map(|is_boundary| !is_boundary)
+            .collect::<Vec<_>>()
+    });
+    smooth_vertices(
+        &mut mesh.vertices,
+        &connectivity,
+        vertex_mask.as_deref(),
+        (0..iterations).map(|_| lambda),
+        parallel,
+    );
+}
+
+/// Performs Taubin λ/µ smoothing of the vertices of the mesh inplace
+///
+/// Every iteration consists of a Laplacian smoothing step with the positive weight `lambda` followed by a step
+/// with the negative weight `mu` that inflates the mesh again (see [`laplacian_smoothing`]), such that the volume
+/// of the mesh is approximately preserved. Typically, `0 < lambda < -mu` holds, e.g. `lambda = 0.5` and `mu = -0.53`.
+///
+/// If a `vertex_mask` is given, only the vertices with a `true` flag are moved (e.g. to exclude vertices close to the
+/// boundary of the domain), all other vertices are kept fixed. Panics if the length of the mask is different from
+/// the number of vertices of the mesh.
+pub fn taubin_smoothing<R: Real>(
+    mesh: &mut TriMesh3d<R>,
+    iterations: usize,
+    lambda: R,
+    mu: R,
+    vertex_mask: Option<&[bool]>,
+) {
+    taubin_smoothing_impl(mesh, iterations, lambda, mu, vertex_mask, false);
+}
+
+/// Performs Taubin λ/µ smoothing of the vertices of the mesh inplace (parallelized over the vertices), see [`taubin_smoothing`]
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub fn par_taubin_smoothing<R: Real>(
+    mesh: &mut TriMesh3d<R>,
+    iterations: usize,
+    lambda: R,
+    mu: R,
+    vertex_mask: Option<&[bool]>,
+) {
+    taubin_smoothing_impl(mesh, iterations, lambda, mu, vertex_mask, true);
+}
+
+fn taubin_smoothing_impl<R: Real>(
+    mesh: &mut TriMesh3d<R>,
+    iterations: usize,
+    lambda: R,
+    mu: R,
+    vertex_mask: Option<&[bool]>,
+    parallel: bool,
+) {
+    profile!("taubin_smoothing");
+    if iterations == 0 {
+        return;
+    }
+
+    let connectivity = Connectivity::from_mesh_impl(mesh, parallel);
+    smooth_vertices(
+        &mut mesh.vertices,
+        &connectivity,
+        vertex_mask,
+        (0..iterations).flat_map(|_| [lambda, mu]),
+        parallel,
+    );
+}
+
+/// Performs one smoothing step per given weight, only vertices with a `true` flag in the `vertex_mask` are moved (all vertices if not given)
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+fn smooth_vertices<R: Real>(
+    vertices: &mut Vec<Vector3<R>>,
+    connectivity: &Connectivity,
+    vertex_mask: Option<&[bool]>,
+    weights: impl Iterator<Item = R>,
+    parallel: bool,
+) {
+    assert_eq!(
+        connectivity.num_vertices(),
+        vertices.len(),
+        "The connectivity has to be built from the same mesh"
+    );
+    if let Some(vertex_mask) = vertex_mask {
+        assert_eq!(
+            vertex_mask.len(),
+            vertices.len(),
+            "The vertex mask needs one entry per vertex"
+        );
+    }
+
+    let mut smoothed_vertices = vertices.clone();
+    for weight in weights {
+        let current_vertices = &*vertices;
+        let smooth_vertex = |(i, smoothed_vertex): (usize, &mut Vector3<R>)| {
+            let vertex = current_vertices[i];
+            let neighbors = connectivity.vertex_neighbors(i);
+            *smoothed_vertex =
+                if neighbors.is_empty() || vertex_mask.is_some_and(|vertex_mask| !vertex_mask[i]) {
+                    vertex
+                } else {
+                    let sum = neighbors
+                        .iter()
+                        .fold(Vector3::zeros(), |sum, &j| sum + current_vertices[j]);
+                    let average = sum.unscale(R::from_usize(neighbors.len()).unwrap());
+                    vertex + (average - vertex) * weight
+                };
+        };
+
+        #[cfg(feature = "parallel")]
+        if parallel {
+            smoothed_vertices
+                .par_iter_mut()
+                .with_min_len(256)
+                .enumerate()
+                .for_each(smooth_vertex);
+        } else {
+            smoothed_vertices
+                .iter_mut()
+                .enumerate()
+                .for_each(smooth_vertex);
+        }
+        #[cfg(not(feature = "parallel"))]
+        smoothed_vertices
+            .iter_mut()
+            .enumerate()
+            .for_each(smooth_vertex);
+
+        std::mem::swap(vertices, &mut smoothed_vertices);
+    }
+}
+
+#[test]
+fn test_vertex_adjacency() {
+    // Two triangles sharing an edge and an isolated vertex
+    let mesh = TriMesh3d::<f64> {
+        vertices: vec![Vector3::zeros(); 5],
+        triangles: vec![[0, 1, 2], [2, 1, 3]],
+    };
+    let connectivity = Connectivity::from_mesh(&mesh);
+    assert_eq!(connectivity.num_vertices(), 5);
+    assert_eq!(connectivity.vertex_neighbors(0), &[1, 2]);
+    assert_eq!(connectivity.vertex_neighbors(1), &[0, 2, 3]);
+    assert_eq!(connectivity.vertex_neighbors(2), &[0, 1, 3]);
+    assert_eq!(connectivity.vertex_neighbors(3), &[1, 2]);
+    assert!(connectivity.vertex_neighbors(4).is_empty());
+
+    assert_eq!(
+        boundary_vertices(&mesh),
+        vec![true, true, true, true, false]
+    );
+}
+
+#[test]
+fn test_laplacian_smoothing() {
+    // Smoothing a noisy sphere reduces the deviation of the vertices from the average radius
+    let center = Vector3::new(1.0, -2.0, 0.5);
+    let mut sphere = TriMesh3d::<f64>::icosphere(&center, 1.0, 3);
+    for (i, v) in sphere.vertices.iter_mut().enumerate() {
+        let noise = 0.05 * ((i * 7919 % 101) as f64 / 100.0 - 0.5);
+        *v = center + (*v - center) * (1.0 + noise);
+    }
+    let radius_deviation = |mesh: &TriMesh3d<f64>| {
+        let radii = mesh
+            .vertices
+            .iter()
+            .map(|v| (v - center).norm())
+            .collect::<Vec<_>>();
+        let mean = radii.iter().sum::<f64>() / radii.len() as f64;
+        radii.iter().map(|r| (r - mean).abs()).fold(0.0, f64::max)
+    };
+
+    let mut smoothed = sphere.clone();
+    laplacian_smoothing(&mut smoothed, 5, 0.5, false);
+    assert!(radius_deviation(&smoothed) < 0.5 * radius_deviation(&sphere));
+    assert_eq!(smoothed.triangles, sphere.triangles);
+
+    #[cfg(feature = "parallel")]
+    {
+        let mut par_smoothed = sphere.clone();
+        par_laplacian_smoothing(&mut par_smoothed, 5, 0.5, false);
+        assert_eq!(par_smoothed.vertices, smoothed.vertices);
+    }
+
+    // Boundary vertices of an open mesh stay fixed
+    let mut open_mesh = sphere.clone();
+    open_mesh.triangles.truncate(open_mesh.triangles.len() / 2);
+    let is_boundary = boundary_vertices(&open_mesh);
+    assert!(is_boundary.iter().any(|&b| b));
+    laplacian_smoothing(&mut open_mesh, 5, 0.5, true);
+    for ((v, original), &is_boundary) in open_mesh
+        .vertices
+        .iter()
+        .zip(sphere.vertices.iter())
+        .zip(is_boundary.iter())
+    {
+        if is_boundary {
+            assert_eq!(v, original);
+        }
+    }
+}
+
+#[test]
+fn test_taubin_smoothing_mask() {
+    let mut sphere = TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 2);
+    for (i, v) in sphere.vertices.iter_mut().enumerate() {
+        *v *= 1.0 + 0.05 * ((i % 3) as f64 - 1.0);
+    }
+
+    // Only the vertices in the upper half are smoothed
+    let vertex_mask = sphere
+        .vertices
+        .iter()
+        .map(|v| v.z > 0.0)
+        .collect::<Vec<_>>();
+    let mut smoothed = sphere.clone();
+    taubin_smoothing(&mut smoothed, 3, 0.5, -0.53, Some(&vertex_mask));
+    for ((v, original), &is_smoothed) in smoothed
+        .vertices
+        .iter()
+        .zip(sphere.vertices.iter())
+        .zip(vertex_mask.iter())
+    {
+        assert_eq!(v != original, is_smoothed);
+    }
+
+    #[cfg(feature = "parallel")]
+    {
+        let mut par_smoothed = sphere.clone();
+        par_taubin_smoothing(&mut par_smoothed, 3, 0.5, -0.53, Some(&vertex_mask));
+        assert_eq!(par_smoothed.vertices, smoothed.vertices);
+    }
+}
@@ -0,0 +1,354 @@
+//! Reordering of triangle meshes for the post-transform vertex cache of GPUs
+//!
+//! The triangles of reconstructed surfaces are generated per cell of the sparse density map and are therefore not
+//! ordered in a way that is beneficial for rendering. The reordering implemented here follows Tom Forsyth,
+//! "Linear-Speed Vertex Cache Optimisation" (2006): triangles are greedily emitted by a score that prefers triangles
+//! with vertices that were recently used (and are therefore likely still in the cache) and vertices with only few
+//! remaining triangles. Afterwards, the vertices are reordered by their first use in the new triangle order to
+//! improve the locality of the vertex fetches.
+//!
+//! The reordering is not applied by the surface reconstruction, see [`TriMesh3d::optimize_for_rendering`] and
+//! [`MeshWithData::optimize_for_rendering`] to apply it to a mesh.
+
+use crate::mesh::{MeshWithData, TriMesh3d};
+use crate::{profile, Real};
+
+/// Size of the simulated LRU vertex cache used for scoring the vertices
+const CACHE_SIZE: usize = 32;
+/// Exponent of the decay of the score of a vertex with its position in the cache
+const CACHE_DECAY_POWER: f32 = 1.5;
+/// Score of the vertices of the triangle that was emitted last
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+/// Scale of the score bonus for vertices with only few remaining triangles
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+/// Exponent of the score bonus for vertices with only few remaining triangles
+const VALENCE_BOOST_POWER: f32 = 0.5;
+
+/// Indices in the input mesh of all vertices and triangles after reordering a mesh for rendering, see [`TriMesh3d::optimize_for_rendering`]
+///
+/// The permutations have the same form as the [`DecimationMap`](crate::mesh::decimation::DecimationMap), i.e. the
+/// vertex `i` of the reordered mesh is the vertex `vertices[i]` of the input mesh. Additional data attached to the
+/// vertices or triangles can be reordered with [`AttributeData::select`](crate::mesh::AttributeData::select).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenderingPermutation {
+    /// Index before the reordering of every vertex
+    pub vertices: Vec<usize>,
+    /// Index before the reordering of every triangle
+    pub triangles: Vec<usize>,
+}
+
+impl<R: Real> TriMesh3d<R> {
+    /// Reorders the triangles and vertices of the mesh to improve the hit rate of the vertex cache of GPUs and returns the applied permutation
+    ///
+    /// The triangles are reordered with a Forsyth-style vertex cache optimization, afterwards the vertices are
+    /// reordered by their first use in the new triangle order. Vertices that are not referenced by any triangle are
+    /// moved to the end in their original order. The geometry of the mesh (including the orientation of the
+    /// triangles) is not changed. Note that the reordering is not free, it runs in time linear in the number of
+    /// triangles but is significantly more expensive than just copying the mesh.
+    pub fn optimize_for_rendering(&mut self) -> RenderingPermutation {
+        profile!("optimize_for_rendering");
+
+        let triangle_order = optimize_triangle_order(self.vertices.len(), &self.triangles);
+
+        // Assign the new vertex indices by the first use in the new triangle order
+        let mut new_indices = vec![usize::MAX; self.vertices.len()];
+        let mut vertex_order = Vec::with_capacity(self.vertices.len());
+        for &t in &triangle_order {
+            for &v in &self.triangles[t] {
+                if new_indices[v] == usize::MAX {
+                    new_indices[v] = vertex_order.len();
+                    vertex_order.push(v);
+                }
+            }
+        }
+        for (v, new_index) in new_indices.iter_mut().enumerate() {
+            if *new_index == usize::MAX {
+                *new_index = vertex_order.len();
+                vertex_order.push(v);
+            }
+        }
+
+        self.vertices = vertex_order.iter().map(|&v| self.vertices[v]).collect();
+        self.triangles = triangle_order
+            .iter()
+            .map(|&t| self.triangles[t].map(|v| new_indices[v]))
+            .collect();
+
+        RenderingPermutation {
+            vertices: vertex_order,
+            triangles: triangle_order,
+        }
+    }
+}
+
+impl<R: Real> MeshWithData<R, TriMesh3d<R>> {
+    /// Reorders the mesh for rendering like [`TriMesh3d::optimize_for_rendering`] and reorders the point and cell attributes accordingly
+    pub fn optimize_for_rendering(&mut self) -> RenderingPermutation {
+        let permutation = self.mesh.optimize_for_rendering();
+        for attribute in self.point_attributes.iter_mut() {
+            attribute.data = attribute.data.select(&permutation.vertices);
+        }
+        for attribute in self.cell_attributes.iter_mut() {
+            attribute.data = attribute.data.select(&permutation.triangles);
+        }
+        permutation
+    }
+}
+
+/// Returns the average number of vertex cache misses per triangle (ACMR) when rendering the triangles in the given order
+///
+/// The vertex cache is simulated as a FIFO cache of the given size, as used by most GPUs. The ACMR is between `0.5`
+/// (for very large meshes with a perfect order) and `3.0` (every vertex of every triangle is a cache miss). Returns
+/// zero if there are no triangles.
+pub fn average_cache_miss_ratio(triangles: &[[usize; 3]], cache_size: usize) -> f64 {
+    if triangles.is_empty() {
+        return 0.0;
+    }
+
+    let mut cache = std::collections::VecDeque::with_capacity(cache_size + 1);
+    let mut misses = 0;
+    for tri in triangles {
+        for v in tri {
+            if !cache.contains(v) {
+                misses += 1;
+                cache.push_back(*v);
+                if cache.len() > cache_size {
+                    cache.pop_front();
+                }
+            }
+        }
+    }
+    misses as f64 / triangles.len() as f64
+}
+
+/// Returns the score of a vertex for the given position in the simulated cache and number of remaining triangles
+fn vertex_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+    if remaining_triangles == 0 {
+        // The vertex is not used by any triangle that still has to be emitted
+        return -1.0;
+    }
+
+    let cache_score = match cache_position {
+        None => 0.0,
+        // The vertices of the last triangle get a fixed score, independent of their order in the triangle
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scaler = 1.0 / (CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scaler).powf(CACHE_DECAY_POWER)
+        }
+    };
+    let valence_boost =
+        VALENCE_BOOST_SCALE * (remaining_triangles as f32).powf(-VALENCE_BOOST_POWER);
+    cache_score + valence_boost
+}
+
+/// Returns the order in which the triangles should be emitted to maximize the hit rate of the vertex cache
+fn optimize_triangle_order(num_vertices: usize, triangles: &[[usize; 3]]) -> Vec<usize> {
+    // Triangles of every vertex, the triangles that still have to be emitted are stored at the front of the ranges
+    let mut offsets = vec![0; num_vertices + 1];
+    for tri in triangles {
+        for &v in tri {
+            offsets[v + 1] += 1;
+        }
+    }
+    for v in 0..num_vertices {
+        offsets[v + 1] += offsets[v];
+    }
+    let mut remaining = vec![0; num_vertices];
+    let mut vertex_triangles = vec![0; offsets[num_vertices]];
+    for (t, tri) in triangles.iter().enumerate() {
+        for &v in tri {
+            vertex_triangles[offsets[v] + remaining[v]] = t;
+            remaining[v] += 1;
+        }
+    }
+
+    let mut vertex_scores = (0..num_vertices)
+        .map(|v| vertex_score(None, remaining[v]))
+        .collect::<Vec<_>>();
+    let triangle_score = |tri: &[usize; 3], vertex_scores: &[f32]| -> f32 {
+        tri.iter().map(|&v| vertex_scores[v]).sum()
+    };
+    let mut emitted = vec![false; triangles.len()];
+
+    // Start with the triangle with the highest score, i.e. with vertices of the lowest valence
+    let mut best_triangle = None;
+    let mut best_score = f32::MIN;
+    for (t, tri) in triangles.iter().enumerate() {
+        let score = triangle_score(tri, &vertex_scores);
+        if score > best_score {
+            best_score = score;
+            best_triangle = Some(t);
+        }
+    }
+
+    let mut order = Vec::with_capacity(triangles.len());
+    let mut cache: Vec<usize> = Vec::with_capacity(CACHE_SIZE + 3);
+    let mut next_unemitted = 0;
+    while order.len() < triangles.len() {
+        let t = match best_triangle {
+            Some(t) => t,
+            None => {
+                // No triangle in the cache is left, continue with the next triangle in the input order
+                while emitted[next_unemitted] {
+                    next_unemitted += 1;
+                }
+                next_unemitted
+            }
+        };
+
+        order.push(t);
+        emitted[t] = true;
+        let tri = &triangles[t];
+
+        // Remove the triangle from the remaining triangles of its vertices
+        for &v in tri {
+            let range = offsets[v]..offsets[v] + remaining[v];
+            let local = vertex_triangles[range.clone()]
+                .iter()
+                .position(|&other| other == t)
+                .expect("emitted triangle has to be a remaining triangle of its vertices");
+            vertex_triangles.swap(range.start + local, range.end - 1);
+            remaining[v] -= 1;
+        }
+
+        // Move the vertices of the triangle to the front of the cache
+        let mut new_cache = Vec::with_capacity(CACHE_SIZE + 3);
+        for &v in tri {
+            if !new_cache.contains(&v) {
+                new_cache.push(v);
+            }
+        }
+        new_cache.extend(cache.iter().copied().filter(|v| !tri.contains(v)));
+        cache = new_cache;
+
+        // Update the scores of all vertices in the cache, vertices that are pushed out of the cache lose their cache score
+        for (position, &v) in cache.iter().enumerate() {
+            let position = (position < CACHE_SIZE).then_some(position);
+            vertex_scores[v] = vertex_score(position, remaining[v]);
+        }
+
+        // Update the scores of the remaining triangles of the vertices in the cache and find the best one
+        best_triangle = None;
+        best_score = f32::MIN;
+        for &v in &cache {
+            for &other in &vertex_triangles[offsets[v]..offsets[v] + remaining[v]] {
+                let score = triangle_score(&triangles[other], &vertex_scores);
+                if score > best_score {
+                    best_score = score;
+                    best_triangle = Some(other);
+                }
+            }
+        }
+
+        cache.truncate(CACHE_SIZE);
+    }
+
+    order
+}
+
+#[test]
+fn test_optimize_for_rendering() {
+    use crate::mesh::{AttributeData, MeshAttribute};
+    use nalgebra::Vector3;
+
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::new(1.0, 2.0, 3.0), 1.0, 4);
+
+    // Shuffle the triangles and vertices to simulate an unordered mesh
+    let mut rng_state = 12345u64;
+    let mut next_random = |n: usize| {
+        rng_state = rng_state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (rng_state >> 33) as usize % n
+    };
+    let mut shuffled = sphere.clone();
+    for i in (1..shuffled.triangles.len()).rev() {
+        let j = next_random(i + 1);
+        shuffled.triangles.swap(i, j);
+    }
+    // An unreferenced vertex is moved to the end
+    shuffled.vertices.insert(0, Vector3::new(-1.0, -1.0, -1.0));
+    for tri in shuffled.triangles.iter_mut() {
+        for v in tri.iter_mut() {
+            *v += 1;
+        }
+    }
+
+    let mut optimized = MeshWithData::new(shuffled.clone())
+        .with_point_data(MeshAttribute::new_real_vector3(
+            "position",
+            shuffled.vertices.clone(),
+        ))
+        .with_cell_data(MeshAttribute::new_real_scalar(
+            "id",
+            (0..shuffled.triangles.len())
+                .map(|t| t as f64)
+                .collect::<Vec<_>>(),
+        ));
+    let permutation = optimized.optimize_for_rendering();
+    let mesh = &optimized.mesh;
+
+    // The permutations are consistent with the reordered mesh
+    assert_eq!(mesh.vertices.len(), shuffled.vertices.len());
+    assert_eq!(mesh.triangles.len(), shuffled.triangles.len());
+    let mut sorted_vertices = permutation.vertices.clone();
+    sorted_vertices.sort_unstable();
+    assert!(sorted_vertices
+        .iter()
+        .copied()
+        .eq(0..shuffled.vertices.len()));
+    let mut sorted_triangles = permutation.triangles.clone();
+    sorted_triangles.sort_unstable();
+    assert!(sorted_triangles
+        .iter()
+        .copied()
+        .eq(0..shuffled.triangles.len()));
+    assert_eq!(permutation.vertices.last(), Some(&0));
+
+    // The mesh is geometrically identical, including the orientation of the triangles
+    for (v, &old_v) in mesh.vertices.iter().zip(&permutation.vertices) {
+        assert_eq!(*v, shuffled.vertices[old_v]);
+    }
+    for (tri, &old_t) in mesh.triangles.iter().zip(&permutation.triangles) {
+        let old_tri = shuffled.triangles[old_t];
+        let mapped = tri.map(|v| permutation.vertices[v]);
+        assert!((0..3).any(|r| [0, 1, 2].map(|i| mapped[(i + r) % 3]) == old_tri));
+    }
+    assert!((mesh.volume() - sphere.volume()).abs() < 1e-12);
+
+    // The attributes are reordered consistently
+    match &optimized.point_attributes[0].data {
+        AttributeData::Vector3Real(positions) => assert_eq!(positions, &mesh.vertices),
+        _ => panic!("unexpected attribute type"),
+    }
+    match &optimized.cell_attributes[0].data {
+        AttributeData::ScalarReal(ids) => {
+            for (&id, &old_t) in ids.iter().zip(&permutation.triangles) {
+                assert_eq!(id, old_t as f64);
+            }
+        }
+        _ => panic!("unexpected attribute type"),
+    }
+
+    // The reordering improves the cache efficiency significantly
+    let acmr_before = average_cache_miss_ratio(&shuffled.triangles, 16);
+    let acmr_after = average_cache_miss_ratio(&mesh.triangles, 16);
+    assert!(acmr_before > 2.0, "ACMR before: {}", acmr_before);
+    assert!(acmr_after < 0.8, "ACMR after: {}", acmr_after);
+
+    // Empty meshes and degenerate triangles are supported
+    let mut empty = TriMesh3d::<f64>::default();
+    assert_eq!(
+        empty.optimize_for_rendering(),
+        RenderingPermutation::default()
+    );
+    let mut degenerate = TriMesh3d::<f64>::from_buffers(
+        vec![Vector3::zeros(), Vector3::x(), Vector3::y()],
+        vec![[0, 1, 1], [0, 1, 2], [2, 2, 2]],
+    )
+    .unwrap();
+    let permutation = degenerate.optimize_for_rendering();
+    assert_eq!(permutation.triangles.len(), 3);
+    assert_eq!(degenerate.triangles.len(), 3);
+}
@@ -3,13 +3,19 @@
 //! This module provides basic sequential and parallel neighborhood search implementations using
 //! spatial hashing. The algorithms return per-particle neighborhood list with indices of all particles
 //! that are within the given radius of the particle.
+//!
+//! Furthermore, the [`ParticleNeighborhoodProvider`] trait abstracts radius queries at arbitrary points,
+//! which allows to use external spatial data structures e.g. for SPH interpolation.
 
 use crate::uniform_grid::UniformGrid;
+use crate::utils::map_collect_into_vec;
+#[cfg(feature = "parallel")]
 use crate::utils::UnsafeSlice;
-use crate::{
-    new_map, profile, AxisAlignedBoundingBox3d, HashState, Index, MapType, ParallelMapType, Real,
-};
+use crate::{new_map, profile, AxisAlignedBoundingBox3d, Index, MapType, Real, ThreadSafe};
+#[cfg(feature = "parallel")]
+use crate::{HashState, ParallelMapType};
 use nalgebra::Vector3;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
 // TODO: Replace some unwrap() calls with errors, e.g. if domain excludes some particles that are neighbors
@@ -25,26 +31,19 @@ pub fn search<I: Index, R: Real>(
     enable_multi_threading: bool,
 ) -> Vec<Vec<usize>> {
     let mut particle_neighbor_lists = Vec::new();
-    if enable_multi_threading {
-        neighborhood_search_spatial_hashing_parallel::<I, R>(
-            domain,
-            particle_positions,
-            search_radius,
-            &mut particle_neighbor_lists,
-        )
-    } else {
-        neighborhood_search_spatial_hashing::<I, R>(
-            domain,
-            particle_positions,
-            search_radius,
-            &mut particle_neighbor_lists,
-        )
-    }
+    search_inplace::<I, R>(
+        domain,
+        particle_positions,
+        search_radius,
+        enable_multi_threading,
+        &mut particle_neighbor_lists,
+    );
     particle_neighbor_lists
 }
 
 /// Performs a neighborhood search inplace, stores the indices of all neighboring particles in the given search radius per particle in the given vector
 #[inline(never)]
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
 pub fn search_inplace<I: Index, R: Real>(
     domain: &AxisAlignedBoundingBox3d<R>,
     particle_positions: &[Vector3<R>],
@@ -52,21 +51,22 @@ pub fn search_inplace<I: Index, R: Real>(
     enable_multi_threading: bool,
     particle_neighbor_lists: &mut Vec<Vec<usize>>,
 ) {
+    #[cfg(feature = "parallel")]
     if enable_multi_threading {
-        neighborhood_search_spatial_hashing_parallel::<I, R>(
-            domain,
-            particle_positions,
-            search_radius,
-            particle_neighbor_lists,
-        )
-    } else {
-        neighborhood_search_spatial_hashing::<I, R>(
+        return neighborhood_search_spatial_hashing_parallel::<I, R>(
             domain,
             particle_positions,
             search_radius,
             particle_neighbor_lists,
-        )
+        );
     }
+
+    neighborhood_search_spatial_hashing::<I, R>(
+        domain,
+        particle_positions,
+        search_radius,
+        particle_neighbor_lists,
+    )
 }
 
 /// Performs a naive neighborhood search with `O(N^2)` complexity, only recommended for testing
@@ -105,6 +105,7 @@ fn init_neighborhood_list(neighborhood_list: &mut Vec<Vec<usize>>, new_len: usiz
 }
 
 /// Allocates enough storage for the given number of particles and clears all existing neighborhood lists in parallel
+#[cfg(feature = "parallel")]
 fn par_init_neighborhood_list(neighborhood_list: &mut Vec<Vec<usize>>, new_len: usize) {
     let old_len = neighborhood_list.len();
     // Reset all neighbor lists that won't be truncated
@@ -204,6 +205,8 @@ pub fn neighborhood_search_spatial_hashing<I: Index, R: Real>(
 
 /// Performs a neighborhood search, returning the indices of all neighboring particles in the given search radius per particle, multi-threaded implementation
 #[inline(never)]
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
 pub fn neighborhood_search_spatial_hashing_parallel<I: Index, R: Real>(
     domain: &AxisAlignedBoundingBox3d<R>,
     particle_positions: &[Vector3<R>],
@@ -407,7 +410,12 @@ fn sequential_generate_cell_to_particle_map<I: Index, R: Real>(
     // Assign all particles to enclosing cells
     for (particle_i, particle) in particle_positions.iter().enumerate() {
         let cell_ijk = grid.enclosing_cell(particle);
-        let cell = grid.get_cell(cell_ijk).unwrap();
+        // Particles outside of the domain have no neighbors and are not neighbors of any other particle
+        let cell = if let Some(cell) = grid.get_cell(cell_ijk) {
+            cell
+        } else {
+            continue;
+        };
         let flat_cell_index = grid.flatten_cell_index(&cell);
 
         particles_per_cell
@@ -420,6 +428,7 @@ fn sequential_generate_cell_to_particle_map<I: Index, R: Real>(
 }
 
 #[inline(never)]
+#[cfg(feature = "parallel")]
 fn parallel_generate_cell_to_particle_map<I: Index, R: Real>(
     grid: &UniformGrid<I, R>,
     particle_positions: &[Vector3<R>],
@@ -433,7 +442,12 @@ fn parallel_generate_cell_to_particle_map<I: Index, R: Real>(
         .enumerate()
         .for_each(|(particle_i, particle)| {
             let cell_ijk = grid.enclosing_cell(particle);
-            let cell = grid.get_cell(cell_ijk).unwrap();
+            // Particles outside of the domain have no neighbors and are not neighbors of any other particle
+            let cell = if let Some(cell) = grid.get_cell(cell_ijk) {
+                cell
+            } else {
+                return;
+            };
             let flat_cell_index = grid.flatten_cell_index(&cell);
 
             particles_per_cell
@@ -444,3 +458,189 @@ fn parallel_generate_cell_to_particle_map<I: Index, R: Real>(
 
     particles_per_cell
 }
+
+/// Spatial acceleration structure that answers radius queries over the indices of a fixed set of particles
+///
+/// The trait is object safe, such that e.g. the [`SphInterpolator`](crate::sph_interpolation::SphInterpolator)
+/// can be used with external spatial data structures (see [`SphInterpolator::with_neighborhood_provider`](crate::sph_interpolation::SphInterpolator::with_neighborhood_provider)).
+/// The crate provides the [`SpatialHashingNeighborhoodProvider`] based on spatial hashing and, if the `rstar` feature is enabled,
+/// an implementation for R-trees of the [`rstar`](https://github.com/georust/rstar) crate storing the particle index per point.
+pub trait ParticleNeighborhoodProvider<R: Real>: ThreadSafe {
+    /// Returns the number of particles that can be returned by the queries of this provider
+    fn num_particles(&self) -> usize;
+
+    /// Calls the visitor with the index of every particle with a distance to the query point less than or equal to the given radius (in arbitrary order)
+    fn visit_particles_within(
+        &self,
+        query_point: &Vector3<R>,
+        radius: R,
+        visitor: &mut dyn FnMut(usize),
+    );
+}
+
+/// Built-in [`ParticleNeighborhoodProvider`] that stores the particles in a spatially hashed uniform grid
+#[derive(Clone, Debug)]
+pub struct SpatialHashingNeighborhoodProvider<R: Real> {
+    grid: UniformGrid<i64, R>,
+    particle_positions: Vec<Vector3<R>>,
+    particles_per_cell: MapType<i64, Vec<usize>>,
+}
+
+impl<R: Real> SpatialHashingNeighborhoodProvider<R> {
+    /// Stores the given particles in a grid with the given cell size, queries are most efficient for radii close to the cell size
+    pub fn new(particle_positions: &[Vector3<R>], cell_size: R) -> Self {
+        profile!("SpatialHashingNeighborhoodProvider::new");
+
+        assert!(
+            cell_size > R::zero(),
+            "Cell size for neighborhood queries has to be positive!"
+        );
+
+        let mut domain = AxisAlignedBoundingBox3d::from_points(particle_positions);
+        domain.grow_uniformly(cell_size);
+        let grid = UniformGrid::from_aabb(&domain, cell_size)
+            .expect("Failed to construct grid for neighborhood queries!");
+        let particles_per_cell =
+            sequential_generate_cell_to_particle_map::<i64, R>(&grid, particle_positions);
+
+        Self {
+            grid,
+            particle_positions: particle_positions.to_vec(),
+            particles_per_cell,
+        }
+    }
+
+    /// Returns the range of cell indices along the given axis that intersects the given interval, `None` if the interval is outside of the grid
+    fn cell_range(&self, axis: usize, min: R, max: R) -> Option<(i64, i64)> {
+        let n_cells = self.grid.cells_per_dim()[axis];
+        let grid_min = self.grid.aabb().min()[axis];
+        let cell_size = self.grid.cell_size();
+
+        // Clamp in floating point to avoid overflows for query points far outside of the grid
+        let last_cell = (n_cells - 1).to_real::<R>()?;
+        let lower = ((min - grid_min) / cell_size).floor().max(R::zero());
+        let upper = ((max - grid_min) / cell_size).floor().min(last_cell);
+        (lower <= upper).then(|| (lower.to_index_unchecked(), upper.to_index_unchecked()))
+    }
+}
+
+impl<R: Real> ParticleNeighborhoodProvider<R> for SpatialHashingNeighborhoodProvider<R> {
+    fn num_particles(&self) -> usize {
+        self.particle_positions.len()
+    }
+
+    fn visit_particles_within(
+        &self,
+        query_point: &Vector3<R>,
+        radius: R,
+        visitor: &mut dyn FnMut(usize),
+    ) {
+        let ranges = [0, 1, 2].map(|axis| {
+            self.cell_range(axis, query_point[axis] - radius, query_point[axis] + radius)
+        });
+        let (i_min, i_max, j_min, j_max, k_min, k_max) = match ranges {
+            [Some((i_min, i_max)), Some((j_min, j_max)), Some((k_min, k_max))] => {
+                (i_min, i_max, j_min, j_max, k_min, k_max)
+            }
+            // The query does not intersect the grid
+            _ => return,
+        };
+
+        let radius_squared = radius * radius;
+        for i in i_min..=i_max {
+            for j in j_min..=j_max {
+                for k in k_min..=k_max {
+                    let flat_cell_index = self.grid.flatten_cell_indices(i, j, k);
+                    let particles = match self.particles_per_cell.get(&flat_cell_index) {
+                        Some(particles) => particles,
+                        None => continue,
+                    };
+                    for &particle_j in particles {
+                        let pos_j = &self.particle_positions[particle_j];
+                        if (pos_j - query_point).norm_squared() <= radius_squared {
+                            visitor(particle_j);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Point type of R-trees that can be used as [`ParticleNeighborhoodProvider`], stores the index of the particle with its position
+#[cfg(feature = "rstar")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "rstar")))]
+pub type RTreeParticle<R> = rstar::primitives::GeomWithData<[R; 3], usize>;
+
+/// Builds an R-tree that stores the index of each of the given particles, e.g. to use it as [`ParticleNeighborhoodProvider`]
+#[cfg(feature = "rstar")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "rstar")))]
+pub fn build_particle_rtree<R: Real>(
+    particle_positions: &[Vector3<R>],
+) -> rstar::RTree<RTreeParticle<R>> {
+    profile!("build_particle_rtree");
+    let particles = particle_positions
+        .iter()
+        .enumerate()
+        .map(|(i, p)| RTreeParticle::new(bytemuck::cast(*p), i))
+        .collect();
+    rstar::RTree::bulk_load(particles)
+}
+
+#[cfg(feature = "rstar")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "rstar")))]
+impl<R: Real> ParticleNeighborhoodProvider<R> for rstar::RTree<RTreeParticle<R>> {
+    fn num_particles(&self) -> usize {
+        self.size()
+    }
+
+    fn visit_particles_within(
+        &self,
+        query_point: &Vector3<R>,
+        radius: R,
+        visitor: &mut dyn FnMut(usize),
+    ) {
+        let query_point = bytemuck::cast::<_, [R; 3]>(*query_point);
+        for particle in self.locate_within_distance(query_point, radius * radius) {
+            visitor(particle.data);
+        }
+    }
+}
+
+/// Returns the sorted indices of all particles without any other particle within the given radius
+///
+/// These are the splash particles according to [`SplashDetection::NeighborCount`](crate::SplashDetection::NeighborCount).
+/// The given neighborhood provider has to contain the given particles.
+pub fn isolated_particles<R: Real>(
+    neighborhood: &dyn ParticleNeighborhoodProvider<R>,
+    particle_positions: &[Vector3<R>],
+    radius: R,
+) -> Vec<usize> {
+    profile!("isolated_particles");
+    assert_eq!(
+        neighborhood.num_particles(),
+        particle_positions.len(),
+        "The neighborhood provider has to contain all particles"
+    );
+
+    let indexed_positions = particle_positions.iter().enumerate().collect::<Vec<_>>();
+    let mut is_isolated = Vec::new();
+    map_collect_into_vec(
+        indexed_positions.as_slice(),
+        |&(particle_i, pos_i)| {
+            let mut has_neighbor = false;
+            neighborhood.visit_particles_within(pos_i, radius, &mut |particle_j| {
+                has_neighbor |= particle_j != particle_i;
+            });
+            !has_neighbor
+        },
+        &mut is_isolated,
+    );
+
+    is_isolated
+        .into_iter()
+        .enumerate()
+        .filter(|(_, is_isolated)| *is_isolated)
+        .map(|(i, _)| i)
+        .collect()
+}
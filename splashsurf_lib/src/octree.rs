@@ -1,8 +1,10 @@
 //! Octree for spatially partitioning particle sets
 
 use crate::generic_tree::*;
-use crate::marching_cubes::SurfacePatch;
+use crate::logging::info;
+use crate::marching_cubes::{MarchingCubesVariant, SurfacePatch};
 use crate::mesh::{HexMesh3d, MeshAttribute, MeshWithData, TriMesh3d};
+use crate::topology::octant::OctantIndex;
 use crate::topology::{Axis, Direction};
 use crate::uniform_grid::{PointIndex, UniformGrid};
 use crate::utils::{ChunkSize, ParallelPolicy};
@@ -11,14 +13,18 @@ use crate::{
     MapType, Real, ReconstructionError,
 };
 use arrayvec::ArrayVec;
-use log::info;
 use nalgebra::Vector3;
 use octant_helper::{HalfspaceFlags, Octant, OctantAxisDirections};
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use smallvec::SmallVec;
 use split_criterion::{default_split_criterion, LeafSplitCriterion};
+#[cfg(feature = "parallel")]
 use std::cell::RefCell;
+use std::ops::ControlFlow;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use thiserror::Error as ThisError;
+#[cfg(feature = "parallel")]
 use thread_local::ThreadLocal;
 
 // TODO: Make margin an Option
@@ -32,6 +38,37 @@ pub enum SubdivisionCriterion {
     MaxParticleCount(usize),
 }
 
+/// Error type returned when the octree based surface reconstruction fails
+#[derive(Debug, ThisError)]
+pub enum OctreeError<I: Index, R: Real> {
+    /// The surface reconstruction was configured to use spatial decomposition but no decomposition parameters were provided
+    #[error("spatial decomposition parameters are missing")]
+    MissingDecompositionParameters,
+    /// The margin for collecting ghost particles is negative or not finite (e.g. due to an invalid ghost particle safety factor)
+    #[error("invalid ghost particle margin `{0}`, the margin has to be finite and non-negative")]
+    InvalidMargin(R),
+    /// The local grid of an octree node could not be constructed
+    #[error("construction of the grid of an octree node failed")]
+    NodeGridConstruction(#[source] GridConstructionError<I, R>),
+    /// The root node does not contain a surface patch after stitching all nodes
+    #[error("the root node does not contain the stitched surface patch")]
+    MissingStitchedPatch,
+}
+
+impl<I: Index, R: Real> OctreeError<I, R> {
+    /// Converts the error to another index type, see [`GridConstructionError::convert_index`]
+    pub(crate) fn convert_index<J: Index>(self) -> OctreeError<J, R> {
+        match self {
+            Self::MissingDecompositionParameters => OctreeError::MissingDecompositionParameters,
+            Self::InvalidMargin(margin) => OctreeError::InvalidMargin(margin),
+            Self::NodeGridConstruction(err) => {
+                OctreeError::NodeGridConstruction(err.convert_index())
+            }
+            Self::MissingStitchedPatch => OctreeError::MissingStitchedPatch,
+        }
+    }
+}
+
 /// Data structure for octree based spatial subdivision of particles sets, for tree iteration/visitation use the [`root`](Self::root) [`OctreeNode`]
 #[derive(Clone, Debug)]
 pub struct Octree<I: Index, R: Real> {
@@ -72,6 +109,8 @@ impl<I: Index, R: Real> TreeNodeMut for OctreeNode<I, R> {
     }
 }
 
+impl<I: Index, R: Real> TreeMetrics for OctreeNode<I, R> {}
+
 /// Optional data that may be stored in [`OctreeNode`]s
 #[derive(Clone, Debug)]
 pub enum NodeData<I: Index, R: Real> {
@@ -133,6 +172,7 @@ impl<I: Index, R: Real> Octree<I, R> {
     /// The margin is used to assign ghost particles to octree nodes. Each octant resulting
     /// from the subdivision gets assigned all particles that are directly inside it plus all
     /// particles from its parent that are within the given margin around the octant.
+    #[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
     pub fn new_subdivided(
         grid: &UniformGrid<I, R>,
         particle_positions: &[Vector3<R>],
@@ -143,6 +183,7 @@ impl<I: Index, R: Real> Octree<I, R> {
     ) -> Self {
         let mut tree = Octree::new(&grid, particle_positions.len());
 
+        #[cfg(feature = "parallel")]
         if enable_multi_threading {
             tree.par_subdivide_recursively_margin(
                 grid,
@@ -151,16 +192,17 @@ impl<I: Index, R: Real> Octree<I, R> {
                 margin,
                 enable_stitching,
             );
-        } else {
-            tree.subdivide_recursively_margin(
-                grid,
-                particle_positions,
-                subdivision_criterion,
-                margin,
-                enable_stitching,
-            );
+            return tree;
         }
 
+        tree.subdivide_recursively_margin(
+            grid,
+            particle_positions,
+            subdivision_criterion,
+            margin,
+            enable_stitching,
+        );
+
         tree
     }
 
@@ -205,6 +247,8 @@ impl<I: Index, R: Real> Octree<I, R> {
     }
 
     /// Subdivide the octree recursively and in parallel using the given splitting criterion and a margin to add ghost particles
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
     pub fn par_subdivide_recursively_margin(
         &mut self,
         grid: &UniformGrid<I, R>,
@@ -222,6 +266,14 @@ impl<I: Index, R: Real> Octree<I, R> {
         );
         let parallel_policy = ParallelPolicy::default();
 
+        // Subdivide the subtrees of nodes with only a few particles sequentially in a single task
+        let min_task_size = parallel_policy.min_task_size;
+        let is_small_node = |node: &OctreeNode<I, R>| {
+            node.data
+                .particle_set()
+                .is_some_and(|particle_set| particle_set.particles.len() < min_task_size)
+        };
+
         let next_id = AtomicUsize::new(0);
         let visitor = {
             let next_id = &next_id;
@@ -253,11 +305,56 @@ impl<I: Index, R: Real> Octree<I, R> {
             }
         };
 
-        self.root.par_visit_mut_bfs(visitor);
+        self.root
+            .par_visit_mut_bfs_with_granularity(is_small_node, visitor);
         self.next_id = next_id.into_inner();
     }
 
+    /// Returns the leaf node whose AABB contains the given point or `None` if the point is outside of the octree
+    ///
+    /// Only the nodes on the path from the root to the leaf are visited.
+    pub fn leaf_containing_point(&self, point: &Vector3<R>) -> Option<&OctreeNode<I, R>> {
+        let leaf = self.root.visit_dfs_controlled(|node| {
+            if !node.aabb().contains_point(point) {
+                ControlFlow::Continue(Descend::No)
+            } else if node.children().is_empty() {
+                ControlFlow::Break(node)
+            } else {
+                ControlFlow::Continue(Descend::Yes)
+            }
+        });
+
+        match leaf {
+            ControlFlow::Break(leaf) => Some(leaf),
+            ControlFlow::Continue(()) => None,
+        }
+    }
+
+    /// Returns all leaf nodes whose AABB intersects the given AABB, ordered depth-first
+    ///
+    /// Subtrees of nodes that do not intersect the AABB are skipped.
+    pub fn leaves_intersecting_aabb(
+        &self,
+        aabb: &AxisAlignedBoundingBox3d<R>,
+    ) -> Vec<&OctreeNode<I, R>> {
+        let mut leaves = Vec::new();
+        let _: ControlFlow<()> = self.root.visit_dfs_controlled(|node| {
+            if !node.aabb().intersects_aabb(aabb) {
+                return ControlFlow::Continue(Descend::No);
+            }
+
+            if node.children().is_empty() {
+                leaves.push(node);
+            }
+            ControlFlow::Continue(Descend::Yes)
+        });
+        leaves
+    }
+
     /// Constructs a hex mesh visualizing the cells of the octree, may contain hanging and duplicate vertices as cells are not connected
+    ///
+    /// The leaf cells are ordered level by level from coarse to fine. In addition to the "node_id" of each cell,
+    /// its "depth" in the octree is stored as a cell attribute.
     pub fn hexmesh(
         &self,
         grid: &UniformGrid<I, R>,
@@ -271,7 +368,8 @@ impl<I: Index, R: Real> Octree<I, R> {
         };
 
         let mut ids = Vec::new();
-        self.root.dfs_iter().for_each(|node| {
+        let mut depths = Vec::new();
+        self.root.visit_level_order(|depth, node| {
             if node.children().is_empty() {
                 if only_non_empty
                     && node
@@ -312,11 +410,15 @@ impl<I: Index, R: Real> Octree<I, R> {
                 mesh.vertices.extend(vertices);
                 mesh.cells.push(cell);
                 ids.push(node.id as u64);
+                depths.push(depth as u64);
             }
         });
 
         assert_eq!(mesh.cells.len(), ids.len());
-        MeshWithData::new(mesh).with_cell_data(MeshAttribute::new("node_id".to_string(), ids))
+        assert_eq!(mesh.cells.len(), depths.len());
+        MeshWithData::new(mesh)
+            .with_cell_data(MeshAttribute::new("node_id".to_string(), ids))
+            .with_cell_data(MeshAttribute::new("depth".to_string(), depths))
     }
 }
 
@@ -397,6 +499,26 @@ impl<I: Index, R: Real> OctreeNode<I, R> {
         &self.aabb
     }
 
+    /// Returns whether the leaves of the subtree of this node contain fewer than the given number of particles in total
+    ///
+    /// The particles are only counted until the given number is reached. This can be used as granularity
+    /// predicate for the parallel visitation functions (e.g. [`ParVisitableTree::par_visit_bfs_with_granularity`]).
+    pub fn is_small_subtree(&self, num_particles: usize) -> bool {
+        let mut subtree_particles = 0;
+        self.visit_dfs_controlled(|node| {
+            subtree_particles += node
+                .data()
+                .particle_set()
+                .map_or(0, |particle_set| particle_set.particles.len());
+            if subtree_particles >= num_particles {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(Descend::Yes)
+            }
+        })
+        .is_continue()
+    }
+
     /// Constructs a [`UniformGrid`](crate::UniformGrid) that represents the domain of this octree node
     pub fn grid(
         &self,
@@ -526,6 +648,8 @@ impl<I: Index, R: Real> OctreeNode<I, R> {
     }
 
     /// Parallel subdivision of this node while considering a margin for "ghost particles" around each octant
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
     pub fn par_subdivide_with_margin(
         &mut self,
         grid: &UniformGrid<I, R>,
@@ -674,17 +798,22 @@ impl<I: Index, R: Real> OctreeNode<I, R> {
 
     fn stitch_children_orthogonal_to(
         &mut self,
-        children_map: &mut MapType<OctantAxisDirections, SurfacePatch<I, R>>,
+        children_map: &mut MapType<OctantIndex, SurfacePatch<I, R>>,
         stitching_axis: Axis,
         iso_surface_threshold: R,
+        variant: MarchingCubesVariant,
     ) -> Result<(), ReconstructionError<I, R>> {
         profile!("stitch_children_orthogonal_to");
 
-        for mut octant in OctantAxisDirections::all().iter().copied() {
-            // Iterate over every octant pair only once
-            if octant.direction(stitching_axis).is_positive() {
+        for octant in OctantIndex::all() {
+            // Iterate over every octant pair only once, starting from the octant on the negative side
+            let sibling = if let Some(sibling) =
+                octant.sibling_across(stitching_axis.with_direction(Direction::Positive))
+            {
+                sibling
+            } else {
                 continue;
-            }
+            };
 
             // First try to get negative side, it might not exist because children were already merged before to another octant in the map
             let negative_side = if let Some(negative_patch) = children_map.remove(&octant) {
@@ -694,18 +823,19 @@ impl<I: Index, R: Real> OctreeNode<I, R> {
             };
 
             // If the negative side on the stitching axis exists, the positive side must also exist
-            octant.set_direction(stitching_axis, Direction::Positive);
-            let positive_side = children_map.remove(&octant).expect("Child node missing!");
+            let positive_side = children_map.remove(&sibling).expect("Child node missing!");
 
             let stitched_patch = marching_cubes::stitch_surface_patches(
                 iso_surface_threshold,
+                variant,
                 stitching_axis,
+                self.id(),
                 negative_side,
                 positive_side,
             )?;
 
-            // Add stitched surface back to map, setting the direction of the octant of the stitched patch to positive
-            children_map.insert(octant, stitched_patch);
+            // Add stitched surface back to map, using the octant on the positive side for the stitched patch
+            children_map.insert(sibling, stitched_patch);
         }
 
         Ok(())
@@ -715,6 +845,7 @@ impl<I: Index, R: Real> OctreeNode<I, R> {
     pub(crate) fn stitch_surface_patches(
         &mut self,
         iso_surface_threshold: R,
+        variant: MarchingCubesVariant,
     ) -> Result<(), ReconstructionError<I, R>> {
         profile!("stitch_surface_patches");
 
@@ -734,10 +865,9 @@ impl<I: Index, R: Real> OctreeNode<I, R> {
             let old_children = std::mem::take(&mut self.children);
 
             let mut children_map = new_map();
-            for (child, octant) in old_children.into_iter().zip(Octant::all().iter().copied()) {
-                let octant_directions = OctantAxisDirections::from(octant);
+            for (child, octant) in old_children.into_iter().zip(OctantIndex::all()) {
                 children_map.insert(
-                    octant_directions,
+                    octant,
                     child
                         .data
                         .into_surface_patch()
@@ -749,9 +879,24 @@ impl<I: Index, R: Real> OctreeNode<I, R> {
             children_map
         };
 
-        self.stitch_children_orthogonal_to(&mut children_map, Axis::X, iso_surface_threshold)?;
-        self.stitch_children_orthogonal_to(&mut children_map, Axis::Y, iso_surface_threshold)?;
-        self.stitch_children_orthogonal_to(&mut children_map, Axis::Z, iso_surface_threshold)?;
+        self.stitch_children_orthogonal_to(
+            &mut children_map,
+            Axis::X,
+            iso_surface_threshold,
+            variant,
+        )?;
+        self.stitch_children_orthogonal_to(
+            &mut children_map,
+            Axis::Y,
+            iso_surface_threshold,
+            variant,
+        )?;
+        self.stitch_children_orthogonal_to(
+            &mut children_map,
+            Axis::Z,
+            iso_surface_threshold,
+            variant,
+        )?;
 
         assert_eq!(
             children_map.len(),
@@ -1102,6 +1247,7 @@ mod octant_helper {
             }
         }
 
+        #[allow(dead_code)]
         pub fn direction(&self, axis: Axis) -> Direction {
             match axis {
                 Axis::X => self.x_axis,
@@ -1110,14 +1256,6 @@ mod octant_helper {
             }
         }
 
-        pub fn set_direction(&mut self, axis: Axis, direction: Direction) {
-            match axis {
-                Axis::X => self.x_axis = direction,
-                Axis::Y => self.y_axis = direction,
-                Axis::Z => self.z_axis = direction,
-            }
-        }
-
         /// Classifies a point relative to zero into the corresponding octant
         #[inline(always)]
         pub fn classify<R: Real>(point: &Vector3<R>) -> Self {
@@ -1247,5 +1385,23 @@ mod octant_helper {
                 assert_eq!(octant_directions, OctantAxisDirections::from(octant));
             }
         }
+
+        #[test]
+        fn test_octant_index_consistency() {
+            use crate::topology::octant::OctantIndex;
+
+            // The children of a node are stored in the order of the octant indices
+            for (octant, octant_index) in Octant::all().iter().copied().zip(OctantIndex::all()) {
+                assert_eq!(octant as usize, octant_index.index());
+
+                let octant_directions = OctantAxisDirections::from(octant);
+                for &axis in Axis::all_possible() {
+                    assert_eq!(
+                        octant_directions.direction(axis),
+                        octant_index.direction(axis)
+                    );
+                }
+            }
+        }
     }
 }
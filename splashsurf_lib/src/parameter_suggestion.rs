@@ -0,0 +1,212 @@
+//! Estimation of the resource requirements of a reconstruction and suggestion of a suitable cube size
+//!
+//! The estimates are based on a simple model of the fluid: the particles are assumed to fill a box with the
+//! same aspect ratio as their bounding box, sampled with a particle spacing of twice the particle radius.
+//! From the volume and surface area of this box, the number of grid points in the sparse density map,
+//! the number of marching cubes cells intersecting the surface and the resulting number of triangles are
+//! estimated for each cube size. For fluids with a very irregular shape (e.g. thin sheets or lots of spray)
+//! the surface area and therefore the triangle count is underestimated.
+
+use std::mem::size_of;
+
+use nalgebra::Vector3;
+
+use crate::{AxisAlignedBoundingBox3d, Real};
+
+/// Cube sizes (in multiplies of the particle radius) that are evaluated by [`suggest_parameters`]
+pub const DEFAULT_RELATIVE_CUBE_SIZES: [f64; 9] =
+    [0.25, 0.375, 0.5, 0.625, 0.75, 1.0, 1.25, 1.5, 2.0];
+
+/// Average number of triangles generated per marching cubes cell that intersects the surface
+const TRIANGLES_PER_SURFACE_CELL: f64 = 2.0;
+/// Average ratio of the number of cells intersecting a surface to the surface area in units of the cell face area
+const SURFACE_CELLS_PER_FACE_AREA: f64 = 1.25;
+/// Approximate overhead factor of the hash map used for the sparse density map
+const HASH_MAP_OVERHEAD: f64 = 1.5;
+
+/// Resource limits for the reconstruction used to select a cube size, unset limits are not checked
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Budget {
+    /// Maximum estimated memory consumption of the reconstruction in bytes
+    pub max_memory_bytes: Option<u64>,
+    /// Maximum estimated number of triangles of the reconstructed mesh
+    pub max_triangles: Option<u64>,
+}
+
+impl Budget {
+    /// Returns whether the given estimate satisfies all limits of this budget
+    pub fn is_satisfied_by<R: Real>(&self, estimate: &ReconstructionEstimate<R>) -> bool {
+        let exceeds_memory =
+            matches!(self.max_memory_bytes, Some(max) if estimate.memory_bytes > max);
+        let exceeds_triangles = matches!(self.max_triangles, Some(max) if estimate.triangles > max);
+        !exceeds_memory && !exceeds_triangles
+    }
+}
+
+/// Estimated resource requirements of a reconstruction with a specific cube size
+#[derive(Clone, Debug)]
+pub struct ReconstructionEstimate<R: Real> {
+    /// The cube size of this estimate (in distance units)
+    pub cube_size: R,
+    /// The cube size of this estimate (in multiplies of the particle radius)
+    pub relative_cube_size: R,
+    /// Total number of points of the background grid enclosing the particles
+    pub grid_points: u64,
+    /// Number of grid points within the kernel support of any particle, i.e. stored in the sparse density map
+    pub density_map_points: u64,
+    /// Number of marching cubes cells intersecting the surface
+    pub surface_cells: u64,
+    /// Number of triangles of the reconstructed mesh
+    pub triangles: u64,
+    /// Number of vertices of the reconstructed mesh
+    pub vertices: u64,
+    /// Memory required for the particle data, the density map and the output mesh in bytes
+    pub memory_bytes: u64,
+}
+
+/// Estimates for a range of cube sizes and the cube size recommended for a budget, see [`suggest_parameters`]
+#[derive(Clone, Debug)]
+pub struct SuggestedParameters<R: Real> {
+    /// Estimates for all evaluated cube sizes, sorted from the smallest to the largest cube size
+    pub estimates: Vec<ReconstructionEstimate<R>>,
+    /// Index into [`estimates`](Self::estimates) of the smallest cube size satisfying the budget, `None` if no cube size satisfies the budget or the budget is empty
+    pub suggested: Option<usize>,
+}
+
+impl<R: Real> SuggestedParameters<R> {
+    /// Returns the estimate of the suggested cube size
+    pub fn suggested_estimate(&self) -> Option<&ReconstructionEstimate<R>> {
+        self.suggested.map(|i| &self.estimates[i])
+    }
+
+    /// Returns the suggested cube size (in distance units)
+    pub fn suggested_cube_size(&self) -> Option<R> {
+        self.suggested_estimate().map(|estimate| estimate.cube_size)
+    }
+}
+
+/// Estimates the resources for all [`DEFAULT_RELATIVE_CUBE_SIZES`] and suggests the smallest cube size satisfying the budget
+pub fn suggest_parameters<R: Real>(
+    particle_positions: &[Vector3<R>],
+    particle_radius: R,
+    compact_support_radius: R,
+    budget: &Budget,
+) -> SuggestedParameters<R> {
+    let aabb = AxisAlignedBoundingBox3d::from_points(particle_positions);
+    suggest_parameters_for_aabb(
+        &aabb,
+        particle_positions.len(),
+        particle_radius,
+        compact_support_radius,
+        budget,
+    )
+}
+
+/// Same as [`suggest_parameters`] but only uses the bounding box of the particles and the number of particles
+pub fn suggest_parameters_for_aabb<R: Real>(
+    particle_aabb: &AxisAlignedBoundingBox3d<R>,
+    particle_count: usize,
+    particle_radius: R,
+    compact_support_radius: R,
+    budget: &Budget,
+) -> SuggestedParameters<R> {
+    let estimates: Vec<_> = DEFAULT_RELATIVE_CUBE_SIZES
+        .iter()
+        .map(|&relative_cube_size| {
+            let cube_size = R::from_f64(relative_cube_size).unwrap() * particle_radius;
+            estimate_reconstruction(
+                particle_aabb,
+                particle_count,
+                particle_radius,
+                compact_support_radius,
+                cube_size,
+            )
+        })
+        .collect();
+
+    let has_limits = budget.max_memory_bytes.is_some() || budget.max_triangles.is_some();
+    let suggested = if has_limits {
+        estimates
+            .iter()
+            .position(|estimate| budget.is_satisfied_by(estimate))
+    } else {
+        None
+    };
+
+    SuggestedParameters {
+        estimates,
+        suggested,
+    }
+}
+
+/// Estimates the resource requirements of a reconstruction of the given particles with the given cube size
+pub fn estimate_reconstruction<R: Real>(
+    particle_aabb: &AxisAlignedBoundingBox3d<R>,
+    particle_count: usize,
+    particle_radius: R,
+    compact_support_radius: R,
+    cube_size: R,
+) -> ReconstructionEstimate<R> {
+    let r = particle_radius.to_f64().unwrap();
+    let h = cube_size.to_f64().unwrap();
+    let csr = compact_support_radius.to_f64().unwrap();
+    let n = particle_count as f64;
+
+    // The surface is located roughly one particle radius outside of the outermost particle centers
+    let extents = particle_aabb
+        .extents()
+        .map(|e| e.to_f64().unwrap() + 2.0 * r);
+
+    // Background grid as constructed by the reconstruction (AABB with particle radius and kernel margin)
+    let grid_margin = r + csr + h;
+    let grid_points = extents
+        .iter()
+        .map(|e| ((e + 2.0 * grid_margin) / h).ceil() + 1.0)
+        .product::<f64>();
+
+    // Shrink the box uniformly if the particles do not fill their bounding box
+    let box_volume = extents.product();
+    let fluid_volume = (n * (2.0 * r).powi(3)).min(box_volume);
+    let scale = if box_volume > 0.0 {
+        (fluid_volume / box_volume).cbrt()
+    } else {
+        0.0
+    };
+    let edges: Vector3<f64> = extents * scale;
+
+    let surface_area = 2.0 * (edges.x * edges.y + edges.y * edges.z + edges.z * edges.x);
+    // Volume of the box dilated by the kernel radius (Steiner formula)
+    let dilated_volume = fluid_volume
+        + surface_area * csr
+        + std::f64::consts::PI * csr.powi(2) * edges.sum()
+        + (4.0 / 3.0) * std::f64::consts::PI * csr.powi(3);
+
+    let density_map_points = (dilated_volume / h.powi(3)).min(grid_points);
+    let surface_cells = SURFACE_CELLS_PER_FACE_AREA * surface_area / h.powi(2);
+    let triangles = TRIANGLES_PER_SURFACE_CELL * surface_cells;
+    // Closed triangle meshes have roughly half as many vertices as triangles
+    let vertices = 0.5 * triangles;
+
+    // Number of neighbors per particle on a lattice with spacing of twice the particle radius
+    let neighbors_per_particle =
+        (4.0 / 3.0) * std::f64::consts::PI * csr.powi(3) / (2.0 * r).powi(3);
+    let particle_bytes = n
+        * (4.0 * size_of::<R>() as f64
+            + neighbors_per_particle * size_of::<usize>() as f64
+            + size_of::<Vec<usize>>() as f64);
+    let density_map_bytes =
+        HASH_MAP_OVERHEAD * density_map_points * (size_of::<u64>() + size_of::<R>()) as f64;
+    let mesh_bytes =
+        vertices * (3 * size_of::<R>()) as f64 + triangles * (3 * size_of::<usize>()) as f64;
+
+    ReconstructionEstimate {
+        cube_size,
+        relative_cube_size: cube_size / particle_radius,
+        grid_points: grid_points as u64,
+        density_map_points: density_map_points as u64,
+        surface_cells: surface_cells as u64,
+        triangles: triangles as u64,
+        vertices: vertices as u64,
+        memory_bytes: (particle_bytes + density_map_bytes + mesh_bytes) as u64,
+    }
+}
@@ -0,0 +1,260 @@
+//! Validation of input particle data, i.e. detection of non-finite positions and duplicate particles
+//!
+//! A single NaN coordinate poisons the bounding box of the particles and results in an empty or absurd
+//! background grid, coincident particles distort the density computation. The [`validate_particles`]
+//! function detects such particles and, depending on the [`ValidationPolicy`], either returns an error
+//! or reports the particles that should be removed before the reconstruction. The validation is not
+//! performed automatically by the surface reconstruction and has to be called explicitly.
+
+use crate::{new_map, profile, MapType, Real};
+use nalgebra::Vector3;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use thiserror::Error as ThisError;
+
+/// Maximum number of indices of invalid particles that are stored in a [`ParticleValidationReport`] and [`ParticleValidationError`]
+pub const MAX_REPORTED_INDICES: usize = 10;
+
+/// Policy for handling invalid particles found by [`validate_particles`]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ValidationPolicy {
+    /// Return an error if any invalid particle is found
+    Error,
+    /// Report all invalid particles so that they can be filtered out (see [`ParticleValidationReport::filter`])
+    Filter,
+    /// Skip the validation, all particles are reported as valid
+    Skip,
+}
+
+/// Result of a successful particle validation
+#[derive(Clone, Debug, Default)]
+pub struct ParticleValidationReport {
+    /// Total number of validated particles
+    pub num_particles: usize,
+    /// Number of particles with at least one non-finite (NaN or infinite) coordinate
+    pub num_non_finite: usize,
+    /// Indices of the first (at most [`MAX_REPORTED_INDICES`]) particles with non-finite coordinates
+    pub non_finite_indices: Vec<usize>,
+    /// Number of particles that duplicate a particle with a lower index (i.e. all particles except the first one of a set of coincident particles)
+    pub num_duplicates: usize,
+    /// Indices of the first (at most [`MAX_REPORTED_INDICES`]) duplicate particles
+    pub duplicate_indices: Vec<usize>,
+    /// Sorted indices of all particles that have to be removed (non-finite positions or duplicates)
+    pub invalid_particles: Vec<usize>,
+}
+
+impl ParticleValidationReport {
+    /// Returns whether no invalid particles were found
+    pub fn is_valid(&self) -> bool {
+        self.invalid_particles.is_empty()
+    }
+
+    /// Returns the sorted indices of all valid particles, e.g. to filter per particle attributes using [`AttributeData::select`](crate::mesh::AttributeData::select)
+    pub fn valid_particles(&self) -> Vec<usize> {
+        let mut invalid_iter = self.invalid_particles.iter().copied().peekable();
+        let mut valid_particles =
+            Vec::with_capacity(self.num_particles - self.invalid_particles.len());
+        for i in 0..self.num_particles {
+            if invalid_iter.peek() == Some(&i) {
+                invalid_iter.next();
+            } else {
+                valid_particles.push(i);
+            }
+        }
+        valid_particles
+    }
+
+    /// Returns a copy of the given per particle values without the values of the invalid particles
+    pub fn filter<T: Clone>(&self, values: &[T]) -> Vec<T> {
+        assert_eq!(
+            values.len(),
+            self.num_particles,
+            "number of values has to match the number of validated particles"
+        );
+        self.valid_particles()
+            .into_iter()
+            .map(|i| values[i].clone())
+            .collect()
+    }
+}
+
+/// Error type returned by [`validate_particles`] if invalid particles are found with [`ValidationPolicy::Error`]
+#[derive(Clone, Eq, PartialEq, Debug, ThisError)]
+pub enum ParticleValidationError {
+    /// Some particles have non-finite coordinates
+    #[error("{count} particles have non-finite positions (first indices: {indices:?})")]
+    NonFinitePositions {
+        /// Number of particles with non-finite coordinates
+        count: usize,
+        /// Indices of the first (at most [`MAX_REPORTED_INDICES`]) particles with non-finite coordinates
+        indices: Vec<usize>,
+    },
+    /// Some particles duplicate other particles
+    #[error("{count} particles are duplicates of other particles (first indices: {indices:?})")]
+    DuplicatePositions {
+        /// Number of duplicate particles
+        count: usize,
+        /// Indices of the first (at most [`MAX_REPORTED_INDICES`]) duplicate particles
+        indices: Vec<usize>,
+    },
+}
+
+/// Validates the particle positions, detecting non-finite coordinates and exact duplicates, see [`validate_particles_with_tolerance`]
+pub fn validate_particles<R: Real>(
+    particle_positions: &[Vector3<R>],
+    policy: ValidationPolicy,
+) -> Result<ParticleValidationReport, ParticleValidationError> {
+    validate_particles_with_tolerance(particle_positions, policy, R::zero())
+}
+
+/// Validates the particle positions, detecting non-finite coordinates and particles closer than the given distance to a particle with a lower index
+///
+/// With a distance of zero, only exact duplicates are detected. Of a set of coincident particles, the
+/// particle with the lowest index is always considered valid. Particles with non-finite coordinates are
+/// not considered for the duplicate detection. The validation is parallelized if the `parallel` feature is enabled.
+pub fn validate_particles_with_tolerance<R: Real>(
+    particle_positions: &[Vector3<R>],
+    policy: ValidationPolicy,
+    duplicate_distance: R,
+) -> Result<ParticleValidationReport, ParticleValidationError> {
+    profile!("validate_particles");
+
+    let num_particles = particle_positions.len();
+    if policy == ValidationPolicy::Skip {
+        return Ok(ParticleValidationReport {
+            num_particles,
+            ..Default::default()
+        });
+    }
+
+    let is_finite = |p: &Vector3<R>| p.iter().all(|c| c.is_finite());
+
+    #[cfg(feature = "parallel")]
+    let non_finite_mask: Vec<bool> = particle_positions
+        .par_iter()
+        .map(|p| !is_finite(p))
+        .collect();
+    #[cfg(not(feature = "parallel"))]
+    let non_finite_mask: Vec<bool> = particle_positions.iter().map(|p| !is_finite(p)).collect();
+
+    let duplicate_mask = find_duplicates(particle_positions, &non_finite_mask, duplicate_distance);
+
+    let (num_non_finite, non_finite_indices) = count_and_collect_first(&non_finite_mask);
+    let (num_duplicates, duplicate_indices) = count_and_collect_first(&duplicate_mask);
+
+    if policy == ValidationPolicy::Error {
+        if num_non_finite > 0 {
+            return Err(ParticleValidationError::NonFinitePositions {
+                count: num_non_finite,
+                indices: non_finite_indices,
+            });
+        }
+        if num_duplicates > 0 {
+            return Err(ParticleValidationError::DuplicatePositions {
+                count: num_duplicates,
+                indices: duplicate_indices,
+            });
+        }
+    }
+
+    let invalid_particles = non_finite_mask
+        .iter()
+        .zip(duplicate_mask.iter())
+        .enumerate()
+        .filter(|(_, (&non_finite, &duplicate))| non_finite || duplicate)
+        .map(|(i, _)| i)
+        .collect();
+
+    Ok(ParticleValidationReport {
+        num_particles,
+        num_non_finite,
+        non_finite_indices,
+        num_duplicates,
+        duplicate_indices,
+        invalid_particles,
+    })
+}
+
+/// Returns the number of set entries of the mask and the indices of the first few set entries
+fn count_and_collect_first(mask: &[bool]) -> (usize, Vec<usize>) {
+    let count = mask.iter().filter(|&&is_set| is_set).count();
+    let first_indices = mask
+        .iter()
+        .enumerate()
+        .filter(|(_, &is_set)| is_set)
+        .map(|(i, _)| i)
+        .take(MAX_REPORTED_INDICES)
+        .collect();
+    (count, first_indices)
+}
+
+/// Returns a mask that is `true` for every particle closer than the given distance to a valid particle with a lower index (or coincident if the distance is zero)
+fn find_duplicates<R: Real>(
+    particle_positions: &[Vector3<R>],
+    non_finite_mask: &[bool],
+    duplicate_distance: R,
+) -> Vec<bool> {
+    profile!("find_duplicates");
+
+    let exact = duplicate_distance <= R::zero();
+    let cell_size = duplicate_distance.to_f64().unwrap();
+    let duplicate_distance_sq = duplicate_distance * duplicate_distance;
+
+    // Exact duplicates are hashed by their bit patterns, near duplicates by the cell of a grid with the duplicate distance as cell size
+    let key = |p: &Vector3<R>| -> [i64; 3] {
+        let mut key = [0; 3];
+        for (k, c) in key.iter_mut().zip(p.iter()) {
+            let c = c.to_f64().unwrap();
+            *k = if exact {
+                // Adding zero maps negative zero to positive zero
+                (c + 0.0).to_bits() as i64
+            } else {
+                (c / cell_size).floor() as i64
+            };
+        }
+        key
+    };
+
+    let mut cells: MapType<[i64; 3], Vec<usize>> = new_map();
+    for (i, p) in particle_positions.iter().enumerate() {
+        if !non_finite_mask[i] {
+            cells.entry(key(p)).or_default().push(i);
+        }
+    }
+
+    let is_duplicate = |i: usize| -> bool {
+        if non_finite_mask[i] {
+            return false;
+        }
+
+        let p = &particle_positions[i];
+        let k = key(p);
+        if exact {
+            return cells[&k][0] != i;
+        }
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let neighbor_cell = [k[0] + dx, k[1] + dy, k[2] + dz];
+                    if let Some(cell_particles) = cells.get(&neighbor_cell) {
+                        // Particles in the cells are sorted by index, only particles with lower index are relevant
+                        let found = cell_particles.iter().take_while(|&&j| j < i).any(|&j| {
+                            (particle_positions[j] - p).norm_squared() < duplicate_distance_sq
+                        });
+                        if found {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+
+        false
+    };
+
+    let indices = 0..particle_positions.len();
+    #[cfg(feature = "parallel")]
+    let indices = indices.into_par_iter();
+    indices.map(is_duplicate).collect()
+}
@@ -1,5 +1,6 @@
-//! Implementation details for the [`profile`](crate::profile) macro
+//! Implementation details for the [`profile`](crate::profile) macro and access to the collected profiling data
 
+use crate::utils::Instant;
 use lazy_static::lazy_static;
 use parking_lot::RwLock;
 use std::collections::hash_map::RandomState;
@@ -7,7 +8,7 @@ use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::hash::{BuildHasher, Hash, Hasher};
 use std::io;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use thread_local::ThreadLocal;
 
 lazy_static! {
@@ -179,20 +180,65 @@ impl Profiler {
     }
 }
 
+/// Profiling data of a single scope and its child scopes, accumulated over all threads
+#[derive(Clone, Debug)]
+pub struct ProfilingScope {
+    /// Name of the scope
+    pub name: &'static str,
+    /// Number of times the scope was entered
+    pub num_calls: usize,
+    /// Sum of the durations of all calls
+    pub total_duration: Duration,
+    /// Average duration of a call
+    pub mean_duration: Duration,
+    /// Child scopes in the order of their first call
+    pub children: Vec<ProfilingScope>,
+}
+
+impl ProfilingScope {
+    /// Returns the first scope with the given name in this scope or its descendants (depth-first)
+    pub fn find(&self, name: &str) -> Option<&ProfilingScope> {
+        if self.name == name {
+            Some(self)
+        } else {
+            self.children.iter().find_map(|child| child.find(name))
+        }
+    }
+}
+
+/// Structured representation of the profiling data collected by all thread local [`Profiler`]s, see [`report`]
+#[derive(Clone, Debug, Default)]
+pub struct ProfilingReport {
+    /// All root scopes in the order of their first call
+    pub roots: Vec<ProfilingScope>,
+}
+
+impl ProfilingReport {
+    /// Returns the first scope with the given name in the report (depth-first)
+    pub fn find(&self, name: &str) -> Option<&ProfilingScope> {
+        self.roots.iter().find_map(|root| root.find(name))
+    }
+
+    /// Pretty print the report to the given writer, see [`write`]
+    pub fn write<W: io::Write>(&self, out: &mut W) -> io::Result<()> {
+        for root in &self.roots {
+            write_recursively(out, root, None, 0)?;
+        }
+        Ok(())
+    }
+}
+
 fn write_recursively<W: io::Write>(
     out: &mut W,
-    sorted_scopes: &[(ScopeId, Scope)],
-    current: &(ScopeId, Scope),
+    scope: &ProfilingScope,
     total_duration: Option<Duration>,
     depth: usize,
 ) -> io::Result<()> {
-    let (id, scope) = current;
-
     for _ in 0..depth {
         write!(out, "  ")?;
     }
 
-    let duration_sum_secs = scope.duration_sum.as_secs_f64();
+    let duration_sum_secs = scope.total_duration.as_secs_f64();
     let total_duration_secs = total_duration.map_or(duration_sum_secs, |t| t.as_secs_f64());
     let percent = duration_sum_secs / total_duration_secs * 100.0;
 
@@ -208,21 +254,45 @@ fn write_recursively<W: io::Write>(
     )?;
 
     // Process children in sorted order
-    let current_hash = ScopeId::get_hash(Some(id));
-    for s in sorted_scopes {
-        let (child_id, _) = s;
-        if child_id.parent_hash == current_hash {
-            // TODO: Prevent infinite recursion for recursive functions, maybe remove current scope from map?
-            //  Maybe we don't have this problem, instead it will be a huge chain which is as long as the recursion depth...
-            write_recursively(out, sorted_scopes, s, Some(scope.duration_sum), depth + 1)?;
-        }
+    for child in &scope.children {
+        write_recursively(out, child, Some(scope.total_duration), depth + 1)?;
     }
 
     Ok(())
 }
 
-/// Pretty print the collected profiling data of all thread local [`Profiler`]s to the given writer
-pub fn write<W: io::Write>(out: &mut W) -> io::Result<()> {
+fn build_recursively(
+    sorted_scopes: &[(ScopeId, Scope)],
+    current: &(ScopeId, Scope),
+) -> ProfilingScope {
+    let (id, scope) = current;
+
+    // TODO: Prevent infinite recursion for recursive functions, maybe remove current scope from map?
+    //  Maybe we don't have this problem, instead it will be a huge chain which is as long as the recursion depth...
+    let current_hash = ScopeId::get_hash(Some(id));
+    let children = sorted_scopes
+        .iter()
+        .filter(|(child_id, _)| child_id.parent_hash == current_hash)
+        .map(|child| build_recursively(sorted_scopes, child))
+        .collect();
+
+    ProfilingScope {
+        name: scope.name,
+        num_calls: scope.num_calls,
+        total_duration: scope.duration_sum,
+        mean_duration: if scope.num_calls > 0 {
+            scope.duration_sum / scope.num_calls as u32
+        } else {
+            Duration::ZERO
+        },
+        children,
+    }
+}
+
+/// Returns the profiling data collected by all thread local [`Profiler`]s as a structured [`ProfilingReport`]
+///
+/// To obtain the profiling data of individual parts of a program (e.g. frames of a sequence), use [`reset`] after each part.
+pub fn report() -> ProfilingReport {
     let mut merged_scopes = HashMap::<ScopeId, Scope>::new();
     let mut roots = HashSet::<ScopeId>::new();
 
@@ -261,12 +331,17 @@ pub fn write<W: io::Write>(out: &mut W) -> io::Result<()> {
         scopes
     };
 
-    // Print the stats
-    for root in &sorted_roots {
-        write_recursively(out, sorted_scopes.as_slice(), root, None, 0)?;
+    ProfilingReport {
+        roots: sorted_roots
+            .iter()
+            .map(|root| build_recursively(sorted_scopes.as_slice(), root))
+            .collect(),
     }
+}
 
-    Ok(())
+/// Pretty print the collected profiling data of all thread local [`Profiler`]s to the given writer
+pub fn write<W: io::Write>(out: &mut W) -> io::Result<()> {
+    report().write(out)
 }
 
 /// Returns the pretty printed output of the collected profiling data as a `String`
@@ -1,4 +1,4 @@
-//! Provides the [`profile`] macro or a dummy implementation depending on the selected feature
+//! Provides the [`profile`] macro or a dummy implementation that only records stage timings depending on the selected feature
 
 #[cfg(feature = "profiling")]
 /// Creates a scope for profiling
@@ -7,9 +7,15 @@
 /// The biggest difference to `coarse-prof` is that it supports profiling in multi-threaded programs.
 ///
 /// Note that this macro only works when the `profiling` feature is enabled. Otherwise a dummy
-/// implementation is provided with the same rules that expand to nothing. This allows the user to
-/// disable this coarse grained profiling without modifying code depending on this macro. This can
-/// be helpful to minimize overhead when using more elaborate profiling approaches.
+/// implementation is provided with the same rules that only record the lightweight stage timings
+/// (see below). This allows the user to disable this coarse grained profiling without modifying code
+/// depending on this macro. This can be helpful to minimize overhead when using more elaborate profiling approaches.
+///
+/// Independent of the `profiling` feature, the macro records the duration of the scope into a
+/// [`TimingsCollector`](crate::timings::TimingsCollector) if one is attached to the current thread,
+/// see the [`timings`](crate::timings) module. If the `tracing` feature is enabled, the macro also
+/// enters a `tracing` span with the name of the scope. As `tracing` spans are not inherited by other
+/// threads, no span is created for scopes with a manually assigned parent scope.
 ///
 /// Profiling works using scope guards that increment a thread local [`Profiler`](crate::profiling::Profiler)
 /// (stored in the static [`PROFILER`](static@crate::profiling::PROFILER) variable) when they are dropped.
@@ -73,29 +79,63 @@
 #[cfg_attr(doc_cfg, doc(cfg(feature = "profiling")))]
 macro_rules! profile {
     ($name:expr) => {
+        let _timings_scope_guard = $crate::timings::enter($name);
+        $crate::profile_span_impl!($name);
         use $crate::profile_impl;
         profile_impl!($name);
     };
     ($scope_id:ident, $name:expr) => {
+        let _timings_scope_guard = $crate::timings::enter($name);
+        $crate::profile_span_impl!($name);
         use $crate::profile_impl;
         profile_impl!($scope_id, $name);
     };
     ($name:expr, parent = $parent_id:ident) => {
+        let _timings_scope_guard = $crate::timings::enter($name);
         use $crate::profile_impl;
         profile_impl!($name, parent = $parent_id);
     };
     ($scope_id:ident, $name:expr, parent = $parent_id:ident) => {
+        let _timings_scope_guard = $crate::timings::enter($name);
         use $crate::profile_impl;
         profile_impl!($scope_id, $name, parent = $parent_id);
     };
 }
 
 #[cfg(not(feature = "profiling"))]
-/// No-op macro if profiling is disabled
+/// Macro that only records the stage timings (see [`timings`](crate::timings)) if profiling is disabled
 #[macro_export]
 macro_rules! profile {
+    ($name:expr) => {
+        let _timings_scope_guard = $crate::timings::enter($name);
+        $crate::profile_span_impl!($name);
+    };
+    ($scope_id:ident, $name:expr) => {
+        let _timings_scope_guard = $crate::timings::enter($name);
+        $crate::profile_span_impl!($name);
+    };
+    ($name:expr, parent = $parent_id:ident) => {
+        let _timings_scope_guard = $crate::timings::enter($name);
+    };
+    ($scope_id:ident, $name:expr, parent = $parent_id:ident) => {
+        let _timings_scope_guard = $crate::timings::enter($name);
+    };
+}
+
+#[cfg(feature = "tracing")]
+/// Enters a `tracing` span with the given name until the end of the current scope, used by [`profile`]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! profile_span_impl {
+    ($name:expr) => {
+        let _tracing_span_guard = $crate::tracing::info_span!($name).entered();
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+/// No-op if the `tracing` feature is disabled, used by [`profile`]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! profile_span_impl {
     ($name:expr) => {};
-    ($scope_id:ident, $name:expr) => {};
-    ($name:expr, parent = $parent_id:ident) => {};
-    ($scope_id:ident, $name:expr, parent = $parent_id:ident) => {};
 }
@@ -0,0 +1,168 @@
+//! Rate-limited progress logging of long-running stages of the reconstruction
+//!
+//! Progress logging is enabled for the duration of a reconstruction on the calling thread if
+//! [`Parameters::progress_interval`](crate::Parameters::progress_interval) is set. Stages that support progress
+//! logging create a [`ProgressLogger`] on the calling thread which can then be shared with worker threads.
+//! The number of processed items is aggregated using relaxed atomics and at most one `info` message is
+//! emitted per interval and stage. Stages that are started on worker threads (e.g. the density map of an
+//! octree leaf) or while another stage is logging its progress on the same thread do not log their progress.
+
+use crate::logging::info;
+use crate::utils::Instant;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Maximum number of processed items between two checks of the elapsed time
+const MAX_CHECK_GRANULARITY: usize = 1024;
+
+thread_local! {
+    /// The interval for progress messages configured on this thread
+    static INTERVAL: Cell<Option<Duration>> = const { Cell::new(None) };
+    /// Whether a stage is currently logging its progress on this thread
+    static STAGE_ACTIVE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Guard that restores the previous progress interval of the thread when dropped, see [`enable`]
+pub(crate) struct EnableGuard {
+    previous: Option<Duration>,
+}
+
+impl Drop for EnableGuard {
+    fn drop(&mut self) {
+        INTERVAL.with(|interval| interval.set(self.previous));
+    }
+}
+
+/// Enables progress logging with the given interval on the current thread until the returned guard is dropped
+pub(crate) fn enable(interval: Option<Duration>) -> EnableGuard {
+    EnableGuard {
+        previous: INTERVAL.with(|current| current.replace(interval)),
+    }
+}
+
+/// Aggregates the progress of a stage and periodically logs it
+pub(crate) struct ProgressLogger {
+    stage: &'static str,
+    unit: &'static str,
+    total: u64,
+    /// The elapsed time is only checked when the number of processed items crosses a multiple of `2^check_granularity_log2`
+    check_granularity_log2: u32,
+    interval_nanos: u64,
+    start: Instant,
+    /// Number of items processed so far
+    processed: AtomicU64,
+    /// Time (relative to `start`) after which the next message should be emitted
+    next_log_nanos: AtomicU64,
+}
+
+impl ProgressLogger {
+    /// Starts logging the progress of a stage if progress logging is enabled on this thread
+    ///
+    /// The total number of items of the stage is only computed if progress logging is enabled. Returns `None`
+    /// if progress logging is disabled or another stage is already logging its progress on this thread.
+    pub(crate) fn start<F: FnOnce() -> usize>(
+        stage: &'static str,
+        unit: &'static str,
+        total: F,
+    ) -> Option<Self> {
+        let interval = INTERVAL.with(|interval| interval.get())?;
+        if STAGE_ACTIVE.with(|active| active.replace(true)) {
+            return None;
+        }
+        let total = total();
+
+        // Check the time about once per 0.1% of the items (but at least every 1024 items)
+        let check_granularity_log2 = (total / 1000)
+            .clamp(1, MAX_CHECK_GRANULARITY)
+            .next_power_of_two()
+            .trailing_zeros();
+        let interval_nanos = u64::try_from(interval.as_nanos()).unwrap_or(u64::MAX);
+        Some(Self {
+            stage,
+            unit,
+            total: total as u64,
+            check_granularity_log2,
+            interval_nanos,
+            start: Instant::now(),
+            processed: AtomicU64::new(0),
+            next_log_nanos: AtomicU64::new(interval_nanos),
+        })
+    }
+
+    /// Adds the given number of processed items and logs the progress if the interval since the last message elapsed
+    #[inline]
+    pub(crate) fn add(&self, n: usize) {
+        let n = n as u64;
+        let before = self.processed.fetch_add(n, Ordering::Relaxed);
+        // Only check the time if the counter crossed a multiple of the granularity
+        if before >> self.check_granularity_log2 != (before + n) >> self.check_granularity_log2 {
+            self.try_log(before + n);
+        }
+    }
+
+    #[cold]
+    fn try_log(&self, processed: u64) {
+        let elapsed_nanos = u64::try_from(self.start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        let next_log_nanos = self.next_log_nanos.load(Ordering::Relaxed);
+        if elapsed_nanos < next_log_nanos {
+            return;
+        }
+
+        // Only the thread that advances the time of the next message emits the message
+        if self
+            .next_log_nanos
+            .compare_exchange(
+                next_log_nanos,
+                elapsed_nanos.saturating_add(self.interval_nanos),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+        {
+            let percent = if self.total > 0 {
+                (processed as f64 / self.total as f64 * 100.0).min(100.0)
+            } else {
+                100.0
+            };
+            let rate = processed as f64 / (elapsed_nanos as f64 * 1e-9).max(f64::EPSILON);
+            info!(
+                "{}: {:.0}% ({}/{} {}, {} {}/s)",
+                self.stage,
+                percent,
+                format_count(processed as f64),
+                format_count(self.total as f64),
+                self.unit,
+                format_count(rate),
+                self.unit
+            );
+        }
+    }
+}
+
+impl Drop for ProgressLogger {
+    fn drop(&mut self) {
+        STAGE_ACTIVE.with(|active| active.set(false));
+    }
+}
+
+/// Formats a count with an SI suffix, e.g. `1.2M` or `850k`
+fn format_count(count: f64) -> String {
+    if count >= 1e9 {
+        format!("{:.1}G", count / 1e9)
+    } else if count >= 1e6 {
+        format!("{:.1}M", count / 1e6)
+    } else if count >= 1e3 {
+        format!("{:.0}k", count / 1e3)
+    } else {
+        format!("{:.0}", count)
+    }
+}
+
+#[test]
+fn test_format_count() {
+    assert_eq!(format_count(42.0), "42");
+    assert_eq!(format_count(850_123.0), "850k");
+    assert_eq!(format_count(1_234_567.0), "1.2M");
+    assert_eq!(format_count(3.1e9), "3.1G");
+}
@@ -1,20 +1,82 @@
 //! Helper functions calling the individual steps of the reconstruction pipeline
 
+use crate::density_map::DensityMap;
 use crate::generic_tree::*;
-use crate::marching_cubes::SurfacePatch;
-use crate::mesh::TriMesh3d;
-use crate::octree::{NodeData, Octree, OctreeNode};
+use crate::kernel::DiscreteSquaredDistanceCubicKernel;
+use crate::logging::{debug, info, trace, warn};
+use crate::marching_cubes::{MarchingCubesError, SurfacePatch};
+use crate::mesh::{AttributeData, MeshAttribute, MeshWithData, TriMesh3d};
+use crate::neighborhood_search::{
+    ParticleNeighborhoodProvider, SpatialHashingNeighborhoodProvider,
+};
+use crate::octree::{NodeData, Octree, OctreeError, OctreeNode, SubdivisionCriterion};
+use crate::progress::ProgressLogger;
+use crate::sph_interpolation::SphInterpolator;
+use crate::timings::{self, LeafTiming, LeafTimings};
 use crate::uniform_grid::{OwningSubdomainGrid, Subdomain, UniformGrid};
+use crate::utils::{ChunkSize, Instant, ParallelPolicy};
 use crate::workspace::LocalReconstructionWorkspace;
 use crate::{
-    density_map, marching_cubes, neighborhood_search, new_map, profile, utils, Index, Parameters,
-    ParticleDensityComputationStrategy, Real, ReconstructionError, SpatialDecompositionParameters,
-    SurfaceReconstruction,
+    density_map, marching_cubes, neighborhood_search, new_map, profile, utils, AttributeError,
+    AttributeInterpolation, AttributeSpecs, CancellationToken, Index, InvalidParticleReason,
+    NormalsComputation, Parameters, ParticleDensityComputationStrategy, Real, ReconstructionError,
+    SpatialDecompositionParameters, SplashDetection, SplashOutput, SurfaceReconstruction,
+    TemporalDensityState, TriangleOrigins,
 };
-use log::{debug, info, trace};
 use nalgebra::Vector3;
 use num::Bounded;
 use parking_lot::Mutex;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Enters a `tracing` span for the visit of an octree node (possibly on a worker thread) as a child of the given parent span
+#[cfg(feature = "tracing")]
+macro_rules! enter_octree_node_span {
+    ($parent_span:expr, $name:expr, $octree_node:expr) => {
+        tracing::info_span!(
+            parent: $parent_span,
+            $name,
+            node_id = $octree_node.id(),
+            num_particles = $octree_node
+                .data()
+                .particle_set()
+                .map_or(0, |particle_set| particle_set.particles.len())
+        )
+        .entered()
+    };
+}
+
+/// Number of threads assumed for [`SubdivisionCriterion::MaxParticleCountAuto`] in a deterministic reconstruction
+const DETERMINISTIC_AUTO_NUM_THREADS: usize = 8;
+
+/// Checks that all particle positions are finite, returns an error with the index of the first invalid particle
+pub(crate) fn check_particle_positions<I: Index, R: Real>(
+    particle_positions: &[Vector3<R>],
+) -> Result<(), ReconstructionError<I, R>> {
+    profile!("check_particle_positions");
+
+    let invalid = particle_positions
+        .iter()
+        .enumerate()
+        .find(|(_, p)| !p.iter().all(|c| c.is_finite()));
+
+    if let Some((index, position)) = invalid {
+        let reason = if position
+            .iter()
+            .any(|c| c.to_f64().unwrap_or(f64::NAN).is_nan())
+        {
+            InvalidParticleReason::NanPosition
+        } else {
+            InvalidParticleReason::InfinitePosition
+        };
+        Err(ReconstructionError::InvalidParticleData { index, reason })
+    } else {
+        Ok(())
+    }
+}
 
 /// Performs a global surface reconstruction without domain decomposition
 pub(crate) fn reconstruct_surface_global<'a, I: Index, R: Real>(
@@ -42,23 +104,151 @@ pub(crate) fn reconstruct_surface_global<'a, I: Index, R: Real>(
         }
     }
 
-    // Clear the current mesh, as reconstruction will be appended to output
-    output_surface.mesh.clear();
+    // Reuse the memory of the density map of the previous reconstruction
+    if let Some(density_map) = output_surface.density_map.take() {
+        workspace.density_map = density_map;
+    }
+
+    // Reserve memory for the sizes reached in the previous reconstruction, the density map of the workspace is
+    // only reused by the sequential and non-deterministic density map generation
+    output_surface.workspace.prime_local(
+        &mut workspace,
+        !parameters.enable_multi_threading && !parameters.deterministic,
+    );
+
+    // Particle densities are only computed upfront if they are required for the splash detection
+    let mut particle_densities = None;
+
+    // Classify splash particles and collect all remaining particles that should contribute to the surface
+    let mut active_particles = std::mem::take(&mut workspace.active_particles);
+    output_surface.splash_particle_indices =
+        if let Some(splash_detection) = &parameters.splash_detection {
+            let mut splash_particles = output_surface
+                .splash_particle_indices
+                .take()
+                .unwrap_or_default();
+            match *splash_detection {
+                SplashDetection::NeighborCount(splash_detection_radius) => {
+                    classify_splash_particles(
+                        &output_surface.grid,
+                        particle_positions,
+                        splash_detection_radius,
+                        parameters.enable_multi_threading,
+                        &mut workspace.particle_neighbor_lists,
+                        &mut splash_particles,
+                    );
+                }
+                SplashDetection::DensityThreshold(factor) => {
+                    let mut densities = std::mem::take(&mut workspace.particle_densities);
+                    compute_particle_densities_and_neighbors(
+                        &output_surface.grid,
+                        particle_positions,
+                        parameters,
+                        &mut workspace.particle_neighbor_lists,
+                        &mut densities,
+                    );
+                    classify_splash_particles_by_density(
+                        densities.as_slice(),
+                        splash_density_threshold(parameters, factor),
+                        &mut splash_particles,
+                    );
+                    particle_densities = Some(densities);
+                }
+            }
+            collect_active_particles(
+                particle_positions.len(),
+                splash_particles.as_slice(),
+                &mut active_particles,
+            );
+            Some(splash_particles)
+        } else {
+            None
+        };
+
+    // Clear the current mesh (unless appending to it), as reconstruction will be appended to output
+    if !output_surface.append_to_mesh {
+        crate::clear_shared_mesh(&mut output_surface.mesh);
+    }
+    output_surface
+        .workspace
+        .prime_mesh(Arc::make_mut(&mut output_surface.mesh));
+    // The density map of the previous frame is blended with the current density map for the temporal smoothing
+    // (the state is discarded if the smoothing is disabled)
+    let temporal_density_state = output_surface.temporal_density_state.take();
+    let previous_density_map = parameters
+        .temporal_density_smoothing
+        .and(temporal_density_state)
+        .and_then(|state| {
+            previous_density_map_on_grid(&state.grid, state.density_map, &output_surface.grid)
+        });
+
     // Perform global reconstruction without octree
-    reconstruct_single_surface_append(
-        &mut *workspace,
-        &output_surface.grid,
-        None,
-        particle_positions,
-        None,
-        parameters,
-        &mut output_surface.mesh,
-    )?;
+    let clamped_density_values = AtomicUsize::new(0);
+    let result = output_surface.cancellation_token.check().and_then(|_| {
+        reconstruct_single_surface_append(
+            &mut *workspace,
+            SingleSurfaceInput {
+                grid: &output_surface.grid,
+                subdomain_grid: None,
+                particle_positions,
+                particle_densities: particle_densities.as_ref().map(|v| v.as_slice()),
+                active_particles: output_surface
+                    .splash_particle_indices
+                    .as_ref()
+                    .map(|_| active_particles.as_slice()),
+                parameters,
+                previous_density_map: previous_density_map.as_ref(),
+            },
+            Arc::make_mut(&mut output_surface.mesh),
+            output_surface
+                .triangle_origins
+                .as_mut()
+                .map(|origins| &mut origins.cells),
+            &clamped_density_values,
+        )
+    });
+    output_surface.stats.warnings.clamped_density_values = clamped_density_values.into_inner();
+
+    // Put back everything taken from the workspace
+    workspace.active_particles = active_particles;
+    if let Some(particle_densities) = particle_densities {
+        workspace.particle_densities = particle_densities;
+    }
 
-    // TODO: Set this correctly
-    output_surface.density_map = None;
+    // The density map is either kept in the output or reused by the next reconstruction
+    let density_map = result?;
+    let density_map_entries = density_map.len();
+    if parameters.temporal_density_smoothing.is_some() {
+        // The blended map is the state of the temporal smoothing for the next frame, the memory of the previous
+        // map is reused instead
+        if output_surface.keep_density_map {
+            output_surface.density_map = Some(density_map.clone());
+        } else if let (Some(previous_density_map), false) =
+            (previous_density_map, parameters.deterministic)
+        {
+            workspace.density_map = previous_density_map;
+        }
+        output_surface.temporal_density_state = Some(TemporalDensityState {
+            grid: output_surface.grid.clone(),
+            density_map,
+        });
+    } else if output_surface.keep_density_map {
+        output_surface.density_map = Some(density_map);
+    } else if !parameters.deterministic {
+        workspace.density_map = density_map;
+    }
     output_surface.particle_densities = Some(std::mem::take(&mut workspace.particle_densities));
 
+    // Record the sizes of the buffers to reserve memory in the next reconstruction
+    let neighbor_lists = workspace.particle_neighbor_lists.len();
+    let neighbor_list_entries = workspace.particle_neighbor_lists.iter().map(Vec::len).sum();
+    drop(workspace);
+    output_surface.workspace.record_global_sizes(
+        density_map_entries,
+        neighbor_lists,
+        neighbor_list_entries,
+    );
+
     Ok(())
 }
 
@@ -70,8 +260,17 @@ pub(crate) fn reconstruct_surface_domain_decomposition<'a, I: Index, R: Real>(
 ) -> Result<(), ReconstructionError<I, R>> {
     profile!("reconstruct_surface_domain_decomposition");
 
-    OctreeBasedSurfaceReconstruction::new(particle_positions, parameters, output_surface)
-        .expect("Unable to construct octree. Missing/invalid decomposition parameters?")
+    if parameters.temporal_density_smoothing.is_some() {
+        warn!("Temporal density smoothing is not supported with spatial decomposition and is ignored.");
+    }
+    output_surface.temporal_density_state = None;
+
+    // The triangles of the octree based reconstruction are also attributed to the octree nodes
+    if let Some(origins) = output_surface.triangle_origins.as_mut() {
+        origins.nodes.resize(origins.cells.len(), usize::MAX);
+    }
+
+    OctreeBasedSurfaceReconstruction::new(particle_positions, parameters, output_surface)?
         .run(particle_positions, output_surface)?;
 
     Ok(())
@@ -87,6 +286,10 @@ struct OctreeBasedSurfaceReconstruction<I: Index, R: Real> {
     grid: UniformGrid<I, R>,
     /// Octree containing the individual particle subdomains, built during the construction of this helper type
     octree: Octree<I, R>,
+    /// Margin around the octree leaves used to collect ghost particles
+    ghost_margin: R,
+    /// Token that is checked before processing each octree node
+    cancellation_token: CancellationToken,
 }
 
 // TODO: Make this less object oriented?
@@ -96,30 +299,67 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
         global_particle_positions: &[Vector3<R>],
         parameters: &Parameters<R>,
         output_surface: &SurfaceReconstruction<I, R>,
-    ) -> Option<Self> {
+    ) -> Result<Self, OctreeError<I, R>> {
         // The grid was already generated by the calling public function
-        let grid = output_surface.grid.clone();
+        let grid = UniformGrid::clone(&output_surface.grid);
 
         // Construct the octree
-        let octree = if let Some(decomposition_parameters) = &parameters.spatial_decomposition {
-            let margin_factor = decomposition_parameters
-                .ghost_particle_safety_factor
-                .unwrap_or(R::one());
+        let (octree, ghost_margin) =
+            if let Some(decomposition_parameters) = &parameters.spatial_decomposition {
+                let margin_factor = decomposition_parameters
+                    .ghost_particle_safety_factor
+                    .unwrap_or(R::one());
+
+                // The ghost particle margin has to cover the splash detection radius to classify all particles consistently
+                let margin = parameters.compact_support_radius * margin_factor;
+                let margin = if let Some(SplashDetection::NeighborCount(splash_detection_radius)) =
+                    parameters.splash_detection
+                {
+                    margin.max(splash_detection_radius)
+                } else {
+                    margin
+                };
 
-            Octree::new_subdivided(
-                &grid,
-                global_particle_positions,
-                decomposition_parameters.subdivision_criterion.clone(),
-                parameters.compact_support_radius * margin_factor,
-                parameters.enable_multi_threading,
-                decomposition_parameters.enable_stitching,
-            )
-        } else {
-            // TODO: Use default values instead?
+                if !margin.is_finite() || margin < R::zero() {
+                    return Err(OctreeError::InvalidMargin(margin));
+                }
 
-            // If there are no decomposition parameters, we cannot construct an octree.
-            return None;
-        };
+                // The automatic particle count depends on the number of threads, use a fixed number instead for deterministic results
+                let subdivision_criterion = match decomposition_parameters.subdivision_criterion {
+                    SubdivisionCriterion::MaxParticleCountAuto if parameters.deterministic => {
+                        SubdivisionCriterion::MaxParticleCount(
+                            ChunkSize::with_num_threads(
+                                &ParallelPolicy::default(),
+                                global_particle_positions.len(),
+                                DETERMINISTIC_AUTO_NUM_THREADS,
+                            )
+                            .chunk_size,
+                        )
+                    }
+                    ref subdivision_criterion => subdivision_criterion.clone(),
+                };
+
+                let octree = Octree::new_subdivided(
+                    &grid,
+                    global_particle_positions,
+                    subdivision_criterion,
+                    margin,
+                    parameters.enable_multi_threading,
+                    decomposition_parameters.enable_stitching,
+                );
+                info!(
+                    "Octree has {} nodes, {} leaves and a depth of {}.",
+                    octree.root().node_count(),
+                    octree.root().leaf_count(),
+                    octree.root().max_depth()
+                );
+                (octree, margin)
+            } else {
+                // TODO: Use default values instead?
+
+                // If there are no decomposition parameters, we cannot construct an octree.
+                return Err(OctreeError::MissingDecompositionParameters);
+            };
 
         // Disable all multi-threading in sub-tasks for now (instead, entire sub-tasks are processed in parallel)
         let parameters = {
@@ -128,11 +368,13 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
             p
         };
 
-        Some(Self {
+        Ok(Self {
             octree,
+            ghost_margin,
             spatial_decomposition: parameters.spatial_decomposition.as_ref().unwrap().clone(),
             grid,
             parameters,
+            cancellation_token: output_surface.cancellation_token.clone(),
         })
     }
 
@@ -181,21 +423,63 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
                 ParticleDensityComputationStrategy::IndependentSubdomains => None,
             };
 
+        self.cancellation_token.check()?;
+
+        // Classify splash particles according to the selected criterion
+        let global_splash_mask = match self.parameters.splash_detection {
+            // Neighbor search per subdomain, using the ghost particles to get complete neighborhoods
+            Some(SplashDetection::NeighborCount(splash_detection_radius)) => {
+                Some(Self::classify_splash_particles_local(
+                    global_particle_positions,
+                    &self.grid,
+                    &self.octree,
+                    &self.parameters,
+                    splash_detection_radius,
+                    output_surface,
+                ))
+            }
+            // Compare the particle densities to the density of an isolated particle
+            Some(SplashDetection::DensityThreshold(factor)) => {
+                let density_threshold = splash_density_threshold(&self.parameters, factor);
+                // Independent subdomains do not provide global densities, this is rejected by the parameter validation
+                let global_particle_densities = global_particle_densities_vec.as_ref().expect(
+                    "density threshold splash detection requires global particle densities",
+                );
+                Some(Self::classify_splash_particles_by_density_global(
+                    global_particle_densities,
+                    density_threshold,
+                ))
+            }
+            None => None,
+        };
+
+        self.cancellation_token.check()?;
+
+        // Ghost margins can only be truncated if particles are skipped at the domain boundary
+        if output_surface.stats.warnings.particles_outside_domain > 0 {
+            output_surface.stats.warnings.truncated_ghost_margins =
+                self.count_truncated_ghost_margins(global_particle_positions);
+        }
+
         {
             let global_particle_densities =
                 global_particle_densities_vec.as_ref().map(|v| v.as_slice());
 
+            let global_splash_mask = global_splash_mask.as_ref().map(|v| v.as_slice());
+
             // Run surface reconstruction
             if self.spatial_decomposition.enable_stitching {
                 self.run_with_stitching(
                     global_particle_positions,
                     global_particle_densities,
+                    global_splash_mask,
                     output_surface,
                 )?;
             } else {
                 self.run_without_stitching(
                     global_particle_positions,
                     global_particle_densities,
+                    global_splash_mask,
                     output_surface,
                 )?;
             }
@@ -210,6 +494,14 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
         output_surface.octree = Some(self.octree);
         output_surface.density_map = None;
         output_surface.particle_densities = global_particle_densities_vec;
+        output_surface.splash_particle_indices = global_splash_mask.map(|splash_mask| {
+            splash_mask
+                .iter()
+                .enumerate()
+                .filter(|(_, &is_splash)| is_splash)
+                .map(|(i, _)| i)
+                .collect()
+        });
 
         Ok(())
     }
@@ -249,6 +541,8 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
             parent_scope,
             "parallel subdomain particle density computation"
         );
+        #[cfg(feature = "tracing")]
+        let parent_span = tracing::Span::current();
         info!("Starting computation of particle densities.");
 
         // Take the global density storage from workspace to move it behind a mutex
@@ -270,6 +564,12 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
                     "visit octree node for density computation",
                     parent = parent_scope
                 );
+                #[cfg(feature = "tracing")]
+                let _node_span = enter_octree_node_span!(
+                    &parent_span,
+                    "visit octree node for density computation",
+                    octree_node
+                );
 
                 let node_particles = if let Some(particle_set) = octree_node.data().particle_set() {
                     &particle_set.particles
@@ -278,10 +578,8 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
                     return;
                 };
 
-                let mut tl_workspace_ref_mut = tl_workspaces
-                    .get_local_with_capacity(node_particles.len())
-                    .borrow_mut();
-                let tl_workspace = &mut *tl_workspace_ref_mut;
+                let mut leaf_workspace = tl_workspaces.get_local_for_leaf(node_particles.len());
+                let tl_workspace = &mut *leaf_workspace;
 
                 Self::collect_node_particle_positions(
                     node_particles,
@@ -319,11 +617,122 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
         *output_surface.workspace.densities_mut() = global_densities.into_inner();
     }
 
+    /// Classifies splash particles per subdomain, returns a global mask that is `true` for every splash particle
+    fn classify_splash_particles_local(
+        global_particle_positions: &[Vector3<R>],
+        grid: &UniformGrid<I, R>,
+        octree: &Octree<I, R>,
+        parameters: &Parameters<R>,
+        splash_detection_radius: R,
+        output_surface: &SurfaceReconstruction<I, R>,
+    ) -> Vec<bool> {
+        profile!(parent_scope, "parallel subdomain splash detection");
+        #[cfg(feature = "tracing")]
+        let parent_span = tracing::Span::current();
+        info!("Starting detection of splash particles.");
+
+        let tl_workspaces = &output_surface.workspace;
+
+        // Collect the splash particles of each leaf, keyed by the id of the leaf
+        let leaf_splash_particles =
+            octree
+                .root()
+                .par_visit_map(|octree_node: &OctreeNode<I, R>| {
+                    profile!(
+                        "visit octree node for splash detection",
+                        parent = parent_scope
+                    );
+                    #[cfg(feature = "tracing")]
+                    let _node_span = enter_octree_node_span!(
+                        &parent_span,
+                        "visit octree node for splash detection",
+                        octree_node
+                    );
+
+                    // Skip non-leaf nodes
+                    let node_particles = &octree_node.data().particle_set()?.particles;
+
+                    let mut leaf_workspace = tl_workspaces.get_local_for_leaf(node_particles.len());
+                    let tl_workspace = &mut *leaf_workspace;
+
+                    Self::collect_node_particle_positions(
+                        node_particles,
+                        global_particle_positions,
+                        &mut tl_workspace.particle_positions,
+                    );
+
+                    neighborhood_search::search_inplace::<I, R>(
+                        &grid.aabb(),
+                        tl_workspace.particle_positions.as_slice(),
+                        splash_detection_radius,
+                        parameters.enable_multi_threading,
+                        &mut tl_workspace.particle_neighbor_lists,
+                    );
+
+                    let splash_particles: Vec<usize> = node_particles
+                        .iter()
+                        .zip(
+                            tl_workspace
+                                .particle_neighbor_lists
+                                .iter()
+                                .zip(tl_workspace.particle_positions.iter()),
+                        )
+                        // Only classify non-ghost particles, their neighborhood is complete due to the ghost particle margin
+                        .filter(|(_, (neighbors, position))| {
+                            neighbors.is_empty() && octree_node.aabb().contains_point(position)
+                        })
+                        .map(|(&global_idx, _)| global_idx)
+                        .collect();
+
+                    Some((octree_node.id(), splash_particles))
+                });
+
+        let mut global_splash_mask = vec![false; global_particle_positions.len()];
+        for (_, splash_particles) in leaf_splash_particles {
+            for global_idx in splash_particles {
+                global_splash_mask[global_idx] = true;
+            }
+        }
+        info!(
+            "Classified {} of {} particles as splash particles.",
+            global_splash_mask
+                .iter()
+                .filter(|&&is_splash| is_splash)
+                .count(),
+            global_particle_positions.len()
+        );
+
+        global_splash_mask
+    }
+
+    /// Classifies all particles with a density below the given threshold as splash particles, returns a global mask that is `true` for every splash particle
+    fn classify_splash_particles_by_density_global(
+        global_particle_densities: &[R],
+        density_threshold: R,
+    ) -> Vec<bool> {
+        let global_splash_mask: Vec<_> = global_particle_densities
+            .iter()
+            .map(|&density| density < density_threshold)
+            .collect();
+
+        info!(
+            "Classified {} of {} particles as splash particles.",
+            global_splash_mask
+                .iter()
+                .filter(|&&is_splash| is_splash)
+                .count(),
+            global_particle_densities.len()
+        );
+
+        global_splash_mask
+    }
+
     /// Performs surface reconstruction without stitching by visiting all octree leaf nodes
     fn run_without_stitching(
         &self,
         global_particle_positions: &[Vector3<R>],
         global_particle_densities: Option<&[R]>,
+        global_splash_mask: Option<&[bool]>,
         output_surface: &mut SurfaceReconstruction<I, R>,
     ) -> Result<(), ReconstructionError<I, R>> {
         // Clear all local meshes
@@ -338,16 +747,33 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
                 });
         }
 
+        // In deterministic mode (or if the triangle origins are recorded), the leaf meshes are collected and appended in a fixed order afterwards
+        let record_origins = output_surface.record_triangle_origins;
+        let collect_leaf_meshes = self.parameters.deterministic || record_origins;
+        let leaf_meshes = Mutex::new(Vec::new());
+
         // Perform individual surface reconstructions on all non-empty leaves of the octree
         {
             let tl_workspaces = &output_surface.workspace;
 
             profile!(parent_scope, "parallel subdomain surf. rec.");
+            #[cfg(feature = "tracing")]
+            let parent_span = tracing::Span::current();
             info!("Starting triangulation of surface patches.");
+            let progress = ProgressLogger::start("octree leaves", "leaves", || self.num_leaves());
+            // Per-leaf timings are only recorded if statistics are collected
+            let leaf_timings = timings::is_attached().then(|| Mutex::new(Vec::new()));
+            let clamped_density_values = AtomicUsize::new(0);
+            // Process subtrees with only a few particles sequentially to reduce the task overhead
+            let min_task_size = ParallelPolicy::default().min_task_size;
+            let is_small_subtree =
+                |octree_node: &OctreeNode<I, R>| octree_node.is_small_subtree(min_task_size);
 
             self.octree
                 .root()
-                .try_par_visit_bfs(|octree_node: &OctreeNode<I, R>| -> Result<(), ReconstructionError<I, R>> {
+                .try_par_visit_bfs_with_granularity(is_small_subtree, |octree_node: &OctreeNode<I, R>| -> Result<(), ReconstructionError<I, R>> {
+                    self.cancellation_token.check()?;
+
                     let particles = if let Some(particle_set) = octree_node.data().particle_set() {
                         &particle_set.particles
                     } else {
@@ -356,12 +782,18 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
                     };
 
                     profile!("visit octree node for reconstruction", parent = parent_scope);
+                    #[cfg(feature = "tracing")]
+                    let _node_span = enter_octree_node_span!(&parent_span, "visit octree node for reconstruction", octree_node);
                     trace!("Processing octree leaf with {} particles", particles.len());
 
                     if particles.is_empty() {
+                        if let Some(progress) = &progress {
+                            progress.add(1);
+                        }
                         return Ok(());
                     } else {
-                        let subdomain_grid = self.extract_node_subdomain(octree_node);
+                        let leaf_start = leaf_timings.is_some().then(Instant::now);
+                        let subdomain_grid = self.extract_node_subdomain(octree_node)?;
 
                         debug!(
                             "Surface reconstruction of local patch with {} particles. (offset: {:?}, cells_per_dim: {:?})",
@@ -370,8 +802,7 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
                             subdomain_grid.subdomain_grid().cells_per_dim());
 
                         let mut tl_workspace = tl_workspaces
-                            .get_local_with_capacity(particles.len())
-                            .borrow_mut();
+                            .get_local_for_leaf(particles.len());
 
                         // Take particle position storage from workspace and fill it with positions of the leaf
                         let mut node_particle_positions = std::mem::take(&mut tl_workspace.particle_positions);
@@ -386,38 +817,90 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
                             None
                         };
 
+                        // Take active particle storage from workspace and fill it with all non-splash particles of the leaf
+                        let node_active_particles = if let Some(global_splash_mask) = global_splash_mask {
+                            let mut node_active_particles = std::mem::take(&mut tl_workspace.active_particles);
+                            Self::collect_node_active_particles(particles, global_splash_mask, &mut node_active_particles);
+                            Some(node_active_particles)
+                        } else {
+                            None
+                        };
+
                         // Take the thread local mesh and append to it without clearing
-                        let mut node_mesh = std::mem::take(&mut tl_workspace.mesh);
+                        let mut node_mesh = if collect_leaf_meshes {
+                            TriMesh3d::default()
+                        } else {
+                            std::mem::take(&mut tl_workspace.mesh)
+                        };
+                        let mut node_triangle_cells = record_origins.then(Vec::new);
 
                         reconstruct_single_surface_append(
                             &mut *tl_workspace,
-                            &self.grid,
-                            Some(&subdomain_grid),
-                            node_particle_positions.as_slice(),
-                            node_particle_densities.as_ref().map(|v| v.as_slice()),
-                            &self.parameters,
+                            SingleSurfaceInput {
+                                grid: &self.grid,
+                                subdomain_grid: Some(&subdomain_grid),
+                                particle_positions: node_particle_positions.as_slice(),
+                                particle_densities: node_particle_densities
+                                    .as_ref()
+                                    .map(|v| v.as_slice()),
+                                active_particles: node_active_particles
+                                    .as_ref()
+                                    .map(|v| v.as_slice()),
+                                parameters: &self.parameters,
+                                previous_density_map: None,
+                            },
                             &mut node_mesh,
+                            node_triangle_cells.as_mut(),
+                            &clamped_density_values,
                         )?;
 
                         trace!("Surface patch successfully processed.");
 
                         // Put back everything taken from the workspace
                         tl_workspace.particle_positions = node_particle_positions;
-                        tl_workspace.mesh = node_mesh;
+                        if collect_leaf_meshes {
+                            let node_origins = node_triangle_cells.map(|cells| TriangleOrigins {
+                                nodes: vec![octree_node.id(); cells.len()],
+                                cells,
+                            });
+                            leaf_meshes.lock().push((*octree_node.min_corner().index(), node_mesh, node_origins));
+                        } else {
+                            tl_workspace.mesh = node_mesh;
+                        }
                         if let Some(node_particle_densities) = node_particle_densities {
                             tl_workspace.particle_densities = node_particle_densities;
                         }
+                        if let Some(node_active_particles) = node_active_particles {
+                            tl_workspace.active_particles = node_active_particles;
+                        }
 
+                        if let (Some(leaf_timings), Some(leaf_start)) = (&leaf_timings, leaf_start) {
+                            leaf_timings.lock().push(LeafTiming {
+                                node_id: octree_node.id(),
+                                num_particles: particles.len(),
+                                duration: leaf_start.elapsed(),
+                            });
+                        }
+                        if let Some(progress) = &progress {
+                            progress.add(1);
+                        }
                         Ok(())
                     }
                 })?;
+
+            output_surface.stats.leaf_timings =
+                leaf_timings.map(|leaf_timings| LeafTimings::new(leaf_timings.into_inner()));
+            output_surface.stats.warnings.clamped_density_values =
+                clamped_density_values.into_inner();
         };
 
         // Append all thread local meshes to global mesh
         {
+            let global_mesh = Arc::make_mut(&mut output_surface.mesh);
+            output_surface.workspace.prime_mesh(global_mesh);
             let tl_workspaces = &mut output_surface.workspace;
             tl_workspaces.local_workspaces_mut().iter_mut().fold(
-                &mut output_surface.mesh,
+                global_mesh,
                 |global_mesh, local_workspace| {
                     global_mesh.append(&mut local_workspace.borrow_mut().mesh);
                     global_mesh
@@ -425,6 +908,20 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
             );
         }
 
+        // Append the leaf meshes ordered by the position of the leaves
+        {
+            let mut leaf_meshes = leaf_meshes.into_inner();
+            leaf_meshes.sort_unstable_by_key(|(min_corner, _, _)| *min_corner);
+            for (_, mut leaf_mesh, leaf_origins) in leaf_meshes {
+                Arc::make_mut(&mut output_surface.mesh).append(&mut leaf_mesh);
+                if let (Some(origins), Some(mut leaf_origins)) =
+                    (output_surface.triangle_origins.as_mut(), leaf_origins)
+                {
+                    origins.append(&mut leaf_origins);
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -433,9 +930,11 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
         &self,
         global_particle_positions: &[Vector3<R>],
         global_particle_densities: Option<&[R]>,
+        global_splash_mask: Option<&[bool]>,
         output_surface: &mut SurfaceReconstruction<I, R>,
     ) -> Result<(), ReconstructionError<I, R>> {
         let mut octree = self.octree.clone();
+        let record_origins = output_surface.record_triangle_origins;
 
         // Perform individual surface reconstructions on all non-empty leaves of the octree
         {
@@ -445,29 +944,47 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
                 parent_scope,
                 "parallel domain decomposed surf. rec. with stitching"
             );
+            #[cfg(feature = "tracing")]
+            let parent_span = tracing::Span::current();
             info!("Starting triangulation of surface patches.");
+            let progress = ProgressLogger::start("octree leaves", "leaves", || self.num_leaves());
+            // Per-leaf timings are only recorded if statistics are collected
+            let leaf_timings = timings::is_attached().then(|| Mutex::new(Vec::new()));
+            let clamped_density_values = AtomicUsize::new(0);
+            // Process subtrees with only a few particles sequentially to reduce the task overhead
+            let min_task_size = ParallelPolicy::default().min_task_size;
+            let is_small_subtree =
+                |octree_node: &OctreeNode<I, R>| octree_node.is_small_subtree(min_task_size);
 
             octree
                 .root_mut()
                 // Use DFS visitation as we can only start stitching after all child nodes of one node are reconstructed/stitched.
-                .try_par_visit_mut_dfs_post(|octree_node: &mut OctreeNode<I, R>| -> Result<(), ReconstructionError<I, R>> {
+                .try_par_visit_mut_dfs_post_with_granularity(is_small_subtree, |octree_node: &mut OctreeNode<I, R>| -> Result<(), ReconstructionError<I, R>> {
                     profile!("visit octree node (reconstruct or stitch)", parent = parent_scope);
+                    #[cfg(feature = "tracing")]
+                    let _node_span = enter_octree_node_span!(&parent_span, "visit octree node (reconstruct or stitch)", octree_node);
+                    self.cancellation_token.check()?;
 
                     // Extract the set of particles of the current node
                     let particles = if let Some(particle_set) = octree_node.data().particle_set() {
                         &particle_set.particles
                     } else {
                         // If node has no particle set, its children were already processed so it can be stitched
-                        octree_node.stitch_surface_patches(self.parameters.iso_surface_threshold)?;
+                        octree_node.stitch_surface_patches(
+                            self.parameters.iso_surface_threshold,
+                            self.parameters.triangulation_scheme,
+                        )?;
                         // After stitching we can directly continue visting the next node
                         return Ok(());
                     };
 
                     trace!("Processing octree leaf with {} particles", particles.len());
 
-                    let subdomain_grid = self.extract_node_subdomain(octree_node);
+                    let num_particles = particles.len();
+                    let leaf_start = (leaf_timings.is_some() && num_particles > 0).then(Instant::now);
+                    let subdomain_grid = self.extract_node_subdomain(octree_node)?;
                     let surface_patch = if particles.is_empty() {
-                        SurfacePatch::new_empty(subdomain_grid)
+                        SurfacePatch::new_empty(subdomain_grid, record_origins)
                     } else {
                         debug!(
                             "Reconstructing surface of local patch with {} particles. (offset: {:?}, cells_per_dim: {:?})",
@@ -477,8 +994,7 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
                         );
 
                         let mut tl_workspace = tl_workspaces
-                            .get_local_with_capacity(particles.len())
-                            .borrow_mut();
+                            .get_local_for_leaf(particles.len());
 
                         // Take particle position storage from workspace and fill it with positions of the leaf
                         let mut node_particle_positions = std::mem::take(&mut tl_workspace.particle_positions);
@@ -493,12 +1009,32 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
                             None
                         };
 
+                        // Take active particle storage from workspace and fill it with all non-splash particles of the leaf
+                        let node_active_particles = if let Some(global_splash_mask) = global_splash_mask {
+                            let mut node_active_particles = std::mem::take(&mut tl_workspace.active_particles);
+                            Self::collect_node_active_particles(particles, global_splash_mask, &mut node_active_particles);
+                            Some(node_active_particles)
+                        } else {
+                            None
+                        };
+
                         let surface_patch = reconstruct_surface_patch(
                             &mut *tl_workspace,
-                            &subdomain_grid,
-                            node_particle_positions.as_slice(),
-                            node_particle_densities.as_ref().map(|v| v.as_slice()),
-                            &self.parameters,
+                            SingleSurfaceInput {
+                                grid: &self.grid,
+                                subdomain_grid: Some(&subdomain_grid),
+                                particle_positions: node_particle_positions.as_slice(),
+                                particle_densities: node_particle_densities
+                                    .as_ref()
+                                    .map(|v| v.as_slice()),
+                                active_particles: node_active_particles
+                                    .as_ref()
+                                    .map(|v| v.as_slice()),
+                                parameters: &self.parameters,
+                                previous_density_map: None,
+                            },
+                            record_origins.then(|| octree_node.id()),
+                            &clamped_density_values,
                         );
 
                         // Put back everything taken from the workspace
@@ -506,49 +1042,136 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
                         if let Some(node_particle_densities) = node_particle_densities {
                             tl_workspace.particle_densities = node_particle_densities;
                         }
+                        if let Some(node_active_particles) = node_active_particles {
+                            tl_workspace.active_particles = node_active_particles;
+                        }
 
                         surface_patch?
                     };
 
                     trace!("Surface patch successfully processed.");
 
+                    if let (Some(leaf_timings), Some(leaf_start)) = (&leaf_timings, leaf_start) {
+                        leaf_timings.lock().push(LeafTiming {
+                            node_id: octree_node.id(),
+                            num_particles,
+                            duration: leaf_start.elapsed(),
+                        });
+                    }
+
                     // Store triangulation in the leaf
                     octree_node
                         .data_mut()
                         .replace(NodeData::SurfacePatch(surface_patch.into()));
 
+                    if let Some(progress) = &progress {
+                        progress.add(1);
+                    }
                     Ok(())
                 })?;
 
+            output_surface.stats.leaf_timings =
+                leaf_timings.map(|leaf_timings| LeafTimings::new(leaf_timings.into_inner()));
+            output_surface.stats.warnings.clamped_density_values =
+                clamped_density_values.into_inner();
             info!("Generation of surface patches is done.");
         };
 
         // Move stitched mesh out of octree
         {
-            let surface_path = octree
+            let mut surface_path = octree
                 .root_mut()
                 .data_mut()
                 .take()
                 .into_surface_patch()
-                .expect("Cannot extract stitched mesh from root node")
+                .ok_or(OctreeError::MissingStitchedPatch)?
                 .patch;
-            output_surface.mesh = surface_path.mesh;
+
+            // The vertices can only be snapped when all triangles around the boundaries of the patches are stitched
+            if let Some(snapping) = self.parameters.vertex_snapping {
+                let kept_triangles = marching_cubes::snap_vertices_to_grid(
+                    &self.grid,
+                    snapping,
+                    &mut surface_path.mesh,
+                    0,
+                    0,
+                );
+                if let Some(origins) = surface_path.origins.as_mut() {
+                    marching_cubes::retain_appended(&mut origins.cells, &kept_triangles);
+                    marching_cubes::retain_appended(&mut origins.nodes, &kept_triangles);
+                }
+            }
+
+            let mut stitched_mesh = surface_path.mesh;
+            if output_surface.append_to_mesh {
+                Arc::make_mut(&mut output_surface.mesh).append(&mut stitched_mesh);
+            } else {
+                output_surface.mesh = Arc::new(stitched_mesh);
+            }
+            if let (Some(origins), Some(mut stitched_origins)) = (
+                output_surface.triangle_origins.as_mut(),
+                surface_path.origins,
+            ) {
+                origins.append(&mut stitched_origins);
+            }
         }
 
         Ok(())
     }
 
+    /// Returns the number of leaves whose ghost particle margin reaches beyond the domain and contains skipped particles
+    fn count_truncated_ghost_margins(&self, global_particle_positions: &[Vector3<R>]) -> usize {
+        let allowed_domain = density_map::allowed_particle_domain(
+            &self.grid,
+            density_map::compute_kernel_evaluation_radius::<I, R>(
+                self.parameters.compact_support_radius,
+                self.parameters.cube_size,
+            )
+            .kernel_evaluation_radius,
+        );
+
+        self.octree
+            .root()
+            .dfs_iter()
+            .filter_map(|node| {
+                node.data()
+                    .particle_set()
+                    .map(|particle_set| (node, &particle_set.particles))
+            })
+            .filter(|(node, particles)| {
+                let mut ghost_aabb = node.aabb().clone();
+                ghost_aabb.grow_uniformly(self.ghost_margin);
+                !self.grid.aabb().contains_aabb(&ghost_aabb)
+                    && particles
+                        .iter()
+                        .any(|&i| !allowed_domain.contains_point(&global_particle_positions[i]))
+            })
+            .count()
+    }
+
+    /// Returns the number of leaves of the octree
+    fn num_leaves(&self) -> usize {
+        self.octree.root().leaf_count()
+    }
+
     /// Computes the subdomain grid for the given octree node
-    fn extract_node_subdomain(&self, octree_node: &OctreeNode<I, R>) -> OwningSubdomainGrid<I, R> {
+    fn extract_node_subdomain(
+        &self,
+        octree_node: &OctreeNode<I, R>,
+    ) -> Result<OwningSubdomainGrid<I, R>, OctreeError<I, R>> {
         let grid = &self.grid;
 
         let subdomain_grid = octree_node
             .grid(octree_node.aabb().min(), grid.cell_size())
-            .expect("Unable to construct Octree node grid");
+            .map_err(OctreeError::NodeGridConstruction)?;
         let subdomain_offset = octree_node.min_corner();
         subdomain_grid.log_grid_info();
 
-        OwningSubdomainGrid::new(grid.clone(), subdomain_grid, *subdomain_offset.index())
+        Ok(OwningSubdomainGrid::new(
+            grid.clone(),
+            subdomain_grid,
+            *subdomain_offset.index(),
+        ))
     }
 
     /// Collects the particle positions of all particles in the node
@@ -584,6 +1207,653 @@ impl<I: Index, R: Real> OctreeBasedSurfaceReconstruction<I, R> {
                 .map(|&idx| global_particle_densities[idx]),
         );
     }
+
+    /// Collects the local indices of all particles in the node that are not classified as splash particles
+    fn collect_node_active_particles(
+        node_particles: &[usize],
+        global_splash_mask: &[bool],
+        node_active_particles: &mut Vec<usize>,
+    ) {
+        node_active_particles.clear();
+        utils::reserve_total(node_active_particles, node_particles.len());
+
+        // Extract the local indices of all non-splash particles of the leaf
+        node_active_particles.extend(
+            node_particles
+                .iter()
+                .enumerate()
+                .filter(|(_, &idx)| !global_splash_mask[idx])
+                .map(|(local_idx, _)| local_idx),
+        );
+    }
+}
+
+/// Classifies all particles without any neighbors within the splash detection radius as splash particles, stores their sorted indices in the given vector
+pub(crate) fn classify_splash_particles<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    particle_positions: &[Vector3<R>],
+    splash_detection_radius: R,
+    enable_multi_threading: bool,
+    particle_neighbor_lists: &mut Vec<Vec<usize>>,
+    splash_particles: &mut Vec<usize>,
+) {
+    profile!("classify_splash_particles");
+
+    trace!("Starting neighborhood search for splash detection...");
+    neighborhood_search::search_inplace::<I, R>(
+        &grid.aabb(),
+        particle_positions,
+        splash_detection_radius,
+        enable_multi_threading,
+        particle_neighbor_lists,
+    );
+
+    splash_particles.clear();
+    splash_particles.extend(
+        particle_neighbor_lists
+            .iter()
+            .enumerate()
+            .filter(|(_, neighbors)| neighbors.is_empty())
+            .map(|(i, _)| i),
+    );
+
+    info!(
+        "Classified {} of {} particles as splash particles.",
+        splash_particles.len(),
+        particle_positions.len()
+    );
+}
+
+/// Classifies all particles with a density below the given threshold as splash particles, stores their sorted indices in the given vector
+pub(crate) fn classify_splash_particles_by_density<R: Real>(
+    particle_densities: &[R],
+    density_threshold: R,
+    splash_particles: &mut Vec<usize>,
+) {
+    profile!("classify_splash_particles_by_density");
+
+    splash_particles.clear();
+    splash_particles.extend(
+        particle_densities
+            .iter()
+            .enumerate()
+            .filter(|(_, &density)| density < density_threshold)
+            .map(|(i, _)| i),
+    );
+
+    info!(
+        "Classified {} of {} particles as splash particles.",
+        splash_particles.len(),
+        particle_densities.len()
+    );
+}
+
+/// Returns the density below which particles are classified as splash particles, i.e. the given factor times the density of an isolated particle
+fn splash_density_threshold<R: Real>(parameters: &Parameters<R>, factor: R) -> R {
+    let particle_rest_density = parameters.rest_density;
+    let particle_rest_volume = R::from_f64((4.0 / 3.0) * std::f64::consts::PI).unwrap()
+        * parameters.particle_radius.powi(3);
+    let particle_rest_mass = particle_rest_volume * particle_rest_density;
+
+    density_map::compute_isolated_particle_density(
+        parameters.compact_support_radius,
+        particle_rest_mass,
+    ) * factor
+}
+
+/// Generates one sphere per splash particle and adds them to the output according to the selected splash output mode
+pub(crate) fn generate_splash_spheres<I: Index, R: Real>(
+    particle_positions: &[Vector3<R>],
+    parameters: &Parameters<R>,
+    output_surface: &mut SurfaceReconstruction<I, R>,
+) {
+    let (sphere_subdivisions, merge_spheres) = match parameters.splash_particle_output {
+        SplashOutput::SeparateMesh {
+            sphere_subdivisions,
+        } => (sphere_subdivisions, false),
+        SplashOutput::MergedSpheres {
+            sphere_subdivisions,
+        } => (sphere_subdivisions, true),
+        SplashOutput::Ignore => {
+            output_surface.splash_mesh = None;
+            output_surface.splash_vertex_particle_indices = None;
+            return;
+        }
+    };
+
+    let splash_particles = if let Some(splash_particles) = &output_surface.splash_particle_indices {
+        splash_particles
+    } else {
+        // Nothing to do if splash detection is disabled
+        output_surface.splash_mesh = None;
+        output_surface.splash_vertex_particle_indices = None;
+        return;
+    };
+
+    profile!("generate_splash_spheres");
+
+    let sphere = TriMesh3d::icosphere(
+        &Vector3::zeros(),
+        parameters.particle_radius,
+        sphere_subdivisions,
+    );
+
+    // Reuse allocated memory of the previous reconstruction
+    let mut splash_mesh = output_surface.splash_mesh.take().unwrap_or_default();
+    splash_mesh.clear();
+    let mut vertex_particle_indices = output_surface
+        .splash_vertex_particle_indices
+        .take()
+        .unwrap_or_default();
+    vertex_particle_indices.clear();
+
+    utils::reserve_total(
+        &mut splash_mesh.vertices,
+        splash_particles.len() * sphere.vertices.len(),
+    );
+    utils::reserve_total(
+        &mut splash_mesh.triangles,
+        splash_particles.len() * sphere.triangles.len(),
+    );
+    utils::reserve_total(
+        &mut vertex_particle_indices,
+        splash_particles.len() * sphere.vertices.len(),
+    );
+
+    for &particle_idx in splash_particles {
+        let center = &particle_positions[particle_idx];
+        let vertex_offset = splash_mesh.vertices.len();
+
+        splash_mesh
+            .vertices
+            .extend(sphere.vertices.iter().map(|v| v + center));
+        splash_mesh
+            .triangles
+            .extend(sphere.triangles.iter().map(|tri| {
+                [
+                    tri[0] + vertex_offset,
+                    tri[1] + vertex_offset,
+                    tri[2] + vertex_offset,
+                ]
+            }));
+        vertex_particle_indices.extend(std::iter::repeat(particle_idx).take(sphere.vertices.len()));
+    }
+
+    info!(
+        "Generated {} spheres with {} triangles and {} vertices for splash particles.",
+        splash_particles.len(),
+        splash_mesh.triangles.len(),
+        splash_mesh.vertices.len()
+    );
+
+    if merge_spheres {
+        Arc::make_mut(&mut output_surface.mesh).append(&mut splash_mesh);
+        output_surface.splash_mesh = None;
+    } else {
+        output_surface.splash_mesh = Some(splash_mesh);
+    }
+    output_surface.splash_vertex_particle_indices = Some(vertex_particle_indices);
+}
+
+/// Computes the `mc_density` and `sph_density` attributes of the vertices, see [`AttributeSpecs::density_attributes`]
+///
+/// Both values are evaluated from the same particles that contributed to the density map of the reconstruction,
+/// i.e. without splash particles, clipped particles and particles outside of the allowed domain of the grid.
+fn compute_density_attributes<I: Index, R: Real>(
+    reconstruction: &SurfaceReconstruction<I, R>,
+    particle_positions: &[Vector3<R>],
+    particle_densities: &[R],
+    particle_rest_mass: R,
+    parameters: &Parameters<R>,
+    surface_vertices: &[Vector3<R>],
+    merged_splash_vertices: &[Vector3<R>],
+) -> (Vec<R>, Vec<R>) {
+    profile!("compute_density_attributes");
+
+    let num_vertices = surface_vertices.len() + merged_splash_vertices.len();
+    if surface_vertices.is_empty() {
+        return (
+            vec![R::from_f64(f64::NAN).unwrap(); num_vertices],
+            vec![R::zero(); num_vertices],
+        );
+    }
+
+    let grid = reconstruction.grid();
+    let compact_support_radius = parameters.compact_support_radius;
+
+    // Collect the particles that contributed to the density map
+    let active_particles = reconstruction
+        .splash_particle_indices()
+        .map(|splash_particles| {
+            let mut active_particles = Vec::new();
+            collect_active_particles(
+                particle_positions.len(),
+                splash_particles,
+                &mut active_particles,
+            );
+            active_particles
+        });
+    let active_particles =
+        collect_unclipped_particles(particle_positions, active_particles.as_deref(), parameters)
+            .or(active_particles)
+            .unwrap_or_else(|| (0..particle_positions.len()).collect());
+    let allowed_domain = density_map::allowed_particle_domain(
+        grid,
+        density_map::compute_kernel_evaluation_radius::<I, R>(
+            compact_support_radius,
+            parameters.cube_size,
+        )
+        .kernel_evaluation_radius,
+    );
+    let (positions, densities): (Vec<_>, Vec<_>) = active_particles
+        .into_iter()
+        .filter(|&i| allowed_domain.contains_point(&particle_positions[i]))
+        .map(|i| (particle_positions[i], particle_densities[i]))
+        .unzip();
+
+    // The SPH interpolation of the density map field (sum of the kernels weighted by the particle volumes) at the vertices
+    let interpolator = SphInterpolator::new(
+        &positions,
+        &densities,
+        particle_rest_mass,
+        compact_support_radius,
+    );
+    let mut sph_densities = interpolator.interpolate_scalar_quantity(
+        &vec![R::one(); positions.len()],
+        surface_vertices,
+        false,
+    );
+    sph_densities.extend(interpolator.interpolate_scalar_quantity(
+        &vec![R::one(); positions.len()],
+        merged_splash_vertices,
+        false,
+    ));
+
+    // Evaluates the density map at a grid point in the same way as the density map generation
+    let kernel = DiscreteSquaredDistanceCubicKernel::new::<f64>(1000, compact_support_radius);
+    let neighborhood = SpatialHashingNeighborhoodProvider::new(&positions, compact_support_radius);
+    let density_map_value = |x: &Vector3<R>| {
+        let mut value = R::zero();
+        neighborhood.visit_particles_within(x, compact_support_radius, &mut |j| {
+            value += particle_rest_mass / densities[j]
+                * kernel.evaluate((positions[j] - x).norm_squared());
+        });
+        density_map::clip_density_value(
+            x,
+            value,
+            &parameters.clipping_planes,
+            parameters.iso_surface_threshold,
+            parameters.cube_size,
+        )
+    };
+
+    let grid_min = grid.aabb().min();
+    let cell_size = grid.cell_size();
+    let mut mc_densities = Vec::with_capacity(num_vertices);
+    utils::map_collect_into_vec(
+        surface_vertices,
+        |vertex| {
+            // Two coordinates of a vertex coincide with grid points, the remaining one is between the two end points of the edge
+            let grid_coordinates = (vertex - grid_min).unscale(cell_size);
+            let distance_to_grid_point = |c: R| (c - c.round()).abs();
+            let edge_axis = (0..3)
+                .max_by(|&a, &b| {
+                    distance_to_grid_point(grid_coordinates[a])
+                        .partial_cmp(&distance_to_grid_point(grid_coordinates[b]))
+                        .unwrap()
+                })
+                .unwrap();
+
+            let mut edge_start = grid_coordinates.map(|c| c.round());
+            edge_start[edge_axis] = grid_coordinates[edge_axis].floor();
+            let alpha = grid_coordinates[edge_axis] - edge_start[edge_axis];
+            let mut edge_end = edge_start;
+            edge_end[edge_axis] += R::one();
+
+            let [start_value, end_value] = [edge_start, edge_end].map(|point| {
+                density_map_value(
+                    &grid
+                        .point_coordinates_array(&[0, 1, 2].map(|d| point[d].to_index_unchecked())),
+                )
+            });
+            start_value * (R::one() - alpha) + end_value * alpha
+        },
+        &mut mc_densities,
+    );
+    // The splash particle spheres are not extracted by marching cubes
+    mc_densities.resize(num_vertices, R::from_f64(f64::NAN).unwrap());
+
+    (mc_densities, sph_densities)
+}
+
+/// Collects the indices of all particles that are not part of the given sorted list of splash particles
+fn collect_active_particles(
+    num_particles: usize,
+    splash_particles: &[usize],
+    active_particles: &mut Vec<usize>,
+) {
+    active_particles.clear();
+    utils::reserve_total(active_particles, num_particles - splash_particles.len());
+
+    let mut splash_iter = splash_particles.iter().copied().peekable();
+    for i in 0..num_particles {
+        if splash_iter.peek() == Some(&i) {
+            splash_iter.next();
+        } else {
+            active_particles.push(i);
+        }
+    }
+}
+
+/// Assembles the reconstructed surface mesh with all attributes selected by the given specification
+pub(crate) fn compute_surface_attributes<I: Index, R: Real>(
+    reconstruction: &SurfaceReconstruction<I, R>,
+    particle_positions: &[Vector3<R>],
+    parameters: &Parameters<R>,
+    attribute_specs: &AttributeSpecs<R>,
+) -> Result<MeshWithData<R, TriMesh3d<R>>, ReconstructionError<I, R>> {
+    profile!("compute_surface_attributes");
+
+    let mesh = reconstruction.mesh();
+    let mut mesh_with_data = MeshWithData::new(mesh.clone());
+
+    let interpolation_methods = attribute_specs.particle_attribute_interpolation;
+    if !interpolation_methods.is_empty()
+        && interpolation_methods.len() != attribute_specs.particle_attributes.len()
+    {
+        return Err(AttributeError::InterpolationMismatch {
+            len: interpolation_methods.len(),
+            expected: attribute_specs.particle_attributes.len(),
+        }
+        .into());
+    }
+    let interpolation_of = |attribute_index: usize| {
+        interpolation_methods
+            .get(attribute_index)
+            .copied()
+            .unwrap_or(AttributeInterpolation::Sph)
+    };
+
+    for (i, attribute) in attribute_specs.particle_attributes.iter().enumerate() {
+        let num_values = match (&attribute.data, interpolation_of(i)) {
+            (AttributeData::ScalarReal(values), _) => values.len(),
+            (AttributeData::Vector3Real(values), _) => values.len(),
+            (
+                AttributeData::VectorReal {
+                    num_components: 0, ..
+                },
+                _,
+            ) => return Err(AttributeError::UnsupportedType(attribute.name.clone()).into()),
+            (
+                AttributeData::VectorReal {
+                    num_components,
+                    values,
+                },
+                _,
+            ) => values.len().div_ceil(*num_components),
+            (AttributeData::ScalarU64(values), AttributeInterpolation::Nearest) => values.len(),
+            (AttributeData::ScalarU64(_), AttributeInterpolation::Sph) => {
+                return Err(AttributeError::UnsupportedType(attribute.name.clone()).into())
+            }
+        };
+        if num_values != particle_positions.len() {
+            return Err(AttributeError::LengthMismatch {
+                name: attribute.name.clone(),
+                len: num_values,
+                expected: particle_positions.len(),
+            }
+            .into());
+        }
+    }
+
+    // Without a separate splash mesh, the splash particle spheres are merged into the end of the surface mesh
+    let merged_splash_vertex_particles = match (
+        reconstruction.splash_vertex_particle_indices(),
+        reconstruction.splash_mesh(),
+    ) {
+        (Some(vertex_particles), None) => vertex_particles.as_slice(),
+        _ => &[],
+    };
+    let (surface_vertices, merged_splash_vertices) = mesh
+        .vertices
+        .split_at(mesh.vertices.len() - merged_splash_vertex_particles.len());
+
+    let requires_interpolation = matches!(
+        attribute_specs.normals,
+        Some(NormalsComputation::SphInterpolation)
+    ) || attribute_specs.vertex_densities
+        || !attribute_specs.particle_attributes.is_empty();
+
+    let requires_densities = requires_interpolation || attribute_specs.density_attributes;
+
+    // Densities are not returned by all reconstruction strategies, compute them if necessary
+    let mut computed_densities = Vec::new();
+    let particle_densities = match reconstruction.particle_densities() {
+        Some(particle_densities) => particle_densities.as_slice(),
+        None if requires_densities => {
+            compute_particle_densities_and_neighbors(
+                reconstruction.grid(),
+                particle_positions,
+                parameters,
+                &mut Vec::new(),
+                &mut computed_densities,
+            );
+            computed_densities.as_slice()
+        }
+        None => &[],
+    };
+    if requires_densities {
+        assert_eq!(
+            particle_positions.len(),
+            particle_densities.len(),
+            "There has to be one density value per particle"
+        );
+    }
+
+    let particle_rest_volume = R::from_f64((4.0 / 3.0) * std::f64::consts::PI).unwrap()
+        * parameters.particle_radius.powi(3);
+    let particle_rest_mass = particle_rest_volume * parameters.rest_density;
+
+    if requires_interpolation {
+        info!(
+            "Constructing global acceleration structure for SPH interpolation to {} vertices...",
+            surface_vertices.len()
+        );
+
+        let interpolator = SphInterpolator::new(
+            particle_positions,
+            particle_densities,
+            particle_rest_mass,
+            parameters.compact_support_radius,
+        );
+
+        if let Some(NormalsComputation::SphInterpolation) = attribute_specs.normals {
+            let mut normals: Vec<Vector3<R>> = interpolator
+                .interpolate_normals(surface_vertices)
+                .into_iter()
+                .map(|n| n.into_inner())
+                .collect();
+            // Use the exact normals for the merged splash particle spheres
+            normals.extend(
+                merged_splash_vertices
+                    .iter()
+                    .zip(merged_splash_vertex_particles.iter())
+                    .map(|(v, &particle_idx)| (v - particle_positions[particle_idx]).normalize()),
+            );
+            mesh_with_data
+                .point_attributes
+                .push(MeshAttribute::new_real_vector3("normals", normals));
+        }
+
+        if attribute_specs.vertex_densities {
+            let mut densities = interpolator.interpolate_scalar_quantity(
+                particle_densities,
+                surface_vertices,
+                true,
+            );
+            densities.extend(
+                merged_splash_vertex_particles
+                    .iter()
+                    .map(|&particle_idx| particle_densities[particle_idx]),
+            );
+            mesh_with_data
+                .point_attributes
+                .push(MeshAttribute::new_real_scalar("density", densities));
+        }
+
+        // Particle nearest to every vertex, only computed if any attribute is transferred from the nearest particles
+        let nearest_particles = (0..attribute_specs.particle_attributes.len())
+            .any(|i| interpolation_of(i) == AttributeInterpolation::Nearest)
+            .then(|| {
+                let mut nearest_particles = interpolator.nearest_particles(surface_vertices);
+                nearest_particles.extend_from_slice(merged_splash_vertex_particles);
+                nearest_particles
+            });
+
+        // Vertices without particles in their support get SPH interpolated values of zero
+        let any_sph_attribute = (0..attribute_specs.particle_attributes.len())
+            .any(|i| interpolation_of(i) == AttributeInterpolation::Sph);
+        if attribute_specs.vertex_densities || any_sph_attribute {
+            let vertices_without_particles =
+                interpolator.num_points_without_particles(surface_vertices);
+            if vertices_without_particles > 0 {
+                warn!(
+                    "{} of {} vertices have no particles within the compact support radius, their interpolated attributes are set to zero",
+                    vertices_without_particles,
+                    surface_vertices.len()
+                );
+            }
+        }
+
+        // The merged splash particle spheres get the values of their originating particles
+        for (i, attribute) in attribute_specs.particle_attributes.iter().enumerate() {
+            let data = match (interpolation_of(i), &attribute.data) {
+                (AttributeInterpolation::Nearest, data) => {
+                    info!(
+                        "Transferring attribute \"{}\" from the nearest particles...",
+                        attribute.name
+                    );
+                    data.select(nearest_particles.as_deref().unwrap_or(&[]))
+                }
+                (AttributeInterpolation::Sph, data) => {
+                    info!("Interpolating attribute \"{}\"...", attribute.name);
+                    let mut interpolated = interpolator
+                        .interpolate_attribute(attribute, surface_vertices)?
+                        .data;
+                    interpolated.append(&mut data.select(merged_splash_vertex_particles));
+                    interpolated
+                }
+            };
+            mesh_with_data
+                .point_attributes
+                .push(MeshAttribute::new(attribute.name.clone(), data));
+        }
+    }
+
+    if attribute_specs.density_attributes {
+        let (mc_densities, sph_densities) = compute_density_attributes(
+            reconstruction,
+            particle_positions,
+            particle_densities,
+            particle_rest_mass,
+            parameters,
+            surface_vertices,
+            merged_splash_vertices,
+        );
+        mesh_with_data
+            .point_attributes
+            .push(MeshAttribute::new_real_scalar("mc_density", mc_densities));
+        mesh_with_data
+            .point_attributes
+            .push(MeshAttribute::new_real_scalar("sph_density", sph_densities));
+    }
+
+    if let Some(NormalsComputation::AreaWeighted) = attribute_specs.normals {
+        #[cfg(feature = "parallel")]
+        let normals = if parameters.enable_multi_threading {
+            mesh.par_vertex_normals()
+        } else {
+            mesh.vertex_normals()
+        };
+        #[cfg(not(feature = "parallel"))]
+        let normals = mesh.vertex_normals();
+        mesh_with_data
+            .point_attributes
+            .push(MeshAttribute::new_real_vector3("normals", normals));
+    }
+
+    if attribute_specs.triangle_cell_ids || attribute_specs.triangle_leaf_ids {
+        let grid = reconstruction.grid();
+        // Background grid cells containing the centroids of the triangles
+        let triangle_cells: Vec<_> = mesh
+            .triangles
+            .iter()
+            .map(|tri| {
+                let centroid =
+                    (mesh.vertices[tri[0]] + mesh.vertices[tri[1]] + mesh.vertices[tri[2]])
+                        / R::from_f64(3.0).unwrap();
+                grid.get_enclosing_cell(&centroid)
+            })
+            .collect();
+
+        if attribute_specs.triangle_cell_ids {
+            let cell_ids: Vec<u64> = triangle_cells
+                .iter()
+                .map(|cell| {
+                    cell.as_ref()
+                        .and_then(|cell| grid.flatten_cell_index(cell).to_u64())
+                        .unwrap_or(u64::MAX)
+                })
+                .collect();
+            mesh_with_data
+                .cell_attributes
+                .push(MeshAttribute::new("cell_id", cell_ids));
+        }
+
+        if let (true, Some(octree)) = (attribute_specs.triangle_leaf_ids, reconstruction.octree()) {
+            let leaf_ids: Vec<u64> = triangle_cells
+                .iter()
+                .map(|cell| {
+                    cell.as_ref()
+                        .and_then(|cell| find_leaf_containing_cell(octree.root(), cell.index()))
+                        .map(|leaf_id| leaf_id as u64)
+                        .unwrap_or(u64::MAX)
+                })
+                .collect();
+            mesh_with_data
+                .cell_attributes
+                .push(MeshAttribute::new("leaf_id", leaf_ids));
+        }
+    }
+
+    Ok(mesh_with_data)
+}
+
+/// Returns the id of the octree leaf containing the background grid cell with the given index
+fn find_leaf_containing_cell<I: Index, R: Real>(
+    node: &OctreeNode<I, R>,
+    cell: &[I; 3],
+) -> Option<usize> {
+    let contains_cell = |node: &OctreeNode<I, R>| {
+        let min = node.min_corner().index();
+        let max = node.max_corner().index();
+        (0..3).all(|i| min[i] <= cell[i] && cell[i] < max[i])
+    };
+
+    let leaf = node.visit_dfs_controlled(|node| {
+        if !contains_cell(node) {
+            ControlFlow::Continue(Descend::No)
+        } else if node.children().is_empty() {
+            ControlFlow::Break(node.id())
+        } else {
+            ControlFlow::Continue(Descend::Yes)
+        }
+    });
+
+    match leaf {
+        ControlFlow::Break(leaf_id) => Some(leaf_id),
+        ControlFlow::Continue(()) => None,
+    }
 }
 
 /// Computes per particle densities into the workspace, also performs the required neighborhood search
@@ -610,6 +1880,11 @@ pub(crate) fn compute_particle_densities_and_neighbors<I: Index, R: Real>(
         particle_neighbor_lists,
     );
 
+    // The order of the neighbors found by the parallel search depends on the scheduling of the threads
+    if parameters.deterministic {
+        sort_neighbor_lists(particle_neighbor_lists, parameters.enable_multi_threading);
+    }
+
     trace!("Computing particle densities...");
     density_map::compute_particle_densities_inplace::<I, R>(
         particle_positions,
@@ -621,20 +1896,138 @@ pub(crate) fn compute_particle_densities_and_neighbors<I: Index, R: Real>(
     );
 }
 
-/// Reconstruct a surface, appends triangulation to the given mesh
-pub(crate) fn reconstruct_single_surface_append<'a, I: Index, R: Real>(
-    workspace: &mut LocalReconstructionWorkspace<I, R>,
+/// Sorts all neighbor lists by particle index, which makes the summation order of the particle densities independent of the neighborhood search
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+fn sort_neighbor_lists(particle_neighbor_lists: &mut [Vec<usize>], enable_multi_threading: bool) {
+    profile!("sort_neighbor_lists");
+
+    #[cfg(feature = "parallel")]
+    if enable_multi_threading {
+        particle_neighbor_lists
+            .par_iter_mut()
+            .for_each(|neighbors| neighbors.sort_unstable());
+        return;
+    }
+
+    particle_neighbor_lists
+        .iter_mut()
+        .for_each(|neighbors| neighbors.sort_unstable());
+}
+
+/// Generates the sparse density map of the (active) particles on the grid or the subdomain, replaces the content of the given map
+pub(crate) fn generate_density_map<I: Index, R: Real>(
     grid: &UniformGrid<I, R>,
     subdomain_grid: Option<&OwningSubdomainGrid<I, R>>,
     particle_positions: &[Vector3<R>],
-    particle_densities: Option<&[R]>,
+    particle_densities: &[R],
+    active_particles: Option<&[usize]>,
     parameters: &Parameters<R>,
-    output_mesh: &'a mut TriMesh3d<R>,
+    density_map: &mut DensityMap<I, R>,
 ) -> Result<(), ReconstructionError<I, R>> {
-    let particle_rest_density = parameters.rest_density;
     let particle_rest_volume = R::from_f64((4.0 / 3.0) * std::f64::consts::PI).unwrap()
         * parameters.particle_radius.powi(3);
-    let particle_rest_mass = particle_rest_volume * particle_rest_density;
+    let particle_rest_mass = particle_rest_volume * parameters.rest_density;
+
+    density_map::generate_sparse_density_map(
+        grid,
+        subdomain_grid,
+        particle_positions,
+        particle_densities,
+        active_particles,
+        particle_rest_mass,
+        parameters.compact_support_radius,
+        parameters.cube_size,
+        // The parallel generation merges thread local contributions in a non-deterministic order
+        parameters.enable_multi_threading && !parameters.deterministic,
+        density_map,
+    )?;
+    Ok(())
+}
+
+/// Returns the subset of the (active) particles that are not further than the compact support radius on the clipped side of any clipping plane, `None` if there are no clipping planes
+fn collect_unclipped_particles<R: Real>(
+    particle_positions: &[Vector3<R>],
+    active_particles: Option<&[usize]>,
+    parameters: &Parameters<R>,
+) -> Option<Vec<usize>> {
+    if parameters.clipping_planes.is_empty() {
+        return None;
+    }
+
+    let margin = parameters.compact_support_radius;
+    let is_unclipped = |i: &usize| {
+        parameters
+            .clipping_planes
+            .iter()
+            .all(|plane| plane.signed_distance(&particle_positions[*i]) > -margin)
+    };
+
+    Some(if let Some(active_particles) = active_particles {
+        active_particles
+            .iter()
+            .copied()
+            .filter(is_unclipped)
+            .collect()
+    } else {
+        (0..particle_positions.len()).filter(is_unclipped).collect()
+    })
+}
+
+/// Returns the density map of the previous frame on the grid of the current reconstruction, `None` if it cannot be resampled
+fn previous_density_map_on_grid<I: Index, R: Real>(
+    previous_grid: &UniformGrid<I, R>,
+    previous_density_map: DensityMap<I, R>,
+    grid: &UniformGrid<I, R>,
+) -> Option<DensityMap<I, R>> {
+    if previous_grid == grid {
+        return Some(previous_density_map);
+    }
+
+    let resampled = density_map::resample_density_map(previous_grid, &previous_density_map, grid);
+    if resampled.is_some() {
+        debug!("Resampled the density map of the previous frame to the changed background grid for temporal smoothing.");
+    } else {
+        info!("The cube size changed since the previous frame, resetting the temporal smoothing of the density map.");
+    }
+    resampled
+}
+
+/// Input of [`reconstruct_single_surface_append`] and [`reconstruct_surface_patch`], i.e. the particles of the global domain or of a subdomain
+pub(crate) struct SingleSurfaceInput<'a, I: Index, R: Real> {
+    /// Background grid of the reconstruction
+    pub grid: &'a UniformGrid<I, R>,
+    /// Grid of the subdomain, `None` for a global reconstruction
+    pub subdomain_grid: Option<&'a OwningSubdomainGrid<I, R>>,
+    pub particle_positions: &'a [Vector3<R>],
+    /// Densities of the particles, computed from the particle positions if not given
+    pub particle_densities: Option<&'a [R]>,
+    /// Indices of the particles that contribute to the density map, all particles contribute if not given
+    pub active_particles: Option<&'a [usize]>,
+    pub parameters: &'a Parameters<R>,
+    /// Density map of the previous frame that is blended with the density map for the temporal smoothing
+    pub previous_density_map: Option<&'a DensityMap<I, R>>,
+}
+
+/// Reconstruct a surface, appends triangulation to the given mesh and returns the triangulated (clipped and temporally smoothed) density map
+///
+/// If a previous density map is given, it is blended with the density map before triangulation using the
+/// factor of [`Parameters::temporal_density_smoothing`].
+pub(crate) fn reconstruct_single_surface_append<I: Index, R: Real>(
+    workspace: &mut LocalReconstructionWorkspace<I, R>,
+    input: SingleSurfaceInput<'_, I, R>,
+    output_mesh: &mut TriMesh3d<R>,
+    triangle_cells: Option<&mut Vec<I>>,
+    clamped_density_values: &AtomicUsize,
+) -> Result<DensityMap<I, R>, ReconstructionError<I, R>> {
+    let SingleSurfaceInput {
+        grid,
+        subdomain_grid,
+        particle_positions,
+        particle_densities,
+        active_particles,
+        parameters,
+        previous_density_map,
+    } = input;
 
     let particle_densities = if let Some(particle_densities) = particle_densities {
         assert_eq!(particle_densities.len(), particle_positions.len());
@@ -650,47 +2043,130 @@ pub(crate) fn reconstruct_single_surface_append<'a, I: Index, R: Real>(
         workspace.particle_densities.as_slice()
     };
 
-    // Create a new density map, reusing memory with the workspace is bad for cache efficiency
-    // Alternatively one could reuse memory with a custom caching allocator
-    let mut density_map = new_map().into();
-    density_map::generate_sparse_density_map(
+    // Skip particles that cannot contribute to the retained domain of the clipping planes
+    let unclipped_particles =
+        collect_unclipped_particles(particle_positions, active_particles, parameters);
+    let active_particles = unclipped_particles.as_deref().or(active_particles);
+
+    // For subdomains create a new density map, reusing memory with the workspace is bad for cache efficiency
+    // as the subdomains vary in size. The global reconstruction reuses the (possibly preallocated) map of the workspace
+    // unless the reconstruction is deterministic: the iteration order of a map depends on its capacity.
+    let reuse_density_map = subdomain_grid.is_none() && !parameters.deterministic;
+    let mut density_map = if reuse_density_map {
+        std::mem::replace(&mut workspace.density_map, new_map().into())
+    } else {
+        new_map().into()
+    };
+    generate_density_map(
         grid,
         subdomain_grid,
         particle_positions,
         particle_densities,
-        None,
-        particle_rest_mass,
-        parameters.compact_support_radius,
-        parameters.cube_size,
-        parameters.enable_multi_threading,
+        active_particles,
+        parameters,
         &mut density_map,
     )?;
+    let num_clamped = density_map::clip_density_map(
+        grid,
+        &parameters.clipping_planes,
+        parameters.iso_surface_threshold,
+        parameters.cube_size,
+        &mut density_map,
+    );
+    clamped_density_values.fetch_add(num_clamped, Ordering::Relaxed);
+
+    if let (Some(previous_density_map), Some(alpha)) =
+        (previous_density_map, parameters.temporal_density_smoothing)
+    {
+        density_map::blend_density_maps(&mut density_map, previous_density_map, alpha);
+    }
 
-    marching_cubes::triangulate_density_map_append(
+    // Subdomains are already processed in parallel
+    if subdomain_grid.is_none() {
+        triangulate_global_density_map(
+            grid,
+            &density_map,
+            parameters,
+            output_mesh,
+            triangle_cells,
+        )?;
+    } else {
+        marching_cubes::triangulate_density_map_append_with_cells(
+            grid,
+            subdomain_grid,
+            &density_map,
+            parameters.iso_surface_threshold,
+            parameters.vertex_snapping,
+            parameters.triangulation_scheme,
+            output_mesh,
+            triangle_cells,
+        )?;
+    }
+
+    Ok(density_map)
+}
+
+/// Triangulates the density map of a global reconstruction (without subdomains), appends the triangles to the given mesh
+///
+/// The triangulation is performed in parallel if multi-threading is enabled and the reconstruction is not
+/// deterministic. The parallel triangulation results in the same mesh up to the order of the vertices and
+/// triangles, in deterministic mode the sequential triangulation is used such that the mesh is bitwise identical
+/// to e.g. the triangulation of a [`DensityMapSnapshot`](crate::DensityMapSnapshot) of the same density map.
+pub(crate) fn triangulate_global_density_map<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    density_map: &DensityMap<I, R>,
+    parameters: &Parameters<R>,
+    output_mesh: &mut TriMesh3d<R>,
+    triangle_cells: Option<&mut Vec<I>>,
+) -> Result<(), MarchingCubesError> {
+    #[cfg(feature = "parallel")]
+    if parameters.enable_multi_threading && !parameters.deterministic {
+        return marching_cubes::par_triangulate_density_map_append_with_cells(
+            grid,
+            density_map,
+            parameters.iso_surface_threshold,
+            parameters.vertex_snapping,
+            parameters.triangulation_scheme,
+            output_mesh,
+            triangle_cells,
+        );
+    }
+
+    marching_cubes::triangulate_density_map_append_with_cells(
         grid,
-        subdomain_grid,
-        &density_map,
+        None,
+        density_map,
         parameters.iso_surface_threshold,
+        parameters.vertex_snapping,
+        parameters.triangulation_scheme,
         output_mesh,
-    )?;
-
-    Ok(())
+        triangle_cells,
+    )
 }
 
 /// Reconstruct a surface, appends triangulation to the given mesh
+///
+/// The input has to specify a subdomain grid, a previous density map is not supported.
 pub(crate) fn reconstruct_surface_patch<I: Index, R: Real>(
     workspace: &mut LocalReconstructionWorkspace<I, R>,
-    subdomain_grid: &OwningSubdomainGrid<I, R>,
-    particle_positions: &[Vector3<R>],
-    particle_densities: Option<&[R]>,
-    parameters: &Parameters<R>,
+    input: SingleSurfaceInput<'_, I, R>,
+    origins_node_id: Option<usize>,
+    clamped_density_values: &AtomicUsize,
 ) -> Result<SurfacePatch<I, R>, ReconstructionError<I, R>> {
     profile!("reconstruct_surface_patch");
 
-    let particle_rest_density = parameters.rest_density;
-    let particle_rest_volume = R::from_f64((4.0 / 3.0) * std::f64::consts::PI).unwrap()
-        * parameters.particle_radius.powi(3);
-    let particle_rest_mass = particle_rest_volume * particle_rest_density;
+    let SingleSurfaceInput {
+        subdomain_grid,
+        particle_positions,
+        particle_densities,
+        active_particles,
+        parameters,
+        previous_density_map,
+        ..
+    } = input;
+    let subdomain_grid =
+        subdomain_grid.expect("surface patches can only be reconstructed on a subdomain grid");
+    debug_assert!(previous_density_map.is_none());
 
     let particle_densities = if let Some(particle_densities) = particle_densities {
         assert_eq!(particle_densities.len(), particle_positions.len());
@@ -708,25 +2184,37 @@ pub(crate) fn reconstruct_surface_patch<I: Index, R: Real>(
 
     // Create a new density map, reusing memory with the workspace is bad for cache efficiency
     // Alternatively, one could reuse memory with a custom caching allocator
+    // Skip particles that cannot contribute to the retained domain of the clipping planes
+    let unclipped_particles =
+        collect_unclipped_particles(particle_positions, active_particles, parameters);
+    let active_particles = unclipped_particles.as_deref().or(active_particles);
+
     let mut density_map = new_map().into();
-    density_map::generate_sparse_density_map(
+    generate_density_map(
         subdomain_grid.global_grid(),
         Some(subdomain_grid),
         particle_positions,
         particle_densities,
-        None,
-        particle_rest_mass,
-        parameters.compact_support_radius,
-        parameters.cube_size,
-        parameters.enable_multi_threading,
+        active_particles,
+        parameters,
         &mut density_map,
     )?;
+    let num_clamped = density_map::clip_density_map(
+        subdomain_grid.global_grid(),
+        &parameters.clipping_planes,
+        parameters.iso_surface_threshold,
+        parameters.cube_size,
+        &mut density_map,
+    );
+    clamped_density_values.fetch_add(num_clamped, Ordering::Relaxed);
 
     // Run marching cubes and get boundary data
     let patch = marching_cubes::triangulate_density_map_to_surface_patch::<I, R>(
         subdomain_grid,
         &density_map,
         parameters.iso_surface_threshold,
+        parameters.triangulation_scheme,
+        origins_node_id,
     )?;
 
     Ok(patch)
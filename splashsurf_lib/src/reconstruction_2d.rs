@@ -0,0 +1,460 @@
+//! Reconstruction of contours of planar (2D) SPH particle data using marching squares
+//!
+//! This module provides the two dimensional counterpart of the surface reconstruction:
+//! the [`reconstruct_contour_2d`] function computes a sparse density map of the particles on a
+//! [`UniformGrid2d`] and extracts the iso-contour of this density map as a [`LineMesh2d`]
+//! using marching squares.
+
+use crate::logging::info;
+use nalgebra::Vector2;
+
+use crate::kernel::CubicSplineKernel2d;
+use crate::mesh::LineMesh2d;
+use crate::topology::square::{LocalVertexIndex, LOCAL_EDGE_VERTICES};
+use crate::uniform_grid::UniformGrid2d;
+use crate::{
+    new_map, profile, AxisAlignedBoundingBox2d, ConversionError, DensityMap, Index, MapType, Real,
+    ReconstructionError,
+};
+
+/// Parameters for the contour reconstruction of planar particle data
+#[derive(Clone, Debug)]
+pub struct Parameters2d<R: Real> {
+    /// Radius per particle (used to calculate the particle area)
+    pub particle_radius: R,
+    /// Rest density of the fluid (mass per area)
+    pub rest_density: R,
+    /// Compact support radius of the kernel, i.e. distance from the particle where kernel reaches zero (in distance units, not relative to particle radius)
+    pub compact_support_radius: R,
+    /// Edge length of the squares of the marching squares implicit background grid (in distance units, not relative to particle radius)
+    pub cell_size: R,
+    /// Density threshold value to distinguish between the inside (above threshold) and outside (below threshold) of the fluid
+    pub iso_surface_threshold: R,
+    /// Manually restrict the domain of the contour reconstruction.
+    /// If not provided, the smallest AABB enclosing all particles is computed instead.
+    pub domain_aabb: Option<AxisAlignedBoundingBox2d<R>>,
+}
+
+impl<R: Real> Parameters2d<R> {
+    /// Tries to convert the parameters from one [Real] type to another [Real] type, returns None if conversion fails
+    pub fn try_convert<T: Real>(&self) -> Option<Parameters2d<T>> {
+        self.try_convert_checked().ok()
+    }
+
+    /// Tries to convert the parameters from one [Real] type to another [Real] type, returns an error naming the first field that cannot be converted
+    pub fn try_convert_checked<T: Real>(&self) -> Result<Parameters2d<T>, ConversionError> {
+        Ok(Parameters2d {
+            particle_radius: ConversionError::convert_value(
+                self.particle_radius,
+                "particle_radius",
+            )?,
+            rest_density: ConversionError::convert_value(self.rest_density, "rest_density")?,
+            compact_support_radius: ConversionError::convert_value(
+                self.compact_support_radius,
+                "compact_support_radius",
+            )?,
+            cell_size: ConversionError::convert_value(self.cell_size, "cell_size")?,
+            iso_surface_threshold: ConversionError::convert_value(
+                self.iso_surface_threshold,
+                "iso_surface_threshold",
+            )?,
+            domain_aabb: match &self.domain_aabb {
+                Some(aabb) => Some(
+                    aabb.try_convert_checked()
+                        .map_err(|err| err.in_field("domain_aabb"))?,
+                ),
+                None => None,
+            },
+        })
+    }
+}
+
+/// Result data returned when the contour reconstruction was successful
+#[derive(Clone, Debug)]
+pub struct ContourReconstruction<I: Index, R: Real> {
+    /// Background grid that was used as a basis for generating the density map for marching squares
+    grid: UniformGrid2d<I, R>,
+    /// Per particle densities
+    particle_densities: Vec<R>,
+    /// Point-based density map generated from the particles that was used as input to marching squares
+    density_map: DensityMap<I, R>,
+    /// Line segments of the contour that is the result of the reconstruction
+    contour: LineMesh2d<R>,
+}
+
+impl<I: Index, R: Real> ContourReconstruction<I, R> {
+    /// Returns a reference to the line segments of the contour that is the result of the reconstruction
+    pub fn contour(&self) -> &LineMesh2d<R> {
+        &self.contour
+    }
+
+    /// Returns a reference to the per particle densities computed during the reconstruction
+    pub fn particle_densities(&self) -> &Vec<R> {
+        &self.particle_densities
+    }
+
+    /// Returns a reference to the sparse density map (discretized on the vertices of the background grid) that is used as input for marching squares
+    pub fn density_map(&self) -> &DensityMap<I, R> {
+        &self.density_map
+    }
+
+    /// Returns a reference to the virtual background grid that was used as a basis for discretization of the density map
+    pub fn grid(&self) -> &UniformGrid2d<I, R> {
+        &self.grid
+    }
+}
+
+impl<I: Index, R: Real> From<ContourReconstruction<I, R>> for LineMesh2d<R> {
+    /// Extracts the reconstructed contour
+    fn from(result: ContourReconstruction<I, R>) -> Self {
+        result.contour
+    }
+}
+
+/// Performs a marching squares reconstruction of the contour of the fluid represented by the given planar particle positions
+pub fn reconstruct_contour_2d<I: Index, R: Real>(
+    particle_positions: &[Vector2<R>],
+    parameters: &Parameters2d<R>,
+) -> Result<ContourReconstruction<I, R>, ReconstructionError<I, R>> {
+    profile!("reconstruct_contour_2d");
+
+    let grid = grid_for_contour_reconstruction(particle_positions, parameters)?;
+    grid.log_grid_info();
+
+    let particle_rest_area = R::pi() * parameters.particle_radius * parameters.particle_radius;
+    let particle_rest_mass = particle_rest_area * parameters.rest_density;
+    let kernel = CubicSplineKernel2d::new(parameters.compact_support_radius);
+
+    let particle_densities = compute_particle_densities_2d(
+        particle_positions,
+        parameters.compact_support_radius,
+        particle_rest_mass,
+        &kernel,
+    );
+
+    let density_map = generate_sparse_density_map_2d(
+        &grid,
+        particle_positions,
+        &particle_densities,
+        particle_rest_mass,
+        parameters.compact_support_radius,
+        &kernel,
+    );
+
+    let contour = marching_squares(&grid, &density_map, parameters.iso_surface_threshold);
+    info!(
+        "Contour reconstruction resulted in {} vertices and {} line segments.",
+        contour.vertices.len(),
+        contour.lines.len()
+    );
+
+    Ok(ContourReconstruction {
+        grid,
+        particle_densities,
+        density_map: density_map.into(),
+        contour,
+    })
+}
+
+/// Constructs the background grid for marching squares based on the parameters supplied to the contour reconstruction
+fn grid_for_contour_reconstruction<I: Index, R: Real>(
+    particle_positions: &[Vector2<R>],
+    parameters: &Parameters2d<R>,
+) -> Result<UniformGrid2d<I, R>, ReconstructionError<I, R>> {
+    let domain_aabb = if let Some(domain_aabb) = &parameters.domain_aabb {
+        domain_aabb.clone()
+    } else {
+        let mut domain_aabb = AxisAlignedBoundingBox2d::from_points(particle_positions);
+        domain_aabb.grow_uniformly(parameters.particle_radius);
+
+        info!(
+            "Minimal enclosing bounding box of particles was computed as: {:?}",
+            domain_aabb
+        );
+
+        // Ensure that every particle's kernel support and the resulting contour is completely in the domain
+        domain_aabb.grow_uniformly(parameters.compact_support_radius + parameters.cell_size);
+        domain_aabb
+    };
+
+    Ok(UniformGrid2d::from_aabb(
+        &domain_aabb,
+        parameters.cell_size,
+    )?)
+}
+
+/// Returns the neighbor lists of all particles using a spatial hashing with cells of the size of the search radius
+fn neighborhood_search_2d<R: Real>(
+    particle_positions: &[Vector2<R>],
+    search_radius: R,
+) -> Vec<Vec<usize>> {
+    profile!("neighborhood_search_2d");
+
+    let cell_of = |p: &Vector2<R>| -> [i64; 2] {
+        let normalized = p / search_radius;
+        [
+            normalized[0].floor().to_i64().unwrap(),
+            normalized[1].floor().to_i64().unwrap(),
+        ]
+    };
+
+    let mut particles_per_cell: MapType<[i64; 2], Vec<usize>> = new_map();
+    for (i, p) in particle_positions.iter().enumerate() {
+        particles_per_cell.entry(cell_of(p)).or_default().push(i);
+    }
+
+    let search_radius_sq = search_radius * search_radius;
+    particle_positions
+        .iter()
+        .enumerate()
+        .map(|(i, p_i)| {
+            let cell = cell_of(p_i);
+            let mut neighbors = Vec::new();
+            for di in -1..=1 {
+                for dj in -1..=1 {
+                    if let Some(cell_particles) =
+                        particles_per_cell.get(&[cell[0] + di, cell[1] + dj])
+                    {
+                        neighbors.extend(cell_particles.iter().copied().filter(|&j| {
+                            j != i
+                                && (particle_positions[j] - p_i).norm_squared() < search_radius_sq
+                        }));
+                    }
+                }
+            }
+            neighbors
+        })
+        .collect()
+}
+
+/// Computes the density of every particle as the kernel weighted sum of the masses of all particles in its neighborhood
+fn compute_particle_densities_2d<R: Real>(
+    particle_positions: &[Vector2<R>],
+    compact_support_radius: R,
+    particle_rest_mass: R,
+    kernel: &CubicSplineKernel2d<R>,
+) -> Vec<R> {
+    profile!("compute_particle_densities_2d");
+
+    let neighborhood_list = neighborhood_search_2d(particle_positions, compact_support_radius);
+    particle_positions
+        .iter()
+        .zip(neighborhood_list.iter())
+        .map(|(p_i, neighbors)| {
+            let kernel_sum = neighbors
+                .iter()
+                .map(|&j| kernel.evaluate((particle_positions[j] - p_i).norm()))
+                .fold(kernel.evaluate(R::zero()), |acc, w| acc + w);
+            kernel_sum * particle_rest_mass
+        })
+        .collect()
+}
+
+/// Computes a sparse density map of the particles on the points of the given background grid
+///
+/// The value at a grid point is given by the sum of the kernel weighted volumes `m_i/ρ_i` of all
+/// particles in the compact support radius around the point.
+fn generate_sparse_density_map_2d<I: Index, R: Real>(
+    grid: &UniformGrid2d<I, R>,
+    particle_positions: &[Vector2<R>],
+    particle_densities: &[R],
+    particle_rest_mass: R,
+    compact_support_radius: R,
+    kernel: &CubicSplineKernel2d<R>,
+) -> MapType<I, R> {
+    profile!("generate_sparse_density_map_2d");
+
+    let support_sq = compact_support_radius * compact_support_radius;
+    let support_vec = Vector2::repeat(compact_support_radius);
+    let mut density_map = new_map();
+
+    for (p_i, &rho_i) in particle_positions.iter().zip(particle_densities.iter()) {
+        let particle_volume = particle_rest_mass / rho_i;
+
+        // Clamp the range of grid points in the support of the particle to the grid (the lower corner
+        // is clamped before computing its cell to avoid negative indices with unsigned index types)
+        let lower = grid.enclosing_cell(&(p_i - support_vec).sup(grid.aabb().min()));
+        let upper = grid.enclosing_cell(&(p_i + support_vec));
+        let n_points = grid.points_per_dim();
+        let i_range = (
+            I::zero().max(lower[0]),
+            (n_points[0] - I::one()).min(upper[0] + I::one()),
+        );
+        let j_range = (
+            I::zero().max(lower[1]),
+            (n_points[1] - I::one()).min(upper[1] + I::one()),
+        );
+
+        let mut i = i_range.0;
+        while i <= i_range.1 {
+            let mut j = j_range.0;
+            while j <= j_range.1 {
+                let point = [i, j];
+                let dx = grid.point_coordinates_array(&point) - p_i;
+                let dist_sq = dx.norm_squared();
+                if dist_sq < support_sq {
+                    let value = particle_volume * kernel.evaluate(dist_sq.sqrt());
+                    *density_map
+                        .entry(grid.flatten_point_index_array(&point))
+                        .or_insert(R::zero()) += value;
+                }
+                j += I::one();
+            }
+            i += I::one();
+        }
+    }
+
+    density_map
+}
+
+/// Marching squares case table: line segments (as pairs of local edge indices) per corner configuration.
+///
+/// The local numbering of corners and edges is given by the [`topology::square`](crate::topology::square) module.
+/// Corner `k` of the cell is inside if bit `k` of the case index is set. Segments are oriented such that
+/// the inside of the fluid is on their left side. For the ambiguous saddle cases (5 and 10), the entry
+/// separates the inside corners, the alternative connecting triangulation is given by [`SADDLE_CONNECTED`].
+const MARCHING_SQUARES_TABLE: [&[[usize; 2]]; 16] = [
+    &[],
+    &[[0, 3]],
+    &[[1, 0]],
+    &[[1, 3]],
+    &[[2, 1]],
+    &[[0, 3], [2, 1]],
+    &[[2, 0]],
+    &[[2, 3]],
+    &[[3, 2]],
+    &[[0, 2]],
+    &[[1, 0], [3, 2]],
+    &[[1, 2]],
+    &[[3, 1]],
+    &[[0, 1]],
+    &[[3, 0]],
+    &[],
+];
+
+/// Line segments of the saddle cases 5 and 10 if the center of the cell is inside of the fluid
+const SADDLE_CONNECTED: [[[usize; 2]; 2]; 2] = [[[0, 1], [2, 3]], [[3, 0], [1, 2]]];
+
+/// Extracts the iso-contour of the given density map using marching squares
+fn marching_squares<I: Index, R: Real>(
+    grid: &UniformGrid2d<I, R>,
+    density_map: &MapType<I, R>,
+    iso_surface_threshold: R,
+) -> LineMesh2d<R> {
+    profile!("marching_squares");
+
+    // Every cell intersected by the contour has at least one corner inside of the fluid
+    let mut cells: Vec<[I; 2]> = Vec::new();
+    for (&flat_point_index, &value) in density_map.iter() {
+        if value > iso_surface_threshold {
+            let point = grid.unflatten_point_index(flat_point_index);
+            for vertex in LocalVertexIndex::all() {
+                let offset = vertex.offset();
+                if point[0] < I::from_u8(offset[0]).unwrap()
+                    || point[1] < I::from_u8(offset[1]).unwrap()
+                {
+                    continue;
+                }
+                let cell = [
+                    point[0] - I::from_u8(offset[0]).unwrap(),
+                    point[1] - I::from_u8(offset[1]).unwrap(),
+                ];
+                if grid.cell_exists(&cell) {
+                    cells.push(cell);
+                }
+            }
+        }
+    }
+    cells.sort_unstable();
+    cells.dedup();
+
+    let mut contour = LineMesh2d::default();
+    let mut edge_to_vertex: MapType<(I, I), usize> = new_map();
+
+    for cell in cells {
+        let mut corner_indices = [I::zero(); 4];
+        let mut corner_values = [R::zero(); 4];
+        let mut case_index = 0;
+        for (k, vertex) in LocalVertexIndex::all().enumerate() {
+            let point = vertex.global_point_index(&cell).unwrap();
+            corner_indices[k] = grid.flatten_point_index_array(&point);
+            corner_values[k] = density_map
+                .get(&corner_indices[k])
+                .copied()
+                .unwrap_or(R::zero());
+            if corner_values[k] > iso_surface_threshold {
+                case_index |= 1 << k;
+            }
+        }
+
+        let segments: &[[usize; 2]] = if case_index == 5 || case_index == 10 {
+            // Resolve the ambiguity of saddle cases using the average value of the cell corners
+            let center_value =
+                corner_values.iter().fold(R::zero(), |acc, &v| acc + v) / R::from_f64(4.0).unwrap();
+            if center_value > iso_surface_threshold {
+                &SADDLE_CONNECTED[if case_index == 5 { 0 } else { 1 }]
+            } else {
+                MARCHING_SQUARES_TABLE[case_index]
+            }
+        } else {
+            MARCHING_SQUARES_TABLE[case_index]
+        };
+
+        for segment in segments {
+            let mut line = [0; 2];
+            for (vertex, &local_edge) in line.iter_mut().zip(segment.iter()) {
+                let [a, b] = LOCAL_EDGE_VERTICES[local_edge];
+                let (index_a, index_b) = (corner_indices[a], corner_indices[b]);
+                let edge_key = (index_a.min(index_b), index_a.max(index_b));
+
+                *vertex = *edge_to_vertex.entry(edge_key).or_insert_with(|| {
+                    let (value_a, value_b) = (corner_values[a], corner_values[b]);
+                    let t = (iso_surface_threshold - value_a) / (value_b - value_a);
+                    let pos_a = grid.point_coordinates_array(&grid.unflatten_point_index(index_a));
+                    let pos_b = grid.point_coordinates_array(&grid.unflatten_point_index(index_b));
+
+                    contour.vertices.push(pos_a + (pos_b - pos_a) * t);
+                    contour.vertices.len() - 1
+                });
+            }
+            contour.lines.push(line);
+        }
+    }
+
+    contour
+}
+
+#[test]
+fn test_marching_squares_table_consistent_with_square_topology() {
+    use crate::topology::square::LocalEdgeIndex;
+
+    for case_index in 0..16 {
+        let inside = |vertex: LocalVertexIndex| case_index & (1 << vertex.index()) != 0;
+
+        let mut cases = vec![MARCHING_SQUARES_TABLE[case_index]];
+        if case_index == 5 || case_index == 10 {
+            cases.push(&SADDLE_CONNECTED[if case_index == 5 { 0 } else { 1 }]);
+        }
+
+        for segments in cases {
+            let used_edges = segments
+                .iter()
+                .flatten()
+                .map(|&e| LocalEdgeIndex::new(e).expect("invalid edge index in LUT"))
+                .collect::<Vec<_>>();
+
+            // Exactly the edges with end points on different sides of the contour are used once by the segments
+            for edge in LocalEdgeIndex::all() {
+                let [origin, target] = edge.vertices();
+                let straddles = inside(origin) != inside(target);
+                assert_eq!(
+                    used_edges.iter().filter(|&&e| e == edge).count(),
+                    straddles as usize,
+                    "case {}, edge {}",
+                    case_index,
+                    edge.index()
+                );
+            }
+        }
+    }
+}
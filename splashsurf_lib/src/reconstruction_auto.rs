@@ -0,0 +1,176 @@
+//! Surface reconstruction with an index type that is selected at runtime depending on the size of the background grid
+//!
+//! The [`Index`](crate::Index) type of [`reconstruct_surface`] limits the maximum number of points of the background grid.
+//! Smaller index types reduce the memory usage of the density map, so the smallest sufficient type should be
+//! used. [`reconstruct_surface_auto`] computes the background grid first, selects `i32` if it can index all
+//! grid points and `i64` otherwise and returns the result wrapped in a [`SurfaceReconstructionDyn`].
+
+use nalgebra::Vector3;
+use num::ToPrimitive;
+
+use crate::mesh::{MeshWithData, TriMesh3d};
+use crate::{
+    grid_for_reconstruction, reconstruct_surface, AttributeSpecs, Parameters, Real,
+    ReconstructionError, ReconstructionStats, SurfaceReconstruction,
+};
+
+/// Index types that can be selected by [`select_index_type`]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum IndexType {
+    /// 32 bit signed integers, background grids with up to `i32::MAX` points
+    I32,
+    /// 64 bit signed integers
+    I64,
+}
+
+impl IndexType {
+    /// Returns the name of the corresponding primitive integer type
+    pub fn name(&self) -> &'static str {
+        match self {
+            IndexType::I32 => "i32",
+            IndexType::I64 => "i64",
+        }
+    }
+}
+
+/// Result of a surface reconstruction with an index type selected at runtime, see [`reconstruct_surface_auto`]
+///
+/// The index type only affects the background grid, density map and octree. The mesh and all per particle
+/// results can be accessed independent of the index type, for all other results match on the variants.
+#[derive(Clone, Debug)]
+pub enum SurfaceReconstructionDyn<R: Real> {
+    /// Reconstruction using `i32` indices
+    I32(SurfaceReconstruction<i32, R>),
+    /// Reconstruction using `i64` indices
+    I64(SurfaceReconstruction<i64, R>),
+}
+
+/// Calls the given expression with the reconstruction of the active variant bound to the given identifier
+macro_rules! dispatch {
+    ($self:expr, $reconstruction:ident => $body:expr) => {
+        match $self {
+            SurfaceReconstructionDyn::I32($reconstruction) => $body,
+            SurfaceReconstructionDyn::I64($reconstruction) => $body,
+        }
+    };
+}
+
+impl<R: Real> SurfaceReconstructionDyn<R> {
+    /// Returns the index type that was selected for the reconstruction
+    pub fn index_type(&self) -> IndexType {
+        match self {
+            SurfaceReconstructionDyn::I32(_) => IndexType::I32,
+            SurfaceReconstructionDyn::I64(_) => IndexType::I64,
+        }
+    }
+
+    /// Returns a reference to the reconstructed surface mesh, see [`SurfaceReconstruction::mesh`]
+    pub fn mesh(&self) -> &TriMesh3d<R> {
+        dispatch!(self, reconstruction => reconstruction.mesh())
+    }
+
+    /// Returns a mutable reference to the reconstructed surface mesh, see [`SurfaceReconstruction::mesh_mut`]
+    pub fn mesh_mut(&mut self) -> &mut TriMesh3d<R> {
+        dispatch!(self, reconstruction => reconstruction.mesh_mut())
+    }
+
+    /// Moves the reconstructed surface mesh out of the reconstruction, see [`SurfaceReconstruction::take_mesh`]
+    pub fn take_mesh(&mut self) -> TriMesh3d<R> {
+        dispatch!(self, reconstruction => reconstruction.take_mesh())
+    }
+
+    /// Returns the global particle density vector if it was computed, see [`SurfaceReconstruction::particle_densities`]
+    pub fn particle_densities(&self) -> Option<&Vec<R>> {
+        dispatch!(self, reconstruction => reconstruction.particle_densities())
+    }
+
+    /// Returns the indices of the splash particles, see [`SurfaceReconstruction::splash_particle_indices`]
+    pub fn splash_particle_indices(&self) -> Option<&Vec<usize>> {
+        dispatch!(self, reconstruction => reconstruction.splash_particle_indices())
+    }
+
+    /// Returns the mesh of the splash particle spheres, see [`SurfaceReconstruction::splash_mesh`]
+    pub fn splash_mesh(&self) -> Option<&TriMesh3d<R>> {
+        dispatch!(self, reconstruction => reconstruction.splash_mesh())
+    }
+
+    /// Returns the originating particles of the splash sphere vertices, see [`SurfaceReconstruction::splash_vertex_particle_indices`]
+    pub fn splash_vertex_particle_indices(&self) -> Option<&Vec<usize>> {
+        dispatch!(self, reconstruction => reconstruction.splash_vertex_particle_indices())
+    }
+
+    /// Returns the statistics of the reconstruction, see [`SurfaceReconstruction::stats`]
+    pub fn stats(&self) -> &ReconstructionStats {
+        dispatch!(self, reconstruction => reconstruction.stats())
+    }
+
+    /// Returns the number of points of the background grid
+    pub fn num_grid_points(&self) -> u64 {
+        dispatch!(self, reconstruction => reconstruction
+            .grid()
+            .points_per_dim()
+            .iter()
+            .map(|n| n.to_u64().unwrap())
+            .product())
+    }
+
+    /// Computes the requested attributes of the mesh, see [`SurfaceReconstruction::mesh_with_attributes`]
+    pub fn mesh_with_attributes(
+        &self,
+        particle_positions: &[Vector3<R>],
+        parameters: &Parameters<R>,
+        attribute_specs: &AttributeSpecs<R>,
+    ) -> Result<MeshWithData<R, TriMesh3d<R>>, ReconstructionError<i64, R>> {
+        match self {
+            SurfaceReconstructionDyn::I32(reconstruction) => reconstruction
+                .mesh_with_attributes(particle_positions, parameters, attribute_specs)
+                .map_err(ReconstructionError::convert_index),
+            SurfaceReconstructionDyn::I64(reconstruction) => {
+                reconstruction.mesh_with_attributes(particle_positions, parameters, attribute_specs)
+            }
+        }
+    }
+}
+
+/// Returns the smallest index type that can index all points of the background grid of a reconstruction with the given parameters
+///
+/// This constructs the background grid like the reconstruction itself, i.e. the bounding box of the particles
+/// is computed if no domain is specified in the parameters. If the grid cannot be constructed at all (e.g. due to
+/// invalid parameters), [`IndexType::I64`] is returned and the error is reported by the reconstruction.
+pub fn select_index_type<R: Real>(
+    particle_positions: &[Vector3<R>],
+    parameters: &Parameters<R>,
+) -> IndexType {
+    let grid = grid_for_reconstruction::<i32, R>(
+        particle_positions,
+        parameters.particle_radius,
+        parameters.compact_support_radius,
+        parameters.cube_size,
+        parameters.domain_aabb.as_ref(),
+        parameters.enable_multi_threading,
+    );
+
+    if grid.is_ok() {
+        IndexType::I32
+    } else {
+        IndexType::I64
+    }
+}
+
+/// Performs a surface reconstruction like [`reconstruct_surface`] with the index type selected by [`select_index_type`]
+///
+/// Errors of reconstructions using `i32` indices are converted to the `i64` error type.
+pub fn reconstruct_surface_auto<R: Real>(
+    particle_positions: &[Vector3<R>],
+    parameters: &Parameters<R>,
+) -> Result<SurfaceReconstructionDyn<R>, ReconstructionError<i64, R>> {
+    parameters.validate()?;
+
+    match select_index_type(particle_positions, parameters) {
+        IndexType::I32 => reconstruct_surface::<i32, R>(particle_positions, parameters)
+            .map(SurfaceReconstructionDyn::I32)
+            .map_err(ReconstructionError::convert_index),
+        IndexType::I64 => reconstruct_surface::<i64, R>(particle_positions, parameters)
+            .map(SurfaceReconstructionDyn::I64),
+    }
+}
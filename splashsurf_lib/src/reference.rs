@@ -0,0 +1,221 @@
+//! Simple and slow reference implementation of the surface reconstruction for correctness testing (requires the `reference-impl` feature)
+//!
+//! The functions of this module implement the same pipeline as [`reconstruct_surface`](crate::reconstruct_surface)
+//! in the most straightforward way: the particle densities are computed by a brute-force `O(N²)` summation over all
+//! particle pairs, the level-set function is evaluated on every point of a dense background grid by summing the
+//! contributions of all particles using the analytic [`CubicSplineKernel`] and the surface is extracted by a
+//! single-threaded marching cubes pass over all cells of the grid. None of the acceleration structures of the
+//! library (neighborhood search, sparse density maps, octree decomposition, stitching) are used.
+//!
+//! The reference reconstruction is only feasible for a few thousand particles on small grids. Its purpose is to
+//! check the output of the optimized code paths, which are expected to agree with the reference up to the error
+//! of the discretized kernel and the order of the vertices and triangles of the mesh.
+
+use crate::density_map::{allowed_particle_domain, compute_kernel_evaluation_radius};
+use crate::kernel::{CubicSplineKernel, SymmetricKernel3d};
+use crate::marching_cubes::marching_cubes_lut::marching_cubes_triangulation_iter;
+use crate::mesh::TriMesh3d;
+use crate::topology::cube::{LOCAL_EDGE_VERTICES, LOCAL_VERTEX_OFFSETS};
+use crate::{
+    grid_for_reconstruction, new_map, Index, Parameters, Real, ReconstructionError, UniformGrid,
+};
+use nalgebra::Vector3;
+
+/// Reconstructs the surface of the particles using the reference implementation
+///
+/// The background grid is constructed in the same way as for [`reconstruct_surface`](crate::reconstruct_surface).
+/// Only the parameters of the global reconstruction are considered: splash detection, clipping planes, spatial
+/// decomposition and multi-threading are ignored. The surface is always extracted with the classic marching cubes
+/// table, independent of the [`triangulation_scheme`](Parameters::triangulation_scheme).
+pub fn reconstruct_surface_reference<I: Index, R: Real>(
+    particle_positions: &[Vector3<R>],
+    parameters: &Parameters<R>,
+) -> Result<TriMesh3d<R>, ReconstructionError<I, R>> {
+    parameters.validate()?;
+
+    let grid = grid_for_reconstruction::<I, R>(
+        particle_positions,
+        parameters.particle_radius,
+        parameters.compact_support_radius,
+        parameters.cube_size,
+        parameters.domain_aabb.as_ref(),
+        false,
+    )?;
+
+    let particle_rest_volume = R::from_f64((4.0 / 3.0) * std::f64::consts::PI).unwrap()
+        * parameters.particle_radius.powi(3);
+    let particle_rest_mass = particle_rest_volume * parameters.rest_density;
+
+    let particle_densities = reference_particle_densities(
+        &grid,
+        particle_positions,
+        parameters.compact_support_radius,
+        particle_rest_mass,
+    );
+    let grid_values = reference_grid_values(
+        &grid,
+        particle_positions,
+        &particle_densities,
+        particle_rest_mass,
+        parameters.compact_support_radius,
+        parameters.cube_size,
+    );
+
+    Ok(reference_marching_cubes(
+        &grid,
+        &grid_values,
+        parameters.iso_surface_threshold,
+    ))
+}
+
+/// Computes the SPH densities of all particles by summing the kernel contributions of all particle pairs
+///
+/// Particles outside of the background grid have no neighbors and are not neighbors of any other particle, like
+/// in the neighborhood search of the optimized code paths.
+pub fn reference_particle_densities<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    particle_positions: &[Vector3<R>],
+    compact_support_radius: R,
+    particle_rest_mass: R,
+) -> Vec<R> {
+    let kernel = CubicSplineKernel::new(compact_support_radius);
+    let is_inside_grid = |x: &Vector3<R>| grid.get_enclosing_cell(x).is_some();
+
+    particle_positions
+        .iter()
+        .map(|x_i| {
+            let mut density = kernel.evaluate(R::zero());
+            if is_inside_grid(x_i) {
+                for x_j in particle_positions.iter().filter(|x_j| is_inside_grid(x_j)) {
+                    if !std::ptr::eq(x_i, x_j) {
+                        density += kernel.evaluate((x_j - x_i).norm());
+                    }
+                }
+            }
+            density * particle_rest_mass
+        })
+        .collect()
+}
+
+/// Evaluates the level-set function at every point of the background grid by summing the contributions of all particles
+///
+/// The returned values are indexed by the flat point indices of the grid. Particles closer to the boundary of the
+/// grid than the kernel evaluation radius do not contribute to the level-set function, like in the density map
+/// generation of the optimized code paths.
+pub fn reference_grid_values<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    particle_positions: &[Vector3<R>],
+    particle_densities: &[R],
+    particle_rest_mass: R,
+    compact_support_radius: R,
+    cube_size: R,
+) -> Vec<R> {
+    assert_eq!(particle_positions.len(), particle_densities.len());
+
+    let kernel = CubicSplineKernel::new(compact_support_radius);
+    let allowed_domain = allowed_particle_domain(
+        grid,
+        compute_kernel_evaluation_radius::<I, R>(compact_support_radius, cube_size)
+            .kernel_evaluation_radius,
+    );
+    let contributing_particles = particle_positions
+        .iter()
+        .zip(particle_densities.iter())
+        .filter(|(x_j, _)| allowed_domain.contains_point(x_j))
+        .map(|(x_j, &density_j)| (x_j, particle_rest_mass / density_j))
+        .collect::<Vec<_>>();
+
+    let [ni, nj, nk] = grid.points_per_dim().map(|n| n.to_usize().unwrap());
+    let mut values = vec![R::zero(); ni * nj * nk];
+    for i in 0..ni {
+        for j in 0..nj {
+            for k in 0..nk {
+                let [i, j, k] = [i, j, k].map(|n| I::from_usize(n).unwrap());
+                let x = grid.point_coordinates_indices(i, j, k);
+                let value = contributing_particles
+                    .iter()
+                    .fold(R::zero(), |value, &(x_j, volume_j)| {
+                        value + volume_j * kernel.evaluate((x - x_j).norm())
+                    });
+                values[grid.flatten_point_indices(i, j, k).to_usize().unwrap()] = value;
+            }
+        }
+    }
+
+    values
+}
+
+/// Triangulates the iso-surface of the given point values of the background grid by marching cubes over all cells
+///
+/// Grid points with values above the threshold are inside of the surface. Every edge crossing the iso-surface gets
+/// exactly one vertex that is linearly interpolated between the values of the two end points of the edge.
+pub fn reference_marching_cubes<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    point_values: &[R],
+    iso_surface_threshold: R,
+) -> TriMesh3d<R> {
+    let num_points = grid.points_per_dim().map(|n| n.to_usize().unwrap());
+    assert_eq!(point_values.len(), num_points.iter().product::<usize>());
+
+    let point_index = |ijk: [usize; 3]| {
+        grid.flatten_point_index_array(&ijk.map(|n| I::from_usize(n).unwrap()))
+            .to_usize()
+            .unwrap()
+    };
+
+    let mut mesh = TriMesh3d::default();
+    // Maps from the flat index of the first point of an edge and the axis of the edge to the vertex on the edge
+    let mut edge_vertices = new_map();
+
+    let [ni, nj, nk] = grid.cells_per_dim().map(|n| n.to_usize().unwrap());
+    for i in 0..ni {
+        for j in 0..nj {
+            for k in 0..nk {
+                let corners = LOCAL_VERTEX_OFFSETS.map(|offset| {
+                    [
+                        i + offset[0] as usize,
+                        j + offset[1] as usize,
+                        k + offset[2] as usize,
+                    ]
+                });
+                let corners_inside =
+                    corners.map(|ijk| point_values[point_index(ijk)] > iso_surface_threshold);
+
+                for edges in marching_cubes_triangulation_iter(&corners_inside) {
+                    let triangle = edges.map(|edge| {
+                        let [a, b] = LOCAL_EDGE_VERTICES[edge as usize].map(|v| corners[v]);
+                        let axis = (0..3).find(|&dim| a[dim] != b[dim]).unwrap();
+
+                        *edge_vertices
+                            .entry((point_index(a), axis))
+                            .or_insert_with(|| {
+                                // Interpolate from the point below to the point above the threshold
+                                let first_vertex = LOCAL_EDGE_VERTICES[edge as usize][0];
+                                let (below, above) = if corners_inside[first_vertex] {
+                                    (b, a)
+                                } else {
+                                    (a, b)
+                                };
+                                let [value_below, value_above] =
+                                    [below, above].map(|ijk| point_values[point_index(ijk)]);
+                                let [x_below, x_above] = [below, above].map(|ijk| {
+                                    grid.point_coordinates_array(
+                                        &ijk.map(|n| I::from_usize(n).unwrap()),
+                                    )
+                                });
+
+                                let alpha = (iso_surface_threshold - value_below)
+                                    / (value_above - value_below);
+                                mesh.vertices
+                                    .push(x_below * (R::one() - alpha) + x_above * alpha);
+                                mesh.vertices.len() - 1
+                            })
+                    });
+                    mesh.triangles.push(triangle);
+                }
+            }
+        }
+    }
+
+    mesh
+}
@@ -1,30 +1,80 @@
 //! Functions for interpolating quantities (e.g. normals, scalar fields) by evaluating SPH sums
 
 use crate::kernel::SymmetricKernel3d;
-use crate::profile;
-use crate::Real;
-use crate::{kernel, ThreadSafe};
+use crate::logging::warn;
+use crate::mesh::{AttributeData, MeshAttribute};
+use crate::neighborhood_search::{
+    ParticleNeighborhoodProvider, SpatialHashingNeighborhoodProvider,
+};
+use crate::utils::map_collect_into_vec;
+use crate::{kernel, AttributeError, ThreadSafe};
+use crate::{profile, Real};
 use nalgebra::{SVector, Unit, Vector3};
-use rayon::prelude::*;
-use rstar::primitives::GeomWithData;
-use rstar::RTree;
 use std::ops::AddAssign;
 
 /// Acceleration structure for interpolating field quantities of the fluid to arbitrary points using SPH interpolation
 pub struct SphInterpolator<R: Real> {
     compact_support_radius: R,
-    tree: RTree<Particle<R>>,
+    /// Positions of all particles
+    particle_positions: Vec<Vector3<R>>,
+    /// Volume associated with each particle which is needed to evaluate the SPH density field
+    particle_volumes: Vec<R>,
+    /// Spatial data structure for the SPH neighbor queries
+    neighborhood: Box<dyn ParticleNeighborhoodProvider<R>>,
 }
 
-/// Particle type that is stored in the R-tree for fast SPH neighbor queries
-type Particle<R> = GeomWithData<[R; 3], ParticleData<R>>;
+/// Particle attributes interpolated to the vertices of a mesh, see [`interpolate_particle_attributes`]
+#[derive(Clone, Debug)]
+pub struct InterpolatedAttributes<R: Real> {
+    /// One attribute with one value per vertex for each of the particle attributes (with the same names)
+    pub attributes: Vec<MeshAttribute<R>>,
+    /// Number of vertices without any particle within the compact support radius, all attributes are zero at these vertices
+    pub vertices_without_particles: usize,
+}
 
-/// Data associated with each particle that is stored in the R-tree
-struct ParticleData<R: Real> {
-    /// Index of the particle in the global particle list
-    index: usize,
-    /// Volume associated with each particle which is needed to evaluate the SPH density field
-    volume: R,
+/// Interpolates per particle attributes to the vertices of a mesh using SPH interpolation with the cubic spline kernel
+///
+/// Builds a [`SphInterpolator`] for the particles (the volume of a particle is its rest mass divided by its density)
+/// and evaluates [`SphInterpolator::interpolate_attribute`] for all attributes at the vertices, e.g. to transfer the
+/// velocities or temperatures of the particles to a reconstructed surface for rendering. Scalar and vector attributes
+/// are supported, integer attributes cannot be interpolated (see [`SphInterpolator::nearest_particles`] instead).
+///
+/// Vertices without particles within the compact support radius get values of zero, their number is returned and
+/// logged as a warning.
+pub fn interpolate_particle_attributes<R: Real>(
+    mesh_vertices: &[Vector3<R>],
+    particle_positions: &[Vector3<R>],
+    particle_attributes: &[MeshAttribute<R>],
+    compact_support_radius: R,
+    particle_rest_mass: R,
+    particle_densities: &[R],
+) -> Result<InterpolatedAttributes<R>, AttributeError> {
+    profile!("interpolate_particle_attributes");
+
+    let interpolator = SphInterpolator::new(
+        particle_positions,
+        particle_densities,
+        particle_rest_mass,
+        compact_support_radius,
+    );
+    let attributes = particle_attributes
+        .iter()
+        .map(|attribute| interpolator.interpolate_attribute(attribute, mesh_vertices))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let vertices_without_particles = interpolator.num_points_without_particles(mesh_vertices);
+    if vertices_without_particles > 0 {
+        warn!(
+            "{} of {} vertices have no particles within the compact support radius, their interpolated attributes are set to zero",
+            vertices_without_particles,
+            mesh_vertices.len()
+        );
+    }
+
+    Ok(InterpolatedAttributes {
+        attributes,
+        vertices_without_particles,
+    })
 }
 
 /// Trait for per-particle quantities that can be interpolated using SPH
@@ -56,20 +106,52 @@ impl<R: Real, const D: usize> InterpolationQuantity<R> for SVector<R, D> {
 }
 
 impl<R: Real> SphInterpolator<R> {
-    /// Initializes the acceleration structure for interpolating values of the given fluid particles, this is a relatively expensive operation (builds an R-tree)
+    /// Initializes the acceleration structure for interpolating values of the given fluid particles, this is a relatively expensive operation (builds a spatial hashing grid)
     pub fn new(
         particle_positions: &[Vector3<R>],
         particle_densities: &[R],
         particle_rest_mass: R,
         compact_support_radius: R,
+    ) -> Self {
+        let neighborhood =
+            SpatialHashingNeighborhoodProvider::new(particle_positions, compact_support_radius);
+        Self::with_neighborhood_provider(
+            particle_positions,
+            particle_densities,
+            particle_rest_mass,
+            compact_support_radius,
+            neighborhood,
+        )
+    }
+
+    /// Initializes the interpolation of values of the given fluid particles using the given provider for all neighbor queries
+    ///
+    /// The provider has to contain exactly the given particles, i.e. the particle indices returned by its queries
+    /// have to refer to the given positions and densities.
+    pub fn with_neighborhood_provider<P: ParticleNeighborhoodProvider<R> + 'static>(
+        particle_positions: &[Vector3<R>],
+        particle_densities: &[R],
+        particle_rest_mass: R,
+        compact_support_radius: R,
+        neighborhood: P,
     ) -> Self {
         assert_eq!(particle_positions.len(), particle_densities.len());
+        assert_eq!(
+            particle_positions.len(),
+            neighborhood.num_particles(),
+            "The neighborhood provider has to contain all particles"
+        );
 
-        let tree = build_rtree(particle_positions, particle_densities, particle_rest_mass);
+        let particle_volumes = particle_densities
+            .iter()
+            .map(|&rho_i| particle_rest_mass / rho_i)
+            .collect();
 
         Self {
             compact_support_radius,
-            tree,
+            particle_positions: particle_positions.to_vec(),
+            particle_volumes,
+            neighborhood: Box::new(neighborhood),
         }
     }
 
@@ -81,40 +163,40 @@ impl<R: Real> SphInterpolator<R> {
     ) {
         profile!("interpolate_normals_inplace");
 
-        let squared_support = self.compact_support_radius * self.compact_support_radius;
         let kernel = kernel::CubicSplineKernel::new(self.compact_support_radius);
 
-        interpolation_points
-            .par_iter()
-            .map(|x_i| {
+        map_collect_into_vec(
+            interpolation_points,
+            |x_i| {
                 // Compute the gradient of the particle density field which points in the same direction as surface normals
                 let mut density_grad = Vector3::zeros();
 
-                // SPH: Iterate over all other particles within the squared support radius
-                let query_point = bytemuck::cast::<_, [R; 3]>(*x_i);
-                for p_j in self
-                    .tree
-                    .locate_within_distance(query_point, squared_support)
-                {
-                    // Volume of the neighbor particle
-                    let vol_j = p_j.data.volume;
-                    // Position of the neighbor particle
-                    let x_j = bytemuck::cast_ref::<_, Vector3<R>>(p_j.geom());
-
-                    // Relative position `dx` and distance `r` of the neighbor particle
-                    let dx = x_j - x_i;
-                    let r = dx.norm();
-
-                    // Compute the contribution of the neighbor to the gradient of the density field
-                    // TODO: Replace this by a discrete gradient norm evaluation
-                    let kernel_grad = dx.unscale(r) * kernel.evaluate_gradient_norm(r);
-                    density_grad += kernel_grad * vol_j;
-                }
+                // SPH: Iterate over all other particles within the support radius
+                self.neighborhood.visit_particles_within(
+                    x_i,
+                    self.compact_support_radius,
+                    &mut |j| {
+                        // Volume of the neighbor particle
+                        let vol_j = self.particle_volumes[j];
+                        // Position of the neighbor particle
+                        let x_j = &self.particle_positions[j];
+
+                        // Relative position `dx` and distance `r` of the neighbor particle
+                        let dx = x_j - x_i;
+                        let r = dx.norm();
+
+                        // Compute the contribution of the neighbor to the gradient of the density field
+                        // TODO: Replace this by a discrete gradient norm evaluation
+                        let kernel_grad = dx.unscale(r) * kernel.evaluate_gradient_norm(r);
+                        density_grad += kernel_grad * vol_j;
+                    },
+                );
 
                 // Normalize the gradient to get the surface normal
                 Unit::new_normalize(density_grad)
-            })
-            .collect_into_vec(normals);
+            },
+            normals,
+        );
     }
 
     /// Interpolates surface normals (i.e. normalized SPH gradient of the indicator function) of the fluid to the given points using SPH interpolation
@@ -195,6 +277,153 @@ impl<R: Real> SphInterpolator<R> {
         values
     }
 
+    /// Interpolates a per particle attribute to the given points using SPH interpolation with first order correction
+    ///
+    /// Returns an attribute with the same name and one value per point, vector attributes with an arbitrary number of
+    /// components are interpolated component-wise. Points without particles within the compact support radius get a
+    /// value of zero (see [`num_points_without_particles`](Self::num_points_without_particles)). Integer attributes
+    /// cannot be interpolated and result in an [`AttributeError::UnsupportedType`] error.
+    pub fn interpolate_attribute(
+        &self,
+        particle_attribute: &MeshAttribute<R>,
+        interpolation_points: &[Vector3<R>],
+    ) -> Result<MeshAttribute<R>, AttributeError> {
+        let name = &particle_attribute.name;
+        let num_values = match &particle_attribute.data {
+            AttributeData::ScalarReal(values) => values.len(),
+            AttributeData::Vector3Real(values) => values.len(),
+            AttributeData::VectorReal {
+                num_components,
+                values,
+            } if *num_components > 0 => values.len().div_ceil(*num_components),
+            AttributeData::VectorReal { .. } | AttributeData::ScalarU64(_) => {
+                return Err(AttributeError::UnsupportedType(name.clone()))
+            }
+        };
+        if num_values != self.particle_positions.len() {
+            return Err(AttributeError::LengthMismatch {
+                name: name.clone(),
+                len: num_values,
+                expected: self.particle_positions.len(),
+            });
+        }
+
+        let data = match &particle_attribute.data {
+            AttributeData::ScalarReal(values) => AttributeData::ScalarReal(
+                self.interpolate_scalar_quantity(values, interpolation_points, true),
+            ),
+            AttributeData::Vector3Real(values) => AttributeData::Vector3Real(
+                self.interpolate_vector_quantity(values, interpolation_points, true),
+            ),
+            AttributeData::VectorReal {
+                num_components,
+                values,
+            } => {
+                let num_components = *num_components;
+                // Every component is interpolated separately
+                let interpolated_components = (0..num_components)
+                    .map(|component| {
+                        let component_values = values
+                            .iter()
+                            .skip(component)
+                            .step_by(num_components)
+                            .copied()
+                            .collect::<Vec<_>>();
+                        self.interpolate_scalar_quantity(
+                            &component_values,
+                            interpolation_points,
+                            true,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut interpolated_values =
+                    Vec::with_capacity(interpolation_points.len() * num_components);
+                for point_idx in 0..interpolation_points.len() {
+                    interpolated_values.extend(
+                        interpolated_components
+                            .iter()
+                            .map(|component_values| component_values[point_idx]),
+                    );
+                }
+                AttributeData::VectorReal {
+                    num_components,
+                    values: interpolated_values,
+                }
+            }
+            AttributeData::ScalarU64(_) => unreachable!(),
+        };
+
+        Ok(MeshAttribute::new(name.clone(), data))
+    }
+
+    /// Returns the number of the given points without any particle within the compact support radius, the SPH interpolation results in zero at these points
+    pub fn num_points_without_particles(&self, points: &[Vector3<R>]) -> usize {
+        profile!("num_points_without_particles");
+
+        let mut has_particles = Vec::with_capacity(points.len());
+        map_collect_into_vec(
+            points,
+            |x_i| {
+                let mut found = false;
+                self.neighborhood.visit_particles_within(
+                    x_i,
+                    self.compact_support_radius,
+                    &mut |_| found = true,
+                );
+                found
+            },
+            &mut has_particles,
+        );
+        has_particles.iter().filter(|&&found| !found).count()
+    }
+
+    /// Returns the index of the particle nearest to each of the given points (independent of the compact support radius), panics if there are no particles
+    pub fn nearest_particles(&self, points: &[Vector3<R>]) -> Vec<usize> {
+        profile!("nearest_particles");
+        assert!(
+            !self.particle_positions.is_empty(),
+            "There has to be at least one particle for nearest particle queries"
+        );
+
+        // Returns the particle with the smallest distance to the point, the smaller index on ties
+        let closer = |x_i: &Vector3<R>, nearest: Option<(usize, R)>, j: usize| {
+            let dist_sq = (self.particle_positions[j] - x_i).norm_squared();
+            match nearest {
+                Some((k, nearest_dist_sq))
+                    if nearest_dist_sq < dist_sq || (nearest_dist_sq == dist_sq && k < j) =>
+                {
+                    Some((k, nearest_dist_sq))
+                }
+                _ => Some((j, dist_sq)),
+            }
+        };
+
+        let mut nearest = Vec::with_capacity(points.len());
+        map_collect_into_vec(
+            points,
+            |x_i| {
+                let mut nearest = None;
+                self.neighborhood.visit_particles_within(
+                    x_i,
+                    self.compact_support_radius,
+                    &mut |j| {
+                        nearest = closer(x_i, nearest, j);
+                    },
+                );
+                // Points without particles in their support (e.g. far away from the surface) fall back to a linear search
+                if nearest.is_none() {
+                    for j in 0..self.particle_positions.len() {
+                        nearest = closer(x_i, nearest, j);
+                    }
+                }
+                nearest.map(|(j, _)| j).unwrap()
+            },
+            &mut nearest,
+        );
+        nearest
+    }
+
     /// Interpolates a per particle quantity to the given points, panics if the there are less per-particles values than particles, appends to the given vector
     #[allow(non_snake_case)]
     fn interpolate_quantity_inplace<T: InterpolationQuantity<R>>(
@@ -205,82 +434,46 @@ impl<R: Real> SphInterpolator<R> {
         first_order_correction: bool,
     ) {
         profile!("interpolate_quantity_inplace");
-        assert_eq!(particle_quantity.len(), self.tree.size());
+        assert_eq!(particle_quantity.len(), self.particle_positions.len());
 
-        let squared_support = self.compact_support_radius * self.compact_support_radius;
         let kernel = kernel::CubicSplineKernel::new(self.compact_support_radius);
 
-        let enable_correction = if first_order_correction {
-            R::one()
-        } else {
-            R::zero()
-        };
-
-        interpolation_points
-            .par_iter()
-            .map(|x_i| {
+        map_collect_into_vec(
+            interpolation_points,
+            |x_i| {
                 let mut interpolated_value = T::zero();
                 let mut correction = R::zero();
 
-                // SPH: Iterate over all other particles within the squared support radius
-                let query_point = bytemuck::cast::<_, [R; 3]>(*x_i);
-                for p_j in self
-                    .tree
-                    .locate_within_distance(query_point, squared_support)
-                {
-                    // Volume of the neighbor particle
-                    let vol_j = p_j.data.volume;
-                    // Position of the neighbor particle
-                    let x_j = bytemuck::cast_ref::<_, Vector3<R>>(p_j.geom());
-
-                    // Relative position `dx` and distance `r` of the neighbor particle
-                    let dx = x_j - x_i;
-                    let r = dx.norm();
-
-                    // Unchecked access is fine as we asserted before that the slice has the correct length
-                    let A_j = unsafe { particle_quantity.get_unchecked(p_j.data.index).clone() };
-                    let W_ij = kernel.evaluate(r);
-
-                    interpolated_value += A_j.scale(vol_j * W_ij);
-                    correction += vol_j * W_ij;
+                // SPH: Iterate over all other particles within the support radius
+                self.neighborhood.visit_particles_within(
+                    x_i,
+                    self.compact_support_radius,
+                    &mut |j| {
+                        // Volume of the neighbor particle
+                        let vol_j = self.particle_volumes[j];
+                        // Position of the neighbor particle
+                        let x_j = &self.particle_positions[j];
+
+                        // Relative position `dx` and distance `r` of the neighbor particle
+                        let dx = x_j - x_i;
+                        let r = dx.norm();
+
+                        let A_j = particle_quantity[j].clone();
+                        let W_ij = kernel.evaluate(r);
+
+                        interpolated_value += A_j.scale(vol_j * W_ij);
+                        correction += vol_j * W_ij;
+                    },
+                );
+
+                // Points without particles in their support keep the value of zero
+                if first_order_correction && correction > R::zero() {
+                    interpolated_value.scale(correction.recip())
+                } else {
+                    interpolated_value
                 }
-
-                let correction_factor =
-                    enable_correction * correction.recip() + (R::one() - enable_correction);
-                interpolated_value.scale(correction_factor)
-            })
-            .collect_into_vec(interpolated_values);
+            },
+            interpolated_values,
+        );
     }
 }
-
-/// Constructs an R-Tree for the given particles, storing each particle's index and volume in the tree
-fn build_rtree<R: Real>(
-    particle_positions: &[Vector3<R>],
-    particle_densities: &[R],
-    particle_rest_mass: R,
-) -> RTree<Particle<R>> {
-    assert_eq!(particle_positions.len(), particle_densities.len());
-
-    // Prepare data for R-tree insertion:
-    // Collect all particles with their position and compute their volume
-    let particles = particle_positions
-        .iter()
-        .zip(particle_densities.iter().copied())
-        .enumerate()
-        .map(|(i, (p, rho_i))| {
-            let data = ParticleData {
-                index: i,
-                volume: particle_rest_mass / rho_i,
-            };
-            Particle::new(bytemuck::cast(*p), data)
-        })
-        .collect();
-
-    // Build the R-tree to accelerate SPH neighbor queries near the interpolation points
-    let tree = {
-        profile!("build R-tree");
-        RTree::bulk_load(particles)
-    };
-
-    tree
-}
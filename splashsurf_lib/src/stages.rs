@@ -0,0 +1,187 @@
+//! Individual stages of the surface reconstruction pipeline that can be composed to custom workflows
+//!
+//! The global surface reconstruction of [`reconstruct_surface`](crate::reconstruct_surface) (without spatial
+//! decomposition) consists of the following stages, performed on the background grid constructed by
+//! [`grid_for_reconstruction`](crate::grid_for_reconstruction):
+//!  1. [`compute_particle_densities`]: neighborhood search and computation of the per particle densities,
+//!  2. [`generate_density_map`]: evaluation of the sparse density map on the points of the grid (followed by
+//!     [`clip_density_map`](crate::density_map::clip_density_map) if clipping planes are used),
+//!  3. [`triangulate_density_map`]: marching cubes triangulation of the density map.
+//!
+//! The stages can be run individually, e.g. to only compute a density map or to triangulate a density map that was
+//! modified or stored in between. Every stage takes a [`ReconstructionWorkspace`] to reuse allocated memory over
+//! subsequent calls. The splash detection, the output of splash particles and the interpolation of attributes are
+//! not part of the stages, they are only performed by the complete reconstruction.
+//!
+//! Composing the stages results in the same mesh as [`reconstruct_surface`](crate::reconstruct_surface) without
+//! spatial decomposition:
+//! ```
+//! use splashsurf_lib::mesh::TriMesh3d;
+//! use splashsurf_lib::nalgebra::Vector3;
+//! use splashsurf_lib::{MarchingCubesVariant,
+//!     grid_for_reconstruction, reconstruct_surface, stages, Parameters, ReconstructionError,
+//!     ReconstructionWorkspace, SplashOutput,
+//! };
+//!
+//! # fn main() -> Result<(), ReconstructionError<i64, f64>> {
+//! let particle_radius = 0.025;
+//! // A block of 10x10x10 particles
+//! let particle_positions = (0..1000)
+//!     .map(|i| Vector3::new((i % 10) as f64, ((i / 10) % 10) as f64, (i / 100) as f64))
+//!     .map(|x| x * 2.0 * particle_radius)
+//!     .collect::<Vec<_>>();
+//! let parameters = Parameters {
+//!     particle_radius,
+//!     rest_density: 1000.0,
+//!     compact_support_radius: 4.0 * particle_radius,
+//!     cube_size: 0.75 * particle_radius,
+//!     iso_surface_threshold: 0.6,
+//!     triangulation_scheme: MarchingCubesVariant::Classic,
+//!     vertex_snapping: None,
+//!     splash_detection: None,
+//!     splash_particle_output: SplashOutput::Ignore,
+//!     domain_aabb: None,
+//!     clipping_planes: Vec::new(),
+//!     enable_multi_threading: false,
+//!     deterministic: false,
+//!     progress_interval: None,
+//!     temporal_density_smoothing: None,
+//!     spatial_decomposition: None,
+//! };
+//!
+//! let mut workspace = ReconstructionWorkspace::default();
+//! let grid = grid_for_reconstruction::<i64, f64>(
+//!     &particle_positions,
+//!     parameters.particle_radius,
+//!     parameters.compact_support_radius,
+//!     parameters.cube_size,
+//!     None,
+//!     parameters.enable_multi_threading,
+//! )?;
+//! let particle_densities =
+//!     stages::compute_particle_densities(&grid, &particle_positions, &parameters, &mut workspace)?;
+//! let density_map = stages::generate_density_map(
+//!     &grid,
+//!     &particle_positions,
+//!     &particle_densities,
+//!     None,
+//!     &parameters,
+//!     &mut workspace,
+//! )?;
+//! let mut mesh = TriMesh3d::default();
+//! stages::triangulate_density_map(&grid, &density_map, &parameters, &mut workspace, &mut mesh)?;
+//!
+//! let reconstruction = reconstruct_surface::<i64, f64>(&particle_positions, &parameters)?;
+//! assert_eq!(mesh.vertices, reconstruction.mesh().vertices);
+//! assert_eq!(mesh.triangles, reconstruction.mesh().triangles);
+//!
+//! // Reuse the memory of the density map in the next call of `generate_density_map`
+//! workspace.recycle_density_map(density_map);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::density_map::DensityMap;
+use crate::mesh::TriMesh3d;
+use crate::workspace::ReconstructionWorkspace;
+use crate::{
+    marching_cubes, new_map, profile, reconstruction, Index, Parameters, Real, ReconstructionError,
+    UniformGrid,
+};
+use nalgebra::Vector3;
+
+/// Performs the neighborhood search and returns the density of every particle
+///
+/// The neighbor lists are stored in the workspace and reused by subsequent calls. All particle positions
+/// have to be finite, otherwise an [`InvalidParticleData`](ReconstructionError::InvalidParticleData) error is returned.
+pub fn compute_particle_densities<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    particle_positions: &[Vector3<R>],
+    parameters: &Parameters<R>,
+    workspace: &mut ReconstructionWorkspace<I, R>,
+) -> Result<Vec<R>, ReconstructionError<I, R>> {
+    profile!("stages::compute_particle_densities");
+    reconstruction::check_particle_positions(particle_positions)?;
+
+    let mut local = workspace.get_local().borrow_mut();
+    let mut particle_densities = Vec::new();
+    reconstruction::compute_particle_densities_and_neighbors(
+        grid,
+        particle_positions,
+        parameters,
+        &mut local.particle_neighbor_lists,
+        &mut particle_densities,
+    );
+    Ok(particle_densities)
+}
+
+/// Generates the sparse density map of the (active) particles on the points of the grid
+///
+/// If `active_particles` is given, only these particles contribute to the density map. The clipping planes of
+/// the parameters are not applied, see [`clip_density_map`](crate::density_map::clip_density_map). Like in the
+/// complete reconstruction, the sequential and non-deterministic generation reuses the memory of a map that
+/// was stored in the workspace with [`ReconstructionWorkspace::recycle_density_map`].
+pub fn generate_density_map<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    particle_positions: &[Vector3<R>],
+    particle_densities: &[R],
+    active_particles: Option<&[usize]>,
+    parameters: &Parameters<R>,
+    workspace: &mut ReconstructionWorkspace<I, R>,
+) -> Result<DensityMap<I, R>, ReconstructionError<I, R>> {
+    profile!("stages::generate_density_map");
+    assert_eq!(
+        particle_densities.len(),
+        particle_positions.len(),
+        "There has to be one density value per particle"
+    );
+
+    let mut local = workspace.get_local().borrow_mut();
+    let mut density_map = if !parameters.enable_multi_threading && !parameters.deterministic {
+        std::mem::replace(&mut local.density_map, new_map().into())
+    } else {
+        new_map().into()
+    };
+    reconstruction::generate_density_map(
+        grid,
+        None,
+        particle_positions,
+        particle_densities,
+        active_particles,
+        parameters,
+        &mut density_map,
+    )?;
+    Ok(density_map)
+}
+
+/// Performs a marching cubes triangulation of the density map and appends the triangles to the given mesh
+///
+/// Memory for the vertices and triangles of the mesh is reserved based on the number of vertices and triangles
+/// appended in the previous call with the same workspace.
+pub fn triangulate_density_map<I: Index, R: Real>(
+    grid: &UniformGrid<I, R>,
+    density_map: &DensityMap<I, R>,
+    parameters: &Parameters<R>,
+    workspace: &mut ReconstructionWorkspace<I, R>,
+    mesh: &mut TriMesh3d<R>,
+) -> Result<(), ReconstructionError<I, R>> {
+    profile!("stages::triangulate_density_map");
+
+    let (vertex_offset, triangle_offset) = (mesh.vertices.len(), mesh.triangles.len());
+    workspace.prime_mesh(mesh);
+    marching_cubes::triangulate_density_map_append_with_cells(
+        grid,
+        None,
+        density_map,
+        parameters.iso_surface_threshold,
+        parameters.vertex_snapping,
+        parameters.triangulation_scheme,
+        mesh,
+        None,
+    )?;
+    workspace.record_mesh_sizes(
+        mesh.vertices.len() - vertex_offset,
+        mesh.triangles.len() - triangle_offset,
+    );
+    Ok(())
+}
@@ -0,0 +1,403 @@
+//! Lightweight wall-clock timings of the reconstruction stages that are available without the `profiling` feature
+//!
+//! The [`profile`](crate::profile) macro records the duration of its scope into a [`TimingsCollector`] if
+//! a collector is attached to the current thread (see [`TimingsCollector::attach`]). Only the two outermost
+//! levels of scopes are recorded: the stages (scopes entered while no other scope is active) and their direct
+//! sub-stages. Scopes entered on other threads (e.g. the workers of a parallel reconstruction) are not recorded.
+//! If no collector is attached to any thread, the overhead of the macro is a single branch.
+//!
+//! To record the timings of a reconstruction performed with [`reconstruct_surface_inplace`](crate::reconstruct_surface_inplace),
+//! a collector can be set using [`SurfaceReconstruction::set_timings_collector`](crate::SurfaceReconstruction::set_timings_collector).
+//! The resulting [`Timings`] are stored in the [`ReconstructionStats`](crate::ReconstructionStats) of the reconstruction.
+//!
+//! While a collector is attached, the octree based reconstruction additionally records the time spent on every
+//! leaf of the octree ([`LeafTimings`]) to analyze the load balance between the leaves. These timings are not
+//! recorded by the collector but stored in the [`ReconstructionStats`](crate::ReconstructionStats) directly.
+
+use crate::utils::Instant;
+use parking_lot::Mutex;
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Number of currently attached collectors over all threads, used to skip the thread local lookup if no collector is attached
+static ATTACHED_COLLECTORS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The collector attached to the current thread and the state of its scope stack
+    static CURRENT: RefCell<ThreadState> = RefCell::new(ThreadState::default());
+}
+
+/// State of the timing scopes of a thread
+#[derive(Default)]
+struct ThreadState {
+    /// The collector receiving the timings of this thread
+    collector: Option<TimingsCollector>,
+    /// Number of currently active scopes on this thread
+    depth: usize,
+    /// Name of the currently active stage (outermost scope)
+    stage: Option<&'static str>,
+}
+
+/// Accumulated timing of a stage or sub-stage
+#[derive(Clone, Debug)]
+pub struct StageTiming {
+    /// Name of the stage, i.e. the name passed to the [`profile`](crate::profile) macro
+    pub name: &'static str,
+    /// Sum of the durations of all calls of the stage
+    pub duration: Duration,
+    /// Number of times the stage was entered
+    pub num_calls: usize,
+    /// Timings of the direct sub-stages in the order in which they were first entered (always empty for sub-stages)
+    pub sub_stages: Vec<StageTiming>,
+}
+
+impl StageTiming {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            duration: Duration::ZERO,
+            num_calls: 0,
+            sub_stages: Vec::new(),
+        }
+    }
+}
+
+/// Returns the timing with the given name, inserting it at the end if it does not exist
+fn find_or_insert<'a>(
+    timings: &'a mut Vec<StageTiming>,
+    name: &'static str,
+) -> &'a mut StageTiming {
+    let index = match timings.iter().position(|timing| timing.name == name) {
+        Some(index) => index,
+        None => {
+            timings.push(StageTiming::new(name));
+            timings.len() - 1
+        }
+    };
+    &mut timings[index]
+}
+
+/// Wall-clock timings of the stages recorded by a [`TimingsCollector`]
+#[derive(Clone, Debug, Default)]
+pub struct Timings {
+    /// Total time during which the collector was attached
+    pub total: Duration,
+    /// Timings of the stages in the order in which they were first entered
+    pub stages: Vec<StageTiming>,
+}
+
+impl Timings {
+    /// Returns the sum of the durations of all stages
+    pub fn stages_duration(&self) -> Duration {
+        self.stages.iter().map(|stage| stage.duration).sum()
+    }
+
+    /// Returns the timing of the stage with the given name
+    pub fn stage(&self, name: &str) -> Option<&StageTiming> {
+        self.stages.iter().find(|stage| stage.name == name)
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |duration: Duration, total: Duration| {
+            if total.is_zero() {
+                0.0
+            } else {
+                duration.as_secs_f64() / total.as_secs_f64() * 100.0
+            }
+        };
+
+        write!(f, "total: {:.2}ms", self.total.as_secs_f64() * 1000.0)?;
+        for stage in &self.stages {
+            write!(
+                f,
+                "\n  {}: {:.2}ms ({:.1}%, {} {})",
+                stage.name,
+                stage.duration.as_secs_f64() * 1000.0,
+                percent(stage.duration, self.total),
+                stage.num_calls,
+                if stage.num_calls > 1 { "calls" } else { "call" }
+            )?;
+            for sub_stage in &stage.sub_stages {
+                write!(
+                    f,
+                    "\n    {}: {:.2}ms ({:.1}%, {} {})",
+                    sub_stage.name,
+                    sub_stage.duration.as_secs_f64() * 1000.0,
+                    percent(sub_stage.duration, stage.duration),
+                    sub_stage.num_calls,
+                    if sub_stage.num_calls > 1 {
+                        "calls"
+                    } else {
+                        "call"
+                    }
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Wall-clock time of the surface reconstruction of a single octree leaf
+#[derive(Clone, Debug)]
+pub struct LeafTiming {
+    /// Id of the octree node, see [`OctreeNode::id`](crate::octree::OctreeNode::id)
+    pub node_id: usize,
+    /// Number of particles of the leaf (including ghost particles)
+    pub num_particles: usize,
+    /// Time required for the reconstruction of the leaf
+    pub duration: Duration,
+}
+
+/// Wall-clock times of all octree leaves of a reconstruction, see [`ReconstructionStats::leaf_timings`](crate::ReconstructionStats::leaf_timings)
+#[derive(Clone, Debug, Default)]
+pub struct LeafTimings {
+    /// Timings of all leaves sorted by decreasing duration
+    pub leaves: Vec<LeafTiming>,
+}
+
+impl LeafTimings {
+    /// Sorts the given leaf timings by decreasing duration
+    pub(crate) fn new(mut leaves: Vec<LeafTiming>) -> Self {
+        leaves.sort_by(|a, b| b.duration.cmp(&a.duration));
+        Self { leaves }
+    }
+
+    /// Returns the timings of the (up to) `n` slowest leaves
+    pub fn slowest(&self, n: usize) -> &[LeafTiming] {
+        &self.leaves[..n.min(self.leaves.len())]
+    }
+
+    /// Returns the mean duration of all leaves
+    pub fn mean(&self) -> Duration {
+        if self.leaves.is_empty() {
+            Duration::ZERO
+        } else {
+            self.leaves
+                .iter()
+                .map(|leaf| leaf.duration)
+                .sum::<Duration>()
+                / self.leaves.len() as u32
+        }
+    }
+
+    /// Returns the duration that is not exceeded by the given fraction of the leaves (nearest-rank percentile, e.g. `0.95` for the p95 leaf time)
+    pub fn percentile(&self, fraction: f64) -> Duration {
+        if self.leaves.is_empty() {
+            return Duration::ZERO;
+        }
+
+        let n = self.leaves.len();
+        let rank = ((fraction.clamp(0.0, 1.0) * n as f64).ceil() as usize).clamp(1, n);
+        // The leaves are sorted by decreasing duration
+        self.leaves[n - rank].duration
+    }
+
+    /// Returns the ratio of the duration of the slowest leaf to the mean duration of all leaves (`1.0` for a perfect balance)
+    pub fn imbalance_ratio(&self) -> f64 {
+        let mean = self.mean();
+        match self.leaves.first() {
+            Some(slowest) if !mean.is_zero() => slowest.duration.as_secs_f64() / mean.as_secs_f64(),
+            _ => 1.0,
+        }
+    }
+}
+
+impl fmt::Display for LeafTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} leaves", self.leaves.len())?;
+        if let Some(slowest) = self.leaves.first() {
+            write!(
+                f,
+                ", slowest: {:.2}ms (leaf {}, {} particles), p95: {:.2}ms, mean: {:.2}ms, imbalance ratio (slowest/mean): {:.2}",
+                slowest.duration.as_secs_f64() * 1000.0,
+                slowest.node_id,
+                slowest.num_particles,
+                self.percentile(0.95).as_secs_f64() * 1000.0,
+                self.mean().as_secs_f64() * 1000.0,
+                self.imbalance_ratio()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Thread-safe sink for the stage timings recorded by the [`profile`](crate::profile) macro, clones share the same data
+#[derive(Clone, Debug, Default)]
+pub struct TimingsCollector {
+    timings: Arc<Mutex<Timings>>,
+}
+
+impl TimingsCollector {
+    /// Returns a new collector without any recorded timings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attaches the collector to the current thread until the returned guard is dropped
+    ///
+    /// Attaching a collector while another collector is attached to the thread replaces the other collector
+    /// until the guard is dropped. The guard should be dropped outside of any [`profile`](crate::profile) scopes.
+    pub fn attach(&self) -> AttachGuard {
+        let previous = CURRENT.with(|state| {
+            std::mem::replace(
+                &mut *state.borrow_mut(),
+                ThreadState {
+                    collector: Some(self.clone()),
+                    ..ThreadState::default()
+                },
+            )
+        });
+        ATTACHED_COLLECTORS.fetch_add(1, Ordering::Relaxed);
+
+        AttachGuard {
+            collector: self.clone(),
+            previous: Some(previous),
+            attach_time: Instant::now(),
+        }
+    }
+
+    /// Returns a copy of the timings recorded so far
+    pub fn timings(&self) -> Timings {
+        self.timings.lock().clone()
+    }
+
+    /// Clears all recorded timings
+    pub fn reset(&self) {
+        *self.timings.lock() = Timings::default();
+    }
+}
+
+/// Guard that detaches a [`TimingsCollector`] from the current thread when dropped, see [`TimingsCollector::attach`]
+pub struct AttachGuard {
+    collector: TimingsCollector,
+    /// State of the thread before attaching the collector, restored when the guard is dropped
+    previous: Option<ThreadState>,
+    attach_time: Instant,
+}
+
+impl Drop for AttachGuard {
+    fn drop(&mut self) {
+        self.collector.timings.lock().total += self.attach_time.elapsed();
+
+        let previous = self.previous.take().unwrap_or_default();
+        CURRENT.with(|state| *state.borrow_mut() = previous);
+        ATTACHED_COLLECTORS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Returns whether a collector is attached to the current thread
+pub(crate) fn is_attached() -> bool {
+    ATTACHED_COLLECTORS.load(Ordering::Relaxed) > 0
+        && CURRENT.with(|state| state.borrow().collector.is_some())
+}
+
+/// Guard recording the duration of a scope of the [`profile`](crate::profile) macro when it is dropped
+#[doc(hidden)]
+pub struct ScopeGuard {
+    collector: TimingsCollector,
+    /// Name of the enclosing stage if this scope is a sub-stage
+    stage: Option<&'static str>,
+    name: &'static str,
+    enter_time: Instant,
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let duration = self.enter_time.elapsed();
+        {
+            let mut timings = self.collector.timings.lock();
+            let timing = match self.stage {
+                None => find_or_insert(&mut timings.stages, self.name),
+                Some(stage) => find_or_insert(
+                    &mut find_or_insert(&mut timings.stages, stage).sub_stages,
+                    self.name,
+                ),
+            };
+            timing.duration += duration;
+        }
+
+        CURRENT.with(|state| {
+            let mut state = state.borrow_mut();
+            state.depth = state.depth.saturating_sub(1);
+            if state.depth == 0 {
+                state.stage = None;
+            }
+        });
+    }
+}
+
+/// Guard that only tracks the depth of scopes that are too deeply nested to be recorded
+#[doc(hidden)]
+pub struct DepthGuard;
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|state| {
+            let mut state = state.borrow_mut();
+            state.depth = state.depth.saturating_sub(1);
+        });
+    }
+}
+
+/// Guard returned by [`enter`], records the scope if it is a stage or sub-stage
+#[doc(hidden)]
+pub enum Guard {
+    Scope(ScopeGuard),
+    Depth(DepthGuard),
+}
+
+/// Enters a timing scope with the given name, used by the [`profile`](crate::profile) macro
+#[doc(hidden)]
+#[inline]
+pub fn enter(name: &'static str) -> Option<Guard> {
+    if ATTACHED_COLLECTORS.load(Ordering::Relaxed) == 0 {
+        None
+    } else {
+        enter_attached(name)
+    }
+}
+
+#[cold]
+fn enter_attached(name: &'static str) -> Option<Guard> {
+    CURRENT.with(|state| {
+        let mut state = state.borrow_mut();
+        let collector = state.collector.clone()?;
+
+        let depth = state.depth;
+        state.depth += 1;
+
+        let stage = match depth {
+            0 => {
+                state.stage = Some(name);
+                None
+            }
+            1 => state.stage,
+            _ => return Some(Guard::Depth(DepthGuard)),
+        };
+
+        {
+            // Insert the timing on entering to keep the order of first entry
+            let mut timings = collector.timings.lock();
+            let timing = match stage {
+                None => find_or_insert(&mut timings.stages, name),
+                Some(stage) => find_or_insert(
+                    &mut find_or_insert(&mut timings.stages, stage).sub_stages,
+                    name,
+                ),
+            };
+            timing.num_calls += 1;
+        }
+
+        Some(Guard::Scope(ScopeGuard {
+            collector,
+            stage,
+            name,
+            enter_time: Instant::now(),
+        }))
+    })
+}
@@ -1,6 +1,10 @@
 //! Helper types for cartesian coordinate system topology
 
-use num::{CheckedAdd, CheckedSub, One};
+pub mod cube;
+pub mod octant;
+pub mod square;
+
+use num::{CheckedAdd, CheckedSub, One, Zero};
 use std::ops::{Add, Sub};
 
 /// Direction on a number line/coordinate axis or identifiers for the end points of a line
@@ -31,6 +35,24 @@ pub struct DirectedAxis {
     pub direction: Direction,
 }
 
+/// Offset from a cell (or point) of a 3D cartesian grid to one of its 26 neighbors, i.e. a step of at most one along each axis
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct NeighborOffset {
+    /// The step along each axis, `None` if the neighbor has the same index along the axis
+    steps: [Option<Direction>; 3],
+}
+
+/// Classes of neighbors in a 3D cartesian grid, based on the shared elements of neighboring cells
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Connectivity {
+    /// The 6 neighbors sharing a face with a cell
+    Face6,
+    /// The 18 neighbors sharing a face or an edge with a cell
+    FaceEdge18,
+    /// All 26 neighbors sharing a face, an edge or a vertex with a cell
+    FaceEdgeVertex26,
+}
+
 /// Collection that stores one value per unique [`DirectedAxis`], can be used e.g. to store neighbors in a cartesian grid
 #[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
 pub struct DirectedAxisArray<T> {
@@ -359,6 +381,203 @@ fn test_directed_axis_all_possible_consistency() {
     }
 }
 
+impl NeighborOffset {
+    /// Constructs a new offset from the steps along each axis, returns `None` if there is no step along any axis
+    /// ```
+    /// use crate::splashsurf_lib::topology::{Direction, NeighborOffset};
+    /// assert!(NeighborOffset::new([Some(Direction::Positive), None, None]).is_some());
+    /// assert!(NeighborOffset::new([None, None, None]).is_none());
+    /// ```
+    pub const fn new(steps: [Option<Direction>; 3]) -> Option<Self> {
+        if steps[0].is_none() && steps[1].is_none() && steps[2].is_none() {
+            None
+        } else {
+            Some(Self { steps })
+        }
+    }
+
+    /// Returns the steps along each axis, `None` if there is no step along the axis
+    #[inline(always)]
+    pub const fn steps(&self) -> &[Option<Direction>; 3] {
+        &self.steps
+    }
+
+    /// Returns the step along the given axis, `None` if there is no step along the axis
+    #[inline(always)]
+    pub const fn step(&self, axis: Axis) -> Option<Direction> {
+        self.steps[axis.dim()]
+    }
+
+    /// Returns the number of axes with a step, i.e. 1 for face, 2 for edge and 3 for vertex neighbors
+    /// ```
+    /// use crate::splashsurf_lib::topology::{Axis, DirectedAxis, Direction, NeighborOffset};
+    /// let offset = NeighborOffset::from(DirectedAxis::new(Axis::Y, Direction::Negative));
+    /// assert_eq!(offset.num_steps(), 1);
+    /// ```
+    pub const fn num_steps(&self) -> usize {
+        let mut num_steps = 0;
+        let mut dim = 0;
+        while dim < 3 {
+            if self.steps[dim].is_some() {
+                num_steps += 1;
+            }
+            dim += 1;
+        }
+        num_steps
+    }
+
+    /// Returns the offset as signed integer steps, i.e. each component is -1, 0 or 1
+    /// ```
+    /// use crate::splashsurf_lib::topology::{Direction, NeighborOffset};
+    /// let offset = NeighborOffset::new([Some(Direction::Negative), None, Some(Direction::Positive)]).unwrap();
+    /// assert_eq!(offset.to_ijk(), [-1, 0, 1]);
+    /// ```
+    pub const fn to_ijk(&self) -> [i32; 3] {
+        let mut ijk = [0; 3];
+        let mut dim = 0;
+        while dim < 3 {
+            ijk[dim] = match self.steps[dim] {
+                Some(Direction::Negative) => -1,
+                Some(Direction::Positive) => 1,
+                None => 0,
+            };
+            dim += 1;
+        }
+        ijk
+    }
+
+    /// Returns the offset in the opposite direction
+    pub const fn opposite(&self) -> Self {
+        let mut steps = self.steps;
+        let mut dim = 0;
+        while dim < 3 {
+            steps[dim] = match steps[dim] {
+                Some(direction) => Some(direction.opposite()),
+                None => None,
+            };
+            dim += 1;
+        }
+        Self { steps }
+    }
+
+    /// Applies the offset to the given index array, returns `None` on overflow
+    /// ```
+    /// use crate::splashsurf_lib::topology::{Direction, NeighborOffset};
+    /// let offset = NeighborOffset::new([Some(Direction::Negative), None, Some(Direction::Positive)]).unwrap();
+    /// assert_eq!(offset.checked_apply(&[1, 2, 3]), Some([0, 2, 4]));
+    /// assert_eq!(offset.checked_apply(&[0u32, 2, 3]), None);
+    /// ```
+    #[inline(always)]
+    pub fn checked_apply<N: Clone + CheckedAdd<Output = N> + CheckedSub<Output = N> + One>(
+        &self,
+        index: &[N; 3],
+    ) -> Option<[N; 3]> {
+        let mut index = index.clone();
+        for (value, step) in index.iter_mut().zip(self.steps.iter()) {
+            if let Some(direction) = step {
+                *value = direction.checked_apply_step(value.clone(), N::one())?;
+            }
+        }
+        Some(index)
+    }
+
+    /// Applies the offset to the given index array, returns `None` if the result is outside of the range `0..upper_bounds[i]` along any axis
+    /// ```
+    /// use crate::splashsurf_lib::topology::{Direction, NeighborOffset};
+    /// let offset = NeighborOffset::new([Some(Direction::Negative), None, Some(Direction::Positive)]).unwrap();
+    /// assert_eq!(offset.checked_apply_within(&[1, 2, 3], &[5, 5, 5]), Some([0, 2, 4]));
+    /// assert_eq!(offset.checked_apply_within(&[0, 2, 3], &[5, 5, 5]), None);
+    /// assert_eq!(offset.checked_apply_within(&[1, 2, 4], &[5, 5, 5]), None);
+    /// ```
+    #[inline(always)]
+    pub fn checked_apply_within<
+        N: Clone + PartialOrd + Zero + CheckedAdd<Output = N> + CheckedSub<Output = N> + One,
+    >(
+        &self,
+        index: &[N; 3],
+        upper_bounds: &[N; 3],
+    ) -> Option<[N; 3]> {
+        let index = self.checked_apply(index)?;
+        index
+            .iter()
+            .zip(upper_bounds.iter())
+            .all(|(value, upper_bound)| *value >= N::zero() && value < upper_bound)
+            .then_some(index)
+    }
+}
+
+impl From<DirectedAxis> for NeighborOffset {
+    /// Returns the offset to the face neighbor in the given direction
+    fn from(directed_axis: DirectedAxis) -> Self {
+        let mut steps = [None; 3];
+        steps[directed_axis.axis.dim()] = Some(directed_axis.direction);
+        Self { steps }
+    }
+}
+
+/// Returns the offsets with at most the given number of steps, ordered lexicographically by their steps along x, y and z (negative, none, positive)
+const fn neighbor_offsets<const N: usize>(max_steps: usize) -> [NeighborOffset; N] {
+    const STEPS: [Option<Direction>; 3] =
+        [Some(Direction::Negative), None, Some(Direction::Positive)];
+
+    let mut offsets = [NeighborOffset { steps: [None; 3] }; N];
+    let mut n = 0;
+    let mut i = 0;
+    while i < 27 {
+        let offset = NeighborOffset {
+            steps: [STEPS[i / 9], STEPS[(i / 3) % 3], STEPS[i % 3]],
+        };
+        let num_steps = offset.num_steps();
+        if num_steps > 0 && num_steps <= max_steps {
+            offsets[n] = offset;
+            n += 1;
+        }
+        i += 1;
+    }
+    assert!(n == N);
+    offsets
+}
+
+const FACE_NEIGHBOR_OFFSETS: [NeighborOffset; 6] = neighbor_offsets(1);
+const FACE_EDGE_NEIGHBOR_OFFSETS: [NeighborOffset; 18] = neighbor_offsets(2);
+const ALL_NEIGHBOR_OFFSETS: [NeighborOffset; 26] = neighbor_offsets(3);
+
+impl Connectivity {
+    /// Returns the offsets to all neighbors of this connectivity class, ordered lexicographically by their steps along x, y and z
+    /// ```
+    /// use crate::splashsurf_lib::topology::Connectivity;
+    /// assert_eq!(Connectivity::Face6.offsets().len(), 6);
+    /// assert_eq!(Connectivity::FaceEdge18.offsets().len(), 18);
+    /// assert_eq!(Connectivity::FaceEdgeVertex26.offsets().len(), 26);
+    /// ```
+    pub const fn offsets(&self) -> &'static [NeighborOffset] {
+        match self {
+            Connectivity::Face6 => &FACE_NEIGHBOR_OFFSETS,
+            Connectivity::FaceEdge18 => &FACE_EDGE_NEIGHBOR_OFFSETS,
+            Connectivity::FaceEdgeVertex26 => &ALL_NEIGHBOR_OFFSETS,
+        }
+    }
+
+    /// Iterator over the indices of all neighbors of the given index that are inside of the range `0..upper_bounds[i]` along each axis
+    /// ```
+    /// use crate::splashsurf_lib::topology::Connectivity;
+    /// // A corner of a 3x3x3 grid
+    /// assert_eq!(Connectivity::FaceEdgeVertex26.neighbors_within(&[0, 0, 0], &[3, 3, 3]).count(), 7);
+    /// ```
+    pub fn neighbors_within<
+        'a,
+        N: Clone + PartialOrd + Zero + CheckedAdd<Output = N> + CheckedSub<Output = N> + One,
+    >(
+        &self,
+        index: &'a [N; 3],
+        upper_bounds: &'a [N; 3],
+    ) -> impl Iterator<Item = [N; 3]> + 'a {
+        self.offsets()
+            .iter()
+            .filter_map(move |offset| offset.checked_apply_within(index, upper_bounds))
+    }
+}
+
 impl<T> DirectedAxisArray<T> {
     /// Constructs a new array and fills it with values produced by the given closure
     pub fn new_with<F: FnMut(&DirectedAxis) -> T>(f: F) -> Self {
@@ -402,3 +621,439 @@ impl<T> DirectedAxisArray<T> {
         self.data.iter()
     }
 }
+
+#[test]
+fn test_neighbor_offsets() {
+    for connectivity in [
+        Connectivity::Face6,
+        Connectivity::FaceEdge18,
+        Connectivity::FaceEdgeVertex26,
+    ] {
+        let offsets = connectivity.offsets();
+        let max_steps = match connectivity {
+            Connectivity::Face6 => 1,
+            Connectivity::FaceEdge18 => 2,
+            Connectivity::FaceEdgeVertex26 => 3,
+        };
+
+        // All offsets are unique, sorted and contain their opposite
+        assert!(offsets.windows(2).all(|w| w[0].to_ijk() < w[1].to_ijk()));
+        assert!(offsets.iter().all(|offset| offset.num_steps() <= max_steps));
+        assert!(offsets
+            .iter()
+            .all(|offset| offsets.contains(&offset.opposite())));
+    }
+
+    // The face neighbors correspond to the directed axes
+    for directed_axis in DirectedAxis::all_possible() {
+        assert!(Connectivity::Face6
+            .offsets()
+            .contains(&NeighborOffset::from(*directed_axis)));
+    }
+}
+
+#[test]
+fn test_neighbor_counts() {
+    let upper_bounds = [4, 4, 4];
+    let count = |connectivity: Connectivity, index: [i32; 3]| {
+        connectivity
+            .neighbors_within(&index, &upper_bounds)
+            .inspect(|neighbor| {
+                assert!(neighbor.iter().all(|&v| v >= 0 && v < 4));
+                assert_ne!(*neighbor, index);
+            })
+            .count()
+    };
+
+    let corner = [0, 0, 0];
+    let edge = [0, 3, 1];
+    let face = [1, 2, 3];
+    let interior = [1, 2, 2];
+
+    assert_eq!(count(Connectivity::Face6, corner), 3);
+    assert_eq!(count(Connectivity::Face6, edge), 4);
+    assert_eq!(count(Connectivity::Face6, face), 5);
+    assert_eq!(count(Connectivity::Face6, interior), 6);
+
+    assert_eq!(count(Connectivity::FaceEdge18, corner), 6);
+    assert_eq!(count(Connectivity::FaceEdge18, edge), 9);
+    assert_eq!(count(Connectivity::FaceEdge18, face), 13);
+    assert_eq!(count(Connectivity::FaceEdge18, interior), 18);
+
+    assert_eq!(count(Connectivity::FaceEdgeVertex26, corner), 7);
+    assert_eq!(count(Connectivity::FaceEdgeVertex26, edge), 11);
+    assert_eq!(count(Connectivity::FaceEdgeVertex26, face), 17);
+    assert_eq!(count(Connectivity::FaceEdgeVertex26, interior), 26);
+
+    // Single cell grid
+    assert_eq!(
+        Connectivity::FaceEdgeVertex26
+            .neighbors_within(&[0, 0, 0], &[1, 1, 1])
+            .count(),
+        0
+    );
+}
+
+/// Abbreviated type alias for cartesian coordinate axes in 2D
+pub type Axis2d = CartesianAxis2d;
+
+/// The cartesian coordinate axes in 2D
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum CartesianAxis2d {
+    /// The x-axis
+    X = 0,
+    /// The y-axis
+    Y = 1,
+}
+
+/// Identifies a direction along a specific cartesian axis in 2D
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct DirectedAxis2d {
+    pub axis: Axis2d,
+    pub direction: Direction,
+}
+
+/// Offset from a cell (or point) of a 2D cartesian grid to one of its 8 neighbors, i.e. a step of at most one along each axis
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct NeighborOffset2d {
+    /// The step along each axis, `None` if the neighbor has the same index along the axis
+    steps: [Option<Direction>; 2],
+}
+
+/// Classes of neighbors in a 2D cartesian grid, based on the shared elements of neighboring cells
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Connectivity2d {
+    /// The 4 neighbors sharing an edge with a cell
+    Edge4,
+    /// All 8 neighbors sharing an edge or a vertex with a cell
+    EdgeVertex8,
+}
+
+impl CartesianAxis2d {
+    /// Returns a reference to an array containing all 2D cartesian axes
+    /// ```
+    /// use crate::splashsurf_lib::topology::Axis2d;
+    /// assert_eq!(Axis2d::all_possible(), &[Axis2d::X, Axis2d::Y]);
+    /// ```
+    #[inline(always)]
+    pub const fn all_possible() -> &'static [Axis2d; 2] {
+        &ALL_AXES_2D
+    }
+
+    /// Converts the cartesian axis into the corresponding 2D dimension index (X=0, Y=1)
+    #[inline(always)]
+    pub const fn dim(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the other axis that is orthogonal to the current axis
+    /// ```
+    /// use crate::splashsurf_lib::topology::Axis2d;
+    /// assert_eq!(Axis2d::X.orthogonal_axis(), Axis2d::Y);
+    /// assert_eq!(Axis2d::Y.orthogonal_axis(), Axis2d::X);
+    /// ```
+    #[inline(always)]
+    pub const fn orthogonal_axis(self) -> Self {
+        ALL_AXES_2D[1 - self.dim()]
+    }
+
+    /// Combines this coordinate axis with a direction into a DirectedAxis2d
+    #[inline(always)]
+    pub const fn with_direction(self, direction: Direction) -> DirectedAxis2d {
+        DirectedAxis2d::new(self, direction)
+    }
+}
+
+const ALL_AXES_2D: [Axis2d; 2] = [Axis2d::X, Axis2d::Y];
+
+impl DirectedAxis2d {
+    /// Returns a reference to an array of all possible directed axes in 2D
+    #[inline(always)]
+    pub const fn all_possible() -> &'static [DirectedAxis2d; 4] {
+        &ALL_DIRECTED_AXES_2D
+    }
+
+    /// Constructs a new directed axis
+    #[inline(always)]
+    pub const fn new(axis: Axis2d, direction: Direction) -> Self {
+        Self { axis, direction }
+    }
+
+    /// Returns a directed axis with the opposite direction
+    #[inline(always)]
+    pub const fn opposite(&self) -> Self {
+        Self::new(self.axis, self.direction.opposite())
+    }
+
+    /// Converts the directed axis into a unique index in the range `(0..=3)`
+    #[inline(always)]
+    pub const fn to_usize(&self) -> usize {
+        self.axis as usize + (self.direction as usize * 2)
+    }
+
+    /// Converts an index in the range `(0..=3)` to the corresponding directed axis, panics if the index is out of range
+    #[inline(always)]
+    pub const fn from_usize(n: usize) -> Self {
+        Self::all_possible()[n]
+    }
+
+    /// Applies the given step in the direction of this directed axis to the given index array, returns `None` on overflow
+    /// ```
+    /// use crate::splashsurf_lib::topology::{Axis2d, DirectedAxis2d, Direction};
+    /// assert_eq!(DirectedAxis2d::new(Axis2d::Y, Direction::Negative)
+    ///                 .checked_apply_step(&[1,2], 3), Some([1,-1]));
+    /// ```
+    #[inline(always)]
+    pub fn checked_apply_step<N: Clone + CheckedAdd<Output = N> + CheckedSub<Output = N>>(
+        &self,
+        index: &[N; 2],
+        step: N,
+    ) -> Option<[N; 2]> {
+        let mut index = index.clone();
+        index[self.axis.dim()] = self
+            .direction
+            .checked_apply_step(index[self.axis.dim()].clone(), step)?;
+        Some(index)
+    }
+}
+
+const ALL_DIRECTED_AXES_2D: [DirectedAxis2d; 4] = [
+    DirectedAxis2d::new(Axis2d::X, Direction::Negative),
+    DirectedAxis2d::new(Axis2d::Y, Direction::Negative),
+    DirectedAxis2d::new(Axis2d::X, Direction::Positive),
+    DirectedAxis2d::new(Axis2d::Y, Direction::Positive),
+];
+
+#[test]
+fn test_directed_axis_2d_all_possible_consistency() {
+    let all_directed_axes = DirectedAxis2d::all_possible();
+    for (i, ax) in all_directed_axes.iter().enumerate() {
+        assert_eq!(ax.to_usize(), i);
+        assert_eq!(*ax, DirectedAxis2d::from_usize(i));
+        assert_eq!(ax.opposite().opposite(), *ax);
+        assert_ne!(ax.opposite(), *ax);
+    }
+}
+
+impl NeighborOffset2d {
+    /// Constructs a new offset from the steps along each axis, returns `None` if there is no step along any axis
+    pub const fn new(steps: [Option<Direction>; 2]) -> Option<Self> {
+        if steps[0].is_none() && steps[1].is_none() {
+            None
+        } else {
+            Some(Self { steps })
+        }
+    }
+
+    /// Returns the steps along each axis, `None` if there is no step along the axis
+    #[inline(always)]
+    pub const fn steps(&self) -> &[Option<Direction>; 2] {
+        &self.steps
+    }
+
+    /// Returns the step along the given axis, `None` if there is no step along the axis
+    #[inline(always)]
+    pub const fn step(&self, axis: Axis2d) -> Option<Direction> {
+        self.steps[axis.dim()]
+    }
+
+    /// Returns the number of axes with a step, i.e. 1 for edge and 2 for vertex neighbors
+    pub const fn num_steps(&self) -> usize {
+        self.steps[0].is_some() as usize + self.steps[1].is_some() as usize
+    }
+
+    /// Returns the offset as signed integer steps, i.e. each component is -1, 0 or 1
+    /// ```
+    /// use crate::splashsurf_lib::topology::{Direction, NeighborOffset2d};
+    /// let offset = NeighborOffset2d::new([Some(Direction::Negative), None]).unwrap();
+    /// assert_eq!(offset.to_ij(), [-1, 0]);
+    /// ```
+    pub const fn to_ij(&self) -> [i32; 2] {
+        let mut ij = [0; 2];
+        let mut dim = 0;
+        while dim < 2 {
+            ij[dim] = match self.steps[dim] {
+                Some(Direction::Negative) => -1,
+                Some(Direction::Positive) => 1,
+                None => 0,
+            };
+            dim += 1;
+        }
+        ij
+    }
+
+    /// Returns the offset in the opposite direction
+    pub const fn opposite(&self) -> Self {
+        let mut steps = self.steps;
+        let mut dim = 0;
+        while dim < 2 {
+            steps[dim] = match steps[dim] {
+                Some(direction) => Some(direction.opposite()),
+                None => None,
+            };
+            dim += 1;
+        }
+        Self { steps }
+    }
+
+    /// Applies the offset to the given index array, returns `None` on overflow
+    #[inline(always)]
+    pub fn checked_apply<N: Clone + CheckedAdd<Output = N> + CheckedSub<Output = N> + One>(
+        &self,
+        index: &[N; 2],
+    ) -> Option<[N; 2]> {
+        let mut index = index.clone();
+        for (value, step) in index.iter_mut().zip(self.steps.iter()) {
+            if let Some(direction) = step {
+                *value = direction.checked_apply_step(value.clone(), N::one())?;
+            }
+        }
+        Some(index)
+    }
+
+    /// Applies the offset to the given index array, returns `None` if the result is outside of the range `0..upper_bounds[i]` along any axis
+    /// ```
+    /// use crate::splashsurf_lib::topology::{Direction, NeighborOffset2d};
+    /// let offset = NeighborOffset2d::new([Some(Direction::Negative), Some(Direction::Positive)]).unwrap();
+    /// assert_eq!(offset.checked_apply_within(&[1, 2], &[5, 5]), Some([0, 3]));
+    /// assert_eq!(offset.checked_apply_within(&[0, 2], &[5, 5]), None);
+    /// ```
+    #[inline(always)]
+    pub fn checked_apply_within<
+        N: Clone + PartialOrd + Zero + CheckedAdd<Output = N> + CheckedSub<Output = N> + One,
+    >(
+        &self,
+        index: &[N; 2],
+        upper_bounds: &[N; 2],
+    ) -> Option<[N; 2]> {
+        let index = self.checked_apply(index)?;
+        index
+            .iter()
+            .zip(upper_bounds.iter())
+            .all(|(value, upper_bound)| *value >= N::zero() && value < upper_bound)
+            .then_some(index)
+    }
+}
+
+impl From<DirectedAxis2d> for NeighborOffset2d {
+    /// Returns the offset to the edge neighbor in the given direction
+    fn from(directed_axis: DirectedAxis2d) -> Self {
+        let mut steps = [None; 2];
+        steps[directed_axis.axis.dim()] = Some(directed_axis.direction);
+        Self { steps }
+    }
+}
+
+/// Returns the 2D offsets with at most the given number of steps, ordered lexicographically by their steps along x and y (negative, none, positive)
+const fn neighbor_offsets_2d<const N: usize>(max_steps: usize) -> [NeighborOffset2d; N] {
+    const STEPS: [Option<Direction>; 3] =
+        [Some(Direction::Negative), None, Some(Direction::Positive)];
+
+    let mut offsets = [NeighborOffset2d { steps: [None; 2] }; N];
+    let mut n = 0;
+    let mut i = 0;
+    while i < 9 {
+        let offset = NeighborOffset2d {
+            steps: [STEPS[i / 3], STEPS[i % 3]],
+        };
+        let num_steps = offset.num_steps();
+        if num_steps > 0 && num_steps <= max_steps {
+            offsets[n] = offset;
+            n += 1;
+        }
+        i += 1;
+    }
+    assert!(n == N);
+    offsets
+}
+
+const EDGE_NEIGHBOR_OFFSETS_2D: [NeighborOffset2d; 4] = neighbor_offsets_2d(1);
+const ALL_NEIGHBOR_OFFSETS_2D: [NeighborOffset2d; 8] = neighbor_offsets_2d(2);
+
+impl Connectivity2d {
+    /// Returns the offsets to all neighbors of this connectivity class, ordered lexicographically by their steps along x and y
+    /// ```
+    /// use crate::splashsurf_lib::topology::Connectivity2d;
+    /// assert_eq!(Connectivity2d::Edge4.offsets().len(), 4);
+    /// assert_eq!(Connectivity2d::EdgeVertex8.offsets().len(), 8);
+    /// ```
+    pub const fn offsets(&self) -> &'static [NeighborOffset2d] {
+        match self {
+            Connectivity2d::Edge4 => &EDGE_NEIGHBOR_OFFSETS_2D,
+            Connectivity2d::EdgeVertex8 => &ALL_NEIGHBOR_OFFSETS_2D,
+        }
+    }
+
+    /// Iterator over the indices of all neighbors of the given index that are inside of the range `0..upper_bounds[i]` along each axis
+    pub fn neighbors_within<
+        'a,
+        N: Clone + PartialOrd + Zero + CheckedAdd<Output = N> + CheckedSub<Output = N> + One,
+    >(
+        &self,
+        index: &'a [N; 2],
+        upper_bounds: &'a [N; 2],
+    ) -> impl Iterator<Item = [N; 2]> + 'a {
+        self.offsets()
+            .iter()
+            .filter_map(move |offset| offset.checked_apply_within(index, upper_bounds))
+    }
+}
+
+#[test]
+fn test_neighbor_offsets_2d() {
+    for connectivity in [Connectivity2d::Edge4, Connectivity2d::EdgeVertex8] {
+        let offsets = connectivity.offsets();
+        let max_steps = match connectivity {
+            Connectivity2d::Edge4 => 1,
+            Connectivity2d::EdgeVertex8 => 2,
+        };
+
+        // All offsets are unique, sorted and contain their opposite
+        assert!(offsets.windows(2).all(|w| w[0].to_ij() < w[1].to_ij()));
+        assert!(offsets.iter().all(|offset| offset.num_steps() <= max_steps));
+        assert!(offsets
+            .iter()
+            .all(|offset| offsets.contains(&offset.opposite())));
+    }
+
+    // The edge neighbors correspond to the directed axes
+    for directed_axis in DirectedAxis2d::all_possible() {
+        assert!(Connectivity2d::Edge4
+            .offsets()
+            .contains(&NeighborOffset2d::from(*directed_axis)));
+    }
+}
+
+#[test]
+fn test_neighbor_counts_2d() {
+    let upper_bounds = [4, 4];
+    let count = |connectivity: Connectivity2d, index: [i32; 2]| {
+        connectivity
+            .neighbors_within(&index, &upper_bounds)
+            .inspect(|neighbor| {
+                assert!(neighbor.iter().all(|&v| v >= 0 && v < 4));
+                assert_ne!(*neighbor, index);
+            })
+            .count()
+    };
+
+    let corner = [3, 0];
+    let edge = [0, 2];
+    let interior = [1, 2];
+
+    assert_eq!(count(Connectivity2d::Edge4, corner), 2);
+    assert_eq!(count(Connectivity2d::Edge4, edge), 3);
+    assert_eq!(count(Connectivity2d::Edge4, interior), 4);
+
+    assert_eq!(count(Connectivity2d::EdgeVertex8, corner), 3);
+    assert_eq!(count(Connectivity2d::EdgeVertex8, edge), 5);
+    assert_eq!(count(Connectivity2d::EdgeVertex8, interior), 8);
+
+    // Single cell grid
+    assert_eq!(
+        Connectivity2d::EdgeVertex8
+            .neighbors_within(&[0, 0], &[1, 1])
+            .count(),
+        0
+    );
+}
@@ -0,0 +1,289 @@
+//! Local numbering of the vertices and edges of a cube shaped grid cell
+//!
+//! The numbering is the one used by the marching cubes lookup table (see [`marching_cubes_lut`](crate::marching_cubes::marching_cubes_lut)),
+//! i.e. the local edge indices of a triangulation returned by the table refer to the edges defined in this module.
+//!
+//! ```text
+//!         7 ________ 6           _____6__
+//!         /|       /|         7/|       /|
+//!       /  |     /  |        /  |     /5 |
+//!   4 /_______ /    |      /__4____ /    10
+//!    |     |  |5    |     |    11  |     |
+//!    |    3|__|_____|2    |     |__|__2__|
+//!    |    /   |    /      8   3/   9    /
+//!    |  /     |  /        |  /     |  /1
+//!    |/_______|/          |/___0___|/
+//!   0          1
+//!          Vertices              Edges
+//! ```
+
+use crate::topology::Axis;
+use crate::Index;
+
+/// Index of one of the 8 vertices of a cube shaped cell in the local numbering (0 to 7)
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct LocalVertexIndex(usize);
+
+/// Index of one of the 12 edges of a cube shaped cell in the local numbering (0 to 11)
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct LocalEdgeIndex(usize);
+
+/// Maps from the local numbering of the cell vertices to their offsets from the cell's minimum point
+pub const LOCAL_VERTEX_OFFSETS: [[u8; 3]; 8] = [
+    [0, 0, 0], // vertex 0
+    [1, 0, 0], // vertex 1
+    [1, 1, 0], // vertex 2
+    [0, 1, 0], // vertex 3
+    [0, 0, 1], // vertex 4
+    [1, 0, 1], // vertex 5
+    [1, 1, 1], // vertex 6
+    [0, 1, 1], // vertex 7
+];
+
+/// Maps from the local numbering of the cell edges to their two local vertices, ordered ascending along the edge's axis
+pub const LOCAL_EDGE_VERTICES: [[usize; 2]; 12] = [
+    [0, 1], // edge 0
+    [1, 2], // edge 1
+    [3, 2], // edge 2
+    [0, 3], // edge 3
+    [4, 5], // edge 4
+    [5, 6], // edge 5
+    [7, 6], // edge 6
+    [4, 7], // edge 7
+    [0, 4], // edge 8
+    [1, 5], // edge 9
+    [2, 6], // edge 10
+    [3, 7], // edge 11
+];
+
+/// Maps from the local numbering of the cell edges to the axis the edge is parallel to
+const LOCAL_EDGE_AXES: [Axis; 12] = [
+    Axis::X, // edge 0
+    Axis::Y, // edge 1
+    Axis::X, // edge 2
+    Axis::Y, // edge 3
+    Axis::X, // edge 4
+    Axis::Y, // edge 5
+    Axis::X, // edge 6
+    Axis::Y, // edge 7
+    Axis::Z, // edge 8
+    Axis::Z, // edge 9
+    Axis::Z, // edge 10
+    Axis::Z, // edge 11
+];
+
+/// Maps from a flattened offset inside of a cell (`x + 2 * y + 4 * z`) to the corresponding local vertex
+const LOCAL_VERTICES_BY_FLAT_OFFSET: [usize; 8] = [0, 1, 3, 2, 4, 5, 7, 6];
+
+/// Maps from a local vertex in a cell and an axis direction originating from this vertex to the local edge index
+#[rustfmt::skip]
+const LOCAL_EDGES_FROM_LOCAL_VERTEX: [[Option<usize>; 3]; 8] = [
+    [Some(0), Some(3), Some(8) ],  // vertex 0
+    [None   , Some(1), Some(9) ],  // vertex 1
+    [None   , None   , Some(10)],  // vertex 2
+    [Some(2), None   , Some(11)],  // vertex 3
+    [Some(4), Some(7), None    ],  // vertex 4
+    [None   , Some(5), None    ],  // vertex 5
+    [None   , None   , None    ],  // vertex 6
+    [Some(6), None   , None    ],  // vertex 7
+];
+
+/// Stores per dimension which local edges of a cell are parallel to an axis of this dimension in CCW ordering
+pub(crate) const LOCAL_EDGES_BY_AXIS: [[usize; 4]; 3] = [
+    [0, 2, 6, 4],   // x-axis
+    [3, 1, 5, 7],   // y-axis
+    [8, 9, 10, 11], // z-axis
+];
+
+/// Applies the offset of a local vertex to the index of a cell's minimum point, returns `None` on overflow
+fn checked_add_offset<I: Index>(cell_ijk: &[I; 3], offset: &[u8; 3]) -> Option<[I; 3]> {
+    Some([
+        cell_ijk[0].checked_add(&I::from_u8(offset[0])?)?,
+        cell_ijk[1].checked_add(&I::from_u8(offset[1])?)?,
+        cell_ijk[2].checked_add(&I::from_u8(offset[2])?)?,
+    ])
+}
+
+impl LocalVertexIndex {
+    /// Returns the local vertex with the given index, `None` if the index is not in the range 0 to 7
+    pub const fn new(index: usize) -> Option<Self> {
+        if index < 8 {
+            Some(Self(index))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over all local vertices of a cell in ascending order
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..8).map(Self)
+    }
+
+    /// Returns the local vertex at the given offset from the cell's minimum point, `None` if an offset is larger than one
+    /// ```
+    /// use crate::splashsurf_lib::topology::cube::LocalVertexIndex;
+    /// assert_eq!(LocalVertexIndex::from_offset([1, 1, 0]).unwrap().index(), 2);
+    /// assert_eq!(LocalVertexIndex::from_offset([2, 0, 0]), None);
+    /// ```
+    pub fn from_offset(offset: [u8; 3]) -> Option<Self> {
+        if offset.iter().any(|&o| o > 1) {
+            return None;
+        }
+        let flat_offset = offset[0] + 2 * offset[1] + 4 * offset[2];
+        Some(Self(LOCAL_VERTICES_BY_FLAT_OFFSET[flat_offset as usize]))
+    }
+
+    /// Returns the index of the vertex in the local numbering (0 to 7)
+    #[inline(always)]
+    pub const fn index(self) -> usize {
+        self.0
+    }
+
+    /// Returns the offset of the vertex from the cell's minimum point
+    #[inline(always)]
+    pub const fn offset(self) -> [u8; 3] {
+        LOCAL_VERTEX_OFFSETS[self.0]
+    }
+
+    /// Returns the local edge originating from this vertex in positive direction of the given axis, if it is part of the cell
+    /// ```
+    /// use crate::splashsurf_lib::topology::Axis;
+    /// use crate::splashsurf_lib::topology::cube::LocalVertexIndex;
+    /// let vertex = LocalVertexIndex::new(1).unwrap();
+    /// assert_eq!(vertex.edge_along(Axis::Z).unwrap().index(), 9);
+    /// assert_eq!(vertex.edge_along(Axis::X), None);
+    /// ```
+    #[inline(always)]
+    pub fn edge_along(self, axis: Axis) -> Option<LocalEdgeIndex> {
+        LOCAL_EDGES_FROM_LOCAL_VERTEX[self.0][axis.dim()].map(LocalEdgeIndex)
+    }
+
+    /// Returns the global index of this vertex for the cell with the given minimum point index, `None` on overflow
+    #[inline(always)]
+    pub fn global_point_index<I: Index>(self, cell_ijk: &[I; 3]) -> Option<[I; 3]> {
+        checked_add_offset(cell_ijk, &self.offset())
+    }
+}
+
+impl LocalEdgeIndex {
+    /// Returns the local edge with the given index, `None` if the index is not in the range 0 to 11
+    pub const fn new(index: usize) -> Option<Self> {
+        if index < 12 {
+            Some(Self(index))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over all local edges of a cell in ascending order
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..12).map(Self)
+    }
+
+    /// Returns the four local edges of a cell that are parallel to the given axis in CCW ordering
+    pub fn parallel_to(axis: Axis) -> [Self; 4] {
+        LOCAL_EDGES_BY_AXIS[axis.dim()].map(Self)
+    }
+
+    /// Returns the index of the edge in the local numbering (0 to 11)
+    #[inline(always)]
+    pub const fn index(self) -> usize {
+        self.0
+    }
+
+    /// Returns the axis the edge is parallel to
+    #[inline(always)]
+    pub const fn axis(self) -> Axis {
+        LOCAL_EDGE_AXES[self.0]
+    }
+
+    /// Returns the two end points of the edge, ordered ascending along the edge's axis
+    /// ```
+    /// use crate::splashsurf_lib::topology::cube::LocalEdgeIndex;
+    /// let [origin, target] = LocalEdgeIndex::new(2).unwrap().vertices();
+    /// assert_eq!((origin.index(), target.index()), (3, 2));
+    /// ```
+    #[inline(always)]
+    pub const fn vertices(self) -> [LocalVertexIndex; 2] {
+        let [origin, target] = LOCAL_EDGE_VERTICES[self.0];
+        [LocalVertexIndex(origin), LocalVertexIndex(target)]
+    }
+
+    /// Returns the end point of the edge with the smaller coordinate along the edge's axis
+    #[inline(always)]
+    pub const fn origin(self) -> LocalVertexIndex {
+        LocalVertexIndex(LOCAL_EDGE_VERTICES[self.0][0])
+    }
+
+    /// Returns the global index of the edge's origin point and its axis for the cell with the given minimum point index, `None` on overflow
+    #[inline(always)]
+    pub fn global_edge_index<I: Index>(self, cell_ijk: &[I; 3]) -> Option<([I; 3], Axis)> {
+        Some((self.origin().global_point_index(cell_ijk)?, self.axis()))
+    }
+}
+
+#[test]
+fn test_cube_local_vertex_offset_consistency() {
+    for vertex in LocalVertexIndex::all() {
+        assert_eq!(LocalVertexIndex::from_offset(vertex.offset()), Some(vertex));
+    }
+}
+
+#[test]
+fn test_cube_local_edge_consistency() {
+    for edge in LocalEdgeIndex::all() {
+        let [origin, target] = edge.vertices();
+        let dim = edge.axis().dim();
+
+        // The end points only differ along the edge's axis
+        for i in 0..3 {
+            if i == dim {
+                assert_eq!(origin.offset()[i] + 1, target.offset()[i]);
+            } else {
+                assert_eq!(origin.offset()[i], target.offset()[i]);
+            }
+        }
+
+        assert_eq!(origin.edge_along(edge.axis()), Some(edge));
+        assert!(LocalEdgeIndex::parallel_to(edge.axis()).contains(&edge));
+    }
+
+    for &axis in Axis::all_possible() {
+        assert!(LocalEdgeIndex::parallel_to(axis)
+            .iter()
+            .all(|edge| edge.axis() == axis));
+    }
+
+    for vertex in LocalVertexIndex::all() {
+        for &axis in Axis::all_possible() {
+            if let Some(edge) = vertex.edge_along(axis) {
+                assert_eq!(edge.origin(), vertex);
+                assert_eq!(edge.axis(), axis);
+            } else {
+                // There is no edge if the vertex is already on the upper face of the cell
+                assert_eq!(vertex.offset()[axis.dim()], 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_cube_global_indices() {
+    let cell_ijk: [u32; 3] = [3, 4, 5];
+    assert_eq!(
+        LocalVertexIndex::new(6)
+            .unwrap()
+            .global_point_index(&cell_ijk),
+        Some([4, 5, 6])
+    );
+    assert_eq!(
+        LocalEdgeIndex::new(6).unwrap().global_edge_index(&cell_ijk),
+        Some(([3, 5, 6], Axis::X))
+    );
+    assert_eq!(
+        LocalVertexIndex::new(1)
+            .unwrap()
+            .global_point_index(&[u32::MAX, 0, 0]),
+        None
+    );
+}
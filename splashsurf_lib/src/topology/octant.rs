@@ -0,0 +1,312 @@
+//! Adjacency between a cube shaped cell and its eight octant subcells, e.g. for the stitching of octree nodes
+//!
+//! When a cell is subdivided into octants, each subcell touches the boundary of the parent cell with three of its
+//! faces, nine of its edges and seven of its vertices. The functions of [`OctantIndex`] map between the faces
+//! (identified by a [`DirectedAxis`]), the local edges and the local vertices (see [`cube`](crate::topology::cube))
+//! of the parent cell and the corresponding entities of a subcell. As the subcells use the same local numbering
+//! as the parent, an entity on the boundary of the parent is identified by the same local index in the subcell.
+
+use crate::topology::cube::{LocalEdgeIndex, LocalVertexIndex};
+use crate::topology::{Axis, DirectedAxis, Direction};
+
+/// Index of one of the 8 octant subcells of a cell (0 to 7)
+///
+/// The octants are numbered like the vertices of a cube in lexicographic order, i.e. bit `i` of the index is set
+/// if the octant is in positive direction along axis `i` (`0`: `(-,-,-)`, `1`: `(+,-,-)`, `2`: `(-,+,-)`, ...).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct OctantIndex(usize);
+
+impl OctantIndex {
+    /// Returns the octant with the given index, `None` if the index is not in the range 0 to 7
+    pub const fn new(index: usize) -> Option<Self> {
+        if index < 8 {
+            Some(Self(index))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the octant in the given directions along the x, y and z-axis
+    /// ```
+    /// use crate::splashsurf_lib::topology::Direction;
+    /// use crate::splashsurf_lib::topology::octant::OctantIndex;
+    /// let octant = OctantIndex::from_directions([Direction::Positive, Direction::Negative, Direction::Positive]);
+    /// assert_eq!(octant.index(), 5);
+    /// ```
+    pub const fn from_directions(directions: [Direction; 3]) -> Self {
+        Self(
+            directions[0].is_positive() as usize
+                | (directions[1].is_positive() as usize) << 1
+                | (directions[2].is_positive() as usize) << 2,
+        )
+    }
+
+    /// Returns an iterator over all octants in ascending order
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..8).map(Self)
+    }
+
+    /// Returns the index of the octant (0 to 7)
+    #[inline(always)]
+    pub const fn index(self) -> usize {
+        self.0
+    }
+
+    /// Returns the direction of the octant along the given axis
+    #[inline(always)]
+    pub const fn direction(self, axis: Axis) -> Direction {
+        Direction::new_positive(self.0 & (1 << axis.dim()) != 0)
+    }
+
+    /// Returns the offset of the octant's minimum point from the parent's minimum point in units of half the parent cell
+    #[inline(always)]
+    pub const fn offset(self) -> [u8; 3] {
+        [
+            (self.0 & 1) as u8,
+            ((self.0 >> 1) & 1) as u8,
+            ((self.0 >> 2) & 1) as u8,
+        ]
+    }
+
+    /// Returns the position of a local vertex of this octant relative to the parent's minimum point in units of half the parent cell
+    ///
+    /// Vertices of different octants with the same position are shared by the octants.
+    pub fn vertex_position(self, child_vertex: LocalVertexIndex) -> [u8; 3] {
+        let offset = self.offset();
+        let vertex_offset = child_vertex.offset();
+        [
+            offset[0] + vertex_offset[0],
+            offset[1] + vertex_offset[1],
+            offset[2] + vertex_offset[2],
+        ]
+    }
+
+    /// Returns the face of this octant that covers a part of the given face of the parent, `None` if the octant does not touch the face
+    /// ```
+    /// use crate::splashsurf_lib::topology::{Axis, Direction};
+    /// use crate::splashsurf_lib::topology::octant::OctantIndex;
+    /// let octant = OctantIndex::new(1).unwrap();
+    /// let face = Axis::X.with_direction(Direction::Positive);
+    /// assert_eq!(octant.child_face(face), Some(face));
+    /// assert_eq!(octant.child_face(face.opposite()), None);
+    /// ```
+    #[inline(always)]
+    pub const fn child_face(self, parent_face: DirectedAxis) -> Option<DirectedAxis> {
+        self.parent_face(parent_face)
+    }
+
+    /// Returns the face of the parent that contains the given face of this octant, `None` if the face is in the interior of the parent
+    #[inline(always)]
+    pub const fn parent_face(self, child_face: DirectedAxis) -> Option<DirectedAxis> {
+        if self.direction(child_face.axis) as usize == child_face.direction as usize {
+            Some(child_face)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the octant that shares the given face with this octant, `None` if the face is on the boundary of the parent
+    /// ```
+    /// use crate::splashsurf_lib::topology::{Axis, Direction};
+    /// use crate::splashsurf_lib::topology::octant::OctantIndex;
+    /// let octant = OctantIndex::new(0).unwrap();
+    /// let sibling = octant.sibling_across(Axis::Y.with_direction(Direction::Positive)).unwrap();
+    /// assert_eq!(sibling.index(), 2);
+    /// assert_eq!(octant.sibling_across(Axis::Y.with_direction(Direction::Negative)), None);
+    /// ```
+    #[inline(always)]
+    pub const fn sibling_across(self, child_face: DirectedAxis) -> Option<OctantIndex> {
+        if self.parent_face(child_face).is_some() {
+            None
+        } else {
+            Some(Self(self.0 ^ (1 << child_face.axis.dim())))
+        }
+    }
+
+    /// Returns the edge of this octant that covers a half of the given edge of the parent, `None` if the octant does not touch the edge
+    #[inline(always)]
+    pub fn child_edge(self, parent_edge: LocalEdgeIndex) -> Option<LocalEdgeIndex> {
+        self.parent_edge(parent_edge)
+    }
+
+    /// Returns the edge of the parent that contains the given edge of this octant, `None` if the edge is not on an edge of the parent
+    #[inline(always)]
+    pub fn parent_edge(self, child_edge: LocalEdgeIndex) -> Option<LocalEdgeIndex> {
+        // The edge is part of a parent edge if the octant is on the same side as the edge along both orthogonal axes
+        let edge_offset = child_edge.origin().offset();
+        let offset = self.offset();
+        child_edge
+            .axis()
+            .orthogonal_axes()
+            .iter()
+            .all(|axis| edge_offset[axis.dim()] == offset[axis.dim()])
+            .then_some(child_edge)
+    }
+
+    /// Returns the vertex of this octant that coincides with the given vertex of the parent, `None` if the octant does not touch the vertex
+    #[inline(always)]
+    pub fn child_vertex(self, parent_vertex: LocalVertexIndex) -> Option<LocalVertexIndex> {
+        self.parent_vertex(parent_vertex)
+    }
+
+    /// Returns the vertex of the parent that coincides with the given vertex of this octant, `None` if the vertex is not a vertex of the parent
+    #[inline(always)]
+    pub fn parent_vertex(self, child_vertex: LocalVertexIndex) -> Option<LocalVertexIndex> {
+        (child_vertex.offset() == self.offset()).then_some(child_vertex)
+    }
+}
+
+#[cfg(test)]
+mod test_octant {
+    use super::*;
+
+    // In the brute-force checks the parent cell is the cube [0, 4]^3 and the octants have an edge length of 2
+
+    /// Returns the position of a parent vertex
+    fn parent_vertex_position(vertex: LocalVertexIndex) -> [u8; 3] {
+        vertex.offset().map(|o| o * 4)
+    }
+
+    /// Returns the position of a vertex of an octant
+    fn child_vertex_position(octant: OctantIndex, vertex: LocalVertexIndex) -> [u8; 3] {
+        octant.vertex_position(vertex).map(|o| o * 2)
+    }
+
+    /// Returns the local vertices of a cube on the given face
+    fn face_vertices(face: DirectedAxis) -> Vec<LocalVertexIndex> {
+        LocalVertexIndex::all()
+            .filter(|v| v.offset()[face.axis.dim()] == face.direction.is_positive() as u8)
+            .collect()
+    }
+
+    /// Returns whether the point is on the face of the parent cell
+    fn is_on_parent_face(point: [u8; 3], face: DirectedAxis) -> bool {
+        point[face.axis.dim()] == 4 * face.direction.is_positive() as u8
+    }
+
+    /// Returns whether the point is on the edge of the parent cell
+    fn is_on_parent_edge(point: [u8; 3], edge: LocalEdgeIndex) -> bool {
+        let origin = parent_vertex_position(edge.origin());
+        edge.axis()
+            .orthogonal_axes()
+            .iter()
+            .all(|axis| point[axis.dim()] == origin[axis.dim()])
+    }
+
+    #[test]
+    fn test_octant_directions() {
+        for octant in OctantIndex::all() {
+            let directions = [
+                octant.direction(Axis::X),
+                octant.direction(Axis::Y),
+                octant.direction(Axis::Z),
+            ];
+            assert_eq!(OctantIndex::from_directions(directions), octant);
+            assert_eq!(
+                LocalVertexIndex::from_offset(octant.offset()).map(|v| v.offset()),
+                Some(octant.offset())
+            );
+        }
+    }
+
+    #[test]
+    fn test_octant_face_adjacency() {
+        for octant in OctantIndex::all() {
+            for &face in DirectedAxis::all_possible() {
+                // A face of the octant is on a face of the parent if all of its vertices are on the parent face
+                let child_face_on_parent = face_vertices(face)
+                    .into_iter()
+                    .all(|v| is_on_parent_face(child_vertex_position(octant, v), face));
+
+                let expected = child_face_on_parent.then_some(face);
+                assert_eq!(octant.child_face(face), expected);
+                assert_eq!(octant.parent_face(face), expected);
+
+                // Interior faces are shared with exactly the sibling across the face
+                match octant.sibling_across(face) {
+                    Some(sibling) => {
+                        assert!(!child_face_on_parent);
+                        let mut own_face = face_vertices(face)
+                            .into_iter()
+                            .map(|v| child_vertex_position(octant, v))
+                            .collect::<Vec<_>>();
+                        let mut sibling_face = face_vertices(face.opposite())
+                            .into_iter()
+                            .map(|v| child_vertex_position(sibling, v))
+                            .collect::<Vec<_>>();
+                        own_face.sort();
+                        sibling_face.sort();
+                        assert_eq!(own_face, sibling_face);
+                        assert_eq!(sibling.sibling_across(face.opposite()), Some(octant));
+                    }
+                    None => assert!(child_face_on_parent),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_octant_edge_adjacency() {
+        for octant in OctantIndex::all() {
+            for parent_edge in LocalEdgeIndex::all() {
+                let expected = LocalEdgeIndex::all()
+                    .filter(|child_edge| child_edge.axis() == parent_edge.axis())
+                    .filter(|child_edge| {
+                        child_edge.vertices().iter().all(|&v| {
+                            is_on_parent_edge(child_vertex_position(octant, v), parent_edge)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                assert!(expected.len() <= 1);
+                assert_eq!(octant.child_edge(parent_edge), expected.first().copied());
+            }
+
+            for child_edge in LocalEdgeIndex::all() {
+                let expected = LocalEdgeIndex::all()
+                    .filter(|parent_edge| parent_edge.axis() == child_edge.axis())
+                    .filter(|&parent_edge| {
+                        child_edge.vertices().iter().all(|&v| {
+                            is_on_parent_edge(child_vertex_position(octant, v), parent_edge)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                assert!(expected.len() <= 1);
+                assert_eq!(octant.parent_edge(child_edge), expected.first().copied());
+            }
+
+            // Each octant touches three edges of the parent
+            assert_eq!(
+                LocalEdgeIndex::all()
+                    .filter_map(|e| octant.child_edge(e))
+                    .count(),
+                3
+            );
+        }
+    }
+
+    #[test]
+    fn test_octant_vertex_adjacency() {
+        for octant in OctantIndex::all() {
+            for parent_vertex in LocalVertexIndex::all() {
+                let expected = LocalVertexIndex::all().find(|&child_vertex| {
+                    child_vertex_position(octant, child_vertex)
+                        == parent_vertex_position(parent_vertex)
+                });
+                assert_eq!(octant.child_vertex(parent_vertex), expected);
+            }
+
+            for child_vertex in LocalVertexIndex::all() {
+                let expected = LocalVertexIndex::all().find(|&parent_vertex| {
+                    child_vertex_position(octant, child_vertex)
+                        == parent_vertex_position(parent_vertex)
+                });
+                assert_eq!(octant.parent_vertex(child_vertex), expected);
+            }
+        }
+
+        // The center of the parent is shared by all octants
+        for octant in OctantIndex::all() {
+            assert!(LocalVertexIndex::all().any(|v| octant.vertex_position(v) == [1, 1, 1]));
+        }
+    }
+}
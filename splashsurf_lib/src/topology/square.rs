@@ -0,0 +1,243 @@
+//! Local numbering of the vertices and edges of a square shaped 2D grid cell
+//!
+//! The numbering is the one used by the marching squares implementation of the [`reconstruction_2d`](crate::reconstruction_2d)
+//! module. Vertices are numbered in counter-clockwise order starting at the lower left corner, edges are numbered in the
+//! order bottom, right, top, left.
+//!
+//! ```text
+//!   3 ________ 2      ____2___
+//!    |        |      |        |
+//!    |        |      3        1
+//!    |________|      |________|
+//!   0          1         0
+//!    Vertices          Edges
+//! ```
+
+use crate::topology::Axis2d;
+use crate::Index;
+
+/// Index of one of the 4 vertices of a square shaped cell in the local numbering (0 to 3)
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct LocalVertexIndex(usize);
+
+/// Index of one of the 4 edges of a square shaped cell in the local numbering (0 to 3)
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct LocalEdgeIndex(usize);
+
+/// Maps from the local numbering of the cell vertices to their offsets from the cell's minimum point
+pub const LOCAL_VERTEX_OFFSETS: [[u8; 2]; 4] = [
+    [0, 0], // vertex 0
+    [1, 0], // vertex 1
+    [1, 1], // vertex 2
+    [0, 1], // vertex 3
+];
+
+/// Maps from the local numbering of the cell edges to their two local vertices, ordered ascending along the edge's axis
+pub const LOCAL_EDGE_VERTICES: [[usize; 2]; 4] = [
+    [0, 1], // edge 0
+    [1, 2], // edge 1
+    [3, 2], // edge 2
+    [0, 3], // edge 3
+];
+
+/// Maps from the local numbering of the cell edges to the axis the edge is parallel to
+const LOCAL_EDGE_AXES: [Axis2d; 4] = [
+    Axis2d::X, // edge 0
+    Axis2d::Y, // edge 1
+    Axis2d::X, // edge 2
+    Axis2d::Y, // edge 3
+];
+
+/// Maps from a flattened offset inside of a cell (`x + 2 * y`) to the corresponding local vertex
+const LOCAL_VERTICES_BY_FLAT_OFFSET: [usize; 4] = [0, 1, 3, 2];
+
+/// Maps from a local vertex in a cell and an axis direction originating from this vertex to the local edge index
+#[rustfmt::skip]
+const LOCAL_EDGES_FROM_LOCAL_VERTEX: [[Option<usize>; 2]; 4] = [
+    [Some(0), Some(3)],  // vertex 0
+    [None   , Some(1)],  // vertex 1
+    [None   , None   ],  // vertex 2
+    [Some(2), None   ],  // vertex 3
+];
+
+/// Stores per dimension which local edges of a cell are parallel to an axis of this dimension
+const LOCAL_EDGES_BY_AXIS: [[usize; 2]; 2] = [
+    [0, 2], // x-axis
+    [3, 1], // y-axis
+];
+
+impl LocalVertexIndex {
+    /// Returns the local vertex with the given index, `None` if the index is not in the range 0 to 3
+    pub const fn new(index: usize) -> Option<Self> {
+        if index < 4 {
+            Some(Self(index))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over all local vertices of a cell in ascending order
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..4).map(Self)
+    }
+
+    /// Returns the local vertex at the given offset from the cell's minimum point, `None` if an offset is larger than one
+    /// ```
+    /// use crate::splashsurf_lib::topology::square::LocalVertexIndex;
+    /// assert_eq!(LocalVertexIndex::from_offset([0, 1]).unwrap().index(), 3);
+    /// assert_eq!(LocalVertexIndex::from_offset([0, 2]), None);
+    /// ```
+    pub fn from_offset(offset: [u8; 2]) -> Option<Self> {
+        if offset.iter().any(|&o| o > 1) {
+            return None;
+        }
+        let flat_offset = offset[0] + 2 * offset[1];
+        Some(Self(LOCAL_VERTICES_BY_FLAT_OFFSET[flat_offset as usize]))
+    }
+
+    /// Returns the index of the vertex in the local numbering (0 to 3)
+    #[inline(always)]
+    pub const fn index(self) -> usize {
+        self.0
+    }
+
+    /// Returns the offset of the vertex from the cell's minimum point
+    #[inline(always)]
+    pub const fn offset(self) -> [u8; 2] {
+        LOCAL_VERTEX_OFFSETS[self.0]
+    }
+
+    /// Returns the local edge originating from this vertex in positive direction of the given axis, if it is part of the cell
+    #[inline(always)]
+    pub fn edge_along(self, axis: Axis2d) -> Option<LocalEdgeIndex> {
+        LOCAL_EDGES_FROM_LOCAL_VERTEX[self.0][axis.dim()].map(LocalEdgeIndex)
+    }
+
+    /// Returns the global index of this vertex for the cell with the given minimum point index, `None` on overflow
+    #[inline(always)]
+    pub fn global_point_index<I: Index>(self, cell_ij: &[I; 2]) -> Option<[I; 2]> {
+        let offset = self.offset();
+        Some([
+            cell_ij[0].checked_add(&I::from_u8(offset[0])?)?,
+            cell_ij[1].checked_add(&I::from_u8(offset[1])?)?,
+        ])
+    }
+}
+
+impl LocalEdgeIndex {
+    /// Returns the local edge with the given index, `None` if the index is not in the range 0 to 3
+    pub const fn new(index: usize) -> Option<Self> {
+        if index < 4 {
+            Some(Self(index))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over all local edges of a cell in ascending order
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..4).map(Self)
+    }
+
+    /// Returns the two local edges of a cell that are parallel to the given axis
+    pub fn parallel_to(axis: Axis2d) -> [Self; 2] {
+        LOCAL_EDGES_BY_AXIS[axis.dim()].map(Self)
+    }
+
+    /// Returns the index of the edge in the local numbering (0 to 3)
+    #[inline(always)]
+    pub const fn index(self) -> usize {
+        self.0
+    }
+
+    /// Returns the axis the edge is parallel to
+    #[inline(always)]
+    pub const fn axis(self) -> Axis2d {
+        LOCAL_EDGE_AXES[self.0]
+    }
+
+    /// Returns the two end points of the edge, ordered ascending along the edge's axis
+    /// ```
+    /// use crate::splashsurf_lib::topology::square::LocalEdgeIndex;
+    /// let [origin, target] = LocalEdgeIndex::new(2).unwrap().vertices();
+    /// assert_eq!((origin.index(), target.index()), (3, 2));
+    /// ```
+    #[inline(always)]
+    pub const fn vertices(self) -> [LocalVertexIndex; 2] {
+        let [origin, target] = LOCAL_EDGE_VERTICES[self.0];
+        [LocalVertexIndex(origin), LocalVertexIndex(target)]
+    }
+
+    /// Returns the end point of the edge with the smaller coordinate along the edge's axis
+    #[inline(always)]
+    pub const fn origin(self) -> LocalVertexIndex {
+        LocalVertexIndex(LOCAL_EDGE_VERTICES[self.0][0])
+    }
+
+    /// Returns the global index of the edge's origin point and its axis for the cell with the given minimum point index, `None` on overflow
+    #[inline(always)]
+    pub fn global_edge_index<I: Index>(self, cell_ij: &[I; 2]) -> Option<([I; 2], Axis2d)> {
+        Some((self.origin().global_point_index(cell_ij)?, self.axis()))
+    }
+}
+
+#[test]
+fn test_square_local_vertex_offset_consistency() {
+    for vertex in LocalVertexIndex::all() {
+        assert_eq!(LocalVertexIndex::from_offset(vertex.offset()), Some(vertex));
+    }
+}
+
+#[test]
+fn test_square_local_edge_consistency() {
+    for edge in LocalEdgeIndex::all() {
+        let [origin, target] = edge.vertices();
+        let dim = edge.axis().dim();
+
+        // The end points only differ along the edge's axis
+        assert_eq!(origin.offset()[dim] + 1, target.offset()[dim]);
+        assert_eq!(origin.offset()[1 - dim], target.offset()[1 - dim]);
+
+        assert_eq!(origin.edge_along(edge.axis()), Some(edge));
+        assert!(LocalEdgeIndex::parallel_to(edge.axis()).contains(&edge));
+    }
+
+    for &axis in Axis2d::all_possible() {
+        assert!(LocalEdgeIndex::parallel_to(axis)
+            .iter()
+            .all(|edge| edge.axis() == axis));
+    }
+
+    for vertex in LocalVertexIndex::all() {
+        for &axis in Axis2d::all_possible() {
+            if let Some(edge) = vertex.edge_along(axis) {
+                assert_eq!(edge.origin(), vertex);
+                assert_eq!(edge.axis(), axis);
+            } else {
+                // There is no edge if the vertex is already on the upper side of the cell
+                assert_eq!(vertex.offset()[axis.dim()], 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_square_global_indices() {
+    let cell_ij: [u32; 2] = [3, 4];
+    assert_eq!(
+        LocalVertexIndex::new(2)
+            .unwrap()
+            .global_point_index(&cell_ij),
+        Some([4, 5])
+    );
+    assert_eq!(
+        LocalEdgeIndex::new(1).unwrap().global_edge_index(&cell_ij),
+        Some(([4, 4], Axis2d::Y))
+    );
+    assert_eq!(
+        LocalVertexIndex::new(3)
+            .unwrap()
+            .global_point_index(&[0, u32::MAX]),
+        None
+    );
+}
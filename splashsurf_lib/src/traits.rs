@@ -11,6 +11,12 @@ pub trait ThreadSafe: Sync + Send {}
 impl<T> ThreadSafe for T where T: Sync + Send {}
 
 /// Trait that has to be implemented for types to be used as background grid cell indices in the context of the library
+///
+/// The trait is implemented for all primitive integer types, signed (e.g. `i32`, `i64`) as well as unsigned
+/// (e.g. `u32`, `u64`, `usize`). All grid indices are relative to the lower corner of the background grid, so
+/// no negative indices occur and the reconstruction results do not depend on the signedness of the index type.
+/// Smaller index types (e.g. `u32` instead of `i64`) reduce the memory usage of the density map but limit the
+/// maximum number of grid points of the domain.
 pub trait Index:
     Copy
     + Hash
@@ -0,0 +1,113 @@
+//! Helper functions for rigid transformations, uniform scaling and translation of point sets
+//!
+//! The functions operate in-place on slices of positions (e.g. particles loaded from a file), see
+//! [`TriMesh3d::transform`](crate::mesh::TriMesh3d::transform) and related methods for meshes. All of them are
+//! also available as a parallelized version with the `par_` prefix if the `parallel` feature is enabled.
+
+use crate::Real;
+use nalgebra::{Isometry3, Point3, Vector3};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Applies the rigid transformation (rotation followed by translation) to all points
+pub fn transform_points<R: Real>(points: &mut [Vector3<R>], isometry: &Isometry3<R>) {
+    transform_points_impl(points, isometry, false);
+}
+
+/// Applies the rigid transformation (rotation followed by translation) to all points (parallelized version)
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub fn par_transform_points<R: Real>(points: &mut [Vector3<R>], isometry: &Isometry3<R>) {
+    transform_points_impl(points, isometry, true);
+}
+
+/// Multiplies all points with the given factor, i.e. scales them uniformly relative to the coordinate origin
+pub fn scale_points<R: Real>(points: &mut [Vector3<R>], scaling: R) {
+    for_each_point_impl(points, false, |p| *p *= scaling);
+}
+
+/// Multiplies all points with the given factor, i.e. scales them uniformly relative to the coordinate origin (parallelized version)
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub fn par_scale_points<R: Real>(points: &mut [Vector3<R>], scaling: R) {
+    for_each_point_impl(points, true, |p| *p *= scaling);
+}
+
+/// Adds the given vector to all points
+pub fn translate_points<R: Real>(points: &mut [Vector3<R>], translation: &Vector3<R>) {
+    for_each_point_impl(points, false, |p| *p += translation);
+}
+
+/// Adds the given vector to all points (parallelized version)
+#[cfg(feature = "parallel")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+pub fn par_translate_points<R: Real>(points: &mut [Vector3<R>], translation: &Vector3<R>) {
+    for_each_point_impl(points, true, |p| *p += translation);
+}
+
+/// Applies the rigid transformation to all points, in parallel if requested
+pub(crate) fn transform_points_impl<R: Real>(
+    points: &mut [Vector3<R>],
+    isometry: &Isometry3<R>,
+    parallel: bool,
+) {
+    // The positions have to be transformed as points, multiplying a vector with an isometry only rotates it
+    for_each_point_impl(points, parallel, |p| {
+        *p = isometry.transform_point(&Point3::from(*p)).coords
+    });
+}
+
+/// Applies the function to all points, in parallel if requested
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+pub(crate) fn for_each_point_impl<R: Real, F>(points: &mut [Vector3<R>], parallel: bool, f: F)
+where
+    F: Fn(&mut Vector3<R>) + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    if parallel {
+        points.par_iter_mut().with_min_len(1024).for_each(f);
+        return;
+    }
+
+    points.iter_mut().for_each(f);
+}
+
+#[test]
+fn test_transform_points() {
+    use nalgebra::{Translation3, UnitQuaternion};
+
+    let original = (0..2000)
+        .map(|i| Vector3::new(i as f64, (i % 7) as f64, -0.5 * i as f64))
+        .collect::<Vec<_>>();
+
+    let mut points = original.clone();
+    scale_points(&mut points, 0.01);
+    translate_points(&mut points, &Vector3::new(1.0, -2.0, 3.0));
+    for (p, o) in points.iter().zip(original.iter()) {
+        assert_eq!(*p, o * 0.01 + Vector3::new(1.0, -2.0, 3.0));
+    }
+
+    // A rotation by 90 degrees around the z-axis followed by a translation
+    let isometry = Isometry3::from_parts(
+        Translation3::new(0.0, 0.0, 1.0),
+        UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2),
+    );
+    let mut points = original.clone();
+    transform_points(&mut points, &isometry);
+    for (p, o) in points.iter().zip(original.iter()) {
+        assert!((p - Vector3::new(-o.y, o.x, o.z + 1.0)).norm() < 1e-9 * (1.0 + o.norm()));
+    }
+
+    #[cfg(feature = "parallel")]
+    {
+        let mut par_points = original.clone();
+        par_transform_points(&mut par_points, &isometry);
+        assert_eq!(par_points, points);
+
+        par_scale_points(&mut par_points, 2.0);
+        par_translate_points(&mut par_points, &Vector3::x());
+        for (p, q) in par_points.iter().zip(points.iter()) {
+            assert_eq!(*p, q * 2.0 + Vector3::x());
+        }
+    }
+}
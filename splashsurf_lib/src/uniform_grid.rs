@@ -1,11 +1,13 @@
 //! Helper types for the implicit background grid used for marching cubes
 
-use crate::topology::{Axis, DirectedAxis, DirectedAxisArray, Direction};
-use crate::{AxisAlignedBoundingBox3d, Index, Real};
+use crate::logging::trace;
+use crate::topology::cube::{LocalEdgeIndex, LocalVertexIndex, LOCAL_EDGES_BY_AXIS};
+use crate::topology::{
+    Axis, Connectivity, DirectedAxis, DirectedAxisArray, Direction, NeighborOffset,
+};
+use crate::{AxisAlignedBoundingBox2d, AxisAlignedBoundingBox3d, Index, Real};
 use bitflags::bitflags;
-use itertools::iproduct;
-use log::trace;
-use nalgebra::Vector3;
+use nalgebra::{Vector2, Vector3};
 use num::Bounded;
 use std::iter::Iterator;
 use thiserror::Error as ThisError;
@@ -237,6 +239,27 @@ pub struct UniformCartesianCubeGrid3d<I: Index, R: Real> {
     n_cells_per_dim: [I; 3],
 }
 
+/// Abbreviated type alias for a uniform cartesian square grid in 2D
+pub type UniformGrid2d<I, R> = UniformCartesianSquareGrid2d<I, R>;
+
+/// Helper type for a 2D cartesian grid based on uniform squares, e.g. used for the reconstruction of contours of planar particle data
+///
+/// The grid is constructed like its three dimensional counterpart [`UniformCartesianCubeGrid3d`]:
+/// its origin is placed on the min coordinates of the supplied AABB which is then filled with uniformly sized squares.
+/// Points of the grid can be indexed using index pairs `(i,j)` or using a flattened index `i*n_y + j`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UniformCartesianSquareGrid2d<I: Index, R: Real> {
+    /// AABB of the grid. Note that the grid may extend beyond the max coordinate of the AABB by less than the `cell_size`.
+    aabb: AxisAlignedBoundingBox2d<R>,
+    /// The edge length of the squares in the grid
+    cell_size: R,
+
+    /// The number of vertices of the grid in each cartesian direction
+    n_points_per_dim: [I; 2],
+    /// The number of cells of the grid in each cartesian direction
+    n_cells_per_dim: [I; 2],
+}
+
 /// Error type for the construction of a [`UniformGrid`]
 #[rustfmt::skip]
 #[derive(Copy, Clone, Eq, PartialEq, Debug, ThisError)]
@@ -264,6 +287,36 @@ pub enum GridConstructionError<I: Index, R: Real> {
     RealTypeTooSmallDomainSize,
 }
 
+impl<I: Index, R: Real> GridConstructionError<I, R> {
+    /// Converts the error to another index type, index values that cannot be represented by the target type are replaced by its maximum value
+    pub(crate) fn convert_index<J: Index>(self) -> GridConstructionError<J, R> {
+        let convert = |i: I| {
+            i.to_u64()
+                .and_then(J::from_u64)
+                .unwrap_or_else(J::max_value)
+        };
+        match self {
+            Self::InvalidCellSize(cell_size) => GridConstructionError::InvalidCellSize(cell_size),
+            Self::DegenerateAabb => GridConstructionError::DegenerateAabb,
+            Self::InconsistentAabb => GridConstructionError::InconsistentAabb,
+            Self::IndexTypeTooSmallCellsPerDim => {
+                GridConstructionError::IndexTypeTooSmallCellsPerDim
+            }
+            Self::IndexTypeTooSmallPointsPerDim => {
+                GridConstructionError::IndexTypeTooSmallPointsPerDim
+            }
+            Self::IndexTypeTooSmallTotalPoints(nx, ny, nz) => {
+                GridConstructionError::IndexTypeTooSmallTotalPoints(
+                    convert(nx),
+                    convert(ny),
+                    convert(nz),
+                )
+            }
+            Self::RealTypeTooSmallDomainSize => GridConstructionError::RealTypeTooSmallDomainSize,
+        }
+    }
+}
+
 impl<I: Index, R: Real> UniformCartesianCubeGrid3d<I, R> {
     /// Construct a new grid enclosing the given AABB
     ///
@@ -506,6 +559,8 @@ impl<I: Index, R: Real> UniformCartesianCubeGrid3d<I, R> {
     }
 
     /// Returns the grid cell index triplet of the cell enclosing a point with the given coordinates in space
+    ///
+    /// Panics if the point is below the grid and the index type is unsigned, see [`Self::get_enclosing_cell`] for a checked version.
     #[inline(always)]
     pub fn enclosing_cell(&self, coord: &Vector3<R>) -> [I; 3] {
         let normalized_coord = (coord - self.aabb.min()) / self.cell_size;
@@ -516,6 +571,17 @@ impl<I: Index, R: Real> UniformCartesianCubeGrid3d<I, R> {
         ]
     }
 
+    /// Returns the cell enclosing a point with the given coordinates in space, `None` if the point is outside of the grid
+    #[inline(always)]
+    pub fn get_enclosing_cell(&self, coord: &Vector3<R>) -> Option<CellIndex<I>> {
+        let normalized_coord = (coord - self.aabb.min()) / self.cell_size;
+        self.get_cell([
+            normalized_coord[0].floor().to_index()?,
+            normalized_coord[1].floor().to_index()?,
+            normalized_coord[2].floor().to_index()?,
+        ])
+    }
+
     /// Returns an AABB of the given cell
     #[inline(always)]
     pub fn cell_aabb(&self, cell: &CellIndex<I>) -> AxisAlignedBoundingBox3d<R> {
@@ -539,20 +605,9 @@ impl<I: Index, R: Real> UniformCartesianCubeGrid3d<I, R> {
         point: &PointIndex<I>,
         direction: DirectedAxis,
     ) -> Option<PointIndex<I>> {
-        let point_ijk = point.index();
-        let DirectedAxis { axis, direction } = &direction;
-        let dim = axis.dim();
-
-        if ((point_ijk[dim] == I::zero()) && direction.is_negative())
-            || ((point_ijk[dim] == self.n_points_per_dim[dim] - I::one())
-                && direction.is_positive())
-        {
-            return None;
-        }
-
-        let mut neighbor_ijk = point_ijk.clone();
-        neighbor_ijk[dim] = direction.apply_step(neighbor_ijk[dim], I::one());
-        Some(PointIndex::from_ijk(neighbor_ijk))
+        NeighborOffset::from(direction)
+            .checked_apply_within(point.index(), &self.n_points_per_dim)
+            .map(PointIndex::from_ijk)
     }
 
     #[inline(always)]
@@ -681,31 +736,9 @@ impl<I: Index, R: Real> UniformCartesianCubeGrid3d<I, R> {
         &'a self,
         cell: &'a CellIndex<I>,
     ) -> impl Iterator<Item = CellIndex<I>> + 'a {
-        let index = cell.index();
-        static STEPS: [Option<Direction>; 3] =
-            [Some(Direction::Negative), None, Some(Direction::Positive)];
-
-        iproduct!(STEPS.iter(), STEPS.iter(), STEPS.iter()).filter_map(
-            move |(step_x, step_y, step_z)| {
-                // Skip the current cell itself
-                if step_x.is_none() && step_y.is_none() && step_z.is_none() {
-                    return None;
-                }
-
-                let neighbor_cell_ijk = [
-                    step_x
-                        .map(|d| d.checked_apply_step(index[0], I::one()))
-                        .unwrap_or(Some(index[0]))?,
-                    step_y
-                        .map(|d| d.checked_apply_step(index[1], I::one()))
-                        .unwrap_or(Some(index[1]))?,
-                    step_z
-                        .map(|d| d.checked_apply_step(index[2], I::one()))
-                        .unwrap_or(Some(index[2]))?,
-                ];
-                self.get_cell(neighbor_cell_ijk)
-            },
-        )
+        Connectivity::FaceEdgeVertex26
+            .neighbors_within(cell.index(), &self.n_cells_per_dim)
+            .map(CellIndex::from_ijk)
     }
 
     // Helper functions for construction of the SparseGrid struct
@@ -765,6 +798,169 @@ impl<I: Index, R: Real> UniformCartesianCubeGrid3d<I, R> {
     }
 }
 
+impl<I: Index, R: Real> UniformCartesianSquareGrid2d<I, R> {
+    /// Construct a new grid enclosing the given AABB
+    ///
+    /// The grid will at least contain the AABB but may be larger depending on the cell size.
+    /// As the construction errors are shared with the 3D grid, the third dimension of
+    /// [`GridConstructionError::IndexTypeTooSmallTotalPoints`] is always reported as one.
+    pub fn from_aabb(
+        aabb: &AxisAlignedBoundingBox2d<R>,
+        cell_size: R,
+    ) -> Result<Self, GridConstructionError<I, R>> {
+        if !(cell_size > R::zero()) {
+            return Err(GridConstructionError::InvalidCellSize(cell_size));
+        }
+
+        if aabb.is_degenerate() {
+            return Err(GridConstructionError::DegenerateAabb);
+        }
+
+        if !aabb.is_consistent() {
+            return Err(GridConstructionError::InconsistentAabb);
+        }
+
+        let n_cells_real = aabb.extents() / cell_size;
+        let n_cells_per_dim = Self::checked_n_cells_per_dim(&n_cells_real)
+            .ok_or(GridConstructionError::IndexTypeTooSmallCellsPerDim)?;
+        let n_points_per_dim = Self::checked_n_points_per_dim(&n_cells_per_dim)
+            .ok_or(GridConstructionError::IndexTypeTooSmallPointsPerDim)?;
+
+        // Compute the bounding box max value
+        let aabb = Self::checked_aabb(aabb.min(), &n_cells_per_dim, cell_size)
+            .ok_or(GridConstructionError::RealTypeTooSmallDomainSize)?;
+
+        // Check that the total number of points can be represented by the index type
+        let _ = n_points_per_dim[0]
+            .checked_mul(&n_points_per_dim[1])
+            .ok_or(GridConstructionError::IndexTypeTooSmallTotalPoints(
+                n_points_per_dim[0],
+                n_points_per_dim[1],
+                I::one(),
+            ))?;
+
+        Ok(Self {
+            aabb,
+            cell_size,
+            n_points_per_dim,
+            n_cells_per_dim,
+        })
+    }
+
+    /// Returns the bounding box of the grid
+    #[inline(always)]
+    pub fn aabb(&self) -> &AxisAlignedBoundingBox2d<R> {
+        &self.aabb
+    }
+
+    /// Returns the cell size used by the grid
+    #[inline(always)]
+    pub fn cell_size(&self) -> R {
+        self.cell_size
+    }
+
+    /// Returns the number of grid points per dimension of the grid
+    #[inline(always)]
+    pub fn points_per_dim(&self) -> &[I; 2] {
+        &self.n_points_per_dim
+    }
+
+    /// Returns the number of grid cells per dimension of the grid
+    #[inline(always)]
+    pub fn cells_per_dim(&self) -> &[I; 2] {
+        &self.n_cells_per_dim
+    }
+
+    /// Returns whether a point exists in the grid
+    #[inline(always)]
+    pub fn point_exists(&self, point_ij: &[I; 2]) -> bool {
+        (point_ij[0] < self.n_points_per_dim[0] && point_ij[1] < self.n_points_per_dim[1])
+            && (point_ij[0] >= I::zero() && point_ij[1] >= I::zero())
+    }
+
+    /// Returns whether a cell exists that has the given point index as its origin point (i.e. lower left corner)
+    #[inline(always)]
+    pub fn cell_exists(&self, cell_min_point_ij: &[I; 2]) -> bool {
+        (cell_min_point_ij[0] < self.n_cells_per_dim[0]
+            && cell_min_point_ij[1] < self.n_cells_per_dim[1])
+            && (cell_min_point_ij[0] >= I::zero() && cell_min_point_ij[1] >= I::zero())
+    }
+
+    /// Flattens the grid point index pair to a single index
+    #[inline(always)]
+    pub fn flatten_point_index_array(&self, ij: &[I; 2]) -> I {
+        ij[0] * self.n_points_per_dim[1] + ij[1]
+    }
+
+    /// Converts a flat point index value back to a point index pair, does not check if the point is part of the grid
+    #[inline(always)]
+    pub fn unflatten_point_index(&self, point_index: I) -> [I; 2] {
+        let ny = self.n_points_per_dim[1];
+        [point_index / ny, point_index % ny]
+    }
+
+    /// Returns the real-valued coordinates of a grid point in space
+    #[inline(always)]
+    pub fn point_coordinates_array(&self, ij: &[I; 2]) -> Vector2<R> {
+        self.aabb.min()
+            + Vector2::new(
+                ij[0].to_real_unchecked::<R>() * self.cell_size,
+                ij[1].to_real_unchecked::<R>() * self.cell_size,
+            )
+    }
+
+    /// Returns the grid cell index pair of the cell enclosing a point with the given coordinates in space
+    #[inline(always)]
+    pub fn enclosing_cell(&self, coord: &Vector2<R>) -> [I; 2] {
+        let normalized_coord = (coord - self.aabb.min()) / self.cell_size;
+        [
+            normalized_coord[0].floor().to_index_unchecked(),
+            normalized_coord[1].floor().to_index_unchecked(),
+        ]
+    }
+
+    fn checked_n_cells_per_dim(n_cells_real: &Vector2<R>) -> Option<[I; 2]> {
+        Some([
+            I::one().max(n_cells_real[0].ceil().to_index()?),
+            I::one().max(n_cells_real[1].ceil().to_index()?),
+        ])
+    }
+
+    fn checked_n_points_per_dim(n_cells_per_dim: &[I; 2]) -> Option<[I; 2]> {
+        Some([
+            n_cells_per_dim[0].checked_add(&I::one())?,
+            n_cells_per_dim[1].checked_add(&I::one())?,
+        ])
+    }
+
+    fn checked_aabb(
+        min: &Vector2<R>,
+        n_cells_per_dim: &[I; 2],
+        cell_size: R,
+    ) -> Option<AxisAlignedBoundingBox2d<R>> {
+        let max = min
+            + Vector2::new(
+                cell_size * n_cells_per_dim[0].to_real()?,
+                cell_size * n_cells_per_dim[1].to_real()?,
+            );
+
+        Some(AxisAlignedBoundingBox2d::new(*min, max))
+    }
+
+    /// Logs the information about the given grid
+    pub(crate) fn log_grid_info(&self) {
+        trace!(
+            "Using a 2D grid with {:?}x{:?} points and {:?}x{:?} cells of edge length {}.",
+            self.points_per_dim()[0],
+            self.points_per_dim()[1],
+            self.cells_per_dim()[0],
+            self.cells_per_dim()[1],
+            self.cell_size()
+        );
+        trace!("The resulting domain size is: {:?}", self.aabb());
+    }
+}
+
 impl<I: Index, R: Real> OwningSubdomainGrid<I, R> {
     /// Creates a new subdomain grid
     pub(crate) fn new(
@@ -897,51 +1093,59 @@ impl<I: Index> CellIndex<I> {
     /// Returns an array containing all local edge indices that are parallel to the given axis in CCW ordering
     #[inline(always)]
     pub fn local_edges_parallel_to(axis: Axis) -> &'static [usize; 4] {
-        &CELL_LOCAL_EDGES_BY_AXIS[axis.dim()]
+        &LOCAL_EDGES_BY_AXIS[axis.dim()]
     }
 
     /// Returns the local index inside of the cube of the given point
     #[inline(always)]
     pub fn local_point_index_of(&self, ijk: &[I; 3]) -> Option<usize> {
-        let delta = [
-            ijk[0].checked_sub(&self.index[0])?.to_usize()?,
-            ijk[1].checked_sub(&self.index[1])?.to_usize()?,
-            ijk[2].checked_sub(&self.index[2])?.to_usize()?,
+        self.local_vertex_of(ijk).map(LocalVertexIndex::index)
+    }
+
+    /// Returns the local vertex inside of the cube of the given point
+    #[inline(always)]
+    pub fn local_vertex_of(&self, ijk: &[I; 3]) -> Option<LocalVertexIndex> {
+        let offset = [
+            ijk[0].checked_sub(&self.index[0])?.to_u8()?,
+            ijk[1].checked_sub(&self.index[1])?.to_u8()?,
+            ijk[2].checked_sub(&self.index[2])?.to_u8()?,
         ];
-        let flat_index = delta[2] * 4 + delta[1] * 2 + delta[0];
-        CELL_LOCAL_POINTS.get(flat_index).copied()
+        LocalVertexIndex::from_offset(offset)
     }
 
     #[inline(always)]
     pub fn local_edge_index_of<'a, 'b>(&self, edge: &NeighborEdge<'a, 'b, I>) -> Option<usize> {
         let (start_point, _) = edge.ascending_point_order();
-        let start_point_local = self.local_point_index_of(start_point.index())?;
-        let edge_dim = edge.connectivity.axis.dim();
-        CELL_LOCAL_EDGES_FROM_LOCAL_POINT[start_point_local][edge_dim]
+        let start_point_local = self.local_vertex_of(start_point.index())?;
+        start_point_local
+            .edge_along(edge.connectivity.axis)
+            .map(LocalEdgeIndex::index)
     }
 
     /// Converts the given local point index (0 to 7) to a global grid point index
     #[inline(always)]
     pub fn global_point_index_of(&self, local_index: usize) -> Option<PointIndex<I>> {
-        let local_coords = CELL_LOCAL_POINT_COORDS.get(local_index)?;
-        Some(PointIndex::from_ijk([
-            self.index[0] + I::from_i8(local_coords[0])?,
-            self.index[1] + I::from_i8(local_coords[1])?,
-            self.index[2] + I::from_i8(local_coords[2])?,
-        ]))
+        self.global_point_of(LocalVertexIndex::new(local_index)?)
+    }
+
+    /// Converts the given local vertex to a global grid point index
+    #[inline(always)]
+    pub fn global_point_of(&self, local_vertex: LocalVertexIndex) -> Option<PointIndex<I>> {
+        local_vertex
+            .global_point_index(&self.index)
+            .map(PointIndex::from_ijk)
     }
 
     /// Converts the given local edge index (0 to 11) to a global grid edge index
     #[inline(always)]
     pub fn global_edge_index_of(&self, local_edge_index: usize) -> Option<EdgeIndex<I>> {
-        let (origin_local_point, axis) = CELL_LOCAL_EDGES.get(local_edge_index).copied()?;
-        let origin_local_coords = CELL_LOCAL_POINT_COORDS[origin_local_point];
-        let origin = [
-            self.index[0] + I::from_i8(origin_local_coords[0])?,
-            self.index[1] + I::from_i8(origin_local_coords[1])?,
-            self.index[2] + I::from_i8(origin_local_coords[2])?,
-        ];
+        self.global_edge_of(LocalEdgeIndex::new(local_edge_index)?)
+    }
 
+    /// Converts the given local edge to a global grid edge index
+    #[inline(always)]
+    pub fn global_edge_of(&self, local_edge: LocalEdgeIndex) -> Option<EdgeIndex<I>> {
+        let (origin, axis) = local_edge.global_edge_index(&self.index)?;
         Some(EdgeIndex {
             origin: PointIndex::from_ijk(origin),
             axis,
@@ -980,107 +1184,6 @@ fn test_cube_cell_local_point_index() {
     assert_eq!(cube.local_point_index_of(&[1, 2, 3]), None);
 }
 
-/// Maps from a flattened coordinate index inside of a cell to the corresponding local vertex
-const CELL_LOCAL_POINTS: [usize; 8] = [0, 1, 3, 2, 4, 5, 7, 6];
-
-/// Maps from the local numbering of the cell vertices to their coordinates in the cell
-const CELL_LOCAL_POINT_COORDS: [[i8; 3]; 8] = [
-    [0, 0, 0], // vertex 0
-    [1, 0, 0], // vertex 1
-    [1, 1, 0], // vertex 2
-    [0, 1, 0], // vertex 3
-    [0, 0, 1], // vertex 4
-    [1, 0, 1], // vertex 5
-    [1, 1, 1], // vertex 6
-    [0, 1, 1], // vertex 7
-];
-
-#[test]
-fn test_cube_local_point_coordinate_consistency() {
-    for (local_point, coords) in CELL_LOCAL_POINT_COORDS.iter().enumerate() {
-        let flattened = coords[0] + 2 * coords[1] + 4 * coords[2];
-        assert_eq!(CELL_LOCAL_POINTS[flattened as usize], local_point);
-    }
-}
-
-/// Maps from a local point in a cell and an axis direction originating from this point to the local edge index
-#[rustfmt::skip]
-const CELL_LOCAL_EDGES_FROM_LOCAL_POINT: [[Option<usize>; 3]; 8] = [
-    [Some(0), Some(3), Some(8) ],  // vertex 0
-    [None   , Some(1), Some(9) ],  // vertex 1
-    [None   , None   , Some(10)],  // vertex 2
-    [Some(2), None   , Some(11)],  // vertex 3
-    [Some(4), Some(7), None    ],  // vertex 4
-    [None   , Some(5), None    ],  // vertex 5
-    [None   , None   , None    ],  // vertex 6
-    [Some(6), None   , None    ],  // vertex 7
-];
-
-/// Maps from local edge index to the corresponding local point index and axis
-const CELL_LOCAL_EDGES: [(usize, Axis); 12] = [
-    (0, Axis::X), // edge 0
-    (1, Axis::Y), // edge 1
-    (3, Axis::X), // edge 2
-    (0, Axis::Y), // edge 3
-    (4, Axis::X), // edge 4
-    (5, Axis::Y), // edge 5
-    (7, Axis::X), // edge 6
-    (4, Axis::Y), // edge 7
-    (0, Axis::Z), // edge 8
-    (1, Axis::Z), // edge 9
-    (2, Axis::Z), // edge 10
-    (3, Axis::Z), // edge 11
-];
-
-/// All local edges of a cell that are parallel to the x-axis in CCW ordering
-const LOCAL_EDGES_PARALLEL_TO_X_AXIS: [usize; 4] = [0, 2, 6, 4];
-/// All local edges of a cell that are parallel to the y-axis in CCW ordering
-const LOCAL_EDGES_PARALLEL_TO_Y_AXIS: [usize; 4] = [3, 1, 5, 7];
-/// All local edges of a cell that are parallel to the z-axis in CCW ordering
-const LOCAL_EDGES_PARALLEL_TO_Z_AXIS: [usize; 4] = [8, 9, 10, 11];
-
-/// Stores per dimension which local edges of a cell are parallel to an axis of this dimension
-const CELL_LOCAL_EDGES_BY_AXIS: [[usize; 4]; 3] = [
-    LOCAL_EDGES_PARALLEL_TO_X_AXIS,
-    LOCAL_EDGES_PARALLEL_TO_Y_AXIS,
-    LOCAL_EDGES_PARALLEL_TO_Z_AXIS,
-];
-
-#[test]
-fn test_cube_local_edge_consistency() {
-    for (local_edge, (local_point, axis)) in CELL_LOCAL_EDGES.iter().copied().enumerate() {
-        assert_eq!(
-            CELL_LOCAL_EDGES_FROM_LOCAL_POINT[local_point][axis.dim()],
-            Some(local_edge)
-        )
-    }
-
-    for (local_point, edges) in CELL_LOCAL_EDGES_FROM_LOCAL_POINT.iter().enumerate() {
-        for (local_edge, axis) in edges
-            .iter()
-            .copied()
-            .zip(Axis::all_possible().iter().copied())
-        {
-            if let Some(local_edge) = local_edge {
-                assert_eq!(CELL_LOCAL_EDGES[local_edge].0, local_point);
-                assert_eq!(CELL_LOCAL_EDGES[local_edge].1, axis);
-            }
-        }
-    }
-}
-
-#[test]
-fn test_cube_local_edge_by_axis_consistency() {
-    for (i, edges_parallel_to_axis) in CELL_LOCAL_EDGES_BY_AXIS.iter().enumerate() {
-        for &local_edge in edges_parallel_to_axis {
-            // Ensure that each edge that is marked as parallel to axis_i is also stored as an axis in axis_i originating at some local point
-            assert!(CELL_LOCAL_EDGES_FROM_LOCAL_POINT
-                .iter()
-                .any(|edge| edge[i] == Some(local_edge)))
-        }
-    }
-}
-
 impl GridBoundaryFaceFlags {
     /// Returns whether none of the face flag bits is set, i.e. it does not correspond to any face
     pub fn is_empty(&self) -> bool {
@@ -1268,27 +1371,72 @@ mod tests {
 
     #[test]
     fn test_basic_uniform_grid_features() {
-        let grid = unit_grid::<i32, f64>();
+        basic_uniform_grid_features::<i32>();
+        basic_uniform_grid_features::<i64>();
+        basic_uniform_grid_features::<u32>();
+        basic_uniform_grid_features::<u64>();
+        basic_uniform_grid_features::<usize>();
+    }
+
+    #[test]
+    fn test_cells_adjacent_to_cell() {
+        cells_adjacent_to_cell::<i32>();
+        cells_adjacent_to_cell::<u64>();
+    }
+
+    fn cells_adjacent_to_cell<I: Index>() {
+        let origin = Vector3::new(0.0, 0.0, 0.0);
+        let n_cubes_per_dim = [I::from_u8(4).unwrap(); 3];
+        let grid = UniformGrid::<I, f64>::new(&origin, &n_cubes_per_dim, 1.0).unwrap();
+
+        let num_adjacent_cells = |i: u8, j: u8, k: u8| {
+            let cell = grid
+                .get_cell([
+                    I::from_u8(i).unwrap(),
+                    I::from_u8(j).unwrap(),
+                    I::from_u8(k).unwrap(),
+                ])
+                .unwrap();
+            grid.cells_adjacent_to_cell(&cell).count()
+        };
+
+        // Corner, edge, face and interior cells
+        assert_eq!(num_adjacent_cells(0, 0, 0), 7);
+        assert_eq!(num_adjacent_cells(3, 3, 3), 7);
+        assert_eq!(num_adjacent_cells(0, 1, 3), 11);
+        assert_eq!(num_adjacent_cells(2, 0, 1), 17);
+        assert_eq!(num_adjacent_cells(1, 2, 2), 26);
+    }
+
+    fn basic_uniform_grid_features<I: Index>() {
+        let grid = unit_grid::<I, f64>();
+        let ijk = |i: u8, j: u8, k: u8| {
+            [
+                I::from_u8(i).unwrap(),
+                I::from_u8(j).unwrap(),
+                I::from_u8(k).unwrap(),
+            ]
+        };
 
         assert_eq!(grid.aabb().max(), &Vector3::new(1.0, 1.0, 1.0));
         assert_eq!(grid.cell_size(), 1.0);
 
         let points = [
-            [0, 0, 0],
-            [1, 0, 0],
-            [1, 1, 0],
-            [0, 1, 0],
-            [0, 0, 1],
-            [1, 0, 1],
-            [1, 1, 1],
-            [0, 1, 1],
+            ijk(0, 0, 0),
+            ijk(1, 0, 0),
+            ijk(1, 1, 0),
+            ijk(0, 1, 0),
+            ijk(0, 0, 1),
+            ijk(1, 0, 1),
+            ijk(1, 1, 1),
+            ijk(0, 1, 1),
         ];
 
         for point in points.iter() {
             assert!(grid.point_exists(point));
         }
 
-        assert!(grid.cell_exists(&[0, 0, 0]));
+        assert!(grid.cell_exists(&ijk(0, 0, 0)));
 
         let origin = grid.get_point(points[0]);
         assert!(origin.is_some());
@@ -1298,19 +1446,19 @@ mod tests {
             grid.get_point_neighbor(&origin, Axis::X.with_direction(Direction::Positive))
                 .unwrap()
                 .index(),
-            &[1, 0, 0]
+            &ijk(1, 0, 0)
         );
         assert_eq!(
             grid.get_point_neighbor(&origin, Axis::Y.with_direction(Direction::Positive))
                 .unwrap()
                 .index(),
-            &[0, 1, 0]
+            &ijk(0, 1, 0)
         );
         assert_eq!(
             grid.get_point_neighbor(&origin, Axis::Z.with_direction(Direction::Positive))
                 .unwrap()
                 .index(),
-            &[0, 0, 1]
+            &ijk(0, 0, 1)
         );
         assert!(grid
             .get_point_neighbor(&origin, Axis::X.with_direction(Direction::Negative))
@@ -1321,5 +1469,19 @@ mod tests {
         assert!(grid
             .get_point_neighbor(&origin, Axis::Z.with_direction(Direction::Negative))
             .is_none());
+
+        // Points outside of the grid do not have an enclosing cell, also for unsigned index types
+        assert_eq!(
+            grid.get_enclosing_cell(&Vector3::new(0.5, 0.5, 0.5))
+                .unwrap()
+                .index(),
+            &ijk(0, 0, 0)
+        );
+        assert!(grid
+            .get_enclosing_cell(&Vector3::new(-0.5, 0.5, 0.5))
+            .is_none());
+        assert!(grid
+            .get_enclosing_cell(&Vector3::new(0.5, 1.5, 0.5))
+            .is_none());
     }
 }
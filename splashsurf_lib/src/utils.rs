@@ -1,9 +1,17 @@
 //! Internal helper functions and types
 
-use log::info;
+use crate::logging::info;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
+#[cfg(feature = "parallel")]
 use std::cell::UnsafeCell;
 
+/// Monotonic clock used for all timings of the crate, `std::time::Instant` panics on `wasm32-unknown-unknown`
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) use std::time::Instant;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) use web_time::Instant;
+
 /// Macro version of Option::map that allows using e.g. using the ?-operator in the map expression
 ///
 /// For example:
@@ -44,13 +52,17 @@ impl<Iter: Iterator> IteratorExt for Iter {
 /// Wrapper for unsafe shared mutable access to a slice, disjoint access has to be ensured separately
 /// Implementation based on: <https://stackoverflow.com/a/65182786/929037>
 #[derive(Copy, Clone)]
+#[cfg(feature = "parallel")]
 pub(crate) struct UnsafeSlice<'a, T> {
     slice: &'a [UnsafeCell<T>],
 }
 
+#[cfg(feature = "parallel")]
 unsafe impl<'a, T: Send + Sync> Send for UnsafeSlice<'a, T> {}
+#[cfg(feature = "parallel")]
 unsafe impl<'a, T: Send + Sync> Sync for UnsafeSlice<'a, T> {}
 
+#[cfg(feature = "parallel")]
 impl<'a, T> UnsafeSlice<'a, T> {
     /// Wraps a slice to be able to share mutable access between threads
     pub fn new(slice: &'a mut [T]) -> Self {
@@ -85,17 +97,19 @@ pub(crate) fn reserve_total<T>(vec: &mut Vec<T>, total_capacity: usize) {
 }
 
 /// Resizes the given vector to the given length and fills new entries with `value.clone()`, parallel or sequential depending on runtime parameter
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
 pub(crate) fn resize_and_fill<T: Clone + Send + Sync>(
     vec: &mut Vec<T>,
     new_len: usize,
     value: T,
     par: bool,
 ) {
+    #[cfg(feature = "parallel")]
     if par {
-        par_resize_and_fill(vec, new_len, value);
-    } else {
-        seq_resize_and_fill(vec, new_len, value);
+        return par_resize_and_fill(vec, new_len, value);
     }
+
+    seq_resize_and_fill(vec, new_len, value);
 }
 
 /// Resizes the given vector to the given length and fills new entries with `value.clone()`, sequential version
@@ -108,6 +122,7 @@ pub(crate) fn seq_resize_and_fill<T: Clone>(vec: &mut Vec<T>, new_len: usize, va
 }
 
 /// Resizes the given vector to the given length and fills new entries with `value.clone()`, parallel version
+#[cfg(feature = "parallel")]
 pub(crate) fn par_resize_and_fill<T: Clone + Send + Sync>(
     vec: &mut Vec<T>,
     new_len: usize,
@@ -121,6 +136,94 @@ pub(crate) fn par_resize_and_fill<T: Clone + Send + Sync>(
     vec.resize(new_len, value);
 }
 
+/// Maps all items of the slice and stores the results in the given vector (replacing its previous content), parallelized if the `parallel` feature is enabled
+pub(crate) fn map_collect_into_vec<T, U, F>(items: &[T], map: F, output: &mut Vec<U>)
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    items.par_iter().map(map).collect_into_vec(output);
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        output.clear();
+        output.extend(items.iter().map(map));
+    }
+}
+
+/// Returns the number of threads of the current thread pool, always one if the `parallel` feature is disabled
+pub(crate) fn current_num_threads() -> usize {
+    #[cfg(feature = "parallel")]
+    return rayon::current_num_threads();
+    #[cfg(not(feature = "parallel"))]
+    return 1;
+}
+
+/// Minimal replacement of `thread_local::ThreadLocal` used if the `parallel` feature is disabled
+///
+/// Stores one value per thread that accessed it. The values are boxed such that references returned
+/// by [`get_or`](Self::get_or) stay valid when values for further threads are inserted.
+#[cfg(not(feature = "parallel"))]
+pub(crate) struct ThreadLocal<T: Send> {
+    values: parking_lot::Mutex<Vec<(std::thread::ThreadId, Box<T>)>>,
+}
+
+#[cfg(not(feature = "parallel"))]
+impl<T: Send> ThreadLocal<T> {
+    /// Creates an empty thread local storage
+    pub fn new() -> Self {
+        Self {
+            values: parking_lot::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the value of the current thread, initializes it with the given function if it does not exist yet
+    pub fn get_or<F: FnOnce() -> T>(&self, create: F) -> &T {
+        let thread = std::thread::current().id();
+        let mut values = self.values.lock();
+        let value: *const T = match values.iter().position(|(id, _)| *id == thread) {
+            Some(i) => &*values[i].1,
+            None => {
+                values.push((thread, Box::new(create())));
+                &*values.last().unwrap().1
+            }
+        };
+        // SAFETY: The boxed values are only dropped or moved out by methods taking `&mut self`,
+        //  so the value outlives the returned reference. Every value is only handed out to its own thread.
+        unsafe { &*value }
+    }
+
+    /// Returns the value of the current thread, initializes it with its default value if it does not exist yet
+    pub fn get_or_default(&self) -> &T
+    where
+        T: Default,
+    {
+        self.get_or(T::default)
+    }
+
+    /// Returns a mutable iterator over the values of all threads
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.values
+            .get_mut()
+            .iter_mut()
+            .map(|(_, value)| &mut **value)
+    }
+
+    /// Drops the values of all threads
+    pub fn clear(&mut self) {
+        self.values.get_mut().clear();
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+impl<T: Send> Default for ThreadLocal<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub struct ParallelPolicy {
     pub min_task_size: usize,
     pub tasks_per_thread: usize,
@@ -143,8 +246,15 @@ pub(crate) struct ChunkSize {
 
 impl ChunkSize {
     pub(crate) fn new(parallel_policy: &ParallelPolicy, num_items: usize) -> Self {
-        let num_threads = rayon::current_num_threads();
+        Self::with_num_threads(parallel_policy, num_items, current_num_threads())
+    }
 
+    /// Computes the chunk size for the given number of threads instead of the threads of the current thread pool
+    pub(crate) fn with_num_threads(
+        parallel_policy: &ParallelPolicy,
+        num_items: usize,
+        num_threads: usize,
+    ) -> Self {
         // Chunks size for 1 chunk per thread
         let equal_distribution = num_items / num_threads;
 
@@ -0,0 +1,131 @@
+//! Entry point for surface reconstructions in the browser using WebAssembly (requires the `wasm` feature)
+//!
+//! The crate has to be compiled for the `wasm32-unknown-unknown` target without the default `parallel` feature, e.g.
+//! using [`wasm-pack`](https://github.com/rustwasm/wasm-pack):
+//! ```text
+//! wasm-pack build splashsurf_lib --target web -- --no-default-features --features wasm
+//! ```
+//! The resulting module exposes the function `reconstructSurface` taking the particle positions as `Float32Array`:
+//! ```text
+//! const mesh = reconstructSurface(positions, particleRadius, compactSupportRadius, cubeSize, isoSurfaceThreshold);
+//! // mesh.vertices: Float32Array (x, y, z per vertex), mesh.indices: Uint32Array (three indices per triangle)
+//! ```
+
+use nalgebra::Vector3;
+use std::error::Error;
+use wasm_bindgen::prelude::*;
+
+use crate::{reconstruct_surface_auto, MarchingCubesVariant, Parameters, SplashOutput};
+
+/// Triangle mesh with flat vertex and index buffers returned by [`reconstruct_surface_wasm`]
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct WasmMesh {
+    vertices: Vec<f32>,
+    indices: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl WasmMesh {
+    /// Returns the vertex coordinates with three consecutive values (x, y, z) per vertex (copied to a `Float32Array`)
+    #[wasm_bindgen(getter)]
+    pub fn vertices(&self) -> Vec<f32> {
+        self.vertices.clone()
+    }
+
+    /// Returns the vertex indices with three consecutive indices per triangle (copied to a `Uint32Array`)
+    #[wasm_bindgen(getter)]
+    pub fn indices(&self) -> Vec<u32> {
+        self.indices.clone()
+    }
+
+    /// Returns the number of vertices of the mesh
+    #[wasm_bindgen(getter, js_name = numVertices)]
+    pub fn num_vertices(&self) -> usize {
+        self.vertices.len() / 3
+    }
+
+    /// Returns the number of triangles of the mesh
+    #[wasm_bindgen(getter, js_name = numTriangles)]
+    pub fn num_triangles(&self) -> usize {
+        self.indices.len() / 3
+    }
+}
+
+/// Reconstructs the surface of the particles with the given flat positions (x, y, z per particle)
+///
+/// All lengths are given in absolute distance units. The reconstruction is performed without spatial decomposition
+/// and multi-threading, so this function is intended for small particle data sets.
+#[wasm_bindgen(js_name = reconstructSurface)]
+pub fn reconstruct_surface_wasm(
+    positions: &[f32],
+    particle_radius: f32,
+    compact_support_radius: f32,
+    cube_size: f32,
+    iso_surface_threshold: f32,
+) -> Result<WasmMesh, JsError> {
+    let parameters = Parameters {
+        particle_radius,
+        rest_density: 1000.0,
+        compact_support_radius,
+        cube_size,
+        iso_surface_threshold,
+        triangulation_scheme: MarchingCubesVariant::Classic,
+        vertex_snapping: None,
+        splash_detection: None,
+        splash_particle_output: SplashOutput::Ignore,
+        domain_aabb: None,
+        clipping_planes: Vec::new(),
+        enable_multi_threading: false,
+        deterministic: false,
+        progress_interval: None,
+        temporal_density_smoothing: None,
+        spatial_decomposition: None,
+    };
+
+    reconstruct_flat(positions, &parameters).map_err(|message| JsError::new(&message))
+}
+
+/// Performs the reconstruction of [`reconstruct_surface_wasm`], returns the error message on failure
+fn reconstruct_flat(positions: &[f32], parameters: &Parameters<f32>) -> Result<WasmMesh, String> {
+    if positions.len() % 3 != 0 {
+        return Err(format!(
+            "the length of the positions array ({}) has to be a multiple of three",
+            positions.len()
+        ));
+    }
+
+    let particles = positions
+        .chunks_exact(3)
+        .map(|p| Vector3::new(p[0], p[1], p[2]))
+        .collect::<Vec<_>>();
+
+    let mut reconstruction = reconstruct_surface_auto(&particles, parameters).map_err(|err| {
+        // Include all sources of the error in the message
+        let mut message = err.to_string();
+        let mut source = err.source();
+        while let Some(err) = source {
+            message.push_str(": ");
+            message.push_str(&err.to_string());
+            source = err.source();
+        }
+        message
+    })?;
+    let mesh = reconstruction.take_mesh();
+
+    if u32::try_from(mesh.vertices.len()).is_err() {
+        return Err(format!(
+            "the mesh has {} vertices, which cannot be indexed with 32 bit indices",
+            mesh.vertices.len()
+        ));
+    }
+
+    Ok(WasmMesh {
+        vertices: mesh.vertices.iter().flat_map(|v| [v.x, v.y, v.z]).collect(),
+        indices: mesh
+            .triangles
+            .iter()
+            .flat_map(|t| t.map(|i| i as u32))
+            .collect(),
+    })
+}
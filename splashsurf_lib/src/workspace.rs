@@ -1,18 +1,51 @@
 //! Workspace for reusing allocated memory between multiple surface reconstructions
 
 use crate::mesh::TriMesh3d;
+#[cfg(not(feature = "parallel"))]
+use crate::utils::ThreadLocal;
 use crate::{new_map, DensityMap, Index, Real};
 use nalgebra::Vector3;
-use std::cell::RefCell;
+use parking_lot::Mutex;
+use std::cell::{RefCell, RefMut};
 use std::fmt;
 use std::fmt::Debug;
+use std::ops::{Deref, DerefMut};
+#[cfg(feature = "parallel")]
 use thread_local::ThreadLocal;
 
 /// Collection of all thread local workspaces used to reduce allocations on subsequent surface reconstructions
+///
+/// The workspace of a [`SurfaceReconstruction`](crate::SurfaceReconstruction) is managed internally, a workspace for
+/// the individual [`stages`](crate::stages) of the reconstruction can be created with [`Default::default`].
 #[derive(Default)]
 pub struct ReconstructionWorkspace<I: Index, R: Real> {
     global_densities: Vec<R>,
     local_workspaces: ThreadLocal<RefCell<LocalReconstructionWorkspace<I, R>>>,
+    /// Workspace retained by the last consolidation that is handed to the next thread requiring a workspace
+    consolidated_workspace: Mutex<Option<LocalReconstructionWorkspace<I, R>>>,
+    leaf_buffers: LeafBufferPool<R>,
+    /// Sizes of the buffers reached in the last reconstruction
+    last_sizes: BufferSizes,
+}
+
+/// Sizes of the buffers reached in a reconstruction, used to reserve memory for the next reconstruction
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct BufferSizes {
+    /// Number of entries of the density map of the global reconstruction
+    pub density_map_entries: usize,
+    /// Number of per particle neighbor lists of the global reconstruction
+    pub neighbor_lists: usize,
+    /// Total number of entries of all neighbor lists of the global reconstruction
+    pub neighbor_list_entries: usize,
+    /// Number of vertices of the surface mesh
+    pub mesh_vertices: usize,
+    /// Number of triangles of the surface mesh
+    pub mesh_triangles: usize,
+}
+
+/// Returns the size that is reserved for a buffer that reached the given size in the last reconstruction (110%)
+fn primed_size(last_size: usize) -> usize {
+    last_size + last_size / 10
 }
 
 impl<I: Index, R: Real> ReconstructionWorkspace<I, R> {
@@ -26,18 +59,44 @@ impl<I: Index, R: Real> ReconstructionWorkspace<I, R> {
         &mut self.global_densities
     }
 
-    /// Returns a reference to a thread local workspace
+    /// Returns a reference to a thread local workspace, takes over the consolidated workspace if not already initialized
     pub(crate) fn get_local(&self) -> &RefCell<LocalReconstructionWorkspace<I, R>> {
-        self.local_workspaces.get_or_default()
+        self.local_workspaces.get_or(|| {
+            RefCell::new(
+                self.consolidated_workspace
+                    .lock()
+                    .take()
+                    .unwrap_or_default(),
+            )
+        })
     }
 
-    /// Returns a reference to a thread local workspace, initializes it with the given capacity if not already initialized
+    /// Returns a reference to a thread local workspace, if not already initialized it takes over the consolidated workspace or is initialized with the given capacity
     pub(crate) fn get_local_with_capacity(
         &self,
         capacity: usize,
     ) -> &RefCell<LocalReconstructionWorkspace<I, R>> {
-        self.local_workspaces
-            .get_or(|| RefCell::new(LocalReconstructionWorkspace::with_capacity(capacity)))
+        self.local_workspaces.get_or(|| {
+            RefCell::new(
+                self.consolidated_workspace
+                    .lock()
+                    .take()
+                    .unwrap_or_else(|| LocalReconstructionWorkspace::with_capacity(capacity)),
+            )
+        })
+    }
+
+    /// Returns the thread local workspace with scratch buffers for the particles of an octree leaf from the shared pool
+    ///
+    /// The particle position, density and active particle buffers of the returned workspace have capacity for the
+    /// given number of particles. They are returned to the pool and the previous buffers of the thread local
+    /// workspace are restored when the returned workspace is dropped.
+    pub(crate) fn get_local_for_leaf(&self, num_particles: usize) -> LeafWorkspace<'_, I, R> {
+        LeafWorkspace::new(
+            self.get_local().borrow_mut(),
+            &self.leaf_buffers,
+            num_particles,
+        )
     }
 
     /// Returns a mutable reference to the thread local workspaces
@@ -46,12 +105,397 @@ impl<I: Index, R: Real> ReconstructionWorkspace<I, R> {
     ) -> &mut ThreadLocal<RefCell<LocalReconstructionWorkspace<I, R>>> {
         &mut self.local_workspaces
     }
+
+    /// Reserves memory in the global density vector and the workspace of the calling thread
+    pub(crate) fn preallocate(&mut self, num_particles: usize, estimated_grid_points: usize) {
+        reserve_total(&mut self.global_densities, num_particles);
+        self.get_local()
+            .borrow_mut()
+            .preallocate(num_particles, estimated_grid_points);
+    }
+
+    /// Frees all memory of the global density vector and the leaf buffer pool and drops all thread local workspaces
+    pub(crate) fn clear(&mut self) {
+        self.global_densities = Vec::new();
+        self.local_workspaces.clear();
+        *self.consolidated_workspace.get_mut() = None;
+        self.leaf_buffers = LeafBufferPool::default();
+        self.last_sizes = BufferSizes::default();
+    }
+
+    /// Stores the density map in the workspace of the current thread to reuse its memory, e.g. in the next call of [`stages::generate_density_map`](crate::stages::generate_density_map)
+    pub fn recycle_density_map(&mut self, density_map: DensityMap<I, R>) {
+        self.get_local().borrow_mut().density_map = density_map;
+    }
+
+    /// Records the sizes of the density map and the neighbor lists reached in the global reconstruction
+    pub(crate) fn record_global_sizes(
+        &mut self,
+        density_map_entries: usize,
+        neighbor_lists: usize,
+        neighbor_list_entries: usize,
+    ) {
+        self.last_sizes.density_map_entries = density_map_entries;
+        self.last_sizes.neighbor_lists = neighbor_lists;
+        self.last_sizes.neighbor_list_entries = neighbor_list_entries;
+    }
+
+    /// Records the number of vertices and triangles of the surface mesh generated by the last reconstruction
+    pub(crate) fn record_mesh_sizes(&mut self, num_vertices: usize, num_triangles: usize) {
+        self.last_sizes.mesh_vertices = num_vertices;
+        self.last_sizes.mesh_triangles = num_triangles;
+    }
+
+    /// Reserves memory in the local workspace for the global reconstruction based on the sizes reached in the last reconstruction
+    ///
+    /// The density map is only primed if it is reused by the density map generation (`prime_density_map`),
+    /// otherwise the reserved memory would be replaced by a new map. Missing neighbor lists are allocated with the
+    /// average length of the neighbor lists of the last reconstruction.
+    pub(crate) fn prime_local(
+        &self,
+        local: &mut LocalReconstructionWorkspace<I, R>,
+        prime_density_map: bool,
+    ) {
+        let sizes = &self.last_sizes;
+        if prime_density_map {
+            local.density_map.reserve(
+                primed_size(sizes.density_map_entries).saturating_sub(local.density_map.len()),
+            );
+        }
+        if sizes.neighbor_lists > local.particle_neighbor_lists.len() {
+            let average_len =
+                primed_size(sizes.neighbor_list_entries.div_ceil(sizes.neighbor_lists));
+            local
+                .particle_neighbor_lists
+                .resize_with(sizes.neighbor_lists, || Vec::with_capacity(average_len));
+        }
+    }
+
+    /// Reserves memory in the surface mesh for appending a mesh of the size generated by the last reconstruction
+    pub(crate) fn prime_mesh(&self, mesh: &mut TriMesh3d<R>) {
+        mesh.vertices
+            .reserve(primed_size(self.last_sizes.mesh_vertices));
+        mesh.triangles
+            .reserve(primed_size(self.last_sizes.mesh_triangles));
+    }
+
+    /// Keeps only the thread local workspace with the most allocated memory and drops the workspaces of all other threads
+    ///
+    /// The retained workspace is not bound to a thread anymore, it is taken over by the first thread that requires
+    /// a workspace in a subsequent reconstruction.
+    pub(crate) fn consolidate(&mut self) {
+        let consolidated_workspace = self.consolidated_workspace.get_mut();
+        let largest = self
+            .local_workspaces
+            .iter_mut()
+            .map(|local| std::mem::take(local.get_mut()))
+            .chain(consolidated_workspace.take())
+            .max_by_key(|local| local.memory_usage().total_bytes());
+        self.local_workspaces.clear();
+        *consolidated_workspace = largest;
+    }
+
+    /// Returns the memory currently allocated by the workspace, including the workspaces of all threads
+    pub(crate) fn memory_usage(&mut self) -> WorkspaceMemoryReport {
+        WorkspaceMemoryReport {
+            global_densities: vec_bytes(&self.global_densities),
+            local_workspaces: self
+                .local_workspaces
+                .iter_mut()
+                .map(|local| local.get_mut().memory_usage())
+                .collect(),
+            consolidated_workspace: self
+                .consolidated_workspace
+                .get_mut()
+                .as_ref()
+                .map(LocalReconstructionWorkspace::memory_usage),
+            leaf_buffers: self.leaf_buffers.memory_usage(),
+        }
+    }
+}
+
+/// Memory allocated by the workspace of a [`SurfaceReconstruction`](crate::SurfaceReconstruction), see [`SurfaceReconstruction::workspace_memory_usage`](crate::SurfaceReconstruction::workspace_memory_usage)
+#[derive(Clone, Debug, Default)]
+pub struct WorkspaceMemoryReport {
+    /// Bytes allocated for the global particle density vector (used by the octree based reconstruction)
+    pub global_densities: usize,
+    /// Memory allocated by the workspaces of the individual threads that took part in previous reconstructions
+    pub local_workspaces: Vec<LocalWorkspaceMemoryReport>,
+    /// Memory retained by the last consolidation of the thread local workspaces that was not yet taken over by a thread, see [`SurfaceReconstruction::consolidate_workspace`](crate::SurfaceReconstruction::consolidate_workspace)
+    pub consolidated_workspace: Option<LocalWorkspaceMemoryReport>,
+    /// Memory of the pool of per leaf scratch buffers shared by all threads (used by the octree based reconstruction)
+    pub leaf_buffers: LeafBufferPoolMemoryReport,
+}
+
+impl WorkspaceMemoryReport {
+    /// Returns the total number of bytes allocated by the workspace
+    pub fn total_bytes(&self) -> usize {
+        self.global_densities
+            + self
+                .local_workspaces
+                .iter()
+                .map(LocalWorkspaceMemoryReport::total_bytes)
+                .sum::<usize>()
+            + self
+                .consolidated_workspace
+                .as_ref()
+                .map_or(0, LocalWorkspaceMemoryReport::total_bytes)
+            + self.leaf_buffers.pooled_bytes
+    }
+}
+
+/// Memory of the pool of per leaf scratch buffers (particle positions, densities and active particles), all values in bytes
+///
+/// The buffers are acquired by the threads for the duration of the processing of a single octree leaf and are
+/// released to the pool afterwards. Between reconstructions all buffers are in the pool.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LeafBufferPoolMemoryReport {
+    /// Bytes of the buffers currently stored in the pool
+    pub pooled_bytes: usize,
+    /// Total bytes of all buffers newly allocated by the pool since its creation
+    pub allocated_bytes: usize,
+    /// Maximum number of bytes of the buffers that were acquired by the threads at the same time (high-water mark)
+    pub peak_bytes_in_use: usize,
+}
+
+/// Memory allocated by the workspace of a single thread, all values in bytes
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LocalWorkspaceMemoryReport {
+    /// Bytes allocated for particle positions (only used by the octree based reconstruction)
+    pub particle_positions: usize,
+    /// Bytes allocated for the per particle neighbor lists (including the individual lists)
+    pub particle_neighbor_lists: usize,
+    /// Bytes allocated for the per particle densities
+    pub particle_densities: usize,
+    /// Bytes allocated for the indices of the particles not classified as splash particles
+    pub active_particles: usize,
+    /// Bytes allocated for vertices and triangles of the local mesh
+    pub mesh: usize,
+    /// Approximate number of bytes allocated for the sparse density map
+    pub density_map: usize,
+}
+
+impl LocalWorkspaceMemoryReport {
+    /// Returns the total number of bytes allocated by the local workspace
+    pub fn total_bytes(&self) -> usize {
+        self.particle_positions
+            + self.particle_neighbor_lists
+            + self.particle_densities
+            + self.active_particles
+            + self.mesh
+            + self.density_map
+    }
+}
+
+/// Returns the number of bytes allocated by the given vector
+fn vec_bytes<T>(vec: &Vec<T>) -> usize {
+    vec.capacity() * std::mem::size_of::<T>()
+}
+
+/// Reserves capacity such that the vector can hold at least the given total number of elements
+fn reserve_total<T>(vec: &mut Vec<T>, total: usize) {
+    vec.reserve(total.saturating_sub(vec.len()));
+}
+
+/// Scratch buffers for the particles of a single octree leaf
+#[derive(Default)]
+struct LeafBuffers<R: Real> {
+    particle_positions: Vec<Vector3<R>>,
+    particle_densities: Vec<R>,
+    active_particles: Vec<usize>,
+}
+
+impl<R: Real> LeafBuffers<R> {
+    /// Swaps the buffers with the corresponding buffers of the local workspace
+    fn swap_with<I: Index>(&mut self, workspace: &mut LocalReconstructionWorkspace<I, R>) {
+        std::mem::swap(
+            &mut self.particle_positions,
+            &mut workspace.particle_positions,
+        );
+        std::mem::swap(
+            &mut self.particle_densities,
+            &mut workspace.particle_densities,
+        );
+        std::mem::swap(&mut self.active_particles, &mut workspace.active_particles);
+    }
+
+    /// Returns the number of bytes allocated by the buffers
+    fn bytes(&self) -> usize {
+        vec_bytes(&self.particle_positions)
+            + vec_bytes(&self.particle_densities)
+            + vec_bytes(&self.active_particles)
+    }
+}
+
+/// Released vectors sorted into buckets by the power of two of their capacity
+struct BufferBuckets<T> {
+    /// The vectors in bucket `i` have a capacity of at least `2^i` and less than `2^(i+1)`
+    buckets: Vec<Vec<Vec<T>>>,
+}
+
+impl<T> Default for BufferBuckets<T> {
+    fn default() -> Self {
+        Self {
+            buckets: Vec::new(),
+        }
+    }
+}
+
+impl<T> BufferBuckets<T> {
+    /// Returns an empty vector with at least the given capacity, the second value is `true` if it was newly allocated
+    ///
+    /// The vector is taken from the smallest non-empty bucket that guarantees the capacity. New vectors are
+    /// allocated with the capacity rounded up to the next power of two such that they can be reused for all
+    /// requests of the same bucket.
+    fn acquire(&mut self, capacity: usize) -> (Vec<T>, bool) {
+        let capacity = capacity.max(1).next_power_of_two();
+        let min_bucket = capacity.trailing_zeros() as usize;
+        self.buckets
+            .iter_mut()
+            .skip(min_bucket)
+            .find_map(|bucket| bucket.pop())
+            .map(|vec| (vec, false))
+            .unwrap_or_else(|| (Vec::with_capacity(capacity), true))
+    }
+
+    /// Clears the vector and stores it in the bucket of its capacity, vectors without allocation are dropped
+    fn release(&mut self, mut vec: Vec<T>) {
+        if vec.capacity() == 0 {
+            return;
+        }
+        vec.clear();
+        let bucket = (usize::BITS - 1 - vec.capacity().leading_zeros()) as usize;
+        if self.buckets.len() <= bucket {
+            self.buckets.resize_with(bucket + 1, Vec::new);
+        }
+        self.buckets[bucket].push(vec);
+    }
+
+    /// Returns the number of bytes allocated by all vectors in the buckets
+    fn bytes(&self) -> usize {
+        self.buckets.iter().flatten().map(vec_bytes).sum()
+    }
+}
+
+/// Pool of the per leaf scratch buffers shared by all threads of a reconstruction
+///
+/// Compared to buffers owned by the thread local workspaces, the memory is bounded by the leaves that are
+/// processed at the same time instead of the largest leaf processed by every thread.
+#[derive(Default)]
+struct LeafBufferPool<R: Real> {
+    state: Mutex<LeafBufferPoolState<R>>,
+}
+
+#[derive(Default)]
+struct LeafBufferPoolState<R: Real> {
+    particle_positions: BufferBuckets<Vector3<R>>,
+    particle_densities: BufferBuckets<R>,
+    active_particles: BufferBuckets<usize>,
+    bytes_in_use: usize,
+    peak_bytes_in_use: usize,
+    allocated_bytes: usize,
+}
+
+impl<R: Real> LeafBufferPool<R> {
+    /// Acquires buffers with capacity for the given number of particles, returns them with their size in bytes
+    fn acquire(&self, num_particles: usize) -> (LeafBuffers<R>, usize) {
+        let mut state = self.state.lock();
+        let (particle_positions, new_positions) = state.particle_positions.acquire(num_particles);
+        let (particle_densities, new_densities) = state.particle_densities.acquire(num_particles);
+        let (active_particles, new_active) = state.active_particles.acquire(num_particles);
+        let buffers = LeafBuffers {
+            particle_positions,
+            particle_densities,
+            active_particles,
+        };
+
+        let bytes = buffers.bytes();
+        state.allocated_bytes += [
+            (new_positions, vec_bytes(&buffers.particle_positions)),
+            (new_densities, vec_bytes(&buffers.particle_densities)),
+            (new_active, vec_bytes(&buffers.active_particles)),
+        ]
+        .iter()
+        .filter(|(is_new, _)| *is_new)
+        .map(|(_, bytes)| bytes)
+        .sum::<usize>();
+        state.bytes_in_use += bytes;
+        state.peak_bytes_in_use = state.peak_bytes_in_use.max(state.bytes_in_use);
+        (buffers, bytes)
+    }
+
+    /// Returns the buffers to the pool, `acquired_bytes` is the size returned by the corresponding [`Self::acquire`] call
+    fn release(&self, buffers: LeafBuffers<R>, acquired_bytes: usize) {
+        let mut state = self.state.lock();
+        state.bytes_in_use -= acquired_bytes;
+        state.particle_positions.release(buffers.particle_positions);
+        state.particle_densities.release(buffers.particle_densities);
+        state.active_particles.release(buffers.active_particles);
+    }
+
+    /// Returns the memory allocated by the pool and the high-water mark of the buffers in use
+    fn memory_usage(&mut self) -> LeafBufferPoolMemoryReport {
+        let state = self.state.get_mut();
+        LeafBufferPoolMemoryReport {
+            pooled_bytes: state.particle_positions.bytes()
+                + state.particle_densities.bytes()
+                + state.active_particles.bytes(),
+            allocated_bytes: state.allocated_bytes,
+            peak_bytes_in_use: state.peak_bytes_in_use,
+        }
+    }
+}
+
+/// Thread local workspace whose per leaf scratch buffers were acquired from the shared [`LeafBufferPool`]
+///
+/// Dereferences to the [`LocalReconstructionWorkspace`] such that it can be used in the same way as the thread
+/// local workspace itself.
+pub(crate) struct LeafWorkspace<'a, I: Index, R: Real> {
+    local: RefMut<'a, LocalReconstructionWorkspace<I, R>>,
+    pool: &'a LeafBufferPool<R>,
+    /// The buffers of the thread local workspace that are restored on drop
+    stashed: LeafBuffers<R>,
+    acquired_bytes: usize,
+}
+
+impl<'a, I: Index, R: Real> LeafWorkspace<'a, I, R> {
+    fn new(
+        mut local: RefMut<'a, LocalReconstructionWorkspace<I, R>>,
+        pool: &'a LeafBufferPool<R>,
+        num_particles: usize,
+    ) -> Self {
+        let (mut stashed, acquired_bytes) = pool.acquire(num_particles);
+        stashed.swap_with(&mut local);
+        Self {
+            local,
+            pool,
+            stashed,
+            acquired_bytes,
+        }
+    }
 }
 
-impl<I: Index, R: Real> Clone for ReconstructionWorkspace<I, R> {
-    /// Returns a new default workspace without any allocated memory
-    fn clone(&self) -> Self {
-        ReconstructionWorkspace::default()
+impl<I: Index, R: Real> Deref for LeafWorkspace<'_, I, R> {
+    type Target = LocalReconstructionWorkspace<I, R>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.local
+    }
+}
+
+impl<I: Index, R: Real> DerefMut for LeafWorkspace<'_, I, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.local
+    }
+}
+
+impl<I: Index, R: Real> Drop for LeafWorkspace<'_, I, R> {
+    /// Restores the buffers of the thread local workspace and releases the pooled buffers
+    fn drop(&mut self) {
+        // Buffers that were taken from the workspace and not put back (e.g. on errors) are released empty
+        let mut buffers = std::mem::take(&mut self.stashed);
+        buffers.swap_with(&mut self.local);
+        self.pool.release(buffers, self.acquired_bytes);
     }
 }
 
@@ -70,10 +514,11 @@ pub(crate) struct LocalReconstructionWorkspace<I: Index, R: Real> {
     pub particle_neighbor_lists: Vec<Vec<usize>>,
     /// Storage for per particle densities
     pub particle_densities: Vec<R>,
+    /// Storage for the indices of all particles that are not classified as splash particles
+    pub active_particles: Vec<usize>,
     /// Storage for the final surface mesh
     pub mesh: TriMesh3d<R>,
-    /// Storage for the density level-set
-    #[allow(unused)]
+    /// Storage for the density level-set (only reused by the global reconstruction)
     pub density_map: DensityMap<I, R>,
 }
 
@@ -91,6 +536,7 @@ impl<I: Index, R: Real> LocalReconstructionWorkspace<I, R> {
             particle_positions: Default::default(),
             particle_neighbor_lists: Default::default(),
             particle_densities: Default::default(),
+            active_particles: Default::default(),
             mesh: Default::default(),
             density_map: new_map().into(),
         }
@@ -102,8 +548,37 @@ impl<I: Index, R: Real> LocalReconstructionWorkspace<I, R> {
             particle_positions: Vec::with_capacity(capacity),
             particle_neighbor_lists: Vec::with_capacity(capacity),
             particle_densities: Vec::with_capacity(capacity),
+            active_particles: Default::default(),
             mesh: Default::default(),
             density_map: new_map().into(),
         }
     }
+
+    /// Reserves memory for the given number of particles and density map entries
+    fn preallocate(&mut self, num_particles: usize, estimated_grid_points: usize) {
+        reserve_total(&mut self.particle_positions, num_particles);
+        reserve_total(&mut self.particle_neighbor_lists, num_particles);
+        reserve_total(&mut self.particle_densities, num_particles);
+        reserve_total(&mut self.active_particles, num_particles);
+        self.density_map
+            .reserve(estimated_grid_points.saturating_sub(self.density_map.len()));
+    }
+
+    /// Returns the memory currently allocated by this workspace
+    fn memory_usage(&self) -> LocalWorkspaceMemoryReport {
+        LocalWorkspaceMemoryReport {
+            particle_positions: vec_bytes(&self.particle_positions),
+            particle_neighbor_lists: vec_bytes(&self.particle_neighbor_lists)
+                + self
+                    .particle_neighbor_lists
+                    .iter()
+                    .map(vec_bytes)
+                    .sum::<usize>(),
+            particle_densities: vec_bytes(&self.particle_densities),
+            active_particles: vec_bytes(&self.active_particles),
+            mesh: vec_bytes(&self.mesh.vertices) + vec_bytes(&self.mesh.triangles),
+            // Hash maps store one additional control byte per entry
+            density_map: self.density_map.capacity() * (std::mem::size_of::<(I, R)>() + 1),
+        }
+    }
 }
@@ -1,5 +1,112 @@
+use nalgebra::Vector3;
+use splashsurf_lib::{MarchingCubesVariant, Parameters, SplashOutput};
+
+pub mod test_append;
+pub mod test_attribute_interpolation;
+pub mod test_attributes;
+pub mod test_clipping;
+pub mod test_contour_2d;
+pub mod test_deterministic;
+pub mod test_fixed_grid;
 #[cfg(feature = "io")]
 pub mod test_full;
+pub mod test_mesh_distance;
+pub mod test_mesh_smoothing;
+pub mod test_mesh_validity;
+pub mod test_mesh_volume;
+pub mod test_neighborhood_provider;
 pub mod test_neighborhood_search;
 #[cfg(feature = "io")]
 pub mod test_octree;
+pub mod test_parameter_suggestion;
+pub mod test_parameters;
+pub mod test_particle_validation;
+#[cfg(feature = "profiling")]
+pub mod test_profiling;
+#[cfg(not(feature = "tracing"))]
+pub mod test_progress;
+pub mod test_reconstruction_auto;
+pub mod test_reconstruction_errors;
+#[cfg(feature = "reference-impl")]
+pub mod test_reference;
+#[cfg(feature = "serde")]
+pub mod test_serde;
+pub mod test_splash;
+pub mod test_stages;
+pub mod test_surface_reconstruction;
+pub mod test_temporal_smoothing;
+#[cfg(feature = "parallel")]
+pub mod test_thread_pool;
+pub mod test_timings;
+#[cfg(feature = "tracing")]
+pub mod test_tracing;
+pub mod test_triangle_origins;
+#[cfg(feature = "io")]
+pub mod test_vtk_io;
+pub mod test_warnings;
+pub mod test_workspace_priming;
+
+/// Generates a block of `n`×`n`×`n` particles on a regular lattice with a spacing of twice the particle radius
+pub fn particle_block(particle_radius: f64, n: usize) -> Vec<Vector3<f64>> {
+    particle_cuboid(particle_radius, [n, n, n])
+}
+
+/// Generates a block of `nx`×`ny`×`nz` particles on a regular lattice with a spacing of twice the particle radius
+pub fn particle_cuboid(particle_radius: f64, [nx, ny, nz]: [usize; 3]) -> Vec<Vector3<f64>> {
+    let spacing = 2.0 * particle_radius;
+    let mut particles = Vec::with_capacity(nx * ny * nz);
+    for i in 0..nx {
+        for j in 0..ny {
+            for k in 0..nz {
+                particles.push(Vector3::new(i as f64, j as f64, k as f64) * spacing);
+            }
+        }
+    }
+    particles
+}
+
+/// Generates the particles of a regular lattice with the given spacing inside of a sphere, the center is a lattice point
+pub fn sphere_particles(spacing: f64, radius: f64, center: Vector3<f64>) -> Vec<Vector3<f64>> {
+    let n = (radius / spacing).ceil() as i32;
+    let mut particles = Vec::new();
+    for i in -n..=n {
+        for j in -n..=n {
+            for k in -n..=n {
+                let x = Vector3::new(i as f64, j as f64, k as f64) * spacing;
+                if x.norm() <= radius {
+                    particles.push(center + x);
+                }
+            }
+        }
+    }
+    particles
+}
+
+/// Returns the spacing of a lattice where every particle occupies its rest volume `4/3 π r³`
+pub fn rest_volume_spacing(particle_radius: f64) -> f64 {
+    particle_radius * (4.0 / 3.0 * std::f64::consts::PI).cbrt()
+}
+
+/// Parameters of a single-threaded global reconstruction with a compact support radius of four and a cube size of 0.75 particle radii
+///
+/// Tests that need other settings override the respective fields, e.g. `Parameters { cube_size, ..default_parameters(r) }`.
+pub fn default_parameters(particle_radius: f64) -> Parameters<f64> {
+    Parameters {
+        particle_radius,
+        rest_density: 1000.0,
+        compact_support_radius: 4.0 * particle_radius,
+        cube_size: 0.75 * particle_radius,
+        iso_surface_threshold: 0.6,
+        triangulation_scheme: MarchingCubesVariant::Classic,
+        vertex_snapping: None,
+        splash_detection: None,
+        splash_particle_output: SplashOutput::Ignore,
+        domain_aabb: None,
+        clipping_planes: Vec::new(),
+        enable_multi_threading: false,
+        deterministic: false,
+        progress_interval: None,
+        temporal_density_smoothing: None,
+        spatial_decomposition: None,
+    }
+}
@@ -0,0 +1,166 @@
+use super::{default_parameters, sphere_particles};
+use nalgebra::Vector3;
+use splashsurf_lib::mesh::{AttributeData, MeshAttribute, MeshWithData, TriMesh3d};
+use splashsurf_lib::{
+    reconstruct_surface, reconstruct_surface_append, reconstruct_surface_append_with_attributes,
+    AttributeError, AttributeSpecs, AxisAlignedBoundingBox3d, NormalsComputation, Parameters,
+    ParticleDensityComputationStrategy, ReconstructionError, SpatialDecompositionParameters,
+    SubdivisionCriterion,
+};
+
+const PARTICLE_RADIUS: f64 = 0.025;
+
+/// Two separated blobs of different size
+fn blobs() -> [Vec<Vector3<f64>>; 2] {
+    [
+        sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::zeros()),
+        sphere_particles(2.0 * PARTICLE_RADIUS, 0.15, Vector3::new(0.6, 0.1, 0.0)),
+    ]
+}
+
+/// Parameters with a domain enclosing both blobs such that all reconstructions use the same background grid
+fn params(octree: bool) -> Parameters<f64> {
+    Parameters {
+        domain_aabb: Some(AxisAlignedBoundingBox3d::new(
+            Vector3::repeat(-0.5),
+            Vector3::new(1.0, 0.5, 0.5),
+        )),
+        enable_multi_threading: octree,
+        spatial_decomposition: octree.then_some(SpatialDecompositionParameters {
+            subdivision_criterion: SubdivisionCriterion::MaxParticleCount(500),
+            ghost_particle_safety_factor: None,
+            enable_stitching: true,
+            particle_density_computation: ParticleDensityComputationStrategy::Global,
+        }),
+        ..default_parameters(PARTICLE_RADIUS)
+    }
+}
+
+/// Returns the vertices of the mesh rounded to a fraction of the particle radius in lexicographical order
+///
+/// The density values of the octree reconstruction differ by round-off errors depending on the subdomains.
+fn rounded_vertices(vertices: &[Vector3<f64>]) -> Vec<Vector3<i64>> {
+    let mut vertices = vertices
+        .iter()
+        .map(|v| (v / (1e-6 * PARTICLE_RADIUS)).map(|c| c.round() as i64))
+        .collect::<Vec<_>>();
+    vertices.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+    vertices
+}
+
+#[test]
+fn test_append_two_blobs() {
+    for octree in [false, true] {
+        let parameters = params(octree);
+        let [blob_a, blob_b] = blobs();
+
+        let mut mesh = TriMesh3d::default();
+        let range_a =
+            reconstruct_surface_append::<i64, f64>(&blob_a, &parameters, &mut mesh).unwrap();
+        let num_vertices_a = mesh.vertices.len();
+        let num_triangles_a = mesh.triangles.len();
+        assert_eq!(range_a.vertices, 0..num_vertices_a);
+        assert_eq!(range_a.triangles, 0..num_triangles_a);
+
+        let range_b =
+            reconstruct_surface_append::<i64, f64>(&blob_b, &parameters, &mut mesh).unwrap();
+        assert_eq!(range_b.vertices, num_vertices_a..mesh.vertices.len());
+        assert_eq!(range_b.triangles, num_triangles_a..mesh.triangles.len());
+        assert!(!range_b.vertices.is_empty());
+
+        // The appended triangles only reference the vertices appended in the same call
+        for (range, other) in [(&range_a, &range_b), (&range_b, &range_a)] {
+            assert!(mesh.triangles[range.triangles.clone()]
+                .iter()
+                .flatten()
+                .all(|v| range.vertices.contains(v) && !other.vertices.contains(v)));
+        }
+
+        // Same surface as a single reconstruction of both blobs (up to the order of the components)
+        let all_particles = [blob_a, blob_b].concat();
+        let reconstruction = reconstruct_surface::<i64, f64>(&all_particles, &parameters).unwrap();
+        assert_eq!(mesh.triangles.len(), reconstruction.mesh().triangles.len());
+        assert_eq!(
+            rounded_vertices(&mesh.vertices),
+            rounded_vertices(&reconstruction.mesh().vertices)
+        );
+        assert!((mesh.area() - reconstruction.mesh().area()).abs() < 1e-9);
+        assert_eq!(mesh.find_boundary_edges().len(), 0);
+    }
+}
+
+#[test]
+fn test_append_with_attributes() {
+    let parameters = params(false);
+    let [blob_a, blob_b] = blobs();
+
+    let attributes_of = |particles: &[Vector3<f64>], value: f64| {
+        vec![MeshAttribute::new_real_scalar(
+            "temperature",
+            vec![value; particles.len()],
+        )]
+    };
+    let attributes_a = attributes_of(&blob_a, 300.0);
+    let attributes_b = attributes_of(&blob_b, 400.0);
+    let specs = |attributes| AttributeSpecs {
+        particle_attributes: attributes,
+        normals: Some(NormalsComputation::AreaWeighted),
+        ..AttributeSpecs::default()
+    };
+
+    // The attributes of the first reconstruction are adopted by the empty mesh
+    let mut mesh = MeshWithData::new(TriMesh3d::default());
+    let range_a = reconstruct_surface_append_with_attributes::<i64, f64>(
+        &blob_a,
+        &parameters,
+        &specs(&attributes_a),
+        &mut mesh,
+    )
+    .unwrap();
+    let range_b = reconstruct_surface_append_with_attributes::<i64, f64>(
+        &blob_b,
+        &parameters,
+        &specs(&attributes_b),
+        &mut mesh,
+    )
+    .unwrap();
+    assert_eq!(range_b.vertices.end, mesh.mesh.vertices.len());
+
+    // All point attributes stay aligned with the vertices
+    assert_eq!(mesh.point_attributes.len(), 2);
+    for attribute in &mesh.point_attributes {
+        match &attribute.data {
+            AttributeData::ScalarReal(values) => {
+                assert_eq!(values.len(), mesh.mesh.vertices.len());
+                for (range, expected) in [(&range_a, 300.0), (&range_b, 400.0)] {
+                    assert!(values[range.vertices.clone()]
+                        .iter()
+                        .all(|v| (v - expected).abs() < 1e-6));
+                }
+            }
+            AttributeData::Vector3Real(normals) => {
+                assert_eq!(normals.len(), mesh.mesh.vertices.len())
+            }
+            data => panic!("unexpected attribute data {:?}", data),
+        }
+    }
+
+    // A reconstruction without the attributes of the mesh cannot be appended
+    let num_vertices = mesh.mesh.vertices.len();
+    let err = reconstruct_surface_append_with_attributes::<i64, f64>(
+        &blob_b,
+        &parameters,
+        &specs(&[]),
+        &mut mesh,
+    )
+    .unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            ReconstructionError::AttributeError(AttributeError::AppendMismatch(name)) if name == "temperature"
+        ),
+        "unexpected error: {:?}",
+        err
+    );
+    assert_eq!(mesh.mesh.vertices.len(), num_vertices);
+}
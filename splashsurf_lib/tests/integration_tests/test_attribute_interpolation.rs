@@ -0,0 +1,146 @@
+use super::particle_block;
+use nalgebra::Vector3;
+use splashsurf_lib::mesh::{AttributeData, MeshAttribute};
+use splashsurf_lib::sph_interpolation::interpolate_particle_attributes;
+use splashsurf_lib::AttributeError;
+
+const PARTICLE_RADIUS: f64 = 0.025;
+const REST_DENSITY: f64 = 1000.0;
+
+fn rest_mass() -> f64 {
+    REST_DENSITY * (4.0 / 3.0) * std::f64::consts::PI * PARTICLE_RADIUS.powi(3)
+}
+
+#[test]
+fn test_interpolate_particle_attributes() {
+    let particles = particle_block(PARTICLE_RADIUS, 10);
+    let densities = vec![REST_DENSITY; particles.len()];
+
+    // Linear fields are reproduced in the interior of the block thanks to the first order correction
+    let temperature = |x: &Vector3<f64>| 300.0 + 10.0 * x.z;
+    let velocity = |x: &Vector3<f64>| Vector3::new(x.x, 2.0 * x.y, -1.0);
+    let attributes = vec![
+        MeshAttribute::new_real_scalar(
+            "temperature",
+            particles.iter().map(temperature).collect::<Vec<_>>(),
+        ),
+        MeshAttribute::new_real_vector3(
+            "velocity",
+            particles.iter().map(velocity).collect::<Vec<_>>(),
+        ),
+        MeshAttribute::new(
+            "two_components",
+            AttributeData::VectorReal {
+                num_components: 2,
+                values: particles
+                    .iter()
+                    .flat_map(|x| [temperature(x), -temperature(x)])
+                    .collect(),
+            },
+        ),
+    ];
+
+    let center = Vector3::repeat(4.5 * 2.0 * PARTICLE_RADIUS);
+    let vertices = vec![
+        center,
+        center + Vector3::new(0.013, -0.021, 0.007),
+        // Far outside of the block
+        Vector3::repeat(10.0),
+    ];
+
+    let result = interpolate_particle_attributes(
+        &vertices,
+        &particles,
+        &attributes,
+        4.0 * PARTICLE_RADIUS,
+        rest_mass(),
+        &densities,
+    )
+    .unwrap();
+    assert_eq!(result.vertices_without_particles, 1);
+    assert_eq!(result.attributes.len(), 3);
+    assert_eq!(result.attributes[0].name, "temperature");
+    assert_eq!(result.attributes[1].name, "velocity");
+
+    match &result.attributes[0].data {
+        AttributeData::ScalarReal(values) => {
+            assert_eq!(values.len(), vertices.len());
+            for (value, vertex) in values.iter().zip(vertices.iter()).take(2) {
+                assert!((value - temperature(vertex)).abs() < 1e-2, "{}", value);
+            }
+            assert_eq!(values[2], 0.0);
+        }
+        _ => panic!("wrong attribute type"),
+    }
+
+    match &result.attributes[1].data {
+        AttributeData::Vector3Real(values) => {
+            assert_eq!(values.len(), vertices.len());
+            for (value, vertex) in values.iter().zip(vertices.iter()).take(2) {
+                assert!((value - velocity(vertex)).norm() < 1e-3, "{:?}", value);
+            }
+            assert_eq!(values[2], Vector3::zeros());
+        }
+        _ => panic!("wrong attribute type"),
+    }
+
+    match &result.attributes[2].data {
+        AttributeData::VectorReal {
+            num_components: 2,
+            values,
+        } => {
+            assert_eq!(values.len(), 2 * vertices.len());
+            for (pair, vertex) in values.chunks(2).zip(vertices.iter()).take(2) {
+                assert!((pair[0] - temperature(vertex)).abs() < 1e-2);
+                assert!((pair[1] + temperature(vertex)).abs() < 1e-2);
+            }
+            assert_eq!(&values[4..], &[0.0, 0.0]);
+        }
+        _ => panic!("wrong attribute type"),
+    }
+}
+
+#[test]
+fn test_interpolate_particle_attributes_errors() {
+    let particles = particle_block(PARTICLE_RADIUS, 3);
+    let densities = vec![REST_DENSITY; particles.len()];
+    let interpolate = |attribute: MeshAttribute<f64>| {
+        interpolate_particle_attributes(
+            &particles,
+            &particles,
+            &[attribute],
+            4.0 * PARTICLE_RADIUS,
+            rest_mass(),
+            &densities,
+        )
+    };
+
+    assert!(matches!(
+        interpolate(MeshAttribute::new(
+            "id",
+            AttributeData::ScalarU64(vec![1; particles.len()])
+        )),
+        Err(AttributeError::UnsupportedType(name)) if name == "id"
+    ));
+    assert!(matches!(
+        interpolate(MeshAttribute::new_real_scalar("pressure", vec![1.0; 5])),
+        Err(AttributeError::LengthMismatch {
+            len: 5,
+            expected: 27,
+            ..
+        })
+    ));
+
+    // Without attributes only the vertices without particles are counted
+    let result = interpolate_particle_attributes(
+        &[Vector3::repeat(-1.0)],
+        &particles,
+        &[],
+        4.0 * PARTICLE_RADIUS,
+        rest_mass(),
+        &densities,
+    )
+    .unwrap();
+    assert!(result.attributes.is_empty());
+    assert_eq!(result.vertices_without_particles, 1);
+}
@@ -0,0 +1,426 @@
+use super::default_parameters;
+use nalgebra::Vector3;
+use splashsurf_lib::mesh::{AttributeData, MeshAttribute, MeshWithData, TriMesh3d};
+use splashsurf_lib::{
+    reconstruct_surface_with_attributes, AttributeError, AttributeInterpolation, AttributeSpecs,
+    NormalsComputation, Parameters, ParticleDensityComputationStrategy,
+    SpatialDecompositionParameters, SplashDetection, SplashOutput, SubdivisionCriterion,
+};
+
+/// Generates a block of particles on a regular lattice followed by a single isolated particle
+fn particles(particle_radius: f64, n: usize) -> Vec<Vector3<f64>> {
+    let mut particles = super::particle_block(particle_radius, n);
+    particles.push(Vector3::new(1.0, 1.0, 1.0));
+    particles
+}
+
+fn params(particle_radius: f64) -> Parameters<f64> {
+    Parameters {
+        splash_detection: Some(SplashDetection::NeighborCount(4.0 * particle_radius)),
+        splash_particle_output: SplashOutput::MergedSpheres {
+            sphere_subdivisions: 1,
+        },
+        ..default_parameters(particle_radius)
+    }
+}
+
+fn find_attribute<'a>(attributes: &'a [MeshAttribute<f64>], name: &str) -> &'a AttributeData<f64> {
+    &attributes
+        .iter()
+        .find(|attribute| attribute.name == name)
+        .unwrap_or_else(|| panic!("attribute \"{}\" is missing", name))
+        .data
+}
+
+fn attribute_len(data: &AttributeData<f64>) -> usize {
+    match data {
+        AttributeData::ScalarU64(values) => values.len(),
+        AttributeData::ScalarReal(values) => values.len(),
+        AttributeData::Vector3Real(values) => values.len(),
+        AttributeData::VectorReal {
+            num_components,
+            values,
+        } => values.len() / num_components,
+    }
+}
+
+fn check_attributes(mesh: &MeshWithData<f64, TriMesh3d<f64>>, expect_leaf_ids: bool) {
+    let num_vertices = mesh.mesh.vertices.len();
+    let num_triangles = mesh.mesh.triangles.len();
+    assert!(num_triangles > 0);
+
+    for name in ["normals", "density", "velocity", "pressure"].iter() {
+        assert_eq!(
+            attribute_len(find_attribute(&mesh.point_attributes, name)),
+            num_vertices
+        );
+    }
+    assert_eq!(
+        attribute_len(find_attribute(&mesh.cell_attributes, "cell_id")),
+        num_triangles
+    );
+    if expect_leaf_ids {
+        assert_eq!(
+            attribute_len(find_attribute(&mesh.cell_attributes, "leaf_id")),
+            num_triangles
+        );
+    } else {
+        assert!(mesh.cell_attributes.iter().all(|a| a.name != "leaf_id"));
+    }
+
+    // Normals are unit vectors
+    if let AttributeData::Vector3Real(normals) = find_attribute(&mesh.point_attributes, "normals") {
+        assert!(normals.iter().all(|n| (n.norm() - 1.0).abs() < 1e-6));
+    } else {
+        panic!("normals have wrong type");
+    }
+
+    // The constant pressure is reproduced by the first order consistent interpolation
+    if let AttributeData::ScalarReal(pressure) = find_attribute(&mesh.point_attributes, "pressure")
+    {
+        assert!(pressure.iter().all(|p| (p - 100.0).abs() < 1e-6));
+    } else {
+        panic!("pressure has wrong type");
+    }
+}
+
+fn particle_attributes(num_particles: usize) -> Vec<MeshAttribute<f64>> {
+    vec![
+        MeshAttribute::new_real_vector3(
+            "velocity",
+            vec![Vector3::new(1.0, 0.0, 0.0); num_particles],
+        ),
+        MeshAttribute::new_real_scalar("pressure", vec![100.0; num_particles]),
+    ]
+}
+
+#[test]
+fn test_reconstruct_with_attributes_global() {
+    let particle_radius = 0.025;
+    let particles = particles(particle_radius, 10);
+    let attributes = particle_attributes(particles.len());
+
+    let specs = AttributeSpecs {
+        normals: Some(NormalsComputation::SphInterpolation),
+        vertex_densities: true,
+        triangle_cell_ids: true,
+        triangle_leaf_ids: true,
+        particle_attributes: attributes.as_slice(),
+        particle_attribute_interpolation: &[],
+        density_attributes: false,
+    };
+
+    let mesh = reconstruct_surface_with_attributes::<i64, f64>(
+        particles.as_slice(),
+        &params(particle_radius),
+        &specs,
+    )
+    .unwrap();
+    check_attributes(&mesh, false);
+}
+
+#[test]
+fn test_reconstruct_with_attributes_octree() {
+    let particle_radius = 0.025;
+    let particles = particles(particle_radius, 10);
+    let attributes = particle_attributes(particles.len());
+
+    let mut parameters = params(particle_radius);
+    parameters.spatial_decomposition = Some(SpatialDecompositionParameters {
+        subdivision_criterion: SubdivisionCriterion::MaxParticleCount(100),
+        ghost_particle_safety_factor: None,
+        enable_stitching: true,
+        particle_density_computation: ParticleDensityComputationStrategy::IndependentSubdomains,
+    });
+
+    let specs = AttributeSpecs {
+        normals: Some(NormalsComputation::AreaWeighted),
+        vertex_densities: true,
+        triangle_cell_ids: true,
+        triangle_leaf_ids: true,
+        particle_attributes: attributes.as_slice(),
+        particle_attribute_interpolation: &[],
+        density_attributes: false,
+    };
+
+    let mesh =
+        reconstruct_surface_with_attributes::<i64, f64>(particles.as_slice(), &parameters, &specs)
+            .unwrap();
+    check_attributes(&mesh, true);
+}
+
+#[test]
+fn test_reconstruct_with_density_attributes() {
+    let particle_radius = 0.025;
+    let particles = particles(particle_radius, 10);
+
+    let mut parameters = params(particle_radius);
+    // Vertices of merged splash particle spheres do not have a marching cubes density
+    parameters.splash_particle_output = SplashOutput::Ignore;
+    let mut octree_parameters = parameters.clone();
+    octree_parameters.spatial_decomposition = Some(SpatialDecompositionParameters {
+        subdivision_criterion: SubdivisionCriterion::MaxParticleCount(100),
+        ghost_particle_safety_factor: None,
+        enable_stitching: true,
+        particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+    });
+
+    let specs = AttributeSpecs {
+        density_attributes: true,
+        ..AttributeSpecs::default()
+    };
+
+    for parameters in [parameters, octree_parameters] {
+        let mesh = reconstruct_surface_with_attributes::<i64, f64>(
+            particles.as_slice(),
+            &parameters,
+            &specs,
+        )
+        .unwrap();
+        let num_vertices = mesh.mesh.vertices.len();
+        assert!(num_vertices > 0);
+
+        // All vertices are extracted at the iso-surface threshold
+        if let AttributeData::ScalarReal(mc_density) =
+            find_attribute(&mesh.point_attributes, "mc_density")
+        {
+            assert_eq!(mc_density.len(), num_vertices);
+            for &value in mc_density {
+                assert!(
+                    (value - parameters.iso_surface_threshold).abs() < 1e-6,
+                    "mc_density {} differs from the threshold",
+                    value
+                );
+            }
+        } else {
+            panic!("mc_density has wrong type");
+        }
+
+        // The SPH interpolation of the density map is close to the threshold (up to the error of the discretized kernel and the linear interpolation)
+        if let AttributeData::ScalarReal(sph_density) =
+            find_attribute(&mesh.point_attributes, "sph_density")
+        {
+            assert_eq!(sph_density.len(), num_vertices);
+            assert!(sph_density
+                .iter()
+                .all(|&value| (value - parameters.iso_surface_threshold).abs() < 0.1));
+        } else {
+            panic!("sph_density has wrong type");
+        }
+    }
+}
+
+#[test]
+fn test_reconstruct_with_attributes_invalid_length() {
+    let particle_radius = 0.025;
+    let particles = particles(particle_radius, 5);
+    let attributes = particle_attributes(particles.len() - 1);
+
+    let specs = AttributeSpecs {
+        particle_attributes: attributes.as_slice(),
+        ..AttributeSpecs::default()
+    };
+
+    assert!(reconstruct_surface_with_attributes::<i64, f64>(
+        particles.as_slice(),
+        &params(particle_radius),
+        &specs,
+    )
+    .is_err());
+}
+
+#[test]
+fn test_reconstruct_with_multi_component_attributes() {
+    let particle_radius = 0.025;
+    let particles = particles(particle_radius, 10);
+    let num_particles = particles.len();
+    let attributes = vec![
+        MeshAttribute::new_real_vector("velocity_2d", 2, [0.5, -1.5].repeat(num_particles)),
+        MeshAttribute::new_real_vector(
+            "stress",
+            6,
+            [1.0, 2.0, 3.0, 4.0, 5.0, 6.0].repeat(num_particles),
+        ),
+    ];
+
+    for spatial_decomposition in [
+        None,
+        Some(SpatialDecompositionParameters {
+            subdivision_criterion: SubdivisionCriterion::MaxParticleCount(100),
+            ghost_particle_safety_factor: None,
+            enable_stitching: true,
+            particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+        }),
+    ] {
+        let parameters = Parameters {
+            spatial_decomposition,
+            ..params(particle_radius)
+        };
+        for interpolation in [AttributeInterpolation::Sph, AttributeInterpolation::Nearest] {
+            let interpolation = [interpolation; 2];
+            let specs = AttributeSpecs {
+                particle_attributes: attributes.as_slice(),
+                particle_attribute_interpolation: &interpolation,
+                ..AttributeSpecs::default()
+            };
+            let mesh = reconstruct_surface_with_attributes::<i64, f64>(
+                particles.as_slice(),
+                &parameters,
+                &specs,
+            )
+            .unwrap();
+            let num_vertices = mesh.mesh.vertices.len();
+
+            // The components are interpolated separately and keep their order
+            for (name, expected) in [
+                ("velocity_2d", &[0.5, -1.5][..]),
+                ("stress", &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0][..]),
+            ] {
+                match find_attribute(&mesh.point_attributes, name) {
+                    AttributeData::VectorReal {
+                        num_components,
+                        values,
+                    } => {
+                        assert_eq!(*num_components, expected.len());
+                        assert_eq!(values.len(), num_vertices * expected.len());
+                        for value in values.chunks(*num_components) {
+                            for (v, e) in value.iter().zip(expected) {
+                                assert!((v - e).abs() < 1e-6, "{}: {:?}", name, value);
+                            }
+                        }
+                    }
+                    _ => panic!("{} has wrong type", name),
+                }
+            }
+        }
+    }
+
+    // The number of values has to be a multiple of the number of components
+    let attributes = vec![MeshAttribute::new(
+        "velocity_2d",
+        AttributeData::VectorReal {
+            num_components: 2,
+            values: vec![0.0; 2 * num_particles + 1],
+        },
+    )];
+    let specs = AttributeSpecs {
+        particle_attributes: attributes.as_slice(),
+        ..AttributeSpecs::default()
+    };
+    let err = reconstruct_surface_with_attributes::<i64, f64>(
+        particles.as_slice(),
+        &params(particle_radius),
+        &specs,
+    )
+    .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            splashsurf_lib::ReconstructionError::AttributeError(
+                AttributeError::LengthMismatch { .. }
+            )
+        ),
+        "unexpected error: {:?}",
+        err
+    );
+}
+
+#[test]
+fn test_reconstruct_with_integer_labels() {
+    let particle_radius = 0.025;
+    let particles = particles(particle_radius, 10);
+    // Phase labels of the particles stored as integers and as real values
+    let labels: Vec<u64> = particles
+        .iter()
+        .map(|p| if p.x < 0.2 { 1 } else { 2 })
+        .collect();
+    let attributes = vec![
+        MeshAttribute::new("phase", labels.clone()),
+        MeshAttribute::new_real_scalar(
+            "phase_real",
+            labels.iter().map(|&l| l as f64).collect::<Vec<_>>(),
+        ),
+        MeshAttribute::new_real_vector3(
+            "velocity",
+            vec![Vector3::new(1.0, 0.0, 0.0); particles.len()],
+        ),
+    ];
+    let interpolation: Vec<_> = attributes
+        .iter()
+        .map(|attribute| AttributeInterpolation::default_for(&attribute.data))
+        .collect();
+    assert_eq!(
+        interpolation,
+        vec![
+            AttributeInterpolation::Nearest,
+            AttributeInterpolation::Sph,
+            AttributeInterpolation::Sph
+        ]
+    );
+
+    let mut parameters = params(particle_radius);
+    parameters.spatial_decomposition = Some(SpatialDecompositionParameters {
+        subdivision_criterion: SubdivisionCriterion::MaxParticleCount(100),
+        ghost_particle_safety_factor: None,
+        enable_stitching: true,
+        particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+    });
+
+    let interpolation = [
+        AttributeInterpolation::Nearest,
+        AttributeInterpolation::Nearest,
+        AttributeInterpolation::Sph,
+    ];
+    let specs = AttributeSpecs {
+        particle_attributes: attributes.as_slice(),
+        particle_attribute_interpolation: &interpolation,
+        ..AttributeSpecs::default()
+    };
+    let mesh =
+        reconstruct_surface_with_attributes::<i64, f64>(particles.as_slice(), &parameters, &specs)
+            .unwrap();
+    let num_vertices = mesh.mesh.vertices.len();
+
+    // Integer labels stay integers and only take values of the particles
+    if let AttributeData::ScalarU64(phase) = find_attribute(&mesh.point_attributes, "phase") {
+        assert_eq!(phase.len(), num_vertices);
+        assert!(phase.iter().all(|&l| l == 1 || l == 2));
+        assert!(phase.contains(&1) && phase.contains(&2));
+    } else {
+        panic!("phase has wrong type");
+    }
+    if let AttributeData::ScalarReal(phase) = find_attribute(&mesh.point_attributes, "phase_real") {
+        assert_eq!(phase.len(), num_vertices);
+        assert!(phase.iter().all(|&l| l == 1.0 || l == 2.0));
+    } else {
+        panic!("phase_real has wrong type");
+    }
+    assert_eq!(
+        attribute_len(find_attribute(&mesh.point_attributes, "velocity")),
+        num_vertices
+    );
+
+    // Integer attributes cannot be interpolated using SPH and there has to be one method per attribute
+    for interpolation in [
+        &[AttributeInterpolation::Sph; 3][..],
+        &[AttributeInterpolation::Nearest][..],
+    ] {
+        let specs = AttributeSpecs {
+            particle_attributes: attributes.as_slice(),
+            particle_attribute_interpolation: interpolation,
+            ..AttributeSpecs::default()
+        };
+        let err = reconstruct_surface_with_attributes::<i64, f64>(
+            particles.as_slice(),
+            &parameters,
+            &specs,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            splashsurf_lib::ReconstructionError::AttributeError(
+                AttributeError::UnsupportedType(_) | AttributeError::InterpolationMismatch { .. }
+            )
+        ));
+    }
+}
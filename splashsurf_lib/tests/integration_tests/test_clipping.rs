@@ -0,0 +1,115 @@
+use super::{default_parameters, particle_block};
+use nalgebra::Vector3;
+use splashsurf_lib::{
+    reconstruct_surface, ClippingPlane, Parameters, ParticleDensityComputationStrategy,
+    SpatialDecompositionParameters, SubdivisionCriterion,
+};
+
+fn params(particle_radius: f64, clipping_planes: Vec<ClippingPlane<f64>>) -> Parameters<f64> {
+    Parameters {
+        clipping_planes,
+        ..default_parameters(particle_radius)
+    }
+}
+
+/// Plane through the center of the block of [`test_diagonal_cut`] that keeps the half with x + y smaller than the center
+fn diagonal_plane(particle_radius: f64) -> ClippingPlane<f64> {
+    ClippingPlane::new(
+        Vector3::repeat(19.0 * particle_radius),
+        Vector3::new(-1.0, -1.0, 0.0),
+    )
+}
+
+/// Reconstructs a particle block cut by a diagonal plane and checks that the cut is flat and at the right location
+fn test_diagonal_cut(parameters: Parameters<f64>) {
+    let particle_radius = parameters.particle_radius;
+    let n = 20;
+    let particles = particle_block(particle_radius, n);
+
+    let plane = parameters.clipping_planes[0].clone();
+
+    let reconstruction =
+        reconstruct_surface::<i64, f64>(particles.as_slice(), &parameters).unwrap();
+    let mesh = reconstruction.mesh();
+    assert!(!mesh.triangles.is_empty());
+
+    let eps = 1e-6 * particle_radius;
+    let mut num_cut_vertices = 0;
+    let mut cut_extents_z = (f64::MAX, f64::MIN);
+    for v in mesh.vertices.iter() {
+        let distance = plane.signed_distance(v);
+        // No part of the surface is on the clipped side of the plane
+        assert!(distance > -eps, "vertex {:?} is on the clipped side", v);
+        if distance.abs() < eps {
+            num_cut_vertices += 1;
+            cut_extents_z = (cut_extents_z.0.min(v.z), cut_extents_z.1.max(v.z));
+        }
+    }
+
+    // The cut is a flat face on the plane spanning (almost) the whole height of the block
+    assert!(num_cut_vertices > 100);
+    let block_height = (n - 1) as f64 * 2.0 * particle_radius;
+    assert!(cut_extents_z.1 - cut_extents_z.0 > 0.9 * block_height);
+
+    // The remaining part of the surface extends to the far corner of the block
+    let max_distance = mesh
+        .vertices
+        .iter()
+        .map(|v| plane.signed_distance(v))
+        .fold(f64::MIN, f64::max);
+    let corner_distance = plane.signed_distance(&Vector3::zeros());
+    assert!((max_distance - corner_distance).abs() < 2.0 * particle_radius);
+}
+
+#[test]
+fn test_clipping_plane_global() {
+    let particle_radius = 0.025;
+    let plane = diagonal_plane(particle_radius);
+    test_diagonal_cut(params(particle_radius, vec![plane]));
+}
+
+#[test]
+fn test_clipping_plane_octree() {
+    let particle_radius = 0.025;
+    let plane = diagonal_plane(particle_radius);
+    let mut parameters = params(particle_radius, vec![plane]);
+    parameters.spatial_decomposition = Some(SpatialDecompositionParameters {
+        subdivision_criterion: SubdivisionCriterion::MaxParticleCount(1000),
+        ghost_particle_safety_factor: None,
+        enable_stitching: true,
+        particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+    });
+    test_diagonal_cut(parameters);
+}
+
+#[test]
+fn test_clipping_plane_skips_particles() {
+    let particle_radius = 0.025;
+    let mut particles = particle_block(particle_radius, 10);
+    let unclipped =
+        reconstruct_surface::<i64, f64>(particles.as_slice(), &params(particle_radius, Vec::new()))
+            .unwrap();
+
+    // A second block far on the clipped side of the plane should not change the surface
+    let offset = Vector3::new(0.0, 0.0, 2.0);
+    particles.extend(
+        particle_block(particle_radius, 10)
+            .into_iter()
+            .map(|p| p + offset),
+    );
+    let plane = ClippingPlane::new(Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 0.0, -1.0));
+    let clipped = reconstruct_surface::<i64, f64>(
+        particles.as_slice(),
+        &params(particle_radius, vec![plane]),
+    )
+    .unwrap();
+
+    assert_eq!(
+        unclipped.mesh().vertices.len(),
+        clipped.mesh().vertices.len()
+    );
+    assert_eq!(
+        unclipped.mesh().triangles.len(),
+        clipped.mesh().triangles.len()
+    );
+}
@@ -0,0 +1,112 @@
+use nalgebra::Vector2;
+use splashsurf_lib::{reconstruct_contour_2d, Parameters2d};
+
+/// Generates particles on a regular lattice with the given spacing that are inside of a disk
+fn disk_particles(disk_radius: f64, particle_radius: f64) -> Vec<Vector2<f64>> {
+    let spacing = 2.0 * particle_radius;
+    let n = (disk_radius / spacing).ceil() as i32;
+    let mut particles = Vec::new();
+    for i in -n..=n {
+        for j in -n..=n {
+            let p = Vector2::new(i as f64, j as f64) * spacing;
+            if p.norm() <= disk_radius {
+                particles.push(p);
+            }
+        }
+    }
+    particles
+}
+
+fn default_params(particle_radius: f64) -> Parameters2d<f64> {
+    Parameters2d {
+        particle_radius,
+        rest_density: 1000.0,
+        compact_support_radius: 4.0 * particle_radius,
+        cell_size: 0.5 * particle_radius,
+        iso_surface_threshold: 0.6,
+        domain_aabb: None,
+    }
+}
+
+#[test]
+fn test_contour_2d_disk() {
+    let disk_radius = 1.0;
+    let particle_radius = 0.01;
+    let particles = disk_particles(disk_radius, particle_radius);
+
+    let reconstruction =
+        reconstruct_contour_2d::<i64, f64>(particles.as_slice(), &default_params(particle_radius))
+            .unwrap();
+    let contour = reconstruction.contour();
+
+    assert_eq!(reconstruction.particle_densities().len(), particles.len());
+    assert!(!contour.lines.is_empty());
+
+    // The contour of a disk should be a single closed curve, i.e. every vertex has two adjacent segments
+    let mut vertex_degree = vec![0; contour.vertices.len()];
+    for line in contour.lines.iter() {
+        assert_ne!(line[0], line[1]);
+        vertex_degree[line[0]] += 1;
+        vertex_degree[line[1]] += 1;
+    }
+    assert!(vertex_degree.iter().all(|&d| d == 2));
+    assert_eq!(contour.vertices.len(), contour.lines.len());
+
+    // All vertices should be close to the boundary of the disk
+    for v in contour.vertices.iter() {
+        assert!((v.norm() - disk_radius).abs() < 3.0 * particle_radius);
+    }
+
+    // The total length of the contour should be close to the circumference of the disk
+    let circumference = 2.0 * std::f64::consts::PI * disk_radius;
+    let length = contour.total_length();
+    println!(
+        "Contour length: {}, circumference: {}",
+        length, circumference
+    );
+    assert!((length - circumference).abs() / circumference < 0.05);
+}
+
+#[test]
+fn test_contour_2d_orientation() {
+    let particle_radius = 0.025;
+    let particles = disk_particles(0.5, particle_radius);
+
+    let contour: splashsurf_lib::mesh::LineMesh2d<f64> =
+        reconstruct_contour_2d::<i64, f64>(particles.as_slice(), &default_params(particle_radius))
+            .unwrap()
+            .into();
+
+    // Line segments are oriented such that the fluid is on their left, i.e. the signed area is positive
+    let signed_area = contour
+        .lines
+        .iter()
+        .map(|line| {
+            let a = contour.vertices[line[0]];
+            let b = contour.vertices[line[1]];
+            0.5 * (a.x * b.y - b.x * a.y)
+        })
+        .sum::<f64>();
+
+    let area = std::f64::consts::PI * 0.5 * 0.5;
+    assert!(signed_area > 0.0);
+    assert!((signed_area - area).abs() / area < 0.1);
+}
+
+#[test]
+fn test_contour_2d_unsigned_index_types() {
+    let particle_radius = 0.01;
+    let particles = disk_particles(0.25, particle_radius);
+    let parameters = default_params(particle_radius);
+
+    let expected = reconstruct_contour_2d::<i64, f64>(particles.as_slice(), &parameters).unwrap();
+    let contour_u32 =
+        reconstruct_contour_2d::<u32, f64>(particles.as_slice(), &parameters).unwrap();
+    let contour_usize =
+        reconstruct_contour_2d::<usize, f64>(particles.as_slice(), &parameters).unwrap();
+
+    for reconstruction in [contour_u32.contour(), contour_usize.contour()] {
+        assert_eq!(reconstruction.vertices, expected.contour().vertices);
+        assert_eq!(reconstruction.lines, expected.contour().lines);
+    }
+}
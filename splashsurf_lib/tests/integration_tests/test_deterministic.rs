@@ -0,0 +1,197 @@
+use super::default_parameters;
+use nalgebra::Vector3;
+use splashsurf_lib::mesh::TriMesh3d;
+use splashsurf_lib::{
+    reconstruct_surface, reconstruct_surface_from_density_map, reconstruct_surface_inplace,
+    MarchingCubesVariant, Parameters, ParticleDensityComputationStrategy,
+    SpatialDecompositionParameters, SubdivisionCriterion, SurfaceReconstruction,
+};
+
+/// Generates a blob of particles on a jittered lattice using a simple linear congruential generator
+fn jittered_blob(particle_radius: f64, n: usize) -> Vec<Vector3<f64>> {
+    let mut state: u64 = 42;
+    let mut next_random = move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+    };
+
+    let spacing = 2.0 * particle_radius;
+    let center = Vector3::repeat(0.5 * n as f64);
+    let mut particles = Vec::new();
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                let lattice_point = Vector3::new(i as f64, j as f64, k as f64);
+                if (lattice_point - center).norm() > 0.5 * n as f64 {
+                    continue;
+                }
+                let jitter = Vector3::new(next_random(), next_random(), next_random()) * 0.3;
+                particles.push((lattice_point + jitter) * spacing);
+            }
+        }
+    }
+    particles
+}
+
+fn params(
+    particle_radius: f64,
+    subdivision_criterion: Option<SubdivisionCriterion>,
+    enable_stitching: bool,
+) -> Parameters<f64> {
+    Parameters {
+        cube_size: 0.5 * particle_radius,
+        enable_multi_threading: true,
+        deterministic: true,
+        spatial_decomposition: subdivision_criterion.map(|subdivision_criterion| {
+            SpatialDecompositionParameters {
+                subdivision_criterion,
+                ghost_particle_safety_factor: None,
+                enable_stitching,
+                particle_density_computation:
+                    ParticleDensityComputationStrategy::SynchronizeSubdomains,
+            }
+        }),
+        ..default_parameters(particle_radius)
+    }
+}
+
+fn all_params(particle_radius: f64) -> Vec<Parameters<f64>> {
+    vec![
+        params(particle_radius, None, false),
+        params(
+            particle_radius,
+            Some(SubdivisionCriterion::MaxParticleCount(500)),
+            false,
+        ),
+        params(
+            particle_radius,
+            Some(SubdivisionCriterion::MaxParticleCount(500)),
+            true,
+        ),
+        params(
+            particle_radius,
+            Some(SubdivisionCriterion::MaxParticleCountAuto),
+            false,
+        ),
+    ]
+}
+
+/// Asserts that the meshes have the same triangles and bitwise identical vertices
+fn assert_bitwise_eq(a: &TriMesh3d<f64>, b: &TriMesh3d<f64>) {
+    assert_eq!(a.triangles, b.triangles);
+    assert_eq!(a.vertices.len(), b.vertices.len());
+    for (va, vb) in a.vertices.iter().zip(b.vertices.iter()) {
+        for (ca, cb) in va.iter().zip(vb.iter()) {
+            assert_eq!(ca.to_bits(), cb.to_bits());
+        }
+    }
+}
+
+#[test]
+fn test_deterministic_repeated() {
+    let particles = jittered_blob(0.025, 16);
+
+    for parameters in all_params(0.025) {
+        let first = reconstruct_surface::<i64, _>(&particles, &parameters).unwrap();
+        let second = reconstruct_surface::<i64, _>(&particles, &parameters).unwrap();
+
+        assert!(!first.mesh().triangles.is_empty());
+        assert_bitwise_eq(first.mesh(), second.mesh());
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_deterministic_thread_count() {
+    use splashsurf_lib::ThreadPoolConfig;
+
+    let particles = jittered_blob(0.025, 16);
+
+    let single_threaded = ThreadPoolConfig::with_num_threads(1).build_local().unwrap();
+    let multi_threaded = ThreadPoolConfig::with_num_threads(4).build_local().unwrap();
+
+    for parameters in all_params(0.025) {
+        let first = single_threaded
+            .install(|| reconstruct_surface::<i64, _>(&particles, &parameters))
+            .unwrap();
+        let second = multi_threaded
+            .install(|| reconstruct_surface::<i64, _>(&particles, &parameters))
+            .unwrap();
+
+        assert!(!first.mesh().triangles.is_empty());
+        assert_bitwise_eq(first.mesh(), second.mesh());
+    }
+}
+
+/// Returns the vertex coordinates of all triangles of the mesh in a canonical order
+fn sorted_triangle_coordinates(mesh: &TriMesh3d<f64>) -> Vec<[[u64; 3]; 3]> {
+    let mut triangles = mesh
+        .triangles
+        .iter()
+        .map(|triangle| {
+            triangle.map(|v| {
+                let vertex = mesh.vertices[v];
+                [vertex.x.to_bits(), vertex.y.to_bits(), vertex.z.to_bits()]
+            })
+        })
+        .collect::<Vec<_>>();
+    triangles.sort_unstable();
+    triangles
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_deterministic_parallel_triangulation() {
+    let particles = jittered_blob(0.025, 16);
+
+    for variant in [MarchingCubesVariant::Classic, MarchingCubesVariant::Mc33] {
+        // The global triangulation is only performed in parallel if multi-threading is enabled and the reconstruction is not deterministic
+        let mut parameters = params(0.025, None, false);
+        parameters.triangulation_scheme = variant;
+        parameters.deterministic = false;
+        let mut parallel = SurfaceReconstruction::<i64, f64>::default();
+        parallel.set_keep_density_map(true);
+        reconstruct_surface_inplace(&particles, &parameters, &mut parallel).unwrap();
+
+        // Sequential triangulation of the same density map
+        parameters.enable_multi_threading = false;
+        let mut sequential = SurfaceReconstruction::<i64, f64>::default();
+        let snapshot = parallel.density_map_snapshot().unwrap();
+        reconstruct_surface_from_density_map(&particles, &parameters, snapshot, &mut sequential)
+            .unwrap();
+
+        assert!(!sequential.mesh().triangles.is_empty());
+        assert_eq!(
+            parallel.mesh().vertices.len(),
+            sequential.mesh().vertices.len()
+        );
+        assert_eq!(
+            sorted_triangle_coordinates(parallel.mesh()),
+            sorted_triangle_coordinates(sequential.mesh())
+        );
+    }
+}
+
+#[test]
+fn test_deterministic_density_map_snapshot() {
+    let particles = jittered_blob(0.025, 16);
+
+    for variant in [MarchingCubesVariant::Classic, MarchingCubesVariant::Mc33] {
+        let mut parameters = params(0.025, None, false);
+        parameters.triangulation_scheme = variant;
+
+        let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+        reconstruction.set_keep_density_map(true);
+        reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+        let snapshot = reconstruction.density_map_snapshot().unwrap();
+
+        // The triangulation of a snapshot results in the same mesh as the (multi-threaded) reconstruction
+        let mut from_snapshot = SurfaceReconstruction::<i64, f64>::default();
+        reconstruct_surface_from_density_map(&particles, &parameters, snapshot, &mut from_snapshot)
+            .unwrap();
+        assert!(!reconstruction.mesh().triangles.is_empty());
+        assert_bitwise_eq(reconstruction.mesh(), from_snapshot.mesh());
+    }
+}
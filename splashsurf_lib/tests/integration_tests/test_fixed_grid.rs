@@ -0,0 +1,153 @@
+use super::{default_parameters, sphere_particles};
+use nalgebra::Vector3;
+use splashsurf_lib::{
+    reconstruct_surface_inplace, AxisAlignedBoundingBox3d, GridEscapePolicy, Parameters,
+    ReconstructionError, SurfaceReconstruction,
+};
+
+const PARTICLE_RADIUS: f64 = 0.025;
+
+fn params() -> Parameters<f64> {
+    Parameters {
+        enable_multi_threading: true,
+        ..default_parameters(PARTICLE_RADIUS)
+    }
+}
+
+/// Returns the bits of the grid origin and the number of points per dimension
+fn grid_bits(reconstruction: &SurfaceReconstruction<i64, f64>) -> ([u64; 3], [i64; 3]) {
+    let grid = reconstruction.grid();
+    (
+        grid.aabb().min().map(f64::to_bits).into(),
+        *grid.points_per_dim(),
+    )
+}
+
+#[test]
+fn test_fixed_grid_reused() {
+    let parameters = params();
+    // The sphere moves by less than the particle radius in every frame
+    let frames = [
+        sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::zeros()),
+        sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::new(0.01, -0.005, 0.0)),
+        sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::new(-0.01, 0.0, 0.015)),
+    ];
+
+    let mut reconstruction = SurfaceReconstruction::default();
+    reconstruction.set_fixed_grid(Some(GridEscapePolicy::Error));
+    let mut moving_reconstruction = SurfaceReconstruction::default();
+
+    let mut first_grid = None;
+    for (frame, particles) in frames.iter().enumerate() {
+        reconstruct_surface_inplace(particles, &parameters, &mut reconstruction).unwrap();
+        assert_eq!(reconstruction.stats().grid_reused, frame > 0);
+        assert_eq!(reconstruction.stats().warnings.particles_outside_domain, 0);
+        assert!(!reconstruction.mesh().triangles.is_empty());
+
+        let first_grid = first_grid.get_or_insert_with(|| grid_bits(&reconstruction));
+        assert_eq!(&grid_bits(&reconstruction), first_grid);
+
+        // Without a fixed grid, the grid moves with the particles
+        reconstruct_surface_inplace(particles, &parameters, &mut moving_reconstruction).unwrap();
+        assert!(!moving_reconstruction.stats().grid_reused);
+        assert_eq!(grid_bits(&moving_reconstruction) == *first_grid, frame == 0);
+    }
+
+    // Different parameters result in a new grid
+    let coarse_parameters = Parameters {
+        cube_size: PARTICLE_RADIUS,
+        ..params()
+    };
+    reconstruct_surface_inplace(&frames[0], &coarse_parameters, &mut reconstruction).unwrap();
+    assert!(!reconstruction.stats().grid_reused);
+    reconstruct_surface_inplace(&frames[1], &coarse_parameters, &mut reconstruction).unwrap();
+    assert!(reconstruction.stats().grid_reused);
+}
+
+#[test]
+fn test_fixed_grid_escape_error() {
+    let parameters = params();
+    let particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::zeros());
+    let escaped_particles =
+        sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::new(0.1, 0.0, 0.0));
+
+    let mut reconstruction = SurfaceReconstruction::default();
+    reconstruction.set_fixed_grid(Some(GridEscapePolicy::Error));
+    reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+    let first_grid = grid_bits(&reconstruction);
+
+    let result = reconstruct_surface_inplace(&escaped_particles, &parameters, &mut reconstruction);
+    match result {
+        Err(ReconstructionError::ParticlesOutsideGrid { count, total }) => {
+            assert!(count > 0);
+            assert_eq!(total, escaped_particles.len());
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    // The grid stays fixed for subsequent frames
+    reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+    assert!(reconstruction.stats().grid_reused);
+    assert_eq!(grid_bits(&reconstruction), first_grid);
+}
+
+#[test]
+fn test_fixed_grid_escape_regrid() {
+    let parameters = params();
+    let particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::zeros());
+    let escaped_particles =
+        sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::new(0.1, 0.0, 0.0));
+
+    let mut reconstruction = SurfaceReconstruction::default();
+    reconstruction.set_fixed_grid(Some(GridEscapePolicy::Regrid));
+    reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+    let first_grid = grid_bits(&reconstruction);
+
+    // A new grid is computed from the escaped particles and reused by the next frame
+    reconstruct_surface_inplace(&escaped_particles, &parameters, &mut reconstruction).unwrap();
+    assert!(!reconstruction.stats().grid_reused);
+    assert_eq!(reconstruction.stats().warnings.particles_outside_domain, 0);
+    let new_grid = grid_bits(&reconstruction);
+    assert_ne!(new_grid, first_grid);
+
+    reconstruct_surface_inplace(&escaped_particles, &parameters, &mut reconstruction).unwrap();
+    assert!(reconstruction.stats().grid_reused);
+    assert_eq!(grid_bits(&reconstruction), new_grid);
+
+    // Disabling the fixed grid computes a new grid for every frame
+    reconstruction.set_fixed_grid(None);
+    reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+    assert!(!reconstruction.stats().grid_reused);
+    assert_eq!(grid_bits(&reconstruction), first_grid);
+}
+
+#[test]
+fn test_fixed_grid_domain() {
+    let parameters = Parameters {
+        domain_aabb: Some(AxisAlignedBoundingBox3d::new(
+            Vector3::repeat(-0.5),
+            Vector3::repeat(0.5),
+        )),
+        ..params()
+    };
+    let particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::zeros());
+
+    let mut reconstruction = SurfaceReconstruction::default();
+    reconstruction.set_fixed_grid(Some(GridEscapePolicy::Error));
+    reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+    assert!(!reconstruction.stats().grid_reused);
+    let first_grid = grid_bits(&reconstruction);
+
+    let moved_particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::new(0.1, 0.1, 0.0));
+    reconstruct_surface_inplace(&moved_particles, &parameters, &mut reconstruction).unwrap();
+    assert!(reconstruction.stats().grid_reused);
+    assert_eq!(grid_bits(&reconstruction), first_grid);
+
+    // Particles leaving the user provided domain trigger the escape policy instead of being skipped
+    let escaped_particles =
+        sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::new(0.35, 0.0, 0.0));
+    assert!(matches!(
+        reconstruct_surface_inplace(&escaped_particles, &parameters, &mut reconstruction),
+        Err(ReconstructionError::ParticlesOutsideGrid { .. })
+    ));
+}
@@ -2,8 +2,9 @@ use nalgebra::Vector3;
 use splashsurf_lib::io::vtk_format::{particles_from_vtk, write_vtk};
 use splashsurf_lib::marching_cubes::check_mesh_consistency;
 use splashsurf_lib::{
-    reconstruct_surface, AxisAlignedBoundingBox3d, Parameters, ParticleDensityComputationStrategy,
-    Real, SpatialDecompositionParameters, SubdivisionCriterion,
+    reconstruct_surface, AxisAlignedBoundingBox3d, MarchingCubesVariant, Parameters,
+    ParticleDensityComputationStrategy, Real, SpatialDecompositionParameters, SplashOutput,
+    SubdivisionCriterion,
 };
 use std::path::Path;
 
@@ -33,8 +34,16 @@ fn params_with_aabb<R: Real>(
         compact_support_radius,
         cube_size,
         iso_surface_threshold,
+        triangulation_scheme: MarchingCubesVariant::Classic,
+        vertex_snapping: None,
+        splash_detection: None,
+        splash_particle_output: SplashOutput::Ignore,
         domain_aabb,
+        clipping_planes: Vec::new(),
         enable_multi_threading: false,
+        deterministic: false,
+        progress_interval: None,
+        temporal_density_smoothing: None,
         spatial_decomposition: None,
     };
 
@@ -0,0 +1,47 @@
+use super::{default_parameters, rest_volume_spacing, sphere_particles};
+use nalgebra::Vector3;
+use splashsurf_lib::mesh::distance::symmetric_distance;
+use splashsurf_lib::mesh::TriMesh3d;
+use splashsurf_lib::{reconstruct_surface, Parameters};
+
+const PARTICLE_RADIUS: f64 = 0.025;
+
+fn reconstruct_sphere(particles: &[Vector3<f64>], cube_size: f64) -> TriMesh3d<f64> {
+    let parameters = Parameters {
+        cube_size,
+        enable_multi_threading: true,
+        ..default_parameters(PARTICLE_RADIUS)
+    };
+    reconstruct_surface::<i64, f64>(particles, &parameters)
+        .unwrap()
+        .mesh()
+        .clone()
+}
+
+#[test]
+fn test_reconstruction_distance_to_reference() {
+    let particles = sphere_particles(rest_volume_spacing(PARTICLE_RADIUS), 0.2, Vector3::zeros());
+    let fine = reconstruct_sphere(&particles, 0.5 * PARTICLE_RADIUS);
+    let coarse = reconstruct_sphere(&particles, 0.75 * PARTICLE_RADIUS);
+
+    // Changing the cube size only changes the surface by a fraction of the cube size
+    let distance = symmetric_distance(&fine, &coarse, 2).unwrap();
+    assert!(
+        distance.max < 0.75 * PARTICLE_RADIUS,
+        "distance between reconstructions: {:?}",
+        distance
+    );
+    assert!(distance.mean <= distance.rms && distance.rms <= distance.max);
+
+    // A sphere with the volume of all particles as reference mesh
+    let particle_volume =
+        particles.len() as f64 * 4.0 / 3.0 * std::f64::consts::PI * PARTICLE_RADIUS.powi(3);
+    let sphere_radius = (3.0 * particle_volume / (4.0 * std::f64::consts::PI)).cbrt();
+    let reference = TriMesh3d::icosphere(&Vector3::zeros(), sphere_radius, 3);
+    let distance = symmetric_distance(&fine, &reference, 1).unwrap();
+    assert!(
+        distance.mean < 0.5 * PARTICLE_RADIUS && distance.max < 2.0 * PARTICLE_RADIUS,
+        "distance to reference sphere: {:?}",
+        distance
+    );
+}
@@ -0,0 +1,40 @@
+use super::{default_parameters, sphere_particles};
+use nalgebra::Vector3;
+use splashsurf_lib::mesh::smoothing::{laplacian_smoothing, taubin_smoothing};
+use splashsurf_lib::mesh::TriMesh3d;
+use splashsurf_lib::{reconstruct_surface, Parameters};
+
+const PARTICLE_RADIUS: f64 = 0.025;
+
+#[test]
+fn test_taubin_smoothing_preserves_volume() {
+    let parameters = Parameters {
+        cube_size: 0.5 * PARTICLE_RADIUS,
+        enable_multi_threading: true,
+        ..default_parameters(PARTICLE_RADIUS)
+    };
+    let reconstruction = reconstruct_surface::<i64, f64>(
+        &sphere_particles(2.0 * PARTICLE_RADIUS, 0.3, Vector3::zeros()),
+        &parameters,
+    )
+    .unwrap();
+    let mesh = reconstruction.mesh();
+    let volume = mesh.volume();
+    assert!(volume > 0.0);
+
+    let relative_volume_change =
+        |smoothed: &TriMesh3d<f64>| (smoothed.volume() - volume).abs() / volume;
+
+    let mut laplacian = mesh.clone();
+    laplacian_smoothing(&mut laplacian, 10, 0.5, false);
+    let mut taubin = mesh.clone();
+    taubin_smoothing(&mut taubin, 10, 0.5, -0.53, None);
+
+    // The vertices are moved
+    assert_ne!(laplacian.vertices, mesh.vertices);
+    assert_ne!(taubin.vertices, mesh.vertices);
+
+    // Laplacian smoothing shrinks the sphere, Taubin smoothing approximately preserves its volume
+    assert!(laplacian.volume() < volume);
+    assert!(relative_volume_change(&taubin) < 0.2 * relative_volume_change(&laplacian));
+}
@@ -0,0 +1,202 @@
+use super::{default_parameters, sphere_particles};
+use nalgebra::Vector3;
+use splashsurf_lib::generic_tree::TreeNode;
+use splashsurf_lib::{
+    reconstruct_surface, MarchingCubesVariant, ParameterError, Parameters,
+    ParticleDensityComputationStrategy, ReconstructionError, SpatialDecompositionParameters,
+    SubdivisionCriterion,
+};
+
+const PARTICLE_RADIUS: f64 = 0.025;
+
+fn params(enable_stitching: Option<bool>) -> Parameters<f64> {
+    Parameters {
+        enable_multi_threading: true,
+        spatial_decomposition: enable_stitching.map(|enable_stitching| {
+            SpatialDecompositionParameters {
+                subdivision_criterion: SubdivisionCriterion::MaxParticleCount(500),
+                ghost_particle_safety_factor: None,
+                enable_stitching,
+                particle_density_computation: ParticleDensityComputationStrategy::Global,
+            }
+        }),
+        ..default_parameters(PARTICLE_RADIUS)
+    }
+}
+
+#[test]
+fn test_mesh_validity_stitching() {
+    // Two separate droplets and a larger sphere
+    let mut particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.3, Vector3::zeros());
+    particles.extend(sphere_particles(
+        2.0 * PARTICLE_RADIUS,
+        0.1,
+        Vector3::new(0.6, 0.0, 0.0),
+    ));
+    particles.extend(sphere_particles(
+        2.0 * PARTICLE_RADIUS,
+        0.12,
+        Vector3::new(0.0, -0.3, 0.5),
+    ));
+
+    for enable_stitching in [None, Some(true)] {
+        let reconstruction =
+            reconstruct_surface::<i64, f64>(&particles, &params(enable_stitching)).unwrap();
+        let mesh = reconstruction.mesh();
+        assert!(!mesh.triangles.is_empty());
+
+        let report = mesh.validity_report();
+        assert!(
+            report.is_valid(),
+            "invalid mesh (stitching: {:?}): {}",
+            enable_stitching,
+            report
+        );
+        assert_eq!(mesh.connected_components().1, 3);
+    }
+
+    // Without stitching, the surface patches of the octree leaves are not connected
+    let reconstruction = reconstruct_surface::<i64, f64>(&particles, &params(Some(false))).unwrap();
+    assert!(reconstruction.octree().unwrap().root().children().len() > 1);
+    let report = reconstruction.mesh().validity_report();
+    assert!(report.boundary_edges.count > 0);
+    assert_eq!(report.out_of_range_indices.count, 0);
+}
+
+#[test]
+fn test_mesh_validity_mc33() {
+    // A sphere and a thin sheet of randomly displaced particles result in many ambiguous cells
+    let mut particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::zeros());
+    let spacing = 2.0 * PARTICLE_RADIUS;
+    for i in -8..=8 {
+        for j in -8..=8 {
+            particles.push(
+                Vector3::new(i as f64, j as f64, 0.0) * spacing + Vector3::new(0.0, 0.0, 0.5),
+            );
+        }
+    }
+    let mut state = 0x853c49e6748fea9b_u64;
+    for particle in particles.iter_mut() {
+        for x in particle.iter_mut() {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            *x += ((state >> 11) as f64 / (1u64 << 53) as f64 - 0.5) * PARTICLE_RADIUS;
+        }
+    }
+
+    for enable_stitching in [None, Some(true)] {
+        let mut parameters = params(enable_stitching);
+        parameters.triangulation_scheme = MarchingCubesVariant::Mc33;
+        let reconstruction = reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+        let mesh = reconstruction.mesh();
+        assert!(!mesh.triangles.is_empty());
+
+        let report = mesh.validity_report();
+        assert!(
+            report.is_valid(),
+            "invalid mesh (stitching: {:?}): {}",
+            enable_stitching,
+            report
+        );
+    }
+}
+
+#[test]
+fn test_mesh_validity_surface_nets() {
+    let mut particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.3, Vector3::zeros());
+    particles.extend(sphere_particles(
+        2.0 * PARTICLE_RADIUS,
+        0.1,
+        Vector3::new(0.6, 0.0, 0.0),
+    ));
+
+    let mut parameters = params(None);
+    parameters.triangulation_scheme = MarchingCubesVariant::SurfaceNets;
+    let reconstruction = reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+    let mesh = reconstruction.mesh();
+    assert!(!mesh.triangles.is_empty());
+
+    let report = mesh.validity_report();
+    assert!(report.is_valid(), "invalid mesh: {}", report);
+    assert_eq!(mesh.connected_components().1, 2);
+
+    // Without stitching the octree leaves are triangulated independently
+    let mut parameters = params(Some(false));
+    parameters.triangulation_scheme = MarchingCubesVariant::SurfaceNets;
+    let reconstruction = reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+    assert!(!reconstruction.mesh().triangles.is_empty());
+    assert_eq!(
+        reconstruction
+            .mesh()
+            .validity_report()
+            .out_of_range_indices
+            .count,
+        0
+    );
+
+    // Stitching of the subdomains is not supported
+    let mut parameters = params(Some(true));
+    parameters.triangulation_scheme = MarchingCubesVariant::SurfaceNets;
+    assert!(matches!(
+        reconstruct_surface::<i64, f64>(&particles, &parameters),
+        Err(ReconstructionError::InvalidParameters(
+            ParameterError::IncompatibleOptions(..)
+        ))
+    ));
+}
+
+#[test]
+fn test_mesh_validity_vertex_snapping() {
+    let mut particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.3, Vector3::zeros());
+    particles.extend(sphere_particles(
+        2.0 * PARTICLE_RADIUS,
+        0.1,
+        Vector3::new(0.6, 0.0, 0.0),
+    ));
+    particles.extend(sphere_particles(
+        2.0 * PARTICLE_RADIUS,
+        0.12,
+        Vector3::new(0.0, -0.3, 0.5),
+    ));
+
+    for enable_stitching in [None, Some(true)] {
+        let reconstruction =
+            reconstruct_surface::<i64, f64>(&particles, &params(enable_stitching)).unwrap();
+        let unsnapped = reconstruction.mesh();
+
+        for variant in [MarchingCubesVariant::Classic, MarchingCubesVariant::Mc33] {
+            let mut parameters = params(enable_stitching);
+            parameters.triangulation_scheme = variant;
+            parameters.vertex_snapping = Some(0.2);
+            let reconstruction = reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+            let mesh = reconstruction.mesh();
+            assert!(!mesh.triangles.is_empty());
+
+            let report = mesh.validity_report();
+            assert!(
+                report.is_valid(),
+                "invalid mesh (stitching: {:?}, variant: {:?}): {}",
+                enable_stitching,
+                variant,
+                report
+            );
+            assert_eq!(mesh.connected_components().1, 3);
+            assert!(mesh.vertices.len() < unsnapped.vertices.len());
+        }
+    }
+
+    // The snapping distance has to be smaller than half of the cube size
+    let mut parameters = params(None);
+    parameters.vertex_snapping = Some(0.5);
+    assert_eq!(
+        parameters.validate().unwrap_err(),
+        ParameterError::InvalidRange("vertex snapping distance", "(0, 0.5)")
+    );
+    assert!(matches!(
+        reconstruct_surface::<i64, f64>(&particles, &parameters),
+        Err(ReconstructionError::InvalidParameters(
+            ParameterError::InvalidRange(..)
+        ))
+    ));
+}
@@ -0,0 +1,44 @@
+use super::{default_parameters, rest_volume_spacing, sphere_particles};
+use nalgebra::Vector3;
+use splashsurf_lib::{reconstruct_surface, Parameters};
+
+const PARTICLE_RADIUS: f64 = 0.025;
+
+#[test]
+fn test_reconstructed_sphere_volume() {
+    let particles = sphere_particles(rest_volume_spacing(PARTICLE_RADIUS), 0.4, Vector3::zeros());
+    let parameters = Parameters {
+        cube_size: 0.5 * PARTICLE_RADIUS,
+        enable_multi_threading: true,
+        ..default_parameters(PARTICLE_RADIUS)
+    };
+    let reconstruction = reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+    let mesh = reconstruction.mesh();
+
+    // The volume of all particles and the area of a sphere with this volume
+    let particle_volume =
+        particles.len() as f64 * 4.0 / 3.0 * std::f64::consts::PI * PARTICLE_RADIUS.powi(3);
+    let sphere_radius = (3.0 * particle_volume / (4.0 * std::f64::consts::PI)).cbrt();
+    let sphere_area = 4.0 * std::f64::consts::PI * sphere_radius.powi(2);
+
+    let volume = mesh.enclosed_volume().unwrap();
+    let area = mesh.area();
+    assert!(
+        (volume / particle_volume - 1.0).abs() < 0.05,
+        "reconstructed volume: {}, particle volume: {}",
+        volume,
+        particle_volume
+    );
+    assert!(
+        (area / sphere_area - 1.0).abs() < 0.05,
+        "reconstructed area: {}, sphere area: {}",
+        area,
+        sphere_area
+    );
+
+    #[cfg(feature = "parallel")]
+    {
+        assert!((mesh.par_enclosed_volume().unwrap() - volume).abs() < 1e-9 * volume);
+        assert!((mesh.par_area() - area).abs() < 1e-9 * area);
+    }
+}
@@ -0,0 +1,188 @@
+use nalgebra::Vector3;
+use splashsurf_lib::neighborhood_search::*;
+
+/// Generates a jittered block of particles with the given spacing followed by a few isolated particles
+fn jittered_particle_block(spacing: f64, n: usize) -> Vec<Vector3<f64>> {
+    let mut particles = Vec::with_capacity(n * n * n + 2);
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                let idx = particles.len() as f64;
+                let jitter = Vector3::new((idx * 1.3).sin(), (idx * 2.1).cos(), (idx * 0.7).sin());
+                let lattice = Vector3::new(i as f64, j as f64, k as f64);
+                particles.push((lattice + jitter * 0.2) * spacing);
+            }
+        }
+    }
+
+    particles.push(Vector3::new(-5.0, -5.0, -5.0) * spacing);
+    particles.push(Vector3::new(n as f64 + 5.0, 0.0, 0.0) * spacing);
+    particles
+}
+
+/// Returns the sorted indices of all particles within the given radius of the query point
+fn query_sorted(
+    provider: &dyn ParticleNeighborhoodProvider<f64>,
+    query_point: &Vector3<f64>,
+    radius: f64,
+) -> Vec<usize> {
+    let mut particles = Vec::new();
+    provider.visit_particles_within(query_point, radius, &mut |j| particles.push(j));
+    particles.sort_unstable();
+    particles
+}
+
+/// Returns the sorted indices of all particles within the given radius of the query point using a linear search
+fn query_naive(particles: &[Vector3<f64>], query_point: &Vector3<f64>, radius: f64) -> Vec<usize> {
+    particles
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| (*p - query_point).norm_squared() <= radius * radius)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Returns query points close to and far away from the particles
+fn query_points(particles: &[Vector3<f64>], spacing: f64) -> Vec<Vector3<f64>> {
+    let mut points: Vec<_> = particles
+        .iter()
+        .step_by(7)
+        .map(|p| p + Vector3::new(0.3, -0.2, 0.1) * spacing)
+        .collect();
+    points.push(Vector3::new(100.0, 100.0, 100.0));
+    points.push(Vector3::new(-100.0, 0.0, 0.0));
+    points
+}
+
+#[test]
+fn test_spatial_hashing_provider() {
+    let spacing = 0.1;
+    let particles = jittered_particle_block(spacing, 8);
+    let provider = SpatialHashingNeighborhoodProvider::new(&particles, 2.0 * spacing);
+    assert_eq!(provider.num_particles(), particles.len());
+
+    // Queries with radii smaller and larger than the cell size
+    for &radius in &[0.5 * spacing, 2.0 * spacing, 4.5 * spacing] {
+        for query_point in particles
+            .iter()
+            .chain(query_points(&particles, spacing).iter())
+        {
+            assert_eq!(
+                query_sorted(&provider, query_point, radius),
+                query_naive(&particles, query_point, radius),
+                "query at {:?} with radius {}",
+                query_point,
+                radius
+            );
+        }
+    }
+
+    // Particles exactly on the query radius are included
+    let pair = [Vector3::new(1.0, 1.0, 1.0), Vector3::new(1.25, 1.0, 1.0)];
+    let provider = SpatialHashingNeighborhoodProvider::new(&pair, 0.1);
+    assert_eq!(query_sorted(&provider, &pair[0], 0.25), vec![0, 1]);
+}
+
+#[test]
+fn test_isolated_particles() {
+    let spacing = 0.1;
+    let particles = jittered_particle_block(spacing, 5);
+    let provider = SpatialHashingNeighborhoodProvider::new(&particles, 2.0 * spacing);
+
+    let n = particles.len();
+    assert_eq!(
+        isolated_particles(&provider, &particles, 2.0 * spacing),
+        vec![n - 2, n - 1]
+    );
+    assert!(isolated_particles(&provider, &particles, 20.0 * spacing).is_empty());
+
+    let empty = SpatialHashingNeighborhoodProvider::new(&[], 1.0);
+    assert!(isolated_particles(&empty, &[], 1.0).is_empty());
+}
+
+#[cfg(feature = "rstar")]
+#[test]
+fn test_interpolation_with_rstar_provider() {
+    use splashsurf_lib::sph_interpolation::SphInterpolator;
+
+    let particle_radius: f64 = 0.025;
+    let spacing = 2.0 * particle_radius;
+    let compact_support_radius = 4.0 * particle_radius;
+    let particle_rest_mass = 1000.0 * (4.0 / 3.0) * std::f64::consts::PI * particle_radius.powi(3);
+
+    let particles = jittered_particle_block(spacing, 10);
+    let densities: Vec<_> = (0..particles.len())
+        .map(|i| 1000.0 + 50.0 * (i as f64 * 0.37).sin())
+        .collect();
+    let velocities: Vec<_> = particles.iter().map(|p| p.component_mul(p)).collect();
+    let points = query_points(&particles, spacing);
+
+    let builtin = SphInterpolator::new(
+        &particles,
+        &densities,
+        particle_rest_mass,
+        compact_support_radius,
+    );
+    let tree = build_particle_rtree(&particles);
+    assert_eq!(
+        isolated_particles(&tree, &particles, compact_support_radius),
+        isolated_particles(
+            &SpatialHashingNeighborhoodProvider::new(&particles, compact_support_radius),
+            &particles,
+            compact_support_radius
+        )
+    );
+    let rstar = SphInterpolator::with_neighborhood_provider(
+        &particles,
+        &densities,
+        particle_rest_mass,
+        compact_support_radius,
+        tree,
+    );
+
+    // Results only differ by the summation order of the neighbors (points without neighbors result in NaN values)
+    let eps = 1e-10;
+    let assert_close = |a: f64, b: f64| {
+        assert!(
+            (a - b).abs() <= eps * (1.0 + a.abs()) || (a.is_nan() && b.is_nan()),
+            "{} != {}",
+            a,
+            b
+        )
+    };
+
+    for (a, b) in builtin
+        .interpolate_normals(&points)
+        .iter()
+        .zip(rstar.interpolate_normals(&points).iter())
+    {
+        for i in 0..3 {
+            assert_close(a[i], b[i]);
+        }
+    }
+
+    for &correction in &[false, true] {
+        for (a, b) in builtin
+            .interpolate_scalar_quantity(&densities, &points, correction)
+            .iter()
+            .zip(rstar.interpolate_scalar_quantity(&densities, &points, correction))
+        {
+            assert_close(*a, b);
+        }
+    }
+
+    for (a, b) in builtin
+        .interpolate_vector_quantity(&velocities, &points, false)
+        .iter()
+        .zip(rstar.interpolate_vector_quantity(&velocities, &points, false))
+    {
+        for i in 0..3 {
+            assert_close(a[i], b[i]);
+        }
+    }
+
+    assert_eq!(
+        builtin.nearest_particles(&points),
+        rstar.nearest_particles(&points)
+    );
+}
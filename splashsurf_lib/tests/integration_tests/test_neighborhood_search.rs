@@ -121,7 +121,7 @@ fn test_neighborhood_search_spatial_hashing_simple() {
     }
 }
 
-#[cfg(feature = "io")]
+#[cfg(all(feature = "io", feature = "parallel"))]
 mod tests_from_files {
     use super::*;
     use splashsurf_lib::io;
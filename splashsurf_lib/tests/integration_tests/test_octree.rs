@@ -1,8 +1,14 @@
-use splashsurf_lib::generic_tree::VisitableTree;
+// The helpers for the comparison of sequential and parallel octrees are only used with the `parallel` feature
+#![cfg_attr(not(feature = "parallel"), allow(dead_code))]
+
+use splashsurf_lib::generic_tree::{TreeNode, VisitableTree};
 use splashsurf_lib::io;
 use splashsurf_lib::nalgebra::Vector3;
 use splashsurf_lib::octree::Octree;
-use splashsurf_lib::{grid_for_reconstruction, Index, Real, SubdivisionCriterion, UniformGrid};
+use splashsurf_lib::{
+    grid_for_reconstruction, AxisAlignedBoundingBox3d, Index, Real, SubdivisionCriterion,
+    UniformGrid,
+};
 use std::path::Path;
 
 /*
@@ -149,6 +155,73 @@ fn build_octree_from_vtk() {
     //octree_to_file(&octree, &grid, "U:\\double_dam_break_frame_26_4732_particles_octree.vtk");
 }
 
+/// Checks the point location and AABB queries of the octree against a search over all leaves
+#[test]
+fn octree_point_and_aabb_queries() {
+    let particles: Vec<_> = (0..20 * 20 * 20)
+        .map(|i| Vector3::new((i % 20) as f64, ((i / 20) % 20) as f64, (i / 400) as f64) * 0.05)
+        .collect();
+
+    let grid =
+        grid_for_reconstruction::<i64, _>(particles.as_slice(), 0.025, 0.1, 0.05, None, true)
+            .unwrap();
+
+    let mut octree = Octree::new(&grid, particles.len());
+    octree.subdivide_recursively_margin(
+        &grid,
+        particles.as_slice(),
+        SubdivisionCriterion::MaxParticleCount(60),
+        0.0,
+        false,
+    );
+    let leaves: Vec<_> = octree
+        .root()
+        .dfs_iter()
+        .filter(|node| node.children().is_empty())
+        .collect();
+    assert!(leaves.len() > 8);
+
+    // Every particle is located in the unique leaf containing it
+    for particle in particles.iter() {
+        let leaf = octree.leaf_containing_point(particle).unwrap();
+        assert!(leaf.children().is_empty());
+        assert!(leaf.aabb().contains_point(particle));
+        assert_eq!(
+            leaves
+                .iter()
+                .filter(|leaf| leaf.aabb().contains_point(particle))
+                .count(),
+            1
+        );
+    }
+    assert!(octree
+        .leaf_containing_point(&Vector3::new(-10.0, 0.0, 0.0))
+        .is_none());
+
+    // The AABB query returns the same leaves as a search over all leaves
+    let query =
+        AxisAlignedBoundingBox3d::new(Vector3::new(0.1, 0.2, 0.3), Vector3::new(0.4, 0.5, 0.6));
+    let expected: Vec<_> = leaves
+        .iter()
+        .filter(|leaf| leaf.aabb().intersects_aabb(&query))
+        .map(|leaf| leaf.id())
+        .collect();
+    let found: Vec<_> = octree
+        .leaves_intersecting_aabb(&query)
+        .into_iter()
+        .map(|leaf| leaf.id())
+        .collect();
+    assert!(!found.is_empty());
+    assert!(found.len() < leaves.len());
+    assert_eq!(found, expected);
+
+    // Querying the root AABB returns all leaves
+    assert_eq!(
+        octree.leaves_intersecting_aabb(octree.root().aabb()).len(),
+        leaves.len()
+    );
+}
+
 struct TestParameters<R: Real> {
     particle_radius: R,
     compact_support_radius: R,
@@ -297,6 +370,7 @@ fn assert_tree_equivalence<I: Index, R: Real>(left_tree: &Octree<I, R>, right_tr
     }
 }
 
+#[cfg(feature = "parallel")]
 fn build_octree_par_consistency<I: Index, R: Real, P: AsRef<Path>>(
     file: P,
     parameters: TestParameters<R>,
@@ -345,6 +419,7 @@ fn build_octree_par_consistency<I: Index, R: Real, P: AsRef<Path>>(
 }
 
 #[test]
+#[cfg(feature = "parallel")]
 fn build_octree_cube() {
     build_octree_par_consistency::<i64, f64, _>(
         "../data/cube_2366_particles.vtk",
@@ -355,6 +430,7 @@ fn build_octree_cube() {
 }
 
 #[test]
+#[cfg(feature = "parallel")]
 fn build_octree_double_dam_break() {
     build_octree_par_consistency::<i64, f64, _>(
         "../data/double_dam_break_frame_26_4732_particles.vtk",
@@ -365,6 +441,7 @@ fn build_octree_double_dam_break() {
 }
 
 #[test]
+#[cfg(feature = "parallel")]
 #[cfg_attr(debug_assertions, ignore)]
 fn build_octree_dam_break() {
     build_octree_par_consistency::<i64, f64, _>(
@@ -376,6 +453,7 @@ fn build_octree_dam_break() {
 }
 
 #[test]
+#[cfg(feature = "parallel")]
 fn build_octree_bunny() {
     build_octree_par_consistency::<i64, f64, _>(
         "../data/bunny_frame_14_7705_particles.vtk",
@@ -386,6 +464,7 @@ fn build_octree_bunny() {
 }
 
 #[test]
+#[cfg(feature = "parallel")]
 #[cfg_attr(debug_assertions, ignore)]
 fn build_octree_hilbert() {
     build_octree_par_consistency::<i64, f64, _>(
@@ -0,0 +1,150 @@
+use super::{default_parameters, particle_block, particle_cuboid};
+use splashsurf_lib::parameter_suggestion::{estimate_reconstruction, Budget};
+use splashsurf_lib::{
+    density_map, reconstruct_surface, suggest_parameters, AxisAlignedBoundingBox3d, Parameters,
+};
+
+fn params(particle_radius: f64, cube_size: f64) -> Parameters<f64> {
+    Parameters {
+        cube_size,
+        enable_multi_threading: true,
+        ..default_parameters(particle_radius)
+    }
+}
+
+fn assert_within_factor(name: &str, estimated: u64, actual: u64, factor: f64) {
+    let ratio = estimated as f64 / actual as f64;
+    assert!(
+        ratio <= factor && ratio >= 1.0 / factor,
+        "estimated {} ({}) differs from actual value ({}) by more than a factor of {}",
+        name,
+        estimated,
+        actual,
+        factor
+    );
+}
+
+#[test]
+fn test_estimates_match_reconstruction() {
+    let particle_radius = 0.025;
+    let blocks = [[20, 20, 20], [40, 10, 20]];
+
+    for &dims in blocks.iter() {
+        let particles = particle_cuboid(particle_radius, dims);
+        let aabb = AxisAlignedBoundingBox3d::from_points(&particles);
+
+        for &relative_cube_size in [0.5, 1.0].iter() {
+            let parameters = params(particle_radius, relative_cube_size * particle_radius);
+            let reconstruction = reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+
+            let estimate = estimate_reconstruction(
+                &aabb,
+                particles.len(),
+                particle_radius,
+                parameters.compact_support_radius,
+                parameters.cube_size,
+            );
+
+            assert_within_factor(
+                "triangle count",
+                estimate.triangles,
+                reconstruction.mesh().triangles.len() as u64,
+                1.5,
+            );
+            assert_within_factor(
+                "vertex count",
+                estimate.vertices,
+                reconstruction.mesh().vertices.len() as u64,
+                1.5,
+            );
+            let particle_rest_mass =
+                (4.0 / 3.0) * std::f64::consts::PI * particle_radius.powi(3) * 1000.0;
+            let density_map = density_map::sequential_generate_sparse_density_map(
+                reconstruction.grid(),
+                &particles,
+                reconstruction.particle_densities().unwrap(),
+                None,
+                particle_rest_mass,
+                parameters.compact_support_radius,
+                parameters.cube_size,
+            )
+            .unwrap();
+            assert_within_factor(
+                "density map size",
+                estimate.density_map_points,
+                density_map.len() as u64,
+                1.5,
+            );
+            assert_within_factor(
+                "grid point count",
+                estimate.grid_points,
+                reconstruction
+                    .grid()
+                    .points_per_dim()
+                    .iter()
+                    .product::<i64>() as u64,
+                1.5,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_suggest_cube_size_for_budget() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 20);
+    let compact_support_radius = 4.0 * particle_radius;
+
+    // Without limits, estimates are computed but nothing is suggested
+    let suggestion = suggest_parameters(
+        &particles,
+        particle_radius,
+        compact_support_radius,
+        &Budget::default(),
+    );
+    assert!(suggestion.suggested.is_none());
+    assert!(suggestion
+        .estimates
+        .windows(2)
+        .all(|w| w[0].cube_size < w[1].cube_size && w[0].triangles >= w[1].triangles));
+
+    // The suggested cube size is the smallest one meeting the triangle budget
+    let max_triangles = 20_000;
+    let suggestion = suggest_parameters(
+        &particles,
+        particle_radius,
+        compact_support_radius,
+        &Budget {
+            max_triangles: Some(max_triangles),
+            ..Default::default()
+        },
+    );
+    let suggested = suggestion.suggested.unwrap();
+    assert!(suggestion.estimates[suggested].triangles <= max_triangles);
+    assert!(suggestion.estimates[..suggested]
+        .iter()
+        .all(|estimate| estimate.triangles > max_triangles));
+    assert_eq!(
+        suggestion.suggested_cube_size(),
+        Some(suggestion.estimates[suggested].cube_size)
+    );
+
+    // The reconstruction with the suggested cube size actually meets the budget (up to the accuracy of the estimate)
+    let cube_size = suggestion.suggested_cube_size().unwrap();
+    let reconstruction =
+        reconstruct_surface::<i64, f64>(&particles, &params(particle_radius, cube_size)).unwrap();
+    assert!(reconstruction.mesh().triangles.len() as u64 <= 3 * max_triangles / 2);
+
+    // A memory budget that is too small cannot be satisfied
+    let suggestion = suggest_parameters(
+        &particles,
+        particle_radius,
+        compact_support_radius,
+        &Budget {
+            max_memory_bytes: Some(1024),
+            ..Default::default()
+        },
+    );
+    assert!(suggestion.suggested.is_none());
+    assert!(suggestion.suggested_cube_size().is_none());
+}
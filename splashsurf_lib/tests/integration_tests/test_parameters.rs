@@ -0,0 +1,234 @@
+use nalgebra::Vector3;
+use splashsurf_lib::transform::{scale_points, translate_points};
+use splashsurf_lib::{
+    reconstruct_surface, AxisAlignedBoundingBox3d, ClippingPlane, MarchingCubesVariant,
+    ParameterError, Parameters, ParticleDensityComputationStrategy, RelativeParameters,
+    SpatialDecompositionParameters, SplashDetection, SplashOutput, SubdivisionCriterion,
+};
+
+fn relative_params() -> RelativeParameters<f64> {
+    RelativeParameters {
+        rest_density: 1000.0,
+        compact_support_radius: None,
+        relative_compact_support_radius: Some(4.0),
+        cube_size: None,
+        relative_cube_size: Some(0.75),
+        iso_surface_threshold: 0.6,
+        triangulation_scheme: MarchingCubesVariant::Classic,
+        vertex_snapping: None,
+        splash_detection: None,
+        splash_particle_output: SplashOutput::Ignore,
+        domain_aabb: None,
+        clipping_planes: Vec::new(),
+        enable_multi_threading: false,
+        deterministic: false,
+        progress_interval: None,
+        temporal_density_smoothing: None,
+        spatial_decomposition: None,
+    }
+}
+
+#[test]
+fn test_relative_parameters_resolve() {
+    let relative = relative_params();
+
+    let parameters = relative.resolve(0.025).unwrap();
+    assert_eq!(parameters.particle_radius, 0.025);
+    assert_eq!(parameters.compact_support_radius, 4.0 * 0.025);
+    assert_eq!(parameters.cube_size, 0.75 * 0.025);
+
+    // Changing the particle radius rescales the relative quantities
+    let parameters = relative.resolve(0.5).unwrap();
+    assert_eq!(parameters.compact_support_radius, 2.0);
+    assert_eq!(parameters.cube_size, 0.375);
+
+    // Absolute values are not rescaled
+    let mut relative = relative_params();
+    relative.relative_cube_size = None;
+    relative.cube_size = Some(0.01);
+    let parameters = relative.resolve(0.5).unwrap();
+    assert_eq!(parameters.compact_support_radius, 2.0);
+    assert_eq!(parameters.cube_size, 0.01);
+
+    let parameters = relative.try_convert::<f32>().unwrap().resolve(0.5).unwrap();
+    assert_eq!(parameters.compact_support_radius, 2.0_f32);
+    assert_eq!(parameters.cube_size, 0.01_f32);
+}
+
+#[test]
+fn test_relative_parameters_validation() {
+    let mut relative = relative_params();
+    relative.compact_support_radius = Some(0.1);
+    assert_eq!(
+        relative.resolve(0.025).unwrap_err(),
+        ParameterError::AbsoluteAndRelativeValue("compact support radius")
+    );
+
+    let mut relative = relative_params();
+    relative.relative_cube_size = None;
+    assert_eq!(
+        relative.resolve(0.025).unwrap_err(),
+        ParameterError::MissingValue("cube size")
+    );
+}
+
+fn absolute_params() -> Parameters<f64> {
+    Parameters {
+        particle_radius: 0.025,
+        rest_density: 1000.0,
+        compact_support_radius: 0.1,
+        cube_size: 0.0125,
+        iso_surface_threshold: 0.6,
+        triangulation_scheme: MarchingCubesVariant::Classic,
+        vertex_snapping: None,
+        splash_detection: Some(SplashDetection::NeighborCount(0.1)),
+        splash_particle_output: SplashOutput::Ignore,
+        domain_aabb: Some(AxisAlignedBoundingBox3d::new(
+            Vector3::new(-1.0, -1.0, -1.0),
+            Vector3::new(1.0, 1.0, 1.0),
+        )),
+        clipping_planes: vec![
+            ClippingPlane::new(Vector3::zeros(), Vector3::new(0.0, 0.0, 1.0)),
+            ClippingPlane::new(Vector3::zeros(), Vector3::new(0.0, 1.0, 0.0)),
+        ],
+        enable_multi_threading: false,
+        deterministic: false,
+        progress_interval: None,
+        temporal_density_smoothing: None,
+        spatial_decomposition: Some(SpatialDecompositionParameters {
+            subdivision_criterion: SubdivisionCriterion::MaxParticleCountAuto,
+            ghost_particle_safety_factor: Some(1.0),
+            enable_stitching: true,
+            particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+        }),
+    }
+}
+
+#[test]
+fn test_parameters_conversion_errors() {
+    let too_large = f64::from(f32::MAX) * 10.0;
+
+    assert!(absolute_params().try_convert_checked::<f32>().is_ok());
+    assert!(absolute_params().try_convert::<f32>().is_some());
+
+    let modifications: Vec<(&str, fn(&mut Parameters<f64>, f64))> = vec![
+        ("particle_radius", |p, v| p.particle_radius = v),
+        ("rest_density", |p, v| p.rest_density = v),
+        ("compact_support_radius", |p, v| {
+            p.compact_support_radius = v
+        }),
+        ("cube_size", |p, v| p.cube_size = v),
+        ("iso_surface_threshold", |p, v| p.iso_surface_threshold = v),
+        ("splash_detection.NeighborCount", |p, v| {
+            p.splash_detection = Some(SplashDetection::NeighborCount(v))
+        }),
+        ("splash_detection.DensityThreshold", |p, v| {
+            p.splash_detection = Some(SplashDetection::DensityThreshold(v))
+        }),
+        ("domain_aabb.min[1]", |p, v| {
+            p.domain_aabb = Some(AxisAlignedBoundingBox3d::new(
+                Vector3::new(-1.0, -v, -1.0),
+                Vector3::new(1.0, 1.0, 1.0),
+            ))
+        }),
+        ("domain_aabb.max[2]", |p, v| {
+            p.domain_aabb = Some(AxisAlignedBoundingBox3d::new(
+                Vector3::new(-1.0, -1.0, -1.0),
+                Vector3::new(1.0, 1.0, v),
+            ))
+        }),
+        ("clipping_planes[1].point[0]", |p, v| {
+            p.clipping_planes[1].point.x = v
+        }),
+        ("clipping_planes[0].normal[2]", |p, v| {
+            p.clipping_planes[0].normal.z = v
+        }),
+        (
+            "spatial_decomposition.ghost_particle_safety_factor",
+            |p, v| {
+                p.spatial_decomposition
+                    .as_mut()
+                    .unwrap()
+                    .ghost_particle_safety_factor = Some(v)
+            },
+        ),
+    ];
+
+    for (field, modify) in modifications {
+        let mut parameters = absolute_params();
+        modify(&mut parameters, too_large);
+
+        let err = parameters.try_convert_checked::<f32>().unwrap_err();
+        assert_eq!(err.field, field);
+        assert!(err.value.abs() == too_large);
+        assert!(err.to_string().contains(field));
+        assert!(parameters.try_convert::<f32>().is_none());
+
+        // Conversion to the same or a larger type still works
+        assert!(parameters.try_convert_checked::<f64>().is_ok());
+    }
+}
+
+#[test]
+fn test_relative_parameters_conversion_errors() {
+    let too_large = f64::from(f32::MAX) * 10.0;
+
+    let mut relative = relative_params();
+    relative.relative_cube_size = Some(too_large);
+    let err = relative.try_convert_checked::<f32>().unwrap_err();
+    assert_eq!(err.field, "relative_cube_size");
+    assert_eq!(err.value, too_large);
+
+    let mut relative = relative_params();
+    relative.rest_density = too_large;
+    let err = relative.try_convert_checked::<f32>().unwrap_err();
+    assert_eq!(err.field, "rest_density");
+}
+
+#[test]
+fn test_parameters_scale_and_translate() {
+    let translation = Vector3::new(1.0, 2.0, -3.0);
+    let mut parameters = absolute_params();
+    parameters.scale_and_translate(0.5, &translation);
+    assert_eq!(parameters.particle_radius, 0.0125);
+    assert_eq!(parameters.compact_support_radius, 0.05);
+    assert_eq!(parameters.cube_size, 0.00625);
+    assert_eq!(parameters.rest_density, 1000.0);
+    assert_eq!(parameters.iso_surface_threshold, 0.6);
+    assert!(matches!(
+        parameters.splash_detection,
+        Some(SplashDetection::NeighborCount(radius)) if radius == 0.05
+    ));
+    let domain_aabb = parameters.domain_aabb.as_ref().unwrap();
+    assert_eq!(*domain_aabb.min(), Vector3::new(0.5, 1.5, -3.5));
+    assert_eq!(*domain_aabb.max(), Vector3::new(1.5, 2.5, -2.5));
+    assert_eq!(parameters.clipping_planes[0].point, translation);
+    assert_eq!(
+        parameters.clipping_planes[0].normal,
+        Vector3::new(0.0, 0.0, 1.0)
+    );
+
+    // Reconstructing scaled particles with scaled parameters results in the scaled surface
+    let particles = (0..1000)
+        .map(|i| Vector3::new((i % 10) as f64, ((i / 10) % 10) as f64, (i / 100) as f64) * 0.05)
+        .collect::<Vec<_>>();
+    let mut parameters = absolute_params();
+    parameters.domain_aabb = None;
+    parameters.clipping_planes.clear();
+    parameters.splash_detection = None;
+    parameters.spatial_decomposition = None;
+    let surface = reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+
+    let scaling = 100.0;
+    let mut scaled_particles = particles.clone();
+    scale_points(&mut scaled_particles, scaling);
+    translate_points(&mut scaled_particles, &translation);
+    parameters.scale_and_translate(scaling, &translation);
+    let scaled_surface = reconstruct_surface::<i64, f64>(&scaled_particles, &parameters).unwrap();
+
+    let mesh = surface.mesh();
+    let scaled_mesh = scaled_surface.mesh();
+    assert_eq!(scaled_mesh.triangles.len(), mesh.triangles.len());
+    let expected_area = mesh.area() * scaling * scaling;
+    assert!((scaled_mesh.area() - expected_area).abs() < 1e-6 * expected_area);
+}
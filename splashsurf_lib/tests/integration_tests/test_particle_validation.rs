@@ -0,0 +1,123 @@
+use super::particle_block;
+use nalgebra::Vector3;
+use splashsurf_lib::particle_validation::{
+    validate_particles_with_tolerance, ParticleValidationError, MAX_REPORTED_INDICES,
+};
+use splashsurf_lib::{validate_particles, ValidationPolicy};
+
+#[test]
+fn test_valid_particles() {
+    let particles = particle_block(0.025, 10);
+
+    let report = validate_particles(&particles, ValidationPolicy::Error).unwrap();
+    assert!(report.is_valid());
+    assert_eq!(report.num_particles, particles.len());
+    assert_eq!(report.valid_particles().len(), particles.len());
+
+    // Lattice neighbors are one spacing apart
+    let report =
+        validate_particles_with_tolerance(&particles, ValidationPolicy::Error, 0.04).unwrap();
+    assert!(report.is_valid());
+}
+
+#[test]
+fn test_nan_particles() {
+    let mut particles = particle_block(0.025, 10);
+    particles[3].x = f64::NAN;
+    particles[500].z = f64::NAN;
+
+    let err = validate_particles(&particles, ValidationPolicy::Error).unwrap_err();
+    assert_eq!(
+        err,
+        ParticleValidationError::NonFinitePositions {
+            count: 2,
+            indices: vec![3, 500],
+        }
+    );
+
+    let report = validate_particles(&particles, ValidationPolicy::Filter).unwrap();
+    assert_eq!(report.num_non_finite, 2);
+    assert_eq!(report.invalid_particles, vec![3, 500]);
+
+    let filtered = report.filter(&particles);
+    assert_eq!(filtered.len(), particles.len() - 2);
+    assert!(filtered.iter().all(|p| p.iter().all(|c| c.is_finite())));
+}
+
+#[test]
+fn test_inf_particles() {
+    let mut particles = particle_block(0.025, 10);
+    for i in (0..particles.len()).step_by(50) {
+        particles[i].y = if i % 100 == 0 {
+            f64::INFINITY
+        } else {
+            f64::NEG_INFINITY
+        };
+    }
+
+    match validate_particles(&particles, ValidationPolicy::Error) {
+        Err(ParticleValidationError::NonFinitePositions { count, indices }) => {
+            assert_eq!(count, 20);
+            assert_eq!(indices.len(), MAX_REPORTED_INDICES);
+            assert_eq!(indices[..3], [0, 50, 100]);
+        }
+        result => panic!("unexpected validation result: {:?}", result),
+    }
+
+    let report = validate_particles(&particles, ValidationPolicy::Filter).unwrap();
+    assert_eq!(report.num_non_finite, 20);
+    assert_eq!(report.num_duplicates, 0);
+    assert_eq!(report.valid_particles().len(), particles.len() - 20);
+}
+
+#[test]
+fn test_coincident_particles() {
+    let mut particles = particle_block(0.025, 5);
+    let num_distinct = particles.len();
+    particles.extend(std::iter::repeat(Vector3::new(1.0, 2.0, 3.0)).take(1000));
+
+    match validate_particles(&particles, ValidationPolicy::Error) {
+        Err(ParticleValidationError::DuplicatePositions { count, indices }) => {
+            assert_eq!(count, 999);
+            assert_eq!(indices[0], num_distinct + 1);
+        }
+        result => panic!("unexpected validation result: {:?}", result),
+    }
+
+    // The first of the coincident particles is kept
+    let report = validate_particles(&particles, ValidationPolicy::Filter).unwrap();
+    assert_eq!(report.num_duplicates, 999);
+    let valid_particles = report.valid_particles();
+    assert_eq!(valid_particles.len(), num_distinct + 1);
+    assert_eq!(*valid_particles.last().unwrap(), num_distinct);
+}
+
+#[test]
+fn test_near_duplicate_particles() {
+    let mut particles = particle_block(0.025, 5);
+    let num_distinct = particles.len();
+    particles.push(particles[7] + Vector3::new(1e-4, -1e-4, 0.0));
+    particles.push(Vector3::new(-0.0, 0.0, 0.0));
+
+    // Without tolerance, only the exact duplicate (negative zero) is detected
+    let report = validate_particles(&particles, ValidationPolicy::Filter).unwrap();
+    assert_eq!(report.invalid_particles, vec![num_distinct + 1]);
+
+    let report =
+        validate_particles_with_tolerance(&particles, ValidationPolicy::Filter, 1e-3).unwrap();
+    assert_eq!(
+        report.invalid_particles,
+        vec![num_distinct, num_distinct + 1]
+    );
+}
+
+#[test]
+fn test_skip_validation() {
+    let mut particles = particle_block(0.025, 5);
+    particles[0].x = f64::NAN;
+    particles.push(particles[1]);
+
+    let report = validate_particles(&particles, ValidationPolicy::Skip).unwrap();
+    assert!(report.is_valid());
+    assert_eq!(report.valid_particles().len(), particles.len());
+}
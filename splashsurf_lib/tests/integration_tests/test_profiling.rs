@@ -0,0 +1,36 @@
+use super::{default_parameters, particle_block};
+use splashsurf_lib::{profiling, reconstruct_surface};
+
+#[test]
+fn test_profiling_report() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 10);
+    let parameters = default_parameters(particle_radius);
+
+    reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+
+    // Other tests may record scopes concurrently, so only the presence of the scopes is checked
+    let report = profiling::report();
+    let root_names: Vec<_> = report.roots.iter().map(|root| root.name).collect();
+    assert!(root_names.contains(&"check_particle_positions"));
+    assert!(root_names.contains(&"reconstruct_surface_global"));
+
+    let global = report.find("reconstruct_surface_global").unwrap();
+    assert!(global.num_calls >= 1);
+    assert!(global.mean_duration <= global.total_duration);
+    let densities = global
+        .find("compute_particle_densities_and_neighbors")
+        .unwrap();
+    assert!(densities.total_duration <= global.total_duration);
+    assert!(global.find("triangulate_density_map_append").is_some());
+
+    // The pretty printed output is generated from the same data
+    let mut buffer = Vec::new();
+    report.write(&mut buffer).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+    assert!(output.contains("reconstruct_surface_global"));
+    assert!(output
+        .lines()
+        .any(|line| line.starts_with("  ")
+            && line.contains("compute_particle_densities_and_neighbors")));
+}
@@ -0,0 +1,113 @@
+use super::{default_parameters, particle_block};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use parking_lot::{const_mutex, Mutex};
+use splashsurf_lib::{
+    reconstruct_surface, Parameters, ParticleDensityComputationStrategy,
+    SpatialDecompositionParameters, SubdivisionCriterion,
+};
+use std::time::Duration;
+
+/// Logger that stores all messages of the progress logging
+struct ProgressLogger {
+    messages: Mutex<Vec<String>>,
+}
+
+const STAGES: [&str; 4] = [
+    "density map",
+    "octree leaves",
+    "marching cubes",
+    "triangulation",
+];
+
+impl Log for ProgressLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info
+    }
+
+    fn log(&self, record: &Record) {
+        let message = record.args().to_string();
+        if STAGES
+            .iter()
+            .any(|stage| message.starts_with(&format!("{}: ", stage)))
+        {
+            self.messages.lock().push(message);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: ProgressLogger = ProgressLogger {
+    messages: const_mutex(Vec::new()),
+};
+
+fn params(particle_radius: f64, progress_interval: Option<Duration>) -> Parameters<f64> {
+    Parameters {
+        enable_multi_threading: true,
+        progress_interval,
+        ..default_parameters(particle_radius)
+    }
+}
+
+/// Returns all collected messages of the given stage
+fn stage_messages(messages: &[String], stage: &str) -> Vec<String> {
+    messages
+        .iter()
+        .filter(|message| message.starts_with(&format!("{}: ", stage)))
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn test_progress_logging() {
+    log::set_logger(&LOGGER).expect("failed to set logger");
+    log::set_max_level(LevelFilter::Info);
+
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 30);
+
+    // No progress is logged by default
+    reconstruct_surface::<i64, f64>(&particles, &params(particle_radius, None)).unwrap();
+    assert!(LOGGER.messages.lock().is_empty());
+
+    // Global reconstruction (sequential and parallel)
+    for enable_multi_threading in [false, true] {
+        let mut parameters = params(particle_radius, Some(Duration::ZERO));
+        parameters.enable_multi_threading = enable_multi_threading;
+        reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+
+        let messages = std::mem::take(&mut *LOGGER.messages.lock());
+        for stage in ["density map", "marching cubes", "triangulation"] {
+            let stage_messages = stage_messages(&messages, stage);
+            assert!(
+                !stage_messages.is_empty(),
+                "no progress messages of stage \"{}\"",
+                stage
+            );
+        }
+
+        let density_map_messages = stage_messages(&messages, "density map");
+        assert!(density_map_messages
+            .iter()
+            .all(|message| message.contains("/27k particles, ")
+                && message.ends_with(" particles/s)")));
+    }
+
+    // Octree based reconstruction, the progress of the stages of the individual leaves is not logged
+    let mut parameters = params(particle_radius, Some(Duration::ZERO));
+    parameters.spatial_decomposition = Some(SpatialDecompositionParameters {
+        subdivision_criterion: SubdivisionCriterion::MaxParticleCount(1000),
+        ghost_particle_safety_factor: None,
+        enable_stitching: true,
+        particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+    });
+    reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+
+    let messages = std::mem::take(&mut *LOGGER.messages.lock());
+    let leaf_messages = stage_messages(&messages, "octree leaves");
+    assert!(!leaf_messages.is_empty());
+    assert!(leaf_messages
+        .iter()
+        .all(|message| message.contains(" leaves/s)")));
+    assert!(stage_messages(&messages, "density map").is_empty());
+}
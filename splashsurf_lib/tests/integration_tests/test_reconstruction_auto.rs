@@ -0,0 +1,74 @@
+use super::{default_parameters, particle_block};
+use splashsurf_lib::octree::OctreeError;
+use splashsurf_lib::reconstruction_auto::select_index_type;
+use splashsurf_lib::{
+    reconstruct_surface, reconstruct_surface_auto, IndexType, ParticleDensityComputationStrategy,
+    ReconstructionError, SpatialDecompositionParameters, SubdivisionCriterion,
+    SurfaceReconstructionDyn,
+};
+
+#[test]
+fn test_select_index_type() {
+    let particles = particle_block(0.025, 10);
+
+    let parameters = default_parameters(0.025);
+    assert_eq!(select_index_type(&particles, &parameters), IndexType::I32);
+
+    // The grid of the particle block (extents of about 0.5) with a cube size of 1e-4 has more than `i32::MAX` points
+    let mut parameters = default_parameters(0.025);
+    parameters.cube_size = 1e-4;
+    assert_eq!(select_index_type(&particles, &parameters), IndexType::I64);
+}
+
+#[test]
+fn test_reconstruct_surface_auto() {
+    let particles = particle_block(0.025, 10);
+    let parameters = default_parameters(0.025);
+
+    let reconstruction = reconstruct_surface_auto(&particles, &parameters).unwrap();
+    assert_eq!(reconstruction.index_type(), IndexType::I32);
+    assert!(matches!(reconstruction, SurfaceReconstructionDyn::I32(_)));
+
+    let expected = reconstruct_surface::<i32, f64>(&particles, &parameters).unwrap();
+    assert!(!expected.mesh().triangles.is_empty());
+    assert_eq!(reconstruction.mesh().triangles, expected.mesh().triangles);
+    assert_eq!(reconstruction.mesh().vertices, expected.mesh().vertices);
+    assert_eq!(
+        reconstruction.particle_densities(),
+        expected.particle_densities()
+    );
+
+    let points = expected.grid().points_per_dim();
+    assert_eq!(
+        reconstruction.num_grid_points(),
+        (points[0] * points[1] * points[2]) as u64
+    );
+}
+
+#[test]
+fn test_reconstruct_surface_auto_errors() {
+    let particles = particle_block(0.025, 10);
+
+    // Errors of the `i32` reconstruction are converted to the `i64` error type
+    let mut parameters = default_parameters(0.025);
+    parameters.spatial_decomposition = Some(SpatialDecompositionParameters {
+        subdivision_criterion: SubdivisionCriterion::MaxParticleCount(100),
+        ghost_particle_safety_factor: Some(-1.0),
+        enable_stitching: true,
+        particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+    });
+    assert!(matches!(
+        reconstruct_surface_auto(&particles, &parameters),
+        Err(ReconstructionError::OctreeError(
+            OctreeError::InvalidMargin(_)
+        ))
+    ));
+
+    // Invalid particles prevent the construction of the grid, the error is reported by the `i64` reconstruction
+    let mut particles = particles;
+    particles[3].x = f64::NAN;
+    assert!(matches!(
+        reconstruct_surface_auto(&particles, &default_parameters(0.025)),
+        Err(ReconstructionError::InvalidParticleData { index: 3, .. })
+    ));
+}
@@ -0,0 +1,217 @@
+use super::{default_parameters, particle_block};
+use anyhow::anyhow;
+use nalgebra::Vector3;
+use splashsurf_lib::marching_cubes::{MarchingCubesError, TriangulationError};
+use splashsurf_lib::mesh::MeshAttribute;
+use splashsurf_lib::octree::OctreeError;
+use splashsurf_lib::{
+    reconstruct_surface, reconstruct_surface_inplace, reconstruct_surface_with_attributes,
+    AttributeError, AttributeSpecs, AxisAlignedBoundingBox3d, CancellationToken,
+    InvalidParticleReason, ParameterError, Parameters, ParticleDensityComputationStrategy,
+    ReconstructionError, SpatialDecompositionParameters, SubdivisionCriterion,
+    SurfaceReconstruction,
+};
+use std::error::Error;
+
+fn octree_params(particle_radius: f64, enable_stitching: bool) -> Parameters<f64> {
+    let mut parameters = default_parameters(particle_radius);
+    parameters.spatial_decomposition = Some(SpatialDecompositionParameters {
+        subdivision_criterion: SubdivisionCriterion::MaxParticleCount(100),
+        ghost_particle_safety_factor: Some(1.0),
+        enable_stitching,
+        particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+    });
+    parameters
+}
+
+#[test]
+fn test_invalid_parameters() {
+    let particles = particle_block(0.025, 5);
+
+    let mut parameters = default_parameters(0.025);
+    parameters.cube_size = 0.0;
+    assert_eq!(
+        parameters.validate().unwrap_err(),
+        ParameterError::InvalidValue("cube size")
+    );
+    assert!(matches!(
+        reconstruct_surface::<i64, _>(&particles, &parameters),
+        Err(ReconstructionError::InvalidParameters(
+            ParameterError::InvalidValue("cube size")
+        ))
+    ));
+
+    let mut parameters = default_parameters(0.025);
+    parameters.compact_support_radius = f64::NAN;
+    assert!(matches!(
+        reconstruct_surface::<i64, _>(&particles, &parameters),
+        Err(ReconstructionError::InvalidParameters(
+            ParameterError::InvalidValue("compact support radius")
+        ))
+    ));
+
+    assert!(default_parameters(0.025).validate().is_ok());
+}
+
+#[test]
+fn test_invalid_particle_data() {
+    let parameters = default_parameters(0.025);
+
+    let mut particles = particle_block(0.025, 5);
+    particles[42].y = f64::NAN;
+    particles[50].x = f64::INFINITY;
+    assert!(matches!(
+        reconstruct_surface::<i64, _>(&particles, &parameters),
+        Err(ReconstructionError::InvalidParticleData {
+            index: 42,
+            reason: InvalidParticleReason::NanPosition
+        })
+    ));
+
+    let mut particles = particle_block(0.025, 5);
+    particles[7].z = f64::NEG_INFINITY;
+    let err = reconstruct_surface::<i64, _>(&particles, &parameters).unwrap_err();
+    assert!(matches!(
+        err,
+        ReconstructionError::InvalidParticleData {
+            index: 7,
+            reason: InvalidParticleReason::InfinitePosition
+        }
+    ));
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn test_density_map_error() {
+    let particles = particle_block(0.025, 2);
+
+    // The domain contains all particles but is smaller than the kernel evaluation margin, so no particle can contribute to the density map
+    let mut parameters = default_parameters(0.025);
+    parameters.domain_aabb = Some(AxisAlignedBoundingBox3d::new(
+        Vector3::new(-0.025, -0.025, -0.025),
+        Vector3::new(0.075, 0.075, 0.075),
+    ));
+    assert!(matches!(
+        reconstruct_surface::<i64, _>(&particles, &parameters),
+        Err(ReconstructionError::DensityMapError(_))
+    ));
+}
+
+#[test]
+fn test_grid_construction_error() {
+    let particles = particle_block(0.025, 5);
+
+    let mut parameters = default_parameters(0.025);
+    parameters.domain_aabb = Some(AxisAlignedBoundingBox3d::new(
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(1.0, 1.0, 0.0),
+    ));
+    assert!(matches!(
+        reconstruct_surface::<i64, _>(&particles, &parameters),
+        Err(ReconstructionError::GridConstructionError(_))
+    ));
+}
+
+#[test]
+fn test_triangulation_error() {
+    // Valid density maps cannot produce triangulation errors, so only the conversion into the reconstruction error is checked
+    let err: ReconstructionError<i64, f64> = MarchingCubesError::from(
+        TriangulationError::TriangleConnectivityError(anyhow!("missing vertex")),
+    )
+    .into();
+    assert!(matches!(err, ReconstructionError::TriangulationError(_)));
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn test_octree_error() {
+    let particles = particle_block(0.025, 10);
+
+    for enable_stitching in [false, true] {
+        let mut parameters = octree_params(0.025, enable_stitching);
+        parameters
+            .spatial_decomposition
+            .as_mut()
+            .unwrap()
+            .ghost_particle_safety_factor = Some(-1.0);
+        assert!(matches!(
+            reconstruct_surface::<i64, _>(&particles, &parameters),
+            Err(ReconstructionError::OctreeError(
+                OctreeError::InvalidMargin(_)
+            ))
+        ));
+    }
+}
+
+#[test]
+fn test_attribute_error() {
+    let particles = particle_block(0.025, 5);
+    let parameters = default_parameters(0.025);
+
+    let attributes = vec![MeshAttribute::new_real_scalar(
+        "pressure",
+        vec![1.0; particles.len() - 1],
+    )];
+    let attribute_specs = AttributeSpecs {
+        particle_attributes: attributes.as_slice(),
+        ..AttributeSpecs::default()
+    };
+    let err =
+        reconstruct_surface_with_attributes::<i64, _>(&particles, &parameters, &attribute_specs)
+            .unwrap_err();
+    match err {
+        ReconstructionError::AttributeError(AttributeError::LengthMismatch {
+            name,
+            len,
+            expected,
+        }) => {
+            assert_eq!(name, "pressure");
+            assert_eq!(len, particles.len() - 1);
+            assert_eq!(expected, particles.len());
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+
+    let attributes = vec![MeshAttribute::new("id", vec![1u64; particles.len()])];
+    let attribute_specs = AttributeSpecs {
+        particle_attributes: attributes.as_slice(),
+        ..AttributeSpecs::default()
+    };
+    assert!(matches!(
+        reconstruct_surface_with_attributes::<i64, _>(&particles, &parameters, &attribute_specs),
+        Err(ReconstructionError::AttributeError(
+            AttributeError::UnsupportedType(_)
+        ))
+    ));
+}
+
+#[test]
+fn test_cancelled() {
+    let particles = particle_block(0.025, 10);
+
+    for parameters in [
+        default_parameters(0.025),
+        octree_params(0.025, false),
+        octree_params(0.025, true),
+    ] {
+        let token = CancellationToken::new();
+        let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+        reconstruction.set_cancellation_token(token.clone());
+
+        reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+        assert!(!reconstruction.mesh().triangles.is_empty());
+        let mut cloned = reconstruction.clone();
+
+        // Cancelling a clone of the token cancels all reconstructions using the token
+        token.cancel();
+        assert!(reconstruction.cancellation_token().is_cancelled());
+        assert!(matches!(
+            reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction),
+            Err(ReconstructionError::Cancelled)
+        ));
+
+        // Clones of the reconstruction get their own token
+        assert!(!cloned.cancellation_token().is_cancelled());
+        reconstruct_surface_inplace(&particles, &parameters, &mut cloned).unwrap();
+    }
+}
@@ -0,0 +1,314 @@
+use super::default_parameters;
+use nalgebra::Vector3;
+use splashsurf_lib::mesh::TriMesh3d;
+use splashsurf_lib::reference::reconstruct_surface_reference;
+use splashsurf_lib::{
+    reconstruct_surface, AxisAlignedBoundingBox3d, Parameters, ParticleDensityComputationStrategy,
+    SpatialDecompositionParameters, SubdivisionCriterion,
+};
+use std::collections::HashMap;
+
+const PARTICLE_RADIUS: f64 = 0.025;
+const CUBE_SIZE: f64 = 0.75 * PARTICLE_RADIUS;
+/// Distance below which vertices of a mesh are merged
+const WELD_TOLERANCE: f64 = 1e-6 * CUBE_SIZE;
+/// Maximum distance between the vertices of an optimized reconstruction and the reference (mostly due to the discretized kernel)
+const VERTEX_TOLERANCE: f64 = 0.1 * CUBE_SIZE;
+
+/// Returns a pseudo random number generator (linear congruential) with values in `[-0.5, 0.5)`
+fn random_generator(seed: u64) -> impl FnMut() -> f64 {
+    let mut state = seed;
+    move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+    }
+}
+
+/// Generates particles on a jittered lattice with particle spacing inside of the given box that fulfill the predicate
+fn jittered_lattice(
+    seed: u64,
+    min: Vector3<f64>,
+    max: Vector3<f64>,
+    predicate: impl Fn(&Vector3<f64>) -> bool,
+) -> Vec<Vector3<f64>> {
+    let mut next_random = random_generator(seed);
+    let spacing = 2.0 * PARTICLE_RADIUS;
+    let n = ((max - min) / spacing).map(|n| n.floor() as usize + 1);
+
+    let mut particles = Vec::new();
+    for i in 0..n.x {
+        for j in 0..n.y {
+            for k in 0..n.z {
+                let lattice_point = min + Vector3::new(i as f64, j as f64, k as f64) * spacing;
+                if !predicate(&lattice_point) {
+                    continue;
+                }
+                let jitter = Vector3::new(next_random(), next_random(), next_random());
+                particles.push(lattice_point + jitter * 0.3 * spacing);
+            }
+        }
+    }
+    particles
+}
+
+fn sphere(seed: u64, center: Vector3<f64>, radius: f64) -> Vec<Vector3<f64>> {
+    jittered_lattice(
+        seed,
+        center.add_scalar(-radius),
+        center.add_scalar(radius),
+        |x| (x - center).norm() <= radius,
+    )
+}
+
+fn sphere_particles() -> Vec<Vector3<f64>> {
+    sphere(1, Vector3::zeros(), 0.2)
+}
+
+/// A sheet that is only two particles thick
+fn thin_sheet_particles() -> Vec<Vector3<f64>> {
+    jittered_lattice(
+        2,
+        Vector3::new(-0.25, -0.2, 0.0),
+        Vector3::new(0.25, 0.2, 2.0 * PARTICLE_RADIUS),
+        |_| true,
+    )
+}
+
+/// Two separate blobs of different size
+fn two_blob_particles() -> Vec<Vector3<f64>> {
+    let mut particles = sphere(3, Vector3::zeros(), 0.15);
+    particles.extend(sphere(4, Vector3::new(0.4, 0.05, 0.0), 0.1));
+    particles
+}
+
+fn params(spatial_decomposition: Option<SpatialDecompositionParameters<f64>>) -> Parameters<f64> {
+    Parameters {
+        cube_size: CUBE_SIZE,
+        spatial_decomposition,
+        ..default_parameters(PARTICLE_RADIUS)
+    }
+}
+
+fn octree_params(
+    particle_density_computation: ParticleDensityComputationStrategy,
+    enable_stitching: bool,
+) -> Parameters<f64> {
+    let ghost_particle_safety_factor = match particle_density_computation {
+        // Local densities of the ghost particles require twice the compact support radius as margin
+        ParticleDensityComputationStrategy::IndependentSubdomains => Some(2.0),
+        _ => None,
+    };
+
+    Parameters {
+        enable_multi_threading: true,
+        ..params(Some(SpatialDecompositionParameters {
+            subdivision_criterion: SubdivisionCriterion::MaxParticleCount(60),
+            ghost_particle_safety_factor,
+            enable_stitching,
+            particle_density_computation,
+        }))
+    }
+}
+
+/// Returns all optimized code paths that are compared to the reference with their names
+fn all_fast_paths() -> Vec<(String, Parameters<f64>)> {
+    let mut paths = vec![
+        ("global sequential".to_string(), params(None)),
+        (
+            "global parallel".to_string(),
+            Parameters {
+                enable_multi_threading: true,
+                ..params(None)
+            },
+        ),
+        (
+            "global deterministic".to_string(),
+            Parameters {
+                enable_multi_threading: true,
+                deterministic: true,
+                ..params(None)
+            },
+        ),
+    ];
+
+    for strategy in [
+        ParticleDensityComputationStrategy::Global,
+        ParticleDensityComputationStrategy::SynchronizeSubdomains,
+        ParticleDensityComputationStrategy::IndependentSubdomains,
+    ] {
+        for enable_stitching in [false, true] {
+            paths.push((
+                format!("octree {:?} (stitching: {})", strategy, enable_stitching),
+                octree_params(strategy, enable_stitching),
+            ));
+        }
+    }
+
+    paths
+}
+
+/// Returns the integer cell of a spatial hash grid with the given cell size
+fn hash_cell(x: &Vector3<f64>, cell_size: f64) -> [i64; 3] {
+    [x.x, x.y, x.z].map(|c| (c / cell_size).floor() as i64)
+}
+
+/// Returns the index of a vertex of the hash grid within the given distance of the point
+fn find_close_vertex(
+    vertices: &[Vector3<f64>],
+    cells: &HashMap<[i64; 3], Vec<usize>>,
+    cell_size: f64,
+    x: &Vector3<f64>,
+    distance: f64,
+) -> Option<usize> {
+    let [i, j, k] = hash_cell(x, cell_size);
+    for di in -1..=1 {
+        for dj in -1..=1 {
+            for dk in -1..=1 {
+                for &v in cells.get(&[i + di, j + dj, k + dk]).into_iter().flatten() {
+                    if (vertices[v] - x).norm() <= distance {
+                        return Some(v);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Merges vertices closer than the weld tolerance (e.g. duplicates at subdomain boundaries) and removes collapsed triangles
+fn weld(mesh: &TriMesh3d<f64>) -> TriMesh3d<f64> {
+    let mut welded = TriMesh3d::default();
+    let mut cells = HashMap::<[i64; 3], Vec<usize>>::new();
+
+    let vertex_map = mesh
+        .vertices
+        .iter()
+        .map(|x| {
+            find_close_vertex(&welded.vertices, &cells, WELD_TOLERANCE, x, WELD_TOLERANCE)
+                .unwrap_or_else(|| {
+                    welded.vertices.push(*x);
+                    let v = welded.vertices.len() - 1;
+                    cells
+                        .entry(hash_cell(x, WELD_TOLERANCE))
+                        .or_default()
+                        .push(v);
+                    v
+                })
+        })
+        .collect::<Vec<_>>();
+
+    welded.triangles = mesh
+        .triangles
+        .iter()
+        .map(|tri| tri.map(|v| vertex_map[v]))
+        .filter(|[a, b, c]| a != b && b != c && c != a)
+        .collect();
+    welded
+}
+
+/// Asserts that every vertex of the first mesh has a vertex of the second mesh within the vertex tolerance
+fn assert_vertices_close(name: &str, a: &TriMesh3d<f64>, b: &TriMesh3d<f64>) {
+    let mut cells = HashMap::<[i64; 3], Vec<usize>>::new();
+    for (v, x) in b.vertices.iter().enumerate() {
+        cells.entry(hash_cell(x, CUBE_SIZE)).or_default().push(v);
+    }
+
+    for x in &a.vertices {
+        assert!(
+            find_close_vertex(&b.vertices, &cells, CUBE_SIZE, x, VERTEX_TOLERANCE).is_some(),
+            "{}: no vertex close to {:?}",
+            name,
+            x
+        );
+    }
+}
+
+/// Asserts that the welded meshes have the same size, close vertices and the same area and volume
+fn assert_matches_reference(name: &str, mesh: &TriMesh3d<f64>, reference: &TriMesh3d<f64>) {
+    let mesh = weld(mesh);
+
+    assert_eq!(
+        mesh.triangles.len(),
+        reference.triangles.len(),
+        "{}: number of triangles",
+        name
+    );
+    assert_eq!(
+        mesh.vertices.len(),
+        reference.vertices.len(),
+        "{}: number of vertices",
+        name
+    );
+    assert_vertices_close(name, &mesh, reference);
+    assert_vertices_close(name, reference, &mesh);
+
+    let relative_difference = |a: f64, b: f64| (a - b).abs() / b.abs();
+    assert!(
+        relative_difference(mesh.area(), reference.area()) < 1e-3,
+        "{}: area {} differs from reference area {}",
+        name,
+        mesh.area(),
+        reference.area()
+    );
+    assert!(
+        relative_difference(mesh.volume(), reference.volume()) < 1e-3,
+        "{}: volume {} differs from reference volume {}",
+        name,
+        mesh.volume(),
+        reference.volume()
+    );
+}
+
+/// Compares the reconstructions of all optimized code paths to the reference reconstruction of the particles
+fn compare_to_reference(
+    particles: &[Vector3<f64>],
+    domain_aabb: Option<AxisAlignedBoundingBox3d<f64>>,
+) {
+    let reference = {
+        let parameters = Parameters {
+            domain_aabb: domain_aabb.clone(),
+            ..params(None)
+        };
+        weld(&reconstruct_surface_reference::<i64, f64>(particles, &parameters).unwrap())
+    };
+    assert!(!reference.triangles.is_empty());
+    assert_eq!(reference.find_boundary_edges().len(), 0);
+
+    for (name, parameters) in all_fast_paths() {
+        let parameters = Parameters {
+            domain_aabb: domain_aabb.clone(),
+            ..parameters
+        };
+        let reconstruction = reconstruct_surface::<i64, f64>(particles, &parameters).unwrap();
+        assert_matches_reference(&name, reconstruction.mesh(), &reference);
+    }
+}
+
+#[test]
+fn test_reference_sphere() {
+    compare_to_reference(&sphere_particles(), None);
+}
+
+#[test]
+fn test_reference_thin_sheet() {
+    compare_to_reference(&thin_sheet_particles(), None);
+}
+
+#[test]
+fn test_reference_two_blobs() {
+    compare_to_reference(&two_blob_particles(), None);
+}
+
+#[test]
+fn test_reference_domain_boundary() {
+    // The domain cuts through the sphere, particles close to its boundary do not contribute to the surface
+    let domain =
+        AxisAlignedBoundingBox3d::new(Vector3::new(-0.3, -0.3, -0.15), Vector3::new(0.3, 0.3, 0.3));
+    let particles = sphere_particles()
+        .into_iter()
+        .filter(|x| domain.contains_point(x))
+        .collect::<Vec<_>>();
+    compare_to_reference(&particles, Some(domain));
+}
@@ -0,0 +1,127 @@
+use nalgebra::Vector3;
+use splashsurf_lib::mesh::{AttributeData, MeshAttribute, MeshWithData, TriMesh3d};
+
+/// A tetrahedron with scalar, vector and integer point attributes and a cell attribute
+fn tetrahedron_with_data() -> MeshWithData<f32, TriMesh3d<f32>> {
+    let mesh = TriMesh3d {
+        vertices: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+        ],
+        triangles: vec![[0, 2, 1], [0, 1, 3], [1, 2, 3], [0, 3, 2]],
+    };
+
+    MeshWithData::new(mesh)
+        .with_point_data(MeshAttribute::new_real_scalar(
+            "density",
+            vec![1000.0, 998.5, 1001.25, 0.1],
+        ))
+        .with_point_data(MeshAttribute::new_real_vector3(
+            "normals",
+            vec![
+                Vector3::new(-1.0, -1.0, -1.0).normalize(),
+                Vector3::x(),
+                Vector3::y(),
+                Vector3::z(),
+            ],
+        ))
+        .with_point_data(MeshAttribute::new(
+            "id",
+            AttributeData::ScalarU64(vec![0, 1, 2, u64::MAX]),
+        ))
+        .with_cell_data(MeshAttribute::new(
+            "stress",
+            AttributeData::VectorReal {
+                num_components: 2,
+                values: vec![0.5, -0.5, 1.0, -1.0, 2.0, -2.0, 4.0, -4.0],
+            },
+        ))
+}
+
+fn assert_attributes_eq(lhs: &[MeshAttribute<f32>], rhs: &[MeshAttribute<f32>]) {
+    assert_eq!(lhs.len(), rhs.len());
+    for (lhs, rhs) in lhs.iter().zip(rhs.iter()) {
+        assert_eq!(lhs.name, rhs.name);
+        match (&lhs.data, &rhs.data) {
+            (AttributeData::ScalarU64(lhs), AttributeData::ScalarU64(rhs)) => assert_eq!(lhs, rhs),
+            (AttributeData::ScalarReal(lhs), AttributeData::ScalarReal(rhs)) => {
+                assert_eq!(lhs, rhs)
+            }
+            (AttributeData::Vector3Real(lhs), AttributeData::Vector3Real(rhs)) => {
+                assert_eq!(lhs, rhs)
+            }
+            (
+                AttributeData::VectorReal {
+                    num_components: lhs_components,
+                    values: lhs,
+                },
+                AttributeData::VectorReal {
+                    num_components: rhs_components,
+                    values: rhs,
+                },
+            ) => {
+                assert_eq!(lhs_components, rhs_components);
+                assert_eq!(lhs, rhs);
+            }
+            _ => panic!("attribute \"{}\" changed its type", lhs.name),
+        }
+    }
+}
+
+fn assert_mesh_with_data_eq(
+    lhs: &MeshWithData<f32, TriMesh3d<f32>>,
+    rhs: &MeshWithData<f32, TriMesh3d<f32>>,
+) {
+    assert_eq!(lhs.mesh.vertices, rhs.mesh.vertices);
+    assert_eq!(lhs.mesh.triangles, rhs.mesh.triangles);
+    assert_attributes_eq(&lhs.point_attributes, &rhs.point_attributes);
+    assert_attributes_eq(&lhs.cell_attributes, &rhs.cell_attributes);
+}
+
+#[test]
+fn test_serde_bincode_round_trip() {
+    let mesh = tetrahedron_with_data();
+
+    let bytes = bincode::serialize(&mesh).unwrap();
+    let deserialized: MeshWithData<f32, TriMesh3d<f32>> = bincode::deserialize(&bytes).unwrap();
+    assert_mesh_with_data_eq(&mesh, &deserialized);
+
+    // The plain mesh without attributes
+    let bytes = bincode::serialize(&mesh.mesh).unwrap();
+    let deserialized: TriMesh3d<f32> = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(deserialized.vertices, mesh.mesh.vertices);
+    assert_eq!(deserialized.triangles, mesh.mesh.triangles);
+
+    // Truncated data is rejected
+    let bytes = bincode::serialize(&mesh).unwrap();
+    assert!(
+        bincode::deserialize::<MeshWithData<f32, TriMesh3d<f32>>>(&bytes[..bytes.len() / 2])
+            .is_err()
+    );
+}
+
+#[test]
+fn test_serde_json_round_trip() {
+    let mesh = tetrahedron_with_data();
+
+    let json = serde_json::to_string(&mesh).unwrap();
+    let deserialized: MeshWithData<f32, TriMesh3d<f32>> = serde_json::from_str(&json).unwrap();
+    assert_mesh_with_data_eq(&mesh, &deserialized);
+
+    // The attributes are stored with their names and data variants
+    let value = serde_json::to_value(&mesh).unwrap();
+    assert_eq!(value["point_attributes"][1]["name"], "normals");
+    assert!(value["point_attributes"][1]["data"]["Vector3Real"].is_array());
+    assert_eq!(
+        value["cell_attributes"][0]["data"]["VectorReal"]["num_components"],
+        2
+    );
+    assert_eq!(value["mesh"]["triangles"][2], serde_json::json!([1, 2, 3]));
+
+    // Double precision meshes can be read back from the same representation
+    let deserialized: MeshWithData<f64, TriMesh3d<f64>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized.mesh.vertices.len(), 4);
+    assert_eq!(deserialized.point_attributes.len(), 3);
+}
@@ -0,0 +1,299 @@
+use super::default_parameters;
+use nalgebra::Vector3;
+use splashsurf_lib::{
+    reconstruct_surface, AxisAlignedBoundingBox3d, ParameterError, Parameters,
+    ParticleDensityComputationStrategy, ReconstructionError, SpatialDecompositionParameters,
+    SplashDetection, SplashOutput, SubdivisionCriterion,
+};
+
+#[derive(Clone)]
+enum Strategy {
+    Global,
+    Octree,
+    OctreeStitching,
+}
+
+/// Generates a block of particles on a regular lattice with the given spacing followed by a few isolated particles
+fn block_with_splash_particles(particle_radius: f64, n: usize) -> (Vec<Vector3<f64>>, Vec<usize>) {
+    let spacing = 2.0 * particle_radius;
+    let mut particles = Vec::with_capacity(n * n * n + 2);
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                particles.push(Vector3::new(i as f64, j as f64, k as f64) * spacing);
+            }
+        }
+    }
+
+    let splash_particles = vec![particles.len(), particles.len() + 1];
+    particles.push(Vector3::new(1.0, 1.0, 1.0));
+    particles.push(Vector3::new(1.0, -0.5, 0.25));
+
+    (particles, splash_particles)
+}
+
+/// Generates a sparse spray of particle pairs and isolated particles on a coarse lattice, returns the indices of the isolated particles
+fn sparse_spray_cloud(particle_radius: f64, n: usize) -> (Vec<Vector3<f64>>, Vec<usize>) {
+    let spacing = 10.0 * particle_radius;
+    let mut particles = Vec::new();
+    let mut isolated_particles = Vec::new();
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                let center = Vector3::new(i as f64, j as f64, k as f64) * spacing;
+                if (i + j + k) % 2 == 0 {
+                    isolated_particles.push(particles.len());
+                    particles.push(center);
+                } else {
+                    particles.push(center);
+                    particles.push(center + Vector3::new(1.5 * particle_radius, 0.0, 0.0));
+                }
+            }
+        }
+    }
+
+    (particles, isolated_particles)
+}
+
+fn params(
+    particle_radius: f64,
+    splash_detection: SplashDetection<f64>,
+    strategy: Strategy,
+) -> Parameters<f64> {
+    let mut parameters = Parameters {
+        splash_detection: Some(splash_detection),
+        ..default_parameters(particle_radius)
+    };
+
+    let enable_stitching = match strategy {
+        Strategy::Global => return parameters,
+        Strategy::Octree => false,
+        Strategy::OctreeStitching => true,
+    };
+
+    parameters.spatial_decomposition = Some(SpatialDecompositionParameters {
+        subdivision_criterion: SubdivisionCriterion::MaxParticleCount(100),
+        ghost_particle_safety_factor: Some(1.0),
+        enable_stitching,
+        particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+    });
+
+    parameters
+}
+
+fn test_splash_detection(splash_detection: SplashDetection<f64>, strategy: Strategy) {
+    let particle_radius = 0.025;
+    let (particles, splash_particles) = block_with_splash_particles(particle_radius, 10);
+    let parameters = params(particle_radius, splash_detection, strategy);
+
+    // All vertices of the surface should be close to the block of particles
+    let mut block_aabb = AxisAlignedBoundingBox3d::from_points(&particles[..1000]);
+    block_aabb.grow_uniformly(parameters.compact_support_radius);
+
+    let reconstruction = reconstruct_surface::<i64, _>(particles.as_slice(), &parameters).unwrap();
+    assert_eq!(
+        reconstruction.splash_particle_indices(),
+        Some(&splash_particles)
+    );
+    assert!(!reconstruction.mesh().vertices.is_empty());
+    assert!(reconstruction
+        .mesh()
+        .vertices
+        .iter()
+        .all(|v| block_aabb.contains_point(v)));
+
+    // Without splash detection the isolated particles should result in additional surface fragments
+    let parameters = Parameters {
+        splash_detection: None,
+        ..parameters
+    };
+    let reconstruction = reconstruct_surface::<i64, _>(particles.as_slice(), &parameters).unwrap();
+    assert_eq!(reconstruction.splash_particle_indices(), None);
+    assert!(reconstruction
+        .mesh()
+        .vertices
+        .iter()
+        .any(|v| !block_aabb.contains_point(v)));
+}
+
+fn test_splash_detection_modes_agree(strategy: Strategy) {
+    let particle_radius = 0.025;
+    let (particles, isolated_particles) = sparse_spray_cloud(particle_radius, 5);
+
+    let mut splash_particles = Vec::new();
+    for splash_detection in [
+        SplashDetection::NeighborCount(4.0 * particle_radius),
+        SplashDetection::DensityThreshold(1.01),
+    ] {
+        let parameters = params(particle_radius, splash_detection, strategy.clone());
+        let reconstruction =
+            reconstruct_surface::<i64, _>(particles.as_slice(), &parameters).unwrap();
+        splash_particles.push(reconstruction.splash_particle_indices().unwrap().clone());
+    }
+
+    assert_eq!(splash_particles[0], isolated_particles);
+    assert_eq!(splash_particles[1], isolated_particles);
+}
+
+#[test]
+fn test_splash_detection_global() {
+    test_splash_detection(
+        SplashDetection::NeighborCount(3.0 * 0.025),
+        Strategy::Global,
+    );
+    test_splash_detection(SplashDetection::DensityThreshold(1.01), Strategy::Global);
+}
+
+#[test]
+fn test_splash_detection_octree() {
+    test_splash_detection(
+        SplashDetection::NeighborCount(3.0 * 0.025),
+        Strategy::Octree,
+    );
+    test_splash_detection(SplashDetection::DensityThreshold(1.01), Strategy::Octree);
+}
+
+#[test]
+fn test_splash_detection_octree_stitching() {
+    test_splash_detection(
+        SplashDetection::NeighborCount(3.0 * 0.025),
+        Strategy::OctreeStitching,
+    );
+    test_splash_detection(
+        SplashDetection::DensityThreshold(1.01),
+        Strategy::OctreeStitching,
+    );
+}
+
+#[test]
+fn test_density_threshold_requires_global_densities() {
+    let particle_radius = 0.025;
+    let (particles, _) = block_with_splash_particles(particle_radius, 10);
+    let mut parameters = params(
+        particle_radius,
+        SplashDetection::DensityThreshold(1.01),
+        Strategy::OctreeStitching,
+    );
+    if let Some(decomposition) = parameters.spatial_decomposition.as_mut() {
+        decomposition.particle_density_computation =
+            ParticleDensityComputationStrategy::IndependentSubdomains;
+    }
+
+    assert!(matches!(
+        reconstruct_surface::<i64, _>(particles.as_slice(), &parameters),
+        Err(ReconstructionError::InvalidParameters(
+            ParameterError::IncompatibleOptions(..)
+        ))
+    ));
+}
+
+#[test]
+fn test_splash_detection_modes_agree_global() {
+    test_splash_detection_modes_agree(Strategy::Global);
+}
+
+#[test]
+fn test_splash_detection_modes_agree_octree() {
+    test_splash_detection_modes_agree(Strategy::Octree);
+}
+
+fn test_splash_spheres(strategy: Strategy) {
+    let particle_radius = 0.025;
+    let (particles, splash_particles) = block_with_splash_particles(particle_radius, 10);
+    let parameters = params(
+        particle_radius,
+        SplashDetection::NeighborCount(3.0 * particle_radius),
+        strategy,
+    );
+
+    let surface = reconstruct_surface::<i64, _>(particles.as_slice(), &parameters).unwrap();
+    assert!(surface.splash_mesh().is_none());
+    assert!(surface.splash_vertex_particle_indices().is_none());
+
+    let sphere_subdivisions = 1;
+    let sphere_vertex_count = 42;
+    let sphere_triangle_count = 80;
+
+    // Spheres in a separate mesh, one per splash particle
+    let separate = reconstruct_surface::<i64, _>(
+        particles.as_slice(),
+        &Parameters {
+            splash_particle_output: SplashOutput::SeparateMesh {
+                sphere_subdivisions,
+            },
+            ..parameters.clone()
+        },
+    )
+    .unwrap();
+
+    let splash_mesh = separate.splash_mesh().unwrap();
+    let vertex_particles = separate.splash_vertex_particle_indices().unwrap();
+    assert_eq!(
+        splash_mesh.vertices.len(),
+        splash_particles.len() * sphere_vertex_count
+    );
+    assert_eq!(
+        splash_mesh.triangles.len(),
+        splash_particles.len() * sphere_triangle_count
+    );
+    assert_eq!(vertex_particles.len(), splash_mesh.vertices.len());
+    for (v, &particle_idx) in splash_mesh.vertices.iter().zip(vertex_particles.iter()) {
+        assert!(splash_particles.contains(&particle_idx));
+        assert!(((v - particles[particle_idx]).norm() - particle_radius).abs() < 1e-12);
+    }
+    assert_eq!(
+        separate.mesh().vertices.len(),
+        surface.mesh().vertices.len()
+    );
+
+    // Spheres appended to the surface mesh
+    let merged = reconstruct_surface::<i64, _>(
+        particles.as_slice(),
+        &Parameters {
+            splash_particle_output: SplashOutput::MergedSpheres {
+                sphere_subdivisions,
+            },
+            ..parameters.clone()
+        },
+    )
+    .unwrap();
+
+    assert!(merged.splash_mesh().is_none());
+    assert_eq!(
+        merged.splash_vertex_particle_indices().unwrap().len(),
+        splash_particles.len() * sphere_vertex_count
+    );
+    assert_eq!(
+        merged.mesh().vertices.len(),
+        surface.mesh().vertices.len() + splash_particles.len() * sphere_vertex_count
+    );
+    assert_eq!(
+        merged.mesh().triangles.len(),
+        surface.mesh().triangles.len() + splash_particles.len() * sphere_triangle_count
+    );
+
+    // No spheres without splash detection
+    let no_detection = reconstruct_surface::<i64, _>(
+        particles.as_slice(),
+        &Parameters {
+            splash_detection: None,
+            splash_particle_output: SplashOutput::MergedSpheres {
+                sphere_subdivisions,
+            },
+            ..parameters
+        },
+    )
+    .unwrap();
+    assert!(no_detection.splash_mesh().is_none());
+    assert!(no_detection.splash_vertex_particle_indices().is_none());
+}
+
+#[test]
+fn test_splash_spheres_global() {
+    test_splash_spheres(Strategy::Global);
+}
+
+#[test]
+fn test_splash_spheres_octree_stitching() {
+    test_splash_spheres(Strategy::OctreeStitching);
+}
@@ -0,0 +1,121 @@
+use super::{default_parameters, sphere_particles};
+use nalgebra::Vector3;
+use splashsurf_lib::generic_tree::TreeNode;
+use splashsurf_lib::mesh::TriMesh3d;
+use splashsurf_lib::{
+    grid_for_reconstruction, reconstruct_surface, stages, Parameters,
+    ParticleDensityComputationStrategy, ReconstructionWorkspace, SpatialDecompositionParameters,
+    SubdivisionCriterion,
+};
+
+const PARTICLE_RADIUS: f64 = 0.025;
+
+fn params(enable_multi_threading: bool) -> Parameters<f64> {
+    Parameters {
+        enable_multi_threading,
+        ..default_parameters(PARTICLE_RADIUS)
+    }
+}
+
+/// Reconstructs the surface by composing the individual stages
+fn reconstruct_staged(
+    particles: &[Vector3<f64>],
+    parameters: &Parameters<f64>,
+    workspace: &mut ReconstructionWorkspace<i64, f64>,
+) -> TriMesh3d<f64> {
+    let grid = grid_for_reconstruction::<i64, f64>(
+        particles,
+        parameters.particle_radius,
+        parameters.compact_support_radius,
+        parameters.cube_size,
+        parameters.domain_aabb.as_ref(),
+        parameters.enable_multi_threading,
+    )
+    .unwrap();
+
+    let particle_densities =
+        stages::compute_particle_densities(&grid, particles, parameters, workspace).unwrap();
+    assert_eq!(particle_densities.len(), particles.len());
+    let density_map = stages::generate_density_map(
+        &grid,
+        particles,
+        &particle_densities,
+        None,
+        parameters,
+        workspace,
+    )
+    .unwrap();
+
+    let mut mesh = TriMesh3d::default();
+    stages::triangulate_density_map(&grid, &density_map, parameters, workspace, &mut mesh).unwrap();
+    workspace.recycle_density_map(density_map);
+    mesh
+}
+
+/// Returns the vertices of the mesh in lexicographical order
+fn sorted_vertices(mesh: &TriMesh3d<f64>) -> Vec<Vector3<f64>> {
+    let mut vertices = mesh.vertices.clone();
+    vertices.sort_by(|a, b| a.as_slice().partial_cmp(b.as_slice()).unwrap());
+    vertices
+}
+
+#[test]
+fn test_stages_global() {
+    let frames = [
+        sphere_particles(2.0 * PARTICLE_RADIUS, 0.3, Vector3::zeros()),
+        sphere_particles(2.0 * PARTICLE_RADIUS, 0.25, Vector3::new(0.1, 0.0, -0.2)),
+    ];
+
+    for enable_multi_threading in [false, true] {
+        let parameters = params(enable_multi_threading);
+        // The workspace is reused for all frames
+        let mut workspace = ReconstructionWorkspace::default();
+        for particles in &frames {
+            let mesh = reconstruct_staged(particles, &parameters, &mut workspace);
+            let reconstruction = reconstruct_surface::<i64, f64>(particles, &parameters).unwrap();
+            assert!(!mesh.triangles.is_empty());
+
+            // The order of the vertices depends on the capacity of the reused density map
+            assert_eq!(mesh.triangles.len(), reconstruction.mesh().triangles.len());
+            assert_eq!(
+                sorted_vertices(&mesh),
+                sorted_vertices(reconstruction.mesh())
+            );
+        }
+    }
+}
+
+#[test]
+fn test_stages_octree() {
+    let particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.3, Vector3::zeros());
+    let mesh = reconstruct_staged(&particles, &params(false), &mut Default::default());
+
+    // The octree reconstruction with stitching results in the same surface as the global reconstruction
+    let parameters = Parameters {
+        spatial_decomposition: Some(SpatialDecompositionParameters {
+            subdivision_criterion: SubdivisionCriterion::MaxParticleCount(500),
+            ghost_particle_safety_factor: None,
+            enable_stitching: true,
+            particle_density_computation: ParticleDensityComputationStrategy::Global,
+        }),
+        ..params(true)
+    };
+    let reconstruction = reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+    assert!(reconstruction.octree().unwrap().root().children().len() > 1);
+
+    assert_eq!(mesh.triangles.len(), reconstruction.mesh().triangles.len());
+    // The density values differ by round-off errors due to the summation order of the subdomains
+    let rounded_vertices = |mesh: &TriMesh3d<f64>| {
+        let mut vertices = mesh
+            .vertices
+            .iter()
+            .map(|v| (v / (1e-6 * PARTICLE_RADIUS)).map(|c| c.round() as i64))
+            .collect::<Vec<_>>();
+        vertices.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+        vertices
+    };
+    assert_eq!(
+        rounded_vertices(&mesh),
+        rounded_vertices(reconstruction.mesh())
+    );
+}
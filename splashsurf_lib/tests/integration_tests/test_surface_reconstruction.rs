@@ -0,0 +1,515 @@
+use super::default_parameters;
+use nalgebra::Vector3;
+use splashsurf_lib::generic_tree::TreeMetrics;
+use splashsurf_lib::mesh::TriMesh3d;
+use splashsurf_lib::parameter_suggestion::estimate_reconstruction;
+use splashsurf_lib::{
+    reconstruct_surface, reconstruct_surface_from_density_map, reconstruct_surface_inplace,
+    AxisAlignedBoundingBox3d, DensityMapSnapshot, Parameters, ParticleDensityComputationStrategy,
+    SpatialDecompositionParameters, SubdivisionCriterion, SurfaceReconstruction,
+};
+use std::sync::Arc;
+
+/// Block of particles on a lattice with a spacing of twice the particle radius, shifted by the offset
+fn particle_block(particle_radius: f64, n: usize, offset: Vector3<f64>) -> Vec<Vector3<f64>> {
+    super::particle_block(particle_radius, n)
+        .into_iter()
+        .map(|x| x + offset)
+        .collect()
+}
+
+fn params(particle_radius: f64, octree: bool) -> Parameters<f64> {
+    Parameters {
+        spatial_decomposition: octree.then(|| SpatialDecompositionParameters {
+            subdivision_criterion: SubdivisionCriterion::MaxParticleCount(200),
+            ghost_particle_safety_factor: None,
+            enable_stitching: true,
+            particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+        }),
+        ..default_parameters(particle_radius)
+    }
+}
+
+/// Returns the vertices of the mesh in lexicographical order
+fn sorted_vertices(mesh: &TriMesh3d<f64>) -> Vec<[f64; 3]> {
+    let mut vertices: Vec<[f64; 3]> = mesh.vertices.iter().map(|v| [v.x, v.y, v.z]).collect();
+    vertices.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    vertices
+}
+
+#[test]
+fn test_take_mesh_and_reuse() {
+    let particle_radius = 0.025;
+
+    for &octree in [false, true].iter() {
+        let parameters = params(particle_radius, octree);
+        let frames = [
+            particle_block(particle_radius, 10, Vector3::zeros()),
+            particle_block(particle_radius, 12, Vector3::new(1.0, 0.5, 0.0)),
+            particle_block(particle_radius, 8, Vector3::new(-1.0, 0.0, 2.0)),
+        ];
+
+        let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+        for particles in frames.iter() {
+            reconstruct_surface_inplace(particles, &parameters, &mut reconstruction).unwrap();
+            let grid_points = *reconstruction.grid().points_per_dim();
+
+            let mesh = reconstruction.take_mesh();
+            assert!(reconstruction.mesh().vertices.is_empty());
+            assert!(reconstruction.mesh().triangles.is_empty());
+            // Everything except for the mesh is kept
+            assert_eq!(*reconstruction.grid().points_per_dim(), grid_points);
+            assert!(reconstruction.particle_densities().is_some());
+            assert_eq!(reconstruction.octree().is_some(), octree);
+
+            // The taken mesh is identical to the result of a fresh reconstruction (up to the order of vertices and triangles)
+            let expected = reconstruct_surface::<i64, f64>(particles, &parameters).unwrap();
+            assert_eq!(mesh.triangles.len(), expected.mesh().triangles.len());
+            assert_eq!(sorted_vertices(&mesh), sorted_vertices(expected.mesh()));
+        }
+    }
+}
+
+#[test]
+fn test_mesh_mut_and_into_parts() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 10, Vector3::zeros());
+    let parameters = params(particle_radius, false);
+
+    let mut reconstruction = reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+    let original_vertices = reconstruction.mesh().vertices.clone();
+    assert!(!original_vertices.is_empty());
+
+    let translation = Vector3::new(1.0, 2.0, 3.0);
+    for v in reconstruction.mesh_mut().vertices.iter_mut() {
+        *v += translation;
+    }
+    assert!(reconstruction
+        .mesh()
+        .vertices
+        .iter()
+        .zip(original_vertices.iter())
+        .all(|(v, v_orig)| *v == v_orig + translation));
+
+    let triangle_count = reconstruction.mesh().triangles.len();
+    let grid_points = *reconstruction.grid().points_per_dim();
+    let parts = reconstruction.into_parts();
+    assert_eq!(parts.mesh.triangles.len(), triangle_count);
+    assert_eq!(parts.mesh.vertices[0], original_vertices[0] + translation);
+    assert_eq!(*parts.grid.points_per_dim(), grid_points);
+    assert!(parts.octree.is_none());
+    assert_eq!(parts.particle_densities.unwrap().len(), particles.len());
+    assert!(parts.splash_particle_indices.is_none());
+    assert!(parts.splash_mesh.is_none());
+}
+
+#[test]
+fn test_preallocate_workspace() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 10, Vector3::zeros());
+    let parameters = params(particle_radius, false);
+
+    let estimate = estimate_reconstruction(
+        &AxisAlignedBoundingBox3d::from_points(&particles),
+        particles.len(),
+        particle_radius,
+        parameters.compact_support_radius,
+        parameters.cube_size,
+    );
+
+    let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+    assert_eq!(reconstruction.workspace_memory_usage().total_bytes(), 0);
+
+    reconstruction.preallocate(particles.len(), 2 * estimate.density_map_points as usize);
+    let preallocated = reconstruction.workspace_memory_usage();
+    assert_eq!(preallocated.local_workspaces.len(), 1);
+    let preallocated_local = preallocated.local_workspaces[0];
+    assert!(preallocated.global_densities >= particles.len() * std::mem::size_of::<f64>());
+    assert!(preallocated_local.particle_densities >= particles.len() * std::mem::size_of::<f64>());
+    assert!(preallocated_local.density_map > 0);
+
+    reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+    let used = reconstruction.workspace_memory_usage();
+    assert_eq!(used.local_workspaces.len(), 1);
+
+    // The density map was filled without reallocation
+    assert_eq!(
+        used.local_workspaces[0].density_map,
+        preallocated_local.density_map
+    );
+    // The preallocated density vector was handed out as result without reallocation
+    assert_eq!(
+        reconstruction.particle_densities().unwrap().capacity() * std::mem::size_of::<f64>(),
+        preallocated_local.particle_densities
+    );
+}
+
+#[test]
+fn test_clear_workspace() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 10, Vector3::zeros());
+
+    for &octree in [false, true].iter() {
+        let mut parameters = params(particle_radius, octree);
+        parameters.enable_multi_threading = true;
+
+        let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+        reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+        let expected_triangles = reconstruction.mesh().triangles.len();
+
+        // Workspaces of all threads (including rayon workers) are accounted for
+        let usage = reconstruction.workspace_memory_usage();
+        assert!(!usage.local_workspaces.is_empty());
+        assert!(usage.total_bytes() > 0);
+
+        reconstruction.clear_workspace();
+        let usage = reconstruction.workspace_memory_usage();
+        assert!(usage.local_workspaces.is_empty());
+        assert_eq!(usage.total_bytes(), 0);
+
+        // The results are kept and the reconstruction can be reused after clearing
+        assert_eq!(reconstruction.mesh().triangles.len(), expected_triangles);
+        reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+        assert_eq!(reconstruction.mesh().triangles.len(), expected_triangles);
+        assert!(reconstruction.workspace_memory_usage().total_bytes() > 0);
+    }
+}
+
+#[test]
+fn test_clone_and_snapshots() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 10, Vector3::zeros());
+    let parameters = params(particle_radius, false);
+
+    let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+    reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+    let expected_vertices = reconstruction.mesh().vertices.clone();
+    assert!(reconstruction.workspace_memory_usage().total_bytes() > 0);
+
+    // Snapshots share the mesh and the grid without copying them
+    let mesh_snapshot = reconstruction.snapshot_mesh();
+    let grid_snapshot = reconstruction.share_grid();
+    assert!(Arc::ptr_eq(&mesh_snapshot, &reconstruction.snapshot_mesh()));
+    assert_eq!(
+        mesh_snapshot.vertices.as_ptr(),
+        reconstruction.mesh().vertices.as_ptr()
+    );
+    assert!(std::ptr::eq(&*grid_snapshot, reconstruction.grid()));
+
+    // Clones share the mesh and the grid, but start with an empty workspace
+    let mut cloned = reconstruction.clone();
+    assert!(Arc::ptr_eq(&cloned.snapshot_mesh(), &mesh_snapshot));
+    assert!(Arc::ptr_eq(&cloned.share_grid(), &grid_snapshot));
+    assert_eq!(cloned.workspace_memory_usage().total_bytes(), 0);
+    assert_eq!(
+        cloned.particle_densities().unwrap(),
+        reconstruction.particle_densities().unwrap()
+    );
+
+    // Snapshots can be sent to other threads
+    let num_vertices = std::thread::spawn({
+        let mesh_snapshot = mesh_snapshot.clone();
+        move || mesh_snapshot.vertices.len()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(num_vertices, expected_vertices.len());
+
+    // Modifying the mesh copies it only while it is shared
+    reconstruction.mesh_mut().vertices[0].x += 1.0;
+    assert_eq!(mesh_snapshot.vertices, expected_vertices);
+    assert_ne!(
+        mesh_snapshot.vertices.as_ptr(),
+        reconstruction.mesh().vertices.as_ptr()
+    );
+    let vertices_ptr = reconstruction.mesh().vertices.as_ptr();
+    reconstruction.mesh_mut().vertices[0].x -= 1.0;
+    assert_eq!(reconstruction.mesh().vertices.as_ptr(), vertices_ptr);
+
+    // Subsequent reconstructions do not modify the snapshots
+    let current_snapshot = reconstruction.snapshot_mesh();
+    let current_vertices = current_snapshot.vertices.clone();
+    let offset = Vector3::new(0.1, 0.0, 0.0);
+    let translated_particles: Vec<_> = particles.iter().map(|p| p + offset).collect();
+    reconstruct_surface_inplace(&translated_particles, &parameters, &mut reconstruction).unwrap();
+    assert_eq!(current_snapshot.vertices, current_vertices);
+    assert_ne!(reconstruction.mesh().vertices, current_vertices);
+    assert_eq!(*grid_snapshot.aabb().min(), *cloned.grid().aabb().min());
+    assert_ne!(
+        *grid_snapshot.aabb().min(),
+        *reconstruction.grid().aabb().min()
+    );
+
+    // The shared mesh is copied when it is taken out of the clone
+    let taken = cloned.take_mesh();
+    assert_eq!(taken.vertices, expected_vertices);
+    assert_ne!(taken.vertices.as_ptr(), mesh_snapshot.vertices.as_ptr());
+    assert!(cloned.mesh().vertices.is_empty());
+}
+
+#[test]
+fn test_leaf_buffer_pool() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 16, Vector3::zeros());
+
+    for &enable_stitching in [false, true].iter() {
+        let mut expected_mesh = None;
+        for &enable_multi_threading in [false, true].iter() {
+            // Many more leaves than threads
+            let mut parameters = params(particle_radius, true);
+            parameters.enable_multi_threading = enable_multi_threading;
+            parameters.deterministic = true;
+            if let Some(decomposition) = parameters.spatial_decomposition.as_mut() {
+                decomposition.subdivision_criterion = SubdivisionCriterion::MaxParticleCount(50);
+                decomposition.enable_stitching = enable_stitching;
+            }
+
+            let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+            reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+            assert!(reconstruction.octree().unwrap().root().leaf_count() > 64);
+            let expected_mesh = expected_mesh.get_or_insert_with(|| reconstruction.mesh().clone());
+
+            // All buffers are back in the pool after the reconstruction
+            let usage = reconstruction.workspace_memory_usage().leaf_buffers;
+            assert!(usage.allocated_bytes > 0);
+            assert_eq!(usage.pooled_bytes, usage.allocated_bytes);
+            assert!(usage.peak_bytes_in_use > 0);
+            assert!(usage.peak_bytes_in_use <= usage.allocated_bytes);
+
+            // The pooled buffers are reused by subsequent reconstructions with identical output
+            for _ in 0..2 {
+                reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+                assert_eq!(reconstruction.mesh().vertices, expected_mesh.vertices);
+                assert_eq!(reconstruction.mesh().triangles, expected_mesh.triangles);
+                if !enable_multi_threading {
+                    // Without concurrent leaves, no new buffers are allocated
+                    assert_eq!(
+                        reconstruction
+                            .workspace_memory_usage()
+                            .leaf_buffers
+                            .allocated_bytes,
+                        usage.allocated_bytes
+                    );
+                }
+            }
+
+            reconstruction.clear_workspace();
+            let usage = reconstruction.workspace_memory_usage().leaf_buffers;
+            assert_eq!(usage.pooled_bytes, 0);
+            assert_eq!(usage.allocated_bytes, 0);
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_consolidate_workspace() {
+    use splashsurf_lib::ThreadPoolConfig;
+
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 16, Vector3::zeros());
+    let pool = ThreadPoolConfig::with_num_threads(4).build_local().unwrap();
+
+    let mut parameters = params(particle_radius, true);
+    parameters.enable_multi_threading = true;
+    parameters.deterministic = true;
+    if let Some(decomposition) = parameters.spatial_decomposition.as_mut() {
+        decomposition.subdivision_criterion = SubdivisionCriterion::MaxParticleCount(50);
+    }
+
+    let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+    pool.install(|| reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction))
+        .unwrap();
+    let expected_mesh = reconstruction.mesh().clone();
+
+    // Only the largest thread local workspace is retained
+    let usage = reconstruction.workspace_memory_usage();
+    let local_bytes = usage
+        .local_workspaces
+        .iter()
+        .map(|local| local.total_bytes())
+        .collect::<Vec<_>>();
+    let largest_local_bytes = *local_bytes.iter().max().unwrap();
+    reconstruction.consolidate_workspace();
+    let consolidated = reconstruction.workspace_memory_usage();
+    assert!(consolidated.local_workspaces.is_empty());
+    assert_eq!(
+        consolidated.consolidated_workspace.unwrap().total_bytes(),
+        largest_local_bytes
+    );
+    assert_eq!(
+        consolidated.total_bytes(),
+        usage.total_bytes() - local_bytes.iter().sum::<usize>() + largest_local_bytes
+    );
+
+    // Subsequent frames give the same results and only retain a single workspace
+    reconstruction.set_consolidate_workspace(true);
+    for _ in 0..3 {
+        pool.install(|| reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction))
+            .unwrap();
+        assert_eq!(reconstruction.mesh().vertices, expected_mesh.vertices);
+        assert_eq!(reconstruction.mesh().triangles, expected_mesh.triangles);
+
+        let usage = reconstruction.workspace_memory_usage();
+        assert!(usage.local_workspaces.is_empty());
+        assert!(usage.consolidated_workspace.is_some());
+    }
+
+    // The retained workspace is taken over by the next reconstruction without reallocation
+    let parameters = params(particle_radius, false);
+    let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+    reconstruction.set_consolidate_workspace(true);
+    reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+    let retained = reconstruction
+        .workspace_memory_usage()
+        .consolidated_workspace
+        .unwrap();
+    assert!(retained.density_map > 0);
+    for _ in 0..2 {
+        reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+        let usage = reconstruction.workspace_memory_usage();
+        assert!(usage.local_workspaces.is_empty());
+        let reused = usage.consolidated_workspace.unwrap();
+        assert_eq!(reused.density_map, retained.density_map);
+        assert_eq!(reused.total_bytes(), retained.total_bytes());
+    }
+}
+
+/// Returns the vertices of the mesh rounded to the given precision in lexicographical order
+fn rounded_sorted_vertices(mesh: &TriMesh3d<f64>, precision: f64) -> Vec<[i64; 3]> {
+    let mut vertices: Vec<[i64; 3]> = mesh
+        .vertices
+        .iter()
+        .map(|v| v.map(|x| (x / precision).round() as i64).into())
+        .collect();
+    vertices.sort_unstable();
+    vertices
+}
+
+#[test]
+fn test_multi_threading_matches_sequential() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 14, Vector3::zeros());
+
+    for &octree in [false, true].iter() {
+        let sequential_parameters = params(particle_radius, octree);
+        let mut parallel_parameters = sequential_parameters.clone();
+        parallel_parameters.enable_multi_threading = true;
+
+        let sequential =
+            reconstruct_surface::<i64, f64>(&particles, &sequential_parameters).unwrap();
+        let parallel = reconstruct_surface::<i64, f64>(&particles, &parallel_parameters).unwrap();
+
+        // Without the `parallel` feature both reconstructions run sequentially, otherwise they only differ in the order of floating point operations
+        assert!(!sequential.mesh().triangles.is_empty());
+        assert_eq!(
+            sequential.mesh().triangles.len(),
+            parallel.mesh().triangles.len()
+        );
+        assert_eq!(
+            rounded_sorted_vertices(sequential.mesh(), 1e-9),
+            rounded_sorted_vertices(parallel.mesh(), 1e-9)
+        );
+    }
+}
+
+#[test]
+fn test_unsigned_index_types() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 10, Vector3::new(-0.3, -0.1, 0.2));
+
+    for &octree in [false, true].iter() {
+        let parameters = params(particle_radius, octree);
+        let expected = reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+        assert!(!expected.mesh().triangles.is_empty());
+
+        // All index computations are relative to the lower corner of the grid, so the results have to be identical
+        let meshes = [
+            reconstruct_surface::<u32, f64>(&particles, &parameters)
+                .unwrap()
+                .into_parts()
+                .mesh,
+            reconstruct_surface::<u64, f64>(&particles, &parameters)
+                .unwrap()
+                .into_parts()
+                .mesh,
+            reconstruct_surface::<usize, f64>(&particles, &parameters)
+                .unwrap()
+                .into_parts()
+                .mesh,
+        ];
+        for mesh in meshes.iter() {
+            assert_eq!(mesh.triangles, expected.mesh().triangles);
+            assert_eq!(mesh.vertices, expected.mesh().vertices);
+        }
+    }
+}
+
+#[test]
+fn test_keep_density_map() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 6, Vector3::zeros());
+
+    // The density map is only kept on request and never for decomposed reconstructions
+    for &(octree, keep) in [(false, false), (false, true), (true, true)].iter() {
+        let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+        reconstruction.set_keep_density_map(keep);
+        reconstruct_surface_inplace(
+            &particles,
+            &params(particle_radius, octree),
+            &mut reconstruction,
+        )
+        .unwrap();
+
+        let expect_map = keep && !octree;
+        assert_eq!(reconstruction.density_map().is_some(), expect_map);
+        assert_eq!(reconstruction.density_map_snapshot().is_some(), expect_map);
+    }
+}
+
+#[test]
+fn test_reconstruct_from_density_map_snapshot() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 6, Vector3::new(0.1, -0.2, 0.3));
+    let parameters = params(particle_radius, false);
+
+    let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+    reconstruction.set_keep_density_map(true);
+    reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+    let snapshot = reconstruction.density_map_snapshot().unwrap();
+
+    // Round trip through the binary format
+    let mut buffer = Vec::new();
+    snapshot.write(&mut buffer).unwrap();
+    let loaded = DensityMapSnapshot::<i64, f64>::read(buffer.as_slice()).unwrap();
+    assert_eq!(loaded.grid, snapshot.grid);
+    let sorted_map = |snapshot: &DensityMapSnapshot<i64, f64>| {
+        let mut values = snapshot.density_map.to_vec();
+        values.sort_by_key(|&(i, _)| i);
+        values
+    };
+    assert_eq!(sorted_map(&loaded), sorted_map(&snapshot));
+    assert_eq!(loaded.particle_densities, snapshot.particle_densities);
+    assert_eq!(
+        loaded.splash_particle_indices,
+        snapshot.splash_particle_indices
+    );
+
+    let mut from_snapshot = SurfaceReconstruction::<i64, f64>::default();
+    reconstruct_surface_from_density_map(&particles, &parameters, loaded, &mut from_snapshot)
+        .unwrap();
+    assert_eq!(
+        sorted_vertices(from_snapshot.mesh()),
+        sorted_vertices(reconstruction.mesh())
+    );
+    assert_eq!(
+        from_snapshot.mesh().triangles.len(),
+        reconstruction.mesh().triangles.len()
+    );
+
+    // Snapshots with other index or float types and corrupted data are rejected
+    assert!(DensityMapSnapshot::<i32, f64>::read(buffer.as_slice()).is_err());
+    assert!(DensityMapSnapshot::<i64, f32>::read(buffer.as_slice()).is_err());
+    assert!(DensityMapSnapshot::<i64, f64>::read(&buffer[..buffer.len() / 2]).is_err());
+    assert!(DensityMapSnapshot::<i64, f64>::read(&b"not a snapshot"[..]).is_err());
+}
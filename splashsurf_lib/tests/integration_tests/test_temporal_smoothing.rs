@@ -0,0 +1,157 @@
+use super::default_parameters;
+use nalgebra::Vector3;
+use splashsurf_lib::{
+    reconstruct_surface, reconstruct_surface_inplace, AxisAlignedBoundingBox3d, ParameterError,
+    Parameters, ReconstructionError, SurfaceReconstruction,
+};
+
+const PARTICLE_RADIUS: f64 = 0.025;
+
+/// Generates a block of particles on a lattice with a small random offset per particle using a simple linear congruential generator
+fn noisy_block(n: usize, noise: f64, seed: u64) -> Vec<Vector3<f64>> {
+    let mut state = seed;
+    let mut next_random = move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+    };
+
+    let spacing = 2.0 * PARTICLE_RADIUS;
+    let mut particles = Vec::new();
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                let lattice_point = Vector3::new(i as f64, j as f64, k as f64);
+                let noise = Vector3::new(next_random(), next_random(), next_random()) * noise;
+                particles.push((lattice_point + noise) * spacing);
+            }
+        }
+    }
+    particles
+}
+
+fn params(temporal_density_smoothing: Option<f64>) -> Parameters<f64> {
+    Parameters {
+        cube_size: 0.6 * PARTICLE_RADIUS,
+        enable_multi_threading: true,
+        temporal_density_smoothing,
+        ..default_parameters(PARTICLE_RADIUS)
+    }
+}
+
+/// Returns the sum of the absolute changes of the number of triangles between subsequent frames
+fn triangle_count_fluctuation(frames: &[Vec<Vector3<f64>>], parameters: &Parameters<f64>) -> usize {
+    let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+    let triangle_counts = frames
+        .iter()
+        .map(|particles| {
+            reconstruct_surface_inplace(particles, parameters, &mut reconstruction).unwrap();
+            reconstruction.mesh().triangles.len()
+        })
+        .collect::<Vec<_>>();
+    triangle_counts
+        .windows(2)
+        .map(|counts| counts[0].abs_diff(counts[1]))
+        .sum()
+}
+
+#[test]
+fn test_temporal_smoothing_reduces_flicker() {
+    // A static block where the particles jitter slightly around their lattice positions in every frame
+    let frames = (0..10)
+        .map(|frame| noisy_block(10, 0.05, frame))
+        .collect::<Vec<_>>();
+
+    // The same background grid for all frames
+    let domain_aabb = Some(AxisAlignedBoundingBox3d::new(
+        Vector3::repeat(-0.2),
+        Vector3::repeat(0.7),
+    ));
+    let unfiltered = Parameters {
+        domain_aabb: domain_aabb.clone(),
+        ..params(None)
+    };
+    let filtered = Parameters {
+        domain_aabb,
+        ..params(Some(0.2))
+    };
+
+    let unfiltered_fluctuation = triangle_count_fluctuation(&frames, &unfiltered);
+    let filtered_fluctuation = triangle_count_fluctuation(&frames, &filtered);
+    assert!(unfiltered_fluctuation > 0);
+    assert!(
+        filtered_fluctuation < unfiltered_fluctuation / 2,
+        "triangle count fluctuation with filter: {}, without filter: {}",
+        filtered_fluctuation,
+        unfiltered_fluctuation
+    );
+}
+
+#[test]
+fn test_temporal_smoothing_resampling() {
+    let particles = noisy_block(8, 0.3, 0);
+    let parameters = params(Some(0.5));
+
+    // Static particles on a background grid that is moved by a multiple of the cube size: the resampled density map
+    // of the previous frame is identical to the density map of the current frame
+    let domain_aabb = AxisAlignedBoundingBox3d::new(Vector3::repeat(-0.2), Vector3::repeat(0.6));
+    let offset = Vector3::new(3.0, -2.0, 1.0) * parameters.cube_size;
+    let moved_domain_aabb =
+        AxisAlignedBoundingBox3d::new(domain_aabb.min() + offset, domain_aabb.max() + offset);
+
+    let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+    let first_frame = Parameters {
+        domain_aabb: Some(domain_aabb),
+        ..parameters.clone()
+    };
+    reconstruct_surface_inplace(&particles, &first_frame, &mut reconstruction).unwrap();
+    let first_grid = reconstruction.grid().clone();
+    let second_frame = Parameters {
+        domain_aabb: Some(moved_domain_aabb),
+        ..parameters.clone()
+    };
+    reconstruct_surface_inplace(&particles, &second_frame, &mut reconstruction).unwrap();
+    assert_ne!(reconstruction.grid(), &first_grid);
+
+    let unfiltered = Parameters {
+        temporal_density_smoothing: None,
+        ..second_frame
+    };
+    let unfiltered = reconstruct_surface::<i64, f64>(&particles, &unfiltered).unwrap();
+    assert_eq!(
+        reconstruction.mesh().triangles.len(),
+        unfiltered.mesh().triangles.len()
+    );
+    assert!((reconstruction.mesh().area() - unfiltered.mesh().area()).abs() < 1e-9);
+
+    // A different cube size resets the smoothing instead of resampling
+    let coarse_parameters = Parameters {
+        cube_size: 0.75 * PARTICLE_RADIUS,
+        ..params(Some(0.5))
+    };
+    let coarse_unfiltered = Parameters {
+        temporal_density_smoothing: None,
+        ..coarse_parameters.clone()
+    };
+    reconstruct_surface_inplace(&particles, &coarse_parameters, &mut reconstruction).unwrap();
+    let unfiltered = reconstruct_surface::<i64, f64>(&particles, &coarse_unfiltered).unwrap();
+    assert_eq!(
+        reconstruction.mesh().triangles.len(),
+        unfiltered.mesh().triangles.len()
+    );
+    assert!((reconstruction.mesh().area() - unfiltered.mesh().area()).abs() < 1e-9);
+}
+
+#[test]
+fn test_temporal_smoothing_factor_validation() {
+    for alpha in [0.0, -0.5, 1.5, f64::NAN] {
+        let result = reconstruct_surface::<i64, f64>(&noisy_block(4, 0.0, 0), &params(Some(alpha)));
+        assert!(matches!(
+            result,
+            Err(ReconstructionError::InvalidParameters(
+                ParameterError::InvalidFraction("temporal density smoothing factor")
+            ))
+        ));
+    }
+}
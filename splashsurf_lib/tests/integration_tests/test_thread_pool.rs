@@ -0,0 +1,71 @@
+use splashsurf_lib::ThreadPoolConfig;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn test_thread_pool_config_default() {
+    let pool = ThreadPoolConfig::with_num_threads(3).build_local().unwrap();
+    assert_eq!(pool.current_num_threads(), 3);
+}
+
+#[test]
+fn test_thread_pool_config_names_and_stack_size() {
+    let config = ThreadPoolConfig {
+        num_threads: 2,
+        thread_name_prefix: Some("surface-worker".to_string()),
+        stack_size: Some(16 * 1024 * 1024),
+        ..Default::default()
+    };
+    let pool = config.build_local().unwrap();
+
+    let (name, index) = pool.install(|| {
+        (
+            std::thread::current().name().map(|name| name.to_string()),
+            pool.current_thread_index(),
+        )
+    });
+    let index = index.expect("closure should run on a worker thread of the pool");
+    assert_eq!(name, Some(format!("surface-worker-{}", index)));
+}
+
+#[test]
+fn test_thread_pool_config_handlers() {
+    let started = Arc::new(AtomicUsize::new(0));
+    let exited = Arc::new(AtomicUsize::new(0));
+
+    let config = ThreadPoolConfig {
+        num_threads: 4,
+        start_handler: Some({
+            let started = started.clone();
+            Arc::new(move |_| {
+                started.fetch_add(1, Ordering::SeqCst);
+            })
+        }),
+        exit_handler: Some({
+            let exited = exited.clone();
+            Arc::new(move |_| {
+                exited.fetch_add(1, Ordering::SeqCst);
+            })
+        }),
+        ..Default::default()
+    };
+    assert!(format!("{:?}", config).contains("start_handler: true"));
+
+    // Threads are started and terminated asynchronously
+    let wait_for = |counter: &AtomicUsize, expected: usize| {
+        for _ in 0..1000 {
+            if counter.load(Ordering::SeqCst) == expected {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), expected);
+    };
+
+    let pool = config.build_local().unwrap();
+    wait_for(&started, 4);
+    assert_eq!(exited.load(Ordering::SeqCst), 0);
+
+    drop(pool);
+    wait_for(&exited, 4);
+}
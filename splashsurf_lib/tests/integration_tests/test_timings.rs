@@ -0,0 +1,185 @@
+use super::{default_parameters, particle_block};
+use splashsurf_lib::timings::TimingsCollector;
+use splashsurf_lib::{
+    profile, reconstruct_surface_inplace, Parameters, ParticleDensityComputationStrategy,
+    SpatialDecompositionParameters, SubdivisionCriterion, SurfaceReconstruction,
+};
+use std::time::Duration;
+
+fn params(particle_radius: f64) -> Parameters<f64> {
+    Parameters {
+        cube_size: 0.5 * particle_radius,
+        ..default_parameters(particle_radius)
+    }
+}
+
+#[test]
+fn test_timings_global() {
+    let particles = particle_block(0.025, 15);
+    let parameters = params(0.025);
+
+    let collector = TimingsCollector::new();
+    let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+    reconstruction.set_timings_collector(Some(collector.clone()));
+    reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+
+    let timings = reconstruction.stats().timings.clone().unwrap();
+    let stage_names: Vec<_> = timings.stages.iter().map(|stage| stage.name).collect();
+    assert_eq!(
+        stage_names,
+        vec![
+            "check_particle_positions",
+            "compute minimum enclosing aabb",
+            "reconstruct_surface_global"
+        ]
+    );
+
+    let global = timings.stage("reconstruct_surface_global").unwrap();
+    assert_eq!(global.num_calls, 1);
+    let sub_stage_names: Vec<_> = global.sub_stages.iter().map(|stage| stage.name).collect();
+    assert!(sub_stage_names.contains(&"compute_particle_densities_and_neighbors"));
+    assert!(sub_stage_names.contains(&"triangulate_density_map_append"));
+    let sub_stages_duration: Duration = global.sub_stages.iter().map(|s| s.duration).sum();
+    assert!(sub_stages_duration <= global.duration);
+
+    // Only the validation of the parameters and logging are not covered by the stages
+    let stages_duration = timings.stages_duration();
+    assert!(stages_duration <= timings.total);
+    assert!(stages_duration.as_secs_f64() >= 0.9 * timings.total.as_secs_f64());
+
+    // The collector is shared with the statistics and reset for every reconstruction
+    assert_eq!(collector.timings().stages.len(), timings.stages.len());
+    reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+    let timings = reconstruction.stats().timings.as_ref().unwrap();
+    assert_eq!(
+        timings
+            .stage("reconstruct_surface_global")
+            .unwrap()
+            .num_calls,
+        1
+    );
+    assert!(timings.to_string().contains("reconstruct_surface_global"));
+
+    // Leaf timings are only recorded by the octree based reconstruction
+    assert!(reconstruction.stats().leaf_timings.is_none());
+
+    // Without a collector, no timings are recorded
+    reconstruction.set_timings_collector(None);
+    reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+    assert!(reconstruction.stats().timings.is_none());
+}
+
+#[test]
+fn test_timings_domain_decomposition() {
+    let particles = particle_block(0.025, 15);
+    let mut parameters = params(0.025);
+    parameters.spatial_decomposition = Some(SpatialDecompositionParameters {
+        subdivision_criterion: SubdivisionCriterion::MaxParticleCount(500),
+        ghost_particle_safety_factor: Some(1.0),
+        enable_stitching: true,
+        particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+    });
+
+    for enable_multi_threading in [false, true] {
+        parameters.enable_multi_threading = enable_multi_threading;
+
+        let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+        reconstruction.set_timings_collector(Some(TimingsCollector::new()));
+        reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+
+        let timings = reconstruction.stats().timings.as_ref().unwrap();
+        let stage = timings
+            .stage("reconstruct_surface_domain_decomposition")
+            .unwrap();
+        assert!(!stage.sub_stages.is_empty());
+        assert!(stage.sub_stages.iter().all(|s| s.sub_stages.is_empty()));
+
+        let stages_duration = timings.stages_duration();
+        assert!(stages_duration <= timings.total);
+        assert!(stages_duration.as_secs_f64() >= 0.9 * timings.total.as_secs_f64());
+    }
+}
+
+#[test]
+fn test_leaf_timings() {
+    let particles = particle_block(0.025, 15);
+    let mut parameters = params(0.025);
+
+    for enable_stitching in [false, true] {
+        parameters.enable_multi_threading = true;
+        parameters.spatial_decomposition = Some(SpatialDecompositionParameters {
+            subdivision_criterion: SubdivisionCriterion::MaxParticleCount(200),
+            ghost_particle_safety_factor: Some(1.0),
+            enable_stitching,
+            particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+        });
+
+        let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+        reconstruction.set_timings_collector(Some(TimingsCollector::new()));
+        reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+
+        // Every leaf with particles is recorded once
+        let leaf_timings = reconstruction.stats().leaf_timings.clone().unwrap();
+        let mut node_ids: Vec<_> = leaf_timings.leaves.iter().map(|l| l.node_id).collect();
+        node_ids.sort_unstable();
+        node_ids.dedup();
+        assert_eq!(node_ids.len(), leaf_timings.leaves.len());
+        assert!(leaf_timings.leaves.len() > 1);
+        assert!(leaf_timings
+            .leaves
+            .iter()
+            .all(|leaf| leaf.num_particles > 0));
+
+        let slowest = leaf_timings.slowest(5);
+        assert_eq!(slowest.len(), 5.min(leaf_timings.leaves.len()));
+        assert!(slowest.windows(2).all(|w| w[0].duration >= w[1].duration));
+        assert!(leaf_timings.percentile(0.95) <= slowest[0].duration);
+        assert!(leaf_timings.mean() <= slowest[0].duration);
+        assert!(leaf_timings.imbalance_ratio() >= 1.0);
+        assert!(leaf_timings.to_string().contains("imbalance ratio"));
+
+        // Leaf timings are only recorded if statistics are collected
+        reconstruction.set_timings_collector(None);
+        reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+        assert!(reconstruction.stats().leaf_timings.is_none());
+
+        let collector = TimingsCollector::new();
+        let _guard = collector.attach();
+        reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction).unwrap();
+        assert!(reconstruction.stats().leaf_timings.is_some());
+    }
+}
+
+#[test]
+fn test_timings_attach() {
+    let collector = TimingsCollector::new();
+
+    // Scopes are not recorded if no collector is attached
+    {
+        profile!("detached stage");
+    }
+
+    {
+        let _guard = collector.attach();
+        for _ in 0..3 {
+            profile!("outer stage");
+            {
+                profile!("inner stage");
+                {
+                    profile!("ignored stage");
+                }
+            }
+        }
+    }
+
+    let timings = collector.timings();
+    assert_eq!(timings.stages.len(), 1);
+    let stage = &timings.stages[0];
+    assert_eq!(stage.name, "outer stage");
+    assert_eq!(stage.num_calls, 3);
+    assert_eq!(stage.sub_stages.len(), 1);
+    assert_eq!(stage.sub_stages[0].name, "inner stage");
+    assert_eq!(stage.sub_stages[0].num_calls, 3);
+    assert!(stage.sub_stages[0].sub_stages.is_empty());
+    assert!(stage.duration <= timings.total);
+}
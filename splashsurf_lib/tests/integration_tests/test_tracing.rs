@@ -0,0 +1,201 @@
+use super::{default_parameters, particle_block};
+use parking_lot::{const_mutex, Mutex};
+use splashsurf_lib::tracing::field::{Field, Visit};
+use splashsurf_lib::tracing::span::{Attributes, Id, Record};
+use splashsurf_lib::tracing::{Event, Metadata, Subscriber};
+use splashsurf_lib::{
+    reconstruct_surface, Parameters, ParticleDensityComputationStrategy,
+    SpatialDecompositionParameters, SubdivisionCriterion,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing_core::span::Current;
+
+/// Span recorded by the [`SpanCollector`]
+#[derive(Clone, Debug)]
+struct CollectedSpan {
+    metadata: &'static Metadata<'static>,
+    name: &'static str,
+    parent: Option<u64>,
+    fields: Vec<&'static str>,
+}
+
+/// Field visitor that only stores the names of the fields
+struct FieldNames(Vec<&'static str>);
+
+impl Visit for FieldNames {
+    fn record_debug(&mut self, field: &Field, _value: &dyn Debug) {
+        self.0.push(field.name());
+    }
+}
+
+thread_local! {
+    /// Stack of the entered spans of the current thread
+    static SPAN_STACK: RefCell<Vec<u64>> = RefCell::new(Vec::new());
+}
+
+/// Minimal subscriber that stores all spans with their parents
+#[derive(Default)]
+struct SpanCollector {
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, CollectedSpan>>,
+}
+
+impl Subscriber for SpanCollector {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let parent = if let Some(parent) = span.parent() {
+            Some(parent.into_u64())
+        } else if span.is_contextual() {
+            SPAN_STACK.with(|stack| stack.borrow().last().copied())
+        } else {
+            None
+        };
+
+        let mut fields = FieldNames(Vec::new());
+        span.record(&mut fields);
+
+        self.spans.lock().insert(
+            id,
+            CollectedSpan {
+                metadata: span.metadata(),
+                name: span.metadata().name(),
+                parent,
+                fields: fields.0,
+            },
+        );
+        Id::from_u64(id)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, span: &Id) {
+        SPAN_STACK.with(|stack| stack.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, _span: &Id) {
+        SPAN_STACK.with(|stack| stack.borrow_mut().pop());
+    }
+
+    fn current_span(&self) -> Current {
+        match SPAN_STACK.with(|stack| stack.borrow().last().copied()) {
+            Some(id) => Current::new(Id::from_u64(id), self.spans.lock()[&id].metadata),
+            None => Current::none(),
+        }
+    }
+}
+
+/// The span collector installed as global subscriber
+static COLLECTOR: Mutex<Option<&'static SpanCollector>> = const_mutex(None);
+
+/// Installs the span collector as global subscriber (required to collect spans of worker threads)
+fn collector() -> &'static SpanCollector {
+    *COLLECTOR.lock().get_or_insert_with(|| {
+        let collector: &'static SpanCollector = Box::leak(Box::default());
+        splashsurf_lib::tracing::subscriber::set_global_default(CollectorRef(collector))
+            .expect("failed to set global tracing subscriber");
+        collector
+    })
+}
+
+/// Forwards to a static [`SpanCollector`] to keep access to the collected spans after installing it
+struct CollectorRef(&'static SpanCollector);
+
+impl Subscriber for CollectorRef {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.0.enabled(metadata)
+    }
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.0.new_span(span)
+    }
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        self.0.record(span, values)
+    }
+    fn record_follows_from(&self, span: &Id, follows: &Id) {
+        self.0.record_follows_from(span, follows)
+    }
+    fn event(&self, event: &Event<'_>) {
+        self.0.event(event)
+    }
+    fn enter(&self, span: &Id) {
+        self.0.enter(span)
+    }
+    fn exit(&self, span: &Id) {
+        self.0.exit(span)
+    }
+    fn current_span(&self) -> Current {
+        self.0.current_span()
+    }
+}
+
+#[test]
+fn test_tracing_span_hierarchy() {
+    let collector = collector();
+
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 12);
+    let parameters = Parameters {
+        enable_multi_threading: true,
+        spatial_decomposition: Some(SpatialDecompositionParameters {
+            subdivision_criterion: SubdivisionCriterion::MaxParticleCount(200),
+            ghost_particle_safety_factor: None,
+            enable_stitching: false,
+            particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+        }),
+        ..default_parameters(particle_radius)
+    };
+
+    reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+
+    let spans = collector.spans.lock().clone();
+    let ancestors = |mut id: u64| {
+        let mut names = Vec::new();
+        while let Some(parent) = spans[&id].parent {
+            names.push(spans[&parent].name);
+            id = parent;
+        }
+        names
+    };
+
+    let root = spans
+        .values()
+        .find(|span| span.name == "reconstruct_surface")
+        .unwrap();
+    assert!(root.fields.contains(&"num_particles"));
+
+    // Spans of octree nodes visited by worker threads are attached to the span of the calling thread
+    let node_spans: Vec<_> = spans
+        .iter()
+        .filter(|(_, span)| span.name == "visit octree node for reconstruction")
+        .collect();
+    assert!(node_spans.len() > 1);
+    for (&id, span) in node_spans {
+        assert_eq!(span.fields, vec!["node_id", "num_particles"]);
+        let ancestors = ancestors(id);
+        assert_eq!(
+            ancestors,
+            vec![
+                "parallel subdomain surf. rec.",
+                "reconstruct_surface_domain_decomposition",
+                "reconstruct_surface"
+            ]
+        );
+    }
+
+    // Scopes of the profile macro on the calling thread are nested automatically
+    let (&check_id, _) = spans
+        .iter()
+        .find(|(_, span)| span.name == "check_particle_positions")
+        .unwrap();
+    assert_eq!(ancestors(check_id), vec!["reconstruct_surface"]);
+}
@@ -0,0 +1,191 @@
+use super::{default_parameters, sphere_particles};
+use nalgebra::Vector3;
+use splashsurf_lib::generic_tree::{TreeNode, VisitableTree};
+use splashsurf_lib::{
+    reconstruct_surface_inplace, MarchingCubesVariant, Parameters,
+    ParticleDensityComputationStrategy, SpatialDecompositionParameters, SplashDetection,
+    SplashOutput, SubdivisionCriterion, SurfaceReconstruction,
+};
+
+const PARTICLE_RADIUS: f64 = 0.025;
+
+fn params(enable_stitching: Option<bool>) -> Parameters<f64> {
+    Parameters {
+        enable_multi_threading: true,
+        spatial_decomposition: enable_stitching.map(|enable_stitching| {
+            SpatialDecompositionParameters {
+                subdivision_criterion: SubdivisionCriterion::MaxParticleCount(500),
+                ghost_particle_safety_factor: None,
+                enable_stitching,
+                particle_density_computation: ParticleDensityComputationStrategy::Global,
+            }
+        }),
+        ..default_parameters(PARTICLE_RADIUS)
+    }
+}
+
+/// Asserts that all vertices of every triangle lie in the grid cell it was attributed to
+fn assert_triangles_in_cells(reconstruction: &SurfaceReconstruction<i64, f64>) {
+    let grid = reconstruction.grid();
+    let mesh = reconstruction.mesh();
+    let origins = reconstruction.triangle_origins().unwrap();
+    assert_eq!(origins.cells.len(), mesh.triangles.len());
+
+    let eps = 1e-6 * grid.cell_size();
+    for (triangle, &cell) in mesh.triangles.iter().zip(origins.cells.iter()) {
+        let cell = grid.try_unflatten_cell_index(cell).unwrap();
+        let aabb = grid.cell_aabb(&cell);
+        for &v in triangle {
+            let vertex = mesh.vertices[v];
+            assert!(
+                (0..3)
+                    .all(|i| vertex[i] >= aabb.min()[i] - eps && vertex[i] <= aabb.max()[i] + eps),
+                "vertex {:?} is not part of the cell {:?}",
+                vertex,
+                cell.index()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_triangle_origins_disabled() {
+    let particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::zeros());
+    let mut reconstruction = SurfaceReconstruction::default();
+    reconstruct_surface_inplace::<i64, f64>(&particles, &params(None), &mut reconstruction)
+        .unwrap();
+    assert!(!reconstruction.mesh().triangles.is_empty());
+    assert!(reconstruction.triangle_origins().is_none());
+}
+
+#[test]
+fn test_triangle_origins_global() {
+    let particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::zeros());
+
+    for variant in [
+        MarchingCubesVariant::Classic,
+        MarchingCubesVariant::Mc33,
+        MarchingCubesVariant::SurfaceNets,
+    ] {
+        let mut parameters = params(None);
+        parameters.triangulation_scheme = variant;
+
+        let mut reconstruction = SurfaceReconstruction::default();
+        reconstruction.set_record_triangle_origins(true);
+        reconstruct_surface_inplace::<i64, f64>(&particles, &parameters, &mut reconstruction)
+            .unwrap();
+
+        let origins = reconstruction.triangle_origins().unwrap();
+        assert!(origins.nodes.is_empty());
+        if variant == MarchingCubesVariant::SurfaceNets {
+            // The quads of surface nets span the four cells around a grid edge
+            assert_eq!(origins.cells.len(), reconstruction.mesh().triangles.len());
+        } else {
+            assert_triangles_in_cells(&reconstruction);
+        }
+    }
+}
+
+#[test]
+fn test_triangle_origins_octree() {
+    let mut particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.3, Vector3::zeros());
+    particles.extend(
+        sphere_particles(2.0 * PARTICLE_RADIUS, 0.1, Vector3::zeros())
+            .iter()
+            .map(|x| x + Vector3::new(0.6, 0.0, 0.0)),
+    );
+
+    for enable_stitching in [false, true] {
+        let mut reconstruction = SurfaceReconstruction::default();
+        reconstruction.set_record_triangle_origins(true);
+        reconstruct_surface_inplace::<i64, f64>(
+            &particles,
+            &params(Some(enable_stitching)),
+            &mut reconstruction,
+        )
+        .unwrap();
+        assert_triangles_in_cells(&reconstruction);
+
+        let octree = reconstruction.octree().unwrap();
+        let mut leaves = Vec::new();
+        let mut inner_nodes = Vec::new();
+        octree.root().visit_bfs(|node| {
+            if node.children().is_empty() {
+                leaves.push(node.id());
+            } else {
+                inner_nodes.push(node.id());
+            }
+        });
+        assert!(leaves.len() > 1);
+
+        let origins = reconstruction.triangle_origins().unwrap();
+        assert_eq!(origins.nodes.len(), reconstruction.mesh().triangles.len());
+        assert!(origins.nodes.iter().any(|node| leaves.contains(node)));
+        if enable_stitching {
+            // Triangles between the leaves are created by stitching their parent nodes
+            assert!(origins.nodes.iter().any(|node| inner_nodes.contains(node)));
+            assert!(origins
+                .nodes
+                .iter()
+                .all(|node| leaves.contains(node) || inner_nodes.contains(node)));
+        } else {
+            assert!(origins.nodes.iter().all(|node| leaves.contains(node)));
+        }
+    }
+}
+
+#[test]
+fn test_triangle_origins_vertex_snapping() {
+    let mut particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.3, Vector3::zeros());
+    particles.extend(
+        sphere_particles(2.0 * PARTICLE_RADIUS, 0.1, Vector3::zeros())
+            .iter()
+            .map(|x| x + Vector3::new(0.6, 0.0, 0.0)),
+    );
+
+    // Snapped vertices move to a corner of the cells of their triangles, collapsed triangles lose their origin
+    for enable_stitching in [None, Some(true)] {
+        let mut parameters = params(enable_stitching);
+        parameters.vertex_snapping = Some(0.2);
+
+        let mut reconstruction = SurfaceReconstruction::default();
+        reconstruction.set_record_triangle_origins(true);
+        reconstruct_surface_inplace::<i64, f64>(&particles, &parameters, &mut reconstruction)
+            .unwrap();
+        assert_triangles_in_cells(&reconstruction);
+
+        let origins = reconstruction.triangle_origins().unwrap();
+        if enable_stitching.is_some() {
+            assert_eq!(origins.nodes.len(), reconstruction.mesh().triangles.len());
+        }
+    }
+}
+
+#[test]
+fn test_triangle_origins_merged_splash_spheres() {
+    let mut particles = sphere_particles(2.0 * PARTICLE_RADIUS, 0.2, Vector3::zeros());
+    particles.push(Vector3::new(1.0, 1.0, 1.0));
+
+    let mut parameters = params(None);
+    parameters.splash_detection = Some(SplashDetection::NeighborCount(4.0 * PARTICLE_RADIUS));
+    parameters.splash_particle_output = SplashOutput::MergedSpheres {
+        sphere_subdivisions: 1,
+    };
+
+    let mut reconstruction = SurfaceReconstruction::default();
+    reconstruction.set_record_triangle_origins(true);
+    reconstruct_surface_inplace::<i64, f64>(&particles, &parameters, &mut reconstruction).unwrap();
+
+    // The triangles of the sphere appended to the mesh have no origin in the grid
+    let origins = reconstruction.triangle_origins().unwrap();
+    assert_eq!(origins.cells.len(), reconstruction.mesh().triangles.len());
+    let unknown = origins
+        .cells
+        .iter()
+        .filter(|&&cell| cell == i64::MAX)
+        .count();
+    assert!(unknown > 0);
+    assert!(origins.cells[..origins.cells.len() - unknown]
+        .iter()
+        .all(|&cell| cell != i64::MAX));
+}
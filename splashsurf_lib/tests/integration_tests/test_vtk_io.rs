@@ -0,0 +1,316 @@
+use super::default_parameters;
+use nalgebra::Vector3;
+use splashsurf_lib::io::vtk_format::{
+    particles_from_vtk, particles_to_vtk, write_vtk, VtkFile, VtkIoError,
+};
+use splashsurf_lib::mesh::{AttributeData, MeshAttribute, MeshWithData, PointCloud3d, TriMesh3d};
+use splashsurf_lib::vtkio::model::{Attribute, ByteOrder, DataSet, Version, Vtk};
+use splashsurf_lib::{reconstruct_surface_with_attributes, AttributeInterpolation, AttributeSpecs};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Returns a path for a test file in a temporary directory that is unique for the test
+fn test_file(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("splashsurf_test_vtk_io_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+fn particles() -> Vec<Vector3<f64>> {
+    (0..100)
+        .map(|i| Vector3::new(i as f64, (i * i) as f64, -(i as f64)) * 0.01)
+        .collect()
+}
+
+#[test]
+fn test_vtk_round_trip() {
+    let file = test_file("round_trip.vtk");
+    particles_to_vtk(&particles(), &file).unwrap();
+    assert_eq!(particles_from_vtk::<f64, _>(&file).unwrap(), particles());
+}
+
+#[test]
+fn test_vtk_truncated_file() {
+    let complete_file = test_file("complete.vtk");
+    particles_to_vtk(&particles(), &complete_file).unwrap();
+
+    // Cut off the file in the middle of the binary point data like a file that is still being written
+    let content = fs::read(&complete_file).unwrap();
+    let mut len = content.len() / 2;
+    while content[len - 1] == b'\n' {
+        len -= 1;
+    }
+    let truncated_file = test_file("truncated.vtk");
+    fs::write(&truncated_file, &content[..len]).unwrap();
+
+    let err = particles_from_vtk::<f64, _>(&truncated_file).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            VtkIoError::Parse {
+                truncated: true,
+                ..
+            }
+        ),
+        "unexpected error: {:?}",
+        err
+    );
+    assert!(err.is_retryable());
+
+    // Missing files are I/O errors that may be resolved later
+    let err = particles_from_vtk::<f64, _>(test_file("missing.vtk")).unwrap_err();
+    assert!(
+        matches!(err, VtkIoError::Io(_)),
+        "unexpected error: {:?}",
+        err
+    );
+    assert!(err.is_retryable());
+}
+
+#[test]
+fn test_vtk_structured_points() {
+    let file = test_file("structured_points.vtk");
+    fs::write(
+        &file,
+        "# vtk DataFile Version 2.0\n\
+         structured points\n\
+         ASCII\n\
+         DATASET STRUCTURED_POINTS\n\
+         DIMENSIONS 2 2 2\n\
+         ORIGIN 0 0 0\n\
+         SPACING 1 1 1\n\
+         POINT_DATA 8\n\
+         SCALARS density float 1\n\
+         LOOKUP_TABLE default\n\
+         0 1 2 3 4 5 6 7\n",
+    )
+    .unwrap();
+
+    let err = particles_from_vtk::<f64, _>(&file).unwrap_err();
+    match &err {
+        VtkIoError::UnsupportedDataSet { found } => assert_eq!(found, "ImageData"),
+        err => panic!("unexpected error: {:?}", err),
+    }
+    assert!(!err.is_retryable());
+}
+
+#[test]
+fn test_vtk_missing_attribute() {
+    let file = test_file("no_attributes.vtk");
+    particles_to_vtk(&particles(), &file).unwrap();
+
+    let piece = VtkFile::load_file(&file)
+        .unwrap()
+        .into_pieces()
+        .into_iter()
+        .next()
+        .unwrap();
+    let err = piece
+        .load_point_attributes::<f64>(&["velocity".to_string()])
+        .unwrap_err();
+    match &err {
+        VtkIoError::AttributeMissing { name } => assert_eq!(name, "velocity"),
+        err => panic!("unexpected error: {:?}", err),
+    }
+    assert!(!err.is_retryable());
+}
+
+/// Loads the point attributes with the given names from the first piece of the VTK file
+fn load_point_attributes(file: &Path, names: &[String]) -> Vec<MeshAttribute<f64>> {
+    VtkFile::load_file(file)
+        .unwrap()
+        .into_pieces()
+        .into_iter()
+        .next()
+        .unwrap()
+        .load_point_attributes::<f64>(names)
+        .unwrap()
+}
+
+/// Asserts that the attribute has the expected type and all of its values are close to the expected value
+fn assert_attribute_values(attribute: &MeshAttribute<f64>, num_values: usize, expected: &[f64]) {
+    let (num_components, values) = match &attribute.data {
+        AttributeData::ScalarU64(values) => {
+            (1, values.iter().map(|&v| v as f64).collect::<Vec<_>>())
+        }
+        AttributeData::ScalarReal(values) => (1, values.clone()),
+        AttributeData::VectorReal {
+            num_components,
+            values,
+        } => (*num_components, values.clone()),
+        data => panic!("{}: unexpected type {:?}", attribute.name, data),
+    };
+    assert_eq!(num_components, expected.len(), "{}", attribute.name);
+    assert_eq!(
+        values.len(),
+        num_values * num_components,
+        "{}",
+        attribute.name
+    );
+    for value in values.chunks(num_components) {
+        for (v, e) in value.iter().zip(expected) {
+            assert!((v - e).abs() < 1e-6, "{}: {:?}", attribute.name, value);
+        }
+    }
+}
+
+#[test]
+fn test_vtk_attribute_types() {
+    let particle_radius = 0.025;
+    let particles = (0..1000)
+        .map(|i| {
+            Vector3::new((i % 10) as f64, ((i / 10) % 10) as f64, (i / 100) as f64)
+                * 2.0
+                * particle_radius
+        })
+        .collect::<Vec<_>>();
+    let n = particles.len();
+
+    // Attributes of all supported types with constant values
+    let mut piece = MeshWithData::new(PointCloud3d::new(particles.clone())).to_unstructured_grid();
+    piece.data.point.extend([
+        Attribute::scalars("temperature", 1).with_data(vec![300.0f32; n]),
+        Attribute::scalars("pressure", 1).with_data(vec![1e5f64; n]),
+        Attribute::scalars("phase", 1).with_data(vec![3i32; n]),
+        Attribute::scalars("id", 1).with_data(vec![42u64; n]),
+        Attribute::scalars("velocity_2d", 2).with_data([0.5f32, -1.5].repeat(n)),
+        Attribute::scalars("orientation", 4).with_data([0.0f64, 0.0, 0.0, 1.0].repeat(n)),
+    ]);
+    let input_file = test_file("attribute_types.vtk");
+    write_vtk(DataSet::inline(piece), &input_file, "particles").unwrap();
+
+    let expected: [(&str, &[f64]); 6] = [
+        ("temperature", &[300.0]),
+        ("pressure", &[1e5]),
+        ("phase", &[3.0]),
+        ("id", &[42.0]),
+        ("velocity_2d", &[0.5, -1.5]),
+        ("orientation", &[0.0, 0.0, 0.0, 1.0]),
+    ];
+    let names = expected
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect::<Vec<_>>();
+
+    let attributes = load_point_attributes(&input_file, &names);
+    for (attribute, (_, values)) in attributes.iter().zip(expected.iter()) {
+        assert_attribute_values(attribute, n, values);
+    }
+    assert!(matches!(attributes[2].data, AttributeData::ScalarU64(_)));
+    assert!(matches!(
+        attributes[5].data,
+        AttributeData::VectorReal { .. }
+    ));
+
+    // Constant values are reproduced by the interpolation to the surface and survive writing the surface mesh
+    let parameters = default_parameters(particle_radius);
+    // The integer attribute is transferred from the nearest particles
+    let interpolation = attributes
+        .iter()
+        .map(|attribute| AttributeInterpolation::default_for(&attribute.data))
+        .collect::<Vec<_>>();
+    let specs = AttributeSpecs {
+        particle_attributes: attributes.as_slice(),
+        particle_attribute_interpolation: interpolation.as_slice(),
+        ..AttributeSpecs::default()
+    };
+    let mesh =
+        reconstruct_surface_with_attributes::<i64, f64>(&particles, &parameters, &specs).unwrap();
+    let num_vertices = mesh.mesh.vertices.len();
+    assert!(num_vertices > 0);
+
+    let output_file = test_file("attribute_types_surface.vtk");
+    write_vtk(&mesh, &output_file, "surface").unwrap();
+    for (attribute, (_, values)) in load_point_attributes(&output_file, &names)
+        .iter()
+        .zip(expected.iter())
+    {
+        assert_attribute_values(attribute, num_vertices, values);
+    }
+}
+
+#[test]
+fn test_vtk_cell_attributes_round_trip() {
+    let sphere = TriMesh3d::<f64>::icosphere(&Vector3::zeros(), 1.0, 1);
+    let num_triangles = sphere.triangles.len();
+    let quality = (0..num_triangles)
+        .map(|t| t as f64 / num_triangles as f64)
+        .collect::<Vec<_>>();
+    let mesh = MeshWithData::new(sphere.clone())
+        .with_vertex_normals()
+        .with_cell_data(MeshAttribute::new(
+            "leaf_id",
+            (0..num_triangles as u64).map(|t| t / 4).collect::<Vec<_>>(),
+        ))
+        .with_cell_data(MeshAttribute::new_real_scalar("quality", quality.clone()));
+
+    let file = test_file("cell_attributes.vtk");
+    write_vtk(&mesh, &file, "surface").unwrap();
+
+    let piece = VtkFile::load_file(&file)
+        .unwrap()
+        .into_pieces()
+        .into_iter()
+        .next()
+        .unwrap();
+    assert_eq!(piece.point_attribute_names(), vec!["normals".to_string()]);
+    assert_eq!(
+        piece.cell_attribute_names(),
+        vec!["leaf_id".to_string(), "quality".to_string()]
+    );
+
+    let loaded = piece.load_as_surface_mesh::<f64>().unwrap();
+    assert_eq!(loaded.mesh.triangles, sphere.triangles);
+    let cell_attributes = piece
+        .load_cell_attributes::<f64>(&piece.cell_attribute_names())
+        .unwrap();
+    match &cell_attributes[0].data {
+        AttributeData::ScalarU64(ids) => {
+            assert_eq!(ids.len(), num_triangles);
+            assert!(ids.iter().enumerate().all(|(t, &id)| id == t as u64 / 4));
+        }
+        data => panic!("unexpected type {:?}", data),
+    }
+    match &cell_attributes[1].data {
+        AttributeData::ScalarReal(values) => assert_eq!(values, &quality),
+        data => panic!("unexpected type {:?}", data),
+    }
+
+    // Point attributes are not found among the cell attributes
+    let err = piece
+        .load_cell_attributes::<f64>(&["normals".to_string()])
+        .unwrap_err();
+    assert!(
+        matches!(&err, VtkIoError::AttributeMissing { name } if name == "normals"),
+        "unexpected error: {:?}",
+        err
+    );
+
+    // Cell attributes with a different number of values than cells are rejected, the piece is loaded from memory
+    // because the legacy parser skips arrays with too few values
+    let mut piece = mesh.to_unstructured_grid();
+    piece
+        .data
+        .cell
+        .push(Attribute::scalars("truncated", 1).with_data(vec![1.0f64; num_triangles - 1]));
+    let vtk = Vtk {
+        version: Version::new((4, 1)),
+        title: "surface".to_string(),
+        file_path: None,
+        byte_order: ByteOrder::BigEndian,
+        data: DataSet::inline(piece),
+    };
+    let err = VtkFile::from_vtk(vtk)
+        .unwrap()
+        .into_pieces()
+        .into_iter()
+        .next()
+        .unwrap()
+        .load_cell_attributes::<f64>(&["truncated".to_string(), "missing".to_string()])
+        .unwrap_err();
+    assert!(
+        matches!(&err, VtkIoError::InvalidData(message) if message.contains(&format!("{} values were expected", num_triangles))),
+        "unexpected error: {:?}",
+        err
+    );
+}
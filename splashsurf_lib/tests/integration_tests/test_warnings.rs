@@ -0,0 +1,112 @@
+use super::{default_parameters, particle_block};
+use nalgebra::Vector3;
+use splashsurf_lib::{
+    reconstruct_surface, AxisAlignedBoundingBox3d, ClippingPlane, Parameters,
+    ParticleDensityComputationStrategy, ReconstructionWarnings, SpatialDecompositionParameters,
+    SubdivisionCriterion,
+};
+
+fn params(particle_radius: f64, octree: bool) -> Parameters<f64> {
+    Parameters {
+        enable_multi_threading: true,
+        spatial_decomposition: octree.then(|| SpatialDecompositionParameters {
+            subdivision_criterion: SubdivisionCriterion::MaxParticleCount(500),
+            ghost_particle_safety_factor: None,
+            enable_stitching: true,
+            particle_density_computation: ParticleDensityComputationStrategy::SynchronizeSubdomains,
+        }),
+        ..default_parameters(particle_radius)
+    }
+}
+
+#[test]
+fn test_no_warnings() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 12);
+
+    for octree in [false, true] {
+        let reconstruction =
+            reconstruct_surface::<i64, f64>(&particles, &params(particle_radius, octree)).unwrap();
+        let warnings = reconstruction.stats().warnings;
+        assert!(warnings.is_empty(), "unexpected warnings: {}", warnings);
+        assert_eq!(warnings.to_string(), "no warnings");
+    }
+}
+
+#[test]
+fn test_particles_outside_domain() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 12);
+
+    // Domain that only covers the lower half of the block in x direction
+    let domain = AxisAlignedBoundingBox3d::new(Vector3::repeat(-0.5), Vector3::new(0.3, 0.5, 0.5));
+
+    for octree in [false, true] {
+        let mut parameters = params(particle_radius, octree);
+        parameters.domain_aabb = Some(domain.clone());
+
+        let reconstruction = reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+        let warnings = reconstruction.stats().warnings;
+        assert!(warnings.particles_outside_domain > 0);
+        assert!(warnings.particles_outside_domain < particles.len());
+        assert_eq!(warnings.clamped_density_values, 0);
+        assert_eq!(
+            warnings.degenerate_triangles,
+            reconstruction.mesh().count_degenerate_triangles()
+        );
+
+        // Ghost margins are only tracked by the octree based reconstruction
+        if octree {
+            assert!(warnings.truncated_ghost_margins > 0);
+        } else {
+            assert_eq!(warnings.truncated_ghost_margins, 0);
+        }
+
+        assert!(warnings.to_string().contains(&format!(
+            "{} particles outside of domain",
+            warnings.particles_outside_domain
+        )));
+    }
+}
+
+#[test]
+fn test_clamped_density_values() {
+    let particle_radius = 0.025;
+    let particles = particle_block(particle_radius, 12);
+
+    for octree in [false, true] {
+        let mut parameters = params(particle_radius, octree);
+        parameters.clipping_planes = vec![ClippingPlane::new(
+            Vector3::repeat(11.0 * particle_radius),
+            Vector3::new(-1.0, 0.0, 0.0),
+        )];
+
+        let reconstruction = reconstruct_surface::<i64, f64>(&particles, &parameters).unwrap();
+        let warnings = reconstruction.stats().warnings;
+        assert!(warnings.clamped_density_values > 0);
+        assert_eq!(warnings.particles_outside_domain, 0);
+        assert_eq!(warnings.truncated_ghost_margins, 0);
+    }
+}
+
+#[test]
+fn test_warnings_aggregation() {
+    let a = ReconstructionWarnings {
+        particles_outside_domain: 3,
+        degenerate_triangles: 1,
+        ..Default::default()
+    };
+    let b = ReconstructionWarnings {
+        clamped_density_values: 5,
+        degenerate_triangles: 2,
+        ..Default::default()
+    };
+
+    let sum = a + b;
+    assert_eq!(sum.total(), 11);
+    assert_eq!(sum.degenerate_triangles, 3);
+    assert_eq!(
+        sum.to_string(),
+        "3 particles outside of domain, 5 clamped density values, 3 degenerate triangles"
+    );
+}
@@ -0,0 +1,112 @@
+use super::default_parameters;
+use nalgebra::Vector3;
+use splashsurf_lib::{reconstruct_surface_inplace, SurfaceReconstruction};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Allocator that counts the allocations of threads that enabled the counting
+struct CountingAllocator;
+
+thread_local! {
+    static COUNT_ALLOCATIONS: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count_allocation() {
+    // The thread locals might not be available during thread shutdown
+    let _ = COUNT_ALLOCATIONS.try_with(|enabled| {
+        if enabled.get() {
+            ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        }
+    });
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations and reallocations of the current thread performed by the closure
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    ALLOCATIONS.with(|count| count.set(0));
+    COUNT_ALLOCATIONS.with(|enabled| enabled.set(true));
+    let result = f();
+    COUNT_ALLOCATIONS.with(|enabled| enabled.set(false));
+    (result, ALLOCATIONS.with(|count| count.get()))
+}
+
+/// Particles of a sphere on a lattice, the offset shifts the particles relative to the lattice like a subsequent frame of a simulation
+fn sphere_particles(particle_radius: f64, offset: f64) -> Vec<Vector3<f64>> {
+    let spacing = 2.0 * particle_radius;
+    super::sphere_particles(spacing, 7.2 * spacing, Vector3::repeat(8.0 * spacing))
+        .into_iter()
+        .map(|x| x.add_scalar(offset))
+        .collect()
+}
+
+#[test]
+fn test_workspace_priming() {
+    let particle_radius = 0.025;
+    let parameters = default_parameters(particle_radius);
+
+    let mut reconstruction = SurfaceReconstruction::<i64, f64>::default();
+    let mut reconstruct_frame = |offset: f64| {
+        let particles = sphere_particles(particle_radius, offset);
+        let (result, allocations) = count_allocations(|| {
+            reconstruct_surface_inplace(&particles, &parameters, &mut reconstruction)
+        });
+        result.unwrap();
+        // Take the mesh like an application that writes it to a file, the mesh is not reused by the next frame
+        let mesh = reconstruction.take_mesh();
+        assert!(!mesh.triangles.is_empty());
+        (particles.len(), mesh, allocations)
+    };
+
+    let (_, first_mesh, first_frame_allocations) = reconstruct_frame(0.0);
+    let (num_particles, second_mesh, second_frame_allocations) =
+        reconstruct_frame(0.1 * particle_radius);
+
+    // The vertices and triangles were reserved for the size of the first mesh with a margin
+    assert!(second_mesh.vertices.capacity() >= first_mesh.vertices.len() * 11 / 10);
+    assert!(second_mesh.triangles.capacity() >= first_mesh.triangles.len() * 11 / 10);
+
+    // Without priming, the first frame allocates at least the neighbor list of every particle, the second frame only
+    // allocates the cells of the neighborhood search (a few hundred for this sphere) and no buffers per particle
+    assert!(
+        second_frame_allocations < first_frame_allocations / 4,
+        "{} allocations in the second frame, {} allocations in the first frame",
+        second_frame_allocations,
+        first_frame_allocations
+    );
+    assert!(
+        first_frame_allocations > num_particles,
+        "{} allocations in the first frame ({} particles)",
+        first_frame_allocations,
+        num_particles
+    );
+    assert!(
+        second_frame_allocations < num_particles,
+        "{} allocations in the second frame ({} particles)",
+        second_frame_allocations,
+        num_particles
+    );
+}
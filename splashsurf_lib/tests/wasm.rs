@@ -0,0 +1,62 @@
+//! Headless WebAssembly test of the `wasm` entry point, run with
+//! ```text
+//! wasm-pack test --node splashsurf_lib -- --no-default-features --features wasm --test wasm
+//! ```
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use splashsurf_lib::wasm::reconstruct_surface_wasm;
+use wasm_bindgen_test::*;
+
+/// Returns the flat positions of a cube of `n^3` particles with a spacing of twice the particle radius
+fn particle_cube(n: usize, particle_radius: f32) -> Vec<f32> {
+    let mut positions = Vec::with_capacity(3 * n * n * n);
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                positions.extend([i, j, k].map(|c| 2.0 * particle_radius * c as f32));
+            }
+        }
+    }
+    positions
+}
+
+#[wasm_bindgen_test]
+fn test_reconstruct_particle_cube() {
+    let particle_radius = 0.025;
+    let positions = particle_cube(6, particle_radius);
+
+    let mesh = reconstruct_surface_wasm(
+        &positions,
+        particle_radius,
+        4.0 * particle_radius,
+        0.5 * particle_radius,
+        0.6,
+    )
+    .unwrap_or_else(|_| panic!("reconstruction failed"));
+
+    let vertices = mesh.vertices();
+    let indices = mesh.indices();
+    assert!(mesh.num_vertices() > 0);
+    assert!(mesh.num_triangles() > 0);
+    assert_eq!(vertices.len(), 3 * mesh.num_vertices());
+    assert_eq!(indices.len(), 3 * mesh.num_triangles());
+    assert!(indices.iter().all(|&i| (i as usize) < mesh.num_vertices()));
+
+    // All vertices are close to the particle cube
+    let max_coord = 2.0 * particle_radius * 5.0;
+    assert!(vertices
+        .iter()
+        .all(|&c| c > -4.0 * particle_radius && c < max_coord + 4.0 * particle_radius));
+}
+
+#[wasm_bindgen_test]
+fn test_reconstruct_invalid_input() {
+    let particle_radius = 0.025;
+    let positions = particle_cube(2, particle_radius);
+
+    // Positions that are not a multiple of three
+    assert!(reconstruct_surface_wasm(&positions[..4], particle_radius, 0.1, 0.0125, 0.6).is_err());
+    // Invalid cube size
+    assert!(reconstruct_surface_wasm(&positions, particle_radius, 0.1, -1.0, 0.6).is_err());
+}